@@ -0,0 +1,137 @@
+//! Hooks to meter the public key operations, behind the `audit` feature.
+//!
+//! An [`Observer`] installed with `set_observer` is told about every key
+//! generation, signature, verification, encryption, decryption and pairing of
+//! the SM2 and SM9 crates: the algorithm, whether it succeeded and how long it
+//! took. Events carry no keys, messages or other secret material.
+//!
+//! Without the feature [`observe`] is a plain call, with it but no observer
+//! installed nothing is timed.
+//!
+//! # Example
+//! ```rust
+//! # #[cfg(feature = "audit")] {
+//! use std::sync::Arc;
+//! use gm_core::audit::{set_observer, Event, Observer};
+//!
+//! struct Log;
+//!
+//! impl Observer for Log {
+//!     fn on_event(&self, e: &Event) {
+//!         println!("{} {:?} ok={} {:?}", e.algorithm, e.operation, e.ok, e.elapsed);
+//!     }
+//! }
+//!
+//! set_observer(Some(Arc::new(Log)));
+//! # set_observer(None);
+//! # }
+//! ```
+use std::time::Duration;
+#[cfg(feature = "audit")]
+use std::sync::{Arc, RwLock};
+#[cfg(feature = "audit")]
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    KeyGen,
+    Sign,
+    Verify,
+    Encrypt,
+    Decrypt,
+    Pairing,
+}
+
+/// One finished operation
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+    /// `"SM2"` or `"SM9"`
+    pub algorithm: &'static str,
+    pub operation: Operation,
+    /// `false` when the operation returned an error, e.g. a signature that
+    /// does not verify
+    pub ok: bool,
+    pub elapsed: Duration,
+}
+
+/// Receives the events, on the thread that ran the operation.
+pub trait Observer: Send + Sync {
+    fn on_event(&self, event: &Event);
+}
+
+#[cfg(feature = "audit")]
+static OBSERVER: RwLock<Option<Arc<dyn Observer>>> = RwLock::new(None);
+
+/// Install `observer` for the whole process, `None` removes it.
+#[cfg(feature = "audit")]
+pub fn set_observer(observer: Option<Arc<dyn Observer>>) {
+    *OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = observer;
+}
+
+#[cfg(feature = "audit")]
+fn current() -> Option<Arc<dyn Observer>> {
+    OBSERVER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Run `f` and report it as `operation` of `algorithm`.
+#[inline(always)]
+pub fn observe<T, E>(
+    algorithm: &'static str,
+    operation: Operation,
+    f: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    #[cfg(feature = "audit")]
+    if let Some(observer) = current() {
+        let start = Instant::now();
+        let r = f();
+        observer.on_event(&Event {
+            algorithm,
+            operation,
+            ok: r.is_ok(),
+            elapsed: start.elapsed(),
+        });
+        return r;
+    }
+    #[cfg(not(feature = "audit"))]
+    let _ = (algorithm, operation);
+    f()
+}
+
+/// [`observe`] for an operation that cannot fail
+#[inline(always)]
+pub fn observe_infallible<T>(algorithm: &'static str, operation: Operation, f: impl FnOnce() -> T) -> T {
+    match observe(algorithm, operation, || Ok::<T, std::convert::Infallible>(f())) {
+        Ok(t) => t,
+        Err(e) => match e {},
+    }
+}
+
+#[cfg(all(test, feature = "audit"))]
+mod test_audit {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    struct Record(Mutex<Vec<(Operation, bool)>>);
+
+    impl Observer for Record {
+        fn on_event(&self, e: &Event) {
+            self.0.lock().unwrap().push((e.operation, e.ok));
+        }
+    }
+
+    #[test]
+    fn test_observe() {
+        assert_eq!(Ok::<_, ()>(1), observe("SM2", Operation::Sign, || Ok(1)));
+
+        let record = Arc::new(Record(Mutex::new(vec![])));
+        set_observer(Some(record.clone()));
+        assert_eq!(Err(()), observe::<(), ()>("SM2", Operation::Verify, || Err(())));
+        assert_eq!(2, observe_infallible("SM9", Operation::Pairing, || 2));
+        set_observer(None);
+        assert_eq!(Ok::<_, ()>(3), observe("SM2", Operation::Sign, || Ok(3)));
+
+        let events = record.0.lock().unwrap().clone();
+        assert_eq!(vec![(Operation::Verify, false), (Operation::Pairing, true)], events);
+    }
+}
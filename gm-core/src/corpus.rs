@@ -0,0 +1,138 @@
+//! Known-broken inputs for the public decoders of the algorithm crates (enable
+//! the `corpus` feature, their dev-dependencies do).
+//!
+//! Every valid encoding is cut at each length, extended, bit-flipped and
+//! replaced by garbage of the same and of other lengths. Three checks run a
+//! decoder over them:
+//!
+//! - [`Corpus::check_decoder`]: the truncated and extended inputs are
+//!   rejected, the others must not panic, they may happen to be valid.
+//! - [`Corpus::check_authenticated`]: everything but the valid input is
+//!   rejected, for decryption and verification.
+//! - [`Corpus::check_unauthenticated`]: nothing panics, for the modes without
+//!   integrity.
+//!
+//! Each crate keeps its own inputs in `src/corpus.rs`, with the garbage
+//! lengths that are near misses for its encodings.
+//!
+//! # Example
+//! ```rust
+//! use gm_core::corpus::Corpus;
+//!
+//! const CORPUS: Corpus = Corpus::new(&[1, 3, 5, 8]);
+//!
+//! CORPUS.check_decoder("u32::from_be_bytes", &[1, 2, 3, 4], |b| {
+//!     <[u8; 4]>::try_from(b).map(u32::from_be_bytes)
+//! });
+//! ```
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::encoding::hex_encode;
+
+/// The garbage lengths and bit-flip stride of a corpus
+#[derive(Debug, Clone, Copy)]
+pub struct Corpus {
+    lengths: &'static [usize],
+    step: usize,
+}
+
+impl Corpus {
+    /// Garbage of the valid length and of `lengths`, every byte flipped
+    pub const fn new(lengths: &'static [usize]) -> Corpus {
+        Corpus { lengths, step: 1 }
+    }
+
+    /// Flip every `step`-th byte only, for decoders too slow to try them all
+    pub const fn with_step(self, step: usize) -> Corpus {
+        Corpus { step, ..self }
+    }
+
+    /// Single bit flips of `valid` and garbage of its length and of others
+    pub fn mangled(&self, valid: &[u8]) -> Vec<Vec<u8>> {
+        let mut v: Vec<Vec<u8>> = (0..valid.len())
+            .step_by(self.step)
+            .map(|i| {
+                let mut b = valid.to_vec();
+                b[i] ^= 1 << (i % 8);
+                b
+            })
+            .collect();
+        for &len in [valid.len()].iter().chain(self.lengths) {
+            v.push(vec![0; len]);
+            v.push(vec![0xff; len]);
+            v.push((0..len).map(|i| (i * 151 + 7) as u8).collect());
+        }
+        v
+    }
+
+    /// Rejects the resized inputs, survives the mangled ones
+    pub fn check_decoder<T, E>(&self, name: &str, valid: &[u8], f: impl Fn(&[u8]) -> Result<T, E>) {
+        assert!(accepts(name, valid, &f), "{} rejects the valid input", name);
+        for b in resized(valid) {
+            assert!(!accepts(name, &b, &f), "{} accepted {}", name, hex_encode(&b));
+        }
+        for b in self.mangled(valid) {
+            accepts(name, &b, &f);
+        }
+    }
+
+    /// Rejects everything but `valid`
+    pub fn check_authenticated<T, E>(&self, name: &str, valid: &[u8], f: impl Fn(&[u8]) -> Result<T, E>) {
+        assert!(accepts(name, valid, &f), "{} rejects the valid input", name);
+        for b in resized(valid).into_iter().chain(self.mangled(valid)) {
+            assert!(!accepts(name, &b, &f), "{} accepted {}", name, hex_encode(&b));
+        }
+    }
+
+    /// Survives everything
+    pub fn check_unauthenticated<T, E>(&self, name: &str, valid: &[u8], f: impl Fn(&[u8]) -> Result<T, E>) {
+        assert!(accepts(name, valid, &f), "{} rejects the valid input", name);
+        for b in resized(valid).into_iter().chain(self.mangled(valid)) {
+            accepts(name, &b, &f);
+        }
+    }
+}
+
+/// Truncations and extensions of `valid`
+pub fn resized(valid: &[u8]) -> Vec<Vec<u8>> {
+    let mut v: Vec<Vec<u8>> = (0..valid.len()).map(|n| valid[..n].to_vec()).collect();
+    for extra in [&[0u8][..], &[0xff; 32], valid] {
+        v.push([valid, extra].concat());
+    }
+    v
+}
+
+/// Runs `f` on `input`, returns whether it succeeded and fails on a panic.
+pub fn accepts<T, E>(name: &str, input: &[u8], f: &impl Fn(&[u8]) -> Result<T, E>) -> bool {
+    match catch_unwind(AssertUnwindSafe(|| f(input).is_ok())) {
+        Ok(ok) => ok,
+        Err(_) => panic!("{} panicked on {}", name, hex_encode(input)),
+    }
+}
+
+#[cfg(test)]
+mod test_corpus {
+    use super::*;
+
+    const CORPUS: Corpus = Corpus::new(&[1, 3, 5, 8]);
+
+    #[test]
+    fn test_mangled() {
+        let valid = [0x5a; 16];
+        assert_eq!(16 + 3 * 5, CORPUS.mangled(&valid).len());
+        assert_eq!(4 + 3 * 5, CORPUS.with_step(4).mangled(&valid).len());
+        assert_eq!(16 + 3, resized(&valid).len());
+    }
+
+    #[test]
+    #[should_panic(expected = "accepted")]
+    fn test_lenient_decoder() {
+        CORPUS.check_decoder("prefix", &[1, 2, 3, 4], |b| b.get(..4).map(<[u8]>::to_vec).ok_or(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "index panicked on")]
+    fn test_panicking_decoder() {
+        CORPUS.check_unauthenticated("index", &[1, 2, 3, 4], |b| Ok::<u8, ()>(b[3]));
+    }
+}
@@ -0,0 +1,197 @@
+//! Timing-leak tests in the style of dudect (enable the `timing-tests`
+//! feature): time an operation on inputs of two classes, a fixed input and
+//! random ones, interleaved at random, and compare the two distributions with
+//! Welch's t-test. A constant-time operation keeps `|t|` small however many
+//! samples are taken, a leak makes it grow with their number.
+//!
+//! As dudect, the test is repeated on the measurements cropped at a few
+//! percentiles, which removes the long tail of interrupts and cache misses,
+//! and the largest `|t|` is reported. Above [`LEAK_THRESHOLD`] the classes
+//! are told apart. Timings are only meaningful in release builds on a quiet
+//! machine, the tests of the crates using this module are ignored by default:
+//!
+//! ```text
+//! cargo test --release --features timing-tests -- --ignored timing
+//! ```
+//!
+//! # Example
+//! ```rust
+//! use gm_core::dudect::{measure, Class};
+//!
+//! let secret = [0x5a; 64];
+//! let report = measure(
+//!     2_000,
+//!     |class, i| match class {
+//!         Class::Fixed => secret,
+//!         Class::Random => [i as u8; 64],
+//!     },
+//!     |input| gm_core::ct_eq(input, &secret),
+//! );
+//! println!("{:?}", report);
+//! ```
+use std::hint::black_box;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// `|t|` beyond which the timings of the classes differ, the value dudect
+/// uses
+pub const LEAK_THRESHOLD: f64 = 4.5;
+
+/// The two input classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Fixed,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TimingReport {
+    /// The largest `|t|` over the croppings
+    pub max_t: f64,
+    /// Measurements taken, both classes
+    pub samples: usize,
+}
+
+impl TimingReport {
+    pub fn leaks(&self) -> bool {
+        self.max_t > LEAK_THRESHOLD
+    }
+}
+
+/// Running mean and variance, Welford's method
+#[derive(Default, Clone, Copy)]
+struct Moments {
+    n: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        if self.n < 2.0 {
+            return 0.0;
+        }
+        self.m2 / (self.n - 1.0)
+    }
+}
+
+/// Welch's t of the two classes
+fn welch_t(m: &[Moments; 2]) -> f64 {
+    let se = (m[0].variance() / m[0].n + m[1].variance() / m[1].n).sqrt();
+    if m[0].n < 2.0 || m[1].n < 2.0 || se == 0.0 {
+        return 0.0;
+    }
+    (m[0].mean - m[1].mean) / se
+}
+
+/// xorshift64, only to interleave the classes unpredictably for the CPU
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Time `op` on `samples` inputs from `input(class, i)`, all prepared before
+/// the first measurement, and run the t-test. Panics for no samples.
+pub fn measure<I>(samples: usize, mut input: impl FnMut(Class, usize) -> I, mut op: impl FnMut(&I) -> bool) -> TimingReport {
+    assert!(samples > 0, "measure needs at least one sample");
+    // the class order differs from run to run, seeded from the wall clock
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+        | 1;
+    let classes: Vec<Class> = (0..samples)
+        .map(|_| if next(&mut state) & 1 == 0 { Class::Fixed } else { Class::Random })
+        .collect();
+    let inputs: Vec<I> = classes.iter().enumerate().map(|(i, &c)| input(c, i)).collect();
+
+    let mut times = Vec::with_capacity(samples);
+    for x in &inputs {
+        let start = Instant::now();
+        black_box(op(black_box(x)));
+        times.push(start.elapsed().as_nanos() as f64);
+    }
+
+    let mut sorted = times.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mut max_t: f64 = 0.0;
+    // no cropping, then the measurements below a few percentiles
+    for pct in [1.0, 0.99, 0.95, 0.9, 0.75, 0.5] {
+        let cut = sorted[((samples as f64 * pct) as usize).clamp(1, samples) - 1];
+        let mut m = [Moments::default(); 2];
+        for (&t, &c) in times.iter().zip(&classes) {
+            if t <= cut {
+                m[(c == Class::Random) as usize].push(t);
+            }
+        }
+        max_t = max_t.max(welch_t(&m).abs());
+    }
+    TimingReport { max_t, samples }
+}
+
+#[cfg(test)]
+mod test_dudect {
+    use super::*;
+
+    #[test]
+    fn test_welch() {
+        let mut m = [Moments::default(); 2];
+        for x in [1.0, 2.0, 3.0, 4.0] {
+            m[0].push(x);
+            m[1].push(x + 10.0);
+        }
+        assert_eq!(2.5, m[0].mean);
+        assert!((m[0].variance() - 5.0 / 3.0).abs() < 1e-12);
+        // -10 / sqrt(2 * (5/3) / 4)
+        assert!((welch_t(&m) + 10.954451150103322).abs() < 1e-9);
+        assert_eq!(0.0, welch_t(&[m[0], m[0]]));
+    }
+
+    /// An early-exit comparison of 4 KiB must be caught, or the harness is
+    /// blind.
+    #[test]
+    fn test_detects_leak() {
+        let secret = vec![0x5a; 4096];
+        let report = measure(
+            4_000,
+            |class, _| match class {
+                Class::Fixed => secret.clone(),
+                Class::Random => vec![0xa5; 4096],
+            },
+            |input| input == &secret,
+        );
+        assert!(report.leaks(), "{:?}", report);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one sample")]
+    fn test_no_samples() {
+        measure(0, |_, i| i, |_| true);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_timing_ct_eq() {
+        let secret = [0x5a; 64];
+        let report = measure(
+            1_000_000,
+            |class, i| match class {
+                Class::Fixed => secret,
+                Class::Random => {
+                    let mut tag = secret;
+                    tag[i % 64] ^= 1 << (i % 8);
+                    tag
+                }
+            },
+            |input| (0..8).fold(true, |eq, _| eq & crate::ct_eq(input, &secret)),
+        );
+        assert!(!report.leaks(), "{:?}", report);
+    }
+}
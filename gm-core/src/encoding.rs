@@ -0,0 +1,387 @@
+//! Text encodings of keys, signatures and ciphertexts: hex, base64 and PEM,
+//! and base58 and bech32 for addresses.
+//!
+//! Decoding is strict. Hex must be an even number of digits without
+//! separators, base64 must be canonical and PEM must be a single block whose
+//! `BEGIN` and `END` labels match. Bech32 must be all lowercase or all
+//! uppercase with a valid checksum. Errors carry the offset of the offending
+//! character where there is one.
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+
+/// Line width of the base64 body of a PEM block (RFC 7468)
+const PEM_LINE: usize = 64;
+
+/// The Bitcoin base58 alphabet
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+const BECH32: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Longest bech32 string of BIP 173
+const BECH32_MAX: usize = 90;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodingError {
+    /// Not a hex digit, at this byte offset of the input
+    InvalidHexChar(usize),
+    OddHexLength,
+    /// Not valid base64, with the byte offset when the error is a single character
+    InvalidBase64(Option<usize>),
+    /// Not a label of RFC 7468: printable ASCII, words separated by a single
+    /// space or hyphen
+    InvalidPemLabel,
+    /// Missing or mismatched `BEGIN` / `END` lines
+    MalformedPem,
+    /// A well-formed PEM block with another label than the expected one
+    UnexpectedPemLabel(String),
+    /// Not a base58 digit, at this byte offset of the input
+    InvalidBase58Char(usize),
+    /// Bad human-readable part, character, case, length, padding or checksum
+    InvalidBech32,
+}
+
+impl std::fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodingError::InvalidHexChar(pos) => write!(f, "invalid hex character at offset {}", pos),
+            EncodingError::OddHexLength => write!(f, "odd number of hex digits"),
+            EncodingError::InvalidBase64(Some(pos)) => write!(f, "invalid base64 at offset {}", pos),
+            EncodingError::InvalidBase64(None) => write!(f, "invalid base64 length or padding"),
+            EncodingError::InvalidPemLabel => write!(f, "invalid PEM label"),
+            EncodingError::MalformedPem => write!(f, "malformed PEM block"),
+            EncodingError::UnexpectedPemLabel(label) => write!(f, "unexpected PEM label {:?}", label),
+            EncodingError::InvalidBase58Char(pos) => write!(f, "invalid base58 character at offset {}", pos),
+            EncodingError::InvalidBech32 => write!(f, "invalid bech32 string"),
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// Lowercase hex
+pub fn hex_encode(data: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(data.len() * 2);
+    for &b in data {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    out
+}
+
+/// Hex in either case.
+pub fn hex_decode(s: &str) -> Result<Vec<u8>, EncodingError> {
+    let digit = |i: usize, c: u8| match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(EncodingError::InvalidHexChar(i)),
+    };
+    let s = s.as_bytes();
+    let mut out = Vec::with_capacity(s.len() / 2);
+    for (i, pair) in s.chunks(2).enumerate() {
+        let hi = digit(2 * i, pair[0])?;
+        if pair.len() == 1 {
+            return Err(EncodingError::OddHexLength);
+        }
+        out.push((hi << 4) | digit(2 * i + 1, pair[1])?);
+    }
+    Ok(out)
+}
+
+/// Standard base64 with padding
+pub fn base64_encode(data: &[u8]) -> String {
+    STANDARD.encode(data)
+}
+
+pub fn base64_decode(s: &str) -> Result<Vec<u8>, EncodingError> {
+    STANDARD.decode(s).map_err(base64_error)
+}
+
+/// URL-safe base64 without padding, as used by JOSE
+pub fn base64url_encode(data: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+pub fn base64url_decode(s: &str) -> Result<Vec<u8>, EncodingError> {
+    URL_SAFE_NO_PAD.decode(s).map_err(base64_error)
+}
+
+fn base64_error(e: base64::DecodeError) -> EncodingError {
+    match e {
+        base64::DecodeError::InvalidByte(pos, _) => EncodingError::InvalidBase64(Some(pos)),
+        base64::DecodeError::InvalidLastSymbol(pos, _) => EncodingError::InvalidBase64(Some(pos)),
+        _ => EncodingError::InvalidBase64(None),
+    }
+}
+
+/// Base58 with the Bitcoin alphabet, a leading `1` per leading zero byte
+pub fn base58_encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    // base-58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &b in &data[zeros..] {
+        let mut carry = b as u32;
+        for d in digits.iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    let mut out = "1".repeat(zeros);
+    out.extend(digits.iter().rev().map(|&d| BASE58[d as usize] as char));
+    out
+}
+
+pub fn base58_decode(s: &str) -> Result<Vec<u8>, EncodingError> {
+    let s = s.as_bytes();
+    let zeros = s.iter().take_while(|&&c| c == b'1').count();
+    // bytes, least significant first
+    let mut bytes: Vec<u8> = Vec::with_capacity(s.len());
+    for (i, &c) in s.iter().enumerate().skip(zeros) {
+        let digit = BASE58.iter().position(|&d| d == c).ok_or(EncodingError::InvalidBase58Char(i))?;
+        let mut carry = digit as u32;
+        for b in bytes.iter_mut() {
+            carry += *b as u32 * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0; zeros];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
+
+fn bech32_polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    values.fold(1, |chk, v| {
+        let top = chk >> 25;
+        let chk = (chk & 0x1ffffff) << 5 ^ v as u32;
+        (0..5).filter(|i| top >> i & 1 == 1).fold(chk, |chk, i| chk ^ GEN[i])
+    })
+}
+
+fn bech32_hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    let b = hrp.as_bytes();
+    b.iter().map(|c| c >> 5).chain([0]).chain(b.iter().map(|c| c & 31))
+}
+
+/// Bech32 of BIP 173 (not bech32m) over bytes regrouped into 5-bit words,
+/// lowercase. The human-readable part is 1 to 83 printable ASCII characters
+/// other than uppercase letters.
+pub fn bech32_encode(hrp: &str, data: &[u8]) -> Result<String, EncodingError> {
+    if hrp.is_empty() || !hrp.bytes().all(|c| (33..=126).contains(&c) && !c.is_ascii_uppercase()) {
+        return Err(EncodingError::InvalidBech32);
+    }
+    let mut words = Vec::with_capacity(data.len() * 8 / 5 + 7);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in data {
+        acc = acc << 8 | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            words.push((acc >> bits & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        words.push((acc << (5 - bits) & 31) as u8);
+    }
+    let chk = bech32_polymod(bech32_hrp_expand(hrp).chain(words.iter().copied()).chain([0; 6])) ^ 1;
+    words.extend((0..6).map(|i| (chk >> (5 * (5 - i)) & 31) as u8));
+    if hrp.len() + 1 + words.len() > BECH32_MAX {
+        return Err(EncodingError::InvalidBech32);
+    }
+    let mut out = format!("{}1", hrp);
+    out.extend(words.iter().map(|&w| BECH32[w as usize] as char));
+    Ok(out)
+}
+
+/// Returns the lowercase human-readable part and the data bytes. Padding
+/// beyond the last byte must be fewer than 5 zero bits.
+pub fn bech32_decode(s: &str) -> Result<(String, Vec<u8>), EncodingError> {
+    let has_lower = s.bytes().any(|c| c.is_ascii_lowercase());
+    let has_upper = s.bytes().any(|c| c.is_ascii_uppercase());
+    if s.len() > BECH32_MAX || (has_lower && has_upper) || !s.bytes().all(|c| (33..=126).contains(&c)) {
+        return Err(EncodingError::InvalidBech32);
+    }
+    let s = s.to_ascii_lowercase();
+    let sep = s.rfind('1').ok_or(EncodingError::InvalidBech32)?;
+    let (hrp, rest) = (&s[..sep], &s.as_bytes()[sep + 1..]);
+    if hrp.is_empty() || rest.len() < 6 {
+        return Err(EncodingError::InvalidBech32);
+    }
+    let words = rest
+        .iter()
+        .map(|&c| BECH32.iter().position(|&d| d == c).map(|w| w as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or(EncodingError::InvalidBech32)?;
+    if bech32_polymod(bech32_hrp_expand(hrp).chain(words.iter().copied())) != 1 {
+        return Err(EncodingError::InvalidBech32);
+    }
+    let mut out = Vec::with_capacity(words.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &w in &words[..words.len() - 6] {
+        acc = acc << 5 | w as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return Err(EncodingError::InvalidBech32);
+    }
+    Ok((hrp.to_string(), out))
+}
+
+/// A PEM block with LF line endings and 64-column lines.
+pub fn pem_encode(label: &str, der: &[u8]) -> Result<String, EncodingError> {
+    check_label(label)?;
+    let body = base64_encode(der);
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for line in body.as_bytes().chunks(PEM_LINE) {
+        // base64 output is ASCII
+        out.push_str(std::str::from_utf8(line).unwrap());
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    Ok(out)
+}
+
+/// Returns the label and the decoded body of a single PEM block.
+///
+/// Surrounding whitespace and CRLF line endings are accepted, text outside the
+/// block and RFC 1421 headers are not.
+pub fn pem_decode(pem: &str) -> Result<(String, Vec<u8>), EncodingError> {
+    let mut lines = pem.trim().lines().map(|l| l.trim_end_matches('\r'));
+    let first = lines.next().ok_or(EncodingError::MalformedPem)?;
+    let label = first
+        .strip_prefix("-----BEGIN ")
+        .and_then(|l| l.strip_suffix("-----"))
+        .ok_or(EncodingError::MalformedPem)?;
+    check_label(label)?;
+    let mut body = String::new();
+    for line in lines {
+        if let Some(end) = line.strip_prefix("-----END ") {
+            if end.strip_suffix("-----") != Some(label) {
+                return Err(EncodingError::MalformedPem);
+            }
+            return Ok((label.to_string(), base64_decode(&body)?));
+        }
+        body.push_str(line);
+    }
+    Err(EncodingError::MalformedPem)
+}
+
+/// [`pem_decode`] that also checks the label.
+pub fn pem_decode_label(label: &str, pem: &str) -> Result<Vec<u8>, EncodingError> {
+    let (found, der) = pem_decode(pem)?;
+    if found != label {
+        return Err(EncodingError::UnexpectedPemLabel(found));
+    }
+    Ok(der)
+}
+
+fn check_label(label: &str) -> Result<(), EncodingError> {
+    let b = label.as_bytes();
+    let separator = |c: u8| c == b' ' || c == b'-';
+    let valid = b.iter().all(|&c| (0x20..=0x7e).contains(&c))
+        && !b.first().is_some_and(|&c| separator(c))
+        && !b.last().is_some_and(|&c| separator(c))
+        && !b.windows(2).any(|w| separator(w[0]) && separator(w[1]));
+    if valid {
+        Ok(())
+    } else {
+        Err(EncodingError::InvalidPemLabel)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::encoding::*;
+
+    #[test]
+    fn test_hex() {
+        assert_eq!("00ff10ab", hex_encode(&[0x00, 0xff, 0x10, 0xab]));
+        assert_eq!(vec![0x00, 0xff, 0x10, 0xab], hex_decode("00FF10ab").unwrap());
+        assert_eq!(Vec::<u8>::new(), hex_decode("").unwrap());
+        assert_eq!(Err(EncodingError::InvalidHexChar(5)), hex_decode("00ff1g"));
+        assert_eq!(Err(EncodingError::InvalidHexChar(2)), hex_decode("00 ff"));
+        assert_eq!(Err(EncodingError::OddHexLength), hex_decode("00f"));
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!("+/8=", base64_encode(&[0xfb, 0xff]));
+        assert_eq!("-_8", base64url_encode(&[0xfb, 0xff]));
+        assert_eq!(vec![0xfb, 0xff], base64_decode("+/8=").unwrap());
+        assert_eq!(vec![0xfb, 0xff], base64url_decode("-_8").unwrap());
+        assert_eq!(Err(EncodingError::InvalidBase64(Some(1))), base64_decode("+_8="));
+        assert_eq!(Err(EncodingError::InvalidBase64(None)), base64_decode("+/8"));
+    }
+
+    #[test]
+    fn test_base58() {
+        assert_eq!("2NEpo7TZRRrLZSi2U", base58_encode(b"Hello World!"));
+        assert_eq!(b"Hello World!".to_vec(), base58_decode("2NEpo7TZRRrLZSi2U").unwrap());
+        assert_eq!("11233QC4", base58_encode(&[0, 0, 0x28, 0x7f, 0xb4, 0xcd]));
+        assert_eq!(vec![0, 0, 0x28, 0x7f, 0xb4, 0xcd], base58_decode("11233QC4").unwrap());
+        assert_eq!("", base58_encode(&[]));
+        assert_eq!(vec![0, 0], base58_decode("11").unwrap());
+        assert_eq!(Err(EncodingError::InvalidBase58Char(3)), base58_decode("233l"));
+        assert_eq!(Err(EncodingError::InvalidBase58Char(0)), base58_decode("0"));
+    }
+
+    #[test]
+    fn test_bech32() {
+        // BIP 173 test vectors
+        assert_eq!(("a".to_string(), vec![]), bech32_decode("A12UEL5L").unwrap());
+        let data = hex_decode("00443214c74254b635cf84653a56d7c675be77df").unwrap();
+        let s = "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw";
+        assert_eq!(("abcdef".to_string(), data.clone()), bech32_decode(s).unwrap());
+        assert_eq!(s, bech32_encode("abcdef", &data).unwrap());
+        assert_eq!(("abcdef".to_string(), data), bech32_decode(&s.to_uppercase()).unwrap());
+
+        let s = bech32_encode("sm", &[1, 2, 3]).unwrap();
+        assert_eq!(("sm".to_string(), vec![1, 2, 3]), bech32_decode(&s).unwrap());
+        assert_eq!(Err(EncodingError::InvalidBech32), bech32_decode(&s.replacen('q', "p", 1)));
+        assert!(bech32_decode("A12uEL5L").is_err());
+        assert!(bech32_decode("a12uel5").is_err());
+        assert!(bech32_decode("12uel5l").is_err());
+        assert!(bech32_encode("SM", &[1]).is_err());
+        assert!(bech32_encode("sm", &[0; 60]).is_err());
+    }
+
+    #[test]
+    fn test_pem() {
+        let der: Vec<u8> = (0u8..100).collect();
+        let pem = pem_encode("PUBLIC KEY", &der).unwrap();
+        assert!(pem.starts_with("-----BEGIN PUBLIC KEY-----\nAAECAwQF"));
+        assert!(pem.ends_with("\n-----END PUBLIC KEY-----\n"));
+        assert_eq!(5, pem.lines().count());
+        assert_eq!(("PUBLIC KEY".to_string(), der.clone()), pem_decode(&pem).unwrap());
+        assert_eq!(der, pem_decode_label("PUBLIC KEY", &pem.replace('\n', "\r\n")).unwrap());
+        assert_eq!(
+            Err(EncodingError::UnexpectedPemLabel("PUBLIC KEY".to_string())),
+            pem_decode_label("PRIVATE KEY", &pem)
+        );
+
+        assert_eq!(Err(EncodingError::InvalidPemLabel), pem_encode("BAD--LABEL", &der));
+        assert_eq!(Err(EncodingError::InvalidPemLabel), pem_encode(" KEY", &der));
+        assert!(pem_encode("", &der).is_ok());
+        let mismatched = pem.replace("END PUBLIC", "END PRIVATE");
+        assert_eq!(Err(EncodingError::MalformedPem), pem_decode(&mismatched));
+        let truncated = &pem[..pem.len() - 30];
+        assert_eq!(Err(EncodingError::MalformedPem), pem_decode(truncated));
+        let corrupted = pem.replace("AAECAwQF", "AAEC*wQF");
+        assert!(matches!(pem_decode(&corrupted), Err(EncodingError::InvalidBase64(Some(4)))));
+    }
+}
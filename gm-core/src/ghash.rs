@@ -0,0 +1,211 @@
+//! GHASH, the universal hash of GCM (NIST SP 800-38D), for SM4-GCM and any
+//! other mode built on GF(2^128) with the GCM bit order.
+//!
+//! Blocks are `u128` read big-endian, so the first bit of a block is the
+//! coefficient of `x^0`. With PCLMULQDQ on x86_64 or PMULL on aarch64,
+//! detected at runtime, a multiplication is four carry-less 64-bit products
+//! and a fold by `x^128 = x^7 + x^2 + x + 1`. Otherwise a branch-free
+//! shift-and-add over the 128 bits of one operand is used, its running time
+//! does not depend on the values.
+//!
+//! # Example
+//! ```rust
+//! use gm_core::ghash::GHash;
+//!
+//! let mut ghash = GHash::new(&[0x42; 16]);
+//! ghash.update_padded(b"aad");
+//! ghash.update_padded(b"ciphertext");
+//! ghash.update_lengths(3, 10);
+//! let s = ghash.finalize();
+//! # assert_eq!(16, s.len());
+//! ```
+
+/// `H`, the running value `Y` and whether the carry-less multiply is used
+#[derive(Clone)]
+pub struct GHash {
+    h: u128,
+    y: u128,
+    clmul: bool,
+}
+
+/// Never prints `H`, it is derived from the key.
+impl std::fmt::Debug for GHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "GHash(..)")
+    }
+}
+
+impl GHash {
+    pub fn new(h: &[u8; 16]) -> GHash {
+        GHash {
+            h: u128::from_be_bytes(*h),
+            y: 0,
+            clmul: hw::available(),
+        }
+    }
+
+    /// Absorb one 16-byte block.
+    pub fn update_block(&mut self, block: &[u8; 16]) {
+        let x = self.y ^ u128::from_be_bytes(*block);
+        self.y = if self.clmul {
+            // SAFETY: the CPU feature was detected in `new`
+            unsafe { hw::mul(x, self.h) }
+        } else {
+            gf128_mul_soft(x, self.h)
+        };
+    }
+
+    /// Absorb `data`, the last block zero padded.
+    pub fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block = [0u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+
+    /// The closing block of GCM, the bit lengths of the AAD and the ciphertext
+    pub fn update_lengths(&mut self, aad_len: usize, ct_len: usize) {
+        let lens = ((aad_len as u128 * 8) << 64) | (ct_len as u128 * 8);
+        self.update_block(&lens.to_be_bytes());
+    }
+
+    pub fn finalize(self) -> [u8; 16] {
+        self.y.to_be_bytes()
+    }
+}
+
+/// `x * y` in GF(2^128) with the GCM bit order, using the carry-less
+/// multiply when the CPU has one.
+pub fn gf128_mul(x: u128, y: u128) -> u128 {
+    if hw::available() {
+        // SAFETY: the CPU feature was just detected
+        unsafe { hw::mul(x, y) }
+    } else {
+        gf128_mul_soft(x, y)
+    }
+}
+
+/// The portable multiplication, branch free.
+pub fn gf128_mul_soft(x: u128, y: u128) -> u128 {
+    const R: u128 = 0xe1 << 120;
+    let mut z = 0u128;
+    let mut v = y;
+    for i in 0..128 {
+        let bit = (x >> (127 - i)) & 1;
+        z ^= v & bit.wrapping_neg();
+        let lsb = v & 1;
+        v = (v >> 1) ^ (R & lsb.wrapping_neg());
+    }
+    z
+}
+
+/// The multiplication on top of a 64 x 64 -> 128 carry-less product: reverse
+/// the bits so bit `i` is the coefficient of `x^i`, multiply, fold the high
+/// half twice by `x^7 + x^2 + x + 1` and reverse back.
+#[inline(always)]
+fn mul_with(x: u128, y: u128, clmul: impl Fn(u64, u64) -> u128) -> u128 {
+    const G: u64 = 0x87;
+    let (a, b) = (x.reverse_bits(), y.reverse_bits());
+    let (a0, a1, b0, b1) = (a as u64, (a >> 64) as u64, b as u64, (b >> 64) as u64);
+    let mid = clmul(a0, b1) ^ clmul(a1, b0);
+    let lo = clmul(a0, b0) ^ (mid << 64);
+    let hi = clmul(a1, b1) ^ (mid >> 64);
+
+    let t0 = clmul(hi as u64, G);
+    let t1 = clmul((hi >> 64) as u64, G);
+    let r = lo ^ t0 ^ (t1 << 64) ^ clmul((t1 >> 64) as u64, G);
+    r.reverse_bits()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod hw {
+    use core::arch::x86_64::*;
+
+    pub(super) fn available() -> bool {
+        std::is_x86_feature_detected!("pclmulqdq")
+    }
+
+    #[target_feature(enable = "pclmulqdq,sse2")]
+    pub(super) unsafe fn mul(x: u128, y: u128) -> u128 {
+        super::mul_with(x, y, |a, b| {
+            let r = _mm_clmulepi64_si128(_mm_cvtsi64_si128(a as i64), _mm_cvtsi64_si128(b as i64), 0x00);
+            let mut out = [0u8; 16];
+            _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, r);
+            u128::from_le_bytes(out)
+        })
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod hw {
+    use core::arch::aarch64::*;
+
+    pub(super) fn available() -> bool {
+        std::arch::is_aarch64_feature_detected!("pmull")
+    }
+
+    #[target_feature(enable = "neon,aes")]
+    pub(super) unsafe fn mul(x: u128, y: u128) -> u128 {
+        super::mul_with(x, y, |a, b| vmull_p64(a, b))
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+mod hw {
+    pub(super) fn available() -> bool {
+        false
+    }
+
+    pub(super) unsafe fn mul(x: u128, y: u128) -> u128 {
+        super::gf128_mul_soft(x, y)
+    }
+}
+
+#[cfg(test)]
+mod test_ghash {
+    use super::*;
+
+    /// Carry-less product bit by bit, to check the folding on any CPU
+    fn clmul_soft(a: u64, b: u64) -> u128 {
+        (0..64).fold(0, |r, i| if (b >> i) & 1 == 1 { r ^ ((a as u128) << i) } else { r })
+    }
+
+    #[test]
+    fn test_mul() {
+        let mut s = 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210u128;
+        let mut values = vec![0, 1, 1 << 127, u128::MAX];
+        for _ in 0..64 {
+            // xorshift
+            s ^= s << 13;
+            s ^= s >> 7;
+            s ^= s << 17;
+            values.push(s);
+        }
+        for &x in &values {
+            for &y in &values[..8] {
+                let expected = gf128_mul_soft(x, y);
+                assert_eq!(expected, mul_with(x, y, clmul_soft), "{:x} {:x}", x, y);
+                assert_eq!(expected, gf128_mul(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ghash() {
+        // NIST GCM test case 2: H = E(K, 0) for the all-zero AES-128 key
+        let h = 0x66e94bd4ef8a2c3b884cfa59ca342b2eu128.to_be_bytes();
+        let mut ghash = GHash::new(&h);
+        ghash.update_padded(&0x0388dace60b6a392f328c2b971b2fe78u128.to_be_bytes());
+        ghash.update_lengths(0, 16);
+        assert_eq!(0xf38cbb1ad69223dcc3457ae5b6b0f885u128.to_be_bytes(), ghash.finalize());
+
+        let ct: Vec<u8> = (0..40).collect();
+        let mut ghash = GHash::new(&h);
+        ghash.update_padded(&[1, 2, 3]);
+        ghash.update_padded(&ct);
+        ghash.update_lengths(3, ct.len());
+        assert_eq!(0x58d59f2655db73e4c36db0aae3e69cd2u128.to_be_bytes(), ghash.finalize());
+        assert_eq!("GHash(..)", format!("{:?}", GHash::new(&h)));
+    }
+}
@@ -0,0 +1,400 @@
+//! Scalar multiplication written once for the elliptic curve groups of the
+//! workspace. The SM2 curve and G1 of SM9 implement [`Group`] with their own
+//! point arithmetic and share the algorithms here.
+//!
+//! Scalars are little-endian `u64` limbs, as the `U256` of both crates.
+//! [`window_mul`] and [`ladder_mul`] run the same sequence of group operations
+//! and table reads whatever the scalar is. The SM2 group law selects its
+//! identity cases in constant time, the one of SM9 G1 still branches on the
+//! identity. [`wnaf_mul`] is faster and variable time, for public
+//! scalars only, such as in verification. [`FixedBase`] trades memory for
+//! speed when one point is multiplied many times.
+pub use subtle::{Choice, ConditionallySelectable};
+
+use subtle::ConstantTimeEq;
+
+/// A group in projective coordinates, written additively
+pub trait Group: ConditionallySelectable {
+    fn identity() -> Self;
+
+    fn is_identity(&self) -> bool;
+
+    /// Any two points, the identity and equal points included
+    fn add(&self, rhs: &Self) -> Self;
+
+    fn double(&self) -> Self;
+
+    fn neg(&self) -> Self;
+}
+
+/// Points [`batch_normalize`] can bring to `Z = 1` with a single inversion
+pub trait Normalize: Group {
+    type Field: Copy;
+
+    fn z(&self) -> Self::Field;
+
+    fn field_mul(a: &Self::Field, b: &Self::Field) -> Self::Field;
+
+    fn field_inv(a: &Self::Field) -> Self::Field;
+
+    /// The same point with `Z = 1`, given `1/Z`
+    fn normalize_with(&self, z_inv: &Self::Field) -> Self;
+}
+
+/// Width of the signed windows of [`window_mul`]
+pub const WINDOW: usize = 5;
+
+/// Width of the non-adjacent form of [`wnaf_mul`]
+pub const WNAF_WIDTH: usize = 5;
+
+fn bit(k: &[u64], i: usize) -> u64 {
+    k.get(i / 64).map_or(0, |limb| (limb >> (i % 64)) & 1)
+}
+
+/// The Booth digit of window `i`, in `[-2^(w-1), 2^(w-1)]`
+fn booth(k: &[u64], w: usize, i: usize) -> i32 {
+    // bits i·w - 1 up to i·w + w - 1, bit -1 is zero
+    let wbits = (0..=w).fold(0u64, |acc, j| {
+        let b = if i * w + j == 0 { 0 } else { bit(k, i * w + j - 1) };
+        acc | (b << j)
+    });
+    let mask = (1 << w) - 1;
+    (wbits & mask) as i32 - ((wbits >> 1) & mask) as i32
+}
+
+/// `[d]P` from the table of `P, 2P, ...`, reading every entry
+fn lookup<G: Group>(table: &[G], d: i32) -> G {
+    let negative = (d >> 31) & 1;
+    let abs = ((d ^ -negative) + negative) as u32;
+    let mut r = G::identity();
+    for (j, p) in table.iter().enumerate() {
+        r.conditional_assign(p, abs.ct_eq(&(j as u32 + 1)));
+    }
+    let neg = r.neg();
+    r.conditional_assign(&neg, Choice::from(negative as u8));
+    r
+}
+
+/// `[k]P` with signed fixed windows
+pub fn window_mul<G: Group>(p: &G, k: &[u64]) -> G {
+    let mut table = [*p; 1 << (WINDOW - 1)];
+    for j in 1..table.len() {
+        table[j] = if j % 2 == 1 {
+            table[j / 2].double()
+        } else {
+            table[j - 1].add(p)
+        };
+    }
+    // one more window than the bits keeps the top digit positive
+    let n = 64 * k.len() / WINDOW + 1;
+    let mut r = G::identity();
+    for i in (0..n).rev() {
+        for _ in 0..WINDOW {
+            r = r.double();
+        }
+        r = r.add(&lookup(&table, booth(k, WINDOW, i)));
+    }
+    r
+}
+
+/// The multiples `[d·2^(w·i)]P` of a point used many times, for every
+/// window `i` of [`window_mul`] and digit `d` in `1..=2^(w-1)`.
+///
+/// [`FixedBase::mul`] is then one addition per window and no doubling, with
+/// the same table reads whatever the scalar is. The table of a 256-bit scalar
+/// is 52 × 16 points, it pays off after a few multiplications.
+#[derive(Clone)]
+pub struct FixedBase<G> {
+    windows: Vec<[G; 1 << (WINDOW - 1)]>,
+}
+
+impl<G: Group> FixedBase<G> {
+    /// The table of `p` for scalars of `limbs` 64-bit limbs
+    pub fn new(p: &G, limbs: usize) -> FixedBase<G> {
+        let n = 64 * limbs / WINDOW + 1;
+        let mut windows = Vec::with_capacity(n);
+        let mut base = *p;
+        for _ in 0..n {
+            let mut row = [base; 1 << (WINDOW - 1)];
+            for j in 1..row.len() {
+                row[j] = row[j - 1].add(&base);
+            }
+            for _ in 0..WINDOW {
+                base = base.double();
+            }
+            windows.push(row);
+        }
+        FixedBase { windows }
+    }
+
+    /// The table itself, row `i` holding `[j]·2^(5i)·P` for `j` in 1..=16,
+    /// for storing it elsewhere
+    pub fn rows(&self) -> &[[G; 1 << (WINDOW - 1)]] {
+        &self.windows
+    }
+
+    /// A table from [`FixedBase::rows`], taken as it is
+    pub fn from_rows(rows: Vec<[G; 1 << (WINDOW - 1)]>) -> FixedBase<G> {
+        FixedBase { windows: rows }
+    }
+
+    /// `[k]P`, `k` of at most the limbs the table was built for
+    pub fn mul(&self, k: &[u64]) -> G {
+        assert!(
+            64 * k.len() / WINDOW < self.windows.len(),
+            "scalar wider than the fixed-base table"
+        );
+        let mut r = G::identity();
+        for (i, row) in self.windows.iter().enumerate() {
+            r = r.add(&lookup(row, booth(k, WINDOW, i)));
+        }
+        r
+    }
+}
+
+/// `[k]P` with the Montgomery ladder, one addition and one doubling per bit
+pub fn ladder_mul<G: Group>(p: &G, k: &[u64]) -> G {
+    let mut r0 = G::identity();
+    let mut r1 = *p;
+    for i in (0..64 * k.len()).rev() {
+        let b = Choice::from(bit(k, i) as u8);
+        G::conditional_swap(&mut r0, &mut r1, b);
+        r1 = r0.add(&r1);
+        r0 = r0.double();
+        G::conditional_swap(&mut r0, &mut r1, b);
+    }
+    r0
+}
+
+/// The width-`w` non-adjacent form of `k`, least significant digit first
+pub fn wnaf(k: &[u64], w: usize) -> Vec<i32> {
+    let mut k = k.to_vec();
+    k.push(0);
+    let mut digits = Vec::with_capacity(64 * k.len());
+    while k.iter().any(|&limb| limb != 0) {
+        let mut d = 0;
+        if k[0] & 1 == 1 {
+            d = (k[0] & ((1 << w) - 1)) as i32;
+            if d >= 1 << (w - 1) {
+                d -= 1 << w;
+            }
+            // k -= d
+            let (mut carry, add) = if d < 0 { (-d as u64, true) } else { (d as u64, false) };
+            for limb in k.iter_mut() {
+                let (v, c) = if add {
+                    limb.overflowing_add(carry)
+                } else {
+                    limb.overflowing_sub(carry)
+                };
+                *limb = v;
+                carry = c as u64;
+            }
+        }
+        digits.push(d);
+        for j in 0..k.len() {
+            k[j] = (k[j] >> 1) | (k.get(j + 1).map_or(0, |next| next << 63));
+        }
+    }
+    digits
+}
+
+/// `[k]P` in variable time, for public `k`
+pub fn wnaf_mul<G: Group>(p: &G, k: &[u64]) -> G {
+    // P, 3P, 5P, ...
+    let twice = p.double();
+    let mut table = [*p; 1 << (WNAF_WIDTH - 2)];
+    for j in 1..table.len() {
+        table[j] = table[j - 1].add(&twice);
+    }
+    let mut r = G::identity();
+    for &d in wnaf(k, WNAF_WIDTH).iter().rev() {
+        r = r.double();
+        if d > 0 {
+            r = r.add(&table[(d / 2) as usize]);
+        } else if d < 0 {
+            r = r.add(&table[(-d / 2) as usize].neg());
+        }
+    }
+    r
+}
+
+/// Bring every point but the identity to `Z = 1` with one inversion.
+pub fn batch_normalize<G: Normalize>(points: &mut [G]) {
+    let mut prefix = Vec::with_capacity(points.len());
+    let mut acc: Option<G::Field> = None;
+    for p in points.iter() {
+        if !p.is_identity() {
+            acc = Some(acc.map_or(p.z(), |a| G::field_mul(&a, &p.z())));
+        }
+        prefix.push(acc);
+    }
+    let Some(mut inv) = acc.map(|a| G::field_inv(&a)) else {
+        return;
+    };
+    for i in (0..points.len()).rev() {
+        if points[i].is_identity() {
+            continue;
+        }
+        // prefix[i - 1] is the product of the Z before this point
+        let before = if i == 0 { None } else { prefix[i - 1] };
+        let z_inv = before.map_or(inv, |b| G::field_mul(&inv, &b));
+        inv = G::field_mul(&inv, &points[i].z());
+        points[i] = points[i].normalize_with(&z_inv);
+    }
+}
+
+#[cfg(test)]
+mod test_group {
+    use super::*;
+
+    const Q: u64 = 1_000_000_007;
+
+    fn mul_mod(a: u64, b: u64) -> u64 {
+        (a as u128 * b as u128 % Q as u128) as u64
+    }
+
+    fn inv_mod(a: u64) -> u64 {
+        let (mut r, mut base, mut e) = (1, a, Q - 2);
+        while e > 0 {
+            if e & 1 == 1 {
+                r = mul_mod(r, base);
+            }
+            base = mul_mod(base, base);
+            e >>= 1;
+        }
+        r
+    }
+
+    /// Z/Q under addition, `x / z` in projective form
+    #[derive(Debug, Clone, Copy)]
+    struct Toy {
+        x: u64,
+        z: u64,
+    }
+
+    impl Toy {
+        fn value(&self) -> u64 {
+            mul_mod(self.x, inv_mod(self.z))
+        }
+    }
+
+    impl ConditionallySelectable for Toy {
+        fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+            Toy {
+                x: u64::conditional_select(&a.x, &b.x, choice),
+                z: u64::conditional_select(&a.z, &b.z, choice),
+            }
+        }
+    }
+
+    impl Group for Toy {
+        fn identity() -> Self {
+            Toy { x: 0, z: 1 }
+        }
+
+        fn is_identity(&self) -> bool {
+            self.x == 0
+        }
+
+        fn add(&self, rhs: &Self) -> Self {
+            let x = (mul_mod(self.x, rhs.z) + mul_mod(rhs.x, self.z)) % Q;
+            Toy { x, z: mul_mod(self.z, rhs.z) }
+        }
+
+        fn double(&self) -> Self {
+            self.add(self)
+        }
+
+        fn neg(&self) -> Self {
+            Toy { x: (Q - self.x) % Q, z: self.z }
+        }
+    }
+
+    impl Normalize for Toy {
+        type Field = u64;
+
+        fn z(&self) -> u64 {
+            self.z
+        }
+
+        fn field_mul(a: &u64, b: &u64) -> u64 {
+            mul_mod(*a, *b)
+        }
+
+        fn field_inv(a: &u64) -> u64 {
+            inv_mod(*a)
+        }
+
+        fn normalize_with(&self, z_inv: &u64) -> Self {
+            Toy { x: mul_mod(self.x, *z_inv), z: 1 }
+        }
+    }
+
+    fn expected(p: u64, k: &[u64]) -> u64 {
+        let k = k.iter().rev().fold(0, |acc, &limb| {
+            let hi = mul_mod(acc, mul_mod(1 << 32, 1 << 32));
+            (hi + limb % Q) % Q
+        });
+        mul_mod(p, k)
+    }
+
+    #[test]
+    fn test_group_mul() {
+        let p = Toy { x: 12345, z: 678 };
+        let scalars: [[u64; 4]; 6] = [
+            [0; 4],
+            [1, 0, 0, 0],
+            [31, 0, 0, 0],
+            [u64::MAX; 4],
+            [0x0123456789abcdef, 0xfedcba9876543210, 0x8000000000000001, 0x7fffffffffffffff],
+            [0, 0, 0, 1 << 63],
+        ];
+        for k in scalars {
+            let e = expected(p.value(), &k);
+            assert_eq!(e, window_mul(&p, &k).value(), "{:x?}", k);
+            assert_eq!(e, ladder_mul(&p, &k).value(), "{:x?}", k);
+            assert_eq!(e, wnaf_mul(&p, &k).value(), "{:x?}", k);
+            assert_eq!(e, FixedBase::new(&p, 4).mul(&k).value(), "{:x?}", k);
+        }
+        let table = FixedBase::new(&p, 4);
+        assert_eq!(expected(p.value(), &[99]), table.mul(&[99]).value());
+        let copy = FixedBase::from_rows(table.rows().to_vec());
+        assert_eq!(expected(p.value(), &[99]), copy.mul(&[99]).value());
+        assert_eq!(expected(p.value(), &[99]), window_mul(&p, &[99]).value());
+    }
+
+    #[test]
+    fn test_wnaf_digits() {
+        let k = [0xfedcba9876543210, 0x0123456789abcdef];
+        let digits = wnaf(&k, 5);
+        let mut nonzero = None;
+        for (i, &d) in digits.iter().enumerate() {
+            if d != 0 {
+                assert!(d % 2 != 0 && d.abs() < 16);
+                // at most one nonzero digit in any 5 consecutive
+                assert!(nonzero.is_none_or(|j| i - j >= 5));
+                nonzero = Some(i);
+            }
+        }
+        let sum = digits.iter().rev().fold(0i128, |acc, &d| acc * 2 + d as i128);
+        assert_eq!(((k[1] as i128) << 64) | k[0] as i128, sum);
+    }
+
+    #[test]
+    fn test_batch_normalize() {
+        let mut points = [
+            Toy { x: 5, z: 7 },
+            Toy::identity(),
+            Toy { x: 9, z: 11 },
+            Toy { x: 0, z: 3 },
+            Toy { x: 1, z: 2 },
+        ];
+        let values: Vec<u64> = points.iter().map(Toy::value).collect();
+        batch_normalize(&mut points);
+        for (p, v) in points.iter().zip(values) {
+            assert_eq!(v, p.value());
+            assert!(p.is_identity() || p.z == 1);
+        }
+        batch_normalize::<Toy>(&mut []);
+    }
+}
@@ -0,0 +1,129 @@
+#![doc = include_str!("../README.md")]
+
+pub mod audit;
+#[cfg(feature = "corpus")]
+pub mod corpus;
+#[cfg(feature = "timing-tests")]
+pub mod dudect;
+pub mod encoding;
+pub mod ghash;
+pub mod group;
+pub mod limb;
+
+/// Produce a signature over a message.
+///
+/// Implemented by `gm_sm2::key::Sm2PrivateKey` (default user ID) and
+/// `gm_sm9::key::Sm9SignKey`.
+pub trait Signer {
+    type Signature;
+    type Error;
+
+    fn sign(&self, msg: &[u8]) -> Result<Self::Signature, Self::Error>;
+}
+
+/// Check a signature produced by the matching [`Signer`].
+///
+/// Implemented by `gm_sm2::key::Sm2PublicKey` (default user ID) and
+/// `gm_sm9::key::Sm9Verifier` (master public key and signer ID).
+pub trait Verifier {
+    type Signature;
+    type Error;
+
+    fn verify(&self, msg: &[u8], sig: &Self::Signature) -> Result<(), Self::Error>;
+}
+
+/// Public key encryption.
+///
+/// Implemented by `gm_sm2::key::Sm2PublicKey` (C1C3C2) and
+/// `gm_sm9::key::Sm9Encryptor` (master public key and recipient ID).
+pub trait Encryptor {
+    type Error;
+
+    fn encrypt(&self, msg: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Decrypt what the matching [`Encryptor`] produced.
+///
+/// Implemented by `gm_sm2::key::Sm2PrivateKey` (C1C3C2) and
+/// `gm_sm9::key::Sm9Decryptor` (user key and its ID).
+pub trait Decryptor {
+    type Error;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Authenticated encryption with associated data, the output is
+/// `ciphertext || tag`.
+///
+/// Implemented by `gm_sm4::gcm::Sm4Gcm` and `gm_sm4::ccm::Sm4Ccm`. This is the
+/// workspace's own trait, not RustCrypto's `aead::Aead`, which the `aead`
+/// feature of gm-sm4 implements for the same modes.
+pub trait Aead {
+    type Error;
+
+    fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Self::Error>;
+
+    fn decrypt(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// Compare a MAC, tag or hash value in constant time.
+///
+/// Only the lengths, which are public, may end the comparison early.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    use subtle::ConstantTimeEq;
+    a.ct_eq(b).into()
+}
+
+/// The SM3 hash of a public key in its canonical encoding, for key pinning,
+/// logs and trust-on-first-use checks.
+///
+/// Displayed as lowercase hex, [`Fingerprint::to_base64`] gives the shorter
+/// unpadded base64 form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint(pub [u8; 32]);
+
+impl Fingerprint {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_base64(&self) -> String {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(self.0)
+    }
+}
+
+impl std::fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for b in self.0 {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_core {
+    use crate::{ct_eq, Fingerprint};
+
+    #[test]
+    fn test_ct_eq() {
+        assert!(ct_eq(b"", b""));
+        assert!(ct_eq(&[1, 2, 3], &[1, 2, 3]));
+        assert!(!ct_eq(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!ct_eq(&[1, 2, 3], &[1, 2]));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let mut b = [0u8; 32];
+        b[0] = 0xab;
+        b[31] = 0x01;
+        let fp = Fingerprint(b);
+        assert_eq!(
+            "ab00000000000000000000000000000000000000000000000000000000000001",
+            fp.to_string()
+        );
+        assert_eq!("qwAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE", fp.to_base64());
+    }
+}
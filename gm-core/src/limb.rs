@@ -0,0 +1,398 @@
+//! Arithmetic on the little-endian `u64` limbs of 256-bit integers, the `U256`
+//! of the SM2 and SM9 crates.
+//!
+//! With the `asm` feature on x86_64 the carry chains use the `adc` and `sbb`
+//! intrinsics, which cut a field addition by about a third and a Montgomery
+//! multiplication by a few percent. Elsewhere, and without the feature, the
+//! portable `u128` path is used. Both give the same results.
+//!
+//! On 32-bit targets, or with the `limb32` feature, the products work on eight
+//! `u32` limbs with `32 x 32 -> 64` multiplies, which such targets have in
+//! hardware, rather than on `u64` limbs that need a `u128` product.
+#[cfg(all(feature = "asm", target_arch = "x86_64"))]
+use core::arch::x86_64 as arch;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+/// `a + b + carry`, and the carry out
+#[inline(always)]
+pub fn adc(a: u64, b: u64, carry: u8) -> (u64, u8) {
+    #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+    {
+        let mut out = 0;
+        // SAFETY: adc is part of the x86_64 baseline
+        #[allow(unused_unsafe)]
+        let c = unsafe { arch::_addcarry_u64(carry, a, b, &mut out) };
+        (out, c)
+    }
+    #[cfg(not(all(feature = "asm", target_arch = "x86_64")))]
+    {
+        let s = a as u128 + b as u128 + carry as u128;
+        (s as u64, (s >> 64) as u8)
+    }
+}
+
+/// `a - b - borrow`, and the borrow out
+#[inline(always)]
+pub fn sbb(a: u64, b: u64, borrow: u8) -> (u64, u8) {
+    #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+    {
+        let mut out = 0;
+        // SAFETY: sbb is part of the x86_64 baseline
+        #[allow(unused_unsafe)]
+        let c = unsafe { arch::_subborrow_u64(borrow, a, b, &mut out) };
+        (out, c)
+    }
+    #[cfg(not(all(feature = "asm", target_arch = "x86_64")))]
+    {
+        let d = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+        (d as u64, (d >> 127) as u8)
+    }
+}
+
+/// `a + b * c + carry` as its low and high limbs, it cannot overflow.
+///
+/// The `u128` product already compiles to a single widening multiply, `mulx`
+/// measured no faster here.
+#[inline(always)]
+pub fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let s = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    (s as u64, (s >> 64) as u64)
+}
+
+/// `a + b` and whether it wrapped
+#[inline(always)]
+pub fn add(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut r = [0; 4];
+    let mut carry = 0;
+    for i in 0..4 {
+        (r[i], carry) = adc(a[i], b[i], carry);
+    }
+    (r, carry != 0)
+}
+
+/// `a - b` and whether it wrapped
+#[inline(always)]
+pub fn sub(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut r = [0; 4];
+    let mut borrow = 0;
+    for i in 0..4 {
+        (r[i], borrow) = sbb(a[i], b[i], borrow);
+    }
+    (r, borrow != 0)
+}
+
+/// The full 512-bit product
+#[inline(always)]
+pub fn mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 8] {
+    #[cfg(any(feature = "limb32", target_pointer_width = "32"))]
+    return limb32::mul(a, b);
+    #[cfg(not(any(feature = "limb32", target_pointer_width = "32")))]
+    return mul64(a, b);
+}
+
+#[cfg(any(test, not(any(feature = "limb32", target_pointer_width = "32"))))]
+#[inline(always)]
+fn mul64(a: &[u64; 4], b: &[u64; 4]) -> [u64; 8] {
+    let mut r = [0; 8];
+    for i in 0..4 {
+        let mut carry = 0;
+        for j in 0..4 {
+            (r[i + j], carry) = mac(r[i + j], a[i], b[j], carry);
+        }
+        r[i + 4] = carry;
+    }
+    r
+}
+
+/// Montgomery multiplication `a * b * 2^-256 mod m` (CIOS), for `a, b < m`.
+///
+/// `m_inv` is `-m^-1 mod 2^64`.
+#[inline(always)]
+pub fn mont_mul(a: &[u64; 4], b: &[u64; 4], m: &[u64; 4], m_inv: u64) -> [u64; 4] {
+    #[cfg(any(feature = "limb32", target_pointer_width = "32"))]
+    return limb32::mont_mul(a, b, m, m_inv);
+    #[cfg(not(any(feature = "limb32", target_pointer_width = "32")))]
+    return mont_mul64(a, b, m, m_inv);
+}
+
+#[cfg(any(test, not(any(feature = "limb32", target_pointer_width = "32"))))]
+#[inline(always)]
+fn mont_mul64(a: &[u64; 4], b: &[u64; 4], m: &[u64; 4], m_inv: u64) -> [u64; 4] {
+    let mut t = [0u64; 6];
+    for &bi in b {
+        // t = t + a * b[i]
+        let mut c = 0;
+        for j in 0..4 {
+            (t[j], c) = mac(t[j], a[j], bi, c);
+        }
+        let carry;
+        (t[4], carry) = adc(t[4], c, 0);
+        t[5] = carry as u64;
+
+        // t = (t + q * m) / 2^64
+        let q = t[0].wrapping_mul(m_inv);
+        let (_, mut c) = mac(t[0], q, m[0], 0);
+        for j in 1..4 {
+            (t[j - 1], c) = mac(t[j], q, m[j], c);
+        }
+        let carry;
+        (t[3], carry) = adc(t[4], c, 0);
+        t[4] = t[5] + carry as u64;
+    }
+
+    // t < 2m
+    let r = [t[0], t[1], t[2], t[3]];
+    let (d, borrow) = sub(&r, m);
+    select(&r, &d, Choice::from((t[4] != 0) as u8 | !borrow as u8))
+}
+
+/// `a == b` without an early exit
+pub fn ct_equal(a: &[u64; 4], b: &[u64; 4]) -> Choice {
+    a[..].ct_eq(&b[..])
+}
+
+/// `b` if `choice` is set, otherwise `a`, in constant time
+pub fn select(a: &[u64; 4], b: &[u64; 4], choice: Choice) -> [u64; 4] {
+    core::array::from_fn(|i| u64::conditional_select(&a[i], &b[i], choice))
+}
+
+/// `a + b` and whether it wrapped, usable in `const` items
+pub const fn const_add(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut r = [0; 4];
+    let mut carry = 0;
+    let mut i = 0;
+    while i < 4 {
+        let s = a[i] as u128 + b[i] as u128 + carry as u128;
+        r[i] = s as u64;
+        carry = (s >> 64) as u64;
+        i += 1;
+    }
+    (r, carry != 0)
+}
+
+/// `a - b` and whether it wrapped, usable in `const` items
+pub const fn const_sub(a: &[u64; 4], b: &[u64; 4]) -> ([u64; 4], bool) {
+    let mut r = [0; 4];
+    let mut borrow = 0;
+    let mut i = 0;
+    while i < 4 {
+        let d = (a[i] as u128).wrapping_sub(b[i] as u128 + borrow as u128);
+        r[i] = d as u64;
+        borrow = (d >> 127) as u64;
+        i += 1;
+    }
+    (r, borrow != 0)
+}
+
+pub const fn const_eq(a: &[u64; 4], b: &[u64; 4]) -> bool {
+    a[0] == b[0] && a[1] == b[1] && a[2] == b[2] && a[3] == b[3]
+}
+
+/// `a + b mod m` for `a, b < m`, usable in `const` items
+pub const fn const_add_mod(a: &[u64; 4], b: &[u64; 4], m: &[u64; 4]) -> [u64; 4] {
+    let (r, carry) = const_add(a, b);
+    let (d, borrow) = const_sub(&r, m);
+    if carry || !borrow {
+        d
+    } else {
+        r
+    }
+}
+
+/// `a - b mod m` for `a, b < m`, usable in `const` items
+pub const fn const_sub_mod(a: &[u64; 4], b: &[u64; 4], m: &[u64; 4]) -> [u64; 4] {
+    let (d, borrow) = const_sub(a, b);
+    if borrow {
+        const_add(&d, m).0
+    } else {
+        d
+    }
+}
+
+/// [`mont_mul`] usable in `const` items, to check curve constants when a
+/// crate is built. It is the portable code without the loops unrolled.
+pub const fn const_mont_mul(a: &[u64; 4], b: &[u64; 4], m: &[u64; 4], m_inv: u64) -> [u64; 4] {
+    let mut t = [0u64; 6];
+    let mut i = 0;
+    while i < 4 {
+        let mut c = 0u128;
+        let mut j = 0;
+        while j < 4 {
+            let s = t[j] as u128 + (a[j] as u128) * (b[i] as u128) + c;
+            t[j] = s as u64;
+            c = s >> 64;
+            j += 1;
+        }
+        let s = t[4] as u128 + c;
+        t[4] = s as u64;
+        t[5] = (s >> 64) as u64;
+
+        let q = t[0].wrapping_mul(m_inv);
+        let mut c = (t[0] as u128 + (q as u128) * (m[0] as u128)) >> 64;
+        let mut j = 1;
+        while j < 4 {
+            let s = t[j] as u128 + (q as u128) * (m[j] as u128) + c;
+            t[j - 1] = s as u64;
+            c = s >> 64;
+            j += 1;
+        }
+        let s = t[4] as u128 + c;
+        t[3] = s as u64;
+        t[4] = t[5] + (s >> 64) as u64;
+        i += 1;
+    }
+    let r = [t[0], t[1], t[2], t[3]];
+    let (d, borrow) = const_sub(&r, m);
+    if t[4] != 0 || !borrow {
+        d
+    } else {
+        r
+    }
+}
+
+/// The same products on eight `u32` limbs
+#[cfg(any(test, feature = "limb32", target_pointer_width = "32"))]
+mod limb32 {
+    fn split<const N: usize, const M: usize>(a: &[u64; N]) -> [u32; M] {
+        core::array::from_fn(|i| (a[i / 2] >> (32 * (i % 2))) as u32)
+    }
+
+    fn join<const N: usize, const M: usize>(a: &[u32; N]) -> [u64; M] {
+        core::array::from_fn(|i| a[2 * i] as u64 | (a[2 * i + 1] as u64) << 32)
+    }
+
+    /// `a + b * c + carry`, it cannot overflow
+    #[inline(always)]
+    fn mac(a: u32, b: u32, c: u32, carry: u32) -> (u32, u32) {
+        let s = a as u64 + (b as u64) * (c as u64) + carry as u64;
+        (s as u32, (s >> 32) as u32)
+    }
+
+    pub(super) fn mul(a: &[u64; 4], b: &[u64; 4]) -> [u64; 8] {
+        let (a, b): ([u32; 8], [u32; 8]) = (split(a), split(b));
+        let mut r = [0u32; 16];
+        for i in 0..8 {
+            let mut carry = 0;
+            for j in 0..8 {
+                (r[i + j], carry) = mac(r[i + j], a[i], b[j], carry);
+            }
+            r[i + 8] = carry;
+        }
+        join(&r)
+    }
+
+    pub(super) fn mont_mul(a: &[u64; 4], b: &[u64; 4], m: &[u64; 4], m_inv: u64) -> [u64; 4] {
+        let (a32, b32, m32): ([u32; 8], [u32; 8], [u32; 8]) = (split(a), split(b), split(m));
+        // -m^-1 mod 2^32 is the low half of -m^-1 mod 2^64
+        let m_inv = m_inv as u32;
+        let mut t = [0u32; 10];
+        for &bi in &b32 {
+            let mut c = 0;
+            for j in 0..8 {
+                (t[j], c) = mac(t[j], a32[j], bi, c);
+            }
+            let s = t[8] as u64 + c as u64;
+            t[8] = s as u32;
+            t[9] = (s >> 32) as u32;
+
+            let q = t[0].wrapping_mul(m_inv);
+            let (_, mut c) = mac(t[0], q, m32[0], 0);
+            for j in 1..8 {
+                (t[j - 1], c) = mac(t[j], q, m32[j], c);
+            }
+            let s = t[8] as u64 + c as u64;
+            t[7] = s as u32;
+            t[8] = t[9] + (s >> 32) as u32;
+        }
+
+        // t < 2m
+        let r = join(&[t[0], t[1], t[2], t[3], t[4], t[5], t[6], t[7]]);
+        let (d, borrow) = super::sub(&r, m);
+        super::select(&r, &d, subtle::Choice::from((t[8] != 0) as u8 | !borrow as u8))
+    }
+}
+
+#[cfg(test)]
+mod test_limb {
+    use super::*;
+
+    fn to_u128(a: &[u64]) -> u128 {
+        a[0] as u128 | (a[1] as u128) << 64
+    }
+
+    #[test]
+    fn test_limb_ops() {
+        let edge = [0, 1, 2, u64::MAX - 1, u64::MAX, 0x8000000000000000];
+        for &a in &edge {
+            for &b in &edge {
+                for c in 0..2 {
+                    let s = a as u128 + b as u128 + c as u128;
+                    assert_eq!((s as u64, (s >> 64) as u8), adc(a, b, c));
+                    let (d, borrow) = sbb(a, b, c);
+                    assert_eq!(a.wrapping_sub(b).wrapping_sub(c as u64), d);
+                    assert_eq!((a as u128) < b as u128 + c as u128, borrow == 1);
+                }
+                let (lo, hi) = mac(u64::MAX, a, b, u64::MAX);
+                let expect = (a as u128 * b as u128).wrapping_add(u64::MAX as u128 * 2);
+                assert_eq!(expect, to_u128(&[lo, hi]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_ops() {
+        let max = [u64::MAX; 4];
+        assert_eq!(([u64::MAX - 1, u64::MAX, u64::MAX, u64::MAX], true), add(&max, &max));
+        assert_eq!(([1, 0, 0, 0], true), sub(&[0; 4], &max));
+        // (2^256 - 1)^2 = 2^512 - 2^257 + 1
+        assert_eq!([1, 0, 0, 0, u64::MAX - 1, u64::MAX, u64::MAX, u64::MAX], mul(&max, &max));
+
+        // the SM9 prime, r = 2^256 mod m is 1 in Montgomery form and m - r is -1
+        let m: [u64; 4] = [0xe56f9b27e351457d, 0x21f2934b1a7aeedb, 0xd603ab4ff58ec745, 0xb640000002a3a6f1];
+        let m_inv = (0..6).fold(1u64, |x, _| x.wrapping_mul(2u64.wrapping_sub(m[0].wrapping_mul(x))));
+        let m_inv = m_inv.wrapping_neg();
+        let r = sub(&[0; 4], &m).0;
+        let neg_r = sub(&m, &r).0;
+        assert_eq!(r, mont_mul(&r, &r, &m, m_inv));
+        assert_eq!(r, mont_mul(&neg_r, &neg_r, &m, m_inv));
+        assert_eq!([1, 0, 0, 0], mont_mul(&r, &[1, 0, 0, 0], &m, m_inv));
+        assert_eq!(sub(&m, &[1, 0, 0, 0]).0, mont_mul(&neg_r, &[1, 0, 0, 0], &m, m_inv));
+    }
+    #[test]
+    fn test_limb32() {
+        let m: [u64; 4] = [0xe56f9b27e351457d, 0x21f2934b1a7aeedb, 0xd603ab4ff58ec745, 0xb640000002a3a6f1];
+        let m_inv = (0..6).fold(1u64, |x, _| x.wrapping_mul(2u64.wrapping_sub(m[0].wrapping_mul(x))));
+        let m_inv = m_inv.wrapping_neg();
+        let mut x = [0x0123456789abcdef, 0xfedcba9876543210, 0x8000000000000001, 0x7fffffffffffffff];
+        for _ in 0..100 {
+            let a = sub(&x, &m).0;
+            let a = if a[3] < m[3] { a } else { x.map(|l| l >> 1) };
+            assert_eq!(mul64(&a, &x), limb32::mul(&a, &x));
+            let b = mont_mul64(&a, &a, &m, m_inv);
+            assert_eq!(b, limb32::mont_mul(&a, &a, &m, m_inv));
+            x = mul64(&b, &x)[2..6].try_into().unwrap();
+        }
+        let max = [u64::MAX; 4];
+        assert_eq!(mul64(&max, &max), limb32::mul(&max, &max));
+    }
+    #[test]
+    fn test_const_ops() {
+        let m: [u64; 4] = [0xe56f9b27e351457d, 0x21f2934b1a7aeedb, 0xd603ab4ff58ec745, 0xb640000002a3a6f1];
+        let m_inv = (0..6).fold(1u64, |x, _| x.wrapping_mul(2u64.wrapping_sub(m[0].wrapping_mul(x))));
+        let m_inv = m_inv.wrapping_neg();
+        let a = [0x0123456789abcdef, 0xfedcba9876543210, 0x8000000000000001, 0x7fffffffffffffff];
+        let b = sub(&m, &[1, 0, 0, 0]).0;
+        assert_eq!(mont_mul(&a, &b, &m, m_inv), const_mont_mul(&a, &b, &m, m_inv));
+        assert_eq!(add(&a, &b), const_add(&a, &b));
+        assert_eq!(sub(&a, &b), const_sub(&a, &b));
+        assert!(const_eq(&a, &a) && !const_eq(&a, &b));
+        // a - 1 = a + (m - 1) mod m, and back
+        let a_1 = sub(&a, &[1, 0, 0, 0]).0;
+        assert_eq!(a_1, const_add_mod(&a, &b, &m));
+        assert_eq!(a, const_sub_mod(&a_1, &b, &m));
+        assert_eq!(b, const_sub_mod(&[0; 4], &[1, 0, 0, 0], &m));
+        assert!(bool::from(ct_equal(&a, &a)) && !bool::from(ct_equal(&a, &b)));
+        assert_eq!(a, select(&a, &b, Choice::from(0)));
+        assert_eq!(b, select(&a, &b, Choice::from(1)));
+    }
+}
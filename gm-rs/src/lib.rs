@@ -0,0 +1,74 @@
+#![doc = include_str!("../README.md")]
+
+pub use gm_core::encoding;
+pub use gm_core::{Aead, Decryptor, Encryptor, Fingerprint, Signer, Verifier};
+
+#[cfg(feature = "sm2")]
+pub use gm_sm2 as sm2;
+#[cfg(feature = "sm3")]
+pub use gm_sm3 as sm3;
+#[cfg(feature = "sm4")]
+pub use gm_sm4 as sm4;
+#[cfg(feature = "sm9")]
+pub use gm_sm9 as sm9;
+#[cfg(feature = "zuc")]
+pub use gm_zuc as zuc;
+
+#[cfg(test)]
+mod test_send_sync {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[cfg(feature = "sm2")]
+    #[test]
+    fn test_sm2() {
+        use gm_sm2::exchange::Exchange;
+        use gm_sm2::key::{Sm2PrivateKey, Sm2PublicKey};
+        use gm_sm2::signature::Sm2Signature;
+
+        assert_send_sync::<Sm2PrivateKey>();
+        assert_send_sync::<Sm2PublicKey>();
+        assert_send_sync::<Sm2Signature>();
+        assert_send_sync::<Exchange>();
+    }
+
+    #[cfg(feature = "sm3")]
+    #[test]
+    fn test_sm3() {
+        assert_send_sync::<gm_sm3::Sm3Hasher>();
+    }
+
+    #[cfg(feature = "sm4")]
+    #[test]
+    fn test_sm4() {
+        assert_send_sync::<gm_sm4::Sm4Cipher>();
+        assert_send_sync::<gm_sm4::Sm4CipherMode>();
+        assert_send_sync::<gm_sm4::gcm::Sm4Gcm>();
+    }
+
+    #[cfg(feature = "sm9")]
+    #[test]
+    fn test_sm9() {
+        use gm_sm9::key::{
+            Sm9Decryptor, Sm9EncKey, Sm9EncMasterKey, Sm9Encryptor, Sm9SignKey, Sm9SignMasterKey,
+            Sm9Verifier,
+        };
+        use gm_sm9::signature::Sm9Signature;
+
+        assert_send_sync::<Sm9SignMasterKey>();
+        assert_send_sync::<Sm9EncMasterKey>();
+        assert_send_sync::<Sm9SignKey>();
+        assert_send_sync::<Sm9EncKey>();
+        assert_send_sync::<Sm9Verifier>();
+        assert_send_sync::<Sm9Encryptor>();
+        assert_send_sync::<Sm9Decryptor>();
+        assert_send_sync::<Sm9Signature>();
+    }
+
+    #[cfg(feature = "zuc")]
+    #[test]
+    fn test_zuc() {
+        assert_send_sync::<gm_zuc::ZUC>();
+        assert_send_sync::<gm_zuc::eea::EEA>();
+        assert_send_sync::<gm_zuc::eia::EIA>();
+    }
+}
@@ -0,0 +1,229 @@
+//! Adaptor signatures for atomic swaps and payment channels.
+//!
+//! A pre-signature is an SM2 signature encrypted to a statement `T = [y]G`:
+//! anyone can check it against `T`, only the holder of `y` can complete it
+//! into an ordinary signature that [`Sm2PublicKey::verify`] accepts, and
+//! whoever sees both learns `y`. A statement and its secret are an SM2 key pair.
+//!
+//! With `e = SM3(ZA || M)` and the signing key `(d, P)`:
+//!
+//! * `R = [k]G + [1 + d]T`, `r = e + x(R) mod n`, `ŝ = (1 + d)^-1·(k - r·d) mod n`
+//! * `V = [d]T` and a Chaum-Pedersen proof that `log_G P = log_T V`
+//! * completing gives `s = ŝ + y`, extracting `y = s - ŝ`
+//!
+//! The nonce of the completed signature is `k + (1 + d)·y`, so it verifies
+//! as `r = e + x([s]G + [r + s]P)`. The pre-signature is `r || ŝ || V || c || z`
+//! with `V` compressed, 161 bytes.
+//!
+//! [`Sm2PublicKey::verify`]: crate::key::Sm2PublicKey::verify
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_inv, fn_mul, fn_random, fn_sub};
+use crate::fields::fp64::fp_from_mont;
+use crate::fields::FieldModOperation;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::{g_mul, Point};
+use crate::signature::Sm2Signature;
+use crate::u256::{u256_cmp, u256_from_be_bytes, u256_to_be_bytes, U256, SM2_ONE};
+use crate::util::{compute_za, DEFAULT_ID};
+
+const DLEQ_TAG: &[u8] = b"SM2-ADAPTOR-DLEQ";
+
+pub const PRE_SIGNATURE_LEN: usize = 32 + 32 + 33 + 32 + 32;
+
+#[derive(Debug, Clone)]
+pub struct Sm2PreSignature {
+    r: U256,
+    s_hat: U256,
+    v: Point,
+    c: U256,
+    z: U256,
+}
+
+impl Sm2PreSignature {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            u256_to_be_bytes(&self.r),
+            u256_to_be_bytes(&self.s_hat),
+            self.v.to_byte_be(true),
+            u256_to_be_bytes(&self.c),
+            u256_to_be_bytes(&self.z),
+        ]
+        .concat()
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Self> {
+        if b.len() != PRE_SIGNATURE_LEN {
+            return Err(Sm2Error::InvalidLength);
+        }
+        Ok(Sm2PreSignature {
+            r: fn_from_bytes(&b[..32])?,
+            s_hat: fn_from_bytes(&b[32..64])?,
+            v: Sm2PublicKey::new(&b[64..97])?.point,
+            c: fn_from_bytes(&b[97..129])?,
+            z: fn_from_bytes(&b[129..])?,
+        })
+    }
+}
+
+fn digest(id: Option<&str>, pk: &Point, msg: &[u8]) -> Sm2Result<U256> {
+    let za = compute_za(id.unwrap_or(DEFAULT_ID), pk)?;
+    Ok(u256_from_be_bytes(&sm3_hash(&[&za[..], msg].concat())))
+}
+
+fn x_of(p: &Point) -> U256 {
+    u256_from_be_bytes(&fp_from_mont(&p.to_affine_point().x).to_byte_be())
+}
+
+/// Fiat-Shamir challenge of the proof that `P` and `V` share their logarithm to `G` and `T`
+fn dleq_challenge(p: &Point, t: &Point, v: &Point, a1: &Point, a2: &Point) -> U256 {
+    let mut data = DLEQ_TAG.to_vec();
+    for point in [p, t, v, a1, a2] {
+        data.extend_from_slice(&point.to_byte_be(false));
+    }
+    fn_add(&u256_from_be_bytes(&sm3_hash(&data)), &[0; 4])
+}
+
+/// Sign `msg` encrypted to `statement`.
+pub fn pre_sign(
+    sk: &Sm2PrivateKey,
+    id: Option<&str>,
+    msg: &[u8],
+    statement: &Sm2PublicKey,
+) -> Sm2Result<Sm2PreSignature> {
+    let t = &statement.point;
+    t.check()?;
+    let pk = &sk.public_key.point;
+    let e = digest(id, pk, msg)?;
+    let d = &sk.d;
+    let one_plus_d = fn_add(&SM2_ONE, d);
+    let v = t.scalar_mul(d);
+
+    let (r, s_hat) = loop {
+        let k = fn_random();
+        let big_r = g_mul(&k).point_add(&t.point_add(&v));
+        if big_r.is_zero() {
+            continue;
+        }
+        let r = fn_add(&e, &x_of(&big_r));
+        if r.is_zero() {
+            continue;
+        }
+        let s_hat = fn_mul(&fn_inv(&one_plus_d), &fn_sub(&k, &fn_mul(&r, d)));
+        break (r, s_hat);
+    };
+
+    let w = fn_random();
+    let c = dleq_challenge(pk, t, &v, &g_mul(&w), &t.scalar_mul(&w));
+    let z = fn_add(&w, &fn_mul(&c, d));
+    Ok(Sm2PreSignature { r, s_hat, v, c, z })
+}
+
+/// Check that `pre` completes, with the secret of `statement`, into a
+/// signature of `msg` by `pk`.
+pub fn pre_verify(
+    pk: &Sm2PublicKey,
+    id: Option<&str>,
+    msg: &[u8],
+    statement: &Sm2PublicKey,
+    pre: &Sm2PreSignature,
+) -> Sm2Result<()> {
+    let (p, t) = (&pk.point, &statement.point);
+    t.check()?;
+    if pre.r.is_zero() || pre.v.is_zero() || !pre.v.is_valid() {
+        return Err(Sm2Error::InvalidDigest);
+    }
+
+    // A1 = [z]G - [c]P, A2 = [z]T - [c]V
+    let a1 = g_mul(&pre.z).point_add(&p.scalar_mul(&pre.c).neg());
+    let a2 = t.scalar_mul(&pre.z).point_add(&pre.v.scalar_mul(&pre.c).neg());
+    if u256_cmp(&dleq_challenge(p, t, &pre.v, &a1, &a2), &pre.c) != 0 {
+        return Err(Sm2Error::InvalidDigest);
+    }
+
+    // [k]G = [ŝ]G + [r + ŝ]P
+    let k_g = g_mul(&pre.s_hat).point_add(&p.scalar_mul(&fn_add(&pre.r, &pre.s_hat)));
+    let big_r = k_g.point_add(&t.point_add(&pre.v));
+    if big_r.is_zero() {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    let e = digest(id, p, msg)?;
+    if u256_cmp(&fn_add(&e, &x_of(&big_r)), &pre.r) != 0 {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    Ok(())
+}
+
+/// Complete `pre` with the secret of its statement into a 64-byte `r || s` signature.
+pub fn complete(pre: &Sm2PreSignature, secret: &Sm2PrivateKey) -> Sm2Result<Vec<u8>> {
+    let s = fn_add(&pre.s_hat, &secret.d);
+    if fn_add(&pre.r, &s).is_zero() {
+        return Err(Sm2Error::ZeroSig);
+    }
+    Ok(Sm2Signature::new(pre.r, s)?.to_vec())
+}
+
+/// Recover the secret of `statement` from `pre` and the signature completed from it.
+pub fn extract(pre: &Sm2PreSignature, sig: &[u8], statement: &Sm2PublicKey) -> Sm2Result<Sm2PrivateKey> {
+    let Sm2Signature { r, s } = Sm2Signature::from_slice(sig)?;
+    if u256_cmp(&r, &pre.r) != 0 {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    let secret = Sm2PrivateKey::new(&u256_to_be_bytes(&fn_sub(&s, &pre.s_hat)))?;
+    if secret.public_key.to_bytes(false) != statement.to_bytes(false) {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod test_adaptor {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_adaptor_swap() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let (statement, secret) = gen_keypair().unwrap();
+        let msg = b"pay 1 coin to Bob";
+
+        let pre = pre_sign(&sk, None, msg, &statement).unwrap();
+        let pre = Sm2PreSignature::from_bytes(&pre.to_bytes()).unwrap();
+        pre_verify(&pk, None, msg, &statement, &pre).unwrap();
+        // the pre-signature alone is not a signature
+        assert!(pk.verify(None, msg, &[u256_to_be_bytes(&pre.r), u256_to_be_bytes(&pre.s_hat)].concat()).is_err());
+
+        let sig = complete(&pre, &secret).unwrap();
+        pk.verify(None, msg, &sig).unwrap();
+        assert!(extract(&pre, &sig, &statement).unwrap() == secret);
+    }
+
+    #[test]
+    fn test_adaptor_rejects() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let (statement, secret) = gen_keypair().unwrap();
+        let (other, other_secret) = gen_keypair().unwrap();
+        let pre = pre_sign(&sk, Some("ALICE123@YAHOO.COM"), b"msg", &statement).unwrap();
+        pre_verify(&pk, Some("ALICE123@YAHOO.COM"), b"msg", &statement, &pre).unwrap();
+
+        assert!(pre_verify(&pk, None, b"msg", &statement, &pre).is_err());
+        assert!(pre_verify(&pk, Some("ALICE123@YAHOO.COM"), b"Msg", &statement, &pre).is_err());
+        assert!(pre_verify(&pk, Some("ALICE123@YAHOO.COM"), b"msg", &other, &pre).is_err());
+        assert!(pre_verify(&other, Some("ALICE123@YAHOO.COM"), b"msg", &statement, &pre).is_err());
+        let bytes = pre.to_bytes();
+        for i in (0..bytes.len()).step_by(5) {
+            let mut b = bytes.clone();
+            b[i] ^= 1;
+            if let Ok(pre) = Sm2PreSignature::from_bytes(&b) {
+                assert!(pre_verify(&pk, Some("ALICE123@YAHOO.COM"), b"msg", &statement, &pre).is_err());
+            }
+        }
+
+        // the wrong secret gives a signature that does not verify
+        let sig = complete(&pre, &other_secret).unwrap();
+        assert!(pk.verify(Some("ALICE123@YAHOO.COM"), b"msg", &sig).is_err());
+        let sig = complete(&pre, &secret).unwrap();
+        assert!(extract(&pre, &sig, &other).is_err());
+    }
+}
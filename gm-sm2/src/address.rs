@@ -0,0 +1,149 @@
+//! Account addresses of SM2 public keys, as on the SM-crypto chains.
+//!
+//! The address is the last 20 bytes of `SM3(x || y)`, the 64-byte affine
+//! point without the `0x04` prefix, the same as FISCO BCOS in SM mode. It is
+//! rendered as:
+//!
+//! * hex: `0x` and 40 lowercase digits
+//! * base58check: `version || address || checksum`, the checksum the first 4
+//!   bytes of `SM3(SM3(version || address))`
+//! * bech32 (BIP 173) under a human-readable prefix of the chain
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::address::Address;
+//! use gm_sm2::key::gen_keypair;
+//!
+//! let (pk, _) = gen_keypair().unwrap();
+//! let address = pk.address();
+//! assert_eq!(address, Address::from_hex(&address.to_hex()).unwrap());
+//! let b58 = address.to_base58check(0);
+//! assert_eq!((0, address), Address::from_base58check(&b58).unwrap());
+//! let b32 = address.to_bech32("sm").unwrap();
+//! assert_eq!(address, Address::from_bech32("sm", &b32).unwrap());
+//! ```
+use gm_core::encoding::{base58_decode, base58_encode, bech32_decode, bech32_encode, hex_decode, hex_encode};
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::Sm2PublicKey;
+
+pub const ADDRESS_LEN: usize = 20;
+
+/// `SM3(x || y)` of the affine point
+pub fn key_hash(pk: &Sm2PublicKey) -> [u8; 32] {
+    sm3_hash(&pk.to_bytes(false)[1..])
+}
+
+/// The last 20 bytes of [`key_hash`], displayed as `0x` hex
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Address(pub [u8; ADDRESS_LEN]);
+
+impl Address {
+    pub fn from_public_key(pk: &Sm2PublicKey) -> Address {
+        Address(key_hash(pk)[32 - ADDRESS_LEN..].try_into().unwrap())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; ADDRESS_LEN] {
+        &self.0
+    }
+
+    pub fn from_slice(b: &[u8]) -> Sm2Result<Address> {
+        Ok(Address(b.try_into().map_err(|_| Sm2Error::InvalidAddress)?))
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("0x{}", hex_encode(&self.0))
+    }
+
+    /// 40 hex digits in either case, with or without `0x`
+    pub fn from_hex(s: &str) -> Sm2Result<Address> {
+        let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+        Address::from_slice(&hex_decode(digits).map_err(|_| Sm2Error::InvalidAddress)?)
+    }
+
+    pub fn to_base58check(&self, version: u8) -> String {
+        let mut data = Vec::with_capacity(1 + ADDRESS_LEN + 4);
+        data.push(version);
+        data.extend_from_slice(&self.0);
+        let checksum = sm3_hash(&sm3_hash(&data));
+        data.extend_from_slice(&checksum[..4]);
+        base58_encode(&data)
+    }
+
+    /// Returns the version byte and the address
+    pub fn from_base58check(s: &str) -> Sm2Result<(u8, Address)> {
+        let data = base58_decode(s).map_err(|_| Sm2Error::InvalidAddress)?;
+        if data.len() != 1 + ADDRESS_LEN + 4 {
+            return Err(Sm2Error::InvalidAddress);
+        }
+        let (body, checksum) = data.split_at(1 + ADDRESS_LEN);
+        if sm3_hash(&sm3_hash(body))[..4] != *checksum {
+            return Err(Sm2Error::InvalidAddress);
+        }
+        Ok((body[0], Address::from_slice(&body[1..])?))
+    }
+
+    /// A lowercase `hrp` of 1 to 83 characters
+    pub fn to_bech32(&self, hrp: &str) -> Sm2Result<String> {
+        bech32_encode(hrp, &self.0).map_err(|_| Sm2Error::InvalidAddress)
+    }
+
+    /// Also fails for another prefix than `hrp`
+    pub fn from_bech32(hrp: &str, s: &str) -> Sm2Result<Address> {
+        let (found, data) = bech32_decode(s).map_err(|_| Sm2Error::InvalidAddress)?;
+        if found != hrp {
+            return Err(Sm2Error::InvalidAddress);
+        }
+        Address::from_slice(&data)
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Sm2PublicKey {
+    /// See [`Address::from_public_key`]
+    pub fn address(&self) -> Address {
+        Address::from_public_key(self)
+    }
+}
+
+#[cfg(test)]
+mod test_address {
+    use super::*;
+    use crate::key::Sm2PrivateKey;
+
+    #[test]
+    fn test_address() {
+        let sk = Sm2PrivateKey::new(&[7; 32]).unwrap();
+        let pk = sk.public_key;
+        let address = pk.address();
+        let xy = [pk.x_bytes(), pk.y_bytes()].concat();
+        assert_eq!(sm3_hash(&xy)[12..], address.0);
+        // the encoding the key was parsed from does not matter
+        assert_eq!(address, Sm2PublicKey::new(&pk.to_bytes(true)).unwrap().address());
+
+        let hex = address.to_hex();
+        assert_eq!(42, hex.len());
+        assert_eq!(hex, address.to_string());
+        assert_eq!(address, Address::from_hex(&hex[2..].to_uppercase()).unwrap());
+        assert_eq!(Err(Sm2Error::InvalidAddress), Address::from_hex(&hex[..40]));
+
+        let b58 = address.to_base58check(0x41);
+        assert_eq!((0x41, address), Address::from_base58check(&b58).unwrap());
+        let mut data = base58_decode(&b58).unwrap();
+        data[5] ^= 1;
+        assert!(Address::from_base58check(&base58_encode(&data)).is_err());
+        assert!(Address::from_base58check(&base58_encode(&data[1..])).is_err());
+
+        let b32 = address.to_bech32("sm").unwrap();
+        assert!(b32.starts_with("sm1"));
+        assert_eq!(address, Address::from_bech32("sm", &b32.to_uppercase()).unwrap());
+        assert!(Address::from_bech32("gm", &b32).is_err());
+        assert!(address.to_bech32("SM").is_err());
+    }
+}
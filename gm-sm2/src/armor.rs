@@ -0,0 +1,229 @@
+//! ASCII armored SM2 messages, in the style of OpenPGP armor (RFC 4880 6.2),
+//! to pass ciphertexts and signed messages around as text.
+//!
+//! ```text
+//! -----BEGIN SM2 MESSAGE-----
+//! Type: Encrypted
+//! Key: <hex fingerprint of the recipient or the signer>
+//!
+//! <base64 body, 64 columns>
+//! =<base64 CRC-24 of the body>
+//! -----END SM2 MESSAGE-----
+//! ```
+//!
+//! An `Encrypted` body is the DER C1C3C2 ciphertext, a `Signed` body is the
+//! 64-byte `r || s` signature under the default user ID followed by the
+//! message. Other headers, such as `Comment`, are ignored on input.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::armor::{decrypt_armored, encrypt_armored, sign_armored, verify_armored};
+//! use gm_sm2::key::gen_keypair;
+//!
+//! let (pk, sk) = gen_keypair().unwrap();
+//! let text = encrypt_armored(&pk, b"hello").unwrap();
+//! assert!(text.starts_with("-----BEGIN SM2 MESSAGE-----\nType: Encrypted\n"));
+//! assert_eq!(b"hello".to_vec(), decrypt_armored(&sk, &text).unwrap());
+//!
+//! let text = sign_armored(&sk, b"hello").unwrap();
+//! assert_eq!(b"hello".to_vec(), verify_armored(&pk, &text).unwrap());
+//! ```
+use gm_core::encoding::{base64_decode, base64_encode, hex_decode};
+use gm_core::Fingerprint;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::{Sm2Model, Sm2PrivateKey, Sm2PublicKey};
+
+const BEGIN: &str = "-----BEGIN SM2 MESSAGE-----";
+const END: &str = "-----END SM2 MESSAGE-----";
+
+/// What the body of an [`Armored`] message holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmorKind {
+    Encrypted,
+    Signed,
+}
+
+impl ArmorKind {
+    fn name(self) -> &'static str {
+        match self {
+            ArmorKind::Encrypted => "Encrypted",
+            ArmorKind::Signed => "Signed",
+        }
+    }
+}
+
+/// A decoded armored message, the body not yet decrypted or verified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Armored {
+    pub kind: ArmorKind,
+    /// The recipient of an encrypted message, the signer of a signed one
+    pub key: Fingerprint,
+    pub body: Vec<u8>,
+}
+
+impl Armored {
+    pub fn to_armor(&self) -> String {
+        let mut out = format!("{}\nType: {}\nKey: {}\n\n", BEGIN, self.kind.name(), self.key);
+        let body = base64_encode(&self.body);
+        for line in body.as_bytes().chunks(64) {
+            // base64 output is ASCII
+            out.push_str(std::str::from_utf8(line).unwrap());
+            out.push('\n');
+        }
+        let crc = crc24(&self.body).to_be_bytes();
+        out.push_str(&format!("={}\n{}\n", base64_encode(&crc[1..]), END));
+        out
+    }
+
+    /// Parse one armored block, surrounding whitespace and CRLF line endings
+    /// are accepted. The checksum is required.
+    pub fn from_armor(text: &str) -> Sm2Result<Armored> {
+        let mut lines = text.trim().lines().map(|l| l.trim_end_matches('\r'));
+        if lines.next() != Some(BEGIN) {
+            return Err(Sm2Error::InvalidArmor);
+        }
+        let (mut kind, mut key) = (None, None);
+        for line in lines.by_ref() {
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(": ").ok_or(Sm2Error::InvalidArmor)?;
+            match name {
+                "Type" if value == "Encrypted" => kind = Some(ArmorKind::Encrypted),
+                "Type" if value == "Signed" => kind = Some(ArmorKind::Signed),
+                "Type" => return Err(Sm2Error::InvalidArmor),
+                "Key" => {
+                    let fp = hex_decode(value).map_err(|_| Sm2Error::InvalidArmor)?;
+                    key = Some(Fingerprint(fp.try_into().map_err(|_| Sm2Error::InvalidArmor)?));
+                }
+                _ => {}
+            }
+        }
+        let (kind, key) = kind.zip(key).ok_or(Sm2Error::InvalidArmor)?;
+
+        let mut body = String::new();
+        let mut crc = None;
+        for line in lines.by_ref() {
+            if let Some(c) = line.strip_prefix('=') {
+                crc = Some(base64_decode(c).map_err(|_| Sm2Error::InvalidArmor)?);
+                break;
+            }
+            body.push_str(line);
+        }
+        if crc.is_none() || lines.next() != Some(END) || lines.next().is_some() {
+            return Err(Sm2Error::InvalidArmor);
+        }
+        let body = base64_decode(&body).map_err(|_| Sm2Error::InvalidArmor)?;
+        if crc.as_deref() != Some(&crc24(&body).to_be_bytes()[1..]) {
+            return Err(Sm2Error::InvalidArmor);
+        }
+        Ok(Armored { kind, key, body })
+    }
+}
+
+/// Encrypt `msg` to `pk` and armor it.
+pub fn encrypt_armored(pk: &Sm2PublicKey, msg: &[u8]) -> Sm2Result<String> {
+    let body = pk.encrypt_asn1(msg, false, Sm2Model::C1C3C2)?;
+    Ok(Armored { kind: ArmorKind::Encrypted, key: pk.fingerprint(), body }.to_armor())
+}
+
+/// Fails with [`Sm2Error::KeyMismatch`] when the message is for another key.
+pub fn decrypt_armored(sk: &Sm2PrivateKey, text: &str) -> Sm2Result<Vec<u8>> {
+    let armored = expect(text, ArmorKind::Encrypted, &sk.public_key)?;
+    sk.decrypt_asn1(&armored.body, false, Sm2Model::C1C3C2)
+}
+
+/// Sign `msg` and armor it together with the signature.
+pub fn sign_armored(sk: &Sm2PrivateKey, msg: &[u8]) -> Sm2Result<String> {
+    let body = [sk.sign(None, msg)?, msg.to_vec()].concat();
+    Ok(Armored { kind: ArmorKind::Signed, key: sk.public_key.fingerprint(), body }.to_armor())
+}
+
+/// Verify a signed message and return it. Fails with [`Sm2Error::KeyMismatch`]
+/// when another key signed it.
+pub fn verify_armored(pk: &Sm2PublicKey, text: &str) -> Sm2Result<Vec<u8>> {
+    let armored = expect(text, ArmorKind::Signed, pk)?;
+    if armored.body.len() < 64 {
+        return Err(Sm2Error::InvalidArmor);
+    }
+    let (sig, msg) = armored.body.split_at(64);
+    pk.verify(None, msg, sig)?;
+    Ok(msg.to_vec())
+}
+
+fn expect(text: &str, kind: ArmorKind, pk: &Sm2PublicKey) -> Sm2Result<Armored> {
+    let armored = Armored::from_armor(text)?;
+    if armored.kind != kind {
+        return Err(Sm2Error::InvalidArmor);
+    }
+    if armored.key != pk.fingerprint() {
+        return Err(Sm2Error::KeyMismatch);
+    }
+    Ok(armored)
+}
+
+/// The CRC-24 of OpenPGP armor, RFC 4880 6.1
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc = 0xb704ceu32;
+    for &b in data {
+        crc ^= (b as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+#[cfg(test)]
+mod test_armor {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_crc24() {
+        assert_eq!(0xb704ce, crc24(b""));
+        // the CRC-24/OPENPGP check value
+        assert_eq!(0x21cf02, crc24(b"123456789"));
+    }
+
+    #[test]
+    fn test_armor() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let (other, other_sk) = gen_keypair().unwrap();
+        let msg = vec![0x5a; 100];
+
+        let text = encrypt_armored(&pk, &msg).unwrap();
+        assert!(text.contains(&format!("Key: {}\n\n", pk.fingerprint())));
+        assert!(text.ends_with("\n-----END SM2 MESSAGE-----\n"));
+        assert_eq!(msg, decrypt_armored(&sk, &text).unwrap());
+        assert_eq!(msg, decrypt_armored(&sk, &text.replace('\n', "\r\n")).unwrap());
+        let commented = text.replacen("Type:", "Comment: gm-rs\nType:", 1);
+        assert_eq!(msg, decrypt_armored(&sk, &commented).unwrap());
+        assert_eq!(Err(Sm2Error::KeyMismatch), decrypt_armored(&other_sk, &text));
+        assert_eq!(Err(Sm2Error::InvalidArmor), verify_armored(&pk, &text));
+
+        let text = sign_armored(&sk, &msg).unwrap();
+        assert_eq!(msg, verify_armored(&pk, &text).unwrap());
+        assert_eq!(Err(Sm2Error::KeyMismatch), verify_armored(&other, &text));
+
+        // a changed body fails the checksum, a missing checksum is rejected
+        let armored = Armored::from_armor(&text).unwrap();
+        let mut lines: Vec<&str> = text.lines().collect();
+        let first = if lines[4].starts_with('A') { "B" } else { "A" };
+        let body_line = format!("{}{}", first, &lines[4][1..]);
+        lines[4] = &body_line;
+        assert_eq!(Err(Sm2Error::InvalidArmor), Armored::from_armor(&lines.join("\n")));
+        let no_crc: Vec<&str> = text.lines().filter(|l| !l.starts_with('=')).collect();
+        assert_eq!(Err(Sm2Error::InvalidArmor), Armored::from_armor(&no_crc.join("\n")));
+
+        // a forged signature with a valid checksum
+        let mut forged = armored.clone();
+        forged.body[70] ^= 1;
+        assert!(verify_armored(&pk, &forged.to_armor()).is_err());
+        assert_eq!(armored, Armored::from_armor(&armored.to_armor()).unwrap());
+    }
+}
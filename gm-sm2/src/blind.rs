@@ -0,0 +1,180 @@
+//! Blind SM2 signatures, for anonymous credentials and e-cash.
+//!
+//! The requester gets an ordinary SM2 signature that [`Sm2PublicKey::verify`]
+//! accepts, the signer never sees the message or the final signature and
+//! cannot link the two later. It rests on `[s]Q + [r]P = R` for every SM2
+//! signature, with `Q = G + P = [1 + d]G` and `R = [k]G`:
+//!
+//! * signer: draws `k`, sends `R = [k]G`
+//! * requester: draws `a`, `b`, computes `R' = [a]R + [b]Q`,
+//!   `r' = e + x(R') mod n` and sends `r̂ = r' / a`
+//! * signer: sends `ŝ = (1 + d)^-1·(k - r̂·d) mod n`
+//! * requester: `s' = a·ŝ + b`, the signature is `(r', s')`
+//!
+//! Any `(r̂, ŝ)` the signer saw and any `(r', s')` are consistent with exactly
+//! one `(a, b)`, which is what makes the sessions unlinkable. As with blind
+//! Schnorr signatures, a signer running many sessions concurrently is open to
+//! the ROS attack, so it should complete one session before opening the next.
+//! A [`BlindNonce`] is consumed by [`blind_sign`] and must never be reused.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::blind::{blind_nonce_generate, blind_sign, BlindRequest};
+//! use gm_sm2::key::gen_keypair;
+//!
+//! let (pk, sk) = gen_keypair().unwrap();
+//! let (nonce, commitment) = blind_nonce_generate();
+//! let (request, blinded) = BlindRequest::new(&pk, None, b"coin 1", &commitment).unwrap();
+//! let s_hat = blind_sign(&sk, nonce, &blinded).unwrap();
+//! let sig = request.unblind(&s_hat).unwrap();
+//! pk.verify(None, b"coin 1", &sig).unwrap();
+//! ```
+//!
+//! [`Sm2PublicKey::verify`]: crate::key::Sm2PublicKey::verify
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_inv, fn_mul, fn_random, fn_sub};
+use crate::fields::fp64::fp_from_mont;
+use crate::fields::FieldModOperation;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::{u256_from_be_bytes, u256_to_be_bytes, U256, SM2_ONE};
+use crate::util::{compute_za, DEFAULT_ID};
+
+/// The signer's `k` for one session, consumed by [`blind_sign`]
+pub struct BlindNonce {
+    k: U256,
+}
+
+/// `R = [k]G`, sent to the requester
+#[derive(Debug, Clone)]
+pub struct BlindCommitment(Point);
+
+impl BlindCommitment {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_byte_be(true)
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Self> {
+        Ok(BlindCommitment(Sm2PublicKey::new(b)?.point))
+    }
+}
+
+/// Signer, first step: a fresh nonce and its commitment.
+pub fn blind_nonce_generate() -> (BlindNonce, BlindCommitment) {
+    let k = fn_random();
+    (BlindNonce { k }, BlindCommitment(g_mul(&k)))
+}
+
+/// Signer, second step: sign the 32-byte blinded `r̂`, consuming the nonce.
+pub fn blind_sign(sk: &Sm2PrivateKey, nonce: BlindNonce, blinded: &[u8]) -> Sm2Result<Vec<u8>> {
+    let r_hat = fn_from_bytes(blinded)?;
+    if r_hat.is_zero() {
+        return Err(Sm2Error::ZeroSig);
+    }
+    let inv = fn_inv(&fn_add(&SM2_ONE, &sk.d));
+    let s_hat = fn_mul(&inv, &fn_sub(&nonce.k, &fn_mul(&r_hat, &sk.d)));
+    if s_hat.is_zero() {
+        return Err(Sm2Error::ZeroSig);
+    }
+    Ok(u256_to_be_bytes(&s_hat))
+}
+
+/// The requester's side of one session, holding the blinding factors
+pub struct BlindRequest {
+    pk: Sm2PublicKey,
+    id: Option<&'static str>,
+    msg: Vec<u8>,
+    a: U256,
+    b: U256,
+    r: U256,
+}
+
+impl BlindRequest {
+    /// Blind `SM3(ZA || msg)` against the signer's commitment, returns the
+    /// request and the 32-byte `r̂` to send.
+    pub fn new(
+        pk: &Sm2PublicKey,
+        id: Option<&'static str>,
+        msg: &[u8],
+        commitment: &BlindCommitment,
+    ) -> Sm2Result<(BlindRequest, Vec<u8>)> {
+        let za = compute_za(id.unwrap_or(DEFAULT_ID), &pk.point)?;
+        let e = u256_from_be_bytes(&sm3_hash(&[&za[..], msg].concat()));
+        let q = g_mul(&SM2_ONE).point_add(&pk.point);
+        loop {
+            let (a, b) = (fn_random(), fn_random());
+            let r_prime = commitment.0.scalar_mul(&a).point_add(&q.scalar_mul(&b));
+            if r_prime.is_zero() {
+                continue;
+            }
+            let x = u256_from_be_bytes(&fp_from_mont(&r_prime.to_affine_point().x).to_byte_be());
+            let r = fn_add(&e, &x);
+            if r.is_zero() {
+                continue;
+            }
+            let r_hat = fn_mul(&r, &fn_inv(&a));
+            let request = BlindRequest {
+                pk: *pk,
+                id,
+                msg: msg.to_vec(),
+                a,
+                b,
+                r,
+            };
+            return Ok((request, u256_to_be_bytes(&r_hat)));
+        }
+    }
+
+    /// Unblind the signer's `ŝ` into the 64-byte `r || s` signature, checked
+    /// with [`Sm2PublicKey::verify`] so a cheating signer is caught here.
+    ///
+    /// [`Sm2PublicKey::verify`]: crate::key::Sm2PublicKey::verify
+    pub fn unblind(self, s_hat: &[u8]) -> Sm2Result<Vec<u8>> {
+        let s_hat = fn_from_bytes(s_hat)?;
+        let s = fn_add(&fn_mul(&self.a, &s_hat), &self.b);
+        let sig = [u256_to_be_bytes(&self.r), u256_to_be_bytes(&s)].concat();
+        self.pk.verify(self.id, &self.msg, &sig)?;
+        Ok(sig)
+    }
+}
+
+#[cfg(test)]
+mod test_blind {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_blind_sign() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let (nonce, commitment) = blind_nonce_generate();
+        let commitment = BlindCommitment::from_bytes(&commitment.to_bytes()).unwrap();
+        let (request, blinded) = BlindRequest::new(&pk, Some("alice@example"), b"coin", &commitment).unwrap();
+        let s_hat = blind_sign(&sk, nonce, &blinded).unwrap();
+        let sig = request.unblind(&s_hat).unwrap();
+        pk.verify(Some("alice@example"), b"coin", &sig).unwrap();
+        assert!(pk.verify(None, b"coin", &sig).is_err());
+
+        // the signer never saw r or s
+        assert_ne!(blinded, sig[..32]);
+        assert_ne!(s_hat, sig[32..]);
+    }
+
+    #[test]
+    fn test_blind_rejects() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let (_, other) = gen_keypair().unwrap();
+        let (nonce, commitment) = blind_nonce_generate();
+        let (request, blinded) = BlindRequest::new(&pk, None, b"coin", &commitment).unwrap();
+        // a signature by another key, or for another nonce, does not unblind
+        let s_hat = blind_sign(&other, nonce, &blinded).unwrap();
+        assert!(request.unblind(&s_hat).is_err());
+        let (request, blinded) = BlindRequest::new(&pk, None, b"coin", &commitment).unwrap();
+        let (nonce, _) = blind_nonce_generate();
+        assert!(request.unblind(&blind_sign(&sk, nonce, &blinded).unwrap()).is_err());
+
+        let (nonce, _) = blind_nonce_generate();
+        assert_eq!(Err(Sm2Error::ZeroSig), blind_sign(&sk, nonce, &[0; 32]));
+    }
+}
@@ -0,0 +1,106 @@
+//! Known-broken inputs for the public decoders.
+//!
+//! Every valid encoding is cut at each length, extended, bit-flipped and
+//! replaced by garbage of the same and of other lengths. A decoder must reject
+//! the truncated and extended inputs and must not panic on the others, which
+//! may happen to be valid encodings. Decrypting and verifying reject them all.
+//! The runner is [`gm_core::corpus`].
+use gm_core::corpus::{accepts, resized, Corpus};
+use pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+
+use crate::detached::SignatureFormat;
+use crate::key::{gen_keypair, Sm2Model, Sm2PrivateKey, Sm2PublicKey};
+use crate::signature::Sm2Signature;
+
+const CORPUS: Corpus = Corpus::new(&[1, 31, 32, 33, 64, 65, 97, 4096]);
+
+#[test]
+fn corpus_keys() {
+    let (pk, sk) = gen_keypair().unwrap();
+    for compress in [false, true] {
+        CORPUS.check_decoder("Sm2PublicKey::new", &pk.to_bytes(compress), Sm2PublicKey::new);
+    }
+    CORPUS.check_decoder("Sm2PrivateKey::new", &sk.to_bytes_be(), Sm2PrivateKey::new);
+
+    let kek = [0x5au8; 16];
+    CORPUS.check_authenticated("Sm2PrivateKey::unwrap_from", &sk.wrap(&kek).unwrap(), |b| {
+        Sm2PrivateKey::unwrap_from(&kek, b)
+    });
+
+    let spki = pk.to_public_key_der().unwrap();
+    CORPUS.check_decoder("from_public_key_der", spki.as_bytes(), Sm2PublicKey::from_public_key_der);
+    let pkcs8 = sk.to_pkcs8_der().unwrap();
+    CORPUS.check_decoder("from_pkcs8_der", pkcs8.as_bytes(), Sm2PrivateKey::from_pkcs8_der);
+
+    // bit flips of the iteration count would only make this slow
+    let encrypted = sk.to_encrypted_pkcs8_der(b"password", 1).unwrap();
+    for b in resized(&encrypted) {
+        assert!(!accepts("from_encrypted_pkcs8_der", &b, &|b: &[u8]| {
+            Sm2PrivateKey::from_encrypted_pkcs8_der(b, b"password")
+        }));
+    }
+}
+
+#[test]
+fn corpus_signatures() {
+    let (pk, sk) = gen_keypair().unwrap();
+    let msg = b"corpus";
+    let sig = sk.sign(None, msg).unwrap();
+    CORPUS.check_authenticated("verify", &sig, |b| pk.verify(None, msg, b));
+    CORPUS.check_authenticated("verify_with_context", &sk.sign_with_context(None, b"ctx", msg).unwrap(), |b| {
+        pk.verify_with_context(None, b"ctx", msg, b)
+    });
+
+    CORPUS.check_authenticated("verify_with_recovery", &sk.sign_with_recovery(None, b"ticket", msg).unwrap(), |b| {
+        pk.verify_with_recovery(None, b, msg)
+    });
+
+    let sig = SignatureFormat::Raw.decode(&sig).unwrap();
+    CORPUS.check_decoder("Sm2Signature::from_der", &sig.to_der(), Sm2Signature::from_der);
+    for format in [SignatureFormat::Raw, SignatureFormat::Der, SignatureFormat::Base64] {
+        CORPUS.check_decoder("SignatureFormat::decode", &format.encode(&sig), |b| format.decode(b));
+    }
+}
+
+#[test]
+fn corpus_ciphertexts() {
+    let (pk, sk) = gen_keypair().unwrap();
+    for compress in [false, true] {
+        for model in [Sm2Model::C1C3C2, Sm2Model::C1C2C3] {
+            let c = pk.encrypt(b"corpus", compress, model).unwrap();
+            CORPUS.check_authenticated("decrypt", &c, |b| sk.decrypt(b, compress, model));
+        }
+    }
+    // C2 is never empty
+    assert!(pk.encrypt(b"", false, Sm2Model::C1C3C2).is_err());
+    let c = pk.encrypt(b"x", false, Sm2Model::C1C3C2).unwrap();
+    CORPUS.check_authenticated("decrypt", &c, |b| sk.decrypt(b, false, Sm2Model::C1C3C2));
+
+    for compress in [false, true] {
+        let c = pk.encrypt_asn1(b"corpus", compress, Sm2Model::C1C3C2).unwrap();
+        CORPUS.check_authenticated("decrypt_asn1", &c, |b| sk.decrypt_asn1(b, compress, Sm2Model::C1C3C2));
+    }
+}
+
+#[cfg(feature = "jwt")]
+#[test]
+fn corpus_jws() {
+    let (pk, sk) = gen_keypair().unwrap();
+    let token = crate::jws::sign_compact(&sk, None, b"corpus").unwrap();
+    CORPUS.check_authenticated("verify_compact", token.as_bytes(), |b| {
+        let token = std::str::from_utf8(b).map_err(|_| crate::error::Sm2Error::InvalidToken)?;
+        crate::jws::verify_compact(&pk, token)
+    });
+}
+
+#[cfg(feature = "cose")]
+#[test]
+fn corpus_cose() {
+    let (pk, sk) = gen_keypair().unwrap();
+    let msg = crate::cose::sign1(&sk, None, b"corpus", b"").unwrap();
+    CORPUS.check_authenticated("verify_sign1", &msg, |b| crate::cose::verify_sign1(&pk, b, b""));
+
+    let key = [0x11u8; 16];
+    let msg = crate::cose::encrypt0(&key, &[0x22; 12], b"corpus", b"").unwrap();
+    CORPUS.check_authenticated("decrypt0", &msg, |b| crate::cose::decrypt0(&key, b, b""));
+}
@@ -0,0 +1,196 @@
+//! COSE (RFC 9052) messages with GM algorithms (enable the `cose` feature).
+//!
+//! * `COSE_Sign1` signed with SM2-with-SM3, the signature is the 64-byte `r || s` value
+//! * `COSE_Encrypt0` encrypted with SM4-GCM, a 12-byte IV and a 16-byte tag
+//!
+//! No algorithm identifiers are registered with IANA for SM2 or SM4 yet, the
+//! values below are taken from the private-use range and both peers must
+//! agree on them.
+use ciborium::value::Value;
+use gm_sm4::gcm::Sm4Gcm;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+
+/// SM2 signature with SM3 (private use)
+pub const COSE_ALG_SM2_SM3: i64 = -65537;
+/// SM4-GCM with a 128-bit tag (private use)
+pub const COSE_ALG_SM4_GCM: i64 = -65538;
+
+const COSE_TAG_SIGN1: u64 = 18;
+const COSE_TAG_ENCRYPT0: u64 = 16;
+const HEADER_ALG: i64 = 1;
+const HEADER_KID: i64 = 4;
+const HEADER_IV: i64 = 5;
+
+/// Create a tagged `COSE_Sign1` message, `kid` goes into the unprotected header.
+pub fn sign1(
+    sk: &Sm2PrivateKey,
+    kid: Option<&[u8]>,
+    payload: &[u8],
+    external_aad: &[u8],
+) -> Sm2Result<Vec<u8>> {
+    let protected = encode(&alg_header(COSE_ALG_SM2_SM3))?;
+    let to_sign = sig_structure(&protected, external_aad, payload)?;
+    let sig = sk.sign(None, &to_sign)?;
+    let mut unprotected = vec![];
+    if let Some(kid) = kid {
+        unprotected.push((Value::from(HEADER_KID), Value::Bytes(kid.to_vec())));
+    }
+    encode(&Value::Tag(
+        COSE_TAG_SIGN1,
+        Box::new(Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(unprotected),
+            Value::Bytes(payload.to_vec()),
+            Value::Bytes(sig),
+        ])),
+    ))
+}
+
+/// Verify a `COSE_Sign1` message (tagged or untagged) and return its payload.
+pub fn verify_sign1(pk: &Sm2PublicKey, msg: &[u8], external_aad: &[u8]) -> Sm2Result<Vec<u8>> {
+    let items = decode_array(msg, COSE_TAG_SIGN1)?;
+    let (protected, payload, sig) = match &items[..] {
+        [Value::Bytes(p), Value::Map(_), Value::Bytes(m), Value::Bytes(s)] => (p, m, s),
+        _ => return Err(Sm2Error::InvalidToken),
+    };
+    check_alg(protected, COSE_ALG_SM2_SM3)?;
+    if sig.len() != 64 {
+        return Err(Sm2Error::InvalidToken);
+    }
+    let to_verify = sig_structure(protected, external_aad, payload)?;
+    pk.verify(None, &to_verify, sig)?;
+    Ok(payload.clone())
+}
+
+/// Create a tagged `COSE_Encrypt0` message with a 16-byte SM4 key, the IV goes
+/// into the unprotected header and must never repeat under the same key.
+pub fn encrypt0(key: &[u8], iv: &[u8; 12], plaintext: &[u8], external_aad: &[u8]) -> Sm2Result<Vec<u8>> {
+    let protected = encode(&alg_header(COSE_ALG_SM4_GCM))?;
+    let aad = enc_structure(&protected, external_aad)?;
+    let gcm = Sm4Gcm::new(key).map_err(|_| Sm2Error::InvalidSecretKey)?;
+    let ct = gcm
+        .encrypt(iv, &aad, plaintext)
+        .map_err(|_| Sm2Error::InvalidSecretKey)?;
+    encode(&Value::Tag(
+        COSE_TAG_ENCRYPT0,
+        Box::new(Value::Array(vec![
+            Value::Bytes(protected),
+            Value::Map(vec![(Value::from(HEADER_IV), Value::Bytes(iv.to_vec()))]),
+            Value::Bytes(ct),
+        ])),
+    ))
+}
+
+/// Decrypt a `COSE_Encrypt0` message (tagged or untagged).
+pub fn decrypt0(key: &[u8], msg: &[u8], external_aad: &[u8]) -> Sm2Result<Vec<u8>> {
+    let items = decode_array(msg, COSE_TAG_ENCRYPT0)?;
+    let (protected, unprotected, ct) = match &items[..] {
+        [Value::Bytes(p), Value::Map(u), Value::Bytes(c)] => (p, u, c),
+        _ => return Err(Sm2Error::InvalidToken),
+    };
+    check_alg(protected, COSE_ALG_SM4_GCM)?;
+    let iv = unprotected
+        .iter()
+        .find(|(k, _)| *k == Value::from(HEADER_IV))
+        .and_then(|(_, v)| v.as_bytes())
+        .ok_or(Sm2Error::InvalidToken)?;
+    let aad = enc_structure(protected, external_aad)?;
+    let gcm = Sm4Gcm::new(key).map_err(|_| Sm2Error::InvalidSecretKey)?;
+    gcm.decrypt(iv, &aad, ct).map_err(|_| Sm2Error::InvalidToken)
+}
+
+fn alg_header(alg: i64) -> Value {
+    Value::Map(vec![(Value::from(HEADER_ALG), Value::from(alg))])
+}
+
+fn check_alg(protected: &[u8], alg: i64) -> Sm2Result<()> {
+    let header = decode(protected)?;
+    let found = header
+        .as_map()
+        .and_then(|m| m.iter().find(|(k, _)| *k == Value::from(HEADER_ALG)))
+        .map(|(_, v)| v.clone());
+    if found != Some(Value::from(alg)) {
+        return Err(Sm2Error::InvalidToken);
+    }
+    Ok(())
+}
+
+fn sig_structure(protected: &[u8], external_aad: &[u8], payload: &[u8]) -> Sm2Result<Vec<u8>> {
+    encode(&Value::Array(vec![
+        Value::Text("Signature1".to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(external_aad.to_vec()),
+        Value::Bytes(payload.to_vec()),
+    ]))
+}
+
+fn enc_structure(protected: &[u8], external_aad: &[u8]) -> Sm2Result<Vec<u8>> {
+    encode(&Value::Array(vec![
+        Value::Text("Encrypt0".to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(external_aad.to_vec()),
+    ]))
+}
+
+fn encode(v: &Value) -> Sm2Result<Vec<u8>> {
+    let mut out = vec![];
+    ciborium::ser::into_writer(v, &mut out).map_err(|_| Sm2Error::InvalidToken)?;
+    Ok(out)
+}
+
+/// A single CBOR item, trailing bytes are an error
+fn decode(mut b: &[u8]) -> Sm2Result<Value> {
+    let v: Value = ciborium::de::from_reader(&mut b).map_err(|_| Sm2Error::InvalidToken)?;
+    if !b.is_empty() {
+        return Err(Sm2Error::InvalidToken);
+    }
+    Ok(v)
+}
+
+fn decode_array(msg: &[u8], tag: u64) -> Sm2Result<Vec<Value>> {
+    let v = match decode(msg)? {
+        Value::Tag(t, inner) if t == tag => *inner,
+        Value::Tag(..) => return Err(Sm2Error::InvalidToken),
+        v => v,
+    };
+    match v {
+        Value::Array(items) => Ok(items),
+        _ => Err(Sm2Error::InvalidToken),
+    }
+}
+
+#[cfg(test)]
+mod test_cose {
+    use crate::cose::{decrypt0, encrypt0, sign1, verify_sign1};
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_sign1() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let msg = sign1(&sk, Some(b"device-01"), b"temperature=21.5", b"").unwrap();
+        println!("COSE_Sign1 = {}", hex::encode(&msg));
+        assert_eq!(0xd2, msg[0]);
+        assert_eq!(
+            b"temperature=21.5".to_vec(),
+            verify_sign1(&pk, &msg, b"").unwrap()
+        );
+        assert!(verify_sign1(&pk, &msg, b"other aad").is_err());
+
+        let (pk2, _) = gen_keypair().unwrap();
+        assert!(verify_sign1(&pk2, &msg, b"").is_err());
+    }
+
+    #[test]
+    fn test_encrypt0() {
+        let key = [0x42u8; 16];
+        let iv = [7u8; 12];
+        let msg = encrypt0(&key, &iv, b"open the door", b"aad").unwrap();
+        println!("COSE_Encrypt0 = {}", hex::encode(&msg));
+        assert_eq!(0xd0, msg[0]);
+        assert_eq!(b"open the door".to_vec(), decrypt0(&key, &msg, b"aad").unwrap());
+        assert!(decrypt0(&key, &msg, b"").is_err());
+        assert!(decrypt0(&[0x43u8; 16], &msg, b"aad").is_err());
+    }
+}
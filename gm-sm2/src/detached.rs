@@ -0,0 +1,148 @@
+//! Detached signatures over large inputs such as firmware images.
+//!
+//! The message is hashed with a streaming SM3 as `SM3(ZA || M)`, so it is never
+//! held in memory. The result is the same signature [`Sm2PrivateKey::sign`]
+//! produces for `M` and the same user ID, in one of the [`SignatureFormat`]s.
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use gm_core::encoding::{base64_decode, base64_encode};
+use gm_sm3::Sm3Hasher;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::signature::Sm2Signature;
+use crate::util::{compute_za, DEFAULT_ID};
+
+/// Encoding of a detached signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// 64-byte `r || s`
+    Raw,
+    /// DER `SM2Signature`, what `openssl dgst -sm3 -sign key.pem -sigopt distid:1234567812345678`
+    /// writes for the default user ID
+    Der,
+    /// Base64 of the DER form, surrounding whitespace is ignored on input
+    Base64,
+}
+
+impl SignatureFormat {
+    pub fn encode(self, sig: &Sm2Signature) -> Vec<u8> {
+        match self {
+            SignatureFormat::Raw => sig.to_vec(),
+            SignatureFormat::Der => sig.to_der(),
+            SignatureFormat::Base64 => base64_encode(&sig.to_der()).into_bytes(),
+        }
+    }
+
+    pub fn decode(self, data: &[u8]) -> Sm2Result<Sm2Signature> {
+        match self {
+            SignatureFormat::Raw => Sm2Signature::from_slice(data),
+            SignatureFormat::Der => Sm2Signature::from_der(data),
+            SignatureFormat::Base64 => {
+                let text = std::str::from_utf8(data).map_err(|_| Sm2Error::InvalidDer)?;
+                let der = base64_decode(text.trim()).map_err(|_| Sm2Error::InvalidDer)?;
+                Sm2Signature::from_der(&der)
+            }
+        }
+    }
+}
+
+/// e = SM3(ZA || M) with M read from `reader`
+pub fn digest_reader<R: Read>(
+    pk: &Sm2PublicKey,
+    id: Option<&str>,
+    mut reader: R,
+) -> Sm2Result<[u8; 32]> {
+    let za = compute_za(id.unwrap_or(DEFAULT_ID), &pk.point)?;
+    let mut hasher = Sm3Hasher::new();
+    hasher.update(&za);
+    io::copy(&mut reader, &mut hasher).map_err(|_| Sm2Error::IoError)?;
+    Ok(hasher.finalize())
+}
+
+pub fn sign_reader<R: Read>(
+    sk: &Sm2PrivateKey,
+    id: Option<&str>,
+    reader: R,
+    format: SignatureFormat,
+) -> Sm2Result<Vec<u8>> {
+    let digest = digest_reader(&sk.public_key, id, reader)?;
+    let sig = Sm2Signature::from_slice(&sk.sign_raw(&digest, &sk.d)?)?;
+    Ok(format.encode(&sig))
+}
+
+pub fn verify_reader<R: Read>(
+    pk: &Sm2PublicKey,
+    id: Option<&str>,
+    reader: R,
+    sig: &[u8],
+    format: SignatureFormat,
+) -> Sm2Result<()> {
+    let sig = format.decode(sig)?;
+    let digest = digest_reader(pk, id, reader)?;
+    pk.verify_raw(&digest, &pk.point, &sig.to_vec())
+}
+
+pub fn sign_file<P: AsRef<Path>>(
+    sk: &Sm2PrivateKey,
+    id: Option<&str>,
+    path: P,
+    format: SignatureFormat,
+) -> Sm2Result<Vec<u8>> {
+    let file = File::open(path).map_err(|_| Sm2Error::IoError)?;
+    sign_reader(sk, id, file, format)
+}
+
+pub fn verify_file<P: AsRef<Path>>(
+    pk: &Sm2PublicKey,
+    id: Option<&str>,
+    path: P,
+    sig: &[u8],
+    format: SignatureFormat,
+) -> Sm2Result<()> {
+    let file = File::open(path).map_err(|_| Sm2Error::IoError)?;
+    verify_reader(pk, id, file, sig, format)
+}
+
+#[cfg(test)]
+mod test_detached {
+    use crate::detached::{sign_file, sign_reader, verify_file, verify_reader, SignatureFormat};
+    use crate::error::Sm2Error;
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_detached_formats() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let image: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        for format in [SignatureFormat::Raw, SignatureFormat::Der, SignatureFormat::Base64] {
+            let sig = sign_reader(&sk, None, &image[..], format).unwrap();
+            println!("{:?}: {}", format, hex::encode(&sig));
+            verify_reader(&pk, None, &image[..], &sig, format).unwrap();
+            assert!(verify_reader(&pk, None, &image[1..], &sig, format).is_err());
+            assert!(verify_reader(&pk, Some("other"), &image[..], &sig, format).is_err());
+        }
+
+        // same signature as the in-memory API
+        let sig = sign_reader(&sk, None, &image[..], SignatureFormat::Raw).unwrap();
+        pk.verify(None, &image, &sig).unwrap();
+        let sig = sk.sign(Some("firmware"), &image).unwrap();
+        verify_reader(&pk, Some("firmware"), &image[..], &sig, SignatureFormat::Raw).unwrap();
+    }
+
+    #[test]
+    fn test_detached_file() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let path = std::env::temp_dir().join(format!("gm-sm2-detached-{}.bin", std::process::id()));
+        std::fs::write(&path, b"firmware image v1.0").unwrap();
+        let sig = sign_file(&sk, None, &path, SignatureFormat::Base64).unwrap();
+        verify_file(&pk, None, &path, &sig, SignatureFormat::Base64).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            Err(Sm2Error::IoError),
+            verify_file(&pk, None, &path, &sig, SignatureFormat::Base64)
+        );
+    }
+}
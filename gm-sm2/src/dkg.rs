@@ -0,0 +1,241 @@
+//! Distributed key generation for t-of-n SM2 keys, Pedersen's protocol on
+//! Feldman verifiable secret sharing.
+//!
+//! No dealer ever holds the private key `d`. Each of the `n` parties is a
+//! dealer of a random secret `a_i0`:
+//!
+//! * party `i` draws `f_i(x) = a_i0 + a_i1·x + ... + a_i(t-1)·x^(t-1)` and
+//!   broadcasts the [`Commitments`] `C_ik = [a_ik]G`
+//! * it sends [`Share`] `f_i(j)` to party `j` over a private channel
+//! * party `j` checks every share it gets, `[f_i(j)]G = Σ [j^k]C_ik`, and
+//!   complains about a dealer whose share fails
+//! * the key share of `j` is `x_j = Σ f_i(j)`, the key is `d = Σ a_i0`, which
+//!   no one knows, and the public key is `P = Σ C_i0`
+//!
+//! Any `t` key shares are Shamir shares of `d`, see [`crate::shamir`]. As in
+//! Pedersen's original protocol a party that acts last can bias `P`, though it
+//! cannot learn `d`. Parties are numbered from 1.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::dkg::{combine, deal};
+//!
+//! let (t, n) = (2, 3);
+//! let dealings: Vec<_> = (1..=n).map(|i| deal(i, t, n as usize).unwrap()).collect();
+//! let commitments: Vec<_> = dealings.iter().map(|(c, _)| c.clone()).collect();
+//! let key_shares: Vec<_> = (1..=n)
+//!     .map(|j| {
+//!         let received: Vec<_> = dealings.iter().map(|(_, s)| s[j as usize - 1].clone()).collect();
+//!         combine(j, &commitments, &received).unwrap()
+//!     })
+//!     .collect();
+//! assert_eq!(key_shares[0].public_key(), key_shares[2].public_key());
+//! ```
+use pkcs8::der::zeroize::Zeroize;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_random};
+use crate::key::Sm2PublicKey;
+use crate::p256_ecc::{g_mul, Point};
+use crate::shamir::{eval_poly, Share};
+use crate::u256::U256;
+
+/// The broadcast of one dealer, `C_ik = [a_ik]G` for `k` in 0..t
+#[derive(Debug, Clone)]
+pub struct Commitments {
+    dealer: u8,
+    points: Vec<Point>,
+}
+
+impl Commitments {
+    pub fn dealer(&self) -> u8 {
+        self.dealer
+    }
+
+    /// The threshold the dealer used
+    pub fn threshold(&self) -> usize {
+        self.points.len()
+    }
+
+    /// The dealer index followed by the `t` points, compressed
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.dealer];
+        for p in &self.points {
+            out.extend_from_slice(&p.to_byte_be(true));
+        }
+        out
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Commitments> {
+        if b.len() < 1 + 33 || !(b.len() - 1).is_multiple_of(33) || b[0] == 0 {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let points = b[1..]
+            .chunks(33)
+            .map(|p| Sm2PublicKey::new(p).map(|pk| pk.point))
+            .collect::<Sm2Result<Vec<Point>>>()?;
+        Ok(Commitments { dealer: b[0], points })
+    }
+
+    /// `Σ [x^k]C_k`, the public image of the dealer's share for party `x`
+    fn eval(&self, x: u8) -> Point {
+        eval_points(&self.points, x)
+    }
+}
+
+/// `Σ [x^k]P_k` by Horner's rule
+pub(crate) fn eval_points(points: &[Point], x: u8) -> Point {
+    let x = [x as u64, 0, 0, 0];
+    points
+        .iter()
+        .rev()
+        .fold(Point::zero(), |acc, p| acc.scalar_mul(&x).point_add(p))
+}
+
+pub(crate) fn same_point(a: &Point, b: &Point) -> bool {
+    a.is_zero() == b.is_zero() && (a.is_zero() || a.to_byte_be(false) == b.to_byte_be(false))
+}
+
+/// Party `dealer` deals a fresh secret: its commitments to broadcast and the
+/// `n` shares to send, the one for party `j` at index `j - 1`.
+pub fn deal(dealer: u8, threshold: usize, n: usize) -> Sm2Result<(Commitments, Vec<Share>)> {
+    if dealer == 0 || dealer as usize > n || threshold == 0 || threshold > n || n > 255 {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let mut coefficients: Vec<U256> = (0..threshold).map(|_| fn_random()).collect();
+    let points = coefficients.iter().map(g_mul).collect();
+    let shares = (1..=n as u8)
+        .map(|j| Share::new(j, eval_poly(&coefficients, j)))
+        .collect();
+    coefficients.zeroize();
+    Ok((Commitments { dealer, points }, shares))
+}
+
+/// Check a share received from the dealer of `commitments`, failing with
+/// [`Sm2Error::InvalidShare`], the ground for a complaint against the dealer.
+pub fn verify_share(commitments: &Commitments, share: &Share) -> Sm2Result<()> {
+    if !same_point(&g_mul(share.value()), &commitments.eval(share.index())) {
+        return Err(Sm2Error::InvalidShare);
+    }
+    Ok(())
+}
+
+/// The result of the protocol for one party
+#[derive(Debug, Clone)]
+pub struct KeyShare {
+    pub(crate) share: Share,
+    pub(crate) public_key: Sm2PublicKey,
+    /// `Σ C_ik` over the dealers, the commitments to the polynomial of `d`
+    pub(crate) group_commitments: Vec<Point>,
+}
+
+impl KeyShare {
+    pub fn index(&self) -> u8 {
+        self.share.index()
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.group_commitments.len()
+    }
+
+    /// `P = [d]G`
+    pub fn public_key(&self) -> Sm2PublicKey {
+        self.public_key
+    }
+
+    /// `[x_j]G` for any party `j`, to check its contributions to a threshold
+    /// signature
+    pub fn public_share(&self, j: u8) -> Sm2PublicKey {
+        Sm2PublicKey {
+            point: eval_points(&self.group_commitments, j).to_affine_point(),
+        }
+    }
+
+    /// The Shamir share of `d`, for [`crate::shamir`] reconstruction
+    pub fn share(&self) -> &Share {
+        &self.share
+    }
+}
+
+/// Party `me` verifies the shares it received, `received[i]` from the dealer
+/// of `commitments[i]`, and combines them into its key share. Every dealer
+/// must have used the same threshold.
+pub fn combine(me: u8, commitments: &[Commitments], received: &[Share]) -> Sm2Result<KeyShare> {
+    let threshold = commitments.first().ok_or(Sm2Error::InvalidLength)?.threshold();
+    if commitments.len() != received.len() || commitments.iter().any(|c| c.threshold() != threshold) {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let mut value = [0; 4];
+    let mut group_commitments = vec![Point::zero(); threshold];
+    for (c, share) in commitments.iter().zip(received) {
+        if share.index() != me {
+            return Err(Sm2Error::InvalidShare);
+        }
+        verify_share(c, share)?;
+        value = fn_add(&value, share.value());
+        for (sum, p) in group_commitments.iter_mut().zip(&c.points) {
+            *sum = sum.point_add(p);
+        }
+    }
+    if group_commitments[0].is_zero() {
+        return Err(Sm2Error::ZeroPoint);
+    }
+    let public_key = Sm2PublicKey {
+        point: group_commitments[0].to_affine_point(),
+    };
+    Ok(KeyShare {
+        share: Share::new(me, value),
+        public_key,
+        group_commitments,
+    })
+}
+
+#[cfg(test)]
+mod test_dkg {
+    use super::*;
+    use crate::key::Sm2PrivateKey;
+
+    fn run(t: usize, n: u8) -> Vec<KeyShare> {
+        let dealings: Vec<_> = (1..=n).map(|i| deal(i, t, n as usize).unwrap()).collect();
+        let commitments: Vec<_> = dealings
+            .iter()
+            .map(|(c, _)| Commitments::from_bytes(&c.to_bytes()).unwrap())
+            .collect();
+        (1..=n)
+            .map(|j| {
+                let received: Vec<_> = dealings.iter().map(|(_, s)| s[j as usize - 1].clone()).collect();
+                combine(j, &commitments, &received).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dkg() {
+        let shares = run(3, 5);
+        let pk = shares[0].public_key();
+        assert!(shares.iter().all(|s| s.public_key() == pk && s.threshold() == 3));
+        for s in &shares {
+            assert_eq!(g_mul(s.share().value()).to_byte_be(false), shares[4].public_share(s.index()).to_bytes(false));
+        }
+
+        // any 3 key shares are Shamir shares of the key, 2 are not enough
+        let picked: Vec<Share> = [4, 0, 2].iter().map(|&i| shares[i].share().clone()).collect();
+        let sk = Sm2PrivateKey::from_shares(&picked, &pk).unwrap();
+        sk.public_key.verify(None, b"m", &sk.sign(None, b"m").unwrap()).unwrap();
+        assert!(Sm2PrivateKey::from_shares(&picked[..2], &pk).is_err());
+    }
+
+    #[test]
+    fn test_complaints() {
+        let (c1, s1) = deal(1, 2, 3).unwrap();
+        let (c2, s2) = deal(2, 2, 3).unwrap();
+        verify_share(&c1, &s1[1]).unwrap();
+        // a share from another dealer, or for another party, is rejected
+        assert_eq!(Err(Sm2Error::InvalidShare), verify_share(&c2, &s1[1]));
+        assert_eq!(Err(Sm2Error::InvalidShare), combine(2, &[c1.clone(), c2.clone()], &[s1[1].clone(), s1[1].clone()]).map(|_| ()));
+        assert_eq!(Err(Sm2Error::InvalidShare), combine(3, &[c1.clone(), c2.clone()], &[s1[1].clone(), s2[1].clone()]).map(|_| ()));
+        let (c3, _) = deal(3, 3, 3).unwrap();
+        assert_eq!(Err(Sm2Error::InvalidLength), combine(2, &[c1, c3], &[s1[1].clone(), s2[1].clone()]).map(|_| ()));
+        assert_eq!(Err(Sm2Error::InvalidLength), deal(4, 2, 3).map(|_| ()));
+    }
+}
@@ -0,0 +1,120 @@
+//! Strong designated verifier signatures with SM2 keys.
+//!
+//! Only the verifier chosen by the signer can check the signature, and it
+//! cannot convince anyone else: it could have produced the same signature
+//! itself with [`dv_simulate`]. A third party without either private key
+//! cannot even tell whether a signature is valid.
+//!
+//! With the signer's key `(d_A, P_A)` and the verifier's `(d_V, P_V)`:
+//!
+//! * `K = SM3("SM2-DVS" || x([d_A]P_V) || P_A || P_V)`, the verifier gets the
+//!   same `K` from `[d_V]P_A`
+//! * the signature is `salt || HMAC-SM3(K, salt || M)`, 48 bytes, with a
+//!   random 16-byte salt so that signatures of one message are unlinkable
+//!
+//! This is not a GB/T 32918 signature and [`Sm2PublicKey::verify`] does not
+//! accept it.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::dvs::{dv_sign, dv_verify};
+//! use gm_sm2::key::gen_keypair;
+//!
+//! let (signer_pk, signer_sk) = gen_keypair().unwrap();
+//! let (verifier_pk, verifier_sk) = gen_keypair().unwrap();
+//! let sig = dv_sign(&signer_sk, &verifier_pk, b"attestation").unwrap();
+//! dv_verify(&verifier_sk, &signer_pk, b"attestation", &sig).unwrap();
+//! ```
+//!
+//! [`Sm2PublicKey::verify`]: crate::key::Sm2PublicKey::verify
+use gm_core::ct_eq;
+use gm_sm3::hmac::sm3_hmac;
+use gm_sm3::sm3_hash;
+use pkcs8::der::zeroize::Zeroizing;
+use rand::RngCore;
+
+use crate::ecdh::diffie_hellman;
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+
+const DVS_TAG: &[u8] = b"SM2-DVS";
+const SALT_LEN: usize = 16;
+
+pub const DV_SIGNATURE_LEN: usize = SALT_LEN + 32;
+
+/// Sign `msg` so that only `verifier` can check it.
+pub fn dv_sign(sk: &Sm2PrivateKey, verifier: &Sm2PublicKey, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+    let key = shared_key(sk, verifier, &sk.public_key, verifier)?;
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    Ok(tag(&key, &salt, msg))
+}
+
+/// Check a signature of `signer` made for the holder of `verifier_sk`.
+pub fn dv_verify(verifier_sk: &Sm2PrivateKey, signer: &Sm2PublicKey, msg: &[u8], sig: &[u8]) -> Sm2Result<()> {
+    if sig.len() != DV_SIGNATURE_LEN {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let key = shared_key(verifier_sk, signer, signer, &verifier_sk.public_key)?;
+    if !ct_eq(&tag(&key, &sig[..SALT_LEN], msg), sig) {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    Ok(())
+}
+
+/// A signature of `signer` that the verifier produces alone, indistinguishable
+/// from one [`dv_sign`] makes. This is why a designated verifier signature
+/// proves nothing to anyone else.
+pub fn dv_simulate(verifier_sk: &Sm2PrivateKey, signer: &Sm2PublicKey, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+    let key = shared_key(verifier_sk, signer, signer, &verifier_sk.public_key)?;
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    Ok(tag(&key, &salt, msg))
+}
+
+/// `K` from our key and the peer's, always bound to the signer and the
+/// verifier in that order
+fn shared_key(
+    sk: &Sm2PrivateKey,
+    peer: &Sm2PublicKey,
+    signer: &Sm2PublicKey,
+    verifier: &Sm2PublicKey,
+) -> Sm2Result<Zeroizing<[u8; 32]>> {
+    let z = Zeroizing::new(diffie_hellman(sk, peer)?);
+    Ok(Zeroizing::new(sm3_hash(
+        &[DVS_TAG, &z[..], &signer.to_bytes(false), &verifier.to_bytes(false)].concat(),
+    )))
+}
+
+fn tag(key: &[u8; 32], salt: &[u8], msg: &[u8]) -> Vec<u8> {
+    [salt, &sm3_hmac(key, &[salt, msg].concat())[..]].concat()
+}
+
+#[cfg(test)]
+mod test_dvs {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_dv_sign_verify() {
+        let (signer_pk, signer_sk) = gen_keypair().unwrap();
+        let (verifier_pk, verifier_sk) = gen_keypair().unwrap();
+        let (other_pk, other_sk) = gen_keypair().unwrap();
+
+        let sig = dv_sign(&signer_sk, &verifier_pk, b"msg").unwrap();
+        assert_eq!(DV_SIGNATURE_LEN, sig.len());
+        dv_verify(&verifier_sk, &signer_pk, b"msg", &sig).unwrap();
+        assert_ne!(sig, dv_sign(&signer_sk, &verifier_pk, b"msg").unwrap());
+        assert_eq!(Err(Sm2Error::InvalidDigest), dv_verify(&verifier_sk, &signer_pk, b"msh", &sig));
+
+        // another verifier, a claimed other signer, or swapped roles fail
+        assert!(dv_verify(&other_sk, &signer_pk, b"msg", &sig).is_err());
+        assert!(dv_verify(&verifier_sk, &other_pk, b"msg", &sig).is_err());
+        assert!(dv_verify(&signer_sk, &verifier_pk, b"msg", &sig).is_err());
+        assert_eq!(Err(Sm2Error::InvalidLength), dv_verify(&verifier_sk, &signer_pk, b"msg", &sig[1..]));
+
+        // the verifier's own simulation verifies just the same
+        let simulated = dv_simulate(&verifier_sk, &signer_pk, b"forged").unwrap();
+        dv_verify(&verifier_sk, &signer_pk, b"forged", &simulated).unwrap();
+    }
+}
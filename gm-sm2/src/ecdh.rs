@@ -0,0 +1,60 @@
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::FieldModOperation;
+use crate::fields::fp64::fp_from_mont;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+
+/// Raw SM2 Diffie-Hellman: returns the x-coordinate of `d·P`.
+///
+/// This is only the scalar multiplication primitive, it does NOT run the
+/// GB/T 32918.3 key exchange (no ZA/ZB, no confirmation hashes, no KDF),
+/// see [`crate::exchange`] for that. Callers building their own protocol
+/// must feed the result into a KDF before using it as a key.
+///
+/// The peer point is checked to be on the curve and not the infinity point,
+/// and the shared point itself must not be the infinity point.
+pub fn diffie_hellman(sk: &Sm2PrivateKey, peer_pk: &Sm2PublicKey) -> Sm2Result<[u8; 32]> {
+    if sk.d.is_zero() {
+        return Err(Sm2Error::InvalidPrivate);
+    }
+    let peer = &peer_pk.point;
+    if peer.is_zero() || !peer.is_valid() {
+        return Err(Sm2Error::InvalidPublic);
+    }
+    let shared = peer.scalar_mul(&sk.d);
+    if shared.is_zero() {
+        return Err(Sm2Error::ZeroPoint);
+    }
+    let shared = shared.to_affine_point();
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&fp_from_mont(&shared.x).to_byte_be());
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test_ecdh {
+    use crate::ecdh::diffie_hellman;
+    use crate::error::Sm2Error;
+    use crate::key::{gen_keypair, Sm2PublicKey};
+    use crate::p256_ecc::Point;
+
+    #[test]
+    fn test_shared_secret() {
+        let (pk_a, sk_a) = gen_keypair().unwrap();
+        let (pk_b, sk_b) = gen_keypair().unwrap();
+        let s_a = diffie_hellman(&sk_a, &pk_b).unwrap();
+        let s_b = diffie_hellman(&sk_b, &pk_a).unwrap();
+        assert_eq!(s_a, s_b);
+        println!("shared secret = {}", hex::encode(s_a));
+    }
+
+    #[test]
+    fn test_reject_invalid_peer() {
+        let (_, sk) = gen_keypair().unwrap();
+        let zero = Sm2PublicKey { point: Point::zero() };
+        assert!(diffie_hellman(&sk, &zero) == Err(Sm2Error::InvalidPublic));
+
+        let (mut pk, _) = gen_keypair().unwrap();
+        pk.point.y = pk.point.x;
+        assert!(diffie_hellman(&sk, &pk) == Err(Sm2Error::InvalidPublic));
+    }
+}
@@ -0,0 +1,240 @@
+//! SM2 public key encryption as a parameterized ECIES, to read and write the
+//! dialects deployed next to GB/T 32918.4.
+//!
+//! Every profile shares the key agreement of GB/T 32918.4, `C1 = [k]G` and
+//! `(x2, y2) = [k]P`, and the `C1 C3 C2` / `C1 C2 C3` layouts with a 32-byte
+//! `C3`. A profile then picks:
+//!
+//! * [`Kdf`]: the SM3 KDF of `x2 || y2`, or HKDF-SM3 with `x2 || y2` as the
+//!   input key material and `C1` as the info
+//! * [`Dem`]: XOR with the KDF output, or SM4-CTR under a 16-byte key and IV
+//!   drawn from it
+//! * [`Mac`]: the GB/T hash `SM3(x2 || M || y2)`, or HMAC-SM3 under a 32-byte
+//!   key drawn from the KDF, over `C2` (encrypt-then-MAC)
+//!
+//! The KDF output is the DEM key followed by the MAC key.
+//! [`EciesProfile::GBT_32918`], the default, is exactly
+//! [`Sm2PublicKey::encrypt`] and [`Sm2PrivateKey::decrypt`].
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::ecies::{Dem, EciesProfile, Kdf, Mac};
+//! use gm_sm2::key::gen_keypair;
+//!
+//! let (pk, sk) = gen_keypair().unwrap();
+//! let profile = EciesProfile { kdf: Kdf::HkdfSm3, mac: Mac::HmacSm3, dem: Dem::Sm4Ctr, ..Default::default() };
+//! let c = profile.encrypt(&pk, b"hello").unwrap();
+//! assert_eq!(b"hello".to_vec(), profile.decrypt(&sk, &c).unwrap());
+//! ```
+use gm_core::ct_eq;
+use gm_sm3::hkdf::hkdf_sm3;
+use gm_sm3::hmac::sm3_hmac;
+use gm_sm3::sm3_hash;
+use gm_sm4::{CipherMode, Sm4CipherMode};
+use pkcs8::der::zeroize::Zeroizing;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::fn_random;
+use crate::fields::fp64::fp_from_mont;
+use crate::fields::FieldModOperation;
+use crate::key::{split_cipher, Sm2Model, Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::{g_mul, Point};
+use crate::util::{kdf, xor_bytes};
+
+type Key = Zeroizing<Vec<u8>>;
+
+/// Derives the DEM and MAC keys from `x2 || y2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    Sm3Kdf,
+    HkdfSm3,
+}
+
+/// Produces `C3`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mac {
+    HashC3,
+    HmacSm3,
+}
+
+/// Turns the message into `C2`, of the same length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dem {
+    Xor,
+    Sm4Ctr,
+}
+
+/// One ECIES dialect
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EciesProfile {
+    pub kdf: Kdf,
+    pub mac: Mac,
+    pub dem: Dem,
+    /// Whether `C1` is compressed
+    pub compressed: bool,
+    pub model: Sm2Model,
+}
+
+impl Default for EciesProfile {
+    fn default() -> Self {
+        EciesProfile::GBT_32918
+    }
+}
+
+impl EciesProfile {
+    /// GB/T 32918.4 with the `C1 C3 C2` layout of GM/T 0009
+    pub const GBT_32918: EciesProfile = EciesProfile {
+        kdf: Kdf::Sm3Kdf,
+        mac: Mac::HashC3,
+        dem: Dem::Xor,
+        compressed: false,
+        model: Sm2Model::C1C3C2,
+    };
+
+    fn is_gbt(&self) -> bool {
+        self.kdf == Kdf::Sm3Kdf && self.mac == Mac::HashC3 && self.dem == Dem::Xor
+    }
+
+    pub fn encrypt(&self, pk: &Sm2PublicKey, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        if self.is_gbt() {
+            return pk.encrypt(msg, self.compressed, self.model);
+        }
+        if msg.is_empty() {
+            return Err(Sm2Error::InvalidLength);
+        }
+        if pk.point.is_zero() {
+            return Err(Sm2Error::ZeroPoint);
+        }
+        let (c1, x2, y2, dem_key, mac_key) = loop {
+            let k = fn_random();
+            let c1 = g_mul(&k).to_affine_point().to_byte_be(self.compressed);
+            let (x2, y2) = coordinates(&pk.point.scalar_mul(&k));
+            match self.derive(&x2, &y2, &c1, msg.len()) {
+                Ok((dem_key, mac_key)) => break (c1, x2, y2, dem_key, mac_key),
+                // an all zero key stream, draw another k
+                Err(Sm2Error::KdfHashError) => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        let c2 = self.dem_apply(&dem_key, msg)?;
+        let c3 = self.c3(&x2, &y2, &mac_key, msg, &c2);
+        Ok(match self.model {
+            Sm2Model::C1C2C3 => [c1, c2, c3.to_vec()].concat(),
+            Sm2Model::C1C3C2 => [c1, c3.to_vec(), c2].concat(),
+        })
+    }
+
+    /// Fails with [`Sm2Error::HashNotEqual`] when `C3` does not match.
+    pub fn decrypt(&self, sk: &Sm2PrivateKey, ciphertext: &[u8]) -> Sm2Result<Vec<u8>> {
+        if self.is_gbt() {
+            return sk.decrypt(ciphertext, self.compressed, self.model);
+        }
+        let (c1, c3, c2) = split_cipher(ciphertext, self.compressed, self.model)?;
+        let c1_point = Point::from_byte(c1)?;
+        c1_point.check()?;
+        let (x2, y2) = coordinates(&c1_point.scalar_mul(&sk.d));
+        let (dem_key, mac_key) = self.derive(&x2, &y2, c1, c2.len())?;
+        if self.mac == Mac::HmacSm3 && !ct_eq(&self.c3(&x2, &y2, &mac_key, &[], c2), c3) {
+            return Err(Sm2Error::HashNotEqual);
+        }
+        let msg = self.dem_apply(&dem_key, c2)?;
+        if self.mac == Mac::HashC3 && !ct_eq(&self.c3(&x2, &y2, &mac_key, &msg, c2), c3) {
+            return Err(Sm2Error::HashNotEqual);
+        }
+        Ok(msg)
+    }
+
+    /// The DEM key and the MAC key, either may be empty
+    fn derive(
+        &self,
+        x2: &[u8],
+        y2: &[u8],
+        c1: &[u8],
+        msg_len: usize,
+    ) -> Sm2Result<(Key, Key)> {
+        let dem_len = match self.dem {
+            Dem::Xor => msg_len,
+            Dem::Sm4Ctr => 32,
+        };
+        let mac_len = match self.mac {
+            Mac::HashC3 => 0,
+            Mac::HmacSm3 => 32,
+        };
+        let z = Zeroizing::new([x2, y2].concat());
+        let mut keys = Zeroizing::new(match self.kdf {
+            Kdf::Sm3Kdf => kdf(&z, dem_len + mac_len)?,
+            Kdf::HkdfSm3 => hkdf_sm3(&[], &z, c1, dem_len + mac_len).map_err(|_| Sm2Error::InvalidKeyLength)?,
+        });
+        // GB/T 32918.4 rejects an all zero XOR key stream
+        if self.dem == Dem::Xor && keys[..dem_len].iter().all(|&b| b == 0) {
+            return Err(Sm2Error::KdfHashError);
+        }
+        let mac_key = Zeroizing::new(keys.split_off(dem_len));
+        Ok((keys, mac_key))
+    }
+
+    fn dem_apply(&self, key: &[u8], data: &[u8]) -> Sm2Result<Vec<u8>> {
+        match self.dem {
+            Dem::Xor => Ok(xor_bytes(data, key)),
+            Dem::Sm4Ctr => Sm4CipherMode::new(&key[..16], CipherMode::Ctr)
+                .and_then(|c| c.encrypt(data, &key[16..]))
+                .map_err(|_| Sm2Error::InvalidKeyLength),
+        }
+    }
+
+    fn c3(&self, x2: &[u8], y2: &[u8], mac_key: &[u8], msg: &[u8], c2: &[u8]) -> [u8; 32] {
+        match self.mac {
+            Mac::HashC3 => sm3_hash(&[x2, msg, y2].concat()),
+            Mac::HmacSm3 => sm3_hmac(mac_key, c2),
+        }
+    }
+}
+
+/// `x2`, `y2` of `[k]P`, big-endian
+fn coordinates(p: &Point) -> (Vec<u8>, Vec<u8>) {
+    let p = p.to_affine_point();
+    (fp_from_mont(&p.x).to_byte_be(), fp_from_mont(&p.y).to_byte_be())
+}
+
+#[cfg(test)]
+mod test_ecies {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_profiles() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let msg = b"encryption standard".to_vec();
+
+        let gbt = EciesProfile::default();
+        assert_eq!(msg, sk.decrypt(&gbt.encrypt(&pk, &msg).unwrap(), false, Sm2Model::C1C3C2).unwrap());
+        assert_eq!(msg, gbt.decrypt(&sk, &pk.encrypt(&msg, false, Sm2Model::C1C3C2).unwrap()).unwrap());
+
+        let mut profiles = vec![];
+        for kdf in [Kdf::Sm3Kdf, Kdf::HkdfSm3] {
+            for mac in [Mac::HashC3, Mac::HmacSm3] {
+                for dem in [Dem::Xor, Dem::Sm4Ctr] {
+                    for (compressed, model) in [(false, Sm2Model::C1C3C2), (true, Sm2Model::C1C2C3)] {
+                        profiles.push(EciesProfile { kdf, mac, dem, compressed, model });
+                    }
+                }
+            }
+        }
+        for profile in &profiles {
+            let c = profile.encrypt(&pk, &msg).unwrap();
+            assert_eq!(msg.len() + 32 + if profile.compressed { 33 } else { 65 }, c.len());
+            assert_eq!(msg, profile.decrypt(&sk, &c).unwrap(), "{:?}", profile);
+
+            let mut bad = c.clone();
+            *bad.last_mut().unwrap() ^= 1;
+            assert!(profile.decrypt(&sk, &bad).is_err());
+            // a ciphertext of one dialect does not decrypt under another
+            for other in profiles.iter().filter(|o| o.compressed == profile.compressed && o.model == profile.model) {
+                if other != profile {
+                    assert!(other.decrypt(&sk, &c).is_err(), "{:?} {:?}", profile, other);
+                }
+            }
+        }
+        assert_eq!(Err(Sm2Error::InvalidLength), profiles[1].encrypt(&pk, b""));
+    }
+}
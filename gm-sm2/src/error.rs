@@ -21,9 +21,35 @@ pub enum Sm2Error {
     InvalidFieldLen,
     ZeroSig,
     InvalidDigestLen,
+    InvalidSignatureLen,
     InvalidDigest,
     InvalidSecretKey,
     KdfHashError,
+    InvalidToken,
+    InvalidPassword,
+    ZeroPrivateKey,
+    PrivateKeyOutOfRange,
+    InvalidLength,
+    IoError,
+    ContextTooLong,
+    NotInRing,
+    UnknownSigner,
+    NonCanonical,
+    InvalidKeyLength,
+    HighS,
+    UnknownKey,
+    UnknownAlgorithm,
+    InvalidSshKey,
+    InvalidArmor,
+    KeyMismatch,
+    InvalidShare,
+    PaillierOutOfRange,
+    PaillierModulusSize,
+    ThresholdAbort,
+    InvalidDerivation,
+    InvalidMnemonic,
+    InvalidAddress,
+    TooManyIterations,
 }
 
 impl ::std::fmt::Debug for Sm2Error {
@@ -51,9 +77,35 @@ impl From<Sm2Error> for &str {
             Sm2Error::InvalidFieldLen => "a SCA-256 field element must be 32-byte long",
             Sm2Error::ZeroSig => "the signature is zero, cannot sign",
             Sm2Error::InvalidDigestLen => "the length of digest must be 32-bytes",
+            Sm2Error::InvalidSignatureLen => "the length of signature must be 64-bytes",
             Sm2Error::InvalidSecretKey => "invalid secret key",
             Sm2Error::KdfHashError => "KDF hash error",
             Sm2Error::InvalidDigest => "invalid signature digest",
+            Sm2Error::InvalidToken => "malformed token or key encoding",
+            Sm2Error::InvalidPassword => "wrong password or corrupted key data",
+            Sm2Error::ZeroPrivateKey => "the private key is zero",
+            Sm2Error::PrivateKeyOutOfRange => "the private key must be in [1, n-2]",
+            Sm2Error::InvalidLength => "invalid input length",
+            Sm2Error::IoError => "failed to read the input",
+            Sm2Error::ContextTooLong => "signature context is longer than 255 bytes",
+            Sm2Error::NotInRing => "the signing key is not in the ring",
+            Sm2Error::UnknownSigner => "the key is not one of the signers",
+            Sm2Error::NonCanonical => "non-canonical encoding, the value is not below its modulus",
+            Sm2Error::InvalidKeyLength => "the KDF output length must be in [1, (2^32 - 1) * 32] bytes",
+            Sm2Error::HighS => "s is above n/2",
+            Sm2Error::UnknownKey => "no key with this handle",
+            Sm2Error::UnknownAlgorithm => "the algorithm is not supported",
+            Sm2Error::InvalidSshKey => "malformed or unsupported OpenSSH key or signature",
+            Sm2Error::InvalidArmor => "malformed armored message or bad checksum",
+            Sm2Error::KeyMismatch => "the message names another key",
+            Sm2Error::InvalidShare => "a share does not match its dealer's commitments",
+            Sm2Error::PaillierOutOfRange => "Paillier plaintext, ciphertext or modulus out of range",
+            Sm2Error::PaillierModulusSize => "Paillier modulus must have an even number of bits, at least 1024",
+            Sm2Error::ThresholdAbort => "threshold signing aborted on an inconsistent message",
+            Sm2Error::InvalidDerivation => "no such child key, hardened from a public key or out of range",
+            Sm2Error::InvalidMnemonic => "unknown word, wrong word count or bad checksum in a mnemonic",
+            Sm2Error::InvalidAddress => "malformed address, wrong length, prefix or checksum",
+            Sm2Error::TooManyIterations => "PBKDF2 iteration count above the accepted maximum",
         }
     }
 }
@@ -77,9 +129,35 @@ impl Display for Sm2Error {
             Sm2Error::InvalidFieldLen => "a SCA-256 field element must be 32-byte long",
             Sm2Error::ZeroSig => "the signature is zero, cannot sign",
             Sm2Error::InvalidDigestLen => "the length of digest must be 32-bytes",
+            Sm2Error::InvalidSignatureLen => "the length of signature must be 64-bytes",
             Sm2Error::InvalidSecretKey => "invalid secret key",
             Sm2Error::KdfHashError => "KDF hash error",
             Sm2Error::InvalidDigest => "invalid signature digest",
+            Sm2Error::InvalidToken => "malformed token or key encoding",
+            Sm2Error::InvalidPassword => "wrong password or corrupted key data",
+            Sm2Error::ZeroPrivateKey => "the private key is zero",
+            Sm2Error::PrivateKeyOutOfRange => "the private key must be in [1, n-2]",
+            Sm2Error::InvalidLength => "invalid input length",
+            Sm2Error::IoError => "failed to read the input",
+            Sm2Error::ContextTooLong => "signature context is longer than 255 bytes",
+            Sm2Error::NotInRing => "the signing key is not in the ring",
+            Sm2Error::UnknownSigner => "the key is not one of the signers",
+            Sm2Error::NonCanonical => "non-canonical encoding, the value is not below its modulus",
+            Sm2Error::InvalidKeyLength => "the KDF output length must be in [1, (2^32 - 1) * 32] bytes",
+            Sm2Error::HighS => "s is above n/2",
+            Sm2Error::UnknownKey => "no key with this handle",
+            Sm2Error::UnknownAlgorithm => "the algorithm is not supported",
+            Sm2Error::InvalidSshKey => "malformed or unsupported OpenSSH key or signature",
+            Sm2Error::InvalidArmor => "malformed armored message or bad checksum",
+            Sm2Error::KeyMismatch => "the message names another key",
+            Sm2Error::InvalidShare => "a share does not match its dealer's commitments",
+            Sm2Error::PaillierOutOfRange => "Paillier plaintext, ciphertext or modulus out of range",
+            Sm2Error::PaillierModulusSize => "Paillier modulus must have an even number of bits, at least 1024",
+            Sm2Error::ThresholdAbort => "threshold signing aborted on an inconsistent message",
+            Sm2Error::InvalidDerivation => "no such child key, hardened from a public key or out of range",
+            Sm2Error::InvalidMnemonic => "unknown word, wrong word count or bad checksum in a mnemonic",
+            Sm2Error::InvalidAddress => "malformed address, wrong length, prefix or checksum",
+            Sm2Error::TooManyIterations => "PBKDF2 iteration count above the accepted maximum",
         };
         write!(f, "{}", err_msg)
     }
@@ -1,13 +1,15 @@
 use byteorder::{BigEndian, WriteBytesExt};
 
+use gm_core::ct_eq;
 use gm_sm3::sm3_hash;
 
 use crate::error::{Sm2Error, Sm2Result};
 use crate::fields::FieldModOperation;
 use crate::fields::fn64::{fn_add, fn_mul};
-use crate::fields::fp64::{fp_from_mont, random_u256};
+use crate::fields::fn64::fn_random;
+use crate::fields::fp64::fp_from_mont;
 use crate::key::{gen_keypair, Sm2PrivateKey, Sm2PublicKey};
-use crate::p256_ecc::{g_mul, Point};
+use crate::p256_ecc::{g_mul, PeerPoint, Point};
 use crate::u256::{SM2_ONE, U256, u256_add, u256_bits_and, u256_sub};
 use crate::util::{compute_za, DEFAULT_ID, kdf};
 
@@ -50,6 +52,7 @@ impl Exchange {
     ) -> Sm2Result<Exchange> {
         let id = id.unwrap_or_else(|| DEFAULT_ID);
         let rhs_id = rhs_id.unwrap_or_else(|| DEFAULT_ID);
+        rhs_pk.point.check().map_err(|_| Sm2Error::InvalidPublic)?;
         Ok(Exchange {
             klen,
             za: compute_za(id, &pk.point)?,
@@ -68,7 +71,7 @@ impl Exchange {
     // A2：计算椭圆曲线点RA = [rA]G=(x1,y1)；
     // A3：将RA发送给用户B；
     pub fn exchange_1(&mut self) -> Sm2Result<Point> {
-        let r = random_u256();
+        let r = fn_random();
         let r_point = g_mul(&r);
         self.r = Some(r);
         self.r_point = Some(r_point);
@@ -77,10 +80,8 @@ impl Exchange {
 
     // Step2: UserB Call
     //
-    pub fn exchange_2(&mut self, ra_point: &Point) -> Sm2Result<(Point, [u8; 32])> {
-        if !ra_point.is_valid() {
-            return Err(Sm2Error::CheckPointErr);
-        }
+    pub fn exchange_2<P: PeerPoint>(&mut self, ra_point: &P) -> Sm2Result<(Point, [u8; 32])> {
+        let ra_point = &ra_point.checked()?;
         // 2^127
         let pow: [u64; 4] = [
             0x8000000000000000,
@@ -89,7 +90,7 @@ impl Exchange {
             0x0000000000000000,
         ];
 
-        let r2 = random_u256();
+        let r2 = fn_random();
         let r2_point = g_mul(&r2);
         self.r = Some(r2);
         self.r_point = Some(r2_point);
@@ -130,7 +131,7 @@ impl Exchange {
         prepend.extend_from_slice(&self.rhs_za); // User A
         prepend.extend_from_slice(&self.za); // User B
 
-        let k_b = kdf(&prepend, self.klen);
+        let k_b = kdf(&prepend, self.klen)?;
         self.k = Some(k_b);
 
         let mut temp: Vec<u8> = Vec::new();
@@ -151,10 +152,8 @@ impl Exchange {
 
     // Step4: UserA Call
     //
-    pub fn exchange_3(&mut self, rb_point: &Point, sb: [u8; 32]) -> Sm2Result<[u8; 32]> {
-        if !rb_point.is_valid() {
-            return Err(Sm2Error::CheckPointErr);
-        }
+    pub fn exchange_3<P: PeerPoint>(&mut self, rb_point: &P, sb: [u8; 32]) -> Sm2Result<[u8; 32]> {
+        let rb_point = &rb_point.checked()?;
         // 2^127
         let pow: [u64; 4] = [
             0x8000000000000000,
@@ -198,7 +197,7 @@ impl Exchange {
         prepend.extend_from_slice(&self.za);
         prepend.extend_from_slice(&self.rhs_za);
 
-        let k_a = kdf(&prepend, self.klen);
+        let k_a = kdf(&prepend, self.klen)?;
         self.k = Some(k_a);
 
         let mut temp: Vec<u8> = Vec::new();
@@ -217,7 +216,7 @@ impl Exchange {
         prepend.extend_from_slice(&temp_hash);
 
         let s1 = sm3_hash(&prepend);
-        if s1 != sb {
+        if !ct_eq(&s1, &sb) {
             return Err(Sm2Error::HashNotEqual);
         }
 
@@ -229,7 +228,8 @@ impl Exchange {
     }
 
     // Step4: UserA Call
-    pub fn exchange_4(&self, sa: [u8; 32], ra_point: &Point) -> Sm2Result<bool> {
+    pub fn exchange_4<P: PeerPoint>(&self, sa: [u8; 32], ra_point: &P) -> Sm2Result<bool> {
+        let ra_point = &ra_point.checked()?;
         let ra_point_affine = ra_point.to_affine_point();
         let x1 = fp_from_mont(&ra_point_affine.x);
         let y1 = fp_from_mont(&ra_point_affine.y);
@@ -256,6 +256,6 @@ impl Exchange {
         prepend.extend_from_slice(&yv.to_byte_be());
         prepend.extend_from_slice(&sm3_hash(&temp));
         let s_2 = sm3_hash(&prepend);
-        Ok(s_2 == sa)
+        Ok(ct_eq(&s_2, &sa))
     }
 }
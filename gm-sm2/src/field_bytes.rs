@@ -0,0 +1,170 @@
+//! Conversions of SM2 keys and signatures to the `GenericArray` types of the
+//! RustCrypto curve crates (enable the `field-bytes` feature), so that code
+//! written against `p256` or `k256` byte types carries over to SM2.
+//!
+//! As there, scalars and coordinates are 32 big-endian bytes, a signature is
+//! `r || s` and points are SEC1 encoded. The types are generic-array 0.14,
+//! re-exported as [`generic_array`], which its last releases deprecate in
+//! favour of 1.x while the RustCrypto crates still build on it.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::field_bytes::{FieldBytes, SignatureBytes};
+//! use gm_sm2::key::{Sm2PrivateKey, Sm2PublicKey};
+//! use gm_sm2::signature::Sm2Signature;
+//!
+//! let sk = Sm2PrivateKey::from_field_bytes(&FieldBytes::from([7u8; 32])).unwrap();
+//! let (x, y) = sk.public_key.to_affine_coordinates();
+//! let pk = Sm2PublicKey::from_affine_coordinates(&x, &y).unwrap();
+//!
+//! let sig = Sm2Signature::from_raw_bytes(&sk.sign(None, b"msg").unwrap()).unwrap();
+//! let bytes: SignatureBytes = sig.to_signature_bytes();
+//! let (r, s) = sig.split_bytes();
+//! assert_eq!(sig, Sm2Signature::from_field_bytes(&r, &s).unwrap());
+//! pk.verify(None, b"msg", &bytes).unwrap();
+//! ```
+#![allow(deprecated)]
+
+pub use generic_array;
+use generic_array::typenum::{U32, U33, U64, U65};
+use generic_array::GenericArray;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::FieldModOperation;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::signature::Sm2Signature;
+
+/// A scalar or a coordinate, big-endian
+pub type FieldBytes = GenericArray<u8, U32>;
+
+/// `r || s`
+pub type SignatureBytes = GenericArray<u8, U64>;
+
+/// SEC1 `0x02`/`0x03 || x`
+pub type CompressedPoint = GenericArray<u8, U33>;
+
+/// SEC1 `0x04 || x || y`
+pub type UncompressedPoint = GenericArray<u8, U65>;
+
+impl Sm2PrivateKey {
+    /// The secret scalar, [`Sm2PrivateKey::new`] on the bytes
+    pub fn from_field_bytes(bytes: &FieldBytes) -> Sm2Result<Self> {
+        Self::new(bytes)
+    }
+
+    pub fn to_field_bytes(&self) -> FieldBytes {
+        self.to_be_bytes().into()
+    }
+}
+
+impl TryFrom<&FieldBytes> for Sm2PrivateKey {
+    type Error = Sm2Error;
+
+    fn try_from(bytes: &FieldBytes) -> Sm2Result<Self> {
+        Self::from_field_bytes(bytes)
+    }
+}
+
+impl Sm2PublicKey {
+    /// A point on the curve other than the infinity
+    pub fn from_affine_coordinates(x: &FieldBytes, y: &FieldBytes) -> Sm2Result<Self> {
+        Self::new(&[&[4][..], x, y].concat())
+    }
+
+    pub fn to_affine_coordinates(&self) -> (FieldBytes, FieldBytes) {
+        (self.x_bytes().into(), self.y_bytes().into())
+    }
+
+    pub fn to_compressed_point(&self) -> CompressedPoint {
+        GenericArray::clone_from_slice(&self.to_bytes(true))
+    }
+
+    pub fn to_uncompressed_point(&self) -> UncompressedPoint {
+        GenericArray::clone_from_slice(&self.to_bytes(false))
+    }
+}
+
+impl From<&Sm2PublicKey> for CompressedPoint {
+    fn from(pk: &Sm2PublicKey) -> Self {
+        pk.to_compressed_point()
+    }
+}
+
+impl From<&Sm2PublicKey> for UncompressedPoint {
+    fn from(pk: &Sm2PublicKey) -> Self {
+        pk.to_uncompressed_point()
+    }
+}
+
+impl Sm2Signature {
+    /// Both in [1, n-1], as [`Sm2Signature::from_raw_bytes`]
+    pub fn from_field_bytes(r: &FieldBytes, s: &FieldBytes) -> Sm2Result<Self> {
+        Self::from_raw_bytes(&[r.as_slice(), s.as_slice()].concat())
+    }
+
+    pub fn split_bytes(&self) -> (FieldBytes, FieldBytes) {
+        let r: [u8; 32] = self.r.to_byte_be().try_into().unwrap();
+        let s: [u8; 32] = self.s.to_byte_be().try_into().unwrap();
+        (r.into(), s.into())
+    }
+
+    pub fn to_signature_bytes(&self) -> SignatureBytes {
+        GenericArray::clone_from_slice(&self.to_raw_bytes())
+    }
+}
+
+impl TryFrom<&SignatureBytes> for Sm2Signature {
+    type Error = Sm2Error;
+
+    fn try_from(bytes: &SignatureBytes) -> Sm2Result<Self> {
+        Self::from_raw_bytes(bytes)
+    }
+}
+
+impl From<&Sm2Signature> for SignatureBytes {
+    fn from(sig: &Sm2Signature) -> Self {
+        sig.to_signature_bytes()
+    }
+}
+
+#[cfg(test)]
+mod test_field_bytes {
+    use super::*;
+    use crate::fields::fn64::SM2_N;
+    use crate::u256::u256_to_be_bytes;
+
+    #[test]
+    fn test_keys() {
+        let sk = Sm2PrivateKey::new(&[7; 32]).unwrap();
+        let bytes = sk.to_field_bytes();
+        assert_eq!([7; 32], bytes.as_slice());
+        assert_eq!(sk, Sm2PrivateKey::try_from(&bytes).unwrap());
+        assert!(Sm2PrivateKey::from_field_bytes(&FieldBytes::default()).is_err());
+
+        let pk = sk.public_key;
+        let (x, y) = pk.to_affine_coordinates();
+        assert_eq!(pk, Sm2PublicKey::from_affine_coordinates(&x, &y).unwrap());
+        assert_eq!(pk.to_bytes(true), CompressedPoint::from(&pk).as_slice());
+        assert_eq!(pk.to_bytes(false), UncompressedPoint::from(&pk).as_slice());
+        // flipping the low bit of y leaves the curve
+        let mut y1 = y;
+        y1[31] ^= 1;
+        assert!(Sm2PublicKey::from_affine_coordinates(&x, &y1).is_err());
+    }
+
+    #[test]
+    fn test_signature() {
+        let sk = Sm2PrivateKey::new(&[7; 32]).unwrap();
+        let sig = Sm2Signature::from_raw_bytes(&sk.sign(None, b"msg").unwrap()).unwrap();
+        let bytes = SignatureBytes::from(&sig);
+        assert_eq!(sig.to_raw_bytes(), bytes.as_slice());
+        assert_eq!(sig, Sm2Signature::try_from(&bytes).unwrap());
+        let (r, s) = sig.split_bytes();
+        assert_eq!(sig, Sm2Signature::from_field_bytes(&r, &s).unwrap());
+        sk.public_key.verify(None, b"msg", &bytes).unwrap();
+
+        let n = FieldBytes::clone_from_slice(&u256_to_be_bytes(&SM2_N));
+        assert_eq!(Err(Sm2Error::NonCanonical), Sm2Signature::from_field_bytes(&n, &s));
+        assert_eq!(Err(Sm2Error::ZeroSig), Sm2Signature::from_field_bytes(&r, &FieldBytes::default()));
+    }
+}
@@ -1,5 +1,11 @@
+use gm_core::limb::{const_eq, const_mont_mul, const_sub};
+use pkcs8::der::zeroize::Zeroize;
+use rand::{CryptoRng, RngCore};
+
+use crate::error::{Sm2Error, Sm2Result};
 use crate::u256::{
-    SM2_ONE, U256, u256_add, u256_cmp, u256_mul, u256_sub, u512_add,
+    SM2_ONE, U256, U512, u256_add, u256_cmp, u256_from_be_bytes, u256_mont_mul, u256_mul,
+    u256_sub, u512_mod,
 };
 
 ///
@@ -41,6 +47,16 @@ pub const SM2_MOD_N_2E512: U256 = [
     0x1eb5e412a22b3d3b,
 ];
 
+// checked when the crate is built
+const _: () = {
+    assert!(const_eq(&const_sub(&[0; 4], &SM2_N).0, &SM2_N_NEG));
+    assert!(const_eq(&const_sub(&SM2_N, &[2, 0, 0, 0]).0, &SM2_N_MINUS_TWO));
+    assert!(SM2_N[0].wrapping_mul(SM2_N_PRIME[0]) == u64::MAX);
+    // R^2 / R = R = 2^256 - n mod n
+    let r = const_mont_mul(&SM2_MOD_N_2E512, &SM2_ONE, &SM2_N, SM2_N_PRIME[0]);
+    assert!(const_eq(&r, &SM2_N_NEG));
+};
+
 pub fn fn_add(a: &U256, b: &U256) -> U256 {
     let (r, c) = u256_add(a, b);
     if c {
@@ -70,45 +86,17 @@ pub fn fn_from_mont(a: &U256) -> U256 {
 }
 
 pub fn fn_mul(a: &U256, b: &U256) -> U256 {
-    let mont_a = fn_to_mont(a);
-    let mont_b = fn_to_mont(b);
-    let mut r = mont_mul(&mont_a, &mont_b);
-    r = fn_from_mont(&r);
-    r
+    fn_from_u512(&u256_mul(a, b))
+}
+
+/// z mod n
+pub fn fn_from_u512(z: &U512) -> U256 {
+    u512_mod(z, &SM2_N, SM2_N_PRIME[0], &SM2_MOD_N_2E512)
 }
 
 fn mont_mul(a: &U256, b: &U256) -> U256 {
-    let mut r = [0u64; 4];
-    let mut z = [0u64; 8];
-    let mut t = [0u64; 8];
-
-    // z = a * b
-    z = u256_mul(a, b);
-
-    // t = low(z) * n'
-    let z_low = [z[0], z[1], z[2], z[3]];
-    let t1 = u256_mul(&z_low, &SM2_N_PRIME);
-    t[0] = t1[0];
-    t[1] = t1[1];
-    t[2] = t1[2];
-    t[3] = t1[3];
-
-    // t = low(t) * n
-    let t_low = [t[0], t[1], t[2], t[3]];
-    t = u256_mul(&t_low, &SM2_N);
-
-    // z = z + t
-    let (sum, c) = u512_add(&z, &t);
-    z = sum;
-
-    // r = high(r)
-    r = [z[4], z[5], z[6], z[7]];
-    if c {
-        r = u256_add(&r, &SM2_N_NEG).0;
-    } else if u256_cmp(&r, &SM2_N) >= 0 {
-        r = u256_sub(&r, &SM2_N).0
-    }
-    r
+    // SM2_N_PRIME[0] = -n^-1 mod 2^64
+    u256_mont_mul(a, b, &SM2_N, SM2_N_PRIME[0])
 }
 
 pub fn fn_pow(a: &U256, e: &U256) -> U256 {
@@ -129,18 +117,96 @@ pub fn fn_pow(a: &U256, e: &U256) -> U256 {
     r
 }
 
+/// a^-1 mod n = a^(n-2) mod n
 pub fn fn_inv(a: &U256) -> U256 {
-    let mont_a = fn_to_mont(a);
-    let mut r = fn_pow(&mont_a, &SM2_N_MINUS_TWO);
-    r = fn_from_mont(&r);
-    r
+    fn_pow(a, &SM2_N_MINUS_TWO)
+}
+
+/// Decode a 32-byte big-endian scalar, rejecting values not below n.
+pub fn fn_from_bytes(buf: &[u8]) -> Sm2Result<U256> {
+    if buf.len() != 32 {
+        return Err(Sm2Error::InvalidFieldLen);
+    }
+    let k = u256_from_be_bytes(buf);
+    if u256_cmp(&k, &SM2_N) >= 0 {
+        return Err(Sm2Error::NonCanonical);
+    }
+    Ok(k)
+}
+
+/// A uniform scalar in [1, n-1]
+pub fn fn_random() -> U256 {
+    fn_random_with(&mut rand::thread_rng())
+}
+
+/// Uniform in [1, n-1], by rejecting the 32-byte draws not in range, so there
+/// is no modulo bias.
+pub fn fn_random_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> U256 {
+    let mut buf = [0u8; 32];
+    loop {
+        rng.fill_bytes(&mut buf);
+        let k = u256_from_be_bytes(&buf);
+        if u256_cmp(&k, &SM2_N) < 0 && k != [0; 4] {
+            buf.zeroize();
+            return k;
+        }
+    }
 }
 
 #[cfg(test)]
 mod test_mod_operation {
     use num_bigint::BigUint;
 
-    use crate::fields::fn64::fn_mul;
+    use rand::{CryptoRng, RngCore};
+
+    use crate::fields::fn64::{fn_from_u512, fn_inv, fn_mul, fn_random_with, SM2_N};
+    use crate::u256::u256_to_be_bytes;
+
+    /// Hands out the queued 32-byte blocks in order
+    /// Hands out its bytes in order, panics once they run out
+    struct Queue(Vec<u8>);
+
+    impl RngCore for Queue {
+        fn next_u32(&mut self) -> u32 {
+            let mut b = [0u8; 4];
+            self.fill_bytes(&mut b);
+            u32::from_le_bytes(b)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut b = [0u8; 8];
+            self.fill_bytes(&mut b);
+            u64::from_le_bytes(b)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.copy_from_slice(&self.0[..dest.len()]);
+            self.0.drain(..dest.len());
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for Queue {}
+
+    #[test]
+    fn test_random_rejects() {
+        // n, all ones and zero are drawn again, n - 1 is kept
+        let n: [u8; 32] = u256_to_be_bytes(&SM2_N).try_into().unwrap();
+        let mut n_1 = n;
+        n_1[31] -= 1;
+        let mut rng = Queue([n, [0xff; 32], [0; 32], n_1].concat());
+        assert_eq!(n_1.to_vec(), u256_to_be_bytes(&fn_random_with(&mut rng)));
+        assert!(rng.0.is_empty());
+
+        let mut rng = Queue((1..=12).collect());
+        assert_eq!(0x04030201, rng.next_u32());
+        assert_eq!(0x0c0b0a0908070605, rng.next_u64());
+        assert!(rng.0.is_empty());
+    }
 
     #[test]
     fn test_mod_op() {
@@ -176,8 +242,69 @@ mod test_mod_operation {
                 .unwrap(),
         );
 
-        let r = ((&a1 * &b1) % n1).to_u64_digits();
-        println!("{:x?}", r);
+        let r1 = ((&a1 * &b1) % &n1).to_u64_digits();
+        println!("{:x?}", r1);
+        assert_eq!(r.to_vec(), r1);
+
+        let inv = fn_inv(&a);
+        assert_eq!([1, 0, 0, 0], fn_mul(&inv, &a));
+        let inv1 = a1.modpow(&(&n1 - 2u8), &n1).to_u64_digits();
+        assert_eq!(inv.to_vec(), inv1);
+    }
+
+    #[test]
+    fn test_from_u512() {
+        let n1 = BigUint::from_bytes_be(
+            &hex::decode("fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54123")
+                .unwrap(),
+        );
+        let max = [u64::MAX; 8];
+        let cases = [
+            max,
+            [0; 8],
+            [1, 2, 3, 4, 5, 6, 7, 8],
+            [
+                0x53bbf40939d54123,
+                0x7203df6b21c6052b,
+                0xffffffffffffffff,
+                0xfffffffeffffffff,
+                0,
+                0,
+                0,
+                0,
+            ],
+        ];
+        for z in cases.iter() {
+            let bytes: Vec<u8> = z.iter().flat_map(|d| d.to_le_bytes()).collect();
+            let z1 = BigUint::from_bytes_le(&bytes);
+            let mut expect = (&z1 % &n1).to_u64_digits();
+            expect.resize(4, 0);
+            assert_eq!(expect, fn_from_u512(z).to_vec());
+        }
+    }
+
+    #[test]
+    fn test_canonical_decoding() {
+        use crate::error::Sm2Error;
+        use crate::fields::fn64::{fn_from_bytes, SM2_N};
+        use crate::fields::fp64::{fp_from_bytes, fp_from_mont, SM2_P};
+        use crate::p256_ecc::Point;
+        use crate::u256::{u256_sub, u256_to_be_bytes, SM2_ONE};
+
+        let n = u256_to_be_bytes(&SM2_N);
+        let n_1 = u256_sub(&SM2_N, &SM2_ONE).0;
+        assert_eq!(Ok(n_1), fn_from_bytes(&u256_to_be_bytes(&n_1)));
+        assert_eq!(Err(Sm2Error::NonCanonical), fn_from_bytes(&n));
+        assert_eq!(Err(Sm2Error::NonCanonical), fn_from_bytes(&[0xff; 32]));
+        assert_eq!(Err(Sm2Error::InvalidFieldLen), fn_from_bytes(&n[1..]));
+
+        let p = u256_to_be_bytes(&SM2_P);
+        let p_1 = u256_sub(&SM2_P, &SM2_ONE).0;
+        assert_eq!(p_1, fp_from_mont(&fp_from_bytes(&u256_to_be_bytes(&p_1)).unwrap()));
+        assert_eq!(Err(Sm2Error::NonCanonical), fp_from_bytes(&p));
 
+        // x + p encodes the same field element as x, it must not decode
+        assert!(Point::from_byte(&[&[0x02][..], &p].concat()).is_err());
+        assert!(Point::from_byte(&[&[0x04][..], &p, &[0; 32]].concat()).is_err());
     }
 }
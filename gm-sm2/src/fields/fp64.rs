@@ -1,4 +1,5 @@
-use rand::RngCore;
+use gm_core::limb::{const_add_mod, const_eq, const_mont_mul, const_sub, ct_equal, select};
+use gm_core::group::Choice;
 
 use crate::error::{Sm2Error, Sm2Result};
 use crate::fields::FieldModOperation;
@@ -88,21 +89,34 @@ pub const SM2_G_Y: U256 = [
     0xbc3736a2f4f6779c,
 ];
 
-#[inline(always)]
-pub fn random_u256() -> U256 {
-    let mut rng = rand::thread_rng();
-    let mut buf: [u8; 32] = [0; 32];
-    let mut ret;
-    loop {
-        rng.fill_bytes(&mut buf[..]);
-        ret = u256_from_be_bytes(&buf);
-        if u256_cmp(&ret, &SM2_P_MINUS_ONE) < 0 && ret != [0, 0, 0, 0] {
-            break;
-        }
-    }
-    ret
+const fn const_mont(a: &U256) -> U256 {
+    const_mont_mul(a, &SM2_MODP_2E512, &SM2_P, SM2_P_PRIME[0])
+}
+
+const fn const_mul(a: &U256, b: &U256) -> U256 {
+    const_mont_mul(a, b, &SM2_P, SM2_P_PRIME[0])
 }
 
+// The constants above are checked when the crate is built, a transcription
+// error fails the build.
+const _: () = {
+    let one = [1, 0, 0, 0];
+    // the Montgomery multiplication uses p' mod 2^64, p * p' = -1
+    assert!(SM2_P[0].wrapping_mul(SM2_P_PRIME[0]) == u64::MAX);
+    assert!(const_eq(&const_sub(&SM2_P, &one).0, &SM2_P_MINUS_ONE));
+    assert!(const_eq(&const_sub(&SM2_P, &[2, 0, 0, 0]).0, &SM2_P_MINUS_TWO));
+    assert!(const_eq(&const_mont(&one), &SM2_MODP_MONT_ONE));
+    // R^2 * R / R = R^2
+    assert!(const_eq(&const_mul(&SM2_MODP_2E512, &SM2_MODP_MONT_ONE), &SM2_MODP_2E512));
+    assert!(const_eq(&const_mont(&const_sub(&SM2_P, &[3, 0, 0, 0]).0), &SM2_MODP_MONT_A));
+
+    // G is on y^2 = x^3 + ax + b
+    let (x, y) = (const_mont(&SM2_G_X), const_mont(&SM2_G_Y));
+    let rhs = const_add_mod(&const_mul(&const_mul(&x, &x), &x), &const_mul(&SM2_MODP_MONT_A, &x), &SM2_P);
+    let rhs = const_add_mod(&rhs, &SM2_MODP_MONT_B, &SM2_P);
+    assert!(const_eq(&const_mul(&y, &y), &rhs));
+};
+
 pub fn fp_pow(a: &U256, e: &U256) -> U256 {
     let mut r = SM2_MODP_MONT_ONE;
     let mut w = 0u64;
@@ -127,6 +141,18 @@ pub(crate) fn fp_from_mont(a: &U256) -> U256 {
     mont_mul(a, &SM2_ONE)
 }
 
+/// Decode 32 big-endian bytes to Montgomery form, rejecting values not below p.
+pub fn fp_from_bytes(buf: &[u8]) -> Sm2Result<U256> {
+    if buf.len() != 32 {
+        return Err(Sm2Error::InvalidFieldLen);
+    }
+    let a = u256_from_be_bytes(buf);
+    if u256_cmp(&a, &SM2_P) >= 0 {
+        return Err(Sm2Error::NonCanonical);
+    }
+    Ok(fp_to_mont(&a))
+}
+
 pub(crate) fn mont_mul(a: &U256, b: &U256) -> U256 {
     let mut r = [0u64; 4];
 
@@ -152,20 +178,20 @@ pub(crate) fn mont_mul(a: &U256, b: &U256) -> U256 {
     let (sum, c) = u512_add(&z, &t);
     z = sum;
 
-    // r = high(r)
+    // r = high(r), less than 2p, minus p without a branch: 2^256 - p is the
+    // Montgomery one, so with the carry out r - p wraps to the same value
     r = [z[4], z[5], z[6], z[7]];
-    if c {
-        r = u256_add(&r, &SM2_MODP_MONT_ONE).0;
-    } else if u256_cmp(&r, &SM2_P) >= 0 {
-        r = u256_sub(&r, &SM2_P).0
-    }
-    r
+    let (d, borrow) = u256_sub(&r, &SM2_P);
+    select(&r, &d, Choice::from(c as u8 | !borrow as u8))
 }
 
+/// Square root as `a^((p + 1) / 4)`, p = 3 mod 4.
+///
+/// The exponent is fixed, so the time does not depend on `a`, only the
+/// final check on a non-square returns early.
 pub fn fp_sqrt(a: &U256) -> Sm2Result<U256> {
     let r = fp_pow(a, &SM2_SQRT_EXP);
-    let a1 = r.fp_sqr();
-    if u256_cmp(&a1, &a) != 0 {
+    if !bool::from(ct_equal(&r.fp_sqr(), a)) {
         return Err(Sm2Error::FieldSqrtError);
     }
     Ok(r)
@@ -200,25 +226,14 @@ impl FieldModOperation for U256 {
 
     fn fp_add(&self, rhs: &Self) -> Self {
         let (r, c) = u256_add(self, rhs);
-        if c {
-            let (diff, _borrow) = u256_add(&r, &SM2_MODP_MONT_ONE);
-            return diff;
-        }
-        if u256_cmp(&r, &SM2_P) >= 0 {
-            let (diff, _borrow) = u256_sub(&r, &SM2_P);
-            return diff;
-        }
-        r
+        let (d, borrow) = u256_sub(&r, &SM2_P);
+        select(&r, &d, Choice::from(c as u8 | !borrow as u8))
     }
 
     fn fp_sub(&self, rhs: &Self) -> Self {
         let (raw_diff, borrow) = u256_sub(self, rhs);
-        if borrow {
-            let (diff, _borrow) = u256_sub(&raw_diff, &SM2_MODP_MONT_ONE);
-            diff
-        } else {
-            raw_diff
-        }
+        let (diff, _borrow) = u256_sub(&raw_diff, &SM2_MODP_MONT_ONE);
+        select(&raw_diff, &diff, Choice::from(borrow as u8))
     }
 
     fn fp_mul(&self, rhs: &Self) -> Self {
@@ -234,16 +249,13 @@ impl FieldModOperation for U256 {
     }
 
     fn fp_div2(&self) -> Self {
-        let mut r = self.clone();
+        let mut r = *self;
         let mut c = 0;
         if r[0] & 0x01 == 1 {
-            r = self.fp_add(&SM2_P);
-            c = u64::from(u256_add(&self, &SM2_P).1)
-        } else {
-            r[0] = self[0];
-            r[1] = self[1];
-            r[2] = self[2];
-            r[3] = self[3];
+            // (a + p) / 2 with the carry of the unreduced sum as bit 255
+            let (sum, carry) = u256_add(self, &SM2_P);
+            r = sum;
+            c = u64::from(carry);
         }
         r[0] = (r[0] >> 1) | ((r[1] & 1) << 63);
         r[1] = (r[1] >> 1) | ((r[2] & 1) << 63);
@@ -264,3 +276,26 @@ impl FieldModOperation for U256 {
         u256_from_be_bytes(input)
     }
 }
+
+#[cfg(test)]
+mod test_fp64 {
+    use crate::fields::fp64::SM2_P;
+    use crate::fields::FieldModOperation;
+    use crate::u256::u256_sub;
+
+    /// For p - 2 the sum a + p overflows 2^256, its carry is bit 255 of the half
+    #[test]
+    fn test_fp_div2() {
+        let p_minus = |k: u64| u256_sub(&SM2_P, &[k, 0, 0, 0]).0;
+        assert_eq!(p_minus(1), p_minus(2).fp_div2());
+        // (1 + p) / 2, without overflow
+        assert_eq!(
+            [0x8000000000000000, 0xffffffff80000000, 0xffffffffffffffff, 0x7fffffff7fffffff],
+            [1, 0, 0, 0].fp_div2()
+        );
+        for a in [[1, 0, 0, 0], [2, 0, 0, 0], p_minus(2), p_minus(3), [u64::MAX, 0, 0, 0]] {
+            let half = a.fp_div2();
+            assert_eq!(a, half.fp_add(&half));
+        }
+    }
+}
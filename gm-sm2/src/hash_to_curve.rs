@@ -0,0 +1,149 @@
+//! Hashing to the SM2 curve, RFC 9380 with SM3 and the simplified SWU map.
+//!
+//! The suite is `SM2_XMD:SM3_SSWU_RO_`: `expand_message_xmd` with SM3,
+//! `hash_to_field` drawing two 48-byte elements, the simplified SWU map with
+//! `Z = -9` (the first value meeting the criteria of RFC 9380 appendix H.2 for
+//! the SM2 prime) and the sum of both mapped points. The cofactor is 1, so
+//! there is nothing to clear. Every caller picks its own domain separation tag.
+//!
+//! The map branches on whether `g(x1)` is a square, so it is meant for public
+//! inputs, such as generators and messages, not for secrets.
+use num_bigint::BigUint;
+
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fp64::{
+    fp_from_mont, fp_sqrt, fp_to_mont, SM2_MODP_MONT_A, SM2_MODP_MONT_B, SM2_MODP_MONT_ONE, SM2_P,
+};
+use crate::fields::FieldModOperation;
+use crate::p256_ecc::Point;
+use crate::u256::{u256_from_be_bytes, u256_to_be_bytes, U256};
+
+/// Bytes drawn per field element, `ceil((256 + 128) / 8)`
+const L: usize = 48;
+
+/// `expand_message_xmd` with SM3, RFC 9380 section 5.3.1
+pub fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Sm2Result<Vec<u8>> {
+    let ell = len.div_ceil(32);
+    if ell > 255 || len > 0xffff || dst.len() > 255 {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+    let b0 = sm3_hash(&[&[0u8; 64][..], msg, &(len as u16).to_be_bytes(), &[0], &dst_prime].concat());
+    let mut bi = sm3_hash(&[&b0[..], &[1], &dst_prime].concat());
+    let mut out = bi.to_vec();
+    for i in 2..=ell {
+        let mixed: Vec<u8> = b0.iter().zip(&bi).map(|(a, b)| a ^ b).collect();
+        bi = sm3_hash(&[&mixed[..], &[i as u8], &dst_prime].concat());
+        out.extend_from_slice(&bi);
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+/// `count` field elements in the Montgomery domain
+fn hash_to_field(msg: &[u8], dst: &[u8], count: usize) -> Sm2Result<Vec<U256>> {
+    let p = BigUint::from_bytes_be(&u256_to_be_bytes(&SM2_P));
+    let uniform = expand_message_xmd(msg, dst, count * L)?;
+    Ok(uniform
+        .chunks(L)
+        .map(|c| {
+            let e = (BigUint::from_bytes_be(c) % &p).to_bytes_be();
+            let mut b = [0u8; 32];
+            b[32 - e.len()..].copy_from_slice(&e);
+            fp_to_mont(&u256_from_be_bytes(&b))
+        })
+        .collect())
+}
+
+/// `g(x) = x^3 + ax + b`
+fn curve_rhs(x: &U256) -> U256 {
+    x.fp_sqr().fp_add(&SM2_MODP_MONT_A).fp_mul(x).fp_add(&SM2_MODP_MONT_B)
+}
+
+fn sgn0(a: &U256) -> u64 {
+    fp_from_mont(a)[0] & 1
+}
+
+/// The simplified SWU map, RFC 9380 section 6.6.2
+fn map_to_curve(u: &U256) -> Point {
+    let z = fp_to_mont(&[9, 0, 0, 0]).fp_neg();
+    let z_u2 = z.fp_mul(&u.fp_sqr());
+    let tv1 = z_u2.fp_sqr().fp_add(&z_u2);
+    let x1 = if tv1.is_zero() {
+        // B / (Z·A)
+        SM2_MODP_MONT_B.fp_mul(&z.fp_mul(&SM2_MODP_MONT_A).fp_inv())
+    } else {
+        // -B / A · (1 + 1 / tv1)
+        let b_over_a = SM2_MODP_MONT_B.fp_mul(&SM2_MODP_MONT_A.fp_inv()).fp_neg();
+        b_over_a.fp_mul(&SM2_MODP_MONT_ONE.fp_add(&tv1.fp_inv()))
+    };
+    let (x, mut y) = match fp_sqrt(&curve_rhs(&x1)) {
+        Ok(y) => (x1, y),
+        Err(_) => {
+            // g(Z·u^2·x1) = Z^3·u^6·g(x1) is a square when g(x1) is not
+            let x2 = z_u2.fp_mul(&x1);
+            (x2, fp_sqrt(&curve_rhs(&x2)).expect("g(x2) is a square"))
+        }
+    };
+    if sgn0(u) != sgn0(&y) {
+        y = y.fp_neg();
+    }
+    Point {
+        x,
+        y,
+        z: SM2_MODP_MONT_ONE,
+    }
+}
+
+/// `hash_to_curve` of the `SM2_XMD:SM3_SSWU_RO_` suite under the tag `dst`
+pub fn hash_to_curve(msg: &[u8], dst: &[u8]) -> Sm2Result<Point> {
+    let u = hash_to_field(msg, dst, 2)?;
+    Ok(map_to_curve(&u[0]).point_add(&map_to_curve(&u[1])))
+}
+
+#[cfg(test)]
+mod test_hash_to_curve {
+    use super::*;
+
+    const DST: &[u8] = b"QUUX-V01-CS02-with-SM2_XMD:SM3_SSWU_RO_";
+
+    fn affine_hex(p: &Point) -> String {
+        hex::encode(&p.to_byte_be(false)[1..])
+    }
+
+    // from an independent Python implementation over hashlib's SM3
+    #[test]
+    fn test_hash_to_curve_vectors() {
+        assert_eq!(
+            "5d46ec95ee9916de5b6825b53541f913b65128c47a824a3bfbc01d0924f5dbe1",
+            hex::encode(expand_message_xmd(b"abc", DST, 32).unwrap())
+        );
+        assert_eq!(
+            "80048bf6454de460598966bc3bc9a3213e8776668817d85cf447eda370991a41\
+             cf41fd9fa681d1416ddb5129e570bef4d74c4e0c1a5be8009717eb1c02e8e9e9",
+            affine_hex(&hash_to_curve(b"", DST).unwrap())
+        );
+        assert_eq!(
+            "7cf8871dffcb584997d9b27cbc1b12308eec4544f38688f7b8c53531afb9fdcd\
+             e803123cc855d859d58857cbea53c0cf0187b160e3a4996a9260879a1b059203",
+            affine_hex(&hash_to_curve(b"abc", DST).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_hash_to_curve_on_curve() {
+        for i in 0..64u32 {
+            let p = hash_to_curve(&i.to_be_bytes(), DST).unwrap();
+            assert!(p.is_valid() && !p.is_zero());
+            let u = hash_to_field(&i.to_be_bytes(), DST, 1).unwrap();
+            assert!(map_to_curve(&u[0]).is_valid());
+        }
+        assert_ne!(
+            affine_hex(&hash_to_curve(b"abc", DST).unwrap()),
+            affine_hex(&hash_to_curve(b"abc", b"another tag").unwrap())
+        );
+        assert!(expand_message_xmd(b"abc", DST, 256 * 32).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! Low level SM2 curve, field and scalar operations (enable the `hazmat`
+//! feature), for prototyping protocols on top of the curve.
+//!
+//! Nothing here checks what a protocol has to check: points may be the
+//! infinity or off the curve when decoded with
+//! [`point_from_bytes_unchecked`], scalars may be zero, and no operation
+//! records audit events or guards against nonce reuse. They are constant
+//! time only as far as the internals are. Misused, these functions leak keys;
+//! prefer the protocols of this crate wherever one fits.
+//!
+//! Scalars are integers mod n and field elements integers mod p, both as
+//! [`U256`] little-endian limbs in plain (not Montgomery) form.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::hazmat::{base_mul, point_add, point_mul, scalar_add, scalar_random};
+//!
+//! let (a, b) = (scalar_random(), scalar_random());
+//! let sum = point_add(&base_mul(&a), &base_mul(&b));
+//! assert_eq!(sum, base_mul(&scalar_add(&a, &b)));
+//! assert_eq!(point_mul(&base_mul(&a), &b), point_mul(&base_mul(&b), &a));
+//! ```
+use rand::{CryptoRng, RngCore};
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_inv, fn_mul, fn_random, fn_random_with, fn_sub};
+use crate::fields::fp64::{fp_from_bytes, fp_from_mont, fp_sqrt, fp_to_mont, SM2_P};
+use crate::fields::FieldModOperation;
+pub use crate::p256_ecc::Point;
+use crate::p256_ecc::g_mul;
+use crate::u256::{u256_cmp, u256_from_be_bytes, u256_to_be_bytes, U256};
+
+/// A canonical scalar from 32 big-endian bytes
+pub fn scalar_from_bytes(b: &[u8]) -> Sm2Result<U256> {
+    fn_from_bytes(b)
+}
+
+pub fn scalar_to_bytes(a: &U256) -> [u8; 32] {
+    u256_to_be_bytes(a).try_into().unwrap()
+}
+
+/// Uniform in [1, n-1]
+pub fn scalar_random() -> U256 {
+    fn_random()
+}
+
+/// Uniform in [1, n-1] from `rng`, without modulo bias
+pub fn scalar_random_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> U256 {
+    fn_random_with(rng)
+}
+
+pub fn scalar_add(a: &U256, b: &U256) -> U256 {
+    fn_add(a, b)
+}
+
+pub fn scalar_sub(a: &U256, b: &U256) -> U256 {
+    fn_sub(a, b)
+}
+
+pub fn scalar_neg(a: &U256) -> U256 {
+    fn_sub(&[0; 4], a)
+}
+
+pub fn scalar_mul(a: &U256, b: &U256) -> U256 {
+    fn_mul(a, b)
+}
+
+/// `a^-1 mod n`, [`Sm2Error::ZeroDivisor`] for zero
+pub fn scalar_inv(a: &U256) -> Sm2Result<U256> {
+    if a.is_zero() {
+        return Err(Sm2Error::ZeroDivisor);
+    }
+    Ok(fn_inv(a))
+}
+
+/// A canonical field element from 32 big-endian bytes
+pub fn fe_from_bytes(b: &[u8]) -> Sm2Result<U256> {
+    fp_from_bytes(b).map(|a| fp_from_mont(&a))
+}
+
+pub fn fe_to_bytes(a: &U256) -> [u8; 32] {
+    u256_to_be_bytes(a).try_into().unwrap()
+}
+
+pub fn fe_add(a: &U256, b: &U256) -> U256 {
+    a.fp_add(b)
+}
+
+pub fn fe_sub(a: &U256, b: &U256) -> U256 {
+    a.fp_sub(b)
+}
+
+pub fn fe_neg(a: &U256) -> U256 {
+    a.fp_neg()
+}
+
+pub fn fe_mul(a: &U256, b: &U256) -> U256 {
+    fp_from_mont(&fp_to_mont(a).fp_mul(&fp_to_mont(b)))
+}
+
+/// `a^-1 mod p`, [`Sm2Error::ZeroDivisor`] for zero
+pub fn fe_inv(a: &U256) -> Sm2Result<U256> {
+    if a.is_zero() {
+        return Err(Sm2Error::ZeroDivisor);
+    }
+    Ok(fp_from_mont(&fp_to_mont(a).fp_inv()))
+}
+
+/// Uniform in [1, p-1] from `rng`, by rejection sampling
+pub fn fe_random_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> U256 {
+    let mut buf = [0u8; 32];
+    loop {
+        rng.fill_bytes(&mut buf);
+        let a = u256_from_be_bytes(&buf);
+        if u256_cmp(&a, &SM2_P) < 0 && !a.is_zero() {
+            return a;
+        }
+    }
+}
+
+/// A square root of `a`, [`Sm2Error::FieldSqrtError`] for a non-square
+pub fn fe_sqrt(a: &U256) -> Sm2Result<U256> {
+    fp_sqrt(&fp_to_mont(a)).map(|r| fp_from_mont(&r))
+}
+
+/// Decode and check that the point is on the curve
+pub fn point_from_bytes(b: &[u8]) -> Sm2Result<Point> {
+    let p = Point::from_byte(b)?;
+    p.check()?;
+    Ok(p)
+}
+
+/// Decode without the curve check, for experiments with invalid points
+pub fn point_from_bytes_unchecked(b: &[u8]) -> Sm2Result<Point> {
+    Point::from_byte(b)
+}
+
+/// The SEC1 encoding, `0x00` for the infinity
+pub fn point_to_bytes(p: &Point, compress: bool) -> Vec<u8> {
+    if p.is_zero() {
+        return vec![0];
+    }
+    p.to_affine_point().to_byte_be(compress)
+}
+
+/// The affine `(x, y)`, `None` for the infinity
+pub fn point_coordinates(p: &Point) -> Option<(U256, U256)> {
+    if p.is_zero() {
+        return None;
+    }
+    let p = p.to_affine_point();
+    Some((fp_from_mont(&p.x), fp_from_mont(&p.y)))
+}
+
+pub fn point_add(a: &Point, b: &Point) -> Point {
+    a.point_add(b)
+}
+
+pub fn point_double(a: &Point) -> Point {
+    a.point_dbl()
+}
+
+pub fn point_neg(a: &Point) -> Point {
+    a.neg()
+}
+
+/// `[k]P`
+pub fn point_mul(p: &Point, k: &U256) -> Point {
+    p.scalar_mul(k)
+}
+
+/// `[k]G`
+pub fn base_mul(k: &U256) -> Point {
+    g_mul(k)
+}
+
+#[cfg(test)]
+mod test_hazmat {
+    use super::*;
+    use crate::fields::fn64::SM2_N;
+    use crate::fields::fp64::{SM2_G_X, SM2_G_Y, SM2_MODP_MONT_B};
+
+    #[test]
+    fn test_scalars() {
+        let (a, b) = (scalar_random(), scalar_random());
+        assert_eq!(a, scalar_from_bytes(&scalar_to_bytes(&a)).unwrap());
+        assert_eq!([1, 0, 0, 0], scalar_mul(&a, &scalar_inv(&a).unwrap()));
+        assert_eq!(a, scalar_add(&scalar_sub(&a, &b), &b));
+        assert_eq!([0; 4], scalar_add(&a, &scalar_neg(&a)));
+        assert_eq!(Err(Sm2Error::ZeroDivisor), scalar_inv(&[0; 4]));
+        assert!(scalar_from_bytes(&u256_to_be_bytes(&SM2_N)).is_err());
+    }
+
+    #[test]
+    fn test_field() {
+        let (x, y) = (SM2_G_X, SM2_G_Y);
+        assert_eq!(x, fe_from_bytes(&fe_to_bytes(&x)).unwrap());
+        assert_eq!([1, 0, 0, 0], fe_mul(&x, &fe_inv(&x).unwrap()));
+        assert_eq!([6, 0, 0, 0], fe_mul(&[2, 0, 0, 0], &[3, 0, 0, 0]));
+        assert_eq!(x, fe_add(&fe_sub(&x, &y), &y));
+        assert_eq!([0; 4], fe_add(&x, &fe_neg(&x)));
+        let r = fe_sqrt(&fe_mul(&y, &y)).unwrap();
+        assert!(r == y || r == fe_neg(&y));
+        // y^2 = x^3 - 3x + b
+        let b = fe_sub(&fe_mul(&y, &y), &fe_sub(&fe_mul(&fe_mul(&x, &x), &x), &fe_mul(&[3, 0, 0, 0], &x)));
+        assert_eq!(fp_from_mont(&SM2_MODP_MONT_B), b);
+        // -1 is not a square as p = 3 mod 4
+        assert_eq!(Err(Sm2Error::FieldSqrtError), fe_sqrt(&fe_neg(&[1, 0, 0, 0])));
+        assert_eq!(Err(Sm2Error::ZeroDivisor), fe_inv(&[0; 4]));
+        let r = fe_random_with(&mut rand::thread_rng());
+        assert_eq!(r, fe_from_bytes(&fe_to_bytes(&r)).unwrap());
+    }
+
+    #[test]
+    fn test_points() {
+        let g = base_mul(&[1, 0, 0, 0]);
+        assert_eq!(Some((SM2_G_X, SM2_G_Y)), point_coordinates(&g));
+        assert_eq!(point_double(&g), point_add(&g, &g));
+        assert_eq!(point_double(&g), base_mul(&[2, 0, 0, 0]));
+        assert!(point_add(&g, &point_neg(&g)).is_zero());
+        assert_eq!(None, point_coordinates(&point_mul(&g, &[0; 4])));
+        assert_eq!(vec![0], point_to_bytes(&Point::zero(), true));
+
+        let k = scalar_random();
+        let p = point_mul(&g, &k);
+        for compress in [true, false] {
+            assert_eq!(p, point_from_bytes(&point_to_bytes(&p, compress)).unwrap());
+        }
+        // (x, y + 1) is off the curve, only the unchecked decoding takes it
+        let (x, y) = point_coordinates(&p).unwrap();
+        let bad = [&[4][..], &fe_to_bytes(&x), &fe_to_bytes(&fe_add(&y, &[1, 0, 0, 0]))].concat();
+        assert!(point_from_bytes(&bad).is_err());
+        assert!(point_from_bytes_unchecked(&bad).is_ok());
+    }
+}
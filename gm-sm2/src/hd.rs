@@ -0,0 +1,418 @@
+//! Hierarchical deterministic SM2 keys, BIP32 on the SM2 curve with HMAC-SM3.
+//!
+//! One seed gives a tree of keys. Each extended key is a key and a 32-byte
+//! chain code, child `i` of it is derived from:
+//!
+//! * `I = HMAC-SM3(c, data || 0x01) || HMAC-SM3(c, data || 0x02)`, 64 bytes
+//!   as SM3 only gives 32, split into `I_L` and the child chain code `I_R`
+//! * `data = 0x00 || d || i` for a hardened `i`, at least 2^31, which only
+//!   the private key can derive
+//! * `data = P || i`, `P` compressed, for a normal `i`, which the public key
+//!   derives too: the child is `d + I_L` and `P + [I_L]G`
+//!
+//! The master key comes from `HMAC-SM3("SM2 seed", ...)` the same way. Where
+//! BIP32 uses HASH160, a key is identified by the first 4 bytes of its
+//! [`Sm2PublicKey::fingerprint`]. A child that would not be a valid SM2 key,
+//! `I_L >= n` or `d + I_L` equal to 0 or n-1, fails with
+//! [`Sm2Error::InvalidDerivation`] and the caller moves on to the next
+//! index, with probability about 2^-127. None of this is compatible with
+//! secp256k1 wallets.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::hd::{DerivationPath, ExtendedPrivateKey};
+//!
+//! let master = ExtendedPrivateKey::from_seed(&[7u8; 32]).unwrap();
+//! let account = master.derive_path(&"m/44'/0'/0'".parse().unwrap()).unwrap();
+//! let path: DerivationPath = "m/0/5".parse().unwrap();
+//! // the public key of the account derives the same non-hardened children
+//! let child = account.derive_path(&path).unwrap();
+//! assert_eq!(child.public_key().public_key(), account.public_key().derive_path(&path).unwrap().public_key());
+//! ```
+use std::fmt;
+use std::str::FromStr;
+
+use gm_sm3::hmac::sm3_hmac;
+use pkcs8::der::zeroize::Zeroize;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, SM2_N};
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::g_mul;
+use crate::u256::{u256_cmp, u256_from_be_bytes, u256_to_be_bytes, U256};
+
+const MASTER_KEY: &[u8] = b"SM2 seed";
+
+/// Indices from this one on are hardened
+pub const HARDENED: u32 = 1 << 31;
+
+/// `depth || parent fingerprint || child number || chain code || key`
+pub const EXTENDED_KEY_LEN: usize = 1 + 4 + 4 + 32 + 33;
+
+/// A path such as `m/44'/0'/0/1`, `'` or `h` marking a hardened index
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Child numbers, hardened ones with [`HARDENED`] set
+    pub fn as_slice(&self) -> &[u32] {
+        &self.0
+    }
+
+    pub fn child(&self, i: u32) -> DerivationPath {
+        let mut path = self.0.clone();
+        path.push(i);
+        DerivationPath(path)
+    }
+}
+
+impl From<Vec<u32>> for DerivationPath {
+    fn from(path: Vec<u32>) -> Self {
+        DerivationPath(path)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Sm2Error;
+
+    fn from_str(s: &str) -> Sm2Result<Self> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(Sm2Error::InvalidDerivation);
+        }
+        parts
+            .map(|part| {
+                let (digits, hardened) = match part.strip_suffix(['\'', 'h']) {
+                    Some(digits) => (digits, HARDENED),
+                    None => (part, 0),
+                };
+                match digits.parse::<u32>() {
+                    Ok(i) if i < HARDENED && !digits.starts_with('+') => Ok(i | hardened),
+                    _ => Err(Sm2Error::InvalidDerivation),
+                }
+            })
+            .collect::<Sm2Result<Vec<u32>>>()
+            .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for &i in &self.0 {
+            if i >= HARDENED {
+                write!(f, "/{}'", i - HARDENED)?;
+            } else {
+                write!(f, "/{}", i)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The position of an extended key in its tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Node {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+}
+
+impl Node {
+    const MASTER: Node = Node {
+        depth: 0,
+        parent_fingerprint: [0; 4],
+        child_number: 0,
+    };
+
+    fn child(&self, parent: &Sm2PublicKey, i: u32) -> Sm2Result<Node> {
+        Ok(Node {
+            depth: self.depth.checked_add(1).ok_or(Sm2Error::InvalidDerivation)?,
+            parent_fingerprint: key_id(parent),
+            child_number: i,
+        })
+    }
+
+    fn to_bytes(self, key: &[u8], chain_code: &[u8; 32]) -> Vec<u8> {
+        let mut out = vec![self.depth];
+        out.extend_from_slice(&self.parent_fingerprint);
+        out.extend_from_slice(&self.child_number.to_be_bytes());
+        out.extend_from_slice(chain_code);
+        out.extend_from_slice(key);
+        out
+    }
+
+    fn from_bytes(b: &[u8]) -> Sm2Result<(Node, [u8; 32], &[u8])> {
+        if b.len() != EXTENDED_KEY_LEN {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let node = Node {
+            depth: b[0],
+            parent_fingerprint: b[1..5].try_into().unwrap(),
+            child_number: u32::from_be_bytes(b[5..9].try_into().unwrap()),
+        };
+        // a master key has no parent
+        if node.depth == 0 && (node.parent_fingerprint != [0; 4] || node.child_number != 0) {
+            return Err(Sm2Error::InvalidDerivation);
+        }
+        Ok((node, b[9..41].try_into().unwrap(), &b[41..]))
+    }
+}
+
+/// The first 4 bytes of the key's fingerprint
+fn key_id(pk: &Sm2PublicKey) -> [u8; 4] {
+    pk.fingerprint().0[..4].try_into().unwrap()
+}
+
+/// `I_L` as a scalar below n, and `I_R`
+fn hmac64(key: &[u8], data: &[u8]) -> Sm2Result<(U256, [u8; 32])> {
+    let mut il = sm3_hmac(key, &[data, &[1]].concat());
+    let ir = sm3_hmac(key, &[data, &[2]].concat());
+    let tweak = u256_from_be_bytes(&il);
+    il.zeroize();
+    if u256_cmp(&tweak, &SM2_N) >= 0 {
+        return Err(Sm2Error::InvalidDerivation);
+    }
+    Ok((tweak, ir))
+}
+
+/// An SM2 private key with its chain code
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExtendedPrivateKey {
+    node: Node,
+    chain_code: [u8; 32],
+    key: Sm2PrivateKey,
+}
+
+/// Never prints the key or the chain code.
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExtendedPrivateKey(depth {}, child {}, …)", self.node.depth, self.node.child_number)
+    }
+}
+
+impl Drop for ExtendedPrivateKey {
+    fn drop(&mut self) {
+        self.chain_code.zeroize();
+    }
+}
+
+impl ExtendedPrivateKey {
+    /// The master key of a seed of 16 to 64 bytes
+    pub fn from_seed(seed: &[u8]) -> Sm2Result<ExtendedPrivateKey> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let (d, chain_code) = hmac64(MASTER_KEY, seed)?;
+        Ok(ExtendedPrivateKey {
+            node: Node::MASTER,
+            chain_code,
+            key: private_key(&d)?,
+        })
+    }
+
+    pub fn private_key(&self) -> &Sm2PrivateKey {
+        &self.key
+    }
+
+    pub fn public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            node: self.node,
+            chain_code: self.chain_code,
+            key: self.key.public_key,
+        }
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.node.depth
+    }
+
+    pub fn child_number(&self) -> u32 {
+        self.node.child_number
+    }
+
+    pub fn derive_child(&self, i: u32) -> Sm2Result<ExtendedPrivateKey> {
+        let mut data = if i >= HARDENED {
+            [&[0][..], &self.key.to_be_bytes()].concat()
+        } else {
+            self.key.public_key.to_bytes(true)
+        };
+        data.extend_from_slice(&i.to_be_bytes());
+        let result = hmac64(&self.chain_code, &data);
+        data.zeroize();
+        let (mut tweak, chain_code) = result?;
+        let mut d = fn_add(&tweak, &self.key.d);
+        tweak.zeroize();
+        let key = private_key(&d);
+        d.zeroize();
+        Ok(ExtendedPrivateKey {
+            node: self.node.child(&self.key.public_key, i)?,
+            chain_code,
+            key: key?,
+        })
+    }
+
+    pub fn derive_path(&self, path: &DerivationPath) -> Sm2Result<ExtendedPrivateKey> {
+        path.0.iter().try_fold(self.clone(), |key, &i| key.derive_child(i))
+    }
+
+    /// The 74-byte encoding, the key as `0x00 || d`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.node.to_bytes(&[&[0][..], &self.key.to_be_bytes()].concat(), &self.chain_code)
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<ExtendedPrivateKey> {
+        let (node, chain_code, key) = Node::from_bytes(b)?;
+        if key[0] != 0 {
+            return Err(Sm2Error::InvalidSecretKey);
+        }
+        Ok(ExtendedPrivateKey {
+            node,
+            chain_code,
+            key: Sm2PrivateKey::new(&key[1..])?,
+        })
+    }
+}
+
+/// A valid SM2 key, `d` in [1, n-2]
+fn private_key(d: &U256) -> Sm2Result<Sm2PrivateKey> {
+    let mut bytes = u256_to_be_bytes(d);
+    let key = Sm2PrivateKey::new(&bytes).map_err(|_| Sm2Error::InvalidDerivation);
+    bytes.zeroize();
+    key
+}
+
+/// An SM2 public key with its chain code, which derives the public keys of
+/// the non-hardened children
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    node: Node,
+    chain_code: [u8; 32],
+    key: Sm2PublicKey,
+}
+
+impl ExtendedPublicKey {
+    pub fn public_key(&self) -> Sm2PublicKey {
+        self.key
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.node.depth
+    }
+
+    pub fn child_number(&self) -> u32 {
+        self.node.child_number
+    }
+
+    /// A hardened `i` fails with [`Sm2Error::InvalidDerivation`].
+    pub fn derive_child(&self, i: u32) -> Sm2Result<ExtendedPublicKey> {
+        if i >= HARDENED {
+            return Err(Sm2Error::InvalidDerivation);
+        }
+        let mut data = self.key.to_bytes(true);
+        data.extend_from_slice(&i.to_be_bytes());
+        let (tweak, chain_code) = hmac64(&self.chain_code, &data)?;
+        let point = g_mul(&tweak).point_add(&self.key.point);
+        // the private child would be 0 or n-1
+        if point.is_zero() || point.point_add(&g_mul(&[1, 0, 0, 0])).is_zero() {
+            return Err(Sm2Error::InvalidDerivation);
+        }
+        Ok(ExtendedPublicKey {
+            node: self.node.child(&self.key, i)?,
+            chain_code,
+            key: Sm2PublicKey {
+                point: point.to_affine_point(),
+            },
+        })
+    }
+
+    pub fn derive_path(&self, path: &DerivationPath) -> Sm2Result<ExtendedPublicKey> {
+        path.0.iter().try_fold(self.clone(), |key, &i| key.derive_child(i))
+    }
+
+    /// The 74-byte encoding, the key compressed
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.node.to_bytes(&self.key.to_bytes(true), &self.chain_code)
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<ExtendedPublicKey> {
+        let (node, chain_code, key) = Node::from_bytes(b)?;
+        Ok(ExtendedPublicKey {
+            node,
+            chain_code,
+            key: Sm2PublicKey::new(key)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_hd {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        let path: DerivationPath = "m/44'/0h/7/2147483647".parse().unwrap();
+        assert_eq!(&[HARDENED + 44, HARDENED, 7, HARDENED - 1], path.as_slice());
+        assert_eq!("m/44'/0'/7/2147483647", path.to_string());
+        assert_eq!(DerivationPath::default(), "m".parse().unwrap());
+        assert_eq!(path.child(3).as_slice()[4], 3);
+        for bad in ["", "44/0", "m/", "m/2147483648", "m/-1", "m/+1", "m/1''", "M/1"] {
+            assert_eq!(Err(Sm2Error::InvalidDerivation), bad.parse::<DerivationPath>(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_derive() {
+        let seed: Vec<u8> = (0..32).collect();
+        let master = ExtendedPrivateKey::from_seed(&seed).unwrap();
+        assert_eq!(master, ExtendedPrivateKey::from_seed(&seed).unwrap());
+        assert_ne!(master, ExtendedPrivateKey::from_seed(&seed[1..]).unwrap());
+        assert_eq!(Err(Sm2Error::InvalidLength), ExtendedPrivateKey::from_seed(&[0; 15]).map(|_| ()));
+        assert_eq!(Err(Sm2Error::InvalidLength), ExtendedPrivateKey::from_seed(&[0; 65]).map(|_| ()));
+
+        let path: DerivationPath = "m/1'/2/3'/4".parse().unwrap();
+        let child = master.derive_path(&path).unwrap();
+        let stepwise = master
+            .derive_child(HARDENED + 1)
+            .and_then(|k| k.derive_child(2))
+            .and_then(|k| k.derive_child(HARDENED + 3))
+            .and_then(|k| k.derive_child(4))
+            .unwrap();
+        assert_eq!(child, stepwise);
+        assert_eq!((4, 4), (child.depth(), child.child_number()));
+        assert_ne!(master.derive_child(1).unwrap(), master.derive_child(HARDENED + 1).unwrap());
+
+        // the children sign for their own public keys
+        let sig = child.private_key().sign(None, b"m").unwrap();
+        child.public_key().public_key().verify(None, b"m", &sig).unwrap();
+
+        // public derivation agrees for normal indices only
+        let xpub = master.derive_child(HARDENED + 1).unwrap().public_key();
+        let normal: DerivationPath = "m/0/1/2".parse().unwrap();
+        assert_eq!(
+            master.derive_child(HARDENED + 1).unwrap().derive_path(&normal).unwrap().public_key(),
+            xpub.derive_path(&normal).unwrap()
+        );
+        assert_eq!(Err(Sm2Error::InvalidDerivation), xpub.derive_child(HARDENED).map(|_| ()));
+    }
+
+    #[test]
+    fn test_encoding() {
+        let master = ExtendedPrivateKey::from_seed(&[1; 16]).unwrap();
+        let child = master.derive_path(&"m/0'/9".parse().unwrap()).unwrap();
+        let bytes = child.to_bytes();
+        assert_eq!(EXTENDED_KEY_LEN, bytes.len());
+        assert_eq!(&key_id(&master.derive_child(HARDENED).unwrap().public_key().public_key()), &bytes[1..5]);
+        assert_eq!(child, ExtendedPrivateKey::from_bytes(&bytes).unwrap());
+        let xpub = child.public_key();
+        assert_eq!(xpub, ExtendedPublicKey::from_bytes(&xpub.to_bytes()).unwrap());
+        assert!(ExtendedPublicKey::from_bytes(&bytes).is_err());
+        assert!(ExtendedPrivateKey::from_bytes(&xpub.to_bytes()).is_err());
+
+        // a master key with a parent is malformed
+        let mut bad = master.to_bytes();
+        bad[1] = 1;
+        assert_eq!(Err(Sm2Error::InvalidDerivation), ExtendedPrivateKey::from_bytes(&bad).map(|_| ()));
+        assert_eq!("ExtendedPrivateKey(depth 2, child 9, …)", format!("{:?}", child));
+    }
+}
@@ -0,0 +1,183 @@
+//! JWS compact serialization and JWT with the `SM2` algorithm (enable the `jwt` feature).
+//!
+//! The signature is SM2-with-SM3 over `BASE64URL(header) || '.' || BASE64URL(payload)`
+//! using the default user ID, encoded as the 64-byte `r || s` value the same
+//! way ES256 encodes ECDSA signatures. Keys are represented as EC JWKs with
+//! `"crv": "SM2"`.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gm_core::encoding::{base64url_decode, base64url_encode};
+use serde_json::{json, Map, Value};
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+
+pub const JWS_ALG_SM2: &str = "SM2";
+pub const JWK_CRV_SM2: &str = "SM2";
+
+/// Sign `payload`, `header` may carry extra members such as `kid` or `typ`,
+/// `alg` is always set to `SM2`.
+pub fn sign_compact(sk: &Sm2PrivateKey, header: Option<&Map<String, Value>>, payload: &[u8]) -> Sm2Result<String> {
+    let mut h = header.cloned().unwrap_or_default();
+    h.insert("alg".to_string(), Value::String(JWS_ALG_SM2.to_string()));
+    let header = serde_json::to_vec(&h).map_err(|_| Sm2Error::InvalidToken)?;
+    let signing_input = format!("{}.{}", base64url_encode(&header), base64url_encode(payload));
+    let sig = sk.sign(None, signing_input.as_bytes())?;
+    Ok(format!("{}.{}", signing_input, base64url_encode(&sig)))
+}
+
+/// Verify a compact JWS and return `(header, payload)`.
+pub fn verify_compact(pk: &Sm2PublicKey, token: &str) -> Sm2Result<(Map<String, Value>, Vec<u8>)> {
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 {
+        return Err(Sm2Error::InvalidToken);
+    }
+    let header = base64url_decode(parts[0]).map_err(|_| Sm2Error::InvalidToken)?;
+    let header: Map<String, Value> =
+        serde_json::from_slice(&header).map_err(|_| Sm2Error::InvalidToken)?;
+    if header.get("alg").and_then(Value::as_str) != Some(JWS_ALG_SM2) {
+        return Err(Sm2Error::InvalidToken);
+    }
+    let sig = base64url_decode(parts[2]).map_err(|_| Sm2Error::InvalidToken)?;
+    if sig.len() != 64 {
+        return Err(Sm2Error::InvalidToken);
+    }
+    let signing_input = &token[..parts[0].len() + 1 + parts[1].len()];
+    pk.verify(None, signing_input.as_bytes(), &sig)?;
+    let payload = base64url_decode(parts[1]).map_err(|_| Sm2Error::InvalidToken)?;
+    Ok((header, payload))
+}
+
+/// Issue a JWT (`typ: JWT`) with the given claims.
+pub fn encode_jwt(sk: &Sm2PrivateKey, claims: &Value) -> Sm2Result<String> {
+    let mut header = Map::new();
+    header.insert("typ".to_string(), Value::String("JWT".to_string()));
+    let payload = serde_json::to_vec(claims).map_err(|_| Sm2Error::InvalidToken)?;
+    sign_compact(sk, Some(&header), &payload)
+}
+
+/// Verify a JWT signature and the `exp` / `nbf` claims when present.
+pub fn decode_jwt(pk: &Sm2PublicKey, token: &str) -> Sm2Result<Value> {
+    let (_, payload) = verify_compact(pk, token)?;
+    let claims: Value = serde_json::from_slice(&payload).map_err(|_| Sm2Error::InvalidToken)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Some(exp) = claims.get("exp").and_then(Value::as_u64) {
+        if now >= exp {
+            return Err(Sm2Error::InvalidToken);
+        }
+    }
+    if let Some(nbf) = claims.get("nbf").and_then(Value::as_u64) {
+        if now < nbf {
+            return Err(Sm2Error::InvalidToken);
+        }
+    }
+    Ok(claims)
+}
+
+impl Sm2PublicKey {
+    pub fn to_jwk(&self) -> Value {
+        let bytes = self.to_bytes(false);
+        json!({
+            "kty": "EC",
+            "crv": JWK_CRV_SM2,
+            "x": base64url_encode(&bytes[1..33]),
+            "y": base64url_encode(&bytes[33..65]),
+        })
+    }
+
+    pub fn from_jwk(jwk: &Value) -> Sm2Result<Self> {
+        if jwk.get("kty").and_then(Value::as_str) != Some("EC")
+            || jwk.get("crv").and_then(Value::as_str) != Some(JWK_CRV_SM2)
+        {
+            return Err(Sm2Error::InvalidToken);
+        }
+        let x = jwk_coordinate(jwk, "x")?;
+        let y = jwk_coordinate(jwk, "y")?;
+        let mut bytes = vec![0x04];
+        bytes.extend_from_slice(&x);
+        bytes.extend_from_slice(&y);
+        Sm2PublicKey::new(&bytes)
+    }
+}
+
+impl Sm2PrivateKey {
+    pub fn to_jwk(&self) -> Value {
+        let mut jwk = self.public_key.to_jwk();
+        jwk["d"] = Value::String(base64url_encode(&self.to_bytes_be()));
+        jwk
+    }
+
+    /// Import a private JWK, the public coordinates must match `d`.
+    pub fn from_jwk(jwk: &Value) -> Sm2Result<Self> {
+        let pk = Sm2PublicKey::from_jwk(jwk)?;
+        let sk = Sm2PrivateKey::new(&jwk_coordinate(jwk, "d")?)?;
+        if sk.public_key.to_bytes(false) != pk.to_bytes(false) {
+            return Err(Sm2Error::InvalidPrivate);
+        }
+        Ok(sk)
+    }
+}
+
+fn jwk_coordinate(jwk: &Value, name: &str) -> Sm2Result<Vec<u8>> {
+    let v = jwk
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or(Sm2Error::InvalidToken)?;
+    let bytes = base64url_decode(v).map_err(|_| Sm2Error::InvalidToken)?;
+    if bytes.len() != 32 {
+        return Err(Sm2Error::InvalidToken);
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test_jws {
+    use serde_json::json;
+
+    use crate::error::Sm2Error;
+    use crate::jws::{decode_jwt, encode_jwt, sign_compact, verify_compact};
+    use crate::key::{gen_keypair, Sm2PrivateKey, Sm2PublicKey};
+
+    #[test]
+    fn test_jws_compact() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let token = sign_compact(&sk, None, b"hello jws").unwrap();
+        println!("{}", token);
+        let (header, payload) = verify_compact(&pk, &token).unwrap();
+        assert_eq!("SM2", header["alg"]);
+        assert_eq!(b"hello jws".to_vec(), payload);
+
+        let mut tampered = token.clone();
+        tampered.insert(token.find('.').unwrap() + 1, 'A');
+        assert!(verify_compact(&pk, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_jwt() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let token = encode_jwt(&sk, &json!({"sub": "alice", "exp": 4102444800u64})).unwrap();
+        let claims = decode_jwt(&pk, &token).unwrap();
+        assert_eq!("alice", claims["sub"]);
+
+        let expired = encode_jwt(&sk, &json!({"sub": "alice", "exp": 1000})).unwrap();
+        assert!(decode_jwt(&pk, &expired) == Err(Sm2Error::InvalidToken));
+    }
+
+    #[test]
+    fn test_jwk() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let jwk = sk.to_jwk();
+        println!("{}", jwk);
+        let sk2 = Sm2PrivateKey::from_jwk(&jwk).unwrap();
+        assert!(sk == sk2);
+        let pk2 = Sm2PublicKey::from_jwk(&pk.to_jwk()).unwrap();
+        assert_eq!(pk.to_bytes(false), pk2.to_bytes(false));
+
+        let mut bad = jwk.clone();
+        bad["crv"] = json!("P-256");
+        assert!(Sm2PublicKey::from_jwk(&bad).is_err());
+    }
+}
@@ -1,27 +1,193 @@
-use hex::{FromHexError, ToHex};
 use num_bigint::BigUint;
 
+use gm_core::audit::{observe, Operation};
+use gm_core::encoding::{hex_decode, hex_encode};
+use gm_core::group::FixedBase;
+use gm_core::{ct_eq, Decryptor, Encryptor, Fingerprint, Signer, Verifier};
 use gm_sm3::sm3_hash;
+use gm_sm4::kw::Sm4Kw;
+use rand::{CryptoRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use crate::error::{Sm2Error, Sm2Result};
 use crate::fields::FieldModOperation;
-use crate::fields::fn64::{fn_add, fn_mul, fn_pow, fn_sub, SM2_N, SM2_N_MINUS_TWO};
-use crate::fields::fp64::{fp_from_mont, random_u256};
+use crate::fields::fn64::{fn_add, fn_mul, fn_pow, fn_random, fn_random_with, fn_sub, SM2_N, SM2_N_MINUS_TWO};
+use crate::fields::fp64::fp_from_mont;
+use crate::nonce::check_fresh;
 use crate::p256_ecc::{g_mul, Point};
+use crate::signature::{Sm2Signature, VerifyOptions};
 use crate::u256::{SM2_ONE, U256, u256_add, u256_cmp, u256_from_be_bytes};
 use crate::util::{compute_za, DEFAULT_ID, kdf, xor_bytes};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Sm2Model {
     C1C2C3,
     C1C3C2,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A raw ciphertext together with its C1 form and component order, so neither
+/// can be given wrongly on decryption. The length and the C1 prefix byte are
+/// checked on construction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sm2Ciphertext {
+    bytes: Vec<u8>,
+    compressed: bool,
+    model: Sm2Model,
+}
+
+impl Sm2Ciphertext {
+    pub fn new(bytes: Vec<u8>, compressed: bool, model: Sm2Model) -> Sm2Result<Sm2Ciphertext> {
+        split_cipher(&bytes, compressed, model)?;
+        let prefix_ok = match bytes[0] {
+            0x04 => !compressed,
+            0x02 | 0x03 => compressed,
+            _ => false,
+        };
+        if !prefix_ok {
+            return Err(Sm2Error::InvalidPoint);
+        }
+        Ok(Sm2Ciphertext { bytes, compressed, model })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub fn model(&self) -> Sm2Model {
+        self.model
+    }
+}
+
+/// C1C3C2, the order of GM/T 0003-2012, with the C1 form read from its first
+/// byte
+impl TryFrom<&[u8]> for Sm2Ciphertext {
+    type Error = Sm2Error;
+
+    fn try_from(bytes: &[u8]) -> Sm2Result<Self> {
+        let compressed = matches!(bytes.first(), Some(0x02 | 0x03));
+        Sm2Ciphertext::new(bytes.to_vec(), compressed, Sm2Model::C1C3C2)
+    }
+}
+
+impl AsRef<[u8]> for Sm2Ciphertext {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl From<Sm2Ciphertext> for Vec<u8> {
+    fn from(c: Sm2Ciphertext) -> Self {
+        c.bytes
+    }
+}
+
+const fn c1_len(compressed: bool) -> usize {
+    if compressed {
+        33
+    } else {
+        65
+    }
+}
+
+/// `(C1, C3, C2)` of a raw ciphertext, C2 is at least one byte
+pub(crate) fn split_cipher(cipher: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<(&[u8], &[u8], &[u8])> {
+    if Sm2PrivateKey::plaintext_len(cipher.len(), compressed).is_none() {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let (c1, rest) = cipher.split_at(c1_len(compressed));
+    Ok(match model {
+        Sm2Model::C1C2C3 => {
+            let (c2, c3) = rest.split_at(rest.len() - 32);
+            (c1, c3, c2)
+        }
+        Sm2Model::C1C3C2 => {
+            let (c3, c2) = rest.split_at(32);
+            (c1, c3, c2)
+        }
+    })
+}
+
+/// An INTEGER coordinate as 32 bytes. Some encoders drop the leading zero
+/// byte DER needs when the top bit is set, the 32-byte negative reading of
+/// such a value is taken as unsigned.
+fn read_coordinate(reader: yasna::BERReader) -> yasna::ASN1Result<[u8; 32]> {
+    let (bytes, nonnegative) = reader.read_bigint_bytes()?;
+    let bytes = match bytes.iter().position(|&b| b != 0) {
+        Some(i) if nonnegative => &bytes[i..],
+        None => &[][..],
+        Some(_) if bytes.len() == 32 => &bytes[..],
+        Some(_) => return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid)),
+    };
+    if bytes.len() > 32 {
+        return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+    }
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(bytes);
+    Ok(out)
+}
+
+/// A raw ciphertext as the ASN.1 `SM2Cipher` of GM/T 0009, the form OpenSSL
+/// reads and writes: `SEQUENCE { x INTEGER, y INTEGER, hash OCTET STRING, ciphertext OCTET STRING }`.
+pub fn cipher_to_asn1(cipher: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+    let (c1, c3, c2) = split_cipher(cipher, compressed, model)?;
+    let c1 = Point::from_byte(c1)?.to_byte_be(false);
+    let x = BigUint::from_bytes_be(&c1[1..33]);
+    let y = BigUint::from_bytes_be(&c1[33..]);
+    Ok(yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer.next().write_biguint(&x);
+            writer.next().write_biguint(&y);
+            writer.next().write_bytes(c3);
+            writer.next().write_bytes(c2);
+        });
+    }))
+}
+
+/// The raw ciphertext of an ASN.1 `SM2Cipher`, see [`cipher_to_asn1`].
+pub fn cipher_from_asn1(der: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+    let (x, y, c3, c2) = yasna::parse_der(der, |reader| {
+        reader.read_sequence(|reader| {
+            let x = read_coordinate(reader.next())?;
+            let y = read_coordinate(reader.next())?;
+            let c3 = reader.next().read_bytes()?;
+            let c2 = reader.next().read_bytes()?;
+            Ok((x, y, c3, c2))
+        })
+    })
+    .map_err(|_| Sm2Error::InvalidDer)?;
+    if c3.len() != 32 || c2.is_empty() {
+        return Err(Sm2Error::InvalidDer);
+    }
+    let c1 = Point::from_byte(&[&[0x04][..], &x, &y].concat())?;
+    // y is dropped when compressing, it has to be checked here
+    if !c1.is_valid() {
+        return Err(Sm2Error::InvalidPublic);
+    }
+    let c1 = c1.to_byte_be(compressed);
+    Ok(match model {
+        Sm2Model::C1C2C3 => [c1, c2, c3].concat(),
+        Sm2Model::C1C3C2 => [c1, c3, c2].concat(),
+    })
+}
+
+/// Compared, hashed and ordered as the point, so by its uncompressed encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Sm2PublicKey {
     pub point: Point,
 }
 
 impl Sm2PublicKey {
+    /// The length of a raw ciphertext of `plain_len` bytes:
+    /// C1 (33 or 65 bytes) + C3 (32 bytes) + C2 (`plain_len` bytes)
+    pub const fn ciphertext_len(plain_len: usize, compressed: bool) -> usize {
+        c1_len(compressed) + 32 + plain_len
+    }
+
     pub fn to_bytes(&self, compress: bool) -> Vec<u8> {
         self.point.to_byte_be(compress)
     }
@@ -39,6 +205,22 @@ impl Sm2PublicKey {
         self.point.is_valid()
     }
 
+    /// The affine x, big-endian and zero-padded to 32 bytes
+    pub fn x_bytes(&self) -> [u8; 32] {
+        self.to_bytes(false)[1..33].try_into().unwrap()
+    }
+
+    /// The affine y, big-endian and zero-padded to 32 bytes
+    pub fn y_bytes(&self) -> [u8; 32] {
+        self.to_bytes(false)[33..].try_into().unwrap()
+    }
+
+    /// SM3 of the 65-byte uncompressed point, the same whichever form the key
+    /// was parsed from.
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint(sm3_hash(&self.to_bytes(false)))
+    }
+
     /// Encrypt the given message and return ASN.1 data
     pub fn encrypt_asn1(
         &self,
@@ -46,72 +228,111 @@ impl Sm2PublicKey {
         compressed: bool,
         model: Sm2Model,
     ) -> Sm2Result<Vec<u8>> {
-        let cipher = self.encrypt(msg, compressed, model).unwrap();
-        let x = BigUint::from_bytes_be(&cipher[0..32]);
-        let y = BigUint::from_bytes_be(&cipher[32..64]);
-        let sm3 = &cipher[64..96];
-        let secret = &cipher[96..];
-        Ok(yasna::construct_der(|writer| {
-            writer.write_sequence(|writer| {
-                writer.next().write_biguint(&x);
-                writer.next().write_biguint(&y);
-                writer.next().write_bytes(&sm3);
-                writer.next().write_bytes(&secret);
-            });
-        }))
+        cipher_to_asn1(&self.encrypt(msg, compressed, model)?, compressed, model)
     }
 
     /// Encrypt the given message.
     pub fn encrypt(&self, msg: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
-        loop {
-            let klen = msg.len();
-            let k = random_u256();
-            let c1_p = g_mul(&k);
-            let c1_p = c1_p.to_affine_point(); // 根据加密算法，z坐标会被丢弃，为保证解密还原回来的坐标在曲线上，则必须转换坐标系到 affine 坐标系
-
-            let s_p = self.point.scalar_mul(&SM2_ONE);
-            if s_p.is_zero() {
-                return Err(Sm2Error::ZeroPoint);
+        self.encrypt_with_aad(msg, &[], compressed, model)
+    }
+
+    /// [`Sm2PublicKey::encrypt`] returning a ciphertext that carries its layout
+    pub fn encrypt_ciphertext(&self, msg: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Sm2Ciphertext> {
+        let bytes = self.encrypt(msg, compressed, model)?;
+        Ok(Sm2Ciphertext { bytes, compressed, model })
+    }
+
+    /// Encrypt and bind `aad`, e.g. the recipient ID or a sequence number, into
+    /// `C3 = SM3(x2 || M || AAD || y2)`. The ciphertext does not carry `aad`,
+    /// it only decrypts with [`Sm2PrivateKey::decrypt_with_aad`] and the same
+    /// `aad`. An empty `aad` gives a standard SM2 ciphertext.
+    pub fn encrypt_with_aad(&self, msg: &[u8], aad: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        if self.point.is_zero() {
+            return Err(Sm2Error::ZeroPoint);
+        }
+        observe("SM2", Operation::Encrypt, || loop {
+            let k = fn_random();
+            let kp = self.point.scalar_mul(&k);
+            if let Some(c) = Self::encrypt_with(msg, aad, compressed, model, &k, &kp)? {
+                return Ok(c);
             }
+        })
+    }
 
-            let c2_p = self.point.scalar_mul(&k).to_affine_point();
-            let x2_bytes = fp_from_mont(&c2_p.x).to_byte_be();
-            let y2_bytes = fp_from_mont(&c2_p.y).to_byte_be();
-            let mut c2_append = vec![];
-            c2_append.extend_from_slice(&x2_bytes);
-            c2_append.extend_from_slice(&y2_bytes);
-
-            let t = kdf(&c2_append[..], klen);
-            let mut flag = true;
-            for elem in &t {
-                if elem != &0 {
-                    flag = false;
-                    break;
-                }
+    /// Encrypt with a caller supplied ephemeral `k`, for checking known-answer
+    /// test vectors only. Reusing `k` reveals the plaintexts.
+    #[cfg(feature = "kat")]
+    pub fn encrypt_with_k(
+        &self,
+        msg: &[u8],
+        compressed: bool,
+        model: Sm2Model,
+        k: &U256,
+    ) -> Sm2Result<Vec<u8>> {
+        if k.is_zero() || u256_cmp(k, &SM2_N) >= 0 {
+            return Err(Sm2Error::InvalidSecretKey);
+        }
+        Self::encrypt_with(msg, &[], compressed, model, k, &self.point.scalar_mul(k))?
+            .ok_or(Sm2Error::KdfHashError)
+    }
+
+    /// `kp` is `[k]PB`. Returns `None` when the KDF output is all zero and
+    /// another `k` must be chosen.
+    fn encrypt_with(
+        msg: &[u8],
+        aad: &[u8],
+        compressed: bool,
+        model: Sm2Model,
+        k: &U256,
+        kp: &Point,
+    ) -> Sm2Result<Option<Vec<u8>>> {
+        // the KDF output of an empty message would be empty, and so all zero
+        if msg.is_empty() {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let klen = msg.len();
+        let c1_p = g_mul(k);
+        let c1_p = c1_p.to_affine_point(); // 根据加密算法，z坐标会被丢弃，为保证解密还原回来的坐标在曲线上，则必须转换坐标系到 affine 坐标系
+
+        let c2_p = kp.to_affine_point();
+        let x2_bytes = fp_from_mont(&c2_p.x).to_byte_be();
+        let y2_bytes = fp_from_mont(&c2_p.y).to_byte_be();
+        let mut c2_append = vec![];
+        c2_append.extend_from_slice(&x2_bytes);
+        c2_append.extend_from_slice(&y2_bytes);
+
+        let t = kdf(&c2_append[..], klen)?;
+        let mut flag = true;
+        for elem in &t {
+            if elem != &0 {
+                flag = false;
+                break;
             }
-            if !flag {
-                let c2 = xor_bytes(msg, &t[..]);
-                let mut c3_append: Vec<u8> = vec![];
-                c3_append.extend_from_slice(&x2_bytes);
-                c3_append.extend_from_slice(msg);
-                c3_append.extend_from_slice(&y2_bytes);
-                let c3 = sm3_hash(&c3_append);
-                let mut c: Vec<u8> = vec![];
-                match model {
-                    Sm2Model::C1C2C3 => {
-                        c.extend_from_slice(&c1_p.to_byte_be(compressed));
-                        c.extend_from_slice(&c2);
-                        c.extend_from_slice(&c3);
-                    }
-                    Sm2Model::C1C3C2 => {
-                        c.extend_from_slice(&c1_p.to_byte_be(compressed));
-                        c.extend_from_slice(&c3);
-                        c.extend_from_slice(&c2);
-                    }
+        }
+        if !flag {
+            let c2 = xor_bytes(msg, &t[..]);
+            let mut c3_append: Vec<u8> = vec![];
+            c3_append.extend_from_slice(&x2_bytes);
+            c3_append.extend_from_slice(msg);
+            c3_append.extend_from_slice(aad);
+            c3_append.extend_from_slice(&y2_bytes);
+            let c3 = sm3_hash(&c3_append);
+            let mut c: Vec<u8> = vec![];
+            match model {
+                Sm2Model::C1C2C3 => {
+                    c.extend_from_slice(&c1_p.to_byte_be(compressed));
+                    c.extend_from_slice(&c2);
+                    c.extend_from_slice(&c3);
+                }
+                Sm2Model::C1C3C2 => {
+                    c.extend_from_slice(&c1_p.to_byte_be(compressed));
+                    c.extend_from_slice(&c3);
+                    c.extend_from_slice(&c2);
                 }
-                return Ok(c);
             }
+            return Ok(Some(c));
         }
+        Ok(None)
     }
 
     pub fn verify(&self, id: Option<&'static str>, msg: &[u8], sig: &[u8]) -> Sm2Result<()> {
@@ -121,19 +342,40 @@ impl Sm2PublicKey {
         self.verify_raw(&digest[..], &self.point, sig)
     }
 
-    fn verify_raw(&self, digest: &[u8], pk: &Point, sig: &[u8]) -> Sm2Result<()> {
+    /// [`Sm2PublicKey::verify`] of a 64-byte `r || s` decoded under `opts`
+    pub fn verify_with_options(
+        &self,
+        id: Option<&'static str>,
+        msg: &[u8],
+        sig: &[u8],
+        opts: &VerifyOptions,
+    ) -> Sm2Result<()> {
+        let sig = Sm2Signature::from_slice_with(sig, opts)?;
+        self.verify(id, msg, &sig.to_vec())
+    }
+
+    /// [`Sm2PublicKey::verify`] of a DER signature decoded under `opts`
+    pub fn verify_der(&self, id: Option<&'static str>, msg: &[u8], der: &[u8], opts: &VerifyOptions) -> Sm2Result<()> {
+        let sig = Sm2Signature::from_der_with(der, opts)?;
+        self.verify(id, msg, &sig.to_vec())
+    }
+
+    /// Verify a signature made by [`Sm2PrivateKey::sign_with_context`].
+    pub fn verify_with_context(&self, id: Option<&'static str>, ctx: &[u8], msg: &[u8], sig: &[u8]) -> Sm2Result<()> {
+        let digest = context_digest(id, ctx, &self.point, msg)?;
+        self.verify_raw(&digest, &self.point, sig)
+    }
+
+    pub(crate) fn verify_raw(&self, digest: &[u8], pk: &Point, sig: &[u8]) -> Sm2Result<()> {
+        observe("SM2", Operation::Verify, || self.verify_digest(digest, pk, sig))
+    }
+
+    fn verify_digest(&self, digest: &[u8], pk: &Point, sig: &[u8]) -> Sm2Result<()> {
         if digest.len() != 32 {
             return Err(Sm2Error::InvalidDigestLen);
         }
-        let n = &SM2_N;
-        let r = &u256_from_be_bytes(&sig[..32]);
-        let s = &u256_from_be_bytes(&sig[32..]);
-        if r.is_zero() || s.is_zero() {
-            return Err(Sm2Error::ZeroSig);
-        }
-        if u256_cmp(r, n) >= 0 || u256_cmp(s, n) >= 0 {
-            return Err(Sm2Error::InvalidDigest);
-        }
+        // checks the length of sig and the range of r and s
+        let Sm2Signature { r, s } = Sm2Signature::from_slice(sig)?;
         let t = fn_add(&s, &r);
         if t.is_zero() {
             return Err(Sm2Error::InvalidDigest);
@@ -142,28 +384,23 @@ impl Sm2PublicKey {
         let t_p = pk.scalar_mul(&t);
         let p = s_g.point_add(&t_p).to_affine_point();
         let x1 = u256_from_be_bytes(&fp_from_mont(&p.x).to_byte_be());
-        let e = u256_from_be_bytes(&digest);
+        let e = u256_from_be_bytes(digest);
         let r1 = fn_add(&x1, &e);
-        return if u256_cmp(r, &r1) == 0 {
+        if u256_cmp(&r, &r1) == 0 {
             Ok(())
         } else {
             Err(Sm2Error::InvalidDigest)
-        };
+        }
     }
 
     pub fn to_hex_string(&self, compressed: bool) -> String {
-        let bytes = self.to_bytes(compressed);
-        bytes.encode_hex::<String>()
+        hex_encode(&self.to_bytes(compressed))
     }
 
-    pub fn from_hex_string(hex_str: &str) -> Result<Self, FromHexError> {
-        let bytes = hex::decode(hex_str);
-        match bytes {
-            Ok(b) => Ok(Self {
-                point: Point::from_byte(b.as_slice()).unwrap(),
-            }),
-            Err(e) => Err(e),
-        }
+    /// Strict hex of either point form, see [`gm_core::encoding::hex_decode`].
+    pub fn from_hex_string(hex_str: &str) -> Sm2Result<Self> {
+        let bytes = hex_decode(hex_str).map_err(|_| Sm2Error::InvalidToken)?;
+        Self::new(&bytes)
     }
 
     pub fn value(&self) -> &Point {
@@ -171,12 +408,59 @@ impl Sm2PublicKey {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Encrypts many messages to one recipient, see [`Sm2PublicKey::encrypt`].
+///
+/// The recipient key is checked once, and `[k]PB` comes from a table of
+/// multiples of `PB` built by [`Sm2Encryptor::new`], about 80 KiB. Every
+/// message still has its own random `k`.
+#[derive(Clone)]
+pub struct Sm2Encryptor {
+    public_key: Sm2PublicKey,
+    table: FixedBase<Point>,
+}
+
+impl Sm2Encryptor {
+    pub fn new(public_key: &Sm2PublicKey) -> Sm2Result<Sm2Encryptor> {
+        public_key.point.check()?;
+        Ok(Sm2Encryptor {
+            public_key: *public_key,
+            table: FixedBase::new(&public_key.point, 4),
+        })
+    }
+
+    pub fn public_key(&self) -> &Sm2PublicKey {
+        &self.public_key
+    }
+
+    pub fn encrypt(&self, msg: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        self.encrypt_with_aad(msg, &[], compressed, model)
+    }
+
+    /// See [`Sm2PublicKey::encrypt_with_aad`].
+    pub fn encrypt_with_aad(&self, msg: &[u8], aad: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        observe("SM2", Operation::Encrypt, || loop {
+            let k = fn_random();
+            let kp = self.table.mul(&k);
+            if let Some(c) = Sm2PublicKey::encrypt_with(msg, aad, compressed, model, &k, &kp)? {
+                return Ok(c);
+            }
+        })
+    }
+}
+
+#[derive(Clone)]
 pub struct Sm2PrivateKey {
-    pub d: U256,
+    pub(crate) d: U256,
     pub public_key: Sm2PublicKey,
 }
 
+/// Never prints d, use [`Sm2PrivateKey::expose_secret`] to get it.
+impl std::fmt::Debug for Sm2PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sm2PrivateKey(…)")
+    }
+}
+
 impl Eq for Sm2PrivateKey {}
 
 impl PartialEq<Self> for Sm2PrivateKey {
@@ -193,18 +477,68 @@ impl AsRef<Sm2PublicKey> for Sm2PrivateKey {
 }
 
 impl Sm2PrivateKey {
+    /// The plaintext length of a raw ciphertext of `cipher_len` bytes, `None`
+    /// when it is too short to decrypt
+    pub const fn plaintext_len(cipher_len: usize, compressed: bool) -> Option<usize> {
+        match cipher_len.checked_sub(c1_len(compressed) + 32) {
+            Some(0) | None => None,
+            len => len,
+        }
+    }
+
+    /// Import d from its 32-byte big-endian form, d must be in [1, n-2].
     pub fn new(sk: &[u8]) -> Sm2Result<Self> {
+        if sk.len() != 32 {
+            return Err(Sm2Error::InvalidFieldLen);
+        }
         let d = u256_from_be_bytes(sk);
+        check_private(&d)?;
         let public_key = public_from_private(&d)?;
         let private_key = Self { d, public_key };
         Ok(private_key)
     }
 
+    pub fn try_from_biguint(d: &BigUint) -> Sm2Result<Self> {
+        if d.bits() > 256 {
+            return Err(Sm2Error::PrivateKeyOutOfRange);
+        }
+        let bytes = d.to_bytes_be();
+        let mut buf = [0u8; 32];
+        buf[32 - bytes.len()..].copy_from_slice(&bytes);
+        Self::new(&buf)
+    }
+
     #[inline]
     pub fn to_bytes_be(&self) -> Vec<u8> {
         self.d.to_byte_be()
     }
 
+    /// d, big-endian and zero-padded to 32 bytes, as [`Sm2PrivateKey::new`] reads it
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        self.d.to_byte_be().try_into().unwrap()
+    }
+
+    /// Wrap d under a 16-byte SM4 key-encryption key (RFC 3394 key wrap),
+    /// the result is 40 bytes.
+    pub fn wrap(&self, kek: &[u8]) -> Sm2Result<Vec<u8>> {
+        let kw = Sm4Kw::new(kek).map_err(|_| Sm2Error::InvalidSecretKey)?;
+        kw.wrap_key(&self.to_bytes_be())
+            .map_err(|_| Sm2Error::InvalidSecretKey)
+    }
+
+    pub fn unwrap_from(kek: &[u8], wrapped: &[u8]) -> Sm2Result<Self> {
+        let kw = Sm4Kw::new(kek).map_err(|_| Sm2Error::InvalidSecretKey)?;
+        let d = kw
+            .unwrap_key(wrapped)
+            .map_err(|_| Sm2Error::InvalidSecretKey)?;
+        Self::new(&d)
+    }
+
+    /// The private scalar d, keep it out of logs.
+    pub fn expose_secret(&self) -> &U256 {
+        &self.d
+    }
+
     /// Sign the given digest.
     pub fn sign(&self, id: Option<&'static str>, msg: &[u8]) -> Sm2Result<Vec<u8>> {
         let id = id.unwrap_or_else(|| DEFAULT_ID);
@@ -213,34 +547,58 @@ impl Sm2PrivateKey {
         self.sign_raw(&digest[..], &self.d)
     }
 
-    fn sign_raw(&self, digest: &[u8], sk: &U256) -> Sm2Result<Vec<u8>> {
+    /// Sign with a context string of at most 255 bytes, e.g. a protocol name,
+    /// so that a signature made for one protocol never verifies in another
+    /// using the same key. The context is bound into the Z value,
+    /// `ZA' = SM3(ZA || "SM2ctx" || len(ctx) || ctx)`, and the digest is
+    /// `SM3(ZA' || M)`. No message signed with plain [`sign`](Self::sign) has the
+    /// same digest, even for an empty context.
+    pub fn sign_with_context(&self, id: Option<&'static str>, ctx: &[u8], msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        let digest = context_digest(id, ctx, &self.public_key.point, msg)?;
+        self.sign_raw(&digest, &self.d)
+    }
+
+    pub(crate) fn sign_raw(&self, digest: &[u8], sk: &U256) -> Sm2Result<Vec<u8>> {
+        observe("SM2", Operation::Sign, || self.sign_digest(digest, sk))
+    }
+
+    fn sign_digest(&self, digest: &[u8], sk: &U256) -> Sm2Result<Vec<u8>> {
         if digest.len() != 32 {
             return Err(Sm2Error::InvalidDigestLen);
         }
         let e = u256_from_be_bytes(&digest);
-        let n = &SM2_N;
-        let s1 = fn_pow(&u256_add(&SM2_ONE, &sk).0, &SM2_N_MINUS_TWO);
         loop {
-            let k = random_u256();
-            let p_x = g_mul(&k).to_affine_point();
-            let x1 = u256_from_be_bytes(&fp_from_mont(&p_x.x).to_byte_be());
-            let r = fn_add(&e, &x1);
-            if r.is_zero() || u256_add(&r, &k).0 == *n {
-                continue;
-            }
-            let s2_1 = fn_mul(&r, &sk);
-            let s2 = fn_sub(&k, &s2_1);
-            let s = fn_mul(&s1, &s2);
-            if s.is_zero() {
-                continue;
+            let k = fn_random();
+            check_fresh(&k);
+            if let Some(sig) = Self::sign_with_k(&e, sk, &k) {
+                return Ok(sig);
             }
-            let mut sig: Vec<u8> = vec![];
-            sig.extend_from_slice(&r.to_byte_be());
-            sig.extend_from_slice(&s.to_byte_be());
-            return Ok(sig);
         }
     }
 
+    /// `r || s` for the nonce `k` in [1, n-1], `None` when `k` has to be
+    /// drawn again. Never call it twice with the same `k`, see
+    /// [`crate::nonce`].
+    pub(crate) fn sign_with_k(e: &U256, sk: &U256, k: &U256) -> Option<Vec<u8>> {
+        let s1 = fn_pow(&u256_add(&SM2_ONE, sk).0, &SM2_N_MINUS_TWO);
+        let p_x = g_mul(k).to_affine_point();
+        let x1 = u256_from_be_bytes(&fp_from_mont(&p_x.x).to_byte_be());
+        let r = fn_add(e, &x1);
+        if r.is_zero() || u256_add(&r, k).0 == SM2_N {
+            return None;
+        }
+        let s2_1 = fn_mul(&r, sk);
+        let s2 = fn_sub(k, &s2_1);
+        let s = fn_mul(&s1, &s2);
+        if s.is_zero() {
+            return None;
+        }
+        let mut sig: Vec<u8> = vec![];
+        sig.extend_from_slice(&r.to_byte_be());
+        sig.extend_from_slice(&s.to_byte_be());
+        Some(sig)
+    }
+
     /// Decrypt the given ASN.1 message.
     pub fn decrypt_asn1(
         &self,
@@ -248,23 +606,7 @@ impl Sm2PrivateKey {
         compressed: bool,
         model: Sm2Model,
     ) -> Sm2Result<Vec<u8>> {
-        let (x, y, sm3, secret) = yasna::parse_der(ciphertext, |reader| {
-            reader.read_sequence(|reader| {
-                let x = reader.next().read_biguint()?;
-                let y = reader.next().read_biguint()?;
-                let sm3 = reader.next().read_bytes()?;
-                let secret = reader.next().read_bytes()?;
-                return Ok((x, y, sm3, secret));
-            })
-        })
-        .unwrap();
-        let x = BigUint::to_bytes_be(&x);
-        let y = BigUint::to_bytes_be(&y);
-        let mut cipher: Vec<u8> = vec![];
-        cipher.extend_from_slice(&x);
-        cipher.extend_from_slice(&y);
-        cipher.extend_from_slice(&sm3);
-        cipher.extend_from_slice(&secret);
+        let cipher = cipher_from_asn1(ciphertext, compressed, model)?;
         self.decrypt(&cipher, compressed, model)
     }
 
@@ -275,31 +617,33 @@ impl Sm2PrivateKey {
         compressed: bool,
         model: Sm2Model,
     ) -> Sm2Result<Vec<u8>> {
-        let c1_end_index = match compressed {
-            true => 33,
-            false => 65,
-        };
-        let c1_bytes = &ciphertext[0..c1_end_index];
-        let len = ciphertext.len();
-        let c2_bytes = match model {
-            Sm2Model::C1C2C3 => &ciphertext[c1_end_index..(len - 32)],
-            Sm2Model::C1C3C2 => &ciphertext[(c1_end_index + 32)..],
-        };
-        let c3_bytes = match model {
-            Sm2Model::C1C2C3 => &ciphertext[(len - 32)..],
-            Sm2Model::C1C3C2 => &ciphertext[c1_end_index..c1_end_index + 32],
-        };
+        self.decrypt_with_aad(ciphertext, &[], compressed, model)
+    }
+
+    /// Decrypt with the layout the ciphertext carries.
+    pub fn decrypt_ciphertext(&self, ciphertext: &Sm2Ciphertext) -> Sm2Result<Vec<u8>> {
+        self.decrypt(&ciphertext.bytes, ciphertext.compressed, ciphertext.model)
+    }
+
+    /// Decrypt a message from [`Sm2PublicKey::encrypt_with_aad`], failing with
+    /// [`Sm2Error::HashNotEqual`] unless `aad` is the one it was encrypted with.
+    pub fn decrypt_with_aad(
+        &self,
+        ciphertext: &[u8],
+        aad: &[u8],
+        compressed: bool,
+        model: Sm2Model,
+    ) -> Sm2Result<Vec<u8>> {
+        observe("SM2", Operation::Decrypt, || self.decrypt_with(ciphertext, aad, compressed, model))
+    }
+
+    fn decrypt_with(&self, ciphertext: &[u8], aad: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        let (c1_bytes, c3_bytes, c2_bytes) = split_cipher(ciphertext, compressed, model)?;
 
         let kelen = c2_bytes.len();
+        // B1: C1 must be on the curve, S = [h]C1 = C1 must not be the infinity point
         let c1_point = Point::from_byte(c1_bytes)?;
-        if !c1_point.to_affine_point().is_valid_affine_point() {
-            return Err(Sm2Error::CheckPointErr);
-        }
-
-        let s_point = c1_point.scalar_mul(&SM2_ONE);
-        if s_point.is_zero() {
-            return Err(Sm2Error::ZeroPoint);
-        }
+        c1_point.check()?;
 
         let c2_point = c1_point.scalar_mul(&self.d).to_affine_point();
         let x2_bytes = fp_from_mont(&c2_point.x).to_byte_be();
@@ -307,7 +651,7 @@ impl Sm2PrivateKey {
         let mut prepend: Vec<u8> = vec![];
         prepend.extend_from_slice(&x2_bytes);
         prepend.extend_from_slice(&y2_bytes);
-        let t = kdf(&prepend, kelen);
+        let t = kdf(&prepend, kelen)?;
         let mut flag = true;
         for elem in &t {
             if elem != &0 {
@@ -329,44 +673,192 @@ impl Sm2PrivateKey {
         let mut prepend: Vec<u8> = vec![];
         prepend.extend_from_slice(&x2_bytes);
         prepend.extend_from_slice(&mb);
+        prepend.extend_from_slice(aad);
         prepend.extend_from_slice(&y2_bytes);
         let u = sm3_hash(&prepend);
-        if u != c3_bytes {
+        if !ct_eq(&u, c3_bytes) {
             return Err(Sm2Error::HashNotEqual);
         }
         Ok(mb)
     }
 
     pub fn to_hex_string(&self) -> String {
-        let bytes = self.d.to_byte_be();
-        bytes.encode_hex::<String>()
-    }
-
-    pub fn from_hex_string(hex_str: &str) -> Result<Self, String> {
-        let bytes = hex::decode(hex_str);
-        match bytes {
-            Ok(b) => {
-                let r = Self::new(b.as_slice());
-                match r {
-                    Ok(sk) => Ok(sk),
-                    Err(e) => Err(e.to_string()),
-                }
-            }
-            Err(e) => Err(e.to_string()),
-        }
+        hex_encode(&self.d.to_byte_be())
+    }
+
+    pub fn from_hex_string(hex_str: &str) -> Sm2Result<Self> {
+        let bytes = hex_decode(hex_str).map_err(|_| Sm2Error::InvalidToken)?;
+        Self::new(&bytes)
     }
 
     pub fn to_public_key(&self) -> Sm2PublicKey {
-        self.public_key.clone()
+        self.public_key
+    }
+
+    /// Restrict the key to signing.
+    pub fn into_signing_key(self) -> Sm2SigningKey {
+        Sm2SigningKey(self)
+    }
+
+    /// Restrict the key to decryption.
+    pub fn into_decryption_key(self) -> Sm2DecryptionKey {
+        Sm2DecryptionKey(self)
+    }
+}
+
+/// A private key that can sign but not decrypt, e.g. a TLS signing key.
+///
+/// Going back to a [`Sm2PrivateKey`] takes an explicit
+/// [`into_private_key`](Self::into_private_key).
+///
+/// ```compile_fail
+/// use gm_sm2::key::{gen_keypair, Sm2Model};
+///
+/// let key = gen_keypair().unwrap().1.into_signing_key();
+/// key.decrypt(b"ciphertext", false, Sm2Model::C1C3C2);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Sm2SigningKey(Sm2PrivateKey);
+
+impl std::fmt::Debug for Sm2SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sm2SigningKey(…)")
+    }
+}
+
+impl AsRef<Sm2PublicKey> for Sm2SigningKey {
+    fn as_ref(&self) -> &Sm2PublicKey {
+        &self.0.public_key
+    }
+}
+
+impl Sm2SigningKey {
+    pub fn public_key(&self) -> &Sm2PublicKey {
+        &self.0.public_key
+    }
+
+    pub fn sign(&self, id: Option<&'static str>, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        self.0.sign(id, msg)
+    }
+
+    pub fn sign_with_context(&self, id: Option<&'static str>, ctx: &[u8], msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        self.0.sign_with_context(id, ctx, msg)
+    }
+
+    pub fn into_private_key(self) -> Sm2PrivateKey {
+        self.0
+    }
+}
+
+/// A private key that can decrypt but not sign.
+///
+/// Going back to a [`Sm2PrivateKey`] takes an explicit
+/// [`into_private_key`](Self::into_private_key).
+#[derive(Clone, PartialEq, Eq)]
+pub struct Sm2DecryptionKey(Sm2PrivateKey);
+
+impl std::fmt::Debug for Sm2DecryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sm2DecryptionKey(…)")
+    }
+}
+
+impl AsRef<Sm2PublicKey> for Sm2DecryptionKey {
+    fn as_ref(&self) -> &Sm2PublicKey {
+        &self.0.public_key
+    }
+}
+
+impl Sm2DecryptionKey {
+    pub fn public_key(&self) -> &Sm2PublicKey {
+        &self.0.public_key
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        self.0.decrypt(ciphertext, compressed, model)
+    }
+
+    pub fn decrypt_with_aad(
+        &self,
+        ciphertext: &[u8],
+        aad: &[u8],
+        compressed: bool,
+        model: Sm2Model,
+    ) -> Sm2Result<Vec<u8>> {
+        self.0.decrypt_with_aad(ciphertext, aad, compressed, model)
+    }
+
+    pub fn decrypt_asn1(&self, ciphertext: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        self.0.decrypt_asn1(ciphertext, compressed, model)
+    }
+
+    pub fn decrypt_ciphertext(&self, ciphertext: &Sm2Ciphertext) -> Sm2Result<Vec<u8>> {
+        self.0.decrypt_ciphertext(ciphertext)
+    }
+
+    pub fn into_private_key(self) -> Sm2PrivateKey {
+        self.0
     }
 }
 
 /// generate key pair
 pub fn gen_keypair() -> Sm2Result<(Sm2PublicKey, Sm2PrivateKey)> {
-    let d = random_u256();
-    let pk = public_from_private(&d)?;
-    let sk = Sm2PrivateKey { d, public_key: pk };
-    Ok((pk, sk))
+    gen_keypair_with_rng(&mut rand::thread_rng())
+}
+
+/// Generate a key pair from `rng`, `d` uniform in [1, n-2].
+pub fn gen_keypair_with_rng<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Sm2Result<(Sm2PublicKey, Sm2PrivateKey)> {
+    observe("SM2", Operation::KeyGen, || {
+        let mut d = fn_random_with(rng);
+        while check_private(&d).is_err() {
+            d = fn_random_with(rng);
+        }
+        let pk = public_from_private(&d)?;
+        let sk = Sm2PrivateKey { d, public_key: pk };
+        Ok((pk, sk))
+    })
+}
+
+/// Verify many `(public key, message, signature)` with the default user ID,
+/// the results are in input order. With the `parallel` feature they are
+/// checked on the rayon thread pool.
+pub fn verify_batch(items: &[(&Sm2PublicKey, &[u8], &[u8])]) -> Vec<Sm2Result<()>> {
+    #[cfg(feature = "parallel")]
+    let items = items.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let items = items.iter();
+    items
+        .map(|(pk, msg, sig)| {
+            if sig.len() != 64 {
+                return Err(Sm2Error::InvalidSignatureLen);
+            }
+            pk.verify(None, msg, sig)
+        })
+        .collect()
+}
+
+const CONTEXT_PREFIX: &[u8] = b"SM2ctx";
+
+pub(crate) fn context_digest(id: Option<&str>, ctx: &[u8], pk: &Point, msg: &[u8]) -> Sm2Result<[u8; 32]> {
+    if ctx.len() > 255 {
+        return Err(Sm2Error::ContextTooLong);
+    }
+    let za = compute_za(id.unwrap_or(DEFAULT_ID), pk)?;
+    // ZA' = SM3(ZA || "SM2ctx" || len(ctx) || ctx), so that SM3(ZA' || M) is
+    // not SM3(ZA || M') for any M'
+    let za_ctx = sm3_hash(&[&za[..], CONTEXT_PREFIX, &[ctx.len() as u8], ctx].concat());
+    Ok(sm3_hash(&[&za_ctx[..], msg].concat()))
+}
+
+/// d = n-1 is rejected too, signing inverts 1 + d.
+fn check_private(d: &U256) -> Sm2Result<()> {
+    if d.is_zero() {
+        return Err(Sm2Error::ZeroPrivateKey);
+    }
+    if u256_cmp(d, &SM2_N_MINUS_TWO) > 0 {
+        return Err(Sm2Error::PrivateKeyOutOfRange);
+    }
+    Ok(())
 }
 
 fn public_from_private(sk: &U256) -> Sm2Result<Sm2PublicKey> {
@@ -377,3 +869,69 @@ fn public_from_private(sk: &U256) -> Sm2Result<Sm2PublicKey> {
         Err(Sm2Error::InvalidPublic)
     }
 }
+
+/// Signs with the default user ID.
+impl Signer for Sm2PrivateKey {
+    type Signature = Sm2Signature;
+    type Error = Sm2Error;
+
+    fn sign(&self, msg: &[u8]) -> Sm2Result<Sm2Signature> {
+        Sm2Signature::from_slice(&Sm2PrivateKey::sign(self, None, msg)?)
+    }
+}
+
+/// Signs with the default user ID.
+impl Signer for Sm2SigningKey {
+    type Signature = Sm2Signature;
+    type Error = Sm2Error;
+
+    fn sign(&self, msg: &[u8]) -> Sm2Result<Sm2Signature> {
+        Signer::sign(&self.0, msg)
+    }
+}
+
+/// Verifies with the default user ID.
+impl Verifier for Sm2PublicKey {
+    type Signature = Sm2Signature;
+    type Error = Sm2Error;
+
+    fn verify(&self, msg: &[u8], sig: &Sm2Signature) -> Sm2Result<()> {
+        Sm2PublicKey::verify(self, None, msg, &sig.to_vec())
+    }
+}
+
+/// Uncompressed C1, C1C3C2 order (GB/T 32918.4-2016).
+impl Encryptor for Sm2PublicKey {
+    type Error = Sm2Error;
+
+    fn encrypt(&self, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        Sm2PublicKey::encrypt(self, msg, false, Sm2Model::C1C3C2)
+    }
+}
+
+/// Uncompressed C1, C1C3C2 order (GB/T 32918.4-2016).
+impl Encryptor for Sm2Encryptor {
+    type Error = Sm2Error;
+
+    fn encrypt(&self, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        Sm2Encryptor::encrypt(self, msg, false, Sm2Model::C1C3C2)
+    }
+}
+
+/// Uncompressed C1, C1C3C2 order (GB/T 32918.4-2016).
+impl Decryptor for Sm2PrivateKey {
+    type Error = Sm2Error;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Sm2Result<Vec<u8>> {
+        Sm2PrivateKey::decrypt(self, ciphertext, false, Sm2Model::C1C3C2)
+    }
+}
+
+/// Uncompressed C1, C1C3C2 order (GB/T 32918.4-2016).
+impl Decryptor for Sm2DecryptionKey {
+    type Error = Sm2Error;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Sm2Result<Vec<u8>> {
+        Decryptor::decrypt(&self.0, ciphertext)
+    }
+}
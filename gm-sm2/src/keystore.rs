@@ -0,0 +1,255 @@
+//! Private keys used by handle, in the style of PKCS#11 and GM/T 0018 devices.
+//!
+//! Code written against [`KeyStore`] never holds the private key, so the
+//! software stores here can later be swapped for an HSM backed one. A handle
+//! is derived from the public key, importing the same key twice gives the
+//! same handle.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::key::Sm2Model;
+//! use gm_sm2::keystore::{KeyStore, MemoryKeyStore};
+//!
+//! let mut store = MemoryKeyStore::default();
+//! let handle = store.generate().unwrap();
+//! let pk = store.public_key(handle).unwrap();
+//! let sig = store.sign(handle, None, b"hello").unwrap();
+//! pk.verify(None, b"hello", &sig).unwrap();
+//! let c = pk.encrypt(b"hello", false, Sm2Model::C1C3C2).unwrap();
+//! assert_eq!(b"hello".to_vec(), store.decrypt(handle, &c, false, Sm2Model::C1C3C2).unwrap());
+//! ```
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gm_sm4::kw::Sm4Kw;
+use pkcs8::der::zeroize::Zeroizing;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::{gen_keypair, Sm2Model, Sm2PrivateKey, Sm2PublicKey};
+
+/// Refers to a key in a [`KeyStore`], the first 8 bytes of the public key
+/// fingerprint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyHandle(pub u64);
+
+impl KeyHandle {
+    pub fn of(public_key: &Sm2PublicKey) -> KeyHandle {
+        let fp = public_key.fingerprint();
+        KeyHandle(u64::from_be_bytes(fp.as_bytes()[..8].try_into().unwrap()))
+    }
+}
+
+/// 16 lowercase hex digits
+impl Display for KeyHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// SM2 private keys that are only used through their handle.
+///
+/// A handle that is not in the store fails with [`Sm2Error::UnknownKey`].
+pub trait KeyStore {
+    fn import(&mut self, key: &Sm2PrivateKey) -> Sm2Result<KeyHandle>;
+
+    fn public_key(&self, handle: KeyHandle) -> Sm2Result<Sm2PublicKey>;
+
+    fn sign(&self, handle: KeyHandle, id: Option<&'static str>, msg: &[u8]) -> Sm2Result<Vec<u8>>;
+
+    fn decrypt(&self, handle: KeyHandle, ciphertext: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>>;
+
+    fn delete(&mut self, handle: KeyHandle) -> Sm2Result<()>;
+
+    /// The handles in the store, sorted
+    fn handles(&self) -> Sm2Result<Vec<KeyHandle>>;
+
+    /// Generate a key in the store, a device would never export it.
+    fn generate(&mut self) -> Sm2Result<KeyHandle> {
+        let (_, sk) = gen_keypair()?;
+        self.import(&sk)
+    }
+}
+
+/// Keys kept in process memory, lost when the store is dropped
+#[derive(Debug, Default)]
+pub struct MemoryKeyStore {
+    keys: HashMap<KeyHandle, Sm2PrivateKey>,
+}
+
+impl MemoryKeyStore {
+    fn key(&self, handle: KeyHandle) -> Sm2Result<&Sm2PrivateKey> {
+        self.keys.get(&handle).ok_or(Sm2Error::UnknownKey)
+    }
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn import(&mut self, key: &Sm2PrivateKey) -> Sm2Result<KeyHandle> {
+        let handle = KeyHandle::of(&key.public_key);
+        self.keys.insert(handle, key.clone());
+        Ok(handle)
+    }
+
+    fn public_key(&self, handle: KeyHandle) -> Sm2Result<Sm2PublicKey> {
+        Ok(self.key(handle)?.public_key)
+    }
+
+    fn sign(&self, handle: KeyHandle, id: Option<&'static str>, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        self.key(handle)?.sign(id, msg)
+    }
+
+    fn decrypt(&self, handle: KeyHandle, ciphertext: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        self.key(handle)?.decrypt(ciphertext, compressed, model)
+    }
+
+    fn delete(&mut self, handle: KeyHandle) -> Sm2Result<()> {
+        self.keys.remove(&handle).map(|_| ()).ok_or(Sm2Error::UnknownKey)
+    }
+
+    fn handles(&self) -> Sm2Result<Vec<KeyHandle>> {
+        let mut handles: Vec<KeyHandle> = self.keys.keys().copied().collect();
+        handles.sort();
+        Ok(handles)
+    }
+}
+
+/// Keys in a directory, one `<handle>.key` file each holding the key wrapped
+/// with SM4-KW under a 16-byte key encryption key, see [`Sm2PrivateKey::wrap`].
+///
+/// Every operation reads the file again, so several processes can share the
+/// directory.
+pub struct FileKeyStore {
+    dir: PathBuf,
+    kek: Zeroizing<Vec<u8>>,
+}
+
+/// Never prints the key encryption key.
+impl std::fmt::Debug for FileKeyStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FileKeyStore({:?})", self.dir)
+    }
+}
+
+impl FileKeyStore {
+    /// Open the store in `dir`, creating the directory if needed.
+    pub fn open<P: AsRef<Path>>(dir: P, kek: &[u8]) -> Sm2Result<FileKeyStore> {
+        Sm4Kw::new(kek).map_err(|_| Sm2Error::InvalidSecretKey)?;
+        fs::create_dir_all(dir.as_ref()).map_err(|_| Sm2Error::IoError)?;
+        Ok(FileKeyStore {
+            dir: dir.as_ref().to_path_buf(),
+            kek: Zeroizing::new(kek.to_vec()),
+        })
+    }
+
+    fn path(&self, handle: KeyHandle) -> PathBuf {
+        self.dir.join(format!("{}.key", handle))
+    }
+
+    /// Fails with [`Sm2Error::InvalidSecretKey`] for a file wrapped under
+    /// another key encryption key.
+    fn load(&self, handle: KeyHandle) -> Sm2Result<Sm2PrivateKey> {
+        let wrapped = match fs::read(self.path(handle)) {
+            Ok(b) => b,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(Sm2Error::UnknownKey),
+            Err(_) => return Err(Sm2Error::IoError),
+        };
+        Sm2PrivateKey::unwrap_from(&self.kek, &wrapped)
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn import(&mut self, key: &Sm2PrivateKey) -> Sm2Result<KeyHandle> {
+        let handle = KeyHandle::of(&key.public_key);
+        fs::write(self.path(handle), key.wrap(&self.kek)?).map_err(|_| Sm2Error::IoError)?;
+        Ok(handle)
+    }
+
+    fn public_key(&self, handle: KeyHandle) -> Sm2Result<Sm2PublicKey> {
+        Ok(self.load(handle)?.public_key)
+    }
+
+    fn sign(&self, handle: KeyHandle, id: Option<&'static str>, msg: &[u8]) -> Sm2Result<Vec<u8>> {
+        self.load(handle)?.sign(id, msg)
+    }
+
+    fn decrypt(&self, handle: KeyHandle, ciphertext: &[u8], compressed: bool, model: Sm2Model) -> Sm2Result<Vec<u8>> {
+        self.load(handle)?.decrypt(ciphertext, compressed, model)
+    }
+
+    fn delete(&mut self, handle: KeyHandle) -> Sm2Result<()> {
+        match fs::remove_file(self.path(handle)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(Sm2Error::UnknownKey),
+            Err(_) => Err(Sm2Error::IoError),
+        }
+    }
+
+    fn handles(&self) -> Sm2Result<Vec<KeyHandle>> {
+        let mut handles = vec![];
+        for entry in fs::read_dir(&self.dir).map_err(|_| Sm2Error::IoError)? {
+            let name = entry.map_err(|_| Sm2Error::IoError)?.file_name();
+            let handle = name
+                .to_str()
+                .and_then(|n| n.strip_suffix(".key"))
+                .filter(|h| h.len() == 16)
+                .and_then(|h| u64::from_str_radix(h, 16).ok());
+            if let Some(h) = handle {
+                handles.push(KeyHandle(h));
+            }
+        }
+        handles.sort();
+        Ok(handles)
+    }
+}
+
+#[cfg(test)]
+mod test_keystore {
+    use super::*;
+
+    fn exercise(store: &mut dyn KeyStore) {
+        let a = store.generate().unwrap();
+        let (pk, sk) = gen_keypair().unwrap();
+        let b = store.import(&sk).unwrap();
+        assert_eq!(b, store.import(&sk).unwrap());
+        assert_eq!(KeyHandle::of(&pk), b);
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(expected, store.handles().unwrap());
+
+        assert_eq!(pk, store.public_key(b).unwrap());
+        let sig = store.sign(b, None, b"msg").unwrap();
+        pk.verify(None, b"msg", &sig).unwrap();
+        let c = pk.encrypt(b"msg", true, Sm2Model::C1C2C3).unwrap();
+        assert_eq!(b"msg".to_vec(), store.decrypt(b, &c, true, Sm2Model::C1C2C3).unwrap());
+        assert!(store.decrypt(a, &c, true, Sm2Model::C1C2C3).is_err());
+
+        store.delete(b).unwrap();
+        assert_eq!(vec![a], store.handles().unwrap());
+        assert_eq!(Err(Sm2Error::UnknownKey), store.sign(b, None, b"msg"));
+        assert_eq!(Err(Sm2Error::UnknownKey), store.delete(b));
+    }
+
+    #[test]
+    fn test_memory_store() {
+        exercise(&mut MemoryKeyStore::default());
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = std::env::temp_dir().join(format!("gm-sm2-keystore-{}", std::process::id()));
+        let kek = [0x5au8; 16];
+        let mut store = FileKeyStore::open(&dir, &kek).unwrap();
+        exercise(&mut store);
+        assert!(!format!("{:?}", store).contains("5a"));
+
+        // another process sees the keys, a wrong KEK cannot use them
+        let handle = store.handles().unwrap()[0];
+        let reopened = FileKeyStore::open(&dir, &kek).unwrap();
+        assert_eq!(store.public_key(handle).unwrap(), reopened.public_key(handle).unwrap());
+        let wrong = FileKeyStore::open(&dir, &[0u8; 16]).unwrap();
+        assert_eq!(Err(Sm2Error::InvalidSecretKey), wrong.sign(handle, None, b"msg"));
+        assert!(FileKeyStore::open(&dir, &kek[..15]).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
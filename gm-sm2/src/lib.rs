@@ -3,13 +3,64 @@
 use pkcs8::ObjectIdentifier;
 use pkcs8::spki::AlgorithmIdentifier;
 
+use crate::error::Sm2Result;
+use crate::key::Sm2PublicKey;
+
+pub mod adaptor;
+pub mod address;
+pub mod armor;
+pub mod blind;
+#[cfg(feature = "cose")]
+pub mod cose;
+#[cfg(test)]
+mod corpus;
+pub mod detached;
+pub mod dkg;
+pub mod dvs;
+pub mod ecdh;
+pub mod ecies;
 pub mod error;
 pub mod exchange;
+#[cfg(feature = "field-bytes")]
+pub mod field_bytes;
+pub mod hash_to_curve;
+pub mod hd;
+#[cfg(feature = "hazmat")]
+pub mod hazmat;
+#[cfg(feature = "jwt")]
+pub mod jws;
 pub mod key;
+pub mod keystore;
+#[cfg(feature = "mnemonic")]
+pub mod mnemonic;
+#[cfg(feature = "experimental")]
+pub mod mta;
+pub mod musig;
+pub mod nonce;
 pub mod p256_ecc;
+#[cfg(feature = "experimental")]
+pub mod paillier;
 pub mod util;
+pub mod pedersen;
 pub mod pkcs;
+#[cfg(test)]
+mod point_vectors;
+#[cfg(test)]
+mod properties;
+pub mod recovery;
+pub mod ring;
+pub mod sdf;
+pub mod shamir;
+pub mod signature;
+pub mod ssh;
+#[cfg(feature = "experimental")]
+pub mod threshold;
+#[cfg(all(test, feature = "timing-tests"))]
+mod timing;
+pub mod tlcp;
 pub mod u256;
+pub mod vrf;
+pub mod x509;
 pub(crate) mod fields;
 pub(crate) mod sm2p256_table;
 
@@ -71,10 +122,33 @@ pub const OID_SM2_CMS_SIGNED_AND_ENVELOPED: ObjectIdentifier = ObjectIdentifier:
 pub const OID_SM2_CMS_ENCRYPTED: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.156.10197.6.1.4.2.5");
 pub const OID_SM2_CMS_KEY_AGREEMENT_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.156.10197.6.1.4.2.6");
 
+/// The user ID of GM/T 0009 used when none is given
+pub const SM2_DEFAULT_ID: &[u8] = b"1234567812345678";
+
+/// ZA = SM3(ENTL || ID || a || b || xG || yG || xA || yA) of GB/T 32918.2.
+///
+/// Signing hashes `ZA || M`, protocols such as TLCP that build the digest
+/// themselves need ZA of the peer key. The ID is at most 8191 bytes.
+///
+/// ```rust
+/// use gm_sm2::key::gen_keypair;
+/// use gm_sm2::{compute_za, SM2_DEFAULT_ID};
+///
+/// let (pk, _) = gen_keypair().unwrap();
+/// let za = compute_za(SM2_DEFAULT_ID, &pk).unwrap();
+/// assert_eq!(32, za.len());
+/// ```
+pub fn compute_za(id: &[u8], pk: &Sm2PublicKey) -> Sm2Result<[u8; 32]> {
+    util::za(id, &pk.point)
+}
+
 #[cfg(test)]
 mod test_sm2 {
+    use gm_core::{Decryptor, Encryptor, Signer, Verifier};
+
+    use crate::error::Sm2Error;
     use crate::exchange;
-    use crate::key::{gen_keypair, Sm2Model, Sm2PrivateKey, Sm2PublicKey};
+    use crate::key::{gen_keypair, Sm2Ciphertext, Sm2Model, Sm2PrivateKey, Sm2PublicKey};
 
     #[test]
     fn test_encrypt_decrypt_with_gen_key() {
@@ -88,6 +162,136 @@ mod test_sm2 {
         assert_eq!(msg, plain)
     }
 
+    #[test]
+    fn test_encrypt_with_aad() {
+        use crate::error::Sm2Error;
+
+        let (pk, sk) = gen_keypair().unwrap();
+        for model in [Sm2Model::C1C2C3, Sm2Model::C1C3C2] {
+            let c = pk.encrypt_with_aad(b"hello", b"to bob #1", true, model).unwrap();
+            assert_eq!(b"hello".to_vec(), sk.decrypt_with_aad(&c, b"to bob #1", true, model).unwrap());
+            assert!(sk.decrypt_with_aad(&c, b"to bob #2", true, model).err() == Some(Sm2Error::HashNotEqual));
+            assert!(sk.decrypt(&c, true, model).is_err());
+        }
+        // no AAD is plain SM2
+        let c = pk.encrypt_with_aad(b"hello", b"", false, Sm2Model::C1C3C2).unwrap();
+        assert_eq!(b"hello".to_vec(), sk.decrypt(&c, false, Sm2Model::C1C3C2).unwrap());
+    }
+
+    #[test]
+    fn test_encryptor() {
+        use crate::key::Sm2Encryptor;
+
+        let (pk, sk) = gen_keypair().unwrap();
+        let encryptor = Sm2Encryptor::new(&pk).unwrap();
+        let mut seen = std::collections::HashSet::new();
+        for i in 0..4u8 {
+            let msg = [i; 40];
+            let c = encryptor.encrypt(&msg, i % 2 == 0, Sm2Model::C1C2C3).unwrap();
+            assert_eq!(msg.to_vec(), sk.decrypt(&c, i % 2 == 0, Sm2Model::C1C2C3).unwrap());
+            // a fresh k for every message
+            assert!(seen.insert(c[..33].to_vec()));
+        }
+        let c = encryptor.encrypt_with_aad(b"hello", b"seq 7", false, Sm2Model::C1C3C2).unwrap();
+        assert_eq!(b"hello".to_vec(), sk.decrypt_with_aad(&c, b"seq 7", false, Sm2Model::C1C3C2).unwrap());
+        let c = Encryptor::encrypt(&encryptor, b"hello traits").unwrap();
+        assert_eq!(b"hello traits".to_vec(), Decryptor::decrypt(&sk, &c).unwrap());
+
+        let zero = Sm2PublicKey { point: crate::p256_ecc::Point::zero() };
+        assert!(Sm2Encryptor::new(&zero).is_err());
+    }
+
+    #[test]
+    fn test_typed_ciphertext() {
+        let (pk, sk) = gen_keypair().unwrap();
+        for (compressed, model) in [(false, Sm2Model::C1C3C2), (true, Sm2Model::C1C2C3)] {
+            let c = pk.encrypt_ciphertext(b"typed", compressed, model).unwrap();
+            assert_eq!((compressed, model), (c.compressed(), c.model()));
+            assert_eq!(b"typed".to_vec(), sk.decrypt_ciphertext(&c).unwrap());
+            let raw: Vec<u8> = c.clone().into();
+            assert_eq!(c, Sm2Ciphertext::new(raw.clone(), compressed, model).unwrap());
+            // the C1 prefix must match the form
+            assert!(Sm2Ciphertext::new(raw, !compressed, model).is_err());
+        }
+
+        let raw = pk.encrypt(b"typed", true, Sm2Model::C1C3C2).unwrap();
+        let c = Sm2Ciphertext::try_from(&raw[..]).unwrap();
+        assert!(c.compressed());
+        assert_eq!(b"typed".to_vec(), sk.into_decryption_key().decrypt_ciphertext(&c).unwrap());
+        assert_eq!(Err(Sm2Error::InvalidLength), Sm2Ciphertext::try_from(&raw[..33 + 32]));
+    }
+
+    #[test]
+    fn test_ciphertext_len() {
+        let (pk, _) = gen_keypair().unwrap();
+        for compressed in [false, true] {
+            let c = pk.encrypt(&[1; 10], compressed, Sm2Model::C1C3C2).unwrap();
+            assert_eq!(Sm2PublicKey::ciphertext_len(10, compressed), c.len());
+            assert_eq!(Some(10), Sm2PrivateKey::plaintext_len(c.len(), compressed));
+        }
+        assert_eq!(107, Sm2PublicKey::ciphertext_len(10, false));
+        assert_eq!(None, Sm2PrivateKey::plaintext_len(97, false));
+        assert_eq!(Some(32), Sm2PrivateKey::plaintext_len(97, true));
+    }
+
+    #[test]
+    fn test_key_usage() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let signing = sk.clone().into_signing_key();
+        let decryption = sk.clone().into_decryption_key();
+        assert_eq!(&pk, signing.public_key());
+        assert_eq!(&pk, decryption.public_key());
+        assert_eq!("Sm2SigningKey(…)", format!("{:?}", signing));
+
+        let sig = signing.sign(None, b"msg").unwrap();
+        pk.verify(None, b"msg", &sig).unwrap();
+        let sig = Signer::sign(&signing, b"msg").unwrap();
+        Verifier::verify(&pk, b"msg", &sig).unwrap();
+
+        let c = pk.encrypt(b"msg", false, Sm2Model::C1C3C2).unwrap();
+        assert_eq!(b"msg".to_vec(), decryption.decrypt(&c, false, Sm2Model::C1C3C2).unwrap());
+        assert_eq!(b"msg".to_vec(), Decryptor::decrypt(&decryption, &c).unwrap());
+
+        assert!(signing.into_private_key() == sk);
+        assert!(decryption.into_private_key() == sk);
+    }
+
+    #[cfg(feature = "audit")]
+    #[test]
+    fn test_audit_events() {
+        use std::sync::{Arc, Mutex};
+        use std::thread::{self, ThreadId};
+
+        use gm_core::audit::{set_observer, Event, Observer, Operation};
+
+        // other tests run at the same time, keep the events of this thread
+        struct Record(ThreadId, Mutex<Vec<(Operation, bool)>>);
+
+        impl Observer for Record {
+            fn on_event(&self, e: &Event) {
+                if thread::current().id() == self.0 {
+                    assert_eq!("SM2", e.algorithm);
+                    self.1.lock().unwrap().push((e.operation, e.ok));
+                }
+            }
+        }
+
+        let record = Arc::new(Record(thread::current().id(), Mutex::new(vec![])));
+        set_observer(Some(record.clone()));
+        let (pk, sk) = gen_keypair().unwrap();
+        let sig = sk.sign(None, b"msg").unwrap();
+        assert!(pk.verify(None, b"other", &sig).is_err());
+        let c = pk.encrypt(b"msg", false, Sm2Model::C1C3C2).unwrap();
+        sk.decrypt(&c, false, Sm2Model::C1C3C2).unwrap();
+        set_observer(None);
+        sk.sign(None, b"msg").unwrap();
+
+        use Operation::*;
+        let events = record.1.lock().unwrap().clone();
+        let expected = [(KeyGen, true), (Sign, true), (Verify, false), (Encrypt, true), (Decrypt, true)];
+        assert_eq!(expected.to_vec(), events);
+    }
+
     #[test]
     fn test_encrypt_decrypt_with_special_key() {
         let public_key = "048626c62a8582c639cb3c87b59118713a519988c5f6497f91dd672abbdaaed0420ea7bc2cd03a7c938adc42b450549d312bec823b74cf22cf57c63cebd011c595";
@@ -114,6 +318,33 @@ mod test_sm2 {
         assert_eq!(msg, plain);
     }
 
+    /// GM/T 0003.5-2012 encryption example with the recommended curve
+    #[cfg(feature = "kat")]
+    #[test]
+    fn test_encrypt_kat() {
+        use crate::u256::u256_from_be_bytes;
+
+        let sk = Sm2PrivateKey::from_hex_string(
+            "3945208f7b2144b13f36e38ac6d39f95889393692860b51a42fb81ef4df7c5b8",
+        )
+        .unwrap();
+        let pk = sk.to_public_key();
+        let k = u256_from_be_bytes(
+            &hex::decode("59276e27d506861a16680f3ad9c02dccef3cc1fa3cdbe4ce6d54b80deac1bc21")
+                .unwrap(),
+        );
+        let msg = b"encryption standard";
+        let c = pk.encrypt_with_k(msg, false, Sm2Model::C1C3C2, &k).unwrap();
+        assert_eq!(
+            "0404ebfc718e8d1798620432268e77feb6415e2ede0e073c0f4f640ecd2e149a73\
+             e858f9d81e5430a57b36daab8f950a3c64e6ee6a63094d99283aff767e124df0\
+             59983c18f809e262923c53aec295d30383b54e39d609d160afcb1908d0bd8766\
+             21886ca989ca9c7d58087307ca93092d651efa",
+            hex::encode(&c)
+        );
+        assert_eq!(msg.to_vec(), sk.decrypt(&c, false, Sm2Model::C1C3C2).unwrap());
+    }
+
     #[test]
     fn test_sign_verify() {
         let msg = b"hello";
@@ -146,6 +377,30 @@ mod test_sm2 {
         pk.verify(None, msg, &signature).unwrap();
     }
 
+    #[test]
+    fn test_traits() {
+        fn round_trip<K, P>(sk: &K, pk: &P, msg: &[u8]) -> bool
+        where
+            K: Signer + Decryptor,
+            P: Verifier<Signature = K::Signature> + Encryptor,
+        {
+            let (sig, ct) = match (sk.sign(msg), pk.encrypt(msg)) {
+                (Ok(sig), Ok(ct)) => (sig, ct),
+                _ => return false,
+            };
+            pk.verify(msg, &sig).is_ok() && sk.decrypt(&ct).ok().as_deref() == Some(msg)
+        }
+
+        let (pk, sk) = gen_keypair().unwrap();
+        assert!(round_trip(&sk, &pk, b"hello traits"));
+
+        let sig = Signer::sign(&sk, b"hello traits").unwrap();
+        assert!(Verifier::verify(&pk, b"hello trait", &sig).is_err());
+        let ct = Encryptor::encrypt(&pk, b"hello traits").unwrap();
+        let plain = sk.decrypt(&ct, false, Sm2Model::C1C3C2).unwrap();
+        assert_eq!(b"hello traits".to_vec(), plain);
+    }
+
     #[test]
     fn test_key_exchange() {
         let id_a = "alice123@qq.com";
@@ -174,4 +429,256 @@ mod test_sm2 {
         let plain = sk.decrypt_asn1(&encrypt, false, Sm2Model::C1C3C2).unwrap();
         assert_eq!(msg, plain);
     }
+
+    // `openssl pkeyutl -encrypt` of "encryption standard", y of the second
+    // one is 31 bytes long
+    #[test]
+    fn test_asn1_cipher_openssl() {
+        use crate::key::{cipher_from_asn1, cipher_to_asn1};
+        let sk = Sm2PrivateKey::from_hex_string("2e3c45f8ed9875766f1a164b301370724c52591bed3836a3b0ed9ad448356507").unwrap();
+        let ders = [
+            "307d022100bcbc310aefb1a339c4075189eb637b4a165fbbc08473fdfeeb65f11a6dd2419902210082e76976052c3d1348e0165c\
+             a084f68746d851ff7a4183bf393acf55f9b17ba1042027a7a0be113a516b1b175ea80103edcc0cc0b672a1b12c93925c8c78fbed\
+             ee1a04139e7c23fd8a35a1421c790eae097da777bc9d24",
+            "307b02210097f6ebacfb65512b8d7d20a1f5542eaae5aa03f27ab1c7441e598cd3d42cec11021f50992833d9776fd13a7886a143\
+             2b3720ffed6345b41d56107bb101ec3a54190420fd1489a77a1c15cf6f513decf2905b7275aa793c28de95e5360a20154f05a674\
+             04137f59cfe8fb9034f4aa9e29149a92af51cf8b43",
+        ];
+        for der in ders {
+            let der = hex::decode(der).unwrap();
+            assert_eq!(b"encryption standard".to_vec(), sk.decrypt_asn1(&der, false, Sm2Model::C1C3C2).unwrap());
+            for (compressed, model) in [(false, Sm2Model::C1C3C2), (true, Sm2Model::C1C2C3)] {
+                let raw = cipher_from_asn1(&der, compressed, model).unwrap();
+                assert_eq!(b"encryption standard".to_vec(), sk.decrypt(&raw, compressed, model).unwrap());
+                assert_eq!(der, cipher_to_asn1(&raw, compressed, model).unwrap());
+            }
+        }
+
+        // x written as 32 bytes without the zero byte DER needs
+        let der = hex::decode(ders[0]).unwrap();
+        let quirk = [&[0x30, 0x7c, 0x02, 0x20][..], &der[5..]].concat();
+        assert_eq!(b"encryption standard".to_vec(), sk.decrypt_asn1(&quirk, false, Sm2Model::C1C3C2).unwrap());
+        assert!(cipher_from_asn1(&quirk[..quirk.len() - 1], false, Sm2Model::C1C3C2).is_err());
+        // a negative x of 31 bytes is not a quirk
+        let short = [&[0x30, 0x7b, 0x02, 0x1f][..], &der[6..]].concat();
+        assert!(cipher_from_asn1(&short, false, Sm2Model::C1C3C2).is_err());
+
+        let pk = sk.public_key;
+        let der = pk.encrypt_asn1(b"to openssl", true, Sm2Model::C1C2C3).unwrap();
+        assert_eq!(b"to openssl".to_vec(), sk.decrypt_asn1(&der, true, Sm2Model::C1C2C3).unwrap());
+        assert_eq!(b"to openssl".to_vec(), sk.decrypt_asn1(&der, false, Sm2Model::C1C3C2).unwrap());
+    }
+
+    #[test]
+    fn test_private_key_debug() {
+        let sk = Sm2PrivateKey::from_hex_string(
+            "eb20009ffbffc90aeeb288ca7d782c722332d1d16a206cafec7dd6c64e6fc525",
+        )
+        .unwrap();
+        let debug = format!("{:?}", sk);
+        println!("{}", debug);
+        assert_eq!("Sm2PrivateKey(…)", debug);
+        assert_eq!(&sk.d, sk.expose_secret());
+    }
+
+    #[test]
+    fn test_private_key_range() {
+        use num_bigint::BigUint;
+
+        use crate::error::Sm2Error;
+
+        let n = BigUint::parse_bytes(
+            b"fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54123",
+            16,
+        )
+        .unwrap();
+        let one = BigUint::from(1u32);
+        assert!(Sm2PrivateKey::try_from_biguint(&one).is_ok());
+        assert!(Sm2PrivateKey::try_from_biguint(&(&n - 2u32)).is_ok());
+        assert_eq!(
+            Err(Sm2Error::ZeroPrivateKey),
+            Sm2PrivateKey::new(&[0u8; 32])
+        );
+        for d in [&n - 1u32, n.clone(), &n + 1u32, one << 256usize] {
+            assert_eq!(
+                Err(Sm2Error::PrivateKeyOutOfRange),
+                Sm2PrivateKey::try_from_biguint(&d)
+            );
+        }
+        assert_eq!(
+            Err(Sm2Error::InvalidFieldLen),
+            Sm2PrivateKey::new(&[1u8; 31])
+        );
+    }
+
+    #[test]
+    fn test_reject_invalid_peer_points() {
+        use crate::error::Sm2Error;
+        use crate::p256_ecc::{Point, UncheckedPoint};
+
+        let (mut alice, mut bob) = exchange::build_ex_pair(16, "alice", "bob").unwrap();
+        assert_eq!(
+            Err(Sm2Error::ZeroPoint),
+            bob.exchange_2(&Point::zero()).map(|_| ())
+        );
+        let mut off_curve = alice.exchange_1().unwrap();
+        off_curve.y = off_curve.x;
+        assert_eq!(
+            Err(Sm2Error::CheckPointErr),
+            bob.exchange_2(&off_curve).map(|_| ())
+        );
+
+        let ra_point = alice.exchange_1().unwrap();
+        let (rb_point, sb) = bob.exchange_2(&UncheckedPoint(ra_point)).unwrap();
+        let sa = alice.exchange_3(&rb_point, sb).unwrap();
+        assert!(bob.exchange_4(sa, &ra_point).unwrap());
+
+        let (pk, sk) = gen_keypair().unwrap();
+        let ct = pk.encrypt(b"hello", false, Sm2Model::C1C3C2).unwrap();
+        assert_eq!(
+            Err(Sm2Error::InvalidLength),
+            sk.decrypt(&ct[..90], false, Sm2Model::C1C3C2)
+        );
+        let mut bad = ct.clone();
+        bad[33..65].copy_from_slice(&ct[1..33]);
+        assert!(sk.decrypt(&bad, false, Sm2Model::C1C3C2).is_err());
+        let mut bad = ct.clone();
+        bad[1..33].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            Err(Sm2Error::InvalidPublic),
+            sk.decrypt(&bad, false, Sm2Model::C1C3C2)
+        );
+        assert_eq!(
+            Err(Sm2Error::InvalidDer),
+            sk.decrypt_asn1(&ct, false, Sm2Model::C1C3C2)
+        );
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        use crate::key::verify_batch;
+
+        let (pk_a, sk_a) = gen_keypair().unwrap();
+        let (pk_b, sk_b) = gen_keypair().unwrap();
+        let sig_a = sk_a.sign(None, b"message a").unwrap();
+        let sig_b = sk_b.sign(None, b"message b").unwrap();
+        let items: Vec<(&Sm2PublicKey, &[u8], &[u8])> = vec![
+            (&pk_a, b"message a", &sig_a),
+            (&pk_b, b"message b", &sig_b),
+            (&pk_a, b"message b", &sig_b),
+            (&pk_b, b"message b", &sig_b[..63]),
+        ];
+        let results = verify_batch(&items);
+        println!("{:?}", results);
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert!(results[2].is_err());
+        assert_eq!(Err(Sm2Error::InvalidSignatureLen), results[3]);
+    }
+
+    #[test]
+    fn test_wrap_private_key() {
+        let (_, sk) = gen_keypair().unwrap();
+        let kek = [0x3cu8; 16];
+        let wrapped = sk.wrap(&kek).unwrap();
+        println!("wrapped = {}", hex::encode(&wrapped));
+        assert_eq!(40, wrapped.len());
+        assert!(sk == Sm2PrivateKey::unwrap_from(&kek, &wrapped).unwrap());
+        assert!(Sm2PrivateKey::unwrap_from(&[0x3du8; 16], &wrapped).is_err());
+    }
+
+    #[test]
+    fn test_compute_za() {
+        use crate::{compute_za, SM2_DEFAULT_ID};
+
+        let (pk, sk) = gen_keypair().unwrap();
+        let za = compute_za(SM2_DEFAULT_ID, &pk).unwrap();
+        println!("ZA = {}", hex::encode(za));
+        assert_eq!(crate::util::compute_za("1234567812345678", &pk.point).unwrap(), za);
+
+        // the signature is over SM3(ZA || M)
+        let msg = b"tlcp handshake";
+        let sig = sk.sign(Some("alice@example.com"), msg).unwrap();
+        let za = compute_za(b"alice@example.com", &pk).unwrap();
+        let e = gm_sm3::sm3_hash(&[&za[..], msg].concat());
+        pk.verify_raw(&e, &pk.point, &sig).unwrap();
+
+        assert!(compute_za(&vec![b'a'; 8192], &pk).is_err());
+    }
+
+    #[test]
+    fn test_hex_string() {
+        use crate::error::Sm2Error;
+
+        let (pk, sk) = gen_keypair().unwrap();
+        for compressed in [true, false] {
+            let pk2 = Sm2PublicKey::from_hex_string(&pk.to_hex_string(compressed)).unwrap();
+            assert_eq!(pk.to_bytes(false), pk2.to_bytes(false));
+        }
+        assert!(sk == Sm2PrivateKey::from_hex_string(&sk.to_hex_string()).unwrap());
+
+        let mut bad = pk.to_hex_string(false);
+        bad.insert(2, ' ');
+        assert!(Sm2PublicKey::from_hex_string(&bad).err() == Some(Sm2Error::InvalidToken));
+        // a well-formed hex string that is not a point
+        let off_curve = format!("04{}", "11".repeat(64));
+        assert!(Sm2PublicKey::from_hex_string(&off_curve).is_err());
+    }
+
+    #[test]
+    fn test_key_components() {
+        let (pk, _) = gen_keypair().unwrap();
+        assert_eq!(pk.to_bytes(false)[1..], [pk.x_bytes(), pk.y_bytes()].concat());
+
+        // a small d keeps its leading zeros
+        let mut d = [0u8; 32];
+        d[31] = 7;
+        let sk = Sm2PrivateKey::new(&d).unwrap();
+        assert_eq!(d, sk.to_be_bytes());
+        assert_eq!(sk, Sm2PrivateKey::new(&sk.to_be_bytes()).unwrap());
+        let x = sk.to_public_key().x_bytes();
+        assert_eq!(&sk.to_public_key().to_bytes(true)[1..], &x[..]);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let (pk, _) = gen_keypair().unwrap();
+        let fp = pk.fingerprint();
+        println!("fingerprint = {} / {}", fp, fp.to_base64());
+        assert_eq!(gm_sm3::sm3_hash(&pk.to_bytes(false)), *fp.as_bytes());
+        let compressed = Sm2PublicKey::new(&pk.to_bytes(true)).unwrap();
+        assert_eq!(fp, compressed.fingerprint());
+        let (other, _) = gen_keypair().unwrap();
+        assert_ne!(fp, other.fingerprint());
+    }
+
+    #[test]
+    fn test_sign_with_context() {
+        use crate::error::Sm2Error;
+
+        let (pk, sk) = gen_keypair().unwrap();
+        let msg = b"transfer 100";
+        let sig = sk.sign_with_context(None, b"payment-v1", msg).unwrap();
+        pk.verify_with_context(None, b"payment-v1", msg, &sig).unwrap();
+        assert!(pk.verify_with_context(None, b"login-v1", msg, &sig).is_err());
+        assert!(pk.verify(None, msg, &sig).is_err());
+
+        let plain = sk.sign(None, msg).unwrap();
+        assert!(pk.verify_with_context(None, b"", msg, &plain).is_err());
+
+        // M' = "SM2ctx" || len(ctx) || ctx || M signed without a context does
+        // not verify as a context signature on M, nor the other way round
+        let crafted = [&b"SM2ctx"[..], &[10], b"payment-v1", msg].concat();
+        let za = crate::util::compute_za(crate::util::DEFAULT_ID, &pk.point).unwrap();
+        assert_ne!(
+            gm_sm3::sm3_hash(&[&za[..], &crafted].concat()),
+            crate::key::context_digest(None, b"payment-v1", &pk.point, msg).unwrap()
+        );
+        let plain = sk.sign(None, &crafted).unwrap();
+        assert!(pk.verify_with_context(None, b"payment-v1", msg, &plain).is_err());
+        assert!(pk.verify(None, &crafted, &sig).is_err());
+        assert_eq!(
+            Err(Sm2Error::ContextTooLong),
+            sk.sign_with_context(None, &[0u8; 256], msg)
+        );
+    }
 }
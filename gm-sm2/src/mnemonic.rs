@@ -0,0 +1,351 @@
+//! Mnemonic phrases for the seeds of [`crate::hd`], BIP39 (enable the
+//! `mnemonic` feature).
+//!
+//! Entropy of 16 to 32 bytes gets a checksum of one bit per 4 bytes and the
+//! bits are cut into 11-bit word indices, 12 to 24 words. The seed is
+//! `PBKDF2(phrase, "mnemonic" || passphrase, 2048)`, 64 bytes.
+//!
+//! [`Variant::Bip39`] is the standard: the checksum comes from SHA-256 and the
+//! seed from HMAC-SHA512, a phrase gives the same seed as in any BIP39
+//! wallet. [`Variant::Sm3`] uses SM3 for both, its phrases and seeds are not
+//! interchangeable with BIP39 ones. Phrases and passphrases are used as
+//! given, without Unicode normalization: a passphrase outside ASCII must be
+//! NFKD normalized by the caller to match other BIP39 implementations.
+//!
+//! The official English and Simplified Chinese BIP39 lists ship with the
+//! crate, [`Wordlist::english`] and [`Wordlist::chinese_simplified`], others
+//! load with [`Wordlist::new`].
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::hd::ExtendedPrivateKey;
+//! use gm_sm2::mnemonic::{Mnemonic, Variant, Wordlist};
+//!
+//! let wordlist = Wordlist::english();
+//! let mnemonic = Mnemonic::generate(Variant::Bip39, 12).unwrap();
+//! let phrase = mnemonic.phrase(wordlist);
+//! let recovered = Mnemonic::from_phrase(Variant::Bip39, wordlist, &phrase).unwrap();
+//! let master = ExtendedPrivateKey::from_seed(&recovered.to_seed(wordlist, "passphrase")[..]).unwrap();
+//! ```
+use std::sync::OnceLock;
+
+use gm_sm3::pbkdf2::pbkdf2_sm3;
+use gm_sm3::sm3_hash;
+use pkcs8::der::zeroize::{Zeroize, Zeroizing};
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::error::{Sm2Error, Sm2Result};
+
+pub const WORDLIST_LEN: usize = 2048;
+
+const PBKDF2_ITERATIONS: u32 = 2048;
+
+const ENGLISH: &str = include_str!("wordlists/english.txt");
+const CHINESE_SIMPLIFIED: &str = include_str!("wordlists/chinese_simplified.txt");
+
+static ENGLISH_LIST: OnceLock<Wordlist> = OnceLock::new();
+static CHINESE_SIMPLIFIED_LIST: OnceLock<Wordlist> = OnceLock::new();
+
+/// The hashes behind the checksum and the seed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Variant {
+    /// SHA-256 checksum and PBKDF2-HMAC-SHA512 seed, as in BIP39
+    #[default]
+    Bip39,
+    /// SM3 checksum and PBKDF2-HMAC-SM3 seed
+    Sm3,
+}
+
+/// 2048 distinct words, a word's index is its position
+#[derive(Debug, Clone)]
+pub struct Wordlist {
+    words: Vec<String>,
+    /// indices ordered by word, for lookup
+    sorted: Vec<u16>,
+}
+
+impl Wordlist {
+    /// Fails with [`Sm2Error::InvalidMnemonic`] unless there are exactly 2048
+    /// distinct words without spaces.
+    pub fn new<'a>(words: impl IntoIterator<Item = &'a str>) -> Sm2Result<Wordlist> {
+        let words: Vec<String> = words.into_iter().map(str::to_string).collect();
+        if words.len() != WORDLIST_LEN || words.iter().any(|w| w.is_empty() || w.contains(char::is_whitespace)) {
+            return Err(Sm2Error::InvalidMnemonic);
+        }
+        let mut sorted: Vec<u16> = (0..WORDLIST_LEN as u16).collect();
+        sorted.sort_by(|&a, &b| words[a as usize].cmp(&words[b as usize]));
+        if sorted.windows(2).any(|w| words[w[0] as usize] == words[w[1] as usize]) {
+            return Err(Sm2Error::InvalidMnemonic);
+        }
+        Ok(Wordlist { words, sorted })
+    }
+
+    /// The BIP39 English list
+    pub fn english() -> &'static Wordlist {
+        ENGLISH_LIST.get_or_init(|| Wordlist::new(ENGLISH.lines()).expect("the English list is valid"))
+    }
+
+    /// The BIP39 Simplified Chinese list, its phrases use ASCII spaces
+    pub fn chinese_simplified() -> &'static Wordlist {
+        CHINESE_SIMPLIFIED_LIST
+            .get_or_init(|| Wordlist::new(CHINESE_SIMPLIFIED.lines()).expect("the Chinese list is valid"))
+    }
+
+    pub fn word(&self, index: u16) -> &str {
+        &self.words[index as usize]
+    }
+
+    pub fn index_of(&self, word: &str) -> Option<u16> {
+        self.sorted
+            .binary_search_by(|&i| self.words[i as usize].as_str().cmp(word))
+            .ok()
+            .map(|pos| self.sorted[pos])
+    }
+}
+
+/// The entropy behind a phrase, never printed
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    entropy: Zeroizing<Vec<u8>>,
+    variant: Variant,
+}
+
+impl std::fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Mnemonic({} words)", self.word_count())
+    }
+}
+
+impl Mnemonic {
+    /// A fresh mnemonic of 12, 15, 18, 21 or 24 words
+    pub fn generate(variant: Variant, words: usize) -> Sm2Result<Mnemonic> {
+        if !words.is_multiple_of(3) || !(12..=24).contains(&words) {
+            return Err(Sm2Error::InvalidMnemonic);
+        }
+        let mut entropy = Zeroizing::new(vec![0u8; words / 3 * 4]);
+        rand::thread_rng().fill_bytes(&mut entropy);
+        Ok(Mnemonic { entropy, variant })
+    }
+
+    /// 16, 20, 24, 28 or 32 bytes of entropy
+    pub fn from_entropy(variant: Variant, entropy: &[u8]) -> Sm2Result<Mnemonic> {
+        if !entropy.len().is_multiple_of(4) || !(16..=32).contains(&entropy.len()) {
+            return Err(Sm2Error::InvalidMnemonic);
+        }
+        Ok(Mnemonic {
+            entropy: Zeroizing::new(entropy.to_vec()),
+            variant,
+        })
+    }
+
+    /// Parse a phrase of words separated by whitespace, checking the words
+    /// and the checksum
+    pub fn from_phrase(variant: Variant, wordlist: &Wordlist, phrase: &str) -> Sm2Result<Mnemonic> {
+        let indices = phrase
+            .split_whitespace()
+            .map(|w| wordlist.index_of(w).ok_or(Sm2Error::InvalidMnemonic))
+            .collect::<Sm2Result<Vec<u16>>>()?;
+        if !indices.len().is_multiple_of(3) || !(12..=24).contains(&indices.len()) {
+            return Err(Sm2Error::InvalidMnemonic);
+        }
+        let mut bits = Zeroizing::new(vec![0u8; (indices.len() * 11).div_ceil(8)]);
+        for (n, &index) in indices.iter().enumerate() {
+            for b in 0..11 {
+                if index >> (10 - b) & 1 == 1 {
+                    let pos = n * 11 + b;
+                    bits[pos / 8] |= 0x80 >> (pos % 8);
+                }
+            }
+        }
+        let mnemonic = Mnemonic::from_entropy(variant, &bits[..indices.len() / 3 * 4])?;
+        if mnemonic.indices() != indices {
+            return Err(Sm2Error::InvalidMnemonic);
+        }
+        Ok(mnemonic)
+    }
+
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn word_count(&self) -> usize {
+        self.entropy.len() / 4 * 3
+    }
+
+    /// The word indices, entropy followed by its checksum
+    fn indices(&self) -> Vec<u16> {
+        let checksum = match self.variant {
+            Variant::Bip39 => Sha256::digest(&self.entropy[..])[0],
+            Variant::Sm3 => sm3_hash(&self.entropy)[0],
+        };
+        let bit = |pos: usize| {
+            let byte = if pos / 8 < self.entropy.len() { self.entropy[pos / 8] } else { checksum };
+            (byte >> (7 - pos % 8) & 1) as u16
+        };
+        (0..self.word_count())
+            .map(|n| (0..11).fold(0, |acc, b| acc << 1 | bit(n * 11 + b)))
+            .collect()
+    }
+
+    /// The words separated by single spaces
+    pub fn phrase(&self, wordlist: &Wordlist) -> String {
+        let mut indices = self.indices();
+        let phrase = indices.iter().map(|&i| wordlist.word(i)).collect::<Vec<_>>().join(" ");
+        indices.zeroize();
+        phrase
+    }
+
+    /// The 64-byte seed of [`crate::hd::ExtendedPrivateKey::from_seed`]. The
+    /// phrase is rebuilt from the entropy, any spacing of the original is
+    /// irrelevant, but it depends on the wordlist.
+    pub fn to_seed(&self, wordlist: &Wordlist, passphrase: &str) -> Zeroizing<[u8; 64]> {
+        let phrase = Zeroizing::new(self.phrase(wordlist));
+        let salt = Zeroizing::new(format!("mnemonic{}", passphrase));
+        let mut seed = Zeroizing::new([0u8; 64]);
+        match self.variant {
+            Variant::Bip39 => {
+                pbkdf2::pbkdf2_hmac::<Sha512>(phrase.as_bytes(), salt.as_bytes(), PBKDF2_ITERATIONS, &mut seed[..])
+            }
+            Variant::Sm3 => {
+                let mut dk = pbkdf2_sm3(phrase.as_bytes(), salt.as_bytes(), PBKDF2_ITERATIONS, 64).unwrap();
+                seed.copy_from_slice(&dk);
+                dk.zeroize();
+            }
+        }
+        seed
+    }
+}
+
+#[cfg(test)]
+mod test_mnemonic {
+    use super::*;
+
+    fn wordlist() -> Wordlist {
+        let words: Vec<String> = (0..WORDLIST_LEN).map(|i| format!("w{:04}", i)).collect();
+        Wordlist::new(words.iter().map(String::as_str)).unwrap()
+    }
+
+    #[test]
+    fn test_wordlist() {
+        let list = wordlist();
+        assert_eq!(Some(1234), list.index_of("w1234"));
+        assert_eq!("w0007", list.word(7));
+        assert_eq!(None, list.index_of("w2048"));
+        let mut words: Vec<String> = (0..WORDLIST_LEN).map(|i| i.to_string()).collect();
+        assert!(Wordlist::new(words.iter().map(String::as_str)).is_ok());
+        words[5] = "6".to_string();
+        assert_eq!(Err(Sm2Error::InvalidMnemonic), Wordlist::new(words.iter().map(String::as_str)).map(|_| ()));
+        assert!(Wordlist::new(words[1..].iter().map(String::as_str)).is_err());
+    }
+
+    #[test]
+    fn test_phrase() {
+        let list = wordlist();
+        // 128 zero bits, then the top 4 bits of the checksum fill the last word
+        let zero = Mnemonic::from_entropy(Variant::Sm3, &[0; 16]).unwrap();
+        let phrase = zero.phrase(&list);
+        let last = format!("w{:04}", sm3_hash(&[0; 16])[0] >> 4);
+        assert_eq!(format!("{}{}", "w0000 ".repeat(11), last), phrase);
+        assert_eq!(zero, Mnemonic::from_phrase(Variant::Sm3, &list, &phrase).unwrap());
+
+        for words in [12, 15, 18, 21, 24] {
+            let m = Mnemonic::generate(Variant::Sm3, words).unwrap();
+            let phrase = m.phrase(&list);
+            assert_eq!(words, phrase.split(' ').count());
+            assert_eq!(m, Mnemonic::from_phrase(Variant::Sm3, &list, &format!("  {}\n", phrase.replace(' ', "\t"))).unwrap());
+        }
+        assert!(Mnemonic::generate(Variant::Sm3, 13).is_err());
+        assert!(Mnemonic::from_entropy(Variant::Sm3, &[0; 17]).is_err());
+
+        // a wrong checksum, an unknown word, a wrong count
+        let other = if last == "w0000" { "w0001" } else { "w0000" };
+        let bad = format!("{}{}", "w0000 ".repeat(11), other);
+        assert_eq!(Err(Sm2Error::InvalidMnemonic), Mnemonic::from_phrase(Variant::Sm3, &list, &bad).map(|_| ()));
+        assert!(Mnemonic::from_phrase(Variant::Sm3, &list, &phrase.replace(&last, "abandon")).is_err());
+        assert!(Mnemonic::from_phrase(Variant::Sm3, &list, &phrase[6..]).is_err());
+        assert_eq!("Mnemonic(12 words)", format!("{:?}", zero));
+    }
+
+    #[test]
+    fn test_seed() {
+        let list = wordlist();
+        let m = Mnemonic::from_entropy(Variant::Sm3, &[0x5a; 32]).unwrap();
+        let seed = m.to_seed(&list, "TREZOR");
+        assert_eq!(seed, m.to_seed(&list, "TREZOR"));
+        assert_ne!(seed, m.to_seed(&list, ""));
+        let phrase = Zeroizing::new(m.phrase(&list));
+        assert_eq!(&seed[..], &pbkdf2_sm3(phrase.as_bytes(), b"mnemonicTREZOR", 2048, 64).unwrap()[..]);
+        crate::hd::ExtendedPrivateKey::from_seed(&seed[..]).unwrap();
+    }
+
+    #[test]
+    fn test_bundled_wordlists() {
+        assert_eq!(
+            "2f5eed53a4727b4bf8880d8f3f199efc90e58503646d9ff8eff3a2ed3b24dbda",
+            hex::encode(Sha256::digest(ENGLISH))
+        );
+        assert_eq!(
+            "5c5942792bd8340cb8b27cd592f1015edf56a8c5b26276ee18a482428e7c5726",
+            hex::encode(Sha256::digest(CHINESE_SIMPLIFIED))
+        );
+        let english = Wordlist::english();
+        assert_eq!(("abandon", "zoo"), (english.word(0), english.word(2047)));
+        assert_eq!(Some(1028), english.index_of("letter"));
+        let chinese = Wordlist::chinese_simplified();
+        assert_eq!(("的", "歇"), (chinese.word(0), chinese.word(2047)));
+    }
+
+    /// The English vectors of the Trezor reference implementation, the
+    /// Chinese ones from Python's hashlib on the same entropy
+    #[test]
+    fn test_bip39_vectors() {
+        let cases = [
+            (
+                Wordlist::english(),
+                "00000000000000000000000000000000",
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                Wordlist::english(),
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            ),
+            (
+                Wordlist::english(),
+                "8080808080808080808080808080808080808080808080808080808080808080",
+                "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor \
+                 acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+                "c0c519bd0e91a2ed54357d9d1ebef6f5af218a153624cf4f2da911a0ed8f7a09e2ef61af0aca007096df430022f7a2b6fb91661a9589097069720d015e4e982f",
+            ),
+            (
+                Wordlist::chinese_simplified(),
+                "00000000000000000000000000000000",
+                "的 的 的 的 的 的 的 的 的 的 的 在",
+                "7f7c7f91ef81f0fb6a3b95b346c50e6472c1d554f8ba90637bad8afce4a4de87c322c1acafa2f6f5e9a8f9b2d2c40e9d389efdc2adbe4445c21a0939fb39e91f",
+            ),
+            (
+                Wordlist::chinese_simplified(),
+                "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+                "枪 疫 霉 尝 俩 闹 饿 贤 枪 疫 霉 卿",
+                "816a69d6866891b246b4d33f54d6d2be624470141754396205d039bdd8003949fec4340253dde4c8e11437a181ad992f56d5b976eb9fbe48f4c5e5fec60a27e1",
+            ),
+        ];
+        for (list, entropy, phrase, seed) in cases {
+            let m = Mnemonic::from_entropy(Variant::Bip39, &hex::decode(entropy).unwrap()).unwrap();
+            assert_eq!(phrase, m.phrase(list));
+            assert_eq!(m, Mnemonic::from_phrase(Variant::Bip39, list, phrase).unwrap());
+            assert_eq!(seed, hex::encode(&m.to_seed(list, "TREZOR")[..]));
+        }
+
+        // the SM3 checksum of zero entropy differs, so does the last word
+        assert!(Mnemonic::from_phrase(Variant::Sm3, Wordlist::english(), cases[0].2).is_err());
+        assert_eq!(Variant::Bip39, Variant::default());
+    }
+}
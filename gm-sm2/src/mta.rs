@@ -0,0 +1,117 @@
+//! Multiplicative to additive share conversion (MtA) with Paillier, the
+//! building block of [`crate::threshold`].
+//!
+//! Alice holds a scalar `a` and a Paillier key, Bob holds a scalar `b`. After
+//! one message each way they hold `α` and `β` with `α + β = a·b mod n`, and
+//! neither learns the other's scalar:
+//!
+//! * Alice sends `Enc(a)` under her key, [`mta_request`]
+//! * Bob answers `Enc(a)^b·Enc(β')` for a random `β'` below 2^640 and keeps
+//!   `β = -β' mod n`, [`mta_respond`]
+//! * Alice decrypts `α = a·b + β' mod n`, [`mta_finish`]
+//!
+//! The mask is large enough to hide `a·b` statistically and small enough that
+//! `a·b + β'` never wraps modulo a Paillier modulus of 1024 bits or more.
+//!
+//! # Security
+//!
+//! Only behind the `experimental` feature: both parties must be trusted to
+//! follow the protocol. There are no range proofs, so a cheating Alice can
+//! encrypt something other than a scalar and learn bits of `b` from whether
+//! the protocol that uses MtA later aborts.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::mta::{mta_finish, mta_request, mta_respond};
+//! use gm_sm2::paillier::PaillierSecretKey;
+//! use gm_sm2::u256::u256_to_be_bytes;
+//! use num_bigint::BigUint;
+//!
+//! let alice = PaillierSecretKey::generate_with_bits(1024).unwrap();
+//! let (a, b) = ([3, 0, 0, 0], [5, 0, 0, 0]);
+//! let request = mta_request(alice.public_key(), &a).unwrap();
+//! let (response, beta) = mta_respond(alice.public_key(), &request, &b).unwrap();
+//! let alpha = mta_finish(&alice, &response).unwrap();
+//!
+//! let n = BigUint::parse_bytes(b"FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFF7203DF6B21C6052B53BBF40939D54123", 16).unwrap();
+//! let sum = BigUint::from_bytes_be(&u256_to_be_bytes(&alpha)) + BigUint::from_bytes_be(&u256_to_be_bytes(&beta));
+//! assert_eq!(BigUint::from(15u32), sum % n);
+//! ```
+use num_bigint::BigUint;
+use pkcs8::der::zeroize::Zeroize;
+use rand::RngCore;
+
+use crate::error::Sm2Result;
+use crate::fields::fn64::{fn_sub, SM2_N};
+use crate::paillier::{PaillierPublicKey, PaillierSecretKey};
+use crate::u256::{u256_from_be_bytes, u256_to_be_bytes, U256};
+
+/// `β'` is below 2^640
+const MASK_BYTES: usize = 80;
+
+fn to_big(x: &U256) -> BigUint {
+    BigUint::from_bytes_be(&u256_to_be_bytes(x))
+}
+
+/// `x mod n` as a scalar
+fn from_big(x: &BigUint) -> U256 {
+    let x = (x % to_big(&SM2_N)).to_bytes_be();
+    let mut buf = [0u8; 32];
+    buf[32 - x.len()..].copy_from_slice(&x);
+    u256_from_be_bytes(&buf)
+}
+
+/// Alice's message, `Enc(a)` under her own key, for a scalar `a` below n
+pub fn mta_request(alice: &PaillierPublicKey, a: &U256) -> Sm2Result<BigUint> {
+    alice.encrypt(&to_big(a))
+}
+
+/// Bob's answer to `Enc(a)` for his `b`, `Enc(a·b + β')`, and his share
+/// `β`. A ciphertext out of range fails with
+/// [`crate::error::Sm2Error::PaillierOutOfRange`].
+pub fn mta_respond(alice: &PaillierPublicKey, enc_a: &BigUint, b: &U256) -> Sm2Result<(BigUint, U256)> {
+    alice.check_ciphertext(enc_a)?;
+    let mut mask = [0u8; MASK_BYTES];
+    rand::thread_rng().fill_bytes(&mut mask);
+    let beta_prime = BigUint::from_bytes_be(&mask);
+    mask.zeroize();
+    let c = alice.add(&alice.mul(enc_a, &to_big(b)), &alice.encrypt(&beta_prime)?);
+    Ok((c, fn_sub(&[0; 4], &from_big(&beta_prime))))
+}
+
+/// Alice's share `α`, from Bob's answer
+pub fn mta_finish(alice: &PaillierSecretKey, c: &BigUint) -> Sm2Result<U256> {
+    Ok(from_big(&alice.decrypt(c)?))
+}
+
+#[cfg(test)]
+mod test_mta {
+    use super::*;
+    use crate::error::Sm2Error;
+    use crate::fields::fn64::{fn_add, fn_mul, fn_random};
+
+    #[test]
+    fn test_mta() {
+        let alice = PaillierSecretKey::generate_with_bits(1024).unwrap();
+        let pk = alice.public_key();
+        let n_1 = fn_sub(&[0; 4], &[1, 0, 0, 0]);
+        for (a, b) in [(fn_random(), fn_random()), (n_1, n_1), ([0; 4], fn_random()), (fn_random(), [0; 4])] {
+            let request = mta_request(pk, &a).unwrap();
+            let (response, beta) = mta_respond(pk, &request, &b).unwrap();
+            let alpha = mta_finish(&alice, &response).unwrap();
+            assert_eq!(fn_mul(&a, &b), fn_add(&alpha, &beta));
+            // the shares themselves are masked
+            assert_ne!(fn_mul(&a, &b), alpha);
+        }
+
+        let request = mta_request(pk, &fn_random()).unwrap();
+        let (r1, _) = mta_respond(pk, &request, &[7, 0, 0, 0]).unwrap();
+        let (r2, _) = mta_respond(pk, &request, &[7, 0, 0, 0]).unwrap();
+        assert_ne!(r1, r2);
+
+        let n2 = pk.n() * pk.n();
+        assert_eq!(Err(Sm2Error::PaillierOutOfRange), mta_respond(pk, &n2, &[1, 0, 0, 0]).map(|_| ()));
+        assert_eq!(Err(Sm2Error::PaillierOutOfRange), mta_respond(pk, pk.n(), &[1, 0, 0, 0]).map(|_| ()));
+        assert_eq!(Err(Sm2Error::PaillierOutOfRange), mta_finish(&alice, &n2).map(|_| ()));
+    }
+}
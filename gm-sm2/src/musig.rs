@@ -0,0 +1,283 @@
+//! n-of-n Schnorr multi-signatures on the SM2 curve, MuSig with nonce commitments.
+//!
+//! This is not a GB/T 32918 signature: it is Schnorr over the SM2 group with
+//! SM3, verified by [`verify`] and not by [`Sm2PublicKey::verify`]. The
+//! signers' keys aggregate into one key `X`, together they produce one 65-byte
+//! signature under `X`.
+//!
+//! * key aggregation: `a_i = H(L || P_i)`, `X = Σ [a_i]P_i`, `L` the hash of all keys
+//! * round 1: each signer draws `k_i`, sends the commitment `H(R_i)` of `R_i = [k_i]G`
+//! * round 2: once all commitments are in, each sends `R_i`, `R = Σ R_i`
+//! * round 3: `c = H(X || R || M)`, each sends `s_i = k_i + c·a_i·d_i`
+//! * the signature is `R || s` with `R` compressed and `s = Σ s_i`, checked as `[s]G = R + [c]X`
+//!
+//! The commitments keep a signer from choosing its nonce after seeing the
+//! others'. A [`SecretNonce`] is consumed by signing and must never be reused.
+//!
+//! [`Sm2PublicKey::verify`]: crate::key::Sm2PublicKey::verify
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_mul, fn_random};
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::{u256_from_be_bytes, u256_to_be_bytes, U256};
+
+const KEYS_TAG: &[u8] = b"SM2-MUSIG-KEYS";
+const COEFFICIENT_TAG: &[u8] = b"SM2-MUSIG-COEF";
+const COMMITMENT_TAG: &[u8] = b"SM2-MUSIG-COM";
+const CHALLENGE_TAG: &[u8] = b"SM2-MUSIG-SIG";
+
+pub const SIGNATURE_LEN: usize = 33 + 32;
+
+fn hash_to_scalar(parts: &[&[u8]]) -> U256 {
+    fn_add(&u256_from_be_bytes(&sm3_hash(&parts.concat())), &[0; 4])
+}
+
+/// `c = H(X || R || M)`
+fn challenge(x: &Point, r: &Point, msg: &[u8]) -> U256 {
+    hash_to_scalar(&[CHALLENGE_TAG, &x.to_byte_be(false), &r.to_byte_be(false), msg])
+}
+
+/// The signers' keys, in an order they all agree on, and their aggregate
+#[derive(Debug, Clone)]
+pub struct KeyAggregation {
+    keys: Vec<Sm2PublicKey>,
+    coefficients: Vec<U256>,
+    aggregate: Point,
+}
+
+impl KeyAggregation {
+    pub fn new(keys: &[Sm2PublicKey]) -> Sm2Result<Self> {
+        if keys.is_empty() {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let encoded: Vec<Vec<u8>> = keys.iter().map(|pk| pk.to_bytes(false)).collect();
+        let l = sm3_hash(&[KEYS_TAG, &encoded.concat()].concat());
+        let coefficients: Vec<U256> = encoded
+            .iter()
+            .map(|pk| hash_to_scalar(&[COEFFICIENT_TAG, &l, pk]))
+            .collect();
+        let aggregate = keys
+            .iter()
+            .zip(&coefficients)
+            .fold(Point::zero(), |x, (pk, a)| x.point_add(&pk.point.scalar_mul(a)));
+        if aggregate.is_zero() {
+            return Err(Sm2Error::ZeroPoint);
+        }
+        Ok(KeyAggregation {
+            keys: keys.to_vec(),
+            coefficients,
+            aggregate,
+        })
+    }
+
+    /// The key [`verify`] checks the aggregated signature against
+    pub fn aggregate_key(&self) -> Sm2PublicKey {
+        Sm2PublicKey {
+            point: self.aggregate.to_affine_point(),
+        }
+    }
+
+    fn index_of(&self, pk: &Sm2PublicKey) -> Sm2Result<usize> {
+        let own = pk.to_bytes(false);
+        self.keys
+            .iter()
+            .position(|k| k.to_bytes(false) == own)
+            .ok_or(Sm2Error::UnknownSigner)
+    }
+
+    /// Round 3 for the signer holding `sk`, consuming its nonce.
+    pub fn partial_sign(
+        &self,
+        sk: &Sm2PrivateKey,
+        nonce: SecretNonce,
+        aggregate_nonce: &AggregateNonce,
+        msg: &[u8],
+    ) -> Sm2Result<PartialSignature> {
+        let i = self.index_of(&sk.public_key)?;
+        let c = challenge(&self.aggregate, &aggregate_nonce.0, msg);
+        let s = fn_add(&nonce.k, &fn_mul(&c, &fn_mul(&self.coefficients[i], &sk.d)));
+        Ok(PartialSignature(s))
+    }
+
+    /// Check the partial signature of `signer` against its round 2 nonce,
+    /// to tell which signer misbehaved when the aggregate does not verify.
+    pub fn partial_verify(
+        &self,
+        signer: &Sm2PublicKey,
+        nonce: &PublicNonce,
+        aggregate_nonce: &AggregateNonce,
+        msg: &[u8],
+        partial: &PartialSignature,
+    ) -> Sm2Result<()> {
+        let i = self.index_of(signer)?;
+        let c = challenge(&self.aggregate, &aggregate_nonce.0, msg);
+        let expected = nonce
+            .0
+            .point_add(&signer.point.scalar_mul(&fn_mul(&c, &self.coefficients[i])));
+        if g_mul(&partial.0).to_byte_be(false) != expected.to_byte_be(false) {
+            return Err(Sm2Error::InvalidDigest);
+        }
+        Ok(())
+    }
+
+    /// Sum the partial signatures of all signers into `R || s`.
+    pub fn aggregate(&self, aggregate_nonce: &AggregateNonce, partials: &[PartialSignature]) -> Sm2Result<Vec<u8>> {
+        if partials.len() != self.keys.len() {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let s = partials.iter().fold([0; 4], |s, p| fn_add(&s, &p.0));
+        Ok([aggregate_nonce.0.to_byte_be(true), u256_to_be_bytes(&s)].concat())
+    }
+}
+
+/// The nonce of one signer, consumed by [`KeyAggregation::partial_sign`]
+pub struct SecretNonce {
+    k: U256,
+}
+
+/// `R_i`, revealed in round 2
+#[derive(Debug, Clone)]
+pub struct PublicNonce(Point);
+
+impl PublicNonce {
+    /// The round 1 commitment to this nonce
+    pub fn commitment(&self) -> [u8; 32] {
+        sm3_hash(&[COMMITMENT_TAG, &self.0.to_byte_be(true)].concat())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_byte_be(true)
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Self> {
+        Ok(PublicNonce(Sm2PublicKey::new(b)?.point))
+    }
+}
+
+/// `R = Σ R_i`
+#[derive(Debug, Clone)]
+pub struct AggregateNonce(Point);
+
+/// `s_i`, sent in round 3
+#[derive(Debug, Clone)]
+pub struct PartialSignature(U256);
+
+impl PartialSignature {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        u256_to_be_bytes(&self.0)
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Self> {
+        Ok(PartialSignature(fn_from_bytes(b)?))
+    }
+}
+
+/// Round 1: a fresh nonce, publish the commitment of the public part first.
+pub fn nonce_generate() -> (SecretNonce, PublicNonce) {
+    let k = fn_random();
+    (SecretNonce { k }, PublicNonce(g_mul(&k)))
+}
+
+/// Round 2: check every nonce against its round 1 commitment and sum them.
+pub fn aggregate_nonces(commitments: &[[u8; 32]], nonces: &[PublicNonce]) -> Sm2Result<AggregateNonce> {
+    if nonces.is_empty() || commitments.len() != nonces.len() {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let mut r = Point::zero();
+    for (commitment, nonce) in commitments.iter().zip(nonces) {
+        if commitment != &nonce.commitment() {
+            return Err(Sm2Error::HashNotEqual);
+        }
+        r = r.point_add(&nonce.0);
+    }
+    if r.is_zero() {
+        return Err(Sm2Error::ZeroPoint);
+    }
+    Ok(AggregateNonce(r))
+}
+
+/// Verify an aggregated signature under [`KeyAggregation::aggregate_key`].
+pub fn verify(aggregate_key: &Sm2PublicKey, msg: &[u8], sig: &[u8]) -> Sm2Result<()> {
+    if sig.len() != SIGNATURE_LEN {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let r = Sm2PublicKey::new(&sig[..33])?.point;
+    let s = fn_from_bytes(&sig[33..])?;
+    let c = challenge(&aggregate_key.point, &r, msg);
+    let expected = r.point_add(&aggregate_key.point.scalar_mul(&c));
+    if g_mul(&s).to_byte_be(false) != expected.to_byte_be(false) {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_musig {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    fn run(sks: &[Sm2PrivateKey], agg: &KeyAggregation, msg: &[u8]) -> Vec<u8> {
+        let (secrets, nonces): (Vec<_>, Vec<_>) = sks.iter().map(|_| nonce_generate()).unzip();
+        let commitments: Vec<_> = nonces.iter().map(PublicNonce::commitment).collect();
+        let r = aggregate_nonces(&commitments, &nonces).unwrap();
+        let partials: Vec<_> = sks
+            .iter()
+            .zip(secrets)
+            .map(|(sk, k)| agg.partial_sign(sk, k, &r, msg).unwrap())
+            .collect();
+        for ((sk, nonce), partial) in sks.iter().zip(&nonces).zip(&partials) {
+            agg.partial_verify(&sk.public_key, nonce, &r, msg, partial).unwrap();
+        }
+        agg.aggregate(&r, &partials).unwrap()
+    }
+
+    #[test]
+    fn test_musig_sign_verify() {
+        let (pks, sks): (Vec<_>, Vec<_>) = (0..3).map(|_| gen_keypair().unwrap()).unzip();
+        let agg = KeyAggregation::new(&pks).unwrap();
+        let x = agg.aggregate_key();
+        let sig = run(&sks, &agg, b"block 42");
+        assert_eq!(SIGNATURE_LEN, sig.len());
+        verify(&x, b"block 42", &sig).unwrap();
+        assert!(verify(&x, b"block 43", &sig).is_err());
+        assert!(verify(&pks[0], b"block 42", &sig).is_err());
+
+        // the order of the keys is part of the aggregate
+        let reordered = KeyAggregation::new(&[pks[1], pks[0], pks[2]]).unwrap();
+        assert!(verify(&reordered.aggregate_key(), b"block 42", &sig).is_err());
+
+        let single = KeyAggregation::new(&pks[..1]).unwrap();
+        verify(&single.aggregate_key(), b"m", &run(&sks[..1], &single, b"m")).unwrap();
+    }
+
+    #[test]
+    fn test_musig_rejects() {
+        let (pks, sks): (Vec<_>, Vec<_>) = (0..2).map(|_| gen_keypair().unwrap()).unzip();
+        let agg = KeyAggregation::new(&pks).unwrap();
+        let (k0, n0) = nonce_generate();
+        let (_, n1) = nonce_generate();
+        let (_, swapped) = nonce_generate();
+        assert!(aggregate_nonces(&[n0.commitment(), n1.commitment()], &[n0.clone(), swapped]).is_err());
+        let r = aggregate_nonces(&[n0.commitment(), n1.commitment()], &[n0.clone(), n1]).unwrap();
+
+        let (_, outsider) = gen_keypair().unwrap();
+        assert!(matches!(
+            agg.partial_sign(&outsider, nonce_generate().0, &r, b"m"),
+            Err(Sm2Error::UnknownSigner)
+        ));
+        let partial = agg.partial_sign(&sks[0], k0, &r, b"m").unwrap();
+        let partial = PartialSignature::from_bytes(&partial.to_bytes()).unwrap();
+        agg.partial_verify(&pks[0], &n0, &r, b"m", &partial).unwrap();
+        assert!(agg.partial_verify(&pks[1], &n0, &r, b"m", &partial).is_err());
+        assert!(agg.aggregate(&r, &[partial]).is_err());
+
+        let sig = run(&sks, &agg, b"m");
+        for i in 0..sig.len() {
+            let mut b = sig.clone();
+            b[i] ^= 1;
+            assert!(verify(&agg.aggregate_key(), b"m", &b).is_err());
+        }
+    }
+}
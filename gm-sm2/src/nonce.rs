@@ -0,0 +1,118 @@
+//! Nonce reuse in SM2 signatures, and how it gives the private key away.
+//!
+//! With `s = (1 + d)^-1 (k - r d)`, each signature gives `k = s + (s + r) d`.
+//! Two signatures sharing `k`, even over different messages, give
+//!
+//! ```text
+//! d = (s2 - s1) / ((s1 + r1) - (s2 + r2))  mod n
+//! ```
+//!
+//! [`Sm2PrivateKey::sign`] draws a fresh `k` uniformly in [1, n-1] for every
+//! signature. Debug builds also check that no thread draws the same `k` twice
+//! in a row, which catches a stuck or badly seeded RNG in tests. The helpers
+//! here let an audit check a batch of collected signatures for the reuse.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::key::gen_keypair;
+//! use gm_sm2::nonce::find_duplicate_k;
+//!
+//! let (pk, sk) = gen_keypair().unwrap();
+//! let a = sk.sign(None, b"a").unwrap();
+//! let b = sk.sign(None, b"b").unwrap();
+//! assert_eq!(None, find_duplicate_k(&pk, &[&a, &b]));
+//! ```
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_inv, fn_mul, fn_sub};
+use crate::fields::FieldModOperation;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::u256::{u256_to_be_bytes, U256};
+
+/// The private key behind two `r || s` signatures of `pk` made with the same
+/// `k`, `None` when they do not share it.
+pub fn recover_private_key_from_duplicate_k(pk: &Sm2PublicKey, sig1: &[u8], sig2: &[u8]) -> Option<Sm2PrivateKey> {
+    let (r1, s1) = split(sig1)?;
+    let (r2, s2) = split(sig2)?;
+    let den = fn_sub(&fn_add(&s1, &r1), &fn_add(&s2, &r2));
+    if den.is_zero() {
+        return None;
+    }
+    let d = fn_mul(&fn_sub(&s2, &s1), &fn_inv(&den));
+    let sk = Sm2PrivateKey::new(&u256_to_be_bytes(&d)).ok()?;
+    (sk.public_key == *pk).then_some(sk)
+}
+
+/// The indices of the first pair of signatures that share `k`, each pair is
+/// checked by recovering the key against `pk`.
+pub fn find_duplicate_k(pk: &Sm2PublicKey, sigs: &[&[u8]]) -> Option<(usize, usize)> {
+    for i in 0..sigs.len() {
+        for j in i + 1..sigs.len() {
+            if recover_private_key_from_duplicate_k(pk, sigs[i], sigs[j]).is_some() {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
+
+fn split(sig: &[u8]) -> Option<(U256, U256)> {
+    if sig.len() != 64 {
+        return None;
+    }
+    Some((fn_from_bytes(&sig[..32]).ok()?, fn_from_bytes(&sig[32..]).ok()?))
+}
+
+/// Panics in debug builds when this thread draws the nonce it drew last.
+/// Only a hash of the nonce is kept.
+#[cfg(debug_assertions)]
+pub(crate) fn check_fresh(k: &U256) {
+    use std::cell::Cell;
+
+    thread_local! {
+        static LAST: Cell<[u8; 32]> = const { Cell::new([0; 32]) };
+    }
+    let h = gm_sm3::sm3_hash(&u256_to_be_bytes(k));
+    assert_ne!(h, LAST.with(|last| last.replace(h)), "SM2 signing nonce reused");
+}
+
+#[cfg(not(debug_assertions))]
+#[inline(always)]
+pub(crate) fn check_fresh(_k: &U256) {}
+
+#[cfg(test)]
+mod test_nonce {
+    use super::*;
+    use crate::fields::fn64::fn_random;
+    use crate::key::gen_keypair;
+    use crate::u256::u256_from_be_bytes;
+    use gm_sm3::sm3_hash;
+
+    #[test]
+    fn test_duplicate_k() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let d = u256_from_be_bytes(&sk.to_be_bytes());
+        let k = fn_random();
+        let e1 = u256_from_be_bytes(&sm3_hash(b"one"));
+        let e2 = u256_from_be_bytes(&sm3_hash(b"two"));
+        let sig1 = Sm2PrivateKey::sign_with_k(&e1, &d, &k).unwrap();
+        let sig2 = Sm2PrivateKey::sign_with_k(&e2, &d, &k).unwrap();
+
+        let recovered = recover_private_key_from_duplicate_k(&pk, &sig1, &sig2).unwrap();
+        assert_eq!(sk.to_be_bytes(), recovered.to_be_bytes());
+
+        let fresh = sk.sign(None, b"three").unwrap();
+        assert!(recover_private_key_from_duplicate_k(&pk, &sig1, &fresh).is_none());
+        assert!(recover_private_key_from_duplicate_k(&pk, &sig1, &sig1).is_none());
+        let (other, _) = gen_keypair().unwrap();
+        assert!(recover_private_key_from_duplicate_k(&other, &sig1, &sig2).is_none());
+        assert_eq!(Some((1, 3)), find_duplicate_k(&pk, &[&fresh, &sig1, b"short", &sig2]));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "nonce reused")]
+    fn test_check_fresh() {
+        let k = fn_random();
+        check_fresh(&k);
+        check_fresh(&k);
+    }
+}
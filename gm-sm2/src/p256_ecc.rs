@@ -1,16 +1,47 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use gm_core::group::{window_mul, Choice, ConditionallySelectable, Group, Normalize};
+use gm_core::limb::{ct_equal, select};
+
 use crate::error::{Sm2Error, Sm2Result};
-use crate::fields::fp64::{fp_sqrt, fp_from_mont, fp_to_mont, SM2_P};
+use crate::fields::fp64::{fp_from_bytes, fp_sqrt, fp_from_mont, SM2_MODP_MONT_ONE, SM2_P};
 use crate::fields::FieldModOperation;
 use crate::sm2p256_table::SM2P256_PRECOMPUTED;
-use crate::u256::{u256_from_be_bytes, SM2_ZERO, U256};
+use crate::u256::{SM2_ZERO, U256};
 
-#[derive(Debug, Clone, Eq, PartialEq, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct Point {
     pub x: U256,
     pub y: U256,
     pub z: U256,
 }
 
+/// A point received from a peer in decryption or key exchange.
+///
+/// [`Point`] is always checked with [`Point::check`], wrap a point in
+/// [`UncheckedPoint`] to skip that when it was validated elsewhere.
+pub trait PeerPoint {
+    fn checked(&self) -> Sm2Result<Point>;
+}
+
+/// A point the caller has already validated, it is used as is.
+#[derive(Debug, Clone, Copy)]
+pub struct UncheckedPoint(pub Point);
+
+impl PeerPoint for Point {
+    fn checked(&self) -> Sm2Result<Point> {
+        self.check()?;
+        Ok(*self)
+    }
+}
+
+impl PeerPoint for UncheckedPoint {
+    fn checked(&self) -> Sm2Result<Point> {
+        Ok(self.0)
+    }
+}
+
 impl Point {
     pub fn zero() -> Point {
         Point {
@@ -44,6 +75,18 @@ impl Point {
         }
     }
 
+    /// Checks a point received from a peer: it must be on the curve and not
+    /// the infinity point. The cofactor of SM2 is 1, so such a point has order n.
+    pub fn check(&self) -> Sm2Result<()> {
+        if self.is_zero() {
+            return Err(Sm2Error::ZeroPoint);
+        }
+        if !self.is_valid() {
+            return Err(Sm2Error::CheckPointErr);
+        }
+        Ok(())
+    }
+
     pub fn is_valid_affine_point(&self) -> bool {
         // y^2 = x * (x^2 + a) + b
         let yy = self.y.fp_sqr();
@@ -88,30 +131,27 @@ impl Point {
     }
 
     pub(crate) fn from_byte(b: &[u8]) -> Sm2Result<Point> {
+        if b.is_empty() {
+            return Err(Sm2Error::InvalidPublic);
+        }
         let flag = b[0];
         // Compressed Point
         if flag == 0x02 || flag == 0x03 {
             if b.len() != 33 {
                 return Err(Sm2Error::InvalidPublic);
             }
-            let y_q;
-            if b[0] == 0x02 {
-                y_q = 0;
-            } else {
-                y_q = 1
-            }
-            let x = fp_to_mont(&U256::from_byte_be(&b[1..]));
+            let y_q = b[0] & 0x01;
+            let x = fp_from_bytes(&b[1..]).map_err(|_| Sm2Error::InvalidPublic)?;
             let xxx = x.fp_mul(&x).fp_mul(&x);
             let ax = x.fp_mul(&crate::fields::fp64::SM2_MODP_MONT_A);
             let yy = xxx
                 .fp_add(&ax)
                 .fp_add(&crate::fields::fp64::SM2_MODP_MONT_B);
 
-            let mut y = fp_sqrt(&yy)?;
-            let y_vec = fp_from_mont(&y).to_byte_be();
-            if y_vec[y_vec.len() - 1] & 0x01 != y_q {
-                y = SM2_P.fp_sub(&y);
-            }
+            // negate without branching on the parity of y
+            let y = fp_sqrt(&yy)?;
+            let odd = fp_from_mont(&y)[0] as u8 & 0x01;
+            let y = select(&y, &SM2_ZERO.fp_sub(&y), Choice::from(odd ^ y_q));
             Ok(Point {
                 x,
                 y,
@@ -120,11 +160,11 @@ impl Point {
         }
         // uncompressed Point
         else {
-            if b.len() != 65 {
+            if flag != 0x04 || b.len() != 65 {
                 return Err(Sm2Error::InvalidPublic);
             }
-            let x = fp_to_mont(&u256_from_be_bytes(&b[1..33]));
-            let y = fp_to_mont(&u256_from_be_bytes(&b[33..65]));
+            let x = fp_from_bytes(&b[1..33]).map_err(|_| Sm2Error::InvalidPublic)?;
+            let y = fp_from_bytes(&b[33..65]).map_err(|_| Sm2Error::InvalidPublic)?;
             Ok(Point {
                 x,
                 y,
@@ -133,6 +173,15 @@ impl Point {
         }
     }
 
+    /// `0x00` for the infinity, the uncompressed encoding otherwise
+    fn canonical_bytes(&self) -> Vec<u8> {
+        if self.is_zero() {
+            vec![0]
+        } else {
+            self.to_byte_be(false)
+        }
+    }
+
     pub fn neg(&self) -> Point {
         Point {
             x: self.x.clone(),
@@ -142,15 +191,15 @@ impl Point {
     }
 
     pub fn point_add(&self, p: &Point) -> Point {
-        // 0 + p2 = p2
-        if self.is_zero() {
-            return p.clone();
-        }
-        // p1 + 0 = p1
-        if p.is_zero() {
-            return self.clone();
-        }
+        // 0 + p2 = p2 and p1 + 0 = p1 are selected over the sum, computed
+        // anyway, so that scalar multiplication does not leak its zero windows
+        let sum = self.add_nonzero(p);
+        let r = Point::conditional_select(&sum, p, ct_equal(&self.z, &SM2_ZERO));
+        Point::conditional_select(&r, self, ct_equal(&p.z, &SM2_ZERO))
+    }
 
+    /// The sum of two points other than the identity
+    fn add_nonzero(&self, p: &Point) -> Point {
         let x1 = self.x;
         let y1 = self.y;
         let z1 = self.z;
@@ -173,6 +222,14 @@ impl Point {
             let s2 = y2_z1.fp_mul(&z1_sqr);
             let h = u2.fp_sub(&u1);
             let r = s2.fp_sub(&s1);
+            // the same point or its negation, in other coordinates
+            if h == SM2_ZERO {
+                return if r == SM2_ZERO {
+                    self.point_dbl()
+                } else {
+                    Point::zero()
+                };
+            }
             let hh = h.fp_sqr();
             let hhh = hh.fp_mul(&h);
             let v = u1.fp_mul(&hh);
@@ -194,45 +251,7 @@ impl Point {
 
     // P = [k]G
     pub fn scalar_mul(&self, scalar: &[u64]) -> Point {
-        let mut pre_table = vec![];
-        for _ in 0..16 {
-            pre_table.push(Point::zero());
-        }
-
-        let mut r = Point::zero();
-        pre_table[1 - 1] = *self;
-        pre_table[2 - 1] = pre_table[1 - 1].point_dbl();
-        pre_table[4 - 1] = pre_table[2 - 1].point_dbl();
-        pre_table[8 - 1] = pre_table[4 - 1].point_dbl();
-        pre_table[3 - 1] = pre_table[1 - 1].point_add(&pre_table[2 - 1]);
-        pre_table[6 - 1] = pre_table[3 - 1].point_dbl();
-        pre_table[7 - 1] = pre_table[1 - 1].point_add(&pre_table[6 - 1]);
-        pre_table[12 - 1] = pre_table[6 - 1].point_dbl();
-        pre_table[5 - 1] = pre_table[1 - 1].point_add(&pre_table[4 - 1]);
-        pre_table[10 - 1] = pre_table[5 - 1].point_dbl();
-        pre_table[14 - 1] = pre_table[7 - 1].point_dbl();
-        pre_table[9 - 1] = pre_table[1 - 1].point_add(&pre_table[8 - 1]);
-        pre_table[11 - 1] = pre_table[1 - 1].point_add(&pre_table[10 - 1]);
-        pre_table[13 - 1] = pre_table[1 - 1].point_add(&pre_table[12 - 1]);
-        pre_table[15 - 1] = pre_table[1 - 1].point_add(&pre_table[14 - 1]);
-
-        for i in 0..scalar.len() {
-            for j in 0..(64 / 4) {
-                let index = scalar[4 - 1 - i] >> ((64 / 4 - 1 - j) * 4);
-                if index & 0x0f != 0 {
-                    r = pre_table[((index - 1) & 0x0f) as usize].point_add(&r)
-                }
-
-                if i + 1 == scalar.len() && j + 1 == 64 / 4 {
-                    break;
-                }
-                r = r.point_dbl();
-                r = r.point_dbl();
-                r = r.point_dbl();
-                r = r.point_dbl();
-            }
-        }
-        r
+        window_mul(self, scalar)
     }
 
     pub fn point_dbl(&self) -> Point {
@@ -261,6 +280,91 @@ impl Point {
     }
 }
 
+/// Points are equal as points of the curve, whatever their Jacobian
+/// coordinates, and ordered by their encoding with the infinity first.
+impl PartialEq for Point {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical_bytes() == other.canonical_bytes()
+    }
+}
+
+impl Eq for Point {}
+
+impl Hash for Point {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical_bytes().hash(state)
+    }
+}
+
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.canonical_bytes().cmp(&other.canonical_bytes())
+    }
+}
+
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ConditionallySelectable for Point {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Point {
+            x: select(&a.x, &b.x, choice),
+            y: select(&a.y, &b.y, choice),
+            z: select(&a.z, &b.z, choice),
+        }
+    }
+}
+
+impl Group for Point {
+    fn identity() -> Self {
+        Point::zero()
+    }
+
+    fn is_identity(&self) -> bool {
+        self.is_zero()
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        self.point_add(rhs)
+    }
+
+    fn double(&self) -> Self {
+        self.point_dbl()
+    }
+
+    fn neg(&self) -> Self {
+        Point::neg(self)
+    }
+}
+
+impl Normalize for Point {
+    type Field = U256;
+
+    fn z(&self) -> U256 {
+        self.z
+    }
+
+    fn field_mul(a: &U256, b: &U256) -> U256 {
+        a.fp_mul(b)
+    }
+
+    fn field_inv(a: &U256) -> U256 {
+        a.fp_inv()
+    }
+
+    fn normalize_with(&self, z_inv: &U256) -> Self {
+        let z_inv2 = z_inv.fp_sqr();
+        Point {
+            x: self.x.fp_mul(&z_inv2),
+            y: self.y.fp_mul(&z_inv2.fp_mul(z_inv)),
+            z: SM2_MODP_MONT_ONE,
+        }
+    }
+}
+
 pub fn g_mul(g: &U256) -> Point {
     let mut r = Point::zero();
     let num = 8;
@@ -289,10 +393,101 @@ pub(crate) fn to_jacobi(x: &U256, y: &U256) -> Point {
 
 #[cfg(test)]
 mod test {
+    use gm_core::group::{batch_normalize, ladder_mul, wnaf_mul, FixedBase};
+
+    use crate::fields::fn64::{fn_random, SM2_N};
     use crate::fields::fp64::fp_to_mont;
     use crate::p256_ecc::{g_mul, to_jacobi, Point};
     use crate::u256::u256_from_be_bytes;
 
+    #[test]
+    fn test_generic_scalar_mul() {
+        let p = g_mul(&fn_random());
+        let table = FixedBase::new(&p, 4);
+        for _ in 0..8 {
+            let k = fn_random();
+            let expected = p.scalar_mul(&k).to_byte_be(false);
+            assert_eq!(expected, ladder_mul(&p, &k).to_byte_be(false));
+            assert_eq!(expected, wnaf_mul(&p, &k).to_byte_be(false));
+            assert_eq!(expected, table.mul(&k).to_byte_be(false));
+        }
+        assert!(table.mul(&SM2_N).is_zero());
+        assert!(p.scalar_mul(&[0; 4]).is_zero());
+        assert!(p.scalar_mul(&SM2_N).is_zero());
+
+        // P + P with P in two different Jacobian forms
+        let q = p.point_dbl().point_add(&p.neg());
+        assert_ne!(p.z, q.z);
+        assert_eq!(p, q);
+        assert_eq!(p.point_dbl().to_byte_be(false), p.point_add(&q).to_byte_be(false));
+        assert!(p.point_add(&q.neg()).is_zero());
+
+        let mut points = [p, Point::zero(), p.point_dbl(), q];
+        let affine: Vec<Vec<u8>> = points.iter().map(|p| p.to_byte_be(false)).collect();
+        batch_normalize(&mut points);
+        assert!(points[1].is_zero());
+        for (i, p) in points.iter().enumerate().filter(|(i, _)| *i != 1) {
+            assert_eq!(crate::fields::fp64::SM2_MODP_MONT_ONE, p.z);
+            assert_eq!(affine[i], p.to_byte_be(false));
+        }
+    }
+
+    #[test]
+    fn test_point_eq_hash_ord() {
+        use std::collections::{BTreeSet, HashMap, HashSet};
+
+        use crate::key::Sm2PublicKey;
+
+        let p = g_mul(&fn_random());
+        let q = p.point_dbl().point_add(&p.neg());
+        let points = [p, q, Point::zero(), p.point_dbl(), Point::zero()];
+        assert_eq!(3, points.iter().collect::<HashSet<_>>().len());
+        let sorted: Vec<Point> = points.iter().copied().collect::<BTreeSet<_>>().into_iter().collect();
+        assert_eq!(3, sorted.len());
+        assert!(sorted[0].is_zero());
+        assert!(sorted[1].to_byte_be(false) < sorted[2].to_byte_be(false));
+
+        let mut keys = HashMap::new();
+        keys.insert(Sm2PublicKey { point: p }, "alice");
+        let parsed = Sm2PublicKey::new(&q.to_byte_be(true)).unwrap();
+        assert_eq!(Some(&"alice"), keys.get(&parsed));
+        assert_ne!(parsed, Sm2PublicKey { point: p.neg() });
+    }
+
+    #[test]
+    fn test_decompress() {
+        use crate::error::Sm2Error;
+
+        for _ in 0..8 {
+            let p = g_mul(&fn_random());
+            for q in [p, p.neg()] {
+                let b = q.to_byte_be(true);
+                assert_eq!(q, Point::from_byte(&b).unwrap());
+            }
+        }
+        // about half of all x have no point, every other x decodes to the
+        // requested parity
+        let mut rejected = 0;
+        for i in 1..32u8 {
+            for flag in [0x02, 0x03] {
+                let mut b = [0u8; 33];
+                b[0] = flag;
+                b[32] = i;
+                match Point::from_byte(&b) {
+                    Ok(q) => {
+                        q.check().unwrap();
+                        assert_eq!(b[..], q.to_byte_be(true)[..]);
+                    }
+                    Err(e) => {
+                        assert_eq!(Sm2Error::FieldSqrtError, e);
+                        rejected += 1;
+                    }
+                }
+            }
+        }
+        assert!(rejected > 0 && rejected < 62);
+    }
+
     #[test]
     fn test_mod_op() {
         // Point at Infinity (1:1:0)
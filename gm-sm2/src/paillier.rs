@@ -0,0 +1,240 @@
+//! Paillier encryption, additively homomorphic, for the multiplicative to
+//! additive share conversion of [`crate::mta`].
+//!
+//! With `N = pq` and `g = N + 1`, `Enc(m) = (1 + mN)·r^N mod N^2`, so
+//! `Enc(a)·Enc(b) = Enc(a + b)` and `Enc(a)^k = Enc(k·a)` modulo `N`. Keys
+//! for real use are 2048 bits, [`PaillierSecretKey::generate`]. The primes
+//! come from Miller-Rabin with 40 random bases.
+//!
+//! # Security
+//!
+//! Only behind the `experimental` feature: nobody proves to the other parties
+//! that `N` is a product of two large primes, nor that a ciphertext encrypts a
+//! value in range, so every party holding or using a key must be trusted.
+use num_bigint::BigUint;
+use num_integer::Integer;
+use num_traits::{One, Zero};
+use rand::RngCore;
+
+use crate::error::{Sm2Error, Sm2Result};
+
+pub const PAILLIER_BITS: usize = 2048;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaillierPublicKey {
+    n: BigUint,
+    n2: BigUint,
+}
+
+impl PaillierPublicKey {
+    pub fn n(&self) -> &BigUint {
+        &self.n
+    }
+
+    /// `N`, big-endian
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.n.to_bytes_be()
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Self> {
+        let n = BigUint::from_bytes_be(b);
+        if n.bits() < 1024 || n.is_even() {
+            return Err(Sm2Error::PaillierOutOfRange);
+        }
+        Ok(Self::from_n(n))
+    }
+
+    fn from_n(n: BigUint) -> Self {
+        let n2 = &n * &n;
+        PaillierPublicKey { n, n2 }
+    }
+
+    /// Encrypt `m` in [0, N).
+    pub fn encrypt(&self, m: &BigUint) -> Sm2Result<BigUint> {
+        if m >= &self.n {
+            return Err(Sm2Error::PaillierOutOfRange);
+        }
+        let r = loop {
+            let r = random_below(&self.n);
+            if !r.is_zero() && r.gcd(&self.n).is_one() {
+                break r;
+            }
+        };
+        let gm = (BigUint::one() + m * &self.n) % &self.n2;
+        Ok(gm * r.modpow(&self.n, &self.n2) % &self.n2)
+    }
+
+    /// The ciphertext of the sum of the plaintexts
+    pub fn add(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        a * b % &self.n2
+    }
+
+    /// The ciphertext of `k` times the plaintext
+    pub fn mul(&self, c: &BigUint, k: &BigUint) -> BigUint {
+        c.modpow(k, &self.n2)
+    }
+
+    /// A ciphertext from a peer is in [1, N^2) and prime to N
+    pub(crate) fn check_ciphertext(&self, c: &BigUint) -> Sm2Result<()> {
+        if c >= &self.n2 || !c.gcd(&self.n).is_one() {
+            return Err(Sm2Error::PaillierOutOfRange);
+        }
+        Ok(())
+    }
+}
+
+/// Never prints the factors.
+#[derive(Clone)]
+pub struct PaillierSecretKey {
+    public: PaillierPublicKey,
+    phi: BigUint,
+    mu: BigUint,
+}
+
+impl std::fmt::Debug for PaillierSecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PaillierSecretKey({} bits)", self.public.n.bits())
+    }
+}
+
+impl PaillierSecretKey {
+    /// A fresh key with a [`PAILLIER_BITS`] modulus, which takes a while.
+    pub fn generate() -> Self {
+        Self::generate_modulus(PAILLIER_BITS)
+    }
+
+    /// A key with a `bits`-bit modulus, at least 1024 and even. Smaller than
+    /// [`PAILLIER_BITS`] only for tests.
+    pub fn generate_with_bits(bits: usize) -> Sm2Result<Self> {
+        if bits < 1024 || !bits.is_multiple_of(2) {
+            return Err(Sm2Error::PaillierModulusSize);
+        }
+        Ok(Self::generate_modulus(bits))
+    }
+
+    fn generate_modulus(bits: usize) -> Self {
+        loop {
+            let p = random_prime(bits / 2);
+            let q = random_prime(bits / 2);
+            let n = &p * &q;
+            if p == q || n.bits() as usize != bits {
+                continue;
+            }
+            let phi = (&p - 1u32) * (&q - 1u32);
+            // gcd(N, phi) = 1 holds for primes of the same size, mu exists
+            if let Some(mu) = phi.modinv(&n) {
+                return PaillierSecretKey {
+                    public: PaillierPublicKey::from_n(n),
+                    phi,
+                    mu,
+                };
+            }
+        }
+    }
+
+    pub fn public_key(&self) -> &PaillierPublicKey {
+        &self.public
+    }
+
+    pub fn decrypt(&self, c: &BigUint) -> Sm2Result<BigUint> {
+        let pk = &self.public;
+        pk.check_ciphertext(c)?;
+        // L(c^phi mod N^2) = (c^phi - 1) / N
+        let l = (c.modpow(&self.phi, &pk.n2) - 1u32) / &pk.n;
+        Ok(l * &self.mu % &pk.n)
+    }
+}
+
+/// Uniform in [0, bound)
+pub(crate) fn random_below(bound: &BigUint) -> BigUint {
+    let bytes = bound.bits().div_ceil(8) as usize + 16;
+    let mut buf = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buf);
+    // 128 extra bits make the modulo bias negligible
+    BigUint::from_bytes_be(&buf) % bound
+}
+
+fn random_prime(bits: usize) -> BigUint {
+    loop {
+        let mut buf = vec![0u8; bits / 8];
+        rand::thread_rng().fill_bytes(&mut buf);
+        // top two bits set so that the product has exactly 2 * bits bits
+        buf[0] |= 0xc0;
+        *buf.last_mut().unwrap() |= 1;
+        let candidate = BigUint::from_bytes_be(&buf);
+        if is_probable_prime(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+const SMALL_PRIMES: [u32; 24] = [3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97];
+
+/// Trial division, then Miller-Rabin with 40 random bases
+fn is_probable_prime(n: &BigUint) -> bool {
+    if n < &BigUint::from(100u32) {
+        return SMALL_PRIMES.iter().any(|&p| n == &BigUint::from(p));
+    }
+    if n.is_even() || SMALL_PRIMES.iter().any(|&p| (n % p).is_zero()) {
+        return false;
+    }
+    let n_1 = n - 1u32;
+    let s = n_1.trailing_zeros().unwrap();
+    let d = &n_1 >> s;
+    let three = BigUint::from(3u32);
+    'witness: for _ in 0..40 {
+        let a = random_below(&(n - &three)) + 2u32;
+        let mut x = a.modpow(&d, n);
+        if x.is_one() || x == n_1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&BigUint::from(2u32), n);
+            if x == n_1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod test_paillier {
+    use super::*;
+
+    #[test]
+    fn test_primes() {
+        for p in [2u32, 3, 97, 101, 65537, 2147483647] {
+            assert_eq!(p != 2, is_probable_prime(&BigUint::from(p)), "{}", p);
+        }
+        // a Carmichael number and a product of two primes
+        assert!(!is_probable_prime(&BigUint::from(561u32)));
+        assert!(!is_probable_prime(&(BigUint::from(65537u32) * 2147483647u32)));
+    }
+
+    #[test]
+    fn test_homomorphism() {
+        let sk = PaillierSecretKey::generate_with_bits(1024).unwrap();
+        let pk = sk.public_key();
+        assert_eq!(1024, pk.n().bits());
+        let (a, b) = (BigUint::from(123456789u64), BigUint::from(987654321u64));
+        let (ca, cb) = (pk.encrypt(&a).unwrap(), pk.encrypt(&b).unwrap());
+        assert_ne!(ca, pk.encrypt(&a).unwrap());
+        assert_eq!(a, sk.decrypt(&ca).unwrap());
+        assert_eq!(&a + &b, sk.decrypt(&pk.add(&ca, &cb)).unwrap());
+        assert_eq!(&a * 1000u32, sk.decrypt(&pk.mul(&ca, &BigUint::from(1000u32))).unwrap());
+        // wraps modulo N
+        let top = pk.n() - 1u32;
+        assert_eq!(a.clone() - 1u32, sk.decrypt(&pk.add(&ca, &pk.encrypt(&top).unwrap())).unwrap());
+
+        assert_eq!(Err(Sm2Error::PaillierOutOfRange), pk.encrypt(pk.n()));
+        assert_eq!(pk, &PaillierPublicKey::from_bytes(&pk.to_bytes()).unwrap());
+        assert!(PaillierPublicKey::from_bytes(&[0xff; 64]).is_err());
+        assert_eq!("PaillierSecretKey(1024 bits)", format!("{:?}", sk));
+
+        for bits in [0, 512, 1023, 1025] {
+            assert_eq!(Sm2Error::PaillierModulusSize, PaillierSecretKey::generate_with_bits(bits).unwrap_err());
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Pedersen commitments over the SM2 group.
+//!
+//! `C = [v]G + [r]H` commits to the value `v` with the blinding `r`. `H` is
+//! [`hash_to_curve`] of a fixed string, so nobody knows its logarithm to `G`
+//! and a commitment cannot be opened to two values. Commitments add up:
+//! `C(v1, r1) + C(v2, r2) = C(v1 + v2, r1 + r2)`, all modulo n, which is what
+//! balance proofs of confidential transactions rely on.
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_random, SM2_N};
+use crate::hash_to_curve::hash_to_curve;
+use crate::key::Sm2PublicKey;
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::{u256_cmp, U256};
+
+const GENERATOR_H_DST: &[u8] = b"SM2-PEDERSEN-V01-CS02-with-SM2_XMD:SM3_SSWU_RO_";
+
+/// The second generator `H`
+pub fn generator_h() -> Point {
+    hash_to_curve(b"H", GENERATOR_H_DST).expect("the tag is short enough")
+}
+
+fn check_scalar(a: &U256) -> Sm2Result<()> {
+    if u256_cmp(a, &SM2_N) >= 0 {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    Ok(())
+}
+
+/// `(r1 + r2 + ...) mod n`, the blinding of a sum of commitments
+pub fn sum_blindings(blindings: &[U256]) -> U256 {
+    blindings.iter().fold([0; 4], |s, r| fn_add(&s, r))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PedersenCommitment(Point);
+
+impl PedersenCommitment {
+    /// Commit to `value` with `blinding`, both below n.
+    pub fn new(value: &U256, blinding: &U256) -> Sm2Result<Self> {
+        check_scalar(value)?;
+        check_scalar(blinding)?;
+        Ok(PedersenCommitment(g_mul(value).point_add(&generator_h().scalar_mul(blinding))))
+    }
+
+    /// Commit to `value` with a fresh blinding, returned for the opening.
+    pub fn with_random_blinding(value: &U256) -> Sm2Result<(Self, U256)> {
+        let blinding = fn_random();
+        Ok((Self::new(value, &blinding)?, blinding))
+    }
+
+    /// Whether `(value, blinding)` opens this commitment
+    pub fn opens_to(&self, value: &U256, blinding: &U256) -> bool {
+        Self::new(value, blinding).is_ok_and(|c| c == *self)
+    }
+
+    pub fn add(&self, other: &PedersenCommitment) -> PedersenCommitment {
+        PedersenCommitment(self.0.point_add(&other.0))
+    }
+
+    pub fn sub(&self, other: &PedersenCommitment) -> PedersenCommitment {
+        PedersenCommitment(self.0.point_add(&other.0.neg()))
+    }
+
+    /// 33 bytes, the compressed point, or a single zero byte for the
+    /// commitment to zero with a zero blinding
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.0.is_zero() {
+            return vec![0];
+        }
+        self.0.to_byte_be(true)
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Self> {
+        if b == [0] {
+            return Ok(PedersenCommitment(Point::zero()));
+        }
+        Ok(PedersenCommitment(Sm2PublicKey::new(b)?.point))
+    }
+}
+
+impl PartialEq for PedersenCommitment {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for PedersenCommitment {}
+
+#[cfg(test)]
+mod test_pedersen {
+    use super::*;
+    use crate::fields::fn64::fn_sub;
+
+    #[test]
+    fn test_pedersen_open() {
+        let (c, r) = PedersenCommitment::with_random_blinding(&[42, 0, 0, 0]).unwrap();
+        assert!(c.opens_to(&[42, 0, 0, 0], &r));
+        assert!(!c.opens_to(&[43, 0, 0, 0], &r));
+        assert!(!c.opens_to(&[42, 0, 0, 0], &fn_add(&r, &[1, 0, 0, 0])));
+        assert!(!c.opens_to(&SM2_N, &r));
+        assert_eq!(c, PedersenCommitment::from_bytes(&c.to_bytes()).unwrap());
+        assert!(PedersenCommitment::new(&[1, 0, 0, 0], &SM2_N).is_err());
+        assert!(generator_h().is_valid());
+    }
+
+    #[test]
+    fn test_pedersen_homomorphic() {
+        // inputs 30 + 12 = outputs 40 + 2
+        let (in1, r1) = PedersenCommitment::with_random_blinding(&[30, 0, 0, 0]).unwrap();
+        let (in2, r2) = PedersenCommitment::with_random_blinding(&[12, 0, 0, 0]).unwrap();
+        let (out1, r3) = PedersenCommitment::with_random_blinding(&[40, 0, 0, 0]).unwrap();
+        let r4 = fn_sub(&sum_blindings(&[r1, r2]), &r3);
+        let out2 = PedersenCommitment::new(&[2, 0, 0, 0], &r4).unwrap();
+
+        let excess = in1.add(&in2).sub(&out1).sub(&out2);
+        assert!(excess.opens_to(&[0; 4], &[0; 4]));
+        assert_eq!(vec![0], excess.to_bytes());
+        assert_eq!(excess, PedersenCommitment::from_bytes(&[0]).unwrap());
+        assert!(in1.add(&in2).opens_to(&[42, 0, 0, 0], &sum_blindings(&[r1, r2])));
+    }
+}
@@ -6,11 +6,16 @@ use pkcs8::{
     der, DecodePublicKey, Document, EncodePrivateKey, EncodePublicKey, PrivateKeyInfo,
     SecretDocument, SubjectPublicKeyInfoRef,
 };
+use gm_core::encoding::pem_decode_label;
+use gm_sm3::pbkdf2::{pbkdf2_sm3, MAX_ITERATIONS};
+use gm_sm4::{CipherMode, Sm4CipherMode};
+use pkcs8::{DecodePrivateKey, ObjectIdentifier};
 use sec1::EcPrivateKey;
 
-use crate::error::Sm2Result;
+use crate::error::{Sm2Error, Sm2Result};
 use crate::key::{Sm2PrivateKey, Sm2PublicKey};
 use crate::p256_ecc::Point;
+use crate::x509::{to_asn1_oid, to_pem};
 use crate::{ALGORITHM_IDENTIFIER, ALGORITHM_OID, OID_SM2_PKCS8};
 
 impl Sm2PrivateKey {
@@ -74,7 +79,7 @@ impl TryFrom<SubjectPublicKeyInfoRef<'_>> for Sm2PublicKey {
             .subject_public_key
             .as_bytes()
             .ok_or_else(|| der::Tag::BitString.value_error())?;
-        Ok(Sm2PublicKey::new(public_key_bytes).unwrap())
+        Sm2PublicKey::new(public_key_bytes).map_err(|_| der::Tag::BitString.value_error().into())
     }
 }
 
@@ -90,6 +95,163 @@ impl EncodePrivateKey for Sm2PrivateKey {
     }
 }
 
+pub const OID_PBES2: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.5.13");
+pub const OID_PBKDF2: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.5.12");
+/// hmac-sm3 as used by GmSSL
+pub const OID_HMAC_SM3: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.156.10197.1.401.2");
+/// hmacWithSM3 as used by OpenSSL, accepted on import
+pub const OID_HMAC_SM3_OPENSSL: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.156.10197.1.401.3.1");
+pub const OID_SM4_CBC: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.156.10197.1.104.2");
+
+pub const PBES2_DEFAULT_ITERATIONS: u32 = 10000;
+
+/// Password protected PKCS#8 (`ENCRYPTED PRIVATE KEY`), PBES2 with
+/// PBKDF2-HMAC-SM3 and SM4-CBC, the format written by GmSSL `sm2keygen -pass`.
+impl Sm2PrivateKey {
+    pub fn to_encrypted_pkcs8_der(&self, password: &[u8], iterations: u32) -> Sm2Result<Vec<u8>> {
+        if iterations == 0 {
+            return Err(Sm2Error::InvalidPassword);
+        }
+        if iterations > MAX_ITERATIONS {
+            return Err(Sm2Error::TooManyIterations);
+        }
+        let pki = self.to_pkcs8_der().map_err(|_| Sm2Error::InvalidDer)?;
+        let salt: [u8; 16] = rand::random();
+        let iv: [u8; 16] = rand::random();
+        let key = Zeroizing::new(pbkdf2_sm3(password, &salt, iterations, 16).map_err(|_| Sm2Error::InvalidPassword)?);
+        let cipher = Sm4CipherMode::new(&key, CipherMode::Cbc).map_err(|_| Sm2Error::InvalidSecretKey)?;
+        let data = cipher
+            .encrypt(pki.as_bytes(), &iv)
+            .map_err(|_| Sm2Error::InvalidSecretKey)?;
+        Ok(encrypted_private_key_info(&salt, iterations, &iv, &data))
+    }
+
+    pub fn to_encrypted_pkcs8_pem(&self, password: &[u8], iterations: u32) -> Sm2Result<String> {
+        to_pem("ENCRYPTED PRIVATE KEY", &self.to_encrypted_pkcs8_der(password, iterations)?)
+    }
+
+    pub fn from_encrypted_pkcs8_der(der: &[u8], password: &[u8]) -> Sm2Result<Self> {
+        let (params, data) = parse_encrypted_private_key_info(der).map_err(|_| Sm2Error::InvalidDer)?;
+        if params.kdf != to_asn1_oid(&OID_PBKDF2)
+            || params.cipher != to_asn1_oid(&OID_SM4_CBC)
+            || params.iv.len() != 16
+            || params.iterations == 0
+        {
+            return Err(Sm2Error::InvalidDer);
+        }
+        if params.iterations > MAX_ITERATIONS {
+            return Err(Sm2Error::TooManyIterations);
+        }
+        match &params.prf {
+            Some(prf)
+                if *prf == to_asn1_oid(&OID_HMAC_SM3)
+                    || *prf == to_asn1_oid(&OID_HMAC_SM3_OPENSSL) => {}
+            _ => return Err(Sm2Error::InvalidDer),
+        }
+        if data.is_empty() || data.len() % 16 != 0 {
+            return Err(Sm2Error::InvalidDer);
+        }
+        let key = Zeroizing::new(pbkdf2_sm3(password, &params.salt, params.iterations, 16).map_err(|_| Sm2Error::InvalidPassword)?);
+        let cipher = Sm4CipherMode::new(&key, CipherMode::Cbc).map_err(|_| Sm2Error::InvalidSecretKey)?;
+        let pki = Zeroizing::new(
+            cipher
+                .decrypt(&data, &params.iv)
+                .map_err(|_| Sm2Error::InvalidPassword)?,
+        );
+        Self::from_pkcs8_der(&pki).map_err(|_| Sm2Error::InvalidPassword)
+    }
+
+    pub fn from_encrypted_pkcs8_pem(pem: &str, password: &[u8]) -> Sm2Result<Self> {
+        let der = pem_decode_label("ENCRYPTED PRIVATE KEY", pem).map_err(|_| Sm2Error::InvalidDer)?;
+        Self::from_encrypted_pkcs8_der(&der, password)
+    }
+}
+
+struct Pbes2Params {
+    kdf: yasna::models::ObjectIdentifier,
+    salt: Vec<u8>,
+    iterations: u32,
+    prf: Option<yasna::models::ObjectIdentifier>,
+    cipher: yasna::models::ObjectIdentifier,
+    iv: Vec<u8>,
+}
+
+fn encrypted_private_key_info(salt: &[u8], iterations: u32, iv: &[u8], data: &[u8]) -> Vec<u8> {
+    yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer.next().write_sequence(|writer| {
+                writer.next().write_oid(&to_asn1_oid(&OID_PBES2));
+                writer.next().write_sequence(|writer| {
+                    writer.next().write_sequence(|writer| {
+                        writer.next().write_oid(&to_asn1_oid(&OID_PBKDF2));
+                        writer.next().write_sequence(|writer| {
+                            writer.next().write_bytes(salt);
+                            writer.next().write_u32(iterations);
+                            writer.next().write_sequence(|writer| {
+                                writer.next().write_oid(&to_asn1_oid(&OID_HMAC_SM3));
+                                writer.next().write_null();
+                            });
+                        });
+                    });
+                    writer.next().write_sequence(|writer| {
+                        writer.next().write_oid(&to_asn1_oid(&OID_SM4_CBC));
+                        writer.next().write_bytes(iv);
+                    });
+                });
+            });
+            writer.next().write_bytes(data);
+        });
+    })
+}
+
+fn parse_encrypted_private_key_info(der: &[u8]) -> yasna::ASN1Result<(Pbes2Params, Vec<u8>)> {
+    yasna::parse_der(der, |reader| {
+        reader.read_sequence(|reader| {
+            let params = reader.next().read_sequence(|reader| {
+                let oid = reader.next().read_oid()?;
+                if oid != to_asn1_oid(&OID_PBES2) {
+                    return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+                }
+                reader.next().read_sequence(|reader| {
+                    let (kdf, salt, iterations, prf) = reader.next().read_sequence(|reader| {
+                        let kdf = reader.next().read_oid()?;
+                        let (salt, iterations, prf) = reader.next().read_sequence(|reader| {
+                            let salt = reader.next().read_bytes()?;
+                            let iterations = reader.next().read_u32()?;
+                            let _key_length = reader.read_optional(|reader| reader.read_u32())?;
+                            let prf = reader.read_optional(|reader| {
+                                reader.read_sequence(|reader| {
+                                    let oid = reader.next().read_oid()?;
+                                    reader.read_optional(|reader| reader.read_null())?;
+                                    Ok(oid)
+                                })
+                            })?;
+                            Ok((salt, iterations, prf))
+                        })?;
+                        Ok((kdf, salt, iterations, prf))
+                    })?;
+                    let (cipher, iv) = reader.next().read_sequence(|reader| {
+                        let cipher = reader.next().read_oid()?;
+                        let iv = reader.next().read_bytes()?;
+                        Ok((cipher, iv))
+                    })?;
+                    Ok(Pbes2Params {
+                        kdf,
+                        salt,
+                        iterations,
+                        prf,
+                        cipher,
+                        iv,
+                    })
+                })
+            })?;
+            let data = reader.next().read_bytes()?;
+            Ok((params, data))
+        })
+    })
+}
+
 impl EncodePublicKey for Sm2PublicKey {
     fn to_public_key_der(&self) -> pkcs8::spki::Result<Document> {
         let public_key_bytes = self.to_bytes(false);
@@ -114,10 +276,16 @@ impl FromStr for Sm2PublicKey {
 mod test_pkcs {
     use pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey, LineEnding};
 
+    use gm_sm3::pbkdf2::MAX_ITERATIONS;
+
+    use super::encrypted_private_key_info;
+    use crate::error::Sm2Error;
     use crate::key::{gen_keypair, Sm2Model, Sm2PrivateKey, Sm2PublicKey};
 
     const SM2_PUBLIC_PEM_EXAMPLE: &str = include_str!("../pki/public_key_pkcs8.pem");
     const SM2_PRIVATE_PEM_EXAMPLE: &str = include_str!("../pki/private_key_pkcs8.pem");
+    const SM2_ENCRYPTED_PEM_EXAMPLE: &str =
+        include_str!("../pki/private_key_pkcs8_encrypted.pem");
 
     #[test]
     fn test_pkcs8() {
@@ -147,4 +315,51 @@ mod test_pkcs {
         let plain = sk.decrypt(&encrypt, false, Sm2Model::C1C2C3).unwrap();
         assert_eq!(msg, plain)
     }
+
+    #[test]
+    fn test_encrypted_pkcs8_from_pem_file() {
+        // openssl pkcs8 -topk8 -v2 sm4-cbc -v2prf hmacWithSM3 -passout pass:123456
+        let sk = Sm2PrivateKey::from_encrypted_pkcs8_pem(SM2_ENCRYPTED_PEM_EXAMPLE, b"123456")
+            .unwrap();
+        assert_eq!(
+            "f2a197a4bda5d296c76e6ac869ec04476154a6d080f93f8ecab4dfa14ed1d048",
+            sk.to_hex_string()
+        );
+        assert_eq!(
+            Err(Sm2Error::InvalidPassword),
+            Sm2PrivateKey::from_encrypted_pkcs8_pem(SM2_ENCRYPTED_PEM_EXAMPLE, b"654321")
+                .map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_encrypted_pkcs8() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let pem = sk.to_encrypted_pkcs8_pem(b"password", 1000).unwrap();
+        println!("{}", pem);
+        let sk2 = Sm2PrivateKey::from_encrypted_pkcs8_pem(&pem, b"password").unwrap();
+        assert!(sk == sk2);
+
+        let msg = b"encrypted private key";
+        let sig = sk2.sign(None, msg).unwrap();
+        pk.verify(None, msg, &sig).unwrap();
+
+        assert!(Sm2PrivateKey::from_encrypted_pkcs8_pem(&pem, b"Password").is_err());
+        assert!(Sm2PrivateKey::from_encrypted_pkcs8_pem(&pem.replace("ENCRYPTED ", ""), b"password").is_err());
+    }
+
+    #[test]
+    fn test_encrypted_pkcs8_iterations() {
+        let (_, sk) = gen_keypair().unwrap();
+        assert_eq!(
+            Err(Sm2Error::TooManyIterations),
+            sk.to_encrypted_pkcs8_der(b"password", MAX_ITERATIONS + 1).map(|_| ())
+        );
+
+        let der = encrypted_private_key_info(&[0; 16], u32::MAX, &[0; 16], &[0; 16]);
+        assert_eq!(
+            Err(Sm2Error::TooManyIterations),
+            Sm2PrivateKey::from_encrypted_pkcs8_der(&der, b"password").map(|_| ())
+        );
+    }
 }
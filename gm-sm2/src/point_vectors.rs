@@ -0,0 +1,172 @@
+//! Point arithmetic against vectors from an independent affine
+//! implementation, `scripts/point_vectors.py`, committed at the end of this
+//! file: sums with the identity, `P + (-P)` and `P + P`, doubling the
+//! identity, and multiples by `0`, `1`, `n - 2`, `n - 1`, `n` and wide scalars,
+//! of `G` and of `[7]G`. Every scalar multiplication of the crate must agree.
+use gm_core::encoding::{hex_decode, hex_encode};
+use gm_core::group::{ladder_mul, window_mul, wnaf_mul, Group};
+
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::u256_from_be_bytes;
+
+/// The empty string is the infinity
+fn point(s: &str) -> Point {
+    if s.is_empty() {
+        return Point::zero();
+    }
+    Point::from_byte(&hex_decode(s).unwrap()).unwrap()
+}
+
+fn encode(p: &Point) -> String {
+    if p.is_zero() {
+        return String::new();
+    }
+    hex_encode(&p.to_byte_be(false))
+}
+
+#[test]
+fn test_add() {
+    for (a, b, sum) in ADD {
+        let (p, q) = (point(a), point(b));
+        assert_eq!(*sum, encode(&p.point_add(&q)), "{} + {}", a, b);
+        assert_eq!(*sum, encode(&Group::add(&q, &p)), "{} + {}", b, a);
+    }
+}
+
+#[test]
+fn test_double() {
+    for (a, double) in DOUBLE {
+        let p = point(a);
+        assert_eq!(*double, encode(&p.point_dbl()), "2 * {}", a);
+        assert_eq!(*double, encode(&p.point_add(&p)), "{0} + {0}", a);
+    }
+}
+
+#[test]
+fn test_mul() {
+    let g = encode(&g_mul(&[1, 0, 0, 0]));
+    for (k, a, product) in MUL {
+        let k = u256_from_be_bytes(&hex_decode(k).unwrap());
+        let p = point(a);
+        assert_eq!(*product, encode(&p.scalar_mul(&k)), "{:x?} * {}", k, a);
+        assert_eq!(*product, encode(&window_mul(&p, &k)));
+        assert_eq!(*product, encode(&ladder_mul(&p, &k)));
+        assert_eq!(*product, encode(&wnaf_mul(&p, &k)));
+        if *a == g {
+            assert_eq!(*product, encode(&g_mul(&k)));
+        }
+    }
+}
+
+// generated by scripts/point_vectors.py sm2, do not edit
+const ADD: &[(&str, &str, &str)] = &[
+    ("0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0456cefd60d7c87c000d58ef57fa73ba4d9c0dfa08c08a7331495c2e1da3f2bd5231b7e7e6cc8189f668535ce0f8eaf1bd6de84c182f6c8e716f780d3a970a23c3",
+     "04a97f7cd4b3c993b4be2daa8cdb41e24ca13f6bd945302244e26918f1d0509ebf530b5dd88c688ef5ccc5cec08a72150f7c400ee5cd045292aaacdd037458f6e6"),
+    ("0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c743c8c95c0b098863a642311c9496deac2f56788239d5b8c0fd20cd1adec60f5f",
+     ""),
+    ("",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0"),
+    ("0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0"),
+    ("",
+     "",
+     ""),
+    ("04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "0483b4a4de96a4d70f4aaf81826982d748ef22ea28be9d44de0a44248a36bb0a07e481c0d9ee8a98d4eeb6d6c6d7e74f8e3e707c8a438529492e663cd4373a2f24"),
+    ("043f47957861f7e60558a70c8d390f61f3734673824272e5a93db479efd37399b593ea67ec24ace7998a0fe04ad9059512de3217881b2b9848bf5c90a816d76f65",
+     "04b0355334592b8647c2611d4b254485f82c8bf6ee23b3b5c9dde56b480701513a1a4d8a3a05293dfca32dc753d3023cc79125293e5f2059fcf73c4bc62c2a9fef",
+     "04e587ee72edc77f20285ddf98204fb75749bbd05a99a166471f4f203adcd61b5a66606ba26d8d839eac42ee13ae65bcaf816dc6ab78051e227f06ef6bfc96cac2"),
+    ("0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c743c8c95c0b098863a642311c9496deac2f56788239d5b8c0fd20cd1adec60f5f",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     ""),
+    ("043f47957861f7e60558a70c8d390f61f3734673824272e5a93db479efd37399b593ea67ec24ace7998a0fe04ad9059512de3217881b2b9848bf5c90a816d76f65",
+     "04b0355334592b8647c2611d4b254485f82c8bf6ee23b3b5c9dde56b480701513ae5b275c4fad6c2035cd238ac2cfdc3386edad6c0a0dfa60408c3b439d3d56010",
+     "043148b2430c7daa50c446fae84c16b439c3921c3413b0d0d3664261cc5bc32ce3031b47bb98ff297960e64290ce1ab20b20623cd1ca206bb4f8ef2a52943b5db6"),
+];
+
+const DOUBLE: &[(&str, &str)] = &[
+    ("0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0456cefd60d7c87c000d58ef57fa73ba4d9c0dfa08c08a7331495c2e1da3f2bd5231b7e7e6cc8189f668535ce0f8eaf1bd6de84c182f6c8e716f780d3a970a23c3"),
+    ("",
+     ""),
+    ("04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "0483b4a4de96a4d70f4aaf81826982d748ef22ea28be9d44de0a44248a36bb0a07e481c0d9ee8a98d4eeb6d6c6d7e74f8e3e707c8a438529492e663cd4373a2f24"),
+    ("043f47957861f7e60558a70c8d390f61f3734673824272e5a93db479efd37399b593ea67ec24ace7998a0fe04ad9059512de3217881b2b9848bf5c90a816d76f65",
+     "04ee74bad849cc44d7050e0a5c7a701bf1fbe25828679b495bc0d4ef757f67df0c7d37840028eba0572af2c8c34757a1c8babb703be5fe86bf1483382cdbc89dd3"),
+    ("0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c743c8c95c0b098863a642311c9496deac2f56788239d5b8c0fd20cd1adec60f5f",
+     "0456cefd60d7c87c000d58ef57fa73ba4d9c0dfa08c08a7331495c2e1da3f2bd52ce481818337e760997aca31f07150e429217b3e6d093718f9087f2c568f5dc3c"),
+];
+
+const MUL: &[(&str, &str, &str)] = &[
+    ("0000000000000000000000000000000000000000000000000000000000000000",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     ""),
+    ("0000000000000000000000000000000000000000000000000000000000000000",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     ""),
+    ("0000000000000000000000000000000000000000000000000000000000000001",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0"),
+    ("0000000000000000000000000000000000000000000000000000000000000001",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1"),
+    ("0000000000000000000000000000000000000000000000000000000000000002",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0456cefd60d7c87c000d58ef57fa73ba4d9c0dfa08c08a7331495c2e1da3f2bd5231b7e7e6cc8189f668535ce0f8eaf1bd6de84c182f6c8e716f780d3a970a23c3"),
+    ("0000000000000000000000000000000000000000000000000000000000000002",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "0483b4a4de96a4d70f4aaf81826982d748ef22ea28be9d44de0a44248a36bb0a07e481c0d9ee8a98d4eeb6d6c6d7e74f8e3e707c8a438529492e663cd4373a2f24"),
+    ("0000000000000000000000000000000000000000000000000000000000000003",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "04a97f7cd4b3c993b4be2daa8cdb41e24ca13f6bd945302244e26918f1d0509ebf530b5dd88c688ef5ccc5cec08a72150f7c400ee5cd045292aaacdd037458f6e6"),
+    ("0000000000000000000000000000000000000000000000000000000000000003",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "046407be639bc5b738137cbf4e8aa505355b551c86a8976933e1de5f100950c8ab141caee612e7ab0712a9423fd80dea5460fcfd988fff6decf945321148e90344"),
+    ("fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54121",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0456cefd60d7c87c000d58ef57fa73ba4d9c0dfa08c08a7331495c2e1da3f2bd52ce481818337e760997aca31f07150e429217b3e6d093718f9087f2c568f5dc3c"),
+    ("fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54121",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "0483b4a4de96a4d70f4aaf81826982d748ef22ea28be9d44de0a44248a36bb0a071b7e3f251175672b114929392818b071c18f8374bc7ad6b7d199c32bc8c5d0db"),
+    ("fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54122",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c743c8c95c0b098863a642311c9496deac2f56788239d5b8c0fd20cd1adec60f5f"),
+    ("fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54122",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf157b82e6ff4c7178f9a32949163ed2d6ddfc86c88e8295b65eddc46b69a81ad43e"),
+    ("fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54123",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     ""),
+    ("fffffffeffffffffffffffffffffffff7203df6b21c6052b53bbf40939d54123",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     ""),
+    ("8000000000000000000000000000000000000000000000000000000000000000",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "04dcb53eb5b07c0513881158cfe779f44aa3fa4bfbdaeda1eb48bb387a1529db42571adb13e629a820f0ab2ad4e5fd9181083d8d22bc54738063d0aca20746e1aa"),
+    ("8000000000000000000000000000000000000000000000000000000000000000",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "04c3a91caaaf52ab23c4c01a0047675473afe20c35eac4d763ebd8c2ef958fceccbf32af168d27824b5440c16a603ff005b548d6f95044a6f2ea9464758b43e679"),
+    ("00000000000000000000000000000000ffffffffffffffffffffffffffffffff",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "04a45612f25eb72c26abdc472df524cab178b75deb15161922f4afcf71fb50e0202d12fd60d0f7cb073e35a0d5501e067edf9a53fa161fffc85d7fde8898e0dc97"),
+    ("00000000000000000000000000000000ffffffffffffffffffffffffffffffff",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "04d7c92479f851026add2840013559f7c607b2ced9036526ced84064aa172e05c842c2d27e82dfa638bb6134cb704ba1d002099aee54ebffba889dcfc6eb407dc8"),
+    ("ffed9235288bc781ae66267594c9c9500925e4749b575bd13653f8dd9b1f282f",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "04cffbfb9230b6b70ec250bbd1922d34ebe80ee9e2bfffb4457852d86c034d8ac3f35f506e41f828862876a89f755da0d9a1b5406153ebb5239112baffd080e655"),
+    ("ffed9235288bc781ae66267594c9c9500925e4749b575bd13653f8dd9b1f282f",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "04383cdfa8aae9d9f995cd774ac646405a02160b334771f0b1eaf17f5d909a5d6238a4b91405a7d9365a2b54e31e171492b8d26f930a765c0b0279b925d68cd288"),
+    ("82523e86feac7eb7dc38f519b91751dacdbd47d364be8049a372db8f6e405d94",
+     "0432c4ae2c1f1981195f9904466a39c9948fe30bbff2660be1715a4589334c74c7bc3736a2f4f6779c59bdcee36b692153d0a9877cc62a474002df32e52139f0a0",
+     "049474f3b84f32607fd72f02e63f2fa264a179fcecfd7f446911476402bfc4c0d689392163cd9d61c73a5eec4cb2775718d7161052f6ba89618712b365a8866922"),
+    ("82523e86feac7eb7dc38f519b91751dacdbd47d364be8049a372db8f6e405d94",
+     "04ddf092555409c19dfdbe86a75c139906a80198337744ee78cd27e384d9fcaf15847d18ffb38e87065cd6b6e9c12d2922037937707d6a49a2223b949657e52bc1",
+     "04a36db20e0c6fcdb118a54e4e8b8fb46b713800a7e42d068d593c15f25ef9059c146e064d6eb6601748039ea3efacb588ebe0f82db2ff8971b28b23535d1cca2d"),
+];
@@ -0,0 +1,205 @@
+//! Property tests of the arithmetic layers against num-bigint.
+//!
+//! The inputs come from a seeded generator that mixes uniform values with the
+//! edge cases limb-carry bugs hide behind: 0, 1, the moduli and their
+//! neighbours, all-ones limbs and values with a single limb set. A failure
+//! prints the case number, rerun with the same seed to reproduce it.
+use num_bigint::BigUint;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::fields::fn64::{fn_add, fn_inv, fn_mul, fn_sub, SM2_N};
+use crate::fields::fp64::{fp_from_mont, fp_sqrt, fp_to_mont, SM2_MODP_MONT_ONE, SM2_P};
+use crate::fields::FieldModOperation;
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::{
+    u256_add, u256_cmp, u256_from_be_bytes, u256_mul, u256_shl, u256_shr, u256_sub,
+    u256_to_be_bytes, U256,
+};
+
+const CASES: usize = 256;
+const SEED: u64 = 0x5eed_0617;
+
+fn big(a: &[u64]) -> BigUint {
+    let digits: Vec<u32> = a.iter().flat_map(|&w| [w as u32, (w >> 32) as u32]).collect();
+    BigUint::from_slice(&digits)
+}
+
+fn from_big(a: &BigUint) -> U256 {
+    let mut r = [0u64; 4];
+    for (r, d) in r.iter_mut().zip(a.to_u64_digits()) {
+        *r = d;
+    }
+    r
+}
+
+fn edge_cases() -> Vec<U256> {
+    let mut v = vec![
+        [0; 4],
+        [1, 0, 0, 0],
+        [u64::MAX; 4],
+        [u64::MAX, 0, 0, 0],
+        [0, 0, 0, 1 << 63],
+        [0, u64::MAX, 0, u64::MAX],
+        SM2_P,
+        SM2_N,
+    ];
+    for m in [SM2_P, SM2_N] {
+        v.push(u256_sub(&m, &[1, 0, 0, 0]).0);
+        v.push(u256_sub(&m, &[2, 0, 0, 0]).0);
+        v.push(u256_add(&m, &[1, 0, 0, 0]).0);
+    }
+    v
+}
+
+/// Any 256-bit value, an edge case one time in four
+fn gen_u256(rng: &mut StdRng) -> U256 {
+    if rng.gen_ratio(1, 4) {
+        let edges = edge_cases();
+        return edges[rng.gen_range(0..edges.len())];
+    }
+    let mut r: U256 = rng.gen();
+    // single limbs and all-ones limbs propagate carries the furthest
+    for w in r.iter_mut() {
+        match rng.gen_range(0..8) {
+            0 => *w = 0,
+            1 => *w = u64::MAX,
+            _ => {}
+        }
+    }
+    r
+}
+
+/// A value below `m`
+fn gen_below(rng: &mut StdRng, m: &U256) -> U256 {
+    from_big(&(big(&gen_u256(rng)) % big(m)))
+}
+
+/// A non-zero scalar mod n
+fn gen_scalar(rng: &mut StdRng) -> U256 {
+    loop {
+        let k = gen_below(rng, &SM2_N);
+        if k != [0; 4] {
+            return k;
+        }
+    }
+}
+
+fn check(name: &str, cases: usize, mut f: impl FnMut(&mut StdRng)) {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for i in 0..cases {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut rng)));
+        if let Err(e) = result {
+            panic!("property {} failed at case {} (seed {:#x}): {:?}", name, i, SEED, e);
+        }
+    }
+}
+
+fn point_eq(a: &Point, b: &Point) -> bool {
+    if a.is_zero() || b.is_zero() {
+        return a.is_zero() && b.is_zero();
+    }
+    let (a, b) = (a.to_affine_point(), b.to_affine_point());
+    a.x == b.x && a.y == b.y
+}
+
+#[test]
+fn prop_u256_ops() {
+    let r256 = BigUint::from(1u8) << 256;
+    check("u256", CASES, |rng| {
+        let (a, b) = (gen_u256(rng), gen_u256(rng));
+        let (ba, bb) = (big(&a), big(&b));
+
+        let (s, carry) = u256_add(&a, &b);
+        assert_eq!(big(&s) + (BigUint::from(carry as u8) << 256), &ba + &bb);
+        let (d, borrow) = u256_sub(&a, &b);
+        assert_eq!(big(&d), (&ba + &r256 - &bb) % &r256);
+        assert_eq!(borrow, ba < bb);
+        assert_eq!(big(&u256_mul(&a, &b)), &ba * &bb);
+        assert_eq!(u256_cmp(&a, &b), ba.cmp(&bb) as i32);
+
+        let n = rng.gen_range(0..256);
+        assert_eq!(big(&u256_shl(&a, n)), (&ba << n) % &r256);
+        assert_eq!(big(&u256_shr(&a, n)), &ba >> n);
+
+        assert_eq!(a, u256_from_be_bytes(&u256_to_be_bytes(&a)));
+    });
+}
+
+#[test]
+fn prop_fp_field() {
+    let p = big(&SM2_P);
+    check("fp", CASES, |rng| {
+        let (a, b, c) = (gen_below(rng, &SM2_P), gen_below(rng, &SM2_P), gen_below(rng, &SM2_P));
+        let (ma, mb, mc) = (fp_to_mont(&a), fp_to_mont(&b), fp_to_mont(&c));
+        assert_eq!(a, fp_from_mont(&ma));
+
+        // against the reference
+        assert_eq!(big(&fp_from_mont(&ma.fp_add(&mb))), (big(&a) + big(&b)) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_sub(&mb))), (big(&a) + &p - big(&b)) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_mul(&mb))), big(&a) * big(&b) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_sqr())), big(&a) * big(&a) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_neg())), (&p - big(&a)) % &p);
+        assert_eq!(ma, ma.fp_div2().fp_double());
+        assert_eq!(ma.fp_triple(), ma.fp_double().fp_add(&ma));
+
+        // ring axioms
+        assert_eq!(ma.fp_add(&mb), mb.fp_add(&ma));
+        assert_eq!(ma.fp_mul(&mb), mb.fp_mul(&ma));
+        assert_eq!(ma.fp_add(&mb).fp_add(&mc), ma.fp_add(&mb.fp_add(&mc)));
+        assert_eq!(ma.fp_mul(&mb).fp_mul(&mc), ma.fp_mul(&mb.fp_mul(&mc)));
+        assert_eq!(ma.fp_mul(&mb.fp_add(&mc)), ma.fp_mul(&mb).fp_add(&ma.fp_mul(&mc)));
+        assert!(ma.fp_add(&ma.fp_neg()).is_zero());
+
+        // field
+        if !ma.is_zero() {
+            assert_eq!(SM2_MODP_MONT_ONE, ma.fp_mul(&ma.fp_inv()));
+        }
+        let sq = ma.fp_sqr();
+        assert_eq!(sq, fp_sqrt(&sq).unwrap().fp_sqr());
+    });
+}
+
+#[test]
+fn prop_fn_scalars() {
+    let n = big(&SM2_N);
+    check("fn", CASES, |rng| {
+        let (a, b, c) = (gen_below(rng, &SM2_N), gen_below(rng, &SM2_N), gen_below(rng, &SM2_N));
+        assert_eq!(big(&fn_add(&a, &b)), (big(&a) + big(&b)) % &n);
+        assert_eq!(big(&fn_sub(&a, &b)), (big(&a) + &n - big(&b)) % &n);
+        assert_eq!(big(&fn_mul(&a, &b)), big(&a) * big(&b) % &n);
+        assert_eq!(fn_mul(&a, &fn_add(&b, &c)), fn_add(&fn_mul(&a, &b), &fn_mul(&a, &c)));
+        if a != [0; 4] {
+            assert_eq!([1, 0, 0, 0], fn_mul(&a, &fn_inv(&a)));
+        }
+    });
+}
+
+#[test]
+fn prop_point_group() {
+    check("point", CASES / 4, |rng| {
+        let (a, b) = (gen_scalar(rng), gen_scalar(rng));
+        let (pa, pb) = (g_mul(&a), g_mul(&b));
+        let pc = g_mul(&gen_scalar(rng));
+        assert!(pa.is_valid());
+
+        // g_mul is a homomorphism, the table and the generic ladder agree
+        assert!(point_eq(&g_mul(&fn_add(&a, &b)), &pa.point_add(&pb)));
+        assert!(point_eq(&pa.scalar_mul(&b), &pb.scalar_mul(&a)));
+        assert!(point_eq(&pa, &g_mul(&[1, 0, 0, 0]).scalar_mul(&a)));
+
+        // group laws
+        assert!(point_eq(&pa.point_add(&pb), &pb.point_add(&pa)));
+        assert!(point_eq(&pa.point_add(&pb).point_add(&pc), &pa.point_add(&pb.point_add(&pc))));
+        assert!(point_eq(&pa.point_add(&pa), &pa.point_dbl()));
+        assert!(pa.point_add(&pa.neg()).is_zero());
+        assert!(point_eq(&pa.point_add(&Point::zero()), &pa));
+        assert!(g_mul(&SM2_N).is_zero());
+
+        // encodings
+        for compress in [true, false] {
+            let decoded = Point::from_byte(&pa.to_byte_be(compress)).unwrap();
+            assert!(point_eq(&pa, &decoded));
+        }
+    });
+}
@@ -0,0 +1,138 @@
+//! Signatures with message recovery for short payloads such as QR-code tickets.
+//!
+//! The Pintsov-Vanstone scheme of ISO/IEC 9796-3 on the SM2 curve with SM3 and
+//! the SM2 KDF. The recoverable part `M_r` is carried masked inside the
+//! signature, only the clear part `M_c`, which may be empty, is sent alongside.
+//! With `R = [k]G = (x1, y1)`:
+//!
+//! * `C = (M_r ⊕ KDF(x1 || y1, |M_r|)) || SM3(x1 || M_r || y1)[..16]`
+//! * `h = SM3(ZA || C || M_c) mod n`, `s = k - d·h mod n`
+//! * the signature is `s || C`, 48 bytes more than `M_r`
+//!
+//! The verifier rebuilds `R = [s]G + [h]P`, unmasks `M_r` and checks the 16
+//! bytes of redundancy, which is what turns a forgery into a failure rather than
+//! a garbage message.
+use gm_core::ct_eq;
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_mul, fn_random, fn_sub};
+use crate::fields::fp64::fp_from_mont;
+use crate::fields::FieldModOperation;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::{u256_from_be_bytes, u256_to_be_bytes, U256};
+use crate::util::{compute_za, kdf, xor_bytes, DEFAULT_ID};
+
+/// Bytes of SM3 redundancy appended to the masked recoverable part
+pub const REDUNDANCY_LEN: usize = 16;
+
+/// `x1 || y1` of an affine point that is not the infinity
+fn coordinates(p: &Point) -> [u8; 64] {
+    let p = p.to_affine_point();
+    let mut b = [0u8; 64];
+    b[..32].copy_from_slice(&fp_from_mont(&p.x).to_byte_be());
+    b[32..].copy_from_slice(&fp_from_mont(&p.y).to_byte_be());
+    b
+}
+
+fn redundancy(xy: &[u8; 64], recoverable: &[u8]) -> Vec<u8> {
+    sm3_hash(&[&xy[..32], recoverable, &xy[32..]].concat())[..REDUNDANCY_LEN].to_vec()
+}
+
+/// `h = SM3(ZA || C || M_c) mod n`
+fn challenge(id: Option<&str>, pk: &Point, c: &[u8], clear: &[u8]) -> Sm2Result<U256> {
+    let za = compute_za(id.unwrap_or(DEFAULT_ID), pk)?;
+    let h = u256_from_be_bytes(&sm3_hash(&[&za[..], c, clear].concat()));
+    Ok(fn_add(&h, &[0; 4]))
+}
+
+impl Sm2PrivateKey {
+    /// Sign `clear` and embed `recoverable`, which must not be empty, in the signature.
+    pub fn sign_with_recovery(&self, id: Option<&str>, recoverable: &[u8], clear: &[u8]) -> Sm2Result<Vec<u8>> {
+        if recoverable.is_empty() {
+            return Err(Sm2Error::InvalidLength);
+        }
+        loop {
+            let k = fn_random();
+            let xy = coordinates(&g_mul(&k));
+            let mut c = xor_bytes(recoverable, &kdf(&xy, recoverable.len())?);
+            c.extend_from_slice(&redundancy(&xy, recoverable));
+
+            let h = challenge(id, &self.public_key.point, &c, clear)?;
+            if h.is_zero() {
+                continue;
+            }
+            let s = fn_sub(&k, &fn_mul(&self.d, &h));
+            if s.is_zero() {
+                continue;
+            }
+            return Ok([&u256_to_be_bytes(&s)[..], &c].concat());
+        }
+    }
+}
+
+impl Sm2PublicKey {
+    /// Verify a signature made by [`Sm2PrivateKey::sign_with_recovery`] over
+    /// `clear` and return the recovered part.
+    pub fn verify_with_recovery(&self, id: Option<&str>, sig: &[u8], clear: &[u8]) -> Sm2Result<Vec<u8>> {
+        if sig.len() <= 32 + REDUNDANCY_LEN {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let s = fn_from_bytes(&sig[..32])?;
+        if s.is_zero() {
+            return Err(Sm2Error::InvalidDigest);
+        }
+        let c = &sig[32..];
+        let h = challenge(id, &self.point, c, clear)?;
+        if h.is_zero() {
+            return Err(Sm2Error::InvalidDigest);
+        }
+        let r = g_mul(&s).point_add(&self.point.scalar_mul(&h));
+        if r.is_zero() {
+            return Err(Sm2Error::InvalidDigest);
+        }
+        let xy = coordinates(&r);
+        let (masked, tag) = c.split_at(c.len() - REDUNDANCY_LEN);
+        let recovered = xor_bytes(masked, &kdf(&xy, masked.len())?);
+        if !ct_eq(tag, &redundancy(&xy, &recovered)) {
+            return Err(Sm2Error::InvalidDigest);
+        }
+        Ok(recovered)
+    }
+}
+
+#[cfg(test)]
+mod test_recovery {
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_recovery_round_trip() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let ticket = b"G1234 2026-10-15 07A";
+        for clear in [&b""[..], b"Beijing-Shanghai"] {
+            let sig = sk.sign_with_recovery(None, ticket, clear).unwrap();
+            assert_eq!(ticket.len() + 48, sig.len());
+            assert_eq!(ticket.to_vec(), pk.verify_with_recovery(None, &sig, clear).unwrap());
+        }
+        let sig = sk.sign_with_recovery(Some("ALICE123@YAHOO.COM"), ticket, b"").unwrap();
+        assert!(pk.verify_with_recovery(None, &sig, b"").is_err());
+        assert!(pk.verify_with_recovery(Some("ALICE123@YAHOO.COM"), &sig, b"").is_ok());
+    }
+
+    #[test]
+    fn test_recovery_rejects() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let sig = sk.sign_with_recovery(None, b"ticket", b"clear").unwrap();
+        assert!(pk.verify_with_recovery(None, &sig, b"Clear").is_err());
+        for i in 0..sig.len() {
+            let mut b = sig.clone();
+            b[i] ^= 0x80;
+            assert!(pk.verify_with_recovery(None, &b, b"clear").is_err());
+        }
+        assert!(pk.verify_with_recovery(None, &sig[..48], b"clear").is_err());
+        let (other, _) = gen_keypair().unwrap();
+        assert!(other.verify_with_recovery(None, &sig, b"clear").is_err());
+        assert!(sk.sign_with_recovery(None, b"", b"clear").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Linkable ring signatures over the SM2 curve.
+//!
+//! The LSAG scheme of Liu, Wei and Wong with SM3 as the hash: a signature
+//! proves that one of the keys of a ring signed, without telling which. Each
+//! signature carries the key image `I = [d]Hp(P)` of the signing key, two
+//! signatures by the same key, over any messages and rings, have the same
+//! image and are linked by [`Sm2RingSignature::is_linked`].
+//!
+//! With the ring `P_0..P_n-1`, the signer at `π` and `m` the message:
+//!
+//! * `c_π+1 = H(L || I || m || [α]G || [α]Hp(P_π))`
+//! * `c_i+1 = H(L || I || m || [s_i]G + [c_i]P_i || [s_i]Hp(P_i) + [c_i]I)` around the ring
+//! * `s_π = α - c_π·d mod n`
+//!
+//! `Hp` is [`hash_to_curve`] under its own tag, `L` is the
+//! concatenation of the uncompressed keys. The signature is `I` compressed,
+//! `c_0` and the `s_i`, `65 + 32n` bytes.
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_mul, fn_random, fn_sub};
+use crate::hash_to_curve::hash_to_curve;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::{u256_cmp, u256_from_be_bytes, u256_to_be_bytes, U256};
+
+const HASH_TO_POINT_DST: &[u8] = b"SM2-LSAG-V01-CS02-with-SM2_XMD:SM3_SSWU_RO_";
+const CHALLENGE_TAG: &[u8] = b"SM2-LSAG-H";
+
+#[derive(Debug, Clone)]
+pub struct Sm2RingSignature {
+    key_image: Point,
+    c0: U256,
+    s: Vec<U256>,
+}
+
+impl Sm2RingSignature {
+    /// The 33-byte compressed key image, the same for every signature of a key
+    pub fn key_image(&self) -> Vec<u8> {
+        self.key_image.to_byte_be(true)
+    }
+
+    /// Whether both signatures were made with the same private key
+    pub fn is_linked(&self, other: &Sm2RingSignature) -> bool {
+        self.key_image() == other.key_image()
+    }
+
+    /// Number of keys in the ring
+    pub fn ring_len(&self) -> usize {
+        self.s.len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut b = self.key_image();
+        b.extend_from_slice(&u256_to_be_bytes(&self.c0));
+        for s in &self.s {
+            b.extend_from_slice(&u256_to_be_bytes(s));
+        }
+        b
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Self> {
+        if b.len() < 33 + 64 || !(b.len() - 33).is_multiple_of(32) {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let key_image = Sm2PublicKey::new(&b[..33])?.point;
+        let mut scalars = b[33..].chunks(32).map(fn_from_bytes);
+        let c0 = scalars.next().unwrap()?;
+        let s = scalars.collect::<Sm2Result<Vec<_>>>()?;
+        Ok(Sm2RingSignature { key_image, c0, s })
+    }
+}
+
+/// A point whose discrete log nobody knows
+fn hash_to_point(pk: &Point) -> Point {
+    hash_to_curve(&pk.to_byte_be(false), HASH_TO_POINT_DST).expect("the tag is short enough")
+}
+
+/// `H(prefix || L || R) mod n`, the prefix holds the ring, the key image and the message
+fn challenge(prefix: &[u8], l: &Point, r: &Point) -> U256 {
+    let h = sm3_hash(&[prefix, &l.to_byte_be(false), &r.to_byte_be(false)].concat());
+    fn_add(&u256_from_be_bytes(&h), &[0; 4])
+}
+
+fn challenge_prefix(ring: &[Sm2PublicKey], key_image: &Point, msg: &[u8]) -> Vec<u8> {
+    let mut prefix = CHALLENGE_TAG.to_vec();
+    prefix.extend_from_slice(&(ring.len() as u32).to_be_bytes());
+    for pk in ring {
+        prefix.extend_from_slice(&pk.to_bytes(false));
+    }
+    prefix.extend_from_slice(&key_image.to_byte_be(false));
+    prefix.extend_from_slice(&(msg.len() as u64).to_be_bytes());
+    prefix.extend_from_slice(msg);
+    prefix
+}
+
+/// `([s]G + [c]P, [s]Hp(P) + [c]I)`
+fn ring_step(pk: &Point, key_image: &Point, c: &U256, s: &U256) -> (Point, Point) {
+    let l = g_mul(s).point_add(&pk.scalar_mul(c));
+    let r = hash_to_point(pk).scalar_mul(s).point_add(&key_image.scalar_mul(c));
+    (l, r)
+}
+
+/// Sign `msg` as one of `ring`, which must contain the public key of `sk`.
+pub fn ring_sign(sk: &Sm2PrivateKey, ring: &[Sm2PublicKey], msg: &[u8]) -> Sm2Result<Sm2RingSignature> {
+    let own = sk.public_key.to_bytes(false);
+    let pi = ring
+        .iter()
+        .position(|pk| pk.to_bytes(false) == own)
+        .ok_or(Sm2Error::NotInRing)?;
+    let n = ring.len();
+    let hp = hash_to_point(&sk.public_key.point);
+    let key_image = hp.scalar_mul(&sk.d);
+    let prefix = challenge_prefix(ring, &key_image, msg);
+
+    let alpha = fn_random();
+    let mut c = vec![[0u64; 4]; n];
+    let mut s: Vec<U256> = (0..n).map(|_| fn_random()).collect();
+    c[(pi + 1) % n] = challenge(&prefix, &g_mul(&alpha), &hp.scalar_mul(&alpha));
+    for j in 1..n {
+        let i = (pi + j) % n;
+        let (l, r) = ring_step(&ring[i].point, &key_image, &c[i], &s[i]);
+        c[(i + 1) % n] = challenge(&prefix, &l, &r);
+    }
+    s[pi] = fn_sub(&alpha, &fn_mul(&c[pi], &sk.d));
+    Ok(Sm2RingSignature { key_image, c0: c[0], s })
+}
+
+/// Verify that one of `ring`, in the order it was signed with, signed `msg`.
+pub fn ring_verify(ring: &[Sm2PublicKey], msg: &[u8], sig: &Sm2RingSignature) -> Sm2Result<()> {
+    if ring.is_empty() || ring.len() != sig.s.len() {
+        return Err(Sm2Error::InvalidLength);
+    }
+    if sig.key_image.is_zero() || !sig.key_image.is_valid() {
+        return Err(Sm2Error::InvalidPoint);
+    }
+    let prefix = challenge_prefix(ring, &sig.key_image, msg);
+    let mut c = sig.c0;
+    for (pk, s) in ring.iter().zip(&sig.s) {
+        let (l, r) = ring_step(&pk.point, &sig.key_image, &c, s);
+        c = challenge(&prefix, &l, &r);
+    }
+    if u256_cmp(&c, &sig.c0) == 0 {
+        Ok(())
+    } else {
+        Err(Sm2Error::InvalidDigest)
+    }
+}
+
+#[cfg(test)]
+mod test_ring {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    fn ring_of(n: usize) -> (Vec<Sm2PublicKey>, Vec<Sm2PrivateKey>) {
+        (0..n).map(|_| gen_keypair().unwrap()).unzip()
+    }
+
+    #[test]
+    fn test_ring_sign_verify() {
+        let (ring, sks) = ring_of(4);
+        for sk in &sks {
+            let sig = ring_sign(sk, &ring, b"vote").unwrap();
+            assert_eq!(65 + 32 * 4, sig.to_bytes().len());
+            let sig = Sm2RingSignature::from_bytes(&sig.to_bytes()).unwrap();
+            ring_verify(&ring, b"vote", &sig).unwrap();
+            assert!(ring_verify(&ring, b"Vote", &sig).is_err());
+            assert!(ring_verify(&ring[..3], b"vote", &sig).is_err());
+            let mut swapped = ring.clone();
+            swapped.swap(0, 1);
+            assert!(ring_verify(&swapped, b"vote", &sig).is_err());
+        }
+
+        let (alone, sk) = ring_of(1);
+        ring_verify(&alone, b"vote", &ring_sign(&sk[0], &alone, b"vote").unwrap()).unwrap();
+        let (outsider, _) = gen_keypair().unwrap();
+        let (_, other) = gen_keypair().unwrap();
+        assert!(matches!(ring_sign(&other, &[outsider], b"vote"), Err(Sm2Error::NotInRing)));
+    }
+
+    #[test]
+    fn test_ring_linkability() {
+        let (ring, sks) = ring_of(3);
+        let a = ring_sign(&sks[1], &ring, b"first").unwrap();
+        let b = ring_sign(&sks[1], &ring[1..], b"second").unwrap();
+        let c = ring_sign(&sks[2], &ring, b"first").unwrap();
+        assert!(a.is_linked(&b));
+        assert!(!a.is_linked(&c));
+    }
+
+    #[test]
+    fn test_ring_rejects_tampering() {
+        let (ring, sks) = ring_of(3);
+        let bytes = ring_sign(&sks[0], &ring, b"vote").unwrap().to_bytes();
+        for i in (0..bytes.len()).step_by(7) {
+            let mut b = bytes.clone();
+            b[i] ^= 1;
+            if let Ok(sig) = Sm2RingSignature::from_bytes(&b) {
+                assert!(ring_verify(&ring, b"vote", &sig).is_err());
+            }
+        }
+        assert!(Sm2RingSignature::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Sm2RingSignature::from_bytes(&bytes[..33 + 32]).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! The SM2, SM3 and SM4 operations of the GM/T 0018 SDF device interface.
+//!
+//! [`SdfDevice`] mirrors the `SDF_*` functions an application uses with a
+//! crypto card, keys inside the device are addressed by index, each index
+//! holding a signing and an encryption key pair. [`SoftSdf`] implements it in
+//! software, so code written against the trait can move to a vendor card by
+//! swapping the implementation.
+//!
+//! # Example
+//! ```rust
+//! use gm_core::Verifier;
+//! use gm_sm2::sdf::{SdfDevice, SoftSdf};
+//!
+//! let mut device = SoftSdf::default();
+//! device.generate_key_pairs(1).unwrap();
+//! let pk = device.export_sign_public_key(1).unwrap();
+//! let digest = device.hash(Some((&pk, "1234567812345678")), b"hello").unwrap();
+//! let sig = device.internal_sign(1, &digest).unwrap();
+//! Verifier::verify(&pk, b"hello", &sig).unwrap();
+//! ```
+use std::collections::HashMap;
+
+use gm_sm3::Sm3Hasher;
+use gm_sm4::padding::Padding;
+use gm_sm4::{CipherMode, Sm4Cipher, Sm4CipherMode};
+use rand::RngCore;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::{gen_keypair, Sm2Model, Sm2PrivateKey, Sm2PublicKey};
+use crate::signature::Sm2Signature;
+use crate::util::compute_za;
+
+/// SGD_SM3 of GM/T 0006
+pub const SGD_SM3: u32 = 0x0000_0001;
+pub const SGD_SM4_ECB: u32 = 0x0000_0401;
+pub const SGD_SM4_CBC: u32 = 0x0000_0402;
+pub const SGD_SM4_CFB: u32 = 0x0000_0404;
+pub const SGD_SM4_OFB: u32 = 0x0000_0408;
+
+/// The `SDF_*` functions for SM2, SM3 and SM4, with the C structures replaced
+/// by the types of this crate.
+///
+/// An index without keys fails with [`Sm2Error::UnknownKey`], an `alg` the
+/// device does not have with [`Sm2Error::UnknownAlgorithm`].
+pub trait SdfDevice {
+    /// `SDF_GenerateRandom`
+    fn generate_random(&self, len: usize) -> Sm2Result<Vec<u8>>;
+
+    /// `SDF_ExportSignPublicKey_ECC`
+    fn export_sign_public_key(&self, index: u32) -> Sm2Result<Sm2PublicKey>;
+
+    /// `SDF_ExportEncPublicKey_ECC`
+    fn export_enc_public_key(&self, index: u32) -> Sm2Result<Sm2PublicKey>;
+
+    /// `SDF_GenerateKeyPair_ECC`, the private key leaves the device
+    fn generate_key_pair(&self) -> Sm2Result<(Sm2PublicKey, Sm2PrivateKey)>;
+
+    /// `SDF_InternalSign_ECC` of `digest = SM3(Z || M)`, see [`hash`](Self::hash)
+    fn internal_sign(&self, index: u32, digest: &[u8]) -> Sm2Result<Sm2Signature>;
+
+    /// `SDF_InternalVerify_ECC`
+    fn internal_verify(&self, index: u32, digest: &[u8], sig: &Sm2Signature) -> Sm2Result<()>;
+
+    /// `SDF_ExternalVerify_ECC`
+    fn external_verify(&self, public_key: &Sm2PublicKey, digest: &[u8], sig: &Sm2Signature) -> Sm2Result<()>;
+
+    /// `SDF_ExternalEncrypt_ECC`, the ciphertext as `C1 || C3 || C2`
+    fn external_encrypt(&self, public_key: &Sm2PublicKey, data: &[u8]) -> Sm2Result<Vec<u8>>;
+
+    /// `SDF_InternalDecrypt_ECC` with the encryption key pair of `index`
+    fn internal_decrypt(&self, index: u32, ciphertext: &[u8]) -> Sm2Result<Vec<u8>>;
+
+    /// `SDF_Encrypt` with one of the `SGD_SM4_*` modes, nothing is padded
+    fn encrypt(&self, key: &[u8], alg: u32, iv: &[u8], data: &[u8]) -> Sm2Result<Vec<u8>>;
+
+    /// `SDF_Decrypt`
+    fn decrypt(&self, key: &[u8], alg: u32, iv: &[u8], data: &[u8]) -> Sm2Result<Vec<u8>>;
+
+    /// `SDF_HashInit`, `SDF_HashUpdate` and `SDF_HashFinal` with `SGD_SM3`,
+    /// hashing Z of the signer's public key and ID first when one is given
+    fn hash(&self, signer: Option<(&Sm2PublicKey, &str)>, data: &[u8]) -> Sm2Result<[u8; 32]>;
+}
+
+struct KeyPairs {
+    sign: Sm2PrivateKey,
+    enc: Sm2PrivateKey,
+}
+
+/// A software [`SdfDevice`] holding its key pairs in memory
+#[derive(Default)]
+pub struct SoftSdf {
+    keys: HashMap<u32, KeyPairs>,
+}
+
+/// Never prints the keys.
+impl std::fmt::Debug for SoftSdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut indexes: Vec<&u32> = self.keys.keys().collect();
+        indexes.sort();
+        write!(f, "SoftSdf({:?})", indexes)
+    }
+}
+
+impl SoftSdf {
+    /// Generate the signing and encryption key pairs of `index`, replacing
+    /// any there, what the management tool of a card does.
+    pub fn generate_key_pairs(&mut self, index: u32) -> Sm2Result<()> {
+        let (_, sign) = gen_keypair()?;
+        let (_, enc) = gen_keypair()?;
+        self.import_key_pairs(index, sign, enc);
+        Ok(())
+    }
+
+    pub fn import_key_pairs(&mut self, index: u32, sign: Sm2PrivateKey, enc: Sm2PrivateKey) {
+        self.keys.insert(index, KeyPairs { sign, enc });
+    }
+
+    fn pairs(&self, index: u32) -> Sm2Result<&KeyPairs> {
+        self.keys.get(&index).ok_or(Sm2Error::UnknownKey)
+    }
+}
+
+fn sm4(key: &[u8], alg: u32, iv: &[u8], data: &[u8], encrypt: bool) -> Sm2Result<Vec<u8>> {
+    let cipher = Sm4Cipher::new(key).map_err(|_| Sm2Error::InvalidSecretKey)?;
+    let mode = match alg {
+        SGD_SM4_ECB => {
+            let blocks = data.chunks_exact(16);
+            if !blocks.remainder().is_empty() {
+                return Err(Sm2Error::InvalidLength);
+            }
+            let mut out = Vec::with_capacity(data.len());
+            for block in blocks {
+                let b = if encrypt { cipher.encrypt(block) } else { cipher.decrypt(block) };
+                out.extend_from_slice(&b.map_err(|_| Sm2Error::InvalidLength)?);
+            }
+            return Ok(out);
+        }
+        SGD_SM4_CBC => CipherMode::Cbc,
+        SGD_SM4_CFB => CipherMode::Cfb,
+        SGD_SM4_OFB => CipherMode::Ofb,
+        _ => return Err(Sm2Error::UnknownAlgorithm),
+    };
+    let c = Sm4CipherMode::with_padding(key, mode, Padding::NoPadding).map_err(|_| Sm2Error::InvalidSecretKey)?;
+    let out = if encrypt { c.encrypt(data, iv) } else { c.decrypt(data, iv) };
+    out.map_err(|_| Sm2Error::InvalidLength)
+}
+
+impl SdfDevice for SoftSdf {
+    fn generate_random(&self, len: usize) -> Sm2Result<Vec<u8>> {
+        let mut out = vec![0u8; len];
+        rand::thread_rng().fill_bytes(&mut out);
+        Ok(out)
+    }
+
+    fn export_sign_public_key(&self, index: u32) -> Sm2Result<Sm2PublicKey> {
+        Ok(self.pairs(index)?.sign.public_key)
+    }
+
+    fn export_enc_public_key(&self, index: u32) -> Sm2Result<Sm2PublicKey> {
+        Ok(self.pairs(index)?.enc.public_key)
+    }
+
+    fn generate_key_pair(&self) -> Sm2Result<(Sm2PublicKey, Sm2PrivateKey)> {
+        gen_keypair()
+    }
+
+    fn internal_sign(&self, index: u32, digest: &[u8]) -> Sm2Result<Sm2Signature> {
+        let key = &self.pairs(index)?.sign;
+        Sm2Signature::from_slice(&key.sign_raw(digest, &key.d)?)
+    }
+
+    fn internal_verify(&self, index: u32, digest: &[u8], sig: &Sm2Signature) -> Sm2Result<()> {
+        let pk = self.export_sign_public_key(index)?;
+        self.external_verify(&pk, digest, sig)
+    }
+
+    fn external_verify(&self, public_key: &Sm2PublicKey, digest: &[u8], sig: &Sm2Signature) -> Sm2Result<()> {
+        public_key.verify_raw(digest, &public_key.point, &sig.to_vec())
+    }
+
+    fn external_encrypt(&self, public_key: &Sm2PublicKey, data: &[u8]) -> Sm2Result<Vec<u8>> {
+        public_key.encrypt(data, false, Sm2Model::C1C3C2)
+    }
+
+    fn internal_decrypt(&self, index: u32, ciphertext: &[u8]) -> Sm2Result<Vec<u8>> {
+        self.pairs(index)?.enc.decrypt(ciphertext, false, Sm2Model::C1C3C2)
+    }
+
+    fn encrypt(&self, key: &[u8], alg: u32, iv: &[u8], data: &[u8]) -> Sm2Result<Vec<u8>> {
+        sm4(key, alg, iv, data, true)
+    }
+
+    fn decrypt(&self, key: &[u8], alg: u32, iv: &[u8], data: &[u8]) -> Sm2Result<Vec<u8>> {
+        sm4(key, alg, iv, data, false)
+    }
+
+    fn hash(&self, signer: Option<(&Sm2PublicKey, &str)>, data: &[u8]) -> Sm2Result<[u8; 32]> {
+        let mut hasher = Sm3Hasher::new();
+        if let Some((pk, id)) = signer {
+            hasher.update(&compute_za(id, &pk.point)?);
+        }
+        hasher.update(data);
+        Ok(hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod test_sdf {
+    use super::*;
+
+    #[test]
+    fn test_soft_sdf() {
+        let mut device = SoftSdf::default();
+        device.generate_key_pairs(3).unwrap();
+        assert_eq!("SoftSdf([3])", format!("{:?}", device));
+        assert_eq!(Err(Sm2Error::UnknownKey), device.export_sign_public_key(4));
+        assert_ne!(device.export_sign_public_key(3), device.export_enc_public_key(3));
+        assert_eq!(32, device.generate_random(32).unwrap().len());
+
+        // the signature is the one of Sm2PrivateKey::sign for the default ID
+        let pk = device.export_sign_public_key(3).unwrap();
+        let digest = device.hash(Some((&pk, "1234567812345678")), b"msg").unwrap();
+        let sig = device.internal_sign(3, &digest).unwrap();
+        device.internal_verify(3, &digest, &sig).unwrap();
+        pk.verify(None, b"msg", &sig.to_vec()).unwrap();
+        let (other, _) = device.generate_key_pair().unwrap();
+        assert!(device.external_verify(&other, &digest, &sig).is_err());
+        assert_eq!(gm_sm3::sm3_hash(b"msg"), device.hash(None, b"msg").unwrap());
+
+        let enc = device.export_enc_public_key(3).unwrap();
+        let c = device.external_encrypt(&enc, b"session key").unwrap();
+        assert_eq!(b"session key".to_vec(), device.internal_decrypt(3, &c).unwrap());
+        assert!(device.internal_decrypt(3, &device.external_encrypt(&pk, b"x").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_soft_sdf_sm4() {
+        let device = SoftSdf::default();
+        let key = [0x01u8, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef, 0xfe, 0xdc, 0xba, 0x98, 0x76, 0x54, 0x32, 0x10];
+        // GB/T 32907 example 1
+        let c = device.encrypt(&key, SGD_SM4_ECB, &[], &key).unwrap();
+        assert_eq!("681edf34d206965e86b3e94f536e4246", hex::encode(&c));
+        for alg in [SGD_SM4_ECB, SGD_SM4_CBC, SGD_SM4_CFB, SGD_SM4_OFB] {
+            let data = [0x5au8; 48];
+            let c = device.encrypt(&key, alg, &[7u8; 16], &data).unwrap();
+            assert_eq!(48, c.len());
+            assert_eq!(data.to_vec(), device.decrypt(&key, alg, &[7u8; 16], &c).unwrap());
+        }
+        assert_eq!(Err(Sm2Error::InvalidLength), device.encrypt(&key, SGD_SM4_CBC, &[0; 16], &[0; 15]));
+        assert_eq!(Err(Sm2Error::UnknownAlgorithm), device.encrypt(&key, SGD_SM3, &[0; 16], &[0; 16]));
+        assert_eq!(Err(Sm2Error::InvalidSecretKey), device.encrypt(&key[..8], SGD_SM4_ECB, &[], &[0; 16]));
+    }
+}
@@ -0,0 +1,199 @@
+//! Shamir secret sharing modulo the SM2 group order n, for backing up or
+//! escrowing private keys.
+//!
+//! A secret is the constant term of a random polynomial of degree `t - 1`,
+//! share `i` is its value at `x = i` for `i` in 1..=n. Any `t` shares give the
+//! secret back by Lagrange interpolation at 0, fewer tell nothing about it.
+//! A share is 33 bytes, the index followed by the value, big-endian.
+//!
+//! Fewer than `t` shares, or shares of different splits, reconstruct to an
+//! unrelated value without any error. [`Sm2PrivateKey::from_shares`] takes
+//! the expected public key to catch that.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::key::{gen_keypair, Sm2PrivateKey};
+//!
+//! let (pk, sk) = gen_keypair().unwrap();
+//! let shares = sk.split_shares(2, 3).unwrap();
+//! let sk2 = Sm2PrivateKey::from_shares(&shares[1..], &pk).unwrap();
+//! assert_eq!(sk.to_be_bytes(), sk2.to_be_bytes());
+//! ```
+use pkcs8::der::zeroize::Zeroize;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_inv, fn_mul, fn_random, fn_sub};
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::u256::{u256_to_be_bytes, U256};
+
+pub const SHARE_LEN: usize = 33;
+
+/// One share, never printed
+#[derive(Clone, PartialEq, Eq)]
+pub struct Share {
+    index: u8,
+    value: U256,
+}
+
+impl std::fmt::Debug for Share {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Share({}, ..)", self.index)
+    }
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl Share {
+    pub(crate) fn new(index: u8, value: U256) -> Share {
+        Share { index, value }
+    }
+
+    /// The x-coordinate, in 1..=255
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub(crate) fn value(&self) -> &U256 {
+        &self.value
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [&[self.index][..], &u256_to_be_bytes(&self.value)].concat()
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<Share> {
+        if b.len() != SHARE_LEN || b[0] == 0 {
+            return Err(Sm2Error::InvalidLength);
+        }
+        Ok(Share {
+            index: b[0],
+            value: fn_from_bytes(&b[1..])?,
+        })
+    }
+}
+
+/// Split a 32-byte big-endian secret into `n` shares, any `threshold` of which
+/// reconstruct it. The secret must be below the group order n, else
+/// [`Sm2Error::NonCanonical`], which for a uniform 32-byte secret happens
+/// with probability about 2^-32.
+pub fn split_secret(secret: &[u8], threshold: usize, n: usize) -> Sm2Result<Vec<Share>> {
+    if threshold == 0 || threshold > n || n > 255 {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let mut coefficients = vec![fn_from_bytes(secret)?];
+    coefficients.extend((1..threshold).map(|_| fn_random()));
+    let shares = (1..=n as u8)
+        .map(|i| Share::new(i, eval_poly(&coefficients, i)))
+        .collect();
+    coefficients.zeroize();
+    Ok(shares)
+}
+
+/// The secret behind `shares`, at least the threshold of them, with distinct
+/// indices.
+pub fn reconstruct_secret(shares: &[Share]) -> Sm2Result<[u8; 32]> {
+    let indices: Vec<u8> = shares.iter().map(Share::index).collect();
+    let mut secret = [0; 4];
+    for share in shares {
+        let l = lagrange_at_zero(&indices, share.index)?;
+        secret = fn_add(&secret, &fn_mul(&l, &share.value));
+    }
+    let out = u256_to_be_bytes(&secret).try_into().unwrap();
+    secret.zeroize();
+    Ok(out)
+}
+
+/// `Σ c_j·x^j mod n`
+pub(crate) fn eval_poly(coefficients: &[U256], x: u8) -> U256 {
+    let x = [x as u64, 0, 0, 0];
+    coefficients
+        .iter()
+        .rev()
+        .fold([0; 4], |acc, c| fn_add(&fn_mul(&acc, &x), c))
+}
+
+/// The Lagrange coefficient of `i` at 0 over `indices`, `Π x_j / (x_j - x_i)`
+/// for the other `x_j`. Fails for a zero or repeated index.
+pub(crate) fn lagrange_at_zero(indices: &[u8], i: u8) -> Sm2Result<U256> {
+    if i == 0 || indices.iter().filter(|&&j| j == i).count() != 1 {
+        return Err(Sm2Error::ZeroDivisor);
+    }
+    let xi = [i as u64, 0, 0, 0];
+    let (mut num, mut den) = ([1, 0, 0, 0], [1, 0, 0, 0]);
+    for &j in indices.iter().filter(|&&j| j != i) {
+        if j == 0 {
+            return Err(Sm2Error::ZeroDivisor);
+        }
+        let xj = [j as u64, 0, 0, 0];
+        num = fn_mul(&num, &xj);
+        den = fn_mul(&den, &fn_sub(&xj, &xi));
+    }
+    Ok(fn_mul(&num, &fn_inv(&den)))
+}
+
+impl Sm2PrivateKey {
+    /// Split d into `n` shares with the given threshold, see [`crate::shamir`].
+    pub fn split_shares(&self, threshold: usize, n: usize) -> Sm2Result<Vec<Share>> {
+        split_secret(&self.to_be_bytes(), threshold, n)
+    }
+
+    /// Reconstruct a key from its shares, failing with
+    /// [`Sm2Error::InvalidSecretKey`] unless it belongs to `public_key`.
+    pub fn from_shares(shares: &[Share], public_key: &Sm2PublicKey) -> Sm2Result<Sm2PrivateKey> {
+        let mut d = reconstruct_secret(shares)?;
+        let sk = Sm2PrivateKey::new(&d);
+        d.zeroize();
+        match sk {
+            Ok(sk) if sk.public_key == *public_key => Ok(sk),
+            _ => Err(Sm2Error::InvalidSecretKey),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_shamir {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    #[test]
+    fn test_split_reconstruct() {
+        let secret: Vec<u8> = (1..=32).collect();
+        let shares = split_secret(&secret, 3, 5).unwrap();
+        assert_eq!(5, shares.len());
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let picked: Vec<Share> = subset.iter().map(|&i| shares[i].clone()).collect();
+            assert_eq!(secret, reconstruct_secret(&picked).unwrap());
+        }
+        assert_eq!(secret, reconstruct_secret(&shares).unwrap());
+        assert_ne!(secret, reconstruct_secret(&shares[..2]).unwrap());
+        assert_eq!(Err(Sm2Error::ZeroDivisor), reconstruct_secret(&[shares[0].clone(), shares[0].clone()]));
+
+        let bytes = shares[3].to_bytes();
+        assert_eq!(SHARE_LEN, bytes.len());
+        assert_eq!(4, bytes[0]);
+        assert_eq!(shares[3], Share::from_bytes(&bytes).unwrap());
+        assert!(Share::from_bytes(&[&[0][..], &bytes[1..]].concat()).is_err());
+        assert_eq!("Share(4, ..)", format!("{:?}", shares[3]));
+
+        assert_eq!(Err(Sm2Error::InvalidLength), split_secret(&secret, 0, 3).map(|_| ()));
+        assert_eq!(Err(Sm2Error::InvalidLength), split_secret(&secret, 4, 3).map(|_| ()));
+        assert_eq!(Err(Sm2Error::NonCanonical), split_secret(&[0xff; 32], 2, 3).map(|_| ()));
+        // 1-of-n is the secret itself
+        assert_eq!(secret, split_secret(&secret, 1, 2).unwrap()[1].to_bytes()[1..]);
+    }
+
+    #[test]
+    fn test_key_shares() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let (other, _) = gen_keypair().unwrap();
+        let shares = sk.split_shares(2, 3).unwrap();
+        let sk2 = Sm2PrivateKey::from_shares(&[shares[2].clone(), shares[0].clone()], &pk).unwrap();
+        assert_eq!(sk.to_be_bytes(), sk2.to_be_bytes());
+        assert_eq!(Err(Sm2Error::InvalidSecretKey), Sm2PrivateKey::from_shares(&shares[..1], &pk).map(|_| ()));
+        assert_eq!(Err(Sm2Error::InvalidSecretKey), Sm2PrivateKey::from_shares(&shares, &other).map(|_| ()));
+    }
+}
@@ -0,0 +1,255 @@
+use num_bigint::BigUint;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_from_bytes, SM2_N};
+use crate::fields::FieldModOperation;
+use crate::u256::{u256_cmp, u256_from_be_bytes, u256_shr, u256_sub, U256};
+
+/// How strictly a signature is decoded before it is verified, the default
+/// accepts what this crate and GB/T 35276 produce and nothing else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Reject BER length forms and INTEGERs with redundant leading zero bytes
+    /// or a missing sign byte. Turned off, either is read as unsigned.
+    pub strict_der: bool,
+    /// Accept `s` above `n/2`. SM2 has no low-s rule, turn it off only for
+    /// signers known to normalize `s`.
+    pub allow_high_s: bool,
+    /// Accept `r` in `[n, 2^256)` and reduce it mod `n`
+    pub allow_non_canonical_r: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        VerifyOptions {
+            strict_der: true,
+            allow_high_s: true,
+            allow_non_canonical_r: false,
+        }
+    }
+}
+
+/// SM2 signature value (r, s)
+///
+/// [`crate::key::Sm2PrivateKey::sign`] returns the 64-byte `r || s` form,
+/// certificates, CMS and TLCP carry the DER form defined in GB/T 35276:
+///
+/// ```text
+/// SM2Signature ::= SEQUENCE {
+///     R INTEGER,
+///     S INTEGER
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sm2Signature {
+    pub r: U256,
+    pub s: U256,
+}
+
+impl Sm2Signature {
+    pub fn new(r: U256, s: U256) -> Sm2Result<Self> {
+        if r.is_zero() || s.is_zero() {
+            return Err(Sm2Error::ZeroSig);
+        }
+        if u256_cmp(&r, &SM2_N) >= 0 || u256_cmp(&s, &SM2_N) >= 0 {
+            return Err(Sm2Error::InvalidDigest);
+        }
+        Ok(Self { r, s })
+    }
+
+    pub(crate) fn from_slice(sig: &[u8]) -> Sm2Result<Self> {
+        Self::from_slice_with(sig, &VerifyOptions::default())
+    }
+
+    /// Decode the 64-byte `r || s` form under `opts`.
+    pub fn from_slice_with(sig: &[u8], opts: &VerifyOptions) -> Sm2Result<Self> {
+        if sig.len() != 64 {
+            return Err(Sm2Error::InvalidSignatureLen);
+        }
+        Self::from_scalars(&sig[..32], &sig[32..], opts)
+    }
+
+    fn from_scalars(r: &[u8], s: &[u8], opts: &VerifyOptions) -> Sm2Result<Self> {
+        let mut r = u256_from_be_bytes(r);
+        if u256_cmp(&r, &SM2_N) >= 0 {
+            if !opts.allow_non_canonical_r {
+                return Err(Sm2Error::NonCanonical);
+            }
+            // 2^256 < 2n, one subtraction reduces it
+            r = u256_sub(&r, &SM2_N).0;
+        }
+        let s = fn_from_bytes(s)?;
+        if !opts.allow_high_s && u256_cmp(&s, &u256_shr(&SM2_N, 1)) > 0 {
+            return Err(Sm2Error::HighS);
+        }
+        Self::new(r, s)
+    }
+
+    pub(crate) fn to_vec(self) -> Vec<u8> {
+        self.to_raw_bytes().to_vec()
+    }
+
+    /// The fixed-length `r || s`, each zero-padded to 32 bytes, the form many
+    /// payment APIs exchange instead of DER.
+    pub fn to_raw_bytes(&self) -> [u8; 64] {
+        let mut raw = [0u8; 64];
+        raw[..32].copy_from_slice(&self.r.to_byte_be());
+        raw[32..].copy_from_slice(&self.s.to_byte_be());
+        raw
+    }
+
+    /// Decode exactly 64 bytes of `r || s`. A DER signature, or one with the
+    /// leading zeros of r or s stripped, fails with
+    /// [`Sm2Error::InvalidSignatureLen`] rather than being misread.
+    pub fn from_raw_bytes(raw: &[u8]) -> Sm2Result<Self> {
+        Self::from_slice(raw)
+    }
+
+    pub fn to_der(self) -> Vec<u8> {
+        let r = BigUint::from_bytes_be(&self.r.to_byte_be());
+        let s = BigUint::from_bytes_be(&self.s.to_byte_be());
+        yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer.next().write_biguint(&r);
+                writer.next().write_biguint(&s);
+            });
+        })
+    }
+
+    pub fn from_der(der: &[u8]) -> Sm2Result<Self> {
+        Self::from_der_with(der, &VerifyOptions::default())
+    }
+
+    /// Decode the DER form under `opts`.
+    pub fn from_der_with(der: &[u8], opts: &VerifyOptions) -> Sm2Result<Self> {
+        let (r, s) = if opts.strict_der {
+            yasna::parse_der(der, |reader| {
+                reader.read_sequence(|reader| {
+                    let r = reader.next().read_biguint()?;
+                    let s = reader.next().read_biguint()?;
+                    Ok((r.to_bytes_be(), s.to_bytes_be()))
+                })
+            })
+        } else {
+            yasna::parse_ber(der, |reader| {
+                reader.read_sequence(|reader| {
+                    let r = read_integer_bytes(reader.next())?;
+                    let s = read_integer_bytes(reader.next())?;
+                    Ok((r, s))
+                })
+            })
+        }
+        .map_err(|_| Sm2Error::InvalidDer)?;
+        Self::from_scalars(&to_32_bytes(&r)?, &to_32_bytes(&s)?, opts)
+    }
+}
+
+/// The contents of an INTEGER however it is padded, read as unsigned
+fn read_integer_bytes(reader: yasna::BERReader) -> yasna::ASN1Result<Vec<u8>> {
+    let value = reader.read_tagged_der()?;
+    if value.tag() != yasna::tags::TAG_INTEGER || value.value().is_empty() {
+        return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+    }
+    Ok(value.value().to_vec())
+}
+
+fn to_32_bytes(v: &[u8]) -> Sm2Result<[u8; 32]> {
+    let start = v.iter().position(|&b| b != 0).unwrap_or(v.len());
+    let v = &v[start..];
+    if v.len() > 32 {
+        return Err(Sm2Error::InvalidDer);
+    }
+    let mut buf = [0u8; 32];
+    buf[32 - v.len()..].copy_from_slice(v);
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod test_signature {
+    use crate::key::gen_keypair;
+    use crate::signature::Sm2Signature;
+
+    #[test]
+    fn test_der_round_trip() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let sig = sk.sign(None, b"hello world").unwrap();
+        let parsed = Sm2Signature::from_slice(&sig).unwrap();
+        let der = parsed.to_der();
+        println!("der = {}", hex::encode(&der));
+        let decoded = Sm2Signature::from_der(&der).unwrap();
+        assert_eq!(parsed, decoded);
+        assert_eq!(sig, decoded.to_vec());
+        pk.verify(None, b"hello world", &decoded.to_vec()).unwrap();
+
+        assert!(Sm2Signature::from_der(&der[1..]).is_err());
+    }
+
+    #[test]
+    fn test_raw_bytes() {
+        use crate::error::Sm2Error;
+
+        // r and s with leading zero bytes keep their full width
+        let sig = Sm2Signature::new([0x1234, 0, 0, 0], [0, 0, 0, 0x00ff000000000000]).unwrap();
+        let raw = sig.to_raw_bytes();
+        assert_eq!([0u8; 30], raw[..30]);
+        assert_eq!([0x12, 0x34, 0x00, 0xff], raw[30..34]);
+        assert_eq!(sig, Sm2Signature::from_raw_bytes(&raw).unwrap());
+
+        let (pk, sk) = gen_keypair().unwrap();
+        let sig = Sm2Signature::from_raw_bytes(&sk.sign(None, b"pay").unwrap()).unwrap();
+        pk.verify(None, b"pay", &sig.to_raw_bytes()).unwrap();
+        let raw = sig.to_raw_bytes();
+        assert_eq!(Err(Sm2Error::InvalidSignatureLen), Sm2Signature::from_raw_bytes(&raw[1..]));
+        assert_eq!(Err(Sm2Error::InvalidSignatureLen), Sm2Signature::from_raw_bytes(&sig.to_der()));
+        assert_eq!(Err(Sm2Error::ZeroSig), Sm2Signature::from_raw_bytes(&[0u8; 64]));
+    }
+
+    /// SEQUENCE { INTEGER r, INTEGER s } with the contents as given
+    fn der_of(r: &[u8], s: &[u8]) -> Vec<u8> {
+        let body = [&[0x02, r.len() as u8][..], r, &[0x02, s.len() as u8], s].concat();
+        [&[0x30, body.len() as u8][..], &body].concat()
+    }
+
+    #[test]
+    fn test_verify_options() {
+        use crate::error::Sm2Error;
+        use crate::fields::fn64::SM2_N;
+        use crate::signature::VerifyOptions;
+        use crate::u256::{u256_add, u256_to_be_bytes};
+
+        let (pk, sk) = gen_keypair().unwrap();
+        let lenient = VerifyOptions {
+            strict_der: false,
+            allow_high_s: true,
+            allow_non_canonical_r: true,
+        };
+        let strict = VerifyOptions::default();
+        let sig = sk.sign(None, b"msg").unwrap();
+
+        // r with a redundant zero byte, s without its sign byte if it needs one
+        let der = der_of(&[&[0, 0][..], &sig[..32]].concat(), &sig[32..]);
+        assert!(pk.verify_der(None, b"msg", &der, &strict).err() == Some(Sm2Error::InvalidDer));
+        pk.verify_der(None, b"msg", &der, &lenient).unwrap();
+        let mut long_form = der_of(&sig[..32], &sig[32..]);
+        long_form.splice(1..2, [0x81, long_form[1]]);
+        assert!(pk.verify_der(None, b"msg", &long_form, &strict).is_err());
+        pk.verify_der(None, b"msg", &long_form, &lenient).unwrap();
+        assert!(pk.verify_der(None, b"other", &der, &lenient).is_err());
+
+        // n + 1 is r = 1 modulo n
+        let one = [&u256_to_be_bytes(&u256_add(&SM2_N, &[1, 0, 0, 0]).0)[..], &sig[32..]].concat();
+        assert!(Sm2Signature::from_slice_with(&one, &strict).err() == Some(Sm2Error::NonCanonical));
+        assert_eq!([1, 0, 0, 0], Sm2Signature::from_slice_with(&one, &lenient).unwrap().r);
+
+        let high = (0..64)
+            .map(|_| sk.sign(None, b"msg").unwrap())
+            .find(|sig| sig[32] >= 0x80)
+            .unwrap();
+        let low_s_only = VerifyOptions {
+            allow_high_s: false,
+            ..strict
+        };
+        pk.verify_with_options(None, b"msg", &high, &strict).unwrap();
+        assert!(pk.verify_with_options(None, b"msg", &high, &low_s_only).err() == Some(Sm2Error::HighS));
+    }
+}
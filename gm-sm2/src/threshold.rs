@@ -0,0 +1,678 @@
+//! t-of-n threshold SM2 signatures on the key shares of [`crate::dkg`], in
+//! the style of GG18 adapted to the SM2 signing equation.
+//!
+//! SM2 signs with `s = (1 + d)^-1·(k + r) - r`. Any `t` or more signers hold
+//! additive shares `w_i` of `1 + d` (the Lagrange weighted key shares, plus 1
+//! for the smallest index) and draw shares `k_i`, `γ_i` of a nonce and of a
+//! mask. Then:
+//!
+//! * round 1: each broadcasts a commitment to `R_i = [k_i]G` and its
+//!   Paillier encryptions of `k_i` and `γ_i`
+//! * round 2: each pair runs the multiplicative to additive conversion of
+//!   [`crate::mta`] twice, turning `γ_i·w_j` and `k_i·γ_j` into additive
+//!   shares, and reveals `R_i`
+//! * round 3: each checks the revealed nonces against the commitments and
+//!   broadcasts its share `δ_i` of `δ = γ·(1 + d)`, which reveals nothing
+//!   about `d` as `γ` is random
+//! * round 4: with `r = e + x(R)` each broadcasts `s_i = δ^-1·(ρ_i + γ_i·r)`,
+//!   `ρ_i` its share of `γ·k`, and anyone sums them to `s = Σ s_i - r`
+//!
+//! Every round consumes the previous state, so nonces never outlive a
+//! session. Any message that fails a check, and a final signature that does
+//! not verify under the public key, abort with [`Sm2Error::ThresholdAbort`];
+//! the signers then start over with fresh nonces.
+//!
+//! [`KeyShare::refresh`] rerandomizes all key shares, keeping the key, so
+//! that shares leaked before the refresh are useless with shares from after.
+//!
+//! # Security
+//!
+//! Only behind the `experimental` feature: every signer must be trusted to
+//! follow the protocol. There are no range proofs in MtA and no proof that a
+//! Paillier modulus is well formed, so a malicious signer can learn about the
+//! others' key shares from the aborts it causes and, over enough sessions,
+//! recover the key. Use it among parties that are honest but curious, never
+//! with untrusted ones.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::dkg::{combine, deal};
+//! use gm_sm2::paillier::PaillierSecretKey;
+//! use gm_sm2::threshold::sign_start;
+//!
+//! let dealings: Vec<_> = (1..=3).map(|i| deal(i, 2, 3).unwrap()).collect();
+//! let commitments: Vec<_> = dealings.iter().map(|(c, _)| c.clone()).collect();
+//! let keys: Vec<_> = (1..=3u8)
+//!     .map(|j| {
+//!         let received: Vec<_> = dealings.iter().map(|(_, s)| s[j as usize - 1].clone()).collect();
+//!         combine(j, &commitments, &received).unwrap()
+//!     })
+//!     .collect();
+//! // parties 1 and 3 sign, each with its own Paillier key
+//! let paillier = [PaillierSecretKey::generate_with_bits(1024).unwrap(), PaillierSecretKey::generate_with_bits(1024).unwrap()];
+//! let (a1, m1) = sign_start(&keys[0], &paillier[0], &[1, 3], None, b"msg").unwrap();
+//! let (b1, n1) = sign_start(&keys[2], &paillier[1], &[1, 3], None, b"msg").unwrap();
+//! let (a2, m2) = a1.next(&[n1]).unwrap();
+//! let (b2, n2) = b1.next(&[m1]).unwrap();
+//! let (a3, m3) = a2.next(&n2).unwrap();
+//! let (b3, n3) = b2.next(&m2).unwrap();
+//! let (a4, m4) = a3.next(&[n3]).unwrap();
+//! let (b4, n4) = b3.next(&[m3]).unwrap();
+//! let sig = a4.finish(&[n4]).unwrap();
+//! assert_eq!(sig, b4.finish(&[m4]).unwrap());
+//! keys[1].public_key().verify(None, b"msg", &sig).unwrap();
+//! ```
+use gm_sm3::sm3_hash;
+use num_bigint::BigUint;
+use pkcs8::der::zeroize::Zeroize;
+
+use crate::dkg::{eval_points, same_point, KeyShare};
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_inv, fn_mul, fn_random, fn_sub};
+use crate::fields::fp64::fp_from_mont;
+use crate::fields::FieldModOperation;
+use crate::key::Sm2PublicKey;
+use crate::mta::{mta_finish, mta_request, mta_respond};
+use crate::p256_ecc::{g_mul, Point};
+use crate::paillier::{PaillierPublicKey, PaillierSecretKey};
+use crate::shamir::{eval_poly, lagrange_at_zero, Share};
+use crate::u256::{u256_from_be_bytes, u256_to_be_bytes, U256};
+use crate::util::{compute_za, DEFAULT_ID};
+
+const COMMITMENT_TAG: &[u8] = b"SM2-THRESHOLD-COM";
+
+fn commit(from: u8, digest: &[u8; 32], nonce: &Point) -> [u8; 32] {
+    sm3_hash(&[COMMITMENT_TAG, &[from], digest, &nonce.to_byte_be(true)].concat())
+}
+
+/// `received` in the order of `others`, one message from each
+fn by_sender<'a, T>(others: &[u8], received: &'a [T], from: impl Fn(&T) -> u8) -> Sm2Result<Vec<&'a T>> {
+    if received.len() != others.len() {
+        return Err(Sm2Error::ThresholdAbort);
+    }
+    others
+        .iter()
+        .map(|&j| {
+            let mut found = received.iter().filter(|m| from(m) == j);
+            match (found.next(), found.next()) {
+                (Some(m), None) => Ok(m),
+                _ => Err(Sm2Error::ThresholdAbort),
+            }
+        })
+        .collect()
+}
+
+/// The secrets of one signer in one session, wiped on drop
+struct Session {
+    me: u8,
+    others: Vec<u8>,
+    public_key: Sm2PublicKey,
+    digest: [u8; 32],
+    paillier: PaillierSecretKey,
+    w: U256,
+    k: U256,
+    gamma: U256,
+    /// The shares of `δ` and `ρ`, summed up over rounds 2 and 3
+    delta: U256,
+    rho: U256,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.w.zeroize();
+        self.k.zeroize();
+        self.gamma.zeroize();
+        self.delta.zeroize();
+        self.rho.zeroize();
+    }
+}
+
+/// Round 1 broadcast
+#[derive(Debug, Clone)]
+pub struct Round1Message {
+    from: u8,
+    commitment: [u8; 32],
+    paillier: PaillierPublicKey,
+    enc_k: BigUint,
+    enc_gamma: BigUint,
+}
+
+/// Round 2 message from one signer to another
+#[derive(Debug, Clone)]
+pub struct Round2Message {
+    from: u8,
+    to: u8,
+    nonce: Point,
+    c_delta: BigUint,
+    c_rho: BigUint,
+}
+
+/// Round 3 broadcast, `δ_i`
+#[derive(Debug, Clone)]
+pub struct DeltaShare {
+    from: u8,
+    delta: U256,
+}
+
+/// Round 4 broadcast, `s_i`
+#[derive(Debug, Clone)]
+pub struct PartialSignature {
+    from: u8,
+    s: U256,
+}
+
+fn put_big(out: &mut Vec<u8>, x: &BigUint) {
+    let b = x.to_bytes_be();
+    out.extend_from_slice(&(b.len() as u16).to_be_bytes());
+    out.extend_from_slice(&b);
+}
+
+fn take<'a>(b: &mut &'a [u8], len: usize) -> Sm2Result<&'a [u8]> {
+    if b.len() < len {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let (head, rest) = b.split_at(len);
+    *b = rest;
+    Ok(head)
+}
+
+fn take_big(b: &mut &[u8]) -> Sm2Result<BigUint> {
+    let len = take(b, 2)?;
+    let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+    Ok(BigUint::from_bytes_be(take(b, len)?))
+}
+
+impl Round1Message {
+    pub fn from(&self) -> u8 {
+        self.from
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.from];
+        out.extend_from_slice(&self.commitment);
+        put_big(&mut out, &BigUint::from_bytes_be(&self.paillier.to_bytes()));
+        put_big(&mut out, &self.enc_k);
+        put_big(&mut out, &self.enc_gamma);
+        out
+    }
+
+    pub fn from_bytes(mut b: &[u8]) -> Sm2Result<Round1Message> {
+        let from = take(&mut b, 1)?[0];
+        let commitment = take(&mut b, 32)?.try_into().unwrap();
+        let paillier = PaillierPublicKey::from_bytes(&take_big(&mut b)?.to_bytes_be())?;
+        let (enc_k, enc_gamma) = (take_big(&mut b)?, take_big(&mut b)?);
+        if !b.is_empty() {
+            return Err(Sm2Error::InvalidLength);
+        }
+        Ok(Round1Message { from, commitment, paillier, enc_k, enc_gamma })
+    }
+}
+
+impl Round2Message {
+    pub fn from(&self) -> u8 {
+        self.from
+    }
+
+    /// The recipient
+    pub fn to(&self) -> u8 {
+        self.to
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.from, self.to];
+        out.extend_from_slice(&self.nonce.to_byte_be(true));
+        put_big(&mut out, &self.c_delta);
+        put_big(&mut out, &self.c_rho);
+        out
+    }
+
+    pub fn from_bytes(mut b: &[u8]) -> Sm2Result<Round2Message> {
+        let head = take(&mut b, 2)?;
+        let (from, to) = (head[0], head[1]);
+        let nonce = Sm2PublicKey::new(take(&mut b, 33)?)?.point;
+        let (c_delta, c_rho) = (take_big(&mut b)?, take_big(&mut b)?);
+        if !b.is_empty() {
+            return Err(Sm2Error::InvalidLength);
+        }
+        Ok(Round2Message { from, to, nonce, c_delta, c_rho })
+    }
+}
+
+impl DeltaShare {
+    pub fn from(&self) -> u8 {
+        self.from
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [&[self.from][..], &u256_to_be_bytes(&self.delta)].concat()
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<DeltaShare> {
+        if b.len() != 33 {
+            return Err(Sm2Error::InvalidLength);
+        }
+        Ok(DeltaShare { from: b[0], delta: fn_from_bytes(&b[1..])? })
+    }
+}
+
+impl PartialSignature {
+    pub fn from(&self) -> u8 {
+        self.from
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [&[self.from][..], &u256_to_be_bytes(&self.s)].concat()
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<PartialSignature> {
+        if b.len() != 33 {
+            return Err(Sm2Error::InvalidLength);
+        }
+        Ok(PartialSignature { from: b[0], s: fn_from_bytes(&b[1..])? })
+    }
+}
+
+/// Waiting for the other signers' [`Round1Message`]
+pub struct Round1 {
+    session: Session,
+    nonce: Point,
+}
+
+/// Waiting for the [`Round2Message`]s addressed to this signer
+pub struct Round2 {
+    session: Session,
+    nonce: Point,
+    commitments: Vec<[u8; 32]>,
+}
+
+/// Waiting for the other signers' [`DeltaShare`]
+pub struct Round3 {
+    session: Session,
+    r: U256,
+}
+
+/// Waiting for the other signers' [`PartialSignature`]
+pub struct Round4 {
+    others: Vec<u8>,
+    public_key: Sm2PublicKey,
+    digest: [u8; 32],
+    r: U256,
+    s: U256,
+}
+
+/// Start signing `msg` as the holder of `key` together with `signers`, the
+/// indices of at least the threshold of parties, this one included. Every
+/// signer needs its own Paillier key, which may serve many sessions.
+pub fn sign_start(
+    key: &KeyShare,
+    paillier: &PaillierSecretKey,
+    signers: &[u8],
+    id: Option<&'static str>,
+    msg: &[u8],
+) -> Sm2Result<(Round1, Round1Message)> {
+    let me = key.index();
+    let mut sorted = signers.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() != signers.len() || signers.len() < key.threshold() || !signers.contains(&me) {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let mut w = fn_mul(&lagrange_at_zero(signers, me)?, key.share.value());
+    if me == sorted[0] {
+        w = fn_add(&w, &[1, 0, 0, 0]);
+    }
+    let za = compute_za(id.unwrap_or(DEFAULT_ID), &key.public_key.point)?;
+    let digest = sm3_hash(&[&za[..], msg].concat());
+    let (k, gamma) = (fn_random(), fn_random());
+    let nonce = g_mul(&k);
+    let public = paillier.public_key();
+    let message = Round1Message {
+        from: me,
+        commitment: commit(me, &digest, &nonce),
+        paillier: public.clone(),
+        enc_k: mta_request(public, &k)?,
+        enc_gamma: mta_request(public, &gamma)?,
+    };
+    let session = Session {
+        me,
+        others: sorted.into_iter().filter(|&j| j != me).collect(),
+        public_key: key.public_key,
+        digest,
+        paillier: paillier.clone(),
+        delta: fn_mul(&gamma, &w),
+        rho: fn_mul(&k, &gamma),
+        w,
+        k,
+        gamma,
+    };
+    Ok((Round1 { session, nonce }, message))
+}
+
+impl Round1 {
+    /// Answer the other signers' encryptions, one message for each of them.
+    pub fn next(self, received: &[Round1Message]) -> Sm2Result<(Round2, Vec<Round2Message>)> {
+        let Round1 { mut session, nonce } = self;
+        let peers = by_sender(&session.others, received, |m| m.from)?;
+        let mut out = Vec::with_capacity(peers.len());
+        for m in &peers {
+            let (c_delta, beta_delta) = mta_respond(&m.paillier, &m.enc_gamma, &session.w).map_err(|_| Sm2Error::ThresholdAbort)?;
+            let (c_rho, beta_rho) = mta_respond(&m.paillier, &m.enc_k, &session.gamma).map_err(|_| Sm2Error::ThresholdAbort)?;
+            session.delta = fn_add(&session.delta, &beta_delta);
+            session.rho = fn_add(&session.rho, &beta_rho);
+            out.push(Round2Message {
+                from: session.me,
+                to: m.from,
+                nonce,
+                c_delta,
+                c_rho,
+            });
+        }
+        let commitments = peers.iter().map(|m| m.commitment).collect();
+        Ok((Round2 { session, nonce, commitments }, out))
+    }
+}
+
+impl Round2 {
+    /// Check the revealed nonces and finish the conversions.
+    pub fn next(self, received: &[Round2Message]) -> Sm2Result<(Round3, DeltaShare)> {
+        let Round2 { mut session, mut nonce, commitments } = self;
+        let peers = by_sender(&session.others, received, |m| m.from)?;
+        for (m, commitment) in peers.iter().zip(&commitments) {
+            if m.to != session.me || commit(m.from, &session.digest, &m.nonce) != *commitment {
+                return Err(Sm2Error::ThresholdAbort);
+            }
+            let alpha_delta = mta_finish(&session.paillier, &m.c_delta).map_err(|_| Sm2Error::ThresholdAbort)?;
+            let alpha_rho = mta_finish(&session.paillier, &m.c_rho).map_err(|_| Sm2Error::ThresholdAbort)?;
+            session.delta = fn_add(&session.delta, &alpha_delta);
+            session.rho = fn_add(&session.rho, &alpha_rho);
+            nonce = nonce.point_add(&m.nonce);
+        }
+        if nonce.is_zero() {
+            return Err(Sm2Error::ThresholdAbort);
+        }
+        let x1 = u256_from_be_bytes(&fp_from_mont(&nonce.to_affine_point().x).to_byte_be());
+        let r = fn_add(&u256_from_be_bytes(&session.digest), &x1);
+        if r.is_zero() {
+            return Err(Sm2Error::ThresholdAbort);
+        }
+        let share = DeltaShare {
+            from: session.me,
+            delta: session.delta,
+        };
+        Ok((Round3 { session, r }, share))
+    }
+}
+
+impl Round3 {
+    /// Unmask `δ` and produce this signer's `s_i`.
+    pub fn next(self, received: &[DeltaShare]) -> Sm2Result<(Round4, PartialSignature)> {
+        let Round3 { session, r } = self;
+        let peers = by_sender(&session.others, received, |m| m.from)?;
+        let delta = peers.iter().fold(session.delta, |acc, m| fn_add(&acc, &m.delta));
+        if delta.is_zero() {
+            return Err(Sm2Error::ThresholdAbort);
+        }
+        let s = fn_mul(&fn_inv(&delta), &fn_add(&session.rho, &fn_mul(&session.gamma, &r)));
+        let partial = PartialSignature { from: session.me, s };
+        let round = Round4 {
+            others: session.others.clone(),
+            public_key: session.public_key,
+            digest: session.digest,
+            r,
+            s,
+        };
+        Ok((round, partial))
+    }
+}
+
+impl Round4 {
+    /// The signature `r || s`, checked under the public key.
+    pub fn finish(self, received: &[PartialSignature]) -> Sm2Result<Vec<u8>> {
+        let peers = by_sender(&self.others, received, |m| m.from)?;
+        let sum = peers.iter().fold(self.s, |acc, m| fn_add(&acc, &m.s));
+        let s = fn_sub(&sum, &self.r);
+        let sig = [u256_to_be_bytes(&self.r), u256_to_be_bytes(&s)].concat();
+        self.public_key
+            .verify_raw(&self.digest, &self.public_key.point, &sig)
+            .map_err(|_| Sm2Error::ThresholdAbort)?;
+        Ok(sig)
+    }
+}
+
+/// The broadcast of one dealer in a refresh, `C_ik = [a_ik]G` for `k` in
+/// 1..t, the constant term being zero
+#[derive(Debug, Clone)]
+pub struct RefreshCommitments {
+    dealer: u8,
+    points: Vec<Point>,
+}
+
+impl RefreshCommitments {
+    pub fn dealer(&self) -> u8 {
+        self.dealer
+    }
+
+    /// The dealer index followed by the `t - 1` points, compressed
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![self.dealer];
+        for p in &self.points {
+            out.extend_from_slice(&p.to_byte_be(true));
+        }
+        out
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm2Result<RefreshCommitments> {
+        if b.is_empty() || !(b.len() - 1).is_multiple_of(33) || b[0] == 0 {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let points = b[1..]
+            .chunks(33)
+            .map(|p| Sm2PublicKey::new(p).map(|pk| pk.point))
+            .collect::<Sm2Result<Vec<Point>>>()?;
+        Ok(RefreshCommitments { dealer: b[0], points })
+    }
+}
+
+/// Party `dealer` deals a sharing of zero for a refresh, its commitments to
+/// broadcast and the shares to send, the one for party `j` at index `j - 1`.
+pub fn refresh_deal(dealer: u8, threshold: usize, n: usize) -> Sm2Result<(RefreshCommitments, Vec<Share>)> {
+    if dealer == 0 || dealer as usize > n || threshold == 0 || threshold > n || n > 255 {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let mut coefficients = vec![[0; 4]];
+    coefficients.extend((1..threshold).map(|_| fn_random()));
+    let points = coefficients[1..].iter().map(g_mul).collect();
+    let shares = (1..=n as u8)
+        .map(|j| Share::new(j, eval_poly(&coefficients, j)))
+        .collect();
+    coefficients.zeroize();
+    Ok((RefreshCommitments { dealer, points }, shares))
+}
+
+impl KeyShare {
+    /// Add the refresh shares received, `received[i]` from the dealer of
+    /// `commitments[i]`, failing with [`Sm2Error::InvalidShare`] on one that
+    /// does not match. All parties must apply the same dealers, and drop
+    /// their old key shares afterwards.
+    pub fn refresh(&self, commitments: &[RefreshCommitments], received: &[Share]) -> Sm2Result<KeyShare> {
+        let threshold = self.threshold();
+        if commitments.is_empty()
+            || commitments.len() != received.len()
+            || commitments.iter().any(|c| c.points.len() + 1 != threshold)
+        {
+            return Err(Sm2Error::InvalidLength);
+        }
+        let mut value = *self.share.value();
+        let mut group_commitments = self.group_commitments.clone();
+        for (c, share) in commitments.iter().zip(received) {
+            let expected = eval_points(&[&[Point::zero()][..], &c.points].concat(), self.index());
+            if share.index() != self.index() || !same_point(&g_mul(share.value()), &expected) {
+                return Err(Sm2Error::InvalidShare);
+            }
+            value = fn_add(&value, share.value());
+            for (sum, p) in group_commitments[1..].iter_mut().zip(&c.points) {
+                *sum = sum.point_add(p);
+            }
+        }
+        let refreshed = KeyShare {
+            share: Share::new(self.index(), value),
+            public_key: self.public_key,
+            group_commitments,
+        };
+        value.zeroize();
+        Ok(refreshed)
+    }
+}
+
+#[cfg(test)]
+mod test_threshold {
+    use super::*;
+    use crate::dkg::{combine, deal};
+    use crate::key::Sm2PrivateKey;
+
+    fn keygen(t: usize, n: u8) -> Vec<KeyShare> {
+        let dealings: Vec<_> = (1..=n).map(|i| deal(i, t, n as usize).unwrap()).collect();
+        let commitments: Vec<_> = dealings.iter().map(|(c, _)| c.clone()).collect();
+        (1..=n)
+            .map(|j| {
+                let received: Vec<_> = dealings.iter().map(|(_, s)| s[j as usize - 1].clone()).collect();
+                combine(j, &commitments, &received).unwrap()
+            })
+            .collect()
+    }
+
+    fn others<T: Clone>(all: &[T], from: impl Fn(&T) -> u8, me: u8) -> Vec<T> {
+        all.iter().filter(|m| from(m) != me).cloned().collect()
+    }
+
+    #[derive(PartialEq)]
+    enum Tamper {
+        Nothing,
+        Nonce,
+        Partial,
+    }
+
+    /// Run a session between `keys`, all signers finishing
+    fn run(keys: &[&KeyShare], paillier: &[PaillierSecretKey], msg: &[u8], tamper: Tamper) -> Sm2Result<Vec<u8>> {
+        let signers: Vec<u8> = keys.iter().map(|k| k.index()).collect();
+        let (states, m1): (Vec<_>, Vec<_>) = keys
+            .iter()
+            .zip(paillier)
+            .map(|(k, p)| sign_start(k, p, &signers, None, msg).unwrap())
+            .unzip();
+        let m1: Vec<_> = m1.iter().map(|m| Round1Message::from_bytes(&m.to_bytes()).unwrap()).collect();
+
+        let (states, m2): (Vec<_>, Vec<_>) = states
+            .into_iter()
+            .zip(&signers)
+            .map(|(s, &me)| s.next(&others(&m1, Round1Message::from, me)).unwrap())
+            .unzip();
+        let mut m2: Vec<_> = m2.concat().iter().map(|m| Round2Message::from_bytes(&m.to_bytes()).unwrap()).collect();
+        if tamper == Tamper::Nonce {
+            m2[0].nonce = g_mul(&fn_random());
+        }
+
+        let mut m3 = vec![];
+        let mut next = vec![];
+        for (s, &me) in states.into_iter().zip(&signers) {
+            let mine: Vec<_> = m2.iter().filter(|m| m.to() == me).cloned().collect();
+            let (s, m) = s.next(&mine)?;
+            next.push(s);
+            m3.push(DeltaShare::from_bytes(&m.to_bytes()).unwrap());
+        }
+
+        let (states, mut m4): (Vec<_>, Vec<_>) = next
+            .into_iter()
+            .zip(&signers)
+            .map(|(s, &me)| s.next(&others(&m3, DeltaShare::from, me)).unwrap())
+            .unzip();
+        if tamper == Tamper::Partial {
+            m4[0].s = fn_add(&m4[0].s, &[1, 0, 0, 0]);
+        }
+
+        let sigs = states
+            .into_iter()
+            .zip(&signers)
+            .map(|(s, &me)| s.finish(&others(&m4, PartialSignature::from, me)))
+            .collect::<Sm2Result<Vec<_>>>()?;
+        assert!(sigs.iter().all(|s| s == &sigs[0]));
+        Ok(sigs[0].clone())
+    }
+
+    #[test]
+    fn test_threshold_sign() {
+        let paillier: Vec<_> = (0..4).map(|_| PaillierSecretKey::generate_with_bits(1024).unwrap()).collect();
+
+        let keys = keygen(2, 3);
+        let pk = keys[0].public_key();
+        for subset in [[0, 1], [2, 0], [1, 2]] {
+            let picked: Vec<_> = subset.iter().map(|&i| &keys[i]).collect();
+            let sig = run(&picked, &paillier[..2], b"2-of-3", Tamper::Nothing).unwrap();
+            pk.verify(None, b"2-of-3", &sig).unwrap();
+        }
+        // more than the threshold may sign too
+        let all: Vec<_> = keys.iter().collect();
+        pk.verify(None, b"3", &run(&all, &paillier[..3], b"3", Tamper::Nothing).unwrap()).unwrap();
+
+        let keys = keygen(3, 5);
+        let pk = keys[0].public_key();
+        for subset in [[0, 1, 2], [4, 1, 3]] {
+            let picked: Vec<_> = subset.iter().map(|&i| &keys[i]).collect();
+            let sig = run(&picked, &paillier[1..], b"3-of-5", Tamper::Nothing).unwrap();
+            pk.verify(None, b"3-of-5", &sig).unwrap();
+        }
+
+        let p = &paillier[0];
+        assert_eq!(Err(Sm2Error::InvalidLength), sign_start(&keys[0], p, &[1, 2], None, b"m").map(|_| ()));
+        assert_eq!(Err(Sm2Error::InvalidLength), sign_start(&keys[0], p, &[1, 2, 2], None, b"m").map(|_| ()));
+        assert_eq!(Err(Sm2Error::InvalidLength), sign_start(&keys[0], p, &[2, 3, 4], None, b"m").map(|_| ()));
+    }
+
+    #[test]
+    fn test_threshold_abort() {
+        let paillier: Vec<_> = (0..2).map(|_| PaillierSecretKey::generate_with_bits(1024).unwrap()).collect();
+        let keys = keygen(2, 3);
+        let picked = [&keys[0], &keys[2]];
+        assert_eq!(Err(Sm2Error::ThresholdAbort), run(&picked, &paillier, b"m", Tamper::Nonce));
+        assert_eq!(Err(Sm2Error::ThresholdAbort), run(&picked, &paillier, b"m", Tamper::Partial));
+
+        // a missing or duplicated message
+        let (a, _) = sign_start(&keys[0], &paillier[0], &[1, 3], None, b"m").unwrap();
+        let (_, m) = sign_start(&keys[2], &paillier[1], &[1, 3], None, b"m").unwrap();
+        assert_eq!(Err(Sm2Error::ThresholdAbort), a.next(&[m.clone(), m]).map(|_| ()));
+    }
+
+    #[test]
+    fn test_refresh() {
+        let paillier: Vec<_> = (0..2).map(|_| PaillierSecretKey::generate_with_bits(1024).unwrap()).collect();
+        let keys = keygen(2, 3);
+        let pk = keys[0].public_key();
+        let dealings: Vec<_> = (1..=3).map(|i| refresh_deal(i, 2, 3).unwrap()).collect();
+        let commitments: Vec<_> = dealings
+            .iter()
+            .map(|(c, _)| RefreshCommitments::from_bytes(&c.to_bytes()).unwrap())
+            .collect();
+        let fresh: Vec<_> = keys
+            .iter()
+            .map(|k| {
+                let received: Vec<_> = dealings.iter().map(|(_, s)| s[k.index() as usize - 1].clone()).collect();
+                k.refresh(&commitments, &received).unwrap()
+            })
+            .collect();
+        assert!(fresh.iter().all(|k| k.public_key() == pk));
+        assert_ne!(keys[0].share().to_bytes(), fresh[0].share().to_bytes());
+        for k in &fresh {
+            assert_eq!(g_mul(k.share().value()).to_byte_be(false), fresh[0].public_share(k.index()).to_bytes(false));
+        }
+
+        let sig = run(&[&fresh[1], &fresh[2]], &paillier, b"m", Tamper::Nothing).unwrap();
+        pk.verify(None, b"m", &sig).unwrap();
+        // an old share with a new one is useless
+        assert!(Sm2PrivateKey::from_shares(&[fresh[0].share().clone(), fresh[1].share().clone()], &pk).is_ok());
+        assert!(Sm2PrivateKey::from_shares(&[keys[0].share().clone(), fresh[1].share().clone()], &pk).is_err());
+        assert_eq!(Err(Sm2Error::ThresholdAbort), run(&[&keys[0], &fresh[1]], &paillier, b"m", Tamper::Nothing));
+
+        let received: Vec<_> = dealings.iter().map(|(_, s)| s[1].clone()).collect();
+        assert_eq!(Err(Sm2Error::InvalidShare), keys[0].refresh(&commitments, &received).map(|_| ()));
+    }
+}
@@ -0,0 +1,71 @@
+//! Timing-leak tests of the operations on secrets, see [`gm_core::dudect`].
+//! Ignored by default, run them in release on a quiet machine:
+//!
+//! ```text
+//! cargo test --release -p gm-sm2 --features timing-tests -- --ignored timing
+//! ```
+//!
+//! [`g_mul`](crate::p256_ecc::g_mul) is left out: it skips the zero bytes of
+//! the scalar and is not claimed constant-time.
+use gm_core::dudect::{measure, Class};
+use gm_core::group::ladder_mul;
+
+use crate::fields::fn64::{fn_inv, fn_random, SM2_N};
+use crate::fields::fp64::SM2_MODP_MONT_ONE;
+use crate::fields::FieldModOperation;
+use crate::p256_ecc::g_mul;
+use crate::u256::U256;
+
+const SAMPLES: usize = 200_000;
+
+/// n - 1, all ones in the top bits, against uniform scalars
+fn scalar(class: Class) -> U256 {
+    match class {
+        Class::Fixed => [SM2_N[0] - 1, SM2_N[1], SM2_N[2], SM2_N[3]],
+        Class::Random => fn_random(),
+    }
+}
+
+#[test]
+#[ignore]
+fn test_timing_scalar_mul() {
+    let p = g_mul(&fn_random());
+    let report = measure(SAMPLES, |class, _| scalar(class), |k| p.scalar_mul(k).is_zero());
+    assert!(!report.leaks(), "{:?}", report);
+}
+
+#[test]
+#[ignore]
+fn test_timing_ladder_mul() {
+    let p = g_mul(&fn_random());
+    let report = measure(SAMPLES, |class, _| scalar(class), |k| ladder_mul(&p, k).is_zero());
+    assert!(!report.leaks(), "{:?}", report);
+}
+
+#[test]
+#[ignore]
+fn test_timing_fp_inv() {
+    let report = measure(
+        SAMPLES,
+        |class, _| match class {
+            Class::Fixed => SM2_MODP_MONT_ONE,
+            Class::Random => fn_random(),
+        },
+        |a| a.fp_inv()[0] == 0,
+    );
+    assert!(!report.leaks(), "{:?}", report);
+}
+
+#[test]
+#[ignore]
+fn test_timing_fn_inv() {
+    let report = measure(
+        SAMPLES,
+        |class, _| match class {
+            Class::Fixed => [1, 0, 0, 0],
+            Class::Random => fn_random(),
+        },
+        |a| fn_inv(a)[0] == 0,
+    );
+    assert!(!report.leaks(), "{:?}", report);
+}
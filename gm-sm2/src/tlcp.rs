@@ -0,0 +1,264 @@
+//! The handshake cryptography of TLCP (GB/T 38636-2020) for its ECC suites.
+//!
+//! The record layer and the handshake state machine are left to the TLS
+//! stack, this covers the parts that are easy to get subtly wrong:
+//!
+//! * the PRF, `P_SM3` of TLS 1.2 with HMAC-SM3
+//! * the master secret and the key block of [`CipherSuite`]
+//! * the `verify_data` of the Finished messages
+//! * the 48-byte pre-master secret, SM2 encrypted (DER, C1C3C2) to the
+//!   server's encryption certificate
+//!
+//! # Example
+//! ```rust
+//! use gm_sm2::key::gen_keypair;
+//! use gm_sm2::tlcp::*;
+//!
+//! let (enc_pk, enc_sk) = gen_keypair().unwrap();
+//! let (client_random, server_random) = ([1u8; 32], [2u8; 32]);
+//!
+//! // client
+//! let pms = generate_pre_master_secret();
+//! let client_key_exchange = encrypt_pre_master_secret(&enc_pk, &pms).unwrap();
+//! let ms = master_secret(&pms, &client_random, &server_random);
+//!
+//! // server
+//! let pms2 = decrypt_pre_master_secret(&enc_sk, &client_key_exchange);
+//! let ms2 = master_secret(&pms2, &client_random, &server_random);
+//! assert_eq!(ms, ms2);
+//!
+//! let keys = key_block(&ms, &client_random, &server_random, CipherSuite::EccSm4CbcSm3);
+//! assert_eq!(16, keys.client_write_key.len());
+//! ```
+use gm_core::group::{Choice, ConditionallySelectable};
+use gm_sm3::hmac::sm3_hmac;
+use gm_sm3::sm3_hash;
+use rand::RngCore;
+
+use crate::error::Sm2Result;
+use crate::key::{Sm2Model, Sm2PrivateKey, Sm2PublicKey};
+
+/// The protocol version of TLCP, also the first two bytes of the pre-master
+/// secret
+pub const TLCP_VERSION: [u8; 2] = [0x01, 0x01];
+
+pub const PRE_MASTER_SECRET_LEN: usize = 48;
+pub const MASTER_SECRET_LEN: usize = 48;
+pub const VERIFY_DATA_LEN: usize = 12;
+
+/// The TLCP suites with SM2 encryption of the pre-master secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherSuite {
+    /// ECC_SM4_CBC_SM3, 0xE013
+    EccSm4CbcSm3,
+    /// ECC_SM4_GCM_SM3, 0xE053
+    EccSm4GcmSm3,
+}
+
+impl CipherSuite {
+    pub const fn id(self) -> u16 {
+        match self {
+            CipherSuite::EccSm4CbcSm3 => 0xE013,
+            CipherSuite::EccSm4GcmSm3 => 0xE053,
+        }
+    }
+
+    /// MAC key, cipher key and IV lengths in the key block
+    pub const fn key_lengths(self) -> (usize, usize, usize) {
+        match self {
+            CipherSuite::EccSm4CbcSm3 => (32, 16, 16),
+            // AEAD: no MAC key and a 4-byte fixed IV
+            CipherSuite::EccSm4GcmSm3 => (0, 16, 4),
+        }
+    }
+}
+
+/// The side that sends a Finished message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+/// `PRF(secret, label, seed) = P_SM3(secret, label || seed)` cut to `len` bytes
+pub fn prf(secret: &[u8], label: &[u8], seed: &[u8], len: usize) -> Vec<u8> {
+    let label_seed = [label, seed].concat();
+    let mut out = Vec::with_capacity(len + 32);
+    // A(0) = label || seed, A(i) = HMAC(secret, A(i-1))
+    let mut a = sm3_hmac(secret, &label_seed);
+    while out.len() < len {
+        out.extend_from_slice(&sm3_hmac(secret, &[&a[..], &label_seed].concat()));
+        a = sm3_hmac(secret, &a);
+    }
+    out.truncate(len);
+    out
+}
+
+/// `TLCP_VERSION` followed by 46 random bytes
+pub fn generate_pre_master_secret() -> [u8; PRE_MASTER_SECRET_LEN] {
+    let mut pms = [0u8; PRE_MASTER_SECRET_LEN];
+    rand::thread_rng().fill_bytes(&mut pms[2..]);
+    pms[..2].copy_from_slice(&TLCP_VERSION);
+    pms
+}
+
+/// The body of ClientKeyExchange for the ECC suites, before its 2-byte
+/// length prefix
+pub fn encrypt_pre_master_secret(
+    server_enc_key: &Sm2PublicKey,
+    pms: &[u8; PRE_MASTER_SECRET_LEN],
+) -> Sm2Result<Vec<u8>> {
+    server_enc_key.encrypt_asn1(pms, false, Sm2Model::C1C3C2)
+}
+
+/// Decrypt ClientKeyExchange. The result is always 48 bytes: if decryption
+/// fails, the length is wrong or the version is not [`TLCP_VERSION`], a
+/// random secret is returned instead, without an error. The handshake then
+/// fails at the Finished message, as RFC 5246 7.4.7.1 requires, so the
+/// server does not become a decryption oracle.
+pub fn decrypt_pre_master_secret(server_enc_key: &Sm2PrivateKey, encrypted: &[u8]) -> [u8; PRE_MASTER_SECRET_LEN] {
+    let random = generate_pre_master_secret();
+    let decrypted = server_enc_key.decrypt_asn1(encrypted, false, Sm2Model::C1C3C2);
+    let mut pms = [0u8; PRE_MASTER_SECRET_LEN];
+    let mut ok = Choice::from(0);
+    if let Ok(d) = decrypted {
+        if d.len() == PRE_MASTER_SECRET_LEN {
+            pms.copy_from_slice(&d);
+            ok = Choice::from((d[0] == TLCP_VERSION[0]) as u8 & (d[1] == TLCP_VERSION[1]) as u8);
+        }
+    }
+    for (p, r) in pms.iter_mut().zip(random) {
+        *p = u8::conditional_select(&r, p, ok);
+    }
+    pms
+}
+
+/// `PRF(pre_master_secret, "master secret", client_random || server_random)`
+pub fn master_secret(
+    pms: &[u8],
+    client_random: &[u8; 32],
+    server_random: &[u8; 32],
+) -> [u8; MASTER_SECRET_LEN] {
+    let seed = [&client_random[..], &server_random[..]].concat();
+    prf(pms, b"master secret", &seed, MASTER_SECRET_LEN).try_into().unwrap()
+}
+
+/// The key block split into its six parts, in the order they are drawn
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyBlock {
+    pub client_write_mac_key: Vec<u8>,
+    pub server_write_mac_key: Vec<u8>,
+    pub client_write_key: Vec<u8>,
+    pub server_write_key: Vec<u8>,
+    pub client_write_iv: Vec<u8>,
+    pub server_write_iv: Vec<u8>,
+}
+
+/// Never prints the keys.
+impl std::fmt::Debug for KeyBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "KeyBlock(..)")
+    }
+}
+
+/// `PRF(master_secret, "key expansion", server_random || client_random)`,
+/// note the randoms are in the opposite order to the master secret
+pub fn key_block(
+    master_secret: &[u8; MASTER_SECRET_LEN],
+    client_random: &[u8; 32],
+    server_random: &[u8; 32],
+    suite: CipherSuite,
+) -> KeyBlock {
+    let (mac_len, key_len, iv_len) = suite.key_lengths();
+    let seed = [&server_random[..], &client_random[..]].concat();
+    let block = prf(master_secret, b"key expansion", &seed, 2 * (mac_len + key_len + iv_len));
+    let mut rest = &block[..];
+    let mut take = |len: usize| {
+        let (part, tail) = rest.split_at(len);
+        rest = tail;
+        part.to_vec()
+    };
+    KeyBlock {
+        client_write_mac_key: take(mac_len),
+        server_write_mac_key: take(mac_len),
+        client_write_key: take(key_len),
+        server_write_key: take(key_len),
+        client_write_iv: take(iv_len),
+        server_write_iv: take(iv_len),
+    }
+}
+
+/// `PRF(master_secret, finished_label, SM3(handshake_messages))`, 12 bytes.
+/// `handshake_messages` is every handshake message up to, not including, this
+/// Finished.
+pub fn verify_data(master_secret: &[u8; MASTER_SECRET_LEN], side: Side, handshake_messages: &[u8]) -> [u8; VERIFY_DATA_LEN] {
+    let label: &[u8] = match side {
+        Side::Client => b"client finished",
+        Side::Server => b"server finished",
+    };
+    prf(master_secret, label, &sm3_hash(handshake_messages), VERIFY_DATA_LEN)
+        .try_into()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test_tlcp {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    // reference values from an independent P_SM3 implementation
+    #[test]
+    fn test_prf() {
+        let pms: Vec<u8> = [&TLCP_VERSION[..], &(0..46).collect::<Vec<u8>>()].concat();
+        let (client_random, server_random) = ([0x0c; 32], [0x05; 32]);
+        let ms = master_secret(&pms, &client_random, &server_random);
+        assert_eq!(
+            "83fafad4e5caf70ea4663df3d38dd342cb140485ea662ac6597514cf3433d44f73a321df9e8ee151d9db819e931c35bd",
+            hex::encode(ms)
+        );
+
+        let kb = key_block(&ms, &client_random, &server_random, CipherSuite::EccSm4CbcSm3);
+        assert_eq!("29882eb29afe190244608db41f71311bed58e7c814d11ed8b3611427d041ab12", hex::encode(&kb.client_write_mac_key));
+        assert_eq!("60b0790c114c05c265c3a6857f593dbe", hex::encode(&kb.server_write_iv));
+        let all = [
+            kb.client_write_mac_key,
+            kb.server_write_mac_key,
+            kb.client_write_key,
+            kb.server_write_key,
+            kb.client_write_iv,
+            kb.server_write_iv,
+        ]
+        .concat();
+        assert_eq!(128, all.len());
+        assert_eq!(all, prf(&ms, b"key expansion", &[[0x05; 32], [0x0c; 32]].concat(), 128));
+
+        let gcm = key_block(&ms, &client_random, &server_random, CipherSuite::EccSm4GcmSm3);
+        assert!(gcm.client_write_mac_key.is_empty());
+        assert_eq!(4, gcm.server_write_iv.len());
+        assert_eq!(&all[..16], &gcm.client_write_key[..]);
+
+        assert_eq!("a57224cfe8f3daf23e250739", hex::encode(verify_data(&ms, Side::Client, b"handshake")));
+        assert_ne!(verify_data(&ms, Side::Client, b"handshake"), verify_data(&ms, Side::Server, b"handshake"));
+    }
+
+    #[test]
+    fn test_pre_master_secret() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let pms = generate_pre_master_secret();
+        assert_eq!(TLCP_VERSION, pms[..2]);
+        let c = encrypt_pre_master_secret(&pk, &pms).unwrap();
+        assert_eq!(pms, decrypt_pre_master_secret(&sk, &c));
+
+        // a bad ciphertext or version yields an unrelated secret, not an error
+        let mut bad = c.clone();
+        let n = bad.len();
+        bad[n - 1] ^= 1;
+        assert_ne!(pms, decrypt_pre_master_secret(&sk, &bad));
+        let mut v = pms;
+        v[1] = 0x03;
+        let c = encrypt_pre_master_secret(&pk, &v).unwrap();
+        assert_ne!(v, decrypt_pre_master_secret(&sk, &c));
+        let short = pk.encrypt_asn1(&pms[..47], false, Sm2Model::C1C3C2).unwrap();
+        assert_eq!(PRE_MASTER_SECRET_LEN, decrypt_pre_master_secret(&sk, &short).len());
+    }
+}
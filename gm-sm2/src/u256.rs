@@ -7,6 +7,13 @@ pub type U512 = [u64; 8];
 pub(crate) const SM2_ZERO: U256 = [0, 0, 0, 0];
 pub(crate) const SM2_ONE: U256 = [1, 0, 0, 0];
 
+#[cfg(feature = "asm")]
+#[inline(always)]
+pub fn u256_add(a: &U256, b: &U256) -> (U256, bool) {
+    gm_core::limb::add(a, b)
+}
+
+#[cfg(not(feature = "asm"))]
 #[inline(always)]
 pub const fn u256_add(a: &U256, b: &U256) -> (U256, bool) {
     let mut sum = [0; 4];
@@ -49,6 +56,13 @@ pub const fn u512_add(a: &U512, b: &U512) -> (U512, bool) {
     (sum, carry)
 }
 
+#[cfg(feature = "asm")]
+#[inline(always)]
+pub fn u256_sub(a: &U256, b: &U256) -> (U256, bool) {
+    gm_core::limb::sub(a, b)
+}
+
+#[cfg(not(feature = "asm"))]
 #[inline(always)]
 pub const fn u256_sub(a: &U256, b: &U256) -> (U256, bool) {
     let mut r = [0; 4];
@@ -103,33 +117,55 @@ pub fn u256_bits_and(a: &U256, b: &U256) -> U256 {
 
 #[inline(always)]
 pub fn u256_mul(a: &U256, b: &U256) -> U512 {
-    let mut a_: [u64; 8] = [0; 8];
-    let mut b_: [u64; 8] = [0; 8];
-    let mut ret: [u64; 8] = [0; 8];
-    let mut s: [u64; 16] = [0; 16];
+    gm_core::limb::mul(a, b)
+}
 
+/// Montgomery multiplication a * b * 2^-256 mod m (CIOS), for a, b < m.
+///
+/// `m_inv` is -m^-1 mod 2^64.
+#[inline(always)]
+pub fn u256_mont_mul(a: &U256, b: &U256, m: &U256, m_inv: u64) -> U256 {
+    gm_core::limb::mont_mul(a, b, m, m_inv)
+}
+
+/// Montgomery reduction of a 512-bit value, z * 2^-256 mod m.
+///
+/// `m_inv` is -m^-1 mod 2^64, m must have its top bit set.
+pub fn u512_mont_reduce(z: &U512, m: &U256, m_inv: u64) -> U256 {
+    let mut t = [0u64; 9];
+    t[..8].copy_from_slice(z);
     for i in 0..4 {
-        a_[2 * i] = a[i] & 0xffffffff;
-        b_[2 * i] = b[i] & 0xffffffff;
-        a_[2 * i + 1] = a[i] >> 32;
-        b_[2 * i + 1] = b[i] >> 32;
-    }
-
-    let mut u = 0;
-    for i in 0..8 {
-        u = 0;
-        for j in 0..8 {
-            u = s[i + j] + a_[i] * b_[j] + u;
-            s[i + j] = u & 0xffffffff;
-            u >>= 32;
+        // t = t + q * m * 2^(64 i), clears limb i
+        let q = t[i].wrapping_mul(m_inv);
+        let mut c = 0u128;
+        for j in 0..4 {
+            let s = t[i + j] as u128 + (q as u128) * (m[j] as u128) + c;
+            t[i + j] = s as u64;
+            c = s >> 64;
+        }
+        for k in t.iter_mut().skip(i + 4) {
+            let s = *k as u128 + c;
+            *k = s as u64;
+            c = s >> 64;
         }
-        s[i + 8] = u;
     }
 
-    for i in 0..8 {
-        ret[i] = (s[2 * i + 1] << 32) | s[2 * i];
+    // t / 2^256 < 2^256 + m < 3m
+    let mut r = [t[4], t[5], t[6], t[7]];
+    let mut hi = t[8];
+    while hi != 0 || u256_cmp(&r, m) >= 0 {
+        let (d, borrow) = u256_sub(&r, m);
+        r = d;
+        hi -= borrow as u64;
     }
-    ret
+    r
+}
+
+/// z mod m for a 512-bit z, `r2` is 2^512 mod m.
+#[inline(always)]
+pub fn u512_mod(z: &U512, m: &U256, m_inv: u64, r2: &U256) -> U256 {
+    // (z R^-1) * R^2 * R^-1 = z
+    u256_mont_mul(&u512_mont_reduce(z, m, m_inv), r2, m, m_inv)
 }
 
 #[inline(always)]
@@ -161,6 +197,112 @@ pub const fn u256_cmp(a: &U256, b: &U256) -> i32 {
     return 0;
 }
 
+/// a << n, bits shifted out are dropped, n >= 256 gives zero
+#[inline(always)]
+pub const fn u256_shl(a: &U256, n: u32) -> U256 {
+    let mut r = [0u64; 4];
+    if n >= 256 {
+        return r;
+    }
+    let limbs = (n / 64) as usize;
+    let bits = n % 64;
+    let mut i = 3;
+    loop {
+        if i >= limbs {
+            let lo = a[i - limbs];
+            r[i] = lo << bits;
+            if bits > 0 && i > limbs {
+                r[i] |= a[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        if i == 0 {
+            break;
+        }
+        i -= 1;
+    }
+    r
+}
+
+/// a >> n, n >= 256 gives zero
+#[inline(always)]
+pub const fn u256_shr(a: &U256, n: u32) -> U256 {
+    let mut r = [0u64; 4];
+    if n >= 256 {
+        return r;
+    }
+    let limbs = (n / 64) as usize;
+    let bits = n % 64;
+    let mut i = 0;
+    while i + limbs < 4 {
+        r[i] = a[i + limbs] >> bits;
+        if bits > 0 && i + limbs + 1 < 4 {
+            r[i] |= a[i + limbs + 1] << (64 - bits);
+        }
+        i += 1;
+    }
+    r
+}
+
+/// Bit i of a, bit 0 is the least significant.
+#[inline(always)]
+pub const fn u256_bit(a: &U256, i: usize) -> bool {
+    i < 256 && (a[i / 64] >> (i % 64)) & 1 == 1
+}
+
+/// The w-bit window starting at bit i, (a >> i) mod 2^w, for 1 <= w <= 64.
+/// Bits past the top of a read as zero.
+#[inline(always)]
+pub const fn u256_get_window(a: &U256, i: usize, w: u32) -> u64 {
+    if i >= 256 {
+        return 0;
+    }
+    let n = i / 64;
+    let j = (i % 64) as u32;
+    let mut bits = a[n] >> j;
+    if j > 0 && n < 3 {
+        bits |= a[n + 1] << (64 - j);
+    }
+    if w >= 64 {
+        bits
+    } else {
+        bits & ((1u64 << w) - 1)
+    }
+}
+
+/// 256 for zero
+#[inline(always)]
+pub const fn u256_leading_zeros(a: &U256) -> u32 {
+    let mut i = 3;
+    loop {
+        if a[i] != 0 {
+            return (3 - i as u32) * 64 + a[i].leading_zeros();
+        }
+        if i == 0 {
+            return 256;
+        }
+        i -= 1;
+    }
+}
+
+/// 256 for zero
+#[inline(always)]
+pub const fn u256_trailing_zeros(a: &U256) -> u32 {
+    let mut i = 0;
+    while i < 4 {
+        if a[i] != 0 {
+            return i as u32 * 64 + a[i].trailing_zeros();
+        }
+        i += 1;
+    }
+    256
+}
+
+/// Number of significant bits, 0 for zero
+#[inline(always)]
+pub const fn u256_bits(a: &U256) -> u32 {
+    256 - u256_leading_zeros(a)
+}
+
 #[inline(always)]
 pub fn u256_to_be_bytes(a: &U256) -> Vec<u8> {
     let mut ret: Vec<u8> = Vec::new();
@@ -184,7 +326,10 @@ pub fn u256_from_be_bytes(input: &[u8]) -> U256 {
 mod test_operation {
     use num_bigint::BigUint;
 
-    use crate::u256::{u256_add, u256_mul, u256_sub};
+    use crate::u256::{
+        u256_add, u256_bit, u256_bits, u256_from_be_bytes, u256_get_window, u256_leading_zeros,
+        u256_mul, u256_shl, u256_shr, u256_sub, u256_to_be_bytes, u256_trailing_zeros, U256,
+    };
 
     #[test]
     fn test_raw_add_u64() {
@@ -230,4 +375,50 @@ mod test_operation {
         mul.reverse();
         assert_eq!(r, *mul);
     }
+
+    #[test]
+    fn test_shift_and_bits() {
+        fn to_big(a: &U256) -> BigUint {
+            BigUint::from_bytes_be(&u256_to_be_bytes(a))
+        }
+        fn from_big(a: &BigUint) -> U256 {
+            let bytes = a.to_bytes_be();
+            let mut buf = [0u8; 32];
+            buf[32 - bytes.len()..].copy_from_slice(&bytes);
+            u256_from_be_bytes(&buf)
+        }
+
+        let mask = (BigUint::from(1u8) << 256usize) - 1u8;
+        let values: [U256; 4] = [
+            [
+                0x54806C11D8806141,
+                0xF1DD2C190F5E93C4,
+                0x597B6027B441A01F,
+                0x85AEF3D078640C98,
+            ],
+            [0, 0x1006E85F5CDFF073, 0, 0x41E00A53DDA532DA],
+            [1, 0, 0, 0],
+            [0, 0, 0, 0],
+        ];
+        for a in values.iter() {
+            let a1 = to_big(a);
+            for n in [0u32, 1, 7, 63, 64, 65, 128, 130, 255, 256, 300] {
+                assert_eq!(from_big(&((&a1 << n as usize) & &mask)), u256_shl(a, n));
+                assert_eq!(from_big(&(&a1 >> n as usize)), u256_shr(a, n));
+            }
+            for i in 0..256 {
+                assert_eq!(a1.bit(i as u64), u256_bit(a, i));
+                for w in [1u32, 4, 5, 7, 64] {
+                    let expect = (&a1 >> i) & ((BigUint::from(1u8) << w as usize) - 1u8);
+                    assert_eq!(expect, BigUint::from(u256_get_window(a, i, w)));
+                }
+            }
+            assert_eq!(a1.bits() as u32, u256_bits(a));
+            assert_eq!(256 - a1.bits() as u32, u256_leading_zeros(a));
+            assert_eq!(
+                a1.trailing_zeros().map(|t| t as u32).unwrap_or(256),
+                u256_trailing_zeros(a)
+            );
+        }
+    }
 }
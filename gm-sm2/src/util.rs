@@ -1,4 +1,5 @@
 use byteorder::{BigEndian, WriteBytesExt};
+use gm_sm3::kdf::sm3_kdf;
 use gm_sm3::sm3_hash;
 
 use crate::error::{Sm2Error, Sm2Result};
@@ -10,6 +11,10 @@ pub(crate) const DEFAULT_ID: &'static str = "1234567812345678";
 
 
 pub fn compute_za(id: &str, pk: &Point) -> Sm2Result<[u8; 32]> {
+    za(id.as_bytes(), pk)
+}
+
+pub(crate) fn za(id: &[u8], pk: &Point) -> Sm2Result<[u8; 32]> {
     if !pk.is_valid() {
         return Err(Sm2Error::InvalidPublic);
     }
@@ -20,9 +25,7 @@ pub fn compute_za(id: &str, pk: &Point) -> Sm2Result<[u8; 32]> {
     prepend
         .write_u16::<BigEndian>((id.len() * 8) as u16)
         .unwrap();
-    for c in id.bytes() {
-        prepend.push(c);
-    }
+    prepend.extend_from_slice(id);
 
     prepend.extend_from_slice(&fp_from_mont(&SM2_MODP_MONT_A).to_byte_be());
     prepend.extend_from_slice(&fp_from_mont(&SM2_MODP_MONT_B).to_byte_be());
@@ -46,32 +49,10 @@ pub fn xor_bytes(a: &[u8], b: &[u8]) -> Vec<u8> {
     result
 }
 
+/// The KDF of GM/T 0003.4, see [`gm_sm3::kdf::Sm3Xof`] to read it incrementally.
 #[inline]
-pub fn kdf(z: &[u8], klen: usize) -> Vec<u8> {
-    let mut ct = 0x00000001u32;
-    let bound = ((klen as f64) / 32.0).ceil() as u32;
-    let mut h_a = Vec::new();
-    for _i in 1..bound {
-        let mut prepend = Vec::new();
-        prepend.extend_from_slice(z);
-        prepend.extend_from_slice(&ct.to_be_bytes());
-
-        let h_a_i = sm3_hash(&prepend[..]);
-        h_a.extend_from_slice(&h_a_i);
-        ct += 1;
-    }
-
-    let mut prepend = Vec::new();
-    prepend.extend_from_slice(z);
-    prepend.extend_from_slice(&ct.to_be_bytes());
-
-    let last = sm3_hash(&prepend[..]);
-    if klen % 32 == 0 {
-        h_a.extend_from_slice(&last);
-    } else {
-        h_a.extend_from_slice(&last[0..(klen % 32)]);
-    }
-    h_a
+pub fn kdf(z: &[u8], klen: usize) -> Sm2Result<Vec<u8>> {
+    sm3_kdf(z, klen).map_err(|_| Sm2Error::InvalidKeyLength)
 }
 
 #[inline(always)]
@@ -239,7 +220,14 @@ mod test_operation {
     use num_bigint::BigUint;
     use num_traits::Num;
 
-    use crate::util::{add_raw_u64, mul_raw_u64, sub_raw_u64};
+    use crate::error::Sm2Error;
+    use crate::util::{add_raw_u64, kdf, mul_raw_u64, sub_raw_u64};
+
+    #[test]
+    fn test_kdf_len() {
+        assert_eq!(33, kdf(b"z", 33).unwrap().len());
+        assert!(kdf(b"z", 0).err() == Some(Sm2Error::InvalidKeyLength));
+    }
 
     #[test]
     fn test_raw_add_u64() {
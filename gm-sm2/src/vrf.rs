@@ -0,0 +1,155 @@
+//! A verifiable random function on the SM2 curve with SM3, for leader election.
+//!
+//! ECVRF of RFC 9381 instantiated with the SM2 group: the holder of a key
+//! turns an input `alpha` into a 32-byte output `beta` that anybody can
+//! recompute from the 81-byte proof `pi` and check against the public key. The
+//! output is unique for a key and an input, however the proof was made.
+//!
+//! * `H = hash_to_curve(Y || alpha)` with the tag `ECVRF_SM2_XMD:SM3_SSWU_RO_ || suite`
+//! * `Gamma = [x]H`, `k` derived from `x` and `H`, `c = SM3(suite || 0x02 || Y || H || Gamma || [k]G || [k]H || 0x00)[..16]`
+//! * `s = k + c·x mod n`, `pi = Gamma || c || s` with the points compressed
+//! * `beta = SM3(suite || 0x03 || Gamma || 0x00)`
+//!
+//! RFC 9381 registers no SM2 suite, the suite byte 0x80 is from its private range.
+use num_bigint::BigUint;
+
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::fields::fn64::{fn_add, fn_from_bytes, fn_mul, SM2_N};
+use crate::hash_to_curve::hash_to_curve;
+use crate::key::{Sm2PrivateKey, Sm2PublicKey};
+use crate::p256_ecc::{g_mul, Point};
+use crate::u256::{u256_cmp, u256_from_be_bytes, u256_to_be_bytes, U256};
+
+const SUITE: u8 = 0x80;
+const DST: &[u8] = b"ECVRF_SM2_XMD:SM3_SSWU_RO_\x80";
+
+pub const PROOF_LEN: usize = 33 + 16 + 32;
+
+fn encode_to_curve(pk: &Point, alpha: &[u8]) -> Sm2Result<Point> {
+    hash_to_curve(&[&pk.to_byte_be(true)[..], alpha].concat(), DST)
+}
+
+/// `k` from 64 bytes of SM3 over the secret and `H`, reduced mod n
+fn nonce(x: &U256, h: &[u8]) -> U256 {
+    let n = BigUint::from_bytes_be(&u256_to_be_bytes(&SM2_N));
+    let x = u256_to_be_bytes(x);
+    let mut counter = 0u8;
+    loop {
+        let wide = [2 * counter, 2 * counter + 1].map(|i| sm3_hash(&[&[SUITE, 0x01][..], &x, h, &[i]].concat()));
+        let k = (BigUint::from_bytes_be(&wide.concat()) % &n).to_bytes_be();
+        let mut b = [0u8; 32];
+        b[32 - k.len()..].copy_from_slice(&k);
+        let k = u256_from_be_bytes(&b);
+        if k != [0; 4] {
+            return k;
+        }
+        counter += 1;
+    }
+}
+
+fn challenge(points: [&Point; 5]) -> U256 {
+    let mut data = vec![SUITE, 0x02];
+    for p in points {
+        data.extend_from_slice(&p.to_byte_be(true));
+    }
+    data.push(0x00);
+    let mut c = [0u8; 32];
+    c[16..].copy_from_slice(&sm3_hash(&data)[..16]);
+    u256_from_be_bytes(&c)
+}
+
+fn beta(gamma: &Point) -> [u8; 32] {
+    sm3_hash(&[&[SUITE, 0x03][..], &gamma.to_byte_be(true), &[0x00]].concat())
+}
+
+fn decode_proof(pi: &[u8]) -> Sm2Result<(Point, U256, U256)> {
+    if pi.len() != PROOF_LEN {
+        return Err(Sm2Error::InvalidLength);
+    }
+    let gamma = Sm2PublicKey::new(&pi[..33])?.point;
+    let mut c = [0u8; 32];
+    c[16..].copy_from_slice(&pi[33..49]);
+    let s = fn_from_bytes(&pi[49..])?;
+    Ok((gamma, u256_from_be_bytes(&c), s))
+}
+
+/// The proof `pi` for the input `alpha`
+pub fn prove(sk: &Sm2PrivateKey, alpha: &[u8]) -> Sm2Result<Vec<u8>> {
+    let y = &sk.public_key.point;
+    let h = encode_to_curve(y, alpha)?;
+    let gamma = h.scalar_mul(&sk.d);
+    let k = nonce(&sk.d, &h.to_byte_be(true));
+    let c = challenge([y, &h, &gamma, &g_mul(&k), &h.scalar_mul(&k)]);
+    let s = fn_add(&k, &fn_mul(&c, &sk.d));
+    Ok([gamma.to_byte_be(true), u256_to_be_bytes(&c)[16..].to_vec(), u256_to_be_bytes(&s)].concat())
+}
+
+/// The output `beta` of a proof, without verifying it
+pub fn proof_to_hash(pi: &[u8]) -> Sm2Result<[u8; 32]> {
+    Ok(beta(&decode_proof(pi)?.0))
+}
+
+/// Verify `pi` for `alpha` under `pk` and return the output `beta`.
+pub fn verify(pk: &Sm2PublicKey, pi: &[u8], alpha: &[u8]) -> Sm2Result<[u8; 32]> {
+    let (gamma, c, s) = decode_proof(pi)?;
+    let y = &pk.point;
+    y.check()?;
+    let h = encode_to_curve(y, alpha)?;
+    // U = [s]G - [c]Y, V = [s]H - [c]Gamma
+    let u = g_mul(&s).point_add(&y.scalar_mul(&c).neg());
+    let v = h.scalar_mul(&s).point_add(&gamma.scalar_mul(&c).neg());
+    if u256_cmp(&challenge([y, &h, &gamma, &u, &v]), &c) != 0 {
+        return Err(Sm2Error::InvalidDigest);
+    }
+    Ok(beta(&gamma))
+}
+
+#[cfg(test)]
+mod test_vrf {
+    use super::*;
+    use crate::key::gen_keypair;
+
+    // from an independent Python implementation over hashlib's SM3
+    #[test]
+    fn test_vrf_vectors() {
+        let sk = Sm2PrivateKey::from_hex_string("3945208F7B2144B13F36E38AC6D39F95889393692860B51A42FB81EF4DF7C5B8").unwrap();
+        let cases: [(&[u8], &str, &str); 2] = [
+            (
+                b"",
+                "03cbd25cd308796dfe125b75e43a8500e5c2eaa5cc598d2ba7d94486e62910ff50ee209c2f84bf4c107d8d10d68047fdfe\
+                 ecbd131042391670d2416e682ed9a1f1b4a206c7c546cca5a5db3fa09ee922e4",
+                "1d7c19afe3f0d807b7a0883f28c43c9d30b05a9704a026c02be9db54b11206fd",
+            ),
+            (
+                b"sample",
+                "02992fb258eb4a1f5596cf976bd129f8c0dc6626dc2389178d3e461e8529e803d66f8e0cac2c6184684a3be1ef08a29286\
+                 db75df9e0cb7e8c2b5a57fc4515b569bb98f377f6b7ff8ca645776e4dae51ebe",
+                "12c7c841acfe45d1e9419af86b4736dfdd7389d6391451ebc2aed544da5ebe1c",
+            ),
+        ];
+        for (alpha, pi, beta) in cases {
+            let proof = prove(&sk, alpha).unwrap();
+            assert_eq!(pi, hex::encode(&proof));
+            assert_eq!(beta, hex::encode(proof_to_hash(&proof).unwrap()));
+            assert_eq!(beta, hex::encode(verify(&sk.public_key, &proof, alpha).unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_vrf_rejects() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let pi = prove(&sk, b"round 7").unwrap();
+        assert_eq!(PROOF_LEN, pi.len());
+        verify(&pk, &pi, b"round 7").unwrap();
+        assert!(verify(&pk, &pi, b"round 8").is_err());
+        assert!(verify(&gen_keypair().unwrap().0, &pi, b"round 7").is_err());
+        for i in 0..pi.len() {
+            let mut b = pi.clone();
+            b[i] ^= 1;
+            assert!(verify(&pk, &b, b"round 7").is_err());
+        }
+        assert!(verify(&pk, &pi[..80], b"round 7").is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! Minimal X.509 support for SM2 keys: PKCS#10 certificate signing requests
+//! and self-signed certificates.
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gm_core::encoding::pem_encode;
+use gm_sm3::sm3_hash;
+use num_bigint::BigUint;
+use pkcs8::{EncodePublicKey, ObjectIdentifier};
+use yasna::models::ObjectIdentifier as Asn1Oid;
+use yasna::tags::{TAG_GENERALIZEDTIME, TAG_UTCTIME};
+use yasna::{DERWriter, Tag};
+
+use crate::error::{Sm2Error, Sm2Result};
+use crate::key::Sm2PrivateKey;
+use crate::signature::Sm2Signature;
+
+/// SM2 signature with SM3, GM/T 0006
+pub const OID_SM2_WITH_SM3: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.156.10197.1.501");
+
+pub const OID_COMMON_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.3");
+pub const OID_COUNTRY: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.6");
+pub const OID_LOCALITY: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.7");
+pub const OID_STATE: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.8");
+pub const OID_ORGANIZATION: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.10");
+pub const OID_ORGANIZATIONAL_UNIT: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.11");
+
+pub const OID_SUBJECT_KEY_IDENTIFIER: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.14");
+pub const OID_KEY_USAGE: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.15");
+pub const OID_SUBJECT_ALT_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.17");
+pub const OID_BASIC_CONSTRAINTS: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.19");
+pub const OID_EXTENSION_REQUEST: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.14");
+
+/// Subject / issuer name, attributes are written in insertion order,
+/// one attribute per RDN.
+#[derive(Debug, Clone, Default)]
+pub struct DistinguishedName {
+    entries: Vec<(ObjectIdentifier, String)>,
+}
+
+impl DistinguishedName {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(mut self, oid: ObjectIdentifier, value: &str) -> Self {
+        self.entries.push((oid, value.to_string()));
+        self
+    }
+
+    pub fn common_name(self, cn: &str) -> Self {
+        self.push(OID_COMMON_NAME, cn)
+    }
+
+    pub fn country(self, c: &str) -> Self {
+        self.push(OID_COUNTRY, c)
+    }
+
+    pub fn state(self, st: &str) -> Self {
+        self.push(OID_STATE, st)
+    }
+
+    pub fn locality(self, l: &str) -> Self {
+        self.push(OID_LOCALITY, l)
+    }
+
+    pub fn organization(self, o: &str) -> Self {
+        self.push(OID_ORGANIZATION, o)
+    }
+
+    pub fn organizational_unit(self, ou: &str) -> Self {
+        self.push(OID_ORGANIZATIONAL_UNIT, ou)
+    }
+
+    pub(crate) fn write(&self, writer: DERWriter) {
+        writer.write_sequence(|writer| {
+            for (oid, value) in &self.entries {
+                writer.next().write_set(|writer| {
+                    writer.next().write_sequence(|writer| {
+                        writer.next().write_oid(&to_asn1_oid(oid));
+                        // countryName is a PrintableString (RFC 5280)
+                        if *oid == OID_COUNTRY {
+                            writer.next().write_printable_string(value);
+                        } else {
+                            writer.next().write_utf8_string(value);
+                        }
+                    });
+                });
+            }
+        });
+    }
+}
+
+pub const KEY_USAGE_DIGITAL_SIGNATURE: u16 = 1 << 0;
+pub const KEY_USAGE_NON_REPUDIATION: u16 = 1 << 1;
+pub const KEY_USAGE_KEY_ENCIPHERMENT: u16 = 1 << 2;
+pub const KEY_USAGE_DATA_ENCIPHERMENT: u16 = 1 << 3;
+pub const KEY_USAGE_KEY_AGREEMENT: u16 = 1 << 4;
+pub const KEY_USAGE_KEY_CERT_SIGN: u16 = 1 << 5;
+pub const KEY_USAGE_CRL_SIGN: u16 = 1 << 6;
+
+/// A certificate extension, `value` is the DER encoding carried in extnValue.
+#[derive(Debug, Clone)]
+pub struct Extension {
+    pub oid: ObjectIdentifier,
+    pub critical: bool,
+    pub value: Vec<u8>,
+}
+
+impl Extension {
+    /// subjectAltName with dNSName entries
+    pub fn subject_alt_name(dns_names: &[&str]) -> Self {
+        let value = yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                for name in dns_names {
+                    writer
+                        .next()
+                        .write_tagged_implicit(Tag::context(2), |writer| writer.write_ia5_string(name));
+                }
+            });
+        });
+        Extension {
+            oid: OID_SUBJECT_ALT_NAME,
+            critical: false,
+            value,
+        }
+    }
+
+    /// keyUsage, `usage` is a combination of the `KEY_USAGE_*` bits
+    pub fn key_usage(usage: u16) -> Self {
+        let bits = 16 - usage.leading_zeros() as usize;
+        let bytes = usage.reverse_bits().to_be_bytes();
+        let value = yasna::construct_der(|writer| {
+            writer.write_bitvec_bytes(&bytes[..bits.div_ceil(8)], bits);
+        });
+        Extension {
+            oid: OID_KEY_USAGE,
+            critical: true,
+            value,
+        }
+    }
+
+    pub fn basic_constraints(ca: bool, path_len: Option<u8>) -> Self {
+        let value = yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                if ca {
+                    writer.next().write_bool(true);
+                    if let Some(len) = path_len {
+                        writer.next().write_u8(len);
+                    }
+                }
+            });
+        });
+        Extension {
+            oid: OID_BASIC_CONSTRAINTS,
+            critical: true,
+            value,
+        }
+    }
+
+    pub(crate) fn write(&self, writer: DERWriter) {
+        writer.write_sequence(|writer| {
+            writer.next().write_oid(&to_asn1_oid(&self.oid));
+            if self.critical {
+                writer.next().write_bool(true);
+            }
+            writer.next().write_bytes(&self.value);
+        });
+    }
+}
+
+/// Builds a PKCS#10 certification request signed with SM2-with-SM3.
+///
+/// # Example
+/// ```rust
+/// use gm_sm2::key::gen_keypair;
+/// use gm_sm2::x509::{CsrBuilder, DistinguishedName, Extension};
+///
+/// let (_, sk) = gen_keypair().unwrap();
+/// let subject = DistinguishedName::new()
+///     .country("CN")
+///     .organization("Example")
+///     .common_name("www.example.com");
+/// let pem = CsrBuilder::new(subject)
+///     .extension(Extension::subject_alt_name(&["www.example.com"]))
+///     .build_pem(&sk)
+///     .unwrap();
+/// assert!(pem.starts_with("-----BEGIN CERTIFICATE REQUEST-----"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CsrBuilder {
+    subject: DistinguishedName,
+    extensions: Vec<Extension>,
+}
+
+impl CsrBuilder {
+    pub fn new(subject: DistinguishedName) -> Self {
+        Self {
+            subject,
+            extensions: vec![],
+        }
+    }
+
+    pub fn extension(mut self, ext: Extension) -> Self {
+        self.extensions.push(ext);
+        self
+    }
+
+    pub fn build_der(&self, sk: &Sm2PrivateKey) -> Sm2Result<Vec<u8>> {
+        let spki = sk
+            .public_key
+            .to_public_key_der()
+            .map_err(|_| Sm2Error::InvalidPublic)?;
+
+        let info = yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer.next().write_u8(0);
+                self.subject.write(writer.next());
+                writer.next().write_der(spki.as_bytes());
+                writer.next().write_tagged_implicit(Tag::context(0), |writer| {
+                    writer.write_set(|writer| {
+                        if self.extensions.is_empty() {
+                            return;
+                        }
+                        writer.next().write_sequence(|writer| {
+                            writer.next().write_oid(&to_asn1_oid(&OID_EXTENSION_REQUEST));
+                            writer.next().write_set(|writer| {
+                                writer.next().write_sequence(|writer| {
+                                    for ext in &self.extensions {
+                                        ext.write(writer.next());
+                                    }
+                                });
+                            });
+                        });
+                    });
+                });
+            });
+        });
+        sign_tbs(&info, sk)
+    }
+
+    pub fn build_pem(&self, sk: &Sm2PrivateKey) -> Sm2Result<String> {
+        let der = self.build_der(sk)?;
+        to_pem("CERTIFICATE REQUEST", &der)
+    }
+}
+
+/// Issues a self-signed X.509 v3 certificate signed with SM2-with-SM3.
+///
+/// Issuer and subject are the same name, a subjectKeyIdentifier (the first
+/// 20 bytes of the SM3 hash of the public key) is always added.
+///
+/// # Example
+/// ```rust
+/// use gm_sm2::key::gen_keypair;
+/// use gm_sm2::x509::*;
+///
+/// let (_, sk) = gen_keypair().unwrap();
+/// let pem = CertificateBuilder::new(DistinguishedName::new().common_name("tlcp.test"))
+///     .validity_days(365)
+///     .extension(Extension::subject_alt_name(&["tlcp.test"]))
+///     .extension(Extension::key_usage(KEY_USAGE_DIGITAL_SIGNATURE))
+///     .self_signed_pem(&sk)
+///     .unwrap();
+/// assert!(pem.starts_with("-----BEGIN CERTIFICATE-----"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CertificateBuilder {
+    subject: DistinguishedName,
+    serial: Vec<u8>,
+    not_before: u64,
+    not_after: u64,
+    extensions: Vec<Extension>,
+}
+
+impl CertificateBuilder {
+    /// Valid from now for one year, with a random 16-byte serial number.
+    pub fn new(subject: DistinguishedName) -> Self {
+        let mut serial = vec![0u8; 16];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut serial);
+        serial[0] &= 0x7f;
+        serial[0] |= 0x40;
+        let now = unix_now();
+        Self {
+            subject,
+            serial,
+            not_before: now,
+            not_after: now + 365 * 86400,
+            extensions: vec![],
+        }
+    }
+
+    /// Big-endian serial number, must be positive and at most 20 bytes
+    pub fn serial_number(mut self, serial: &[u8]) -> Self {
+        self.serial = serial.to_vec();
+        self
+    }
+
+    /// notBefore / notAfter as seconds since the Unix epoch
+    pub fn validity(mut self, not_before: u64, not_after: u64) -> Self {
+        self.not_before = not_before;
+        self.not_after = not_after;
+        self
+    }
+
+    pub fn validity_days(self, days: u64) -> Self {
+        let now = unix_now();
+        self.validity(now, now + days * 86400)
+    }
+
+    pub fn extension(mut self, ext: Extension) -> Self {
+        self.extensions.push(ext);
+        self
+    }
+
+    pub fn self_signed_der(&self, sk: &Sm2PrivateKey) -> Sm2Result<Vec<u8>> {
+        let serial = BigUint::from_bytes_be(&self.serial);
+        if serial.bits() == 0 || self.serial.len() > 20 || self.not_after < self.not_before {
+            return Err(Sm2Error::InvalidDer);
+        }
+        let spki = sk
+            .public_key
+            .to_public_key_der()
+            .map_err(|_| Sm2Error::InvalidPublic)?;
+        let ski = yasna::construct_der(|writer| {
+            writer.write_bytes(&sm3_hash(&sk.public_key.to_bytes(false))[..20]);
+        });
+        let mut extensions = vec![Extension {
+            oid: OID_SUBJECT_KEY_IDENTIFIER,
+            critical: false,
+            value: ski,
+        }];
+        extensions.extend_from_slice(&self.extensions);
+
+        let tbs = yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer
+                    .next()
+                    .write_tagged(Tag::context(0), |writer| writer.write_u8(2));
+                writer.next().write_biguint(&serial);
+                write_sm2_with_sm3(writer.next());
+                self.subject.write(writer.next());
+                writer.next().write_sequence(|writer| {
+                    write_time(writer.next(), self.not_before);
+                    write_time(writer.next(), self.not_after);
+                });
+                self.subject.write(writer.next());
+                writer.next().write_der(spki.as_bytes());
+                writer.next().write_tagged(Tag::context(3), |writer| {
+                    writer.write_sequence(|writer| {
+                        for ext in &extensions {
+                            ext.write(writer.next());
+                        }
+                    });
+                });
+            });
+        });
+        sign_tbs(&tbs, sk)
+    }
+
+    pub fn self_signed_pem(&self, sk: &Sm2PrivateKey) -> Sm2Result<String> {
+        let der = self.self_signed_der(sk)?;
+        to_pem("CERTIFICATE", &der)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// UTCTime up to 2049, GeneralizedTime from 2050 on (RFC 5280 4.1.2.5)
+fn write_time(writer: DERWriter, secs: u64) {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    let (year, month, day) = civil_from_days(days);
+    let (hh, mm, ss) = (rem / 3600, rem % 3600 / 60, rem % 60);
+    if year < 2050 {
+        let s = format!("{:02}{:02}{:02}{:02}{:02}{:02}Z", year % 100, month, day, hh, mm, ss);
+        writer.write_tagged_implicit(TAG_UTCTIME, |writer| writer.write_bytes(s.as_bytes()));
+    } else {
+        let s = format!("{:04}{:02}{:02}{:02}{:02}{:02}Z", year, month, day, hh, mm, ss);
+        writer.write_tagged_implicit(TAG_GENERALIZEDTIME, |writer| writer.write_bytes(s.as_bytes()));
+    }
+}
+
+/// days since 1970-01-01 to (year, month, day), proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// SEQUENCE { tbs, AlgorithmIdentifier { sm2-with-sm3 }, BIT STRING signature }
+pub(crate) fn sign_tbs(tbs: &[u8], sk: &Sm2PrivateKey) -> Sm2Result<Vec<u8>> {
+    let sig = Sm2Signature::from_slice(&sk.sign(None, tbs)?)?.to_der();
+    Ok(yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer.next().write_der(tbs);
+            write_sm2_with_sm3(writer.next());
+            writer.next().write_bitvec_bytes(&sig, sig.len() * 8);
+        });
+    }))
+}
+
+pub(crate) fn write_sm2_with_sm3(writer: DERWriter) {
+    writer.write_sequence(|writer| {
+        writer.next().write_oid(&to_asn1_oid(&OID_SM2_WITH_SM3));
+    });
+}
+
+pub(crate) fn to_pem(label: &str, der: &[u8]) -> Sm2Result<String> {
+    pem_encode(label, der).map_err(|_| Sm2Error::InvalidDer)
+}
+
+pub(crate) fn to_asn1_oid(oid: &ObjectIdentifier) -> Asn1Oid {
+    let arcs: Vec<u64> = oid.arcs().map(|a| a as u64).collect();
+    Asn1Oid::from_slice(&arcs)
+}
+
+#[cfg(test)]
+mod test_x509 {
+    use crate::key::gen_keypair;
+    use crate::signature::Sm2Signature;
+    use crate::x509::{
+        civil_from_days, CertificateBuilder, CsrBuilder, DistinguishedName, Extension,
+        KEY_USAGE_DIGITAL_SIGNATURE, KEY_USAGE_KEY_CERT_SIGN,
+    };
+
+    #[test]
+    fn test_csr() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let subject = DistinguishedName::new()
+            .country("CN")
+            .state("Beijing")
+            .organization("gm-rs")
+            .common_name("localhost");
+        let builder =
+            CsrBuilder::new(subject).extension(Extension::subject_alt_name(&["localhost"]));
+        println!("{}", builder.build_pem(&sk).unwrap());
+
+        let der = builder.build_der(&sk).unwrap();
+        let (info, sig) = yasna::parse_der(&der, |reader| {
+            reader.read_sequence(|reader| {
+                let info = reader.next().read_der()?;
+                reader.next().read_der()?;
+                let (sig, _) = reader.next().read_bitvec_bytes()?;
+                Ok((info, sig))
+            })
+        })
+        .unwrap();
+        let sig = Sm2Signature::from_der(&sig).unwrap();
+        pk.verify(None, &info, &sig.to_vec()).unwrap();
+    }
+
+    #[test]
+    fn test_self_signed_certificate() {
+        let (pk, sk) = gen_keypair().unwrap();
+        let subject = DistinguishedName::new()
+            .country("CN")
+            .organization("gm-rs")
+            .common_name("tlcp.test");
+        let builder = CertificateBuilder::new(subject)
+            .serial_number(&[0x01, 0x02, 0x03])
+            .validity(1700000000, 2600000000)
+            .extension(Extension::basic_constraints(true, Some(0)))
+            .extension(Extension::key_usage(
+                KEY_USAGE_DIGITAL_SIGNATURE | KEY_USAGE_KEY_CERT_SIGN,
+            ))
+            .extension(Extension::subject_alt_name(&["tlcp.test", "localhost"]));
+        println!("{}", builder.self_signed_pem(&sk).unwrap());
+
+        let der = builder.self_signed_der(&sk).unwrap();
+        let (tbs, sig) = yasna::parse_der(&der, |reader| {
+            reader.read_sequence(|reader| {
+                let tbs = reader.next().read_der()?;
+                reader.next().read_der()?;
+                let (sig, _) = reader.next().read_bitvec_bytes()?;
+                Ok((tbs, sig))
+            })
+        })
+        .unwrap();
+        let sig = Sm2Signature::from_der(&sig).unwrap();
+        pk.verify(None, &tbs, &sig.to_vec()).unwrap();
+
+        // notBefore UTCTime, notAfter GeneralizedTime (2052)
+        assert!(tbs.windows(15).any(|w| w == b"\x17\x0d231114221320Z"));
+        assert!(tbs.windows(17).any(|w| w == b"\x18\x0f20520522141320Z"));
+
+        assert_eq!((1970, 1, 1), civil_from_days(0));
+        assert_eq!((2000, 3, 1), civil_from_days(11017));
+    }
+}
@@ -0,0 +1,69 @@
+use crate::hmac::sm3_hmac;
+use crate::Sm3Error;
+
+/// HKDF (RFC 5869) with HMAC-SM3, extract then expand to `len` bytes.
+///
+/// An empty `salt` stands for 32 zero bytes, `len` is at most 255 * 32.
+///
+/// # Example
+/// ```rust
+/// use gm_sm3::hkdf::hkdf_sm3;
+///
+/// let okm = hkdf_sm3(b"salt", b"input key material", b"context", 16).unwrap();
+/// assert_eq!(16, okm.len());
+/// ```
+pub fn hkdf_sm3(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, Sm3Error> {
+    hkdf_expand(&hkdf_extract(salt, ikm), info, len)
+}
+
+/// `PRK = HMAC-SM3(salt, IKM)`
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; 32] {
+    // HMAC pads the key with zeros, so an empty salt is already 32 zero bytes
+    sm3_hmac(salt, ikm)
+}
+
+/// `T(i) = HMAC-SM3(PRK, T(i-1) || info || i)`, concatenated and cut to `len`
+pub fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>, Sm3Error> {
+    if len > 255 * 32 {
+        return Err(Sm3Error::InvalidHkdfLength);
+    }
+    let mut okm = Vec::with_capacity(len + 32);
+    let mut t: Vec<u8> = vec![];
+    let mut i = 1u8;
+    while okm.len() < len {
+        t = sm3_hmac(prk, &[&t[..], info, &[i]].concat()).to_vec();
+        okm.extend_from_slice(&t);
+        i = i.wrapping_add(1);
+    }
+    okm.truncate(len);
+    Ok(okm)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hkdf::{hkdf_expand, hkdf_extract, hkdf_sm3};
+    use crate::Sm3Error;
+
+    // the inputs of RFC 5869 A.1, outputs from an independent HMAC-SM3
+    #[test]
+    fn test_hkdf() {
+        let ikm = [0x0bu8; 22];
+        let salt: Vec<u8> = (0..13).collect();
+        let info: Vec<u8> = (0xf0..0xfa).collect();
+        assert_eq!(
+            "e0d6f7b0bd056327b7659f1f39ad850561fbcf4fb10fb58e88eafa55cf7cd01e",
+            hex::encode(hkdf_extract(&salt, &ikm))
+        );
+        assert_eq!(
+            "c69fe91b7aaee2dd5718d72dcaee0cce93f1b8e41f792da51261b6a517e68b36ed2c595572b01dfa359b",
+            hex::encode(hkdf_sm3(&salt, &ikm, &info, 42).unwrap())
+        );
+        assert_eq!(
+            "c8c91a38ae2fb3b023a7c38ce9f0748f28230d59b6b950ba3ba949bf0d713a57",
+            hex::encode(hkdf_sm3(b"", &ikm, b"", 32).unwrap())
+        );
+        assert_eq!(hkdf_sm3(b"", &ikm, b"", 32).unwrap(), hkdf_sm3(&[0; 32], &ikm, b"", 32).unwrap());
+        assert_eq!(255 * 32, hkdf_expand(&[1; 32], b"", 255 * 32).unwrap().len());
+        assert!(matches!(hkdf_expand(&[1; 32], b"", 255 * 32 + 1), Err(Sm3Error::InvalidHkdfLength)));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::sm3_hash;
+
+const BLOCK_SIZE: usize = 64;
+
+/// HMAC-SM3 (RFC 2104 with SM3 as the hash function)
+///
+/// # Example
+/// ```rust
+/// use gm_sm3::hmac::sm3_hmac;
+///
+/// let mac = sm3_hmac(b"Jefe", b"what do ya want for nothing?");
+/// assert_eq!(
+///     "2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882",
+///     hex::encode(mac)
+/// );
+/// ```
+pub fn sm3_hmac(key: &[u8], msg: &[u8]) -> [u8; 32] {
+    let mut key_block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        key_block[..32].copy_from_slice(&sm3_hash(key));
+    } else {
+        key_block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut ipad = [0x36u8; BLOCK_SIZE];
+    let mut opad = [0x5cu8; BLOCK_SIZE];
+    for i in 0..BLOCK_SIZE {
+        ipad[i] ^= key_block[i];
+        opad[i] ^= key_block[i];
+    }
+
+    // H((K ^ ipad) || msg)
+    let mut inner = Vec::with_capacity(BLOCK_SIZE + msg.len());
+    inner.extend_from_slice(&ipad);
+    inner.extend_from_slice(msg);
+    let inner = sm3_hash(&inner);
+
+    // H((K ^ opad) || inner)
+    let mut outer = Vec::with_capacity(BLOCK_SIZE + 32);
+    outer.extend_from_slice(&opad);
+    outer.extend_from_slice(&inner);
+    sm3_hash(&outer)
+}
+
+/// Check a 32-byte HMAC-SM3 tag, comparing every byte whatever the first
+/// difference is. Truncated tags are rejected.
+///
+/// # Example
+/// ```rust
+/// use gm_sm3::{sm3_hmac, sm3_hmac_verify};
+///
+/// let tag = sm3_hmac(b"key", b"msg");
+/// assert!(sm3_hmac_verify(b"key", b"msg", &tag));
+/// assert!(!sm3_hmac_verify(b"key", b"msg!", &tag));
+/// ```
+pub fn sm3_hmac_verify(key: &[u8], msg: &[u8], tag: &[u8]) -> bool {
+    if tag.len() != 32 {
+        return false;
+    }
+    sm3_hmac(key, msg)
+        .iter()
+        .zip(tag.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hmac::{sm3_hmac, sm3_hmac_verify};
+
+    #[test]
+    fn test_hmac() {
+        let mac = sm3_hmac(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            "2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882",
+            hex::encode(mac)
+        );
+
+        let key = [0xaau8; 131];
+        let mac = sm3_hmac(&key, b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(
+            "b4fd844e13342002f0b2e0690ea7741f1497d993a70494cea601e657bedf67a0",
+            hex::encode(mac)
+        );
+    }
+
+    #[test]
+    fn test_hmac_verify() {
+        let key = [0xaau8; 131];
+        let mut tag = sm3_hmac(&key, b"msg");
+        assert!(sm3_hmac_verify(&key, b"msg", &tag));
+        assert!(!sm3_hmac_verify(&key[1..], b"msg", &tag));
+        assert!(!sm3_hmac_verify(&key, b"msg", &tag[..16]));
+        tag[31] ^= 1;
+        assert!(!sm3_hmac_verify(&key, b"msg", &tag));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{Sm3Error, Sm3Hasher};
+
+/// The longest output the standard allows, `(2^32 - 1) * 32` bytes
+pub const MAX_KDF_LEN: u128 = u32::MAX as u128 * 32;
+
+/// The key derivation function of GM/T 0003.3 and GM/T 0004, read as a
+/// stream: `SM3(Z || ct)` for `ct = 1, 2, ...`, each counter a 32-bit
+/// big-endian integer.
+///
+/// Output is squeezed as needed, reading `a` then `b` bytes gives the same
+/// bytes as a single read of `a + b`. The standard caps the output at
+/// [`MAX_KDF_LEN`] bytes, past that the reader is exhausted.
+///
+/// # Example
+/// ```rust
+/// use std::io::Read;
+/// use gm_sm3::kdf::{sm3_kdf, Sm3Xof};
+///
+/// let mut xof = Sm3Xof::new(b"shared secret");
+/// let mut a = [0u8; 20];
+/// let mut b = [0u8; 50];
+/// xof.squeeze(&mut a);
+/// xof.read_exact(&mut b).unwrap();
+/// assert_eq!(sm3_kdf(b"shared secret", 70).unwrap(), [&a[..], &b[..]].concat());
+/// ```
+#[derive(Clone)]
+pub struct Sm3Xof {
+    // the state after Z, cloned for every counter
+    z: Sm3Hasher,
+    ct: u32,
+    block: [u8; 32],
+    pos: usize,
+    exhausted: bool,
+}
+
+impl Sm3Xof {
+    pub fn new(z: &[u8]) -> Sm3Xof {
+        let mut hasher = Sm3Hasher::new();
+        hasher.update(z);
+        Sm3Xof {
+            z: hasher,
+            ct: 0,
+            block: [0; 32],
+            pos: 32,
+            exhausted: false,
+        }
+    }
+
+    /// Fill `out` with the next `out.len()` bytes.
+    ///
+    /// # Panics
+    /// Past [`MAX_KDF_LEN`] bytes of output, see [`Sm3Xof::remaining`].
+    pub fn squeeze(&mut self, out: &mut [u8]) {
+        assert!(
+            out.len() as u128 <= self.remaining(),
+            "SM3 KDF output is limited to (2^32 - 1) * 32 bytes"
+        );
+        let mut filled = 0;
+        while filled < out.len() {
+            if self.pos == 32 {
+                self.next_block();
+            }
+            let n = (32 - self.pos).min(out.len() - filled);
+            out[filled..filled + n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+            self.pos += n;
+            filled += n;
+        }
+    }
+
+    /// The next `len` bytes in a new vector
+    pub fn squeeze_vec(&mut self, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        self.squeeze(&mut out);
+        out
+    }
+
+    /// How many bytes can still be squeezed
+    pub fn remaining(&self) -> u128 {
+        if self.exhausted {
+            return (32 - self.pos) as u128;
+        }
+        (u32::MAX - self.ct) as u128 * 32 + (32 - self.pos) as u128
+    }
+
+    fn next_block(&mut self) {
+        self.ct += 1;
+        self.exhausted = self.ct == u32::MAX;
+        let mut hasher = self.z.clone();
+        hasher.update(&self.ct.to_be_bytes());
+        self.block = hasher.finalize();
+        self.pos = 0;
+    }
+}
+
+impl std::io::Read for Sm3Xof {
+    /// Reads stop at the end of the output, a read then returns `Ok(0)`.
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = (buf.len() as u128).min(self.remaining()) as usize;
+        self.squeeze(&mut buf[..n]);
+        Ok(n)
+    }
+}
+
+/// The first `klen` bytes of the KDF of `z`, `klen` in `[1, MAX_KDF_LEN]`
+pub fn sm3_kdf(z: &[u8], klen: usize) -> Result<Vec<u8>, Sm3Error> {
+    if klen == 0 || klen as u128 > MAX_KDF_LEN {
+        return Err(Sm3Error::InvalidKeyLength);
+    }
+    Ok(Sm3Xof::new(z).squeeze_vec(klen))
+}
+
+#[cfg(test)]
+mod test_kdf {
+    use std::io::Read;
+
+    use super::*;
+    use crate::sm3_hash;
+
+    #[test]
+    fn test_kdf_blocks() {
+        let z = b"GM/T 0003.3";
+        let expected: Vec<u8> = (1u32..=4)
+            .flat_map(|ct| sm3_hash(&[&z[..], &ct.to_be_bytes()].concat()))
+            .collect();
+        for klen in [1, 31, 32, 33, 64, 100, 128] {
+            assert_eq!(expected[..klen], sm3_kdf(z, klen).unwrap());
+        }
+        assert!(matches!(sm3_kdf(z, 0), Err(Sm3Error::InvalidKeyLength)));
+        #[cfg(target_pointer_width = "64")]
+        assert!(matches!(sm3_kdf(z, MAX_KDF_LEN as usize + 1), Err(Sm3Error::InvalidKeyLength)));
+
+        // any split of the output reads the same bytes
+        for split in [0, 1, 31, 32, 33, 95] {
+            let mut xof = Sm3Xof::new(z);
+            let mut out = xof.squeeze_vec(split);
+            let mut rest = vec![0u8; 128 - split];
+            xof.read_exact(&mut rest).unwrap();
+            out.extend_from_slice(&rest);
+            assert_eq!(expected, out);
+        }
+    }
+
+    #[test]
+    fn test_kdf_exhausted() {
+        let mut xof = Sm3Xof::new(b"z");
+        xof.ct = u32::MAX - 2;
+        assert_eq!(64, xof.remaining());
+        let mut buf = [0u8; 100];
+        assert_eq!(64, xof.read(&mut buf).unwrap());
+        assert_eq!(0, xof.read(&mut buf).unwrap());
+        assert_eq!(sm3_hash(&[&b"z"[..], &u32::MAX.to_be_bytes()].concat()), buf[32..64]);
+    }
+}
@@ -2,12 +2,25 @@
 
 use std::fmt::{Display, Formatter};
 use const_oid::ObjectIdentifier;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub mod hkdf;
+pub mod hmac;
+pub mod kdf;
+pub mod pbkdf2;
+
+pub use hmac::{sm3_hmac, sm3_hmac_verify};
 
 
 pub const OID_SM3: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.156.10197.1.401");
 
 pub enum Sm3Error {
     ErrorMsgLen,
+    InvalidKeyLength,
+    InvalidDigestLength,
+    InvalidHkdfLength,
+    InvalidPbkdf2Params,
 }
 
 impl std::fmt::Debug for Sm3Error {
@@ -20,6 +33,10 @@ impl From<Sm3Error> for &str {
     fn from(e: Sm3Error) -> Self {
         match e {
             Sm3Error::ErrorMsgLen => "SM3 Pad error: error msg len",
+            Sm3Error::InvalidKeyLength => "the KDF output length must be in [1, (2^32 - 1) * 32] bytes",
+            Sm3Error::InvalidDigestLength => "an SM3 digest is 32 bytes",
+            Sm3Error::InvalidHkdfLength => "the HKDF output length must be at most 255 * 32 bytes",
+            Sm3Error::InvalidPbkdf2Params => "PBKDF2 needs at least one iteration and an output of 1 to (2^32 - 1) * 32 bytes",
         }
     }
 }
@@ -28,11 +45,62 @@ impl Display for Sm3Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let err_msg = match self {
             Sm3Error::ErrorMsgLen => "SM3 Pad error: error msg len",
+            Sm3Error::InvalidKeyLength => "the KDF output length must be in [1, (2^32 - 1) * 32] bytes",
+            Sm3Error::InvalidDigestLength => "an SM3 digest is 32 bytes",
+            Sm3Error::InvalidHkdfLength => "the HKDF output length must be at most 255 * 32 bytes",
+            Sm3Error::InvalidPbkdf2Params => "PBKDF2 needs at least one iteration and an output of 1 to (2^32 - 1) * 32 bytes",
         };
         write!(f, "{}", err_msg)
     }
 }
 
+/// An SM3 digest, which cannot be passed where a key or an arbitrary slice
+/// is expected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sm3Digest(pub [u8; 32]);
+
+impl Sm3Digest {
+    pub fn of(msg: &[u8]) -> Sm3Digest {
+        Sm3Digest(sm3_hash(msg))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Sm3Digest {
+    fn from(b: [u8; 32]) -> Self {
+        Sm3Digest(b)
+    }
+}
+
+impl From<Sm3Digest> for [u8; 32] {
+    fn from(d: Sm3Digest) -> Self {
+        d.0
+    }
+}
+
+impl TryFrom<&[u8]> for Sm3Digest {
+    type Error = Sm3Error;
+
+    fn try_from(b: &[u8]) -> Result<Self, Sm3Error> {
+        b.try_into().map(Sm3Digest).map_err(|_| Sm3Error::InvalidDigestLength)
+    }
+}
+
+impl AsRef<[u8]> for Sm3Digest {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::LowerHex for Sm3Digest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
 // 0 ≤ j ≤ 15
 pub(crate) const T00: u32 = 0x79cc4519;
 
@@ -92,26 +160,169 @@ fn t(j: usize) -> u32 {
 /// ```
 ///
 pub fn sm3_hash(msg: &[u8]) -> [u8; 32] {
-    let msg = pad(msg).unwrap();
-    let len = msg.len();
-    let mut b_i: [u8; 64] = [0; 64];
-    let mut count_group: usize = 0;
-    let mut v_i = IV;
-    while count_group * 64 != len {
-        for i in (count_group * 64)..(count_group * 64 + 64) {
-            b_i[i - count_group * 64] = msg[i];
+    let mut hasher = Sm3Hasher::new();
+    hasher.update(msg);
+    hasher.finalize()
+}
+
+/// [`sm3_hash`] into `out`, which must be exactly 32 bytes. Nothing is
+/// allocated.
+pub fn sm3_hash_into(msg: &[u8], out: &mut [u8]) -> Result<(), Sm3Error> {
+    let out: &mut [u8; 32] = out.try_into().map_err(|_| Sm3Error::InvalidDigestLength)?;
+    let mut hasher = Sm3Hasher::new();
+    hasher.update(msg);
+    hasher.finalize_into(out);
+    Ok(())
+}
+
+/// Incremental SM3 for data that does not fit in memory.
+///
+/// It also implements [`std::io::Write`], so a file can be hashed with
+/// `std::io::copy(&mut file, &mut hasher)`. The hasher is `Send` and owns its
+/// state, in async code it can be fed chunk by chunk as they arrive, or moved
+/// into a blocking task together with a reader, see [`sm3_hash_reader`].
+///
+/// # Example
+/// ```rust
+/// use gm_sm3::{sm3_hash, Sm3Hasher};
+///
+/// let mut hasher = Sm3Hasher::new();
+/// hasher.update(b"ab");
+/// hasher.update(b"c");
+/// assert_eq!(sm3_hash(b"abc"), hasher.finalize());
+/// ```
+#[derive(Clone)]
+pub struct Sm3Hasher {
+    v: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Default for Sm3Hasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sm3Hasher {
+    pub fn new() -> Sm3Hasher {
+        Sm3Hasher {
+            v: IV,
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
         }
-        cf(&mut v_i, b_i);
-        count_group += 1;
     }
-    let mut output: [u8; 32] = [0; 32];
-    for i in 0..8 {
-        output[i * 4] = (v_i[i] >> 24) as u8;
-        output[i * 4 + 1] = (v_i[i] >> 16) as u8;
-        output[i * 4 + 2] = (v_i[i] >> 8) as u8;
-        output[i * 4 + 3] = v_i[i] as u8;
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        if self.block_len > 0 {
+            let n = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + n].copy_from_slice(&data[..n]);
+            self.block_len += n;
+            data = &data[n..];
+            if self.block_len < 64 {
+                return;
+            }
+            cf(&mut self.v, self.block);
+            self.block_len = 0;
+        }
+        let mut chunks = data.chunks_exact(64);
+        for chunk in &mut chunks {
+            let mut b_i = [0u8; 64];
+            b_i.copy_from_slice(chunk);
+            cf(&mut self.v, b_i);
+        }
+        let rest = chunks.remainder();
+        self.block[..rest.len()].copy_from_slice(rest);
+        self.block_len = rest.len();
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        let mut output: [u8; 32] = [0; 32];
+        self.finalize_into(&mut output);
+        output
+    }
+
+    pub fn finalize_into(mut self, out: &mut [u8; 32]) {
+        let bit_length = self.total_len << 3;
+        self.update(&[0x80]);
+        self.update(&[0x00; 64][..(64 + 56 - self.block_len) % 64]);
+        self.update(&bit_length.to_be_bytes());
+
+        for i in 0..8 {
+            out[i * 4..i * 4 + 4].copy_from_slice(&self.v[i].to_be_bytes());
+        }
+    }
+}
+
+/// Hash everything `reader` yields, 64 KiB at a time.
+///
+/// This blocks until the reader is exhausted, from an async runtime call it
+/// inside `spawn_blocking` rather than on a worker thread.
+pub fn sm3_hash_reader<R: std::io::Read>(reader: R) -> std::io::Result<[u8; 32]> {
+    hash_reader_with_progress(reader, |_| {})
+}
+
+fn hash_reader_with_progress<R: std::io::Read>(
+    mut reader: R,
+    mut progress: impl FnMut(u64),
+) -> std::io::Result<[u8; 32]> {
+    let mut hasher = Sm3Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut done = 0u64;
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(n) => {
+                hasher.update(&buf[..n]);
+                done += n as u64;
+                progress(done);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Hash the file at `path` with buffered reads.
+pub fn sm3_hash_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<[u8; 32]> {
+    std::fs::File::open(path).and_then(sm3_hash_reader)
+}
+
+/// [`sm3_hash_file`] calling `progress(hashed, total)` after every 64 KiB
+/// read, `total` is the file size when it was opened.
+pub fn sm3_hash_file_with_progress<P: AsRef<std::path::Path>>(
+    path: P,
+    mut progress: impl FnMut(u64, u64),
+) -> std::io::Result<[u8; 32]> {
+    let file = std::fs::File::open(path)?;
+    let total = file.metadata()?.len();
+    hash_reader_with_progress(file, |done| progress(done, total))
+}
+
+/// Hash every file in `paths`, the results are in the order of `paths`.
+/// With the `parallel` feature the files are hashed on the rayon thread pool.
+pub fn sm3_hash_files<P: AsRef<std::path::Path> + Sync>(paths: &[P]) -> Vec<std::io::Result<[u8; 32]>> {
+    #[cfg(feature = "parallel")]
+    let paths = paths.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let paths = paths.iter();
+    paths
+        .map(sm3_hash_file)
+        .collect()
+}
+
+impl std::io::Write for Sm3Hasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
     }
-    output
 }
 
 fn cf(v_i: &mut [u32; 8], b_i: [u8; 64]) {
@@ -188,32 +399,27 @@ fn cf(v_i: &mut [u32; 8], b_i: [u8; 64]) {
     v_i[7] ^= h;
 }
 
-fn pad(msg: &[u8]) -> Result<Vec<u8>, Sm3Error> {
-    let bit_length = (msg.len() << 3) as u64;
-    let mut msg = msg.to_vec();
-    msg.push(0x80);
-    let blocksize = 64;
-    while msg.len() % blocksize != 56 {
-        msg.push(0x00);
-    }
-    msg.push((bit_length >> 56 & 0xff) as u8);
-    msg.push((bit_length >> 48 & 0xff) as u8);
-    msg.push((bit_length >> 40 & 0xff) as u8);
-    msg.push((bit_length >> 32 & 0xff) as u8);
-    msg.push((bit_length >> 24 & 0xff) as u8);
-    msg.push((bit_length >> 16 & 0xff) as u8);
-    msg.push((bit_length >> 8 & 0xff) as u8);
-    msg.push((bit_length & 0xff) as u8);
-    if msg.len() % 64 != 0 {
-        return Err(Sm3Error::ErrorMsgLen);
-    }
-    Ok(msg)
-}
-
 #[cfg(test)]
 mod test {
     use crate::*;
 
+    #[test]
+    fn test_digest_type() {
+        let d = Sm3Digest::of(b"abc");
+        assert_eq!(sm3_hash(b"abc"), <[u8; 32]>::from(d));
+        assert_eq!("66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0", format!("{:x}", d));
+        assert_eq!(d, Sm3Digest::try_from(d.as_ref()).unwrap());
+        assert!(matches!(Sm3Digest::try_from(&d.as_bytes()[..31]), Err(Sm3Error::InvalidDigestLength)));
+    }
+
+    #[test]
+    fn test_hash_into() {
+        let mut out = [0u8; 33];
+        sm3_hash_into(b"abc", &mut out[..32]).unwrap();
+        assert_eq!(sm3_hash(b"abc"), out[..32]);
+        assert!(matches!(sm3_hash_into(b"abc", &mut out), Err(Sm3Error::InvalidDigestLength)));
+    }
+
     #[test]
     fn test_hash_1() {
         let hash = sm3_hash(b"abc");
@@ -233,4 +439,58 @@ mod test {
             r
         );
     }
+
+    #[test]
+    fn test_hasher() {
+        let msg: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        for len in [0, 1, 55, 56, 63, 64, 65, 128, 1000] {
+            for step in [1, 7, 64, 100] {
+                let mut hasher = Sm3Hasher::new();
+                for chunk in msg[..len].chunks(step) {
+                    hasher.update(chunk);
+                }
+                assert_eq!(sm3_hash(&msg[..len]), hasher.finalize());
+            }
+        }
+
+        let mut hasher = Sm3Hasher::default();
+        std::io::copy(&mut &msg[..], &mut hasher).unwrap();
+        assert_eq!(sm3_hash(&msg), hasher.finalize());
+        assert_eq!(sm3_hash(&msg), sm3_hash_reader(&msg[..]).unwrap());
+    }
+
+    #[test]
+    fn test_hash_files() {
+        let dir = std::env::temp_dir();
+        let paths: Vec<_> = (0..3)
+            .map(|i| dir.join(format!("gm-sm3-files-{}-{}.txt", std::process::id(), i)))
+            .collect();
+        for (i, p) in paths.iter().enumerate() {
+            std::fs::write(p, vec![b'a'; i * 100]).unwrap();
+        }
+        let mut all = paths.clone();
+        all.push(dir.join("gm-sm3-files-missing"));
+        let hashes = sm3_hash_files(&all);
+        for (i, h) in hashes[..3].iter().enumerate() {
+            assert_eq!(&sm3_hash(&vec![b'a'; i * 100]), h.as_ref().unwrap());
+        }
+        assert!(hashes[3].is_err());
+        for p in paths {
+            std::fs::remove_file(p).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_hash_file_progress() {
+        let path = std::env::temp_dir().join(format!("gm-sm3-progress-{}.bin", std::process::id()));
+        let data = vec![0x5au8; 200 * 1024];
+        std::fs::write(&path, &data).unwrap();
+        let mut calls = vec![];
+        let hash = sm3_hash_file_with_progress(&path, |done, total| calls.push((done, total))).unwrap();
+        assert_eq!(sm3_hash(&data), hash);
+        assert_eq!(hash, sm3_hash_file(&path).unwrap());
+        assert_eq!(Some(&(200 * 1024, 200 * 1024)), calls.last());
+        assert!(calls.windows(2).all(|w| w[0].0 < w[1].0));
+        std::fs::remove_file(path).unwrap();
+    }
 }
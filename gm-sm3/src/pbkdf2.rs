@@ -0,0 +1,146 @@
+use crate::hmac::sm3_hmac;
+use crate::Sm3Error;
+
+const PHC_ID: &str = "$pbkdf2-sm3$";
+
+/// The longest hash [`verify_password`] derives, the length comes from the
+/// stored string
+pub const MAX_HASH_LEN: usize = 64;
+
+/// The highest iteration count a reader of key files should accept, counts
+/// come from the file and are spent before the MAC can be checked. Ten
+/// million iterations take seconds, a crafted `u32::MAX` would take hours.
+pub const MAX_ITERATIONS: u32 = 10_000_000;
+
+/// PBKDF2 (RFC 8018) with HMAC-SM3 as the PRF, failing with
+/// [`Sm3Error::InvalidPbkdf2Params`] for 0 iterations or an output length out
+/// of [1, (2^32 - 1) * 32].
+///
+/// # Example
+/// ```rust
+/// use gm_sm3::pbkdf2::pbkdf2_sm3;
+///
+/// let dk = pbkdf2_sm3(b"password", b"salt", 1, 32).unwrap();
+/// assert_eq!(
+///     "4612f922a1fdcefaf4312fc6f8f3322b489cbf24f2ea361b44c2bd8fa2c6dcb0",
+///     hex::encode(dk)
+/// );
+/// ```
+pub fn pbkdf2_sm3(password: &[u8], salt: &[u8], iterations: u32, dklen: usize) -> Result<Vec<u8>, Sm3Error> {
+    if iterations == 0 || dklen == 0 || dklen as u64 > u32::MAX as u64 * 32 {
+        return Err(Sm3Error::InvalidPbkdf2Params);
+    }
+    let mut dk = Vec::with_capacity(dklen + 32);
+    let mut block = 1u32;
+    while dk.len() < dklen {
+        let mut salt_block = salt.to_vec();
+        salt_block.extend_from_slice(&block.to_be_bytes());
+        let mut u = sm3_hmac(password, &salt_block);
+        let mut t = u;
+        for _ in 1..iterations {
+            u = sm3_hmac(password, &u);
+            for (a, b) in t.iter_mut().zip(u.iter()) {
+                *a ^= b;
+            }
+        }
+        dk.extend_from_slice(&t);
+        block += 1;
+    }
+    dk.truncate(dklen);
+    Ok(dk)
+}
+
+/// Hash a password for storage, the result is a PHC-style string
+/// `$pbkdf2-sm3$i=<iterations>$<salt hex>$<hash hex>`.
+///
+/// The caller provides a random salt of at least 16 bytes.
+pub fn hash_password(password: &[u8], salt: &[u8], iterations: u32) -> Result<String, Sm3Error> {
+    let dk = pbkdf2_sm3(password, salt, iterations, 32)?;
+    Ok(format!(
+        "{}i={}${}${}",
+        PHC_ID,
+        iterations,
+        to_hex(salt),
+        to_hex(&dk)
+    ))
+}
+
+/// Check `password` against a string produced by [`hash_password`]. Hashes
+/// longer than [`MAX_HASH_LEN`] are rejected before deriving anything.
+pub fn verify_password(password: &[u8], encoded: &str) -> bool {
+    let rest = match encoded.strip_prefix(PHC_ID) {
+        Some(r) => r,
+        None => return false,
+    };
+    let parts: Vec<&str> = rest.split('$').collect();
+    if parts.len() != 3 {
+        return false;
+    }
+    let iterations = match parts[0].strip_prefix("i=").map(|i| i.parse::<u32>()) {
+        Some(Ok(i)) if i > 0 => i,
+        _ => return false,
+    };
+    let (salt, expected) = match (from_hex(parts[1]), from_hex(parts[2])) {
+        (Some(s), Some(h)) if !h.is_empty() && h.len() <= MAX_HASH_LEN => (s, h),
+        _ => return false,
+    };
+    let dk = match pbkdf2_sm3(password, &salt, iterations, expected.len()) {
+        Ok(dk) => dk,
+        Err(_) => return false,
+    };
+    dk.iter()
+        .zip(expected.iter())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+fn to_hex(b: &[u8]) -> String {
+    b.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pbkdf2::{hash_password, pbkdf2_sm3, verify_password, MAX_HASH_LEN};
+
+    #[test]
+    fn test_pbkdf2() {
+        let dk = pbkdf2_sm3(b"password", b"salt", 1, 32).unwrap();
+        assert_eq!(
+            "4612f922a1fdcefaf4312fc6f8f3322b489cbf24f2ea361b44c2bd8fa2c6dcb0",
+            hex::encode(dk)
+        );
+
+        let dk = pbkdf2_sm3(b"password", b"salt", 4096, 40).unwrap();
+        assert_eq!(
+            "b6e8f2074c87432b78f62e5ced980fdff89e86af2f693dab1638e2b3683045dd844438500eead50c",
+            hex::encode(dk)
+        );
+
+        assert!(pbkdf2_sm3(b"password", b"salt", 0, 32).is_err());
+        assert!(pbkdf2_sm3(b"password", b"salt", 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_password_hash() {
+        assert!(hash_password(b"123456", b"0123456789abcdef", 0).is_err());
+        let encoded = hash_password(b"123456", b"0123456789abcdef", 1000).unwrap();
+        println!("{}", encoded);
+        assert!(encoded.starts_with("$pbkdf2-sm3$i=1000$30313233"));
+        assert!(verify_password(b"123456", &encoded));
+        assert!(!verify_password(b"1234567", &encoded));
+        assert!(!verify_password(b"123456", "$pbkdf2-sm3$i=0$00$00"));
+        assert!(!verify_password(b"123456", "$pbkdf2-sha256$i=1$00$00"));
+        let long = format!("$pbkdf2-sm3$i=1$00${}", "00".repeat(MAX_HASH_LEN + 1));
+        assert!(!verify_password(b"123456", &long));
+    }
+}
@@ -0,0 +1,193 @@
+//! The RustCrypto `aead` traits for SM4-GCM and SM4-CCM (enable the `aead`
+//! feature), so that code generic over `aead::Aead` or `AeadInPlace` runs on
+//! SM4. The `aead` crate is re-exported as [`aead`](::aead).
+//!
+//! [`Sm4Gcm`] takes a 12-byte nonce and gives a 16-byte tag. The traits fix
+//! the sizes at the type level, so CCM is [`Sm4CcmAead`] with the nonce and
+//! tag lengths as type parameters, [`Sm4CcmRfc8998`] the ones of RFC 8998.
+//! [`gm_core::Aead`], which the modes also implement, is the workspace's own
+//! trait over slices.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm4::aead::aead::{Aead, KeyInit, Payload};
+//! use gm_sm4::aead::Sm4CcmRfc8998;
+//! use gm_sm4::gcm::Sm4Gcm;
+//!
+//! fn roundtrip<A: Aead + KeyInit>(key: &[u8]) -> Vec<u8> {
+//!     let a = A::new_from_slice(key).unwrap();
+//!     let nonce = Default::default();
+//!     let sealed = a.encrypt(&nonce, Payload { msg: b"hello", aad: b"header" }).unwrap();
+//!     a.decrypt(&nonce, Payload { msg: &sealed, aad: b"header" }).unwrap()
+//! }
+//!
+//! assert_eq!(b"hello".to_vec(), roundtrip::<Sm4Gcm>(&[0x11; 16]));
+//! assert_eq!(b"hello".to_vec(), roundtrip::<Sm4CcmRfc8998>(&[0x11; 16]));
+//! ```
+use std::marker::PhantomData;
+
+pub use aead;
+use aead::consts::{U0, U10, U11, U12, U13, U14, U16, U4, U6, U7, U8, U9};
+use aead::generic_array::{ArrayLength, GenericArray};
+use aead::{AeadCore, AeadInPlace, Error, Key, KeyInit, KeySizeUser, Nonce, Tag};
+
+use crate::ccm::Sm4Ccm;
+use crate::gcm::Sm4Gcm;
+use crate::Sm4Result;
+
+/// Split `ciphertext || tag` into `buffer` and the returned tag
+fn detach<T: ArrayLength<u8>>(sealed: Sm4Result<Vec<u8>>, buffer: &mut [u8]) -> aead::Result<GenericArray<u8, T>> {
+    let sealed = sealed.map_err(|_| Error)?;
+    let (ct, tag) = sealed.split_at(buffer.len());
+    buffer.copy_from_slice(ct);
+    Ok(GenericArray::clone_from_slice(tag))
+}
+
+fn attach(opened: Sm4Result<Vec<u8>>, buffer: &mut [u8]) -> aead::Result<()> {
+    buffer.copy_from_slice(&opened.map_err(|_| Error)?);
+    Ok(())
+}
+
+impl KeySizeUser for Sm4Gcm {
+    type KeySize = U16;
+}
+
+impl KeyInit for Sm4Gcm {
+    fn new(key: &Key<Self>) -> Self {
+        Sm4Gcm::new(key).expect("an SM4 key is 16 bytes")
+    }
+}
+
+impl AeadCore for Sm4Gcm {
+    type NonceSize = U12;
+    type TagSize = U16;
+    type CiphertextOverhead = U0;
+}
+
+impl AeadInPlace for Sm4Gcm {
+    fn encrypt_in_place_detached(&self, nonce: &Nonce<Self>, aad: &[u8], buffer: &mut [u8]) -> aead::Result<Tag<Self>> {
+        detach(Sm4Gcm::encrypt(self, nonce, aad, buffer), buffer)
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        attach(Sm4Gcm::decrypt(self, nonce, aad, &[&buffer[..], tag].concat()), buffer)
+    }
+}
+
+/// The CCM nonce lengths, 7 to 13 bytes
+pub trait CcmNonceSize: ArrayLength<u8> {}
+
+/// The CCM tag lengths, 4, 6, ..., 16 bytes
+pub trait CcmTagSize: ArrayLength<u8> {}
+
+impl CcmNonceSize for U7 {}
+impl CcmNonceSize for U8 {}
+impl CcmNonceSize for U9 {}
+impl CcmNonceSize for U10 {}
+impl CcmNonceSize for U11 {}
+impl CcmNonceSize for U12 {}
+impl CcmNonceSize for U13 {}
+
+impl CcmTagSize for U4 {}
+impl CcmTagSize for U6 {}
+impl CcmTagSize for U8 {}
+impl CcmTagSize for U10 {}
+impl CcmTagSize for U12 {}
+impl CcmTagSize for U14 {}
+impl CcmTagSize for U16 {}
+
+/// [`Sm4Ccm`] with an `N`-byte nonce and a `T`-byte tag
+#[derive(Debug, Clone)]
+pub struct Sm4CcmAead<N, T> {
+    ccm: Sm4Ccm,
+    sizes: PhantomData<(N, T)>,
+}
+
+/// The 12-byte nonce and 16-byte tag of RFC 8998
+pub type Sm4CcmRfc8998 = Sm4CcmAead<U12, U16>;
+
+impl<N: CcmNonceSize, T: CcmTagSize> KeySizeUser for Sm4CcmAead<N, T> {
+    type KeySize = U16;
+}
+
+impl<N: CcmNonceSize, T: CcmTagSize> KeyInit for Sm4CcmAead<N, T> {
+    fn new(key: &Key<Self>) -> Self {
+        Sm4CcmAead {
+            ccm: Sm4Ccm::new(key, N::USIZE, T::USIZE).expect("the sizes are checked by the bounds"),
+            sizes: PhantomData,
+        }
+    }
+}
+
+impl<N: CcmNonceSize, T: CcmTagSize> AeadCore for Sm4CcmAead<N, T> {
+    type NonceSize = N;
+    type TagSize = T;
+    type CiphertextOverhead = U0;
+}
+
+impl<N: CcmNonceSize, T: CcmTagSize> AeadInPlace for Sm4CcmAead<N, T> {
+    fn encrypt_in_place_detached(&self, nonce: &Nonce<Self>, aad: &[u8], buffer: &mut [u8]) -> aead::Result<Tag<Self>> {
+        detach(self.ccm.encrypt(nonce, aad, buffer), buffer)
+    }
+
+    fn decrypt_in_place_detached(
+        &self,
+        nonce: &Nonce<Self>,
+        aad: &[u8],
+        buffer: &mut [u8],
+        tag: &Tag<Self>,
+    ) -> aead::Result<()> {
+        attach(self.ccm.decrypt(nonce, aad, &[&buffer[..], tag].concat()), buffer)
+    }
+}
+
+#[cfg(test)]
+mod test_aead {
+    use super::*;
+    use aead::{Aead, Payload};
+
+    const KEY: &str = "0123456789abcdeffedcba9876543210";
+    const NONCE: &str = "00001234567800000000abcd";
+    const AAD: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+
+    /// The output of the traits is the one of the native modes
+    fn check<A: AeadInPlace + KeyInit>(native: impl Fn(&[u8], &[u8], &[u8]) -> Vec<u8>) {
+        let (key, nonce, aad) = (hex::decode(KEY).unwrap(), hex::decode(NONCE).unwrap(), hex::decode(AAD).unwrap());
+        let a = A::new_from_slice(&key).unwrap();
+        let nonce = Nonce::<A>::from_slice(&nonce);
+        let pt = [0xaau8; 37];
+        let sealed = a.encrypt(nonce, Payload { msg: &pt, aad: &aad }).unwrap();
+        assert_eq!(native(nonce, &aad, &pt), sealed);
+        assert_eq!(pt.to_vec(), a.decrypt(nonce, Payload { msg: &sealed, aad: &aad }).unwrap());
+
+        let mut buffer = pt;
+        let tag = a.encrypt_in_place_detached(nonce, &aad, &mut buffer).unwrap();
+        assert_eq!(&sealed[..pt.len()], &buffer[..]);
+        let mut bad = tag.clone();
+        bad[0] ^= 1;
+        assert!(a.decrypt_in_place_detached(nonce, &aad, &mut buffer.clone(), &bad).is_err());
+        a.decrypt_in_place_detached(nonce, &aad, &mut buffer, &tag).unwrap();
+        assert_eq!(pt, buffer);
+    }
+
+    #[test]
+    fn test_gcm() {
+        let gcm = Sm4Gcm::new(&hex::decode(KEY).unwrap()).unwrap();
+        check::<Sm4Gcm>(|n, a, p| gcm.encrypt(n, a, p).unwrap());
+    }
+
+    #[test]
+    fn test_ccm() {
+        let key = hex::decode(KEY).unwrap();
+        let ccm = Sm4Ccm::new(&key, 12, 16).unwrap();
+        check::<Sm4CcmRfc8998>(|n, a, p| ccm.encrypt(n, a, p).unwrap());
+        let ccm = Sm4Ccm::new(&key, 12, 8).unwrap();
+        check::<Sm4CcmAead<U12, U8>>(|n, a, p| ccm.encrypt(n, a, p).unwrap());
+    }
+}
@@ -0,0 +1,238 @@
+use gm_core::{ct_eq, Aead};
+
+use crate::{to_block, Sm4Cipher, Sm4Error, Sm4Result};
+
+/// SM4-CCM (NIST SP 800-38C / RFC 3610 with SM4 as the block cipher, RFC 8998)
+///
+/// The tag length is one of 4, 6, ..., 16 bytes and the nonce is 7 to 13 bytes,
+/// a shorter nonce leaves more room for the message length. The output of
+/// `encrypt` is `ciphertext || tag`.
+///
+/// # Example
+/// ```rust
+/// use gm_sm4::ccm::Sm4Ccm;
+///
+/// let ccm = Sm4Ccm::new(&[0x11u8; 16], 12, 8).unwrap();
+/// let nonce = [0u8; 12];
+/// let sealed = ccm.encrypt(&nonce, b"header", b"hello").unwrap();
+/// assert_eq!(5 + 8, sealed.len());
+/// let opened = ccm.decrypt(&nonce, b"header", &sealed).unwrap();
+/// assert_eq!(b"hello".to_vec(), opened);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sm4Ccm {
+    cipher: Sm4Cipher,
+    nonce_len: usize,
+    tag_len: usize,
+}
+
+impl Sm4Ccm {
+    pub fn new(key: &[u8], nonce_len: usize, tag_len: usize) -> Sm4Result<Sm4Ccm> {
+        if key.len() != 16 {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        if !(7..=13).contains(&nonce_len) || !(4..=16).contains(&tag_len) || !tag_len.is_multiple_of(2) {
+            return Err(Sm4Error::ErrorBlockSize);
+        }
+        Ok(Sm4Ccm {
+            cipher: Sm4Cipher::new(key)?,
+            nonce_len,
+            tag_len,
+        })
+    }
+
+    pub fn tag_len(&self) -> usize {
+        self.tag_len
+    }
+
+    /// The plaintext followed by the `tag_len`-byte tag
+    pub fn ciphertext_len(&self, plain_len: usize) -> usize {
+        plain_len + self.tag_len
+    }
+
+    pub fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Sm4Result<Vec<u8>> {
+        self.check(nonce, plaintext.len())?;
+        let tag = self.mac(nonce, aad, plaintext)?;
+        let mut out = self.ctr(nonce, plaintext)?;
+        out.extend_from_slice(&self.encrypt_tag(nonce, &tag)?);
+        Ok(out)
+    }
+
+    pub fn decrypt(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Sm4Result<Vec<u8>> {
+        if ciphertext.len() < self.tag_len {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let (ct, tag) = ciphertext.split_at(ciphertext.len() - self.tag_len);
+        self.check(nonce, ct.len())?;
+        let pt = self.ctr(nonce, ct)?;
+        let expected = self.encrypt_tag(nonce, &self.mac(nonce, aad, &pt)?)?;
+        if !ct_eq(&expected, tag) {
+            return Err(Sm4Error::InvalidTag);
+        }
+        Ok(pt)
+    }
+
+    /// The message length must fit into the `15 - nonce_len` bytes left in B0.
+    fn check(&self, nonce: &[u8], msg_len: usize) -> Sm4Result<()> {
+        if nonce.len() != self.nonce_len {
+            return Err(Sm4Error::ErrorBlockSize);
+        }
+        let l = 15 - self.nonce_len;
+        if l < 8 && (msg_len as u64) >> (8 * l) != 0 {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        Ok(())
+    }
+
+    /// Counter block `A_i = flags || nonce || i`
+    fn counter(&self, nonce: &[u8], i: u64) -> [u8; 16] {
+        let l = 15 - self.nonce_len;
+        let mut a = [0u8; 16];
+        a[0] = (l - 1) as u8;
+        a[1..=self.nonce_len].copy_from_slice(nonce);
+        a[16 - l..].copy_from_slice(&(i as u128).to_be_bytes()[16 - l..]);
+        a
+    }
+
+    fn ctr(&self, nonce: &[u8], data: &[u8]) -> Sm4Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len());
+        for (i, chunk) in data.chunks(16).enumerate() {
+            let ks = self.cipher.encrypt(&self.counter(nonce, i as u64 + 1))?;
+            out.extend(chunk.iter().zip(ks.iter()).map(|(a, b)| a ^ b));
+        }
+        Ok(out)
+    }
+
+    fn encrypt_tag(&self, nonce: &[u8], tag: &[u8; 16]) -> Sm4Result<Vec<u8>> {
+        let s0 = self.cipher.encrypt(&self.counter(nonce, 0))?;
+        Ok(tag.iter().zip(s0.iter()).take(self.tag_len).map(|(a, b)| a ^ b).collect())
+    }
+
+    /// CBC-MAC over `B0 || encoded aad || message`, both zero padded to blocks
+    fn mac(&self, nonce: &[u8], aad: &[u8], msg: &[u8]) -> Sm4Result<[u8; 16]> {
+        let l = 15 - self.nonce_len;
+        let mut b0 = [0u8; 16];
+        b0[0] = (u8::from(!aad.is_empty()) << 6) | ((((self.tag_len - 2) / 2) as u8) << 3) | (l - 1) as u8;
+        b0[1..=self.nonce_len].copy_from_slice(nonce);
+        b0[16 - l..].copy_from_slice(&(msg.len() as u128).to_be_bytes()[16 - l..]);
+
+        let mut header = vec![];
+        if !aad.is_empty() {
+            let a = aad.len() as u64;
+            if a < 0xff00 {
+                header.extend_from_slice(&(a as u16).to_be_bytes());
+            } else if a <= u32::MAX as u64 {
+                header.extend_from_slice(&[0xff, 0xfe]);
+                header.extend_from_slice(&(a as u32).to_be_bytes());
+            } else {
+                header.extend_from_slice(&[0xff, 0xff]);
+                header.extend_from_slice(&a.to_be_bytes());
+            }
+            header.extend_from_slice(aad);
+        }
+
+        let mut y = to_block(&self.cipher.encrypt(&b0)?);
+        for data in [&header[..], msg] {
+            for chunk in data.chunks(16) {
+                for (y, b) in y.iter_mut().zip(chunk) {
+                    *y ^= b;
+                }
+                y = to_block(&self.cipher.encrypt(&y)?);
+            }
+        }
+        Ok(y)
+    }
+}
+
+impl Aead for Sm4Ccm {
+    type Error = Sm4Error;
+
+    fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Sm4Result<Vec<u8>> {
+        Sm4Ccm::encrypt(self, nonce, aad, plaintext)
+    }
+
+    fn decrypt(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Sm4Result<Vec<u8>> {
+        Sm4Ccm::decrypt(self, nonce, aad, ciphertext)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ccm::Sm4Ccm;
+    use crate::Sm4Error;
+
+    #[test]
+    fn test_ccm_rfc8998() {
+        let key = hex::decode("0123456789abcdeffedcba9876543210").unwrap();
+        let nonce = hex::decode("00001234567800000000abcd").unwrap();
+        let aad = hex::decode("feedfacedeadbeeffeedfacedeadbeefabaddad2").unwrap();
+        let pt = hex::decode(
+            "aaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbccccccccccccccccdddddddddddddddd\
+             eeeeeeeeeeeeeeeeffffffffffffffffeeeeeeeeeeeeeeeeaaaaaaaaaaaaaaaa",
+        )
+        .unwrap();
+        let ccm = Sm4Ccm::new(&key, 12, 16).unwrap();
+        let sealed = ccm.encrypt(&nonce, &aad, &pt).unwrap();
+        assert_eq!(
+            "48af93501fa62adbcd414cce6034d895dda1bf8f132f042098661572e7483094\
+             fd12e518ce062c98acee28d95df4416bed31a2f04476c18bb40c84a74b97dc5b\
+             16842d4fa186f56ab33256971fa110f4",
+            hex::encode(&sealed)
+        );
+        assert_eq!(pt, ccm.decrypt(&nonce, &aad, &sealed).unwrap());
+
+        let mut bad = sealed.clone();
+        bad[0] ^= 1;
+        assert!(matches!(
+            ccm.decrypt(&nonce, &aad, &bad),
+            Err(Sm4Error::InvalidTag)
+        ));
+    }
+
+    #[test]
+    fn test_ccm_lengths() {
+        // EVP SM4-CCM of OpenSSL 3.5
+        let key = hex::decode("404142434445464748494a4b4c4d4e4f").unwrap();
+        let cases = [
+            (7, 4, "0001020304050607", "20212223", "a9550cebba3bb146"),
+            (
+                13,
+                10,
+                "",
+                "202122232425262728292a2b2c2d2e2f3031",
+                "35fb45fb2d4d28530dba9e43a0b38f30486d1a8bb47f9a7f31e9a778",
+            ),
+        ];
+        for (nonce_len, tag_len, aad, pt, expected) in cases {
+            let nonce: Vec<u8> = (0x10..0x10 + nonce_len as u8).collect();
+            let ccm = Sm4Ccm::new(&key, nonce_len, tag_len).unwrap();
+            let aad = hex::decode(aad).unwrap();
+            let pt = hex::decode(pt).unwrap();
+            let sealed = ccm.encrypt(&nonce, &aad, &pt).unwrap();
+            assert_eq!(expected, hex::encode(&sealed));
+            assert_eq!(pt, ccm.decrypt(&nonce, &aad, &sealed).unwrap());
+        }
+
+        assert!(Sm4Ccm::new(&key, 6, 16).is_err());
+        assert!(Sm4Ccm::new(&key, 12, 5).is_err());
+        let ccm = Sm4Ccm::new(&key, 13, 16).unwrap();
+        assert!(ccm.encrypt(&[0u8; 12], b"", b"x").is_err());
+        assert!(ccm.encrypt(&[0u8; 13], b"", &vec![0u8; 0x10000]).is_err());
+    }
+
+    #[test]
+    fn test_aead_trait() {
+        use crate::gcm::Sm4Gcm;
+        use gm_core::Aead;
+
+        fn roundtrip<A: Aead>(a: &A, nonce: &[u8]) -> Vec<u8> {
+            let sealed = a.encrypt(nonce, b"aad", b"sensor reading").ok().unwrap();
+            a.decrypt(nonce, b"aad", &sealed).ok().unwrap()
+        }
+        let key = [0x24u8; 16];
+        let ccm = Sm4Ccm::new(&key, 12, 16).unwrap();
+        let gcm = Sm4Gcm::new(&key).unwrap();
+        assert_eq!(b"sensor reading".to_vec(), roundtrip(&ccm, &[1u8; 12]));
+        assert_eq!(b"sensor reading".to_vec(), roundtrip(&gcm, &[1u8; 12]));
+    }
+}
@@ -0,0 +1,135 @@
+//! The RustCrypto `cipher` traits for the SM4 block cipher (enable the
+//! `cipher` feature), so that the generic modes of the `cbc`, `ctr`, `cfb-mode`
+//! and similar crates run on [`Sm4Cipher`]. The `cipher` crate is re-exported
+//! as [`cipher`](::cipher).
+//!
+//! # Example
+//! ```rust
+//! use gm_sm4::cipher::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+//! use gm_sm4::Sm4Cipher;
+//!
+//! let key = hex::decode("0123456789abcdeffedcba9876543210").unwrap();
+//! let sm4 = <Sm4Cipher as KeyInit>::new_from_slice(&key).unwrap();
+//! let mut block = [0x11u8; 16].into();
+//! BlockEncrypt::encrypt_block(&sm4, &mut block);
+//! BlockDecrypt::decrypt_block(&sm4, &mut block);
+//! assert_eq!([0x11u8; 16], block.as_slice());
+//! ```
+pub use cipher;
+use cipher::consts::{U1, U16};
+use cipher::inout::InOut;
+use cipher::{
+    AlgorithmName, Block, BlockBackend, BlockCipher, BlockClosure, BlockDecrypt, BlockEncrypt, BlockSizeUser, Key,
+    KeyInit, KeySizeUser, ParBlocksSizeUser,
+};
+
+use crate::Sm4Cipher;
+
+impl KeySizeUser for Sm4Cipher {
+    type KeySize = U16;
+}
+
+impl KeyInit for Sm4Cipher {
+    fn new(key: &Key<Self>) -> Self {
+        Sm4Cipher::new(key).expect("an SM4 key is 16 bytes")
+    }
+}
+
+impl BlockSizeUser for Sm4Cipher {
+    type BlockSize = U16;
+}
+
+impl BlockCipher for Sm4Cipher {}
+
+impl AlgorithmName for Sm4Cipher {
+    fn write_alg_name(f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SM4")
+    }
+}
+
+/// One block at a time through [`Sm4Cipher::encrypt_block`] or
+/// [`Sm4Cipher::decrypt_block`]
+struct Backend<'a> {
+    cipher: &'a Sm4Cipher,
+    decrypt: bool,
+}
+
+impl BlockSizeUser for Backend<'_> {
+    type BlockSize = U16;
+}
+
+impl ParBlocksSizeUser for Backend<'_> {
+    type ParBlocksSize = U1;
+}
+
+impl BlockBackend for Backend<'_> {
+    fn proc_block(&mut self, mut block: InOut<'_, '_, Block<Self>>) {
+        let mut b: [u8; 16] = (*block.get_in()).into();
+        if self.decrypt {
+            self.cipher.decrypt_block(&mut b);
+        } else {
+            self.cipher.encrypt_block(&mut b);
+        }
+        *block.get_out() = b.into();
+    }
+}
+
+impl BlockEncrypt for Sm4Cipher {
+    fn encrypt_with_backend(&self, f: impl BlockClosure<BlockSize = U16>) {
+        f.call(&mut Backend {
+            cipher: self,
+            decrypt: false,
+        });
+    }
+}
+
+impl BlockDecrypt for Sm4Cipher {
+    fn decrypt_with_backend(&self, f: impl BlockClosure<BlockSize = U16>) {
+        f.call(&mut Backend {
+            cipher: self,
+            decrypt: true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test_cipher {
+    use super::*;
+    use crate::{CipherMode, Sm4CipherMode};
+    use cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, StreamCipher};
+
+    const KEY: &str = "0123456789abcdeffedcba9876543210";
+
+    #[test]
+    fn test_block() {
+        // GB/T 32907-2016 appendix A
+        let key = hex::decode(KEY).unwrap();
+        let sm4 = <Sm4Cipher as KeyInit>::new_from_slice(&key).unwrap();
+        let mut block = Block::<Sm4Cipher>::clone_from_slice(&key);
+        BlockEncrypt::encrypt_block(&sm4, &mut block);
+        assert_eq!("681edf34d206965e86b3e94f536e4246", hex::encode(block));
+        BlockDecrypt::decrypt_block(&sm4, &mut block);
+        assert_eq!(key, block.as_slice());
+        assert!(<Sm4Cipher as KeyInit>::new_from_slice(&key[1..]).is_err());
+    }
+
+    /// The `cbc` and `ctr` crates give the bytes of the native modes
+    #[test]
+    fn test_modes() {
+        let key = hex::decode(KEY).unwrap();
+        let iv = [0x24u8; 16];
+        let data: Vec<u8> = (0..64).collect();
+
+        let native = Sm4CipherMode::new(&key, CipherMode::Cbc).unwrap().encrypt(&data, &iv).unwrap();
+        let enc = cbc::Encryptor::<Sm4Cipher>::new_from_slices(&key, &iv).unwrap();
+        let c = enc.encrypt_padded_vec_mut::<cipher::block_padding::Pkcs7>(&data);
+        assert_eq!(native, c);
+        let dec = cbc::Decryptor::<Sm4Cipher>::new_from_slices(&key, &iv).unwrap();
+        assert_eq!(data, dec.decrypt_padded_vec_mut::<cipher::block_padding::Pkcs7>(&c).unwrap());
+
+        let native = Sm4CipherMode::new(&key, CipherMode::Ctr).unwrap().encrypt(&data[..50], &iv).unwrap();
+        let mut buf = data[..50].to_vec();
+        ctr::Ctr128BE::<Sm4Cipher>::new_from_slices(&key, &iv).unwrap().apply_keystream(&mut buf);
+        assert_eq!(native, buf);
+    }
+}
@@ -0,0 +1,91 @@
+//! Known-broken inputs for the public decoders.
+//!
+//! Every valid ciphertext is cut at each length, extended, bit-flipped and
+//! replaced by garbage of the same and of other lengths. The authenticated
+//! modes and the key wraps reject them all, the unauthenticated modes must
+//! not panic on any of them. The runner is [`gm_core::corpus`].
+use gm_core::corpus::{accepts, resized, Corpus};
+
+use crate::ccm::Sm4Ccm;
+use crate::gcm::Sm4Gcm;
+use crate::kw::Sm4Kw;
+use crate::padding::Padding;
+use crate::xts::{Sm4Xts, XtsStandard};
+use crate::{CipherMode, Sm4Cipher, Sm4CipherMode};
+
+const KEY: [u8; 16] = [0x5a; 16];
+
+const CORPUS: Corpus = Corpus::new(&[1, 15, 16, 17, 24, 32, 4096]);
+
+#[test]
+fn corpus_block_cipher() {
+    let lengths: Vec<Vec<u8>> = (0..=40).filter(|&n| n != 16).map(|n| vec![0x11; n]).collect();
+    for b in &lengths {
+        assert!(!accepts("Sm4Cipher::new", b, &Sm4Cipher::new));
+        assert!(!accepts("Sm4CipherMode::new", b, &|k: &[u8]| Sm4CipherMode::new(k, CipherMode::Cbc)));
+    }
+    let cipher = Sm4Cipher::new(&KEY).unwrap();
+    for b in &lengths {
+        assert!(!accepts("Sm4Cipher::encrypt", b, &|b: &[u8]| cipher.encrypt(b)));
+        assert!(!accepts("Sm4Cipher::decrypt", b, &|b: &[u8]| cipher.decrypt(b)));
+    }
+}
+
+#[test]
+fn corpus_modes() {
+    let iv = [0x22u8; 16];
+    let paddings = [
+        Padding::NoPadding,
+        Padding::Pkcs7,
+        Padding::Iso9797M1,
+        Padding::Iso9797M2,
+        Padding::Iso9797M3,
+    ];
+    for padding in paddings {
+        for mode in [CipherMode::Cbc, CipherMode::Cfb, CipherMode::Ofb, CipherMode::Ctr] {
+            let cipher = Sm4CipherMode::with_padding(&KEY, mode, padding).unwrap();
+            let c = cipher.encrypt(&[0x33; 32], &iv).unwrap();
+            CORPUS.check_unauthenticated("Sm4CipherMode::decrypt", &c, |b| cipher.decrypt(b, &iv));
+            for b in resized(&iv) {
+                assert!(!accepts("Sm4CipherMode::decrypt", &b, &|iv: &[u8]| cipher.decrypt(&c, iv)));
+            }
+        }
+        CORPUS.check_unauthenticated("Padding::unpad", &padding.pad(&[0x33; 20]), |b| padding.unpad(b));
+    }
+
+    let mut key = [0u8; 32];
+    key[16..].fill(1);
+    for standard in [XtsStandard::Gb, XtsStandard::Ieee] {
+        let xts = Sm4Xts::with_standard(&key, standard).unwrap();
+        let c = xts.encrypt(&iv, &[0x33; 48]).unwrap();
+        CORPUS.check_unauthenticated("Sm4Xts::decrypt", &c, |b| xts.decrypt(&iv, b));
+        CORPUS.check_unauthenticated("Sm4Xts::decrypt_sectors", &c, |b| xts.decrypt_sectors(b, 32, 0));
+    }
+}
+
+#[test]
+fn corpus_aead() {
+    let gcm = Sm4Gcm::new(&KEY).unwrap();
+    let c = gcm.encrypt(&[0x44; 12], b"aad", b"corpus").unwrap();
+    CORPUS.check_authenticated("Sm4Gcm::decrypt", &c, |b| gcm.decrypt(&[0x44; 12], b"aad", b));
+    for b in resized(&[0x44; 12]) {
+        assert!(!accepts("Sm4Gcm::decrypt", &b, &|nonce: &[u8]| gcm.decrypt(nonce, b"aad", &c)));
+    }
+
+    let ccm = Sm4Ccm::new(&KEY, 12, 16).unwrap();
+    let c = ccm.encrypt(&[0x44; 12], b"aad", b"corpus").unwrap();
+    CORPUS.check_authenticated("Sm4Ccm::decrypt", &c, |b| ccm.decrypt(&[0x44; 12], b"aad", b));
+    for b in resized(&[0x44; 12]) {
+        assert!(!accepts("Sm4Ccm::decrypt", &b, &|nonce: &[u8]| ccm.decrypt(nonce, b"aad", &c)));
+    }
+}
+
+#[test]
+fn corpus_key_wrap() {
+    let kw = Sm4Kw::new(&KEY).unwrap();
+    CORPUS.check_authenticated("Sm4Kw::unwrap_key", &kw.wrap_key(&[0x66; 16]).unwrap(), |b| kw.unwrap_key(b));
+    for len in [1, 8, 20] {
+        let wrapped = kw.wrap_key_padded(&vec![0x66; len]).unwrap();
+        CORPUS.check_authenticated("Sm4Kw::unwrap_key_padded", &wrapped, |b| kw.unwrap_key_padded(b));
+    }
+}
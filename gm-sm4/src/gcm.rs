@@ -0,0 +1,168 @@
+use gm_core::ghash::GHash;
+use gm_core::{ct_eq, Aead};
+
+use crate::{to_block, Sm4Cipher, Sm4Error, Sm4Result};
+
+pub const GCM_TAG_LEN: usize = 16;
+
+/// SM4-GCM (NIST SP 800-38D with SM4 as the block cipher, RFC 8998)
+///
+/// The output of `encrypt` is `ciphertext || tag`, with a 16-byte tag.
+///
+/// # Example
+/// ```rust
+/// use gm_sm4::gcm::Sm4Gcm;
+///
+/// let gcm = Sm4Gcm::new(&[0x11u8; 16]).unwrap();
+/// let nonce = [0u8; 12];
+/// let sealed = gcm.encrypt(&nonce, b"header", b"hello").unwrap();
+/// let opened = gcm.decrypt(&nonce, b"header", &sealed).unwrap();
+/// assert_eq!(b"hello".to_vec(), opened);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sm4Gcm {
+    cipher: Sm4Cipher,
+    h: u128,
+}
+
+impl Sm4Gcm {
+    pub fn new(key: &[u8]) -> Sm4Result<Sm4Gcm> {
+        if key.len() != 16 {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let cipher = Sm4Cipher::new(key)?;
+        let h = u128::from_be_bytes(to_block(&cipher.encrypt(&[0u8; 16])?));
+        Ok(Sm4Gcm { cipher, h })
+    }
+
+    /// The plaintext followed by the tag
+    pub const fn ciphertext_len(plain_len: usize) -> usize {
+        plain_len + GCM_TAG_LEN
+    }
+
+    pub fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Sm4Result<Vec<u8>> {
+        let j0 = self.pre_counter(nonce)?;
+        let mut out = self.ctr(j0, plaintext)?;
+        let tag = self.tag(j0, aad, &out)?;
+        out.extend_from_slice(&tag);
+        Ok(out)
+    }
+
+    pub fn decrypt(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Sm4Result<Vec<u8>> {
+        if ciphertext.len() < GCM_TAG_LEN {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let (ct, tag) = ciphertext.split_at(ciphertext.len() - GCM_TAG_LEN);
+        let j0 = self.pre_counter(nonce)?;
+        let expected = self.tag(j0, aad, ct)?;
+        if !ct_eq(&expected, tag) {
+            return Err(Sm4Error::InvalidTag);
+        }
+        self.ctr(j0, ct)
+    }
+
+    fn pre_counter(&self, nonce: &[u8]) -> Sm4Result<u128> {
+        if nonce.is_empty() {
+            return Err(Sm4Error::ErrorBlockSize);
+        }
+        if nonce.len() == 12 {
+            let mut j0 = [0u8; 16];
+            j0[..12].copy_from_slice(nonce);
+            j0[15] = 1;
+            return Ok(u128::from_be_bytes(j0));
+        }
+        Ok(self.ghash(&[], nonce))
+    }
+
+    fn ctr(&self, j0: u128, data: &[u8]) -> Sm4Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len());
+        let mut counter = j0;
+        for chunk in data.chunks(16) {
+            counter = inc32(counter);
+            let ks = self.cipher.encrypt(&counter.to_be_bytes())?;
+            out.extend(chunk.iter().zip(ks.iter()).map(|(a, b)| a ^ b));
+        }
+        Ok(out)
+    }
+
+    fn tag(&self, j0: u128, aad: &[u8], ct: &[u8]) -> Sm4Result<[u8; 16]> {
+        let s = self.ghash(aad, ct);
+        let ek = u128::from_be_bytes(to_block(&self.cipher.encrypt(&j0.to_be_bytes())?));
+        Ok((s ^ ek).to_be_bytes())
+    }
+
+    fn ghash(&self, aad: &[u8], ct: &[u8]) -> u128 {
+        let mut ghash = GHash::new(&self.h.to_be_bytes());
+        ghash.update_padded(aad);
+        ghash.update_padded(ct);
+        ghash.update_lengths(aad.len(), ct.len());
+        u128::from_be_bytes(ghash.finalize())
+    }
+}
+
+impl Aead for Sm4Gcm {
+    type Error = Sm4Error;
+
+    fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Sm4Result<Vec<u8>> {
+        Sm4Gcm::encrypt(self, nonce, aad, plaintext)
+    }
+
+    fn decrypt(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Sm4Result<Vec<u8>> {
+        Sm4Gcm::decrypt(self, nonce, aad, ciphertext)
+    }
+}
+
+fn inc32(x: u128) -> u128 {
+    let hi = x & !0xffff_ffffu128;
+    let lo = (x as u32).wrapping_add(1);
+    hi | lo as u128
+}
+
+#[cfg(test)]
+mod test {
+    use crate::gcm::Sm4Gcm;
+    use crate::Sm4Error;
+
+    #[test]
+    fn test_gcm_rfc8998() {
+        let key = hex::decode("0123456789abcdeffedcba9876543210").unwrap();
+        let nonce = hex::decode("00001234567800000000abcd").unwrap();
+        let aad = hex::decode("feedfacedeadbeeffeedfacedeadbeefabaddad2").unwrap();
+        let pt = hex::decode(
+            "aaaaaaaaaaaaaaaabbbbbbbbbbbbbbbbccccccccccccccccdddddddddddddddd\
+             eeeeeeeeeeeeeeeeffffffffffffffffeeeeeeeeeeeeeeeeaaaaaaaaaaaaaaaa",
+        )
+        .unwrap();
+        let gcm = Sm4Gcm::new(&key).unwrap();
+        let sealed = gcm.encrypt(&nonce, &aad, &pt).unwrap();
+        assert_eq!(
+            "17f399f08c67d5ee19d0dc9969c4bb7d5fd46fd3756489069157b282bb200735\
+             d82710ca5c22f0ccfa7cbf93d496ac15a56834cbcf98c397b4024a2691233b8d\
+             83de3541e4c2b58177e065a9bf7b62ec",
+            hex::encode(&sealed)
+        );
+        assert_eq!(pt, gcm.decrypt(&nonce, &aad, &sealed).unwrap());
+
+        let mut bad = sealed.clone();
+        bad[0] ^= 1;
+        assert!(matches!(
+            gcm.decrypt(&nonce, &aad, &bad),
+            Err(Sm4Error::InvalidTag)
+        ));
+    }
+
+    #[test]
+    fn test_gcm_long_nonce() {
+        let key = hex::decode("0123456789abcdeffedcba9876543210").unwrap();
+        let nonce: Vec<u8> = (0..16).collect();
+        let gcm = Sm4Gcm::new(&key).unwrap();
+        let sealed = gcm.encrypt(&nonce, b"", b"hello gcm, 25 bytes long!").unwrap();
+        assert_eq!(
+            "c261a497384b44b56d1a8b5b5e9b724b5d604b575821ff178a0e748cb2ab3ebc21b2cc83d14d0e7026",
+            hex::encode(&sealed)
+        );
+
+        let sealed = gcm.encrypt(&[0u8; 12], b"", b"").unwrap();
+        assert_eq!("4e595bf03f23bd10329baf5698e898ec", hex::encode(&sealed));
+    }
+}
@@ -0,0 +1,198 @@
+use gm_core::ct_eq;
+
+use crate::{Sm4Cipher, Sm4Error, Sm4Result};
+
+/// Default IV of RFC 3394
+const KW_IV: [u8; 8] = [0xa6; 8];
+/// Alternative IV prefix of RFC 5649, followed by the 32-bit key length
+const KWP_IV: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+/// SM4 key wrap (RFC 3394 / NIST SP 800-38F KW) and key wrap with padding
+/// (RFC 5649 / KWP) under a 16-byte key-encryption key.
+///
+/// # Example
+/// ```rust
+/// use gm_sm4::kw::Sm4Kw;
+///
+/// let kw = Sm4Kw::new(&[0x5au8; 16]).unwrap();
+/// let session_key = [0x11u8; 16];
+/// let wrapped = kw.wrap_key(&session_key).unwrap();
+/// assert_eq!(24, wrapped.len());
+/// assert_eq!(session_key.to_vec(), kw.unwrap_key(&wrapped).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sm4Kw {
+    cipher: Sm4Cipher,
+}
+
+impl Sm4Kw {
+    pub fn new(kek: &[u8]) -> Sm4Result<Sm4Kw> {
+        if kek.len() != 16 {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        Ok(Sm4Kw {
+            cipher: Sm4Cipher::new(kek)?,
+        })
+    }
+
+    /// KW, the key is a multiple of 8 bytes and at least 16.
+    pub fn wrap_key(&self, key: &[u8]) -> Sm4Result<Vec<u8>> {
+        if key.len() < 16 || !key.len().is_multiple_of(8) {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        self.wrap(KW_IV, key)
+    }
+
+    pub fn unwrap_key(&self, wrapped: &[u8]) -> Sm4Result<Vec<u8>> {
+        if wrapped.len() < 24 || !wrapped.len().is_multiple_of(8) {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let (a, key) = self.unwrap(wrapped)?;
+        if !ct_eq(&a, &KW_IV) {
+            return Err(Sm4Error::InvalidTag);
+        }
+        Ok(key)
+    }
+
+    /// KWP, any key length from 1 byte on.
+    pub fn wrap_key_padded(&self, key: &[u8]) -> Sm4Result<Vec<u8>> {
+        if key.is_empty() || key.len() > u32::MAX as usize {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let mut iv = [0u8; 8];
+        iv[..4].copy_from_slice(&KWP_IV);
+        iv[4..].copy_from_slice(&(key.len() as u32).to_be_bytes());
+        let mut padded = key.to_vec();
+        padded.resize(key.len().div_ceil(8) * 8, 0);
+        if padded.len() == 8 {
+            // a single semiblock is encrypted as one block together with the IV
+            let mut block = [0u8; 16];
+            block[..8].copy_from_slice(&iv);
+            block[8..].copy_from_slice(&padded);
+            return self.cipher.encrypt(&block);
+        }
+        self.wrap(iv, &padded)
+    }
+
+    pub fn unwrap_key_padded(&self, wrapped: &[u8]) -> Sm4Result<Vec<u8>> {
+        if wrapped.len() < 16 || !wrapped.len().is_multiple_of(8) {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let (a, padded) = if wrapped.len() == 16 {
+            let block = self.cipher.decrypt(wrapped)?;
+            let mut a = [0u8; 8];
+            a.copy_from_slice(&block[..8]);
+            (a, block[8..].to_vec())
+        } else {
+            self.unwrap(wrapped)?
+        };
+        let len = u32::from_be_bytes(a[4..].try_into().unwrap()) as usize;
+        let valid = ct_eq(&a[..4], &KWP_IV)
+            && len <= padded.len()
+            && len + 8 > padded.len()
+            && padded[len..].iter().all(|&b| b == 0);
+        if !valid {
+            return Err(Sm4Error::InvalidTag);
+        }
+        Ok(padded[..len].to_vec())
+    }
+
+    /// W(S) of SP 800-38F, 6n rounds over the n semiblocks of `key`
+    fn wrap(&self, iv: [u8; 8], key: &[u8]) -> Sm4Result<Vec<u8>> {
+        let n = key.len() / 8;
+        let mut a = iv;
+        let mut r: Vec<[u8; 8]> = key.chunks(8).map(|c| c.try_into().unwrap()).collect();
+        let mut block = [0u8; 16];
+        for j in 0..6 {
+            for (i, ri) in r.iter_mut().enumerate() {
+                block[..8].copy_from_slice(&a);
+                block[8..].copy_from_slice(ri);
+                let b = self.cipher.encrypt(&block)?;
+                let t = (n * j + i + 1) as u64;
+                a.copy_from_slice(&b[..8]);
+                xor_counter(&mut a, t);
+                ri.copy_from_slice(&b[8..]);
+            }
+        }
+        let mut out = a.to_vec();
+        for ri in r {
+            out.extend_from_slice(&ri);
+        }
+        Ok(out)
+    }
+
+    /// W^-1(C), returns the recovered IV for the caller to check
+    fn unwrap(&self, wrapped: &[u8]) -> Sm4Result<([u8; 8], Vec<u8>)> {
+        let n = wrapped.len() / 8 - 1;
+        let mut a: [u8; 8] = wrapped[..8].try_into().unwrap();
+        let mut r: Vec<[u8; 8]> = wrapped[8..].chunks(8).map(|c| c.try_into().unwrap()).collect();
+        let mut block = [0u8; 16];
+        for j in (0..6).rev() {
+            for (i, ri) in r.iter_mut().enumerate().rev() {
+                let t = (n * j + i + 1) as u64;
+                xor_counter(&mut a, t);
+                block[..8].copy_from_slice(&a);
+                block[8..].copy_from_slice(ri);
+                let b = self.cipher.decrypt(&block)?;
+                a.copy_from_slice(&b[..8]);
+                ri.copy_from_slice(&b[8..]);
+            }
+        }
+        Ok((a, r.concat()))
+    }
+}
+
+fn xor_counter(a: &mut [u8; 8], t: u64) {
+    for (a, t) in a.iter_mut().zip(t.to_be_bytes()) {
+        *a ^= t;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::kw::Sm4Kw;
+    use crate::Sm4Error;
+
+    #[test]
+    fn test_kw() {
+        // RFC 3394 computed with SM4 in place of AES (python cryptography, SM4-ECB)
+        let kw = Sm4Kw::new(&hex::decode("000102030405060708090a0b0c0d0e0f").unwrap()).unwrap();
+        let key = hex::decode("00112233445566778899aabbccddeeff0001020304050607").unwrap();
+        for (len, expected) in [(16, KW_16), (24, KW_24)] {
+            let wrapped = kw.wrap_key(&key[..len]).unwrap();
+            println!("{}: {}", len, hex::encode(&wrapped));
+            assert_eq!(expected, hex::encode(&wrapped));
+            assert_eq!(&key[..len], &kw.unwrap_key(&wrapped).unwrap()[..]);
+        }
+
+        let mut bad = kw.wrap_key(&key[..16]).unwrap();
+        bad[10] ^= 1;
+        assert!(matches!(kw.unwrap_key(&bad), Err(Sm4Error::InvalidTag)));
+        assert!(kw.wrap_key(&key[..12]).is_err());
+    }
+
+    #[test]
+    fn test_kwp() {
+        let kw = Sm4Kw::new(&hex::decode("5840df6e29b02af1ab493b705bf16ea1").unwrap()).unwrap();
+        let key = hex::decode("c37b7e6492584340bed12207808941155068f738").unwrap();
+        for (len, expected) in [(20, KWP_20), (7, KWP_7)] {
+            let wrapped = kw.wrap_key_padded(&key[..len]).unwrap();
+            println!("{}: {}", len, hex::encode(&wrapped));
+            assert_eq!(expected, hex::encode(&wrapped));
+            assert_eq!(&key[..len], &kw.unwrap_key_padded(&wrapped).unwrap()[..]);
+        }
+        for len in 1..=20 {
+            let wrapped = kw.wrap_key_padded(&key[..len]).unwrap();
+            assert_eq!(&key[..len], &kw.unwrap_key_padded(&wrapped).unwrap()[..]);
+        }
+
+        // a KW blob is not a valid KWP blob
+        let wrapped = kw.wrap_key(&key[..16]).unwrap();
+        assert!(kw.unwrap_key_padded(&wrapped).is_err());
+    }
+
+    const KW_16: &str = "c72e8dbfefe856259fff77de2023b380a9e2d0b8acb9b6f6";
+    const KW_24: &str = "a874c3d64c7a639b7e8c97243550f528090df4cdcfb2cb81d403899fced7b88a";
+    const KWP_20: &str = "c47d86253815615060826963826843eb1be39ecd9a032d04b22a710eb93d63e0";
+    const KWP_7: &str = "4ae1a7d3e07fdff571cd9842c24eb999";
+}
@@ -5,6 +5,21 @@ use std::error;
 use std::fmt::{Display, Formatter};
 use const_oid::ObjectIdentifier;
 
+use crate::padding::Padding;
+
+#[cfg(feature = "aead")]
+pub mod aead;
+pub mod ccm;
+#[cfg(feature = "cipher")]
+pub mod cipher;
+#[cfg(test)]
+mod corpus;
+pub mod file;
+pub mod gcm;
+pub mod kw;
+pub mod padding;
+pub mod xts;
+
 
 pub const OID_SM4: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.156.10197.1.104");
 
@@ -42,6 +57,9 @@ pub enum Sm4Error {
     ErrorBlockSize,
     ErrorDataLen,
     InvalidLastU8,
+    InvalidTag,
+    InvalidKey,
+    InvalidPadding,
 }
 
 impl ::std::fmt::Debug for Sm4Error {
@@ -58,6 +76,9 @@ impl From<Sm4Error> for &str {
             Sm4Error::InvalidLastU8 => {
                 "the last u8 of cbc_decrypt out in SM4 must be positive which isn't greater than 16"
             }
+            Sm4Error::InvalidTag => "authentication tag mismatch",
+            Sm4Error::InvalidKey => "invalid SM4 key",
+            Sm4Error::InvalidPadding => "invalid padding",
         }
     }
 }
@@ -75,6 +96,9 @@ impl Display for Sm4Error {
                 Sm4Error::InvalidLastU8 => {
                     "the last u8 of cbc_decrypt out in SM4 must be positive which isn't greater than 16"
                 }
+                Sm4Error::InvalidTag => "authentication tag mismatch",
+                Sm4Error::InvalidKey => "invalid SM4 key",
+                Sm4Error::InvalidPadding => "invalid padding",
             }
         )
     }
@@ -135,6 +159,9 @@ pub struct Sm4Cipher {
 
 impl Sm4Cipher {
     pub fn new(k: &[u8]) -> Sm4Result<Sm4Cipher> {
+        if k.len() != 16 {
+            return Err(Sm4Error::InvalidKey);
+        }
         let mut rk = [0u32; 32];
         let mk = [
             u32::from_be_bytes(k[0..4].try_into().unwrap()),
@@ -159,53 +186,108 @@ impl Sm4Cipher {
     }
 
     pub fn encrypt(&self, block: &[u8]) -> Sm4Result<Vec<u8>> {
-        let mut x = [
-            u32::from_be_bytes(block[0..4].try_into().unwrap()),
-            u32::from_be_bytes(block[4..8].try_into().unwrap()),
-            u32::from_be_bytes(block[8..12].try_into().unwrap()),
-            u32::from_be_bytes(block[12..16].try_into().unwrap()),
-        ];
+        let mut b: [u8; 16] = block.try_into().map_err(|_| Sm4Error::ErrorBlockSize)?;
+        self.encrypt_block(&mut b);
+        Ok(b.to_vec())
+    }
 
-        let rk = &self.rk;
-        for i in 0..8 {
-            x[0] ^= t(x[1] ^ x[2] ^ x[3] ^ rk[i * 4]);
-            x[1] ^= t(x[2] ^ x[3] ^ x[0] ^ rk[i * 4 + 1]);
-            x[2] ^= t(x[3] ^ x[0] ^ x[1] ^ rk[i * 4 + 2]);
-            x[3] ^= t(x[0] ^ x[1] ^ x[2] ^ rk[i * 4 + 3]);
-        }
+    pub fn decrypt(&self, block: &[u8]) -> Sm4Result<Vec<u8>> {
+        let mut b: [u8; 16] = block.try_into().map_err(|_| Sm4Error::ErrorBlockSize)?;
+        self.decrypt_block(&mut b);
+        Ok(b.to_vec())
+    }
 
-        let mut out: [u8; 16] = [0; 16];
-        out[0..4].copy_from_slice(&x[3].to_be_bytes());
-        out[4..8].copy_from_slice(&x[2].to_be_bytes());
-        out[8..12].copy_from_slice(&x[1].to_be_bytes());
-        out[12..16].copy_from_slice(&x[0].to_be_bytes());
+    /// Encrypt one block in place, without allocating
+    pub fn encrypt_block(&self, block: &mut [u8; 16]) {
+        self.crypt_block(block, |i| self.rk[i]);
+    }
 
-        Ok(out.to_vec())
+    pub fn decrypt_block(&self, block: &mut [u8; 16]) {
+        self.crypt_block(block, |i| self.rk[31 - i]);
     }
 
-    pub fn decrypt(&self, block: &[u8]) -> Sm4Result<Vec<u8>> {
+    fn crypt_block(&self, block: &mut [u8; 16], rk: impl Fn(usize) -> u32) {
         let mut x = [
             u32::from_be_bytes(block[0..4].try_into().unwrap()),
             u32::from_be_bytes(block[4..8].try_into().unwrap()),
             u32::from_be_bytes(block[8..12].try_into().unwrap()),
             u32::from_be_bytes(block[12..16].try_into().unwrap()),
         ];
-        let rk = &self.rk;
         for i in 0..8 {
-            x[0] ^= t(x[1] ^ x[2] ^ x[3] ^ rk[31 - i * 4]);
-            x[1] ^= t(x[2] ^ x[3] ^ x[0] ^ rk[31 - (i * 4 + 1)]);
-            x[2] ^= t(x[3] ^ x[0] ^ x[1] ^ rk[31 - (i * 4 + 2)]);
-            x[3] ^= t(x[0] ^ x[1] ^ x[2] ^ rk[31 - (i * 4 + 3)]);
+            x[0] ^= t(x[1] ^ x[2] ^ x[3] ^ rk(i * 4));
+            x[1] ^= t(x[2] ^ x[3] ^ x[0] ^ rk(i * 4 + 1));
+            x[2] ^= t(x[3] ^ x[0] ^ x[1] ^ rk(i * 4 + 2));
+            x[3] ^= t(x[0] ^ x[1] ^ x[2] ^ rk(i * 4 + 3));
         }
-        let mut out: [u8; 16] = [0; 16];
-        out[0..4].copy_from_slice(&x[3].to_be_bytes());
-        out[4..8].copy_from_slice(&x[2].to_be_bytes());
-        out[8..12].copy_from_slice(&x[1].to_be_bytes());
-        out[12..16].copy_from_slice(&x[0].to_be_bytes());
-        Ok(out.to_vec())
+        block[0..4].copy_from_slice(&x[3].to_be_bytes());
+        block[4..8].copy_from_slice(&x[2].to_be_bytes());
+        block[8..12].copy_from_slice(&x[1].to_be_bytes());
+        block[12..16].copy_from_slice(&x[0].to_be_bytes());
     }
 }
 
+/// A 128-bit SM4 key, Debug does not print it
+#[derive(Clone, PartialEq, Eq)]
+pub struct Sm4Key(pub [u8; 16]);
+
+impl std::fmt::Debug for Sm4Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sm4Key(..)")
+    }
+}
+
+impl From<[u8; 16]> for Sm4Key {
+    fn from(b: [u8; 16]) -> Self {
+        Sm4Key(b)
+    }
+}
+
+impl TryFrom<&[u8]> for Sm4Key {
+    type Error = Sm4Error;
+
+    fn try_from(b: &[u8]) -> Sm4Result<Self> {
+        b.try_into().map(Sm4Key).map_err(|_| Sm4Error::InvalidKey)
+    }
+}
+
+impl AsRef<[u8]> for Sm4Key {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// The 16-byte IV, or initial counter block, of [`Sm4CipherMode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Iv(pub [u8; 16]);
+
+impl From<[u8; 16]> for Iv {
+    fn from(b: [u8; 16]) -> Self {
+        Iv(b)
+    }
+}
+
+impl TryFrom<&[u8]> for Iv {
+    type Error = Sm4Error;
+
+    fn try_from(b: &[u8]) -> Sm4Result<Self> {
+        b.try_into().map(Iv).map_err(|_| Sm4Error::ErrorBlockSize)
+    }
+}
+
+impl AsRef<[u8]> for Iv {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Sm4Cipher {
+    /// [`Sm4Cipher::new`] for a key whose length is already checked
+    pub fn with_key(key: &Sm4Key) -> Sm4Cipher {
+        Sm4Cipher::new(&key.0).expect("an Sm4Key is 16 bytes")
+    }
+}
+
+/// `Cfb` is CFB128, the full-block feedback mode of GB/T 17964.
 pub enum CipherMode {
     Cfb,
     Ofb,
@@ -216,16 +298,22 @@ pub enum CipherMode {
 pub struct Sm4CipherMode {
     cipher: Sm4Cipher,
     mode: CipherMode,
+    padding: Padding,
 }
 
-fn block_xor(a: &[u8], b: &[u8]) -> [u8; 16] {
-    let mut out: [u8; 16] = [0; 16];
-    for i in 0..16 {
-        out[i] = a[i] ^ b[i];
-    }
+/// The first 16 bytes of `b`
+pub(crate) fn to_block(b: &[u8]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    out.copy_from_slice(&b[..16]);
     out
 }
 
+fn xor_in(block: &mut [u8], ks: &[u8; 16]) {
+    for (b, k) in block.iter_mut().zip(ks) {
+        *b ^= k;
+    }
+}
+
 fn block_add_one(a: &mut [u8]) {
     let mut carry = 1;
     for i in 0..16 {
@@ -239,213 +327,201 @@ fn block_add_one(a: &mut [u8]) {
 }
 
 impl Sm4CipherMode {
+    /// CBC uses PKCS#7 padding, the stream modes are not padded.
     pub fn new(key: &[u8], mode: CipherMode) -> Sm4Result<Sm4CipherMode> {
-        let cipher = Sm4Cipher::new(key)?;
-        Ok(Sm4CipherMode { cipher, mode })
+        let padding = match mode {
+            CipherMode::Cbc => Padding::Pkcs7,
+            _ => Padding::NoPadding,
+        };
+        Sm4CipherMode::with_padding(key, mode, padding)
     }
 
-    pub fn encrypt(&self, data: &[u8], iv: &[u8]) -> Sm4Result<Vec<u8>> {
-        if iv.len() != 16 {
-            return Err(Sm4Error::ErrorBlockSize);
-        }
-        match self.mode {
-            CipherMode::Cfb => self.cfb_encrypt(data, iv),
-            CipherMode::Ofb => self.ofb_encrypt(data, iv),
-            CipherMode::Ctr => self.ctr_encrypt(data, iv),
-            CipherMode::Cbc => self.cbc_encrypt(data, iv),
-        }
+    /// Any mode with an explicit padding, e.g. CBC with ISO/IEC 9797-1
+    /// method 2 or CFB padded to whole blocks as some middleware does.
+    pub fn with_padding(key: &[u8], mode: CipherMode, padding: Padding) -> Sm4Result<Sm4CipherMode> {
+        let cipher = Sm4Cipher::new(key)?;
+        Ok(Sm4CipherMode {
+            cipher,
+            mode,
+            padding,
+        })
     }
 
-    pub fn decrypt(&self, data: &[u8], iv: &[u8]) -> Sm4Result<Vec<u8>> {
-        if iv.len() != 16 {
-            return Err(Sm4Error::ErrorBlockSize);
-        }
-        match self.mode {
-            CipherMode::Cfb => self.cfb_decrypt(data, iv),
-            CipherMode::Ofb => self.ofb_encrypt(data, iv),
-            CipherMode::Ctr => self.ctr_encrypt(data, iv),
-            CipherMode::Cbc => self.cbc_decrypt(data, iv),
-        }
+    /// The output length of `encrypt` for `plain_len` bytes, set by the padding
+    pub fn ciphertext_len(&self, plain_len: usize) -> usize {
+        self.padding.padded_len(plain_len)
     }
 
-    fn cfb_encrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, Sm4Error> {
-        let block_num = data.len() / 16;
-        let tail_len = data.len() - block_num * 16;
+    /// [`Sm4CipherMode::new`] for a key whose length is already checked
+    pub fn with_key(key: &Sm4Key, mode: CipherMode) -> Sm4CipherMode {
+        Sm4CipherMode::new(&key.0, mode).expect("an Sm4Key is 16 bytes")
+    }
 
-        let mut out: Vec<u8> = Vec::new();
-        let mut vec_buf: Vec<u8> = vec![0; 16];
-        vec_buf.clone_from_slice(iv);
+    /// [`Sm4CipherMode::encrypt`] with a typed IV, which cannot be swapped
+    /// with the data
+    pub fn encrypt_with_iv(&self, data: &[u8], iv: &Iv) -> Sm4Result<Vec<u8>> {
+        self.encrypt(data, &iv.0)
+    }
 
-        // Normal
-        for i in 0..block_num {
-            let enc = self.cipher.encrypt(&vec_buf[..])?;
-            let ct = block_xor(&enc, &data[i * 16..i * 16 + 16]);
-            for i in ct.iter() {
-                out.push(*i);
-            }
-            vec_buf.clone_from_slice(&ct);
-        }
+    pub fn decrypt_with_iv(&self, data: &[u8], iv: &Iv) -> Sm4Result<Vec<u8>> {
+        self.decrypt(data, &iv.0)
+    }
 
-        // Last block
-        let enc = self.cipher.encrypt(&vec_buf[..])?;
-        for i in 0..tail_len {
-            let b = data[block_num * 16 + i] ^ enc[i];
-            out.push(b);
-        }
+    pub fn encrypt(&self, data: &[u8], iv: &[u8]) -> Sm4Result<Vec<u8>> {
+        let mut out = vec![0; self.ciphertext_len(data.len())];
+        self.encrypt_into(data, iv, &mut out)?;
         Ok(out)
     }
 
-    fn cfb_decrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, Sm4Error> {
-        let block_num = data.len() / 16;
-        let tail_len = data.len() - block_num * 16;
-
-        let mut out: Vec<u8> = Vec::new();
-        let mut vec_buf: Vec<u8> = vec![0; 16];
-        vec_buf.clone_from_slice(iv);
-
-        // Normal
-        for i in 0..block_num {
-            let enc = self.cipher.encrypt(&vec_buf[..])?;
-            let ct = &data[i * 16..i * 16 + 16];
-            let pt = block_xor(&enc, ct);
-            for i in pt.iter() {
-                out.push(*i);
-            }
-            vec_buf.clone_from_slice(ct);
-        }
-
-        // Last block
-        let enc = self.cipher.encrypt(&vec_buf[..])?;
-        for i in 0..tail_len {
-            let b = data[block_num * 16 + i] ^ enc[i];
-            out.push(b);
-        }
+    pub fn decrypt(&self, data: &[u8], iv: &[u8]) -> Sm4Result<Vec<u8>> {
+        let mut out = vec![0; data.len()];
+        let len = self.decrypt_into(data, iv, &mut out)?;
+        out.truncate(len);
         Ok(out)
     }
 
-    fn ofb_encrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, Sm4Error> {
-        let block_num = data.len() / 16;
-        let tail_len = data.len() - block_num * 16;
-
-        let mut out: Vec<u8> = Vec::new();
-        let mut vec_buf: Vec<u8> = vec![0; 16];
-        vec_buf.clone_from_slice(iv);
-
-        // Normal
-        for i in 0..block_num {
-            let enc = self.cipher.encrypt(&vec_buf[..])?;
-            let ct = block_xor(&enc, &data[i * 16..i * 16 + 16]);
-            for i in ct.iter() {
-                out.push(*i);
-            }
-            vec_buf.clone_from_slice(&enc);
-        }
+    /// [`Sm4CipherMode::encrypt`] into `out`, which must be exactly
+    /// [`Sm4CipherMode::ciphertext_len`] bytes. Nothing is allocated.
+    pub fn encrypt_into(&self, data: &[u8], iv: &[u8], out: &mut [u8]) -> Sm4Result<()> {
+        let iv: [u8; 16] = iv.try_into().map_err(|_| Sm4Error::ErrorBlockSize)?;
+        self.padding.pad_into(data, out)?;
+        self.apply(out, &iv, true)
+    }
 
-        // Last block
-        let enc = self.cipher.encrypt(&vec_buf[..])?;
-        for i in 0..tail_len {
-            let b = data[block_num * 16 + i] ^ enc[i];
-            out.push(b);
+    /// [`Sm4CipherMode::decrypt`] into `out`, which must be exactly as long as
+    /// `data`. The plaintext is the first returned-length bytes of `out`, the
+    /// rest is cleared. Nothing is allocated.
+    pub fn decrypt_into(&self, data: &[u8], iv: &[u8], out: &mut [u8]) -> Sm4Result<usize> {
+        let iv: [u8; 16] = iv.try_into().map_err(|_| Sm4Error::ErrorBlockSize)?;
+        if out.len() != data.len() {
+            return Err(Sm4Error::ErrorDataLen);
         }
-        Ok(out)
+        out.copy_from_slice(data);
+        let range = self
+            .apply(out, &iv, false)
+            .and_then(|_| self.padding.unpadded_range(out))
+            .inspect_err(|_| out.fill(0))?;
+        let len = range.len();
+        out.copy_within(range, 0);
+        out[len..].fill(0);
+        Ok(len)
     }
 
-    fn ctr_encrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, Sm4Error> {
-        let block_num = data.len() / 16;
-        let tail_len = data.len() - block_num * 16;
-
-        let mut out: Vec<u8> = Vec::new();
-        let mut vec_buf: Vec<u8> = vec![0; 16];
-        vec_buf.clone_from_slice(iv);
-
-        // Normal
-        for i in 0..block_num {
-            let enc = self.cipher.encrypt(&vec_buf[..])?;
-            let ct = block_xor(&enc, &data[i * 16..i * 16 + 16]);
-            for i in ct.iter() {
-                out.push(*i);
+    /// Run the mode over `buf` in place
+    fn apply(&self, buf: &mut [u8], iv: &[u8; 16], encrypt: bool) -> Sm4Result<()> {
+        let mut state = *iv;
+        match self.mode {
+            CipherMode::Cbc => {
+                if !buf.len().is_multiple_of(16) {
+                    return Err(Sm4Error::ErrorDataLen);
+                }
+                for block in buf.chunks_exact_mut(16) {
+                    let block: &mut [u8; 16] = block.try_into().unwrap();
+                    if encrypt {
+                        xor_in(block, &state);
+                        self.cipher.encrypt_block(block);
+                        state = *block;
+                    } else {
+                        let c = *block;
+                        self.cipher.decrypt_block(block);
+                        xor_in(block, &state);
+                        state = c;
+                    }
+                }
+            }
+            CipherMode::Cfb => {
+                for block in buf.chunks_mut(16) {
+                    let mut ks = state;
+                    self.cipher.encrypt_block(&mut ks);
+                    if !encrypt && block.len() == 16 {
+                        state.copy_from_slice(block);
+                    }
+                    xor_in(block, &ks);
+                    if encrypt && block.len() == 16 {
+                        state.copy_from_slice(block);
+                    }
+                }
+            }
+            CipherMode::Ofb => {
+                for block in buf.chunks_mut(16) {
+                    self.cipher.encrypt_block(&mut state);
+                    xor_in(block, &state);
+                }
+            }
+            CipherMode::Ctr => {
+                for block in buf.chunks_mut(16) {
+                    let mut ks = state;
+                    self.cipher.encrypt_block(&mut ks);
+                    xor_in(block, &ks);
+                    block_add_one(&mut state);
+                }
             }
-            block_add_one(&mut vec_buf[..]);
-        }
-
-        // Last block
-        let enc = self.cipher.encrypt(&vec_buf[..])?;
-        for i in 0..tail_len {
-            let b = data[block_num * 16 + i] ^ enc[i];
-            out.push(b);
         }
-        Ok(out)
+        Ok(())
     }
+}
 
-    fn cbc_encrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, Sm4Error> {
-        let block_num = data.len() / 16;
-        let remind = data.len() % 16;
-
-        let mut out: Vec<u8> = Vec::new();
-        let mut vec_buf: Vec<u8> = vec![0; 16];
-        vec_buf.copy_from_slice(iv);
-
-        // Normal
-        for i in 0..block_num {
-            let ct = block_xor(&vec_buf, &data[i * 16..i * 16 + 16]);
-            let enc = self.cipher.encrypt(&ct)?;
-
-            out.extend_from_slice(&enc);
-            vec_buf = enc;
-        }
-
-        if remind != 0 {
-            let mut last_block = [16 - remind as u8; 16];
-            last_block[..remind].copy_from_slice(&data[block_num * 16..]);
-
-            let ct = block_xor(&vec_buf, &last_block);
-            let enc = self.cipher.encrypt(&ct)?;
-            out.extend_from_slice(&enc);
-        } else {
-            let ff_padding = block_xor(&vec_buf, &[0x10; 16]);
-            let enc = self.cipher.encrypt(&ff_padding)?;
-            out.extend_from_slice(&enc);
-        }
+#[cfg(test)]
+mod sm4test {
+    use hex_literal::hex;
+    use crate::{CipherMode, Iv, Sm4Cipher, Sm4CipherMode, Sm4Error, Sm4Key};
 
-        Ok(out)
+    #[test]
+    fn test_typed_key_iv() {
+        let key = hex!("0123456789abcdeffedcba9876543210");
+        let iv = hex!("000102030405060708090a0b0c0d0e0f");
+        let slices = Sm4CipherMode::new(&key, CipherMode::Cbc).unwrap();
+        let typed = Sm4CipherMode::with_key(&Sm4Key(key), CipherMode::Cbc);
+        let c = typed.encrypt_with_iv(b"typed", &Iv(iv)).unwrap();
+        assert_eq!(slices.encrypt(b"typed", &iv).unwrap(), c);
+        assert_eq!(b"typed".to_vec(), typed.decrypt_with_iv(&c, &Iv::from(iv)).unwrap());
+        assert_eq!(Sm4Cipher::new(&key).unwrap(), Sm4Cipher::with_key(&Sm4Key::from(key)));
+
+        assert_eq!(Sm4Key(key), Sm4Key::try_from(&key[..]).unwrap());
+        assert!(matches!(Sm4Key::try_from(&key[..15]), Err(Sm4Error::InvalidKey)));
+        assert!(matches!(Iv::try_from(&iv[..]), Ok(Iv(_))));
+        assert!(matches!(Iv::try_from(&[0u8; 17][..]), Err(Sm4Error::ErrorBlockSize)));
+        assert_eq!("Sm4Key(..)", format!("{:?}", Sm4Key(key)));
     }
 
-    fn cbc_decrypt(&self, data: &[u8], iv: &[u8]) -> Result<Vec<u8>, Sm4Error> {
-        let data_len = data.len();
-        let block_num = data_len / 16;
-        if data_len % 16 != 0 {
-            return Err(Sm4Error::ErrorDataLen);
+    #[test]
+    fn test_into() {
+        let key = Sm4Key(hex!("0123456789abcdeffedcba9876543210"));
+        let iv = [9u8; 16];
+        let data: Vec<u8> = (0..40).collect();
+        for mode in [CipherMode::Cbc, CipherMode::Cfb, CipherMode::Ofb, CipherMode::Ctr] {
+            let cipher = Sm4CipherMode::with_key(&key, mode);
+            let mut out = [0u8; 48];
+            let c = &mut out[..cipher.ciphertext_len(data.len())];
+            cipher.encrypt_into(&data, &iv, c).unwrap();
+            assert_eq!(cipher.encrypt(&data, &iv).unwrap(), c);
+
+            let mut plain = [0u8; 48];
+            let plain = &mut plain[..c.len()];
+            assert_eq!(data.len(), cipher.decrypt_into(c, &iv, plain).unwrap());
+            assert_eq!(&data[..], &plain[..data.len()]);
+            assert!(cipher.encrypt_into(&data, &iv, &mut out[..47]).is_err());
         }
 
-        let mut out: Vec<u8> = Vec::new();
-        let mut vec_buf = [0; 16];
-        vec_buf.copy_from_slice(iv);
-
-        // Normal
-        for i in 0..block_num {
-            let enc = self.cipher.decrypt(&data[i * 16..i * 16 + 16])?;
-            let ct = block_xor(&vec_buf, &enc);
+        let mut block = [0u8; 16];
+        let cipher = Sm4Cipher::with_key(&key);
+        cipher.encrypt_block(&mut block);
+        assert_eq!(cipher.encrypt(&[0; 16]).unwrap(), block);
+        cipher.decrypt_block(&mut block);
+        assert_eq!([0; 16], block);
+    }
 
-            for j in ct.iter() {
-                out.push(*j);
+    #[test]
+    fn test_ciphertext_len() {
+        let key = Sm4Key([7; 16]);
+        for mode in [CipherMode::Cbc, CipherMode::Ctr] {
+            let cipher = Sm4CipherMode::with_key(&key, mode);
+            for len in [0, 1, 16, 33] {
+                let c = cipher.encrypt_with_iv(&vec![1; len], &Iv([0; 16])).unwrap();
+                assert_eq!(cipher.ciphertext_len(len), c.len());
             }
-            vec_buf.copy_from_slice(&data[i * 16..i * 16 + 16]);
-        }
-
-        let last_u8 = out[data_len - 1];
-        if last_u8 > 0x10 || last_u8 == 0 {
-            return Err(Sm4Error::InvalidLastU8);
         }
-        out.resize(data_len - last_u8 as usize, 0);
-
-        Ok(out)
     }
-}
-
-#[cfg(test)]
-mod sm4test {
-    use hex_literal::hex;
-    use crate::Sm4Cipher;
 
     #[test]
     fn test_en_1() {
@@ -473,4 +549,39 @@ mod sm4test {
         }
         assert_eq!(&ciphertext, block.as_slice());
     }
+
+    #[test]
+    fn test_modes_openssl() {
+        // openssl enc -sm4-cfb / -sm4-ofb, and -sm4-cbc -nopad on the padded input
+        use crate::padding::Padding;
+        use crate::{CipherMode, Sm4CipherMode};
+
+        let key = hex!("0123456789abcdeffedcba9876543210");
+        let iv = hex!("000102030405060708090a0b0c0d0e0f");
+        let pt: Vec<u8> = (0u8..37).collect();
+        let cases = [
+            (CipherMode::Cfb, Padding::NoPadding, CFB),
+            (CipherMode::Ofb, Padding::NoPadding, OFB),
+            (CipherMode::Cbc, Padding::Iso9797M2, CBC_M2),
+            (CipherMode::Cbc, Padding::Iso9797M3, CBC_M3),
+        ];
+        for (mode, padding, expected) in cases {
+            let cipher = Sm4CipherMode::with_padding(&key, mode, padding).unwrap();
+            let ct = cipher.encrypt(&pt, &iv).unwrap();
+            println!("{:?}: {}", padding, hex::encode(&ct));
+            assert_eq!(expected, hex::encode(&ct));
+            assert_eq!(pt, cipher.decrypt(&ct, &iv).unwrap());
+        }
+
+        let cbc = Sm4CipherMode::with_padding(&key, CipherMode::Cbc, Padding::NoPadding).unwrap();
+        assert!(cbc.encrypt(&pt, &iv).is_err());
+        assert_eq!(32, cbc.encrypt(&pt[..32], &iv).unwrap().len());
+    }
+
+    const CFB: &str = "06999e6239a36eaa2284fd89eda5f765cab243c911b87479b3c487b45ecea6584a2eeb378d";
+    const OFB: &str = "06999e6239a36eaa2284fd89eda5f765e3fe505fa3964c6a7946f68fc13ef63f7b66ba6bab";
+    const CBC_M2: &str = "2677f46b09c122cc975533105bd4a22ad9ee98830e69745c9827f934a19621f8\
+                          a12483f611e8e3cca423a3962d00fcae";
+    const CBC_M3: &str = "d0c307ca8ca861dc8fc0fcae2b668d9895fdc98b88d56e7c39b9e032868ef572\
+                          d7b3438ca216468d107ce536302e323e5d7cecf406fa629395f826ca69800622";
 }
@@ -0,0 +1,169 @@
+use std::ops::Range;
+
+use crate::{Sm4Error, Sm4Result};
+
+const BLOCK: usize = 16;
+
+/// Padding schemes for the block modes, GB/T 17964 refers to the three
+/// methods of GB/T 15852.1 (ISO/IEC 9797-1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// No padding, the data must already be whole blocks for CBC
+    NoPadding,
+    /// PKCS#7, `n` bytes of value `n`, always adds at least one byte
+    Pkcs7,
+    /// ISO/IEC 9797-1 method 1, zeros up to the block boundary, nothing is added
+    /// to whole blocks and empty data becomes one zero block. Unpadding strips
+    /// every trailing zero of the last block, so only use it for data that
+    /// cannot end with 0x00.
+    Iso9797M1,
+    /// ISO/IEC 9797-1 method 2 (ISO/IEC 7816-4), a 0x80 byte followed by zeros
+    Iso9797M2,
+    /// ISO/IEC 9797-1 method 3, a block holding the bit length of the data,
+    /// big-endian, followed by the data zero padded as in method 1
+    Iso9797M3,
+}
+
+impl Padding {
+    /// The length of `pad` of `len` bytes
+    pub const fn padded_len(self, len: usize) -> usize {
+        match self {
+            Padding::NoPadding => len,
+            Padding::Pkcs7 => len + BLOCK - len % BLOCK,
+            Padding::Iso9797M1 if len == 0 => BLOCK,
+            Padding::Iso9797M1 => len.div_ceil(BLOCK) * BLOCK,
+            Padding::Iso9797M2 => (len + 1).div_ceil(BLOCK) * BLOCK,
+            Padding::Iso9797M3 => BLOCK + len.div_ceil(BLOCK) * BLOCK,
+        }
+    }
+
+    pub fn pad(self, data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0; self.padded_len(data.len())];
+        self.pad_into(data, &mut out).unwrap();
+        out
+    }
+
+    /// [`Padding::pad`] into `out`, which must be exactly
+    /// [`Padding::padded_len`] bytes
+    pub fn pad_into(self, data: &[u8], out: &mut [u8]) -> Sm4Result<()> {
+        if out.len() != self.padded_len(data.len()) {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let start = if self == Padding::Iso9797M3 { BLOCK } else { 0 };
+        let (body, tail) = out[start..].split_at_mut(data.len());
+        body.copy_from_slice(data);
+        match self {
+            Padding::Pkcs7 => tail.fill(tail.len() as u8),
+            Padding::Iso9797M2 => {
+                tail.fill(0);
+                tail[0] = 0x80;
+            }
+            _ => tail.fill(0),
+        }
+        if self == Padding::Iso9797M3 {
+            out[..BLOCK].copy_from_slice(&((data.len() as u128) * 8).to_be_bytes());
+        }
+        Ok(())
+    }
+
+    pub fn unpad(self, data: &[u8]) -> Sm4Result<Vec<u8>> {
+        Ok(data[self.unpadded_range(data)?].to_vec())
+    }
+
+    /// Where the data is within padded `data`
+    pub fn unpadded_range(self, data: &[u8]) -> Sm4Result<Range<usize>> {
+        if self != Padding::NoPadding && (data.is_empty() || !data.len().is_multiple_of(BLOCK)) {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let len = match self {
+            Padding::NoPadding => data.len(),
+            Padding::Pkcs7 => {
+                let n = data[data.len() - 1] as usize;
+                if n == 0 || n > BLOCK || data[data.len() - n..].iter().any(|&b| b as usize != n) {
+                    return Err(Sm4Error::InvalidLastU8);
+                }
+                data.len() - n
+            }
+            Padding::Iso9797M1 => {
+                let last = data.len() - BLOCK;
+                let zeros = data[last..].iter().rev().take_while(|&&b| b == 0).count();
+                data.len() - zeros
+            }
+            Padding::Iso9797M2 => {
+                let last = data.len() - BLOCK;
+                let zeros = data[last..].iter().rev().take_while(|&&b| b == 0).count();
+                if zeros == BLOCK || data[data.len() - zeros - 1] != 0x80 {
+                    return Err(Sm4Error::InvalidPadding);
+                }
+                data.len() - zeros - 1
+            }
+            Padding::Iso9797M3 => {
+                let bits = u128::from_be_bytes(data[..BLOCK].try_into().unwrap());
+                let body = &data[BLOCK..];
+                if bits % 8 != 0 || bits / 8 > body.len() as u128 || body.len() as u128 - bits / 8 >= BLOCK as u128 {
+                    return Err(Sm4Error::InvalidPadding);
+                }
+                let len = (bits / 8) as usize;
+                if body[len..].iter().any(|&b| b != 0) {
+                    return Err(Sm4Error::InvalidPadding);
+                }
+                return Ok(BLOCK..BLOCK + len);
+            }
+        };
+        Ok(0..len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::padding::Padding;
+    use crate::{CipherMode, Sm4CipherMode};
+
+    #[test]
+    fn test_padding() {
+        let data: Vec<u8> = (1u8..=20).collect();
+        assert_eq!(
+            "0102030405060708090a0b0c0d0e0f1011121314800000000000000000000000",
+            hex::encode(Padding::Iso9797M2.pad(&data))
+        );
+        assert_eq!(48, Padding::Iso9797M3.pad(&data).len());
+        assert_eq!(&[0u8, 0xa0][..], &Padding::Iso9797M3.pad(&data)[14..16]);
+        assert_eq!(32, Padding::Iso9797M1.pad(&data).len());
+        assert_eq!(16, Padding::Iso9797M1.pad(&data[..16]).len());
+        assert_eq!(32, Padding::Pkcs7.pad(&data[..16]).len());
+
+        for padding in [
+            Padding::Pkcs7,
+            Padding::Iso9797M1,
+            Padding::Iso9797M2,
+            Padding::Iso9797M3,
+        ] {
+            for len in [0, 1, 15, 16, 17, 20] {
+                let padded = padding.pad(&data[..len]);
+                assert_eq!(0, padded.len() % 16);
+                assert_eq!(padding.padded_len(len), padded.len());
+                assert_eq!(&data[..len], &padding.unpad(&padded).unwrap()[..], "{:?}", padding);
+            }
+        }
+
+        assert!(Padding::Iso9797M2.unpad(&[0u8; 16]).is_err());
+        assert!(Padding::Pkcs7.unpad(&[0u8; 16]).is_err());
+        let mut m3 = Padding::Iso9797M3.pad(&data);
+        m3[15] = 0x08;
+        assert!(Padding::Iso9797M3.unpad(&m3).is_err());
+    }
+
+    #[test]
+    fn test_padding_empty() {
+        assert_eq!(vec![0u8; 16], Padding::Iso9797M1.pad(b""));
+        assert!(Padding::Iso9797M1.unpad(&[0u8; 16]).unwrap().is_empty());
+
+        let iv = [0u8; 16];
+        for padding in [Padding::Pkcs7, Padding::Iso9797M1, Padding::Iso9797M2, Padding::Iso9797M3] {
+            let cipher = Sm4CipherMode::with_padding(&[7; 16], CipherMode::Cbc, padding).unwrap();
+            let c = cipher.encrypt(b"", &iv).unwrap();
+            assert!(!c.is_empty());
+            assert!(cipher.decrypt(&c, &iv).unwrap().is_empty(), "{:?}", padding);
+        }
+    }
+}
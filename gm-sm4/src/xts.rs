@@ -0,0 +1,210 @@
+use crate::{to_block, Sm4Cipher, Sm4Error, Sm4Result};
+
+/// How the tweak is advanced from one block to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XtsStandard {
+    /// GB/T 17964-2021, multiplication in the GCM bit order, the default of OpenSSL SM4-XTS
+    Gb,
+    /// IEEE 1619 / NIST SP 800-38E, little-endian multiplication
+    Ieee,
+}
+
+/// XTS-SM4 for disk and file-system encryption (GB/T 17964-2021 or IEEE 1619)
+///
+/// The key is 32 bytes, `key1 || key2`, where `key2` encrypts the tweak.
+/// A data unit (sector) must be at least 16 bytes, a partial last block is
+/// handled with ciphertext stealing so the ciphertext has the same length.
+///
+/// # Example
+/// ```rust
+/// use gm_sm4::xts::Sm4Xts;
+///
+/// let mut key = [0x11u8; 32];
+/// key[16..].fill(0x22);
+/// let xts = Sm4Xts::new(&key).unwrap();
+/// let disk = vec![0u8; 4096];
+/// let enc = xts.encrypt_sectors(&disk, 512, 0).unwrap();
+/// assert_eq!(disk, xts.decrypt_sectors(&enc, 512, 0).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sm4Xts {
+    data: Sm4Cipher,
+    tweak: Sm4Cipher,
+    standard: XtsStandard,
+}
+
+impl Sm4Xts {
+    /// XTS as specified by GB/T 17964-2021.
+    pub fn new(key: &[u8]) -> Sm4Result<Sm4Xts> {
+        Sm4Xts::with_standard(key, XtsStandard::Gb)
+    }
+
+    /// The two halves must differ, equal halves are rejected as OpenSSL does.
+    pub fn with_standard(key: &[u8], standard: XtsStandard) -> Sm4Result<Sm4Xts> {
+        if key.len() != 32 {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        if key[..16] == key[16..] {
+            return Err(Sm4Error::InvalidKey);
+        }
+        Ok(Sm4Xts {
+            data: Sm4Cipher::new(&key[..16])?,
+            tweak: Sm4Cipher::new(&key[16..])?,
+            standard,
+        })
+    }
+
+    /// Encrypt one data unit under a raw 16-byte tweak.
+    pub fn encrypt(&self, tweak: &[u8; 16], data: &[u8]) -> Sm4Result<Vec<u8>> {
+        self.process(tweak, data, true)
+    }
+
+    pub fn decrypt(&self, tweak: &[u8; 16], data: &[u8]) -> Sm4Result<Vec<u8>> {
+        self.process(tweak, data, false)
+    }
+
+    /// Encrypt consecutive sectors of `sector_size` bytes, the tweak of each
+    /// sector is its number as a 128-bit little-endian value, starting at
+    /// `first_sector`. The last sector may be shorter but not below 16 bytes.
+    pub fn encrypt_sectors(&self, data: &[u8], sector_size: usize, first_sector: u128) -> Sm4Result<Vec<u8>> {
+        self.sectors(data, sector_size, first_sector, true)
+    }
+
+    pub fn decrypt_sectors(&self, data: &[u8], sector_size: usize, first_sector: u128) -> Sm4Result<Vec<u8>> {
+        self.sectors(data, sector_size, first_sector, false)
+    }
+
+    fn sectors(&self, data: &[u8], sector_size: usize, first_sector: u128, enc: bool) -> Sm4Result<Vec<u8>> {
+        if sector_size < 16 {
+            return Err(Sm4Error::ErrorBlockSize);
+        }
+        let mut out = Vec::with_capacity(data.len());
+        for (i, sector) in data.chunks(sector_size).enumerate() {
+            let tweak = first_sector.wrapping_add(i as u128).to_le_bytes();
+            out.extend_from_slice(&self.process(&tweak, sector, enc)?);
+        }
+        Ok(out)
+    }
+
+    fn process(&self, tweak: &[u8; 16], data: &[u8], enc: bool) -> Sm4Result<Vec<u8>> {
+        if data.len() < 16 {
+            return Err(Sm4Error::ErrorDataLen);
+        }
+        let mut t = self.tweak.encrypt(tweak).map(|b| to_block(&b))?;
+        let full = data.len() / 16;
+        let tail = data.len() % 16;
+        // with stealing, the last full block is handled together with the tail
+        let plain_blocks = if tail == 0 { full } else { full - 1 };
+
+        let mut out = Vec::with_capacity(data.len());
+        for block in data[..plain_blocks * 16].chunks(16) {
+            out.extend_from_slice(&self.block(t, block, enc)?);
+            t = self.next_tweak(t);
+        }
+        if tail == 0 {
+            return Ok(out);
+        }
+
+        let last = &data[plain_blocks * 16..plain_blocks * 16 + 16];
+        let rest = &data[plain_blocks * 16 + 16..];
+        // decryption uses the tweaks of the last two blocks in reverse order
+        let (t_first, t_second) = if enc { (t, self.next_tweak(t)) } else { (self.next_tweak(t), t) };
+        let cc = self.block(t_first, last, enc)?;
+        let mut pp = [0u8; 16];
+        pp[..tail].copy_from_slice(rest);
+        pp[tail..].copy_from_slice(&cc[tail..]);
+        out.extend_from_slice(&self.block(t_second, &pp, enc)?);
+        out.extend_from_slice(&cc[..tail]);
+        Ok(out)
+    }
+
+    fn next_tweak(&self, t: [u8; 16]) -> [u8; 16] {
+        match self.standard {
+            XtsStandard::Gb => {
+                let t = u128::from_be_bytes(t);
+                let lsb = t & 1;
+                ((t >> 1) ^ ((0xe1 << 120) & lsb.wrapping_neg())).to_be_bytes()
+            }
+            XtsStandard::Ieee => {
+                let t = u128::from_le_bytes(t);
+                let carry = t >> 127;
+                ((t << 1) ^ (0x87 & carry.wrapping_neg())).to_le_bytes()
+            }
+        }
+    }
+
+    fn block(&self, t: [u8; 16], block: &[u8], enc: bool) -> Sm4Result<[u8; 16]> {
+        let mut x = [0u8; 16];
+        for i in 0..16 {
+            x[i] = block[i] ^ t[i];
+        }
+        let y = if enc {
+            self.data.encrypt(&x)?
+        } else {
+            self.data.decrypt(&x)?
+        };
+        for i in 0..16 {
+            x[i] = y[i] ^ t[i];
+        }
+        Ok(x)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::xts::{Sm4Xts, XtsStandard};
+
+    fn key() -> Vec<u8> {
+        hex::decode("2b7e151628aed2a6abf7158809cf4f3c000102030405060708090a0b0c0d0e0f").unwrap()
+    }
+
+    #[test]
+    fn test_xts_openssl() {
+        // EVP SM4-XTS of OpenSSL 3.5 with xts_standard GB and IEEE
+        let tweak: [u8; 16] = hex::decode("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let pt: Vec<u8> = (0u8..64).collect();
+        for (standard, vectors) in [(XtsStandard::Gb, GB), (XtsStandard::Ieee, IEEE)] {
+            let xts = Sm4Xts::with_standard(&key(), standard).unwrap();
+            for (len, expected) in [64, 37, 16].into_iter().zip(vectors) {
+                let ct = xts.encrypt(&tweak, &pt[..len]).unwrap();
+                println!("{:?} {}: {}", standard, len, hex::encode(&ct));
+                assert_eq!(expected, hex::encode(&ct));
+                assert_eq!(&pt[..len], &xts.decrypt(&tweak, &ct).unwrap()[..]);
+            }
+            assert!(xts.encrypt(&tweak, &pt[..15]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_xts_sectors() {
+        let xts = Sm4Xts::new(&key()).unwrap();
+        let disk: Vec<u8> = (0..1300u32).map(|i| (i % 253) as u8).collect();
+        let enc = xts.encrypt_sectors(&disk, 512, 7).unwrap();
+        assert_eq!(disk.len(), enc.len());
+        assert_eq!(disk, xts.decrypt_sectors(&enc, 512, 7).unwrap());
+
+        // each sector is independent
+        let second = xts.encrypt(&8u128.to_le_bytes(), &disk[512..1024]).unwrap();
+        assert_eq!(&enc[512..1024], &second[..]);
+        assert_ne!(disk, xts.decrypt_sectors(&enc, 512, 8).unwrap());
+
+        assert!(Sm4Xts::new(&[0x11u8; 32]).is_err());
+        assert!(Sm4Xts::new(&[0x11u8; 16]).is_err());
+    }
+
+    const GB: [&str; 3] = [
+        "17442e5e3ae9a314caf8f752df8c4ba26e7f3416d59ce0b3a6f0fc9442229765\
+         fcbc8ec75498ac4c791ddf27e310a2db0e762d9ac905459fa9abd7427ac5f2c2",
+        "17442e5e3ae9a314caf8f752df8c4ba2536883d05b2064a813b03f34b7ac470c6e7f3416d5",
+        "17442e5e3ae9a314caf8f752df8c4ba2",
+    ];
+    const IEEE: [&str; 3] = [
+        "17442e5e3ae9a314caf8f752df8c4ba2fd81bbd09e2a1b7d955c8558808040c7\
+         aca4ccd757b2faf01a0564c7c335bc8c4da75fc26ea23bc1077db2d71dee3be6",
+        "17442e5e3ae9a314caf8f752df8c4ba2b3efcb6e21a20bfb172f4f15fb691b98fd81bbd09e",
+        "17442e5e3ae9a314caf8f752df8c4ba2",
+    ];
+}
@@ -2,6 +2,7 @@ use criterion::{Criterion, criterion_group, criterion_main};
 
 use gm_sm9::fields::FieldElement;
 use gm_sm9::fields::fp::{fp_from_mont, mont_mul, fp_to_mont};
+use gm_sm9::key::{Sm9EncMasterKey, Sm9SignMasterKey};
 
 fn bench_mod_add(c: &mut Criterion) {
     let mut group = c.benchmark_group("sm9");
@@ -67,11 +68,33 @@ fn bench_mod_inv(c: &mut Criterion) {
     group.finish();
 }
 
+// 64 keys extracted and serialized, one by one and as a batch
+fn bench_extract_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sm9");
+    let ids: Vec<String> = (0..64).map(|i| format!("user{}@example.com", i)).collect();
+    let msk = Sm9SignMasterKey::master_key_generate();
+    group.bench_function("bench_sign_extract_key_x64", |x| {
+        x.iter(|| ids.iter().map(|id| msk.extract_key(id.as_bytes()).unwrap().to_bytes()).collect::<Vec<_>>())
+    });
+    group.bench_function("bench_sign_extract_batch_x64", |x| {
+        x.iter(|| msk.extract_batch(&ids).iter().map(|k| k.unwrap().to_bytes()).collect::<Vec<_>>())
+    });
+    let msk = Sm9EncMasterKey::master_key_generate();
+    group.bench_function("bench_enc_extract_key_x64", |x| {
+        x.iter(|| ids.iter().map(|id| msk.extract_key(id.as_bytes()).unwrap().to_bytes()).collect::<Vec<_>>())
+    });
+    group.bench_function("bench_enc_extract_batch_x64", |x| {
+        x.iter(|| msk.extract_batch(&ids).iter().map(|k| k.unwrap().to_bytes()).collect::<Vec<_>>())
+    });
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_mod_add,
     bench_mod_sub,
     bench_mod_mul,
     bench_mod_inv,
+    bench_extract_batch,
 );
 criterion_main!(benches);
@@ -0,0 +1,278 @@
+//! BLS signatures on the SM9 BN curve, with aggregation.
+//!
+//! Signatures live in G1 (65 bytes), public keys in G2 (129 bytes, 65
+//! compressed), with the curve and the generators of SM9. This is not an SM9
+//! signature and has nothing identity-based about it, it only shares the
+//! groups and the pairing:
+//!
+//! * `pk = [x]P2`, `sig = [x]H(M)`, checked as `e(sig, P2) = e(H(M), pk)`
+//! * `H` hashes to G1 by try-and-increment over SM3, the `y` kept is the even one
+//! * an aggregate is the sum of the signatures, checked against the product of
+//!   `e(H(M_i), pk_i)`
+//!
+//! An aggregate over one message, [`fast_aggregate_verify`], is only safe for
+//! keys whose proof of possession has been checked, otherwise a rogue key
+//! cancels the others out. [`aggregate_verify`] avoids that by requiring
+//! distinct messages.
+use std::collections::HashSet;
+
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm9Error, Sm9Result};
+use crate::fields::fp::{fp_from_mont, fp_sqrt, fp_to_mont};
+use crate::fields::{fn_random_u256, mod_n_from_bytes, FieldElement};
+use crate::gt::Gt;
+use crate::points::{twist_point_add_full, Point, TwistPoint};
+use crate::u256::{u256_from_be_bytes, u256_to_be_bytes, u512_mod_u256, U256};
+use crate::{
+    SM9_MODP_MONT_FIVE, SM9_MODP_MONT_ONE, SM9_N, SM9_P, SM9_TWIST_POINT_MONT_P2,
+};
+
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_SM9G1_XMD:SM3_TAI_NUL_";
+const POP_DST: &[u8] = b"BLS_POP_SM9G1_XMD:SM3_TAI_POP_";
+
+/// Try-and-increment: `x = SM3(.. || 0) || SM3(.. || 1) mod p` over the tag,
+/// the message and a counter, until `x^3 + 5` is a square
+fn hash_to_g1(msg: &[u8], dst: &[u8]) -> Point {
+    let mut counter = 0u32;
+    loop {
+        let prefix = [&[dst.len() as u8][..], dst, msg, &counter.to_be_bytes()].concat();
+        let wide = [0u8, 1].map(|i| sm3_hash(&[&prefix[..], &[i]].concat()));
+        let (hi, lo) = (u256_from_be_bytes(&wide[0]), u256_from_be_bytes(&wide[1]));
+        let x = u512_mod_u256(&[lo[0], lo[1], lo[2], lo[3], hi[0], hi[1], hi[2], hi[3]], &SM9_P);
+        let x = fp_to_mont(&x);
+        let rhs = x.fp_sqr().fp_mul(&x).fp_add(&SM9_MODP_MONT_FIVE);
+        if let Some(mut y) = fp_sqrt(&rhs) {
+            if fp_from_mont(&y)[0] & 1 == 1 {
+                y = y.fp_neg();
+            }
+            return Point {
+                x,
+                y,
+                z: SM9_MODP_MONT_ONE,
+            };
+        }
+        counter += 1;
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct BlsSecretKey(U256);
+
+// never printed, like the other secret keys
+impl std::fmt::Debug for BlsSecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BlsSecretKey(…)")
+    }
+}
+
+impl BlsSecretKey {
+    pub fn generate() -> Self {
+        BlsSecretKey(fn_random_u256())
+    }
+
+    /// Restore a key from x in [1, n-1].
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Self> {
+        if b.len() != 32 {
+            return Err(Sm9Error::InvalidLength);
+        }
+        let x = mod_n_from_bytes(b).map_err(|_| Sm9Error::InvalidPrivate)?;
+        if x == [0; 4] {
+            return Err(Sm9Error::InvalidPrivate);
+        }
+        Ok(BlsSecretKey(x))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        u256_to_be_bytes(&self.0)
+    }
+
+    pub fn public_key(&self) -> BlsPublicKey {
+        BlsPublicKey(TwistPoint::g_mul(&self.0))
+    }
+
+    pub fn sign(&self, msg: &[u8]) -> BlsSignature {
+        BlsSignature(hash_to_g1(msg, SIGNATURE_DST).point_mul(&self.0))
+    }
+
+    /// A signature of the public key itself under its own tag, published
+    /// with the key so that it can take part in [`fast_aggregate_verify`]
+    pub fn prove_possession(&self) -> BlsSignature {
+        let pk = self.public_key().to_bytes();
+        BlsSignature(hash_to_g1(&pk, POP_DST).point_mul(&self.0))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlsPublicKey(TwistPoint);
+
+impl BlsPublicKey {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
+
+    /// 65 bytes instead of 129, see [`TwistPoint::to_bytes_compressed`]
+    pub fn to_bytes_compressed(&self) -> Vec<u8> {
+        self.0.to_bytes_compressed()
+    }
+
+    /// 129 bytes, or 65 compressed, a point of the order-n subgroup of the
+    /// twist.
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Self> {
+        let q = TwistPoint::from_bytes(b)?;
+        // the twist has a cofactor, G2 is the part killed by n
+        if !q.point_mul(&SM9_N).is_zero() {
+            return Err(Sm9Error::InvalidPublic);
+        }
+        Ok(BlsPublicKey(q))
+    }
+
+    pub fn verify(&self, msg: &[u8], sig: &BlsSignature) -> Sm9Result<()> {
+        check_pairings(sig, &[(hash_to_g1(msg, SIGNATURE_DST), self.0)])
+    }
+
+    pub fn verify_possession(&self, pop: &BlsSignature) -> Sm9Result<()> {
+        check_pairings(pop, &[(hash_to_g1(&self.to_bytes(), POP_DST), self.0)])
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BlsSignature(Point);
+
+impl BlsSignature {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
+
+    /// 65 bytes, a point of G1 other than the infinity.
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Self> {
+        if b.len() != 65 || b[0] != 0x04 {
+            return Err(Sm9Error::InvalidLength);
+        }
+        let p = Point::from_bytes(b)?;
+        if !p.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
+        }
+        Ok(BlsSignature(p))
+    }
+}
+
+/// `e(sig, P2) = Π e(P_i, Q_i)`
+fn check_pairings(sig: &BlsSignature, pairs: &[(Point, TwistPoint)]) -> Sm9Result<()> {
+    if sig.0.is_zero() {
+        return Err(Sm9Error::ZeroSig);
+    }
+    let lhs = Gt::pairing(&sig.0, &SM9_TWIST_POINT_MONT_P2);
+    let rhs = pairs
+        .iter()
+        .fold(Gt::one(), |acc, (p, q)| acc.mul(&Gt::pairing(p, q)));
+    if lhs != rhs {
+        return Err(Sm9Error::InvalidDigest);
+    }
+    Ok(())
+}
+
+/// The sum of the signatures, `None` for an empty list
+pub fn aggregate(sigs: &[BlsSignature]) -> Option<BlsSignature> {
+    let (first, rest) = sigs.split_first()?;
+    Some(BlsSignature(rest.iter().fold(first.0, |acc, s| acc.point_add(&s.0))))
+}
+
+/// Verify an aggregate of signatures by `pks[i]` of `msgs[i]`, all distinct.
+pub fn aggregate_verify(pks: &[BlsPublicKey], msgs: &[&[u8]], sig: &BlsSignature) -> Sm9Result<()> {
+    if pks.is_empty() || pks.len() != msgs.len() {
+        return Err(Sm9Error::InvalidLength);
+    }
+    if msgs.iter().collect::<HashSet<_>>().len() != msgs.len() {
+        return Err(Sm9Error::InvalidDigest);
+    }
+    let pairs: Vec<_> = pks
+        .iter()
+        .zip(msgs)
+        .map(|(pk, msg)| (hash_to_g1(msg, SIGNATURE_DST), pk.0))
+        .collect();
+    check_pairings(sig, &pairs)
+}
+
+/// Verify an aggregate of signatures of `msg` by all of `pks`, each of which
+/// must have passed [`BlsPublicKey::verify_possession`].
+pub fn fast_aggregate_verify(pks: &[BlsPublicKey], msg: &[u8], sig: &BlsSignature) -> Sm9Result<()> {
+    let (first, rest) = pks.split_first().ok_or(Sm9Error::InvalidLength)?;
+    let pk = rest.iter().fold(first.0, |acc, pk| twist_point_add_full(&acc, &pk.0));
+    BlsPublicKey(pk).verify(msg, sig)
+}
+
+#[cfg(test)]
+mod test_bls {
+    use super::*;
+
+    // from an independent Python implementation over hashlib's SM3
+    #[test]
+    fn test_bls_vectors() {
+        let sk = BlsSecretKey::from_bytes(&hex::decode("000130e78459d78545cb54c587e02cf480ce0b66340f319f348a1d5b1f2dc5f4").unwrap()).unwrap();
+        let cases: [(&[u8], &str, &str); 2] = [
+            (
+                b"",
+                "76d7a586774f3a7fdfd09d6326d2b9ddcb9b5eca15b8b44a692ab43be959e13a6a6624dcb5cb0adbfcc879de5bf469f3dc9b96b713a641cac0efc2d2807aca80",
+                "9a906f27b068af4067546383da76d80086391c78c5e9bd948e522988beee3c166cec5fcc321f3343526fc25f446fc55448d1ae05c03b15845b585f51fadbb26d",
+            ),
+            (
+                b"abc",
+                "5cc44f6931308ea16c82f445bf11d94ccf94386f3497fad3fa64ebe55a735e936fa269512e5cf486bf043d235d73cdf2fa38b41a67323d3b92ada8a1fb7a8aaa",
+                "0caab6819619bc9a2b609fb153ff1a76205377120588b5df6cc91577915e61c49f06b7166676d9225696fd9770cec56343694847ff2fd82bb12bfb5b8b073681",
+            ),
+        ];
+        for (msg, h, sig) in cases {
+            assert_eq!(h, hex::encode(&hash_to_g1(msg, SIGNATURE_DST).to_bytes_be()[1..]));
+            let s = sk.sign(msg);
+            assert_eq!(sig, hex::encode(&s.to_bytes()[1..]));
+            sk.public_key().verify(msg, &s).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_bls_sign_verify() {
+        let sk = BlsSecretKey::generate();
+        let pk = BlsPublicKey::from_bytes(&sk.public_key().to_bytes()).unwrap();
+        let sig = BlsSignature::from_bytes(&sk.sign(b"block").to_bytes()).unwrap();
+        pk.verify(b"block", &sig).unwrap();
+        assert!(pk.verify(b"Block", &sig).is_err());
+        assert!(BlsSecretKey::generate().public_key().verify(b"block", &sig).is_err());
+        pk.verify_possession(&sk.prove_possession()).unwrap();
+        // a signature is not a proof of possession
+        assert!(pk.verify_possession(&sk.sign(&pk.to_bytes())).is_err());
+        assert!(BlsSecretKey::from_bytes(&sk.to_bytes()).unwrap().public_key() == pk);
+
+        let mut b = sig.to_bytes();
+        b[64] ^= 1;
+        assert!(BlsSignature::from_bytes(&b).is_err());
+        let mut b = pk.to_bytes();
+        b[128] ^= 1;
+        assert!(BlsPublicKey::from_bytes(&b).is_err());
+
+        let compressed = pk.to_bytes_compressed();
+        assert_eq!(65, compressed.len());
+        let decoded = BlsPublicKey::from_bytes(&compressed).unwrap();
+        assert!(decoded == pk);
+        decoded.verify(b"block", &sig).unwrap();
+        assert!(BlsPublicKey::from_bytes(&compressed[..64]).is_err());
+    }
+
+    #[test]
+    fn test_bls_aggregate() {
+        let sks: Vec<_> = (0..3).map(|_| BlsSecretKey::generate()).collect();
+        let pks: Vec<_> = sks.iter().map(BlsSecretKey::public_key).collect();
+        let msgs: [&[u8]; 3] = [b"tx 1", b"tx 2", b"tx 3"];
+
+        let sig = aggregate(&[sks[0].sign(msgs[0]), sks[1].sign(msgs[1]), sks[2].sign(msgs[2])]).unwrap();
+        aggregate_verify(&pks, &msgs, &sig).unwrap();
+        assert!(aggregate_verify(&pks, &[msgs[0], msgs[2], msgs[1]], &sig).is_err());
+        assert!(aggregate_verify(&pks[..2], &msgs[..2], &sig).is_err());
+        assert!(aggregate_verify(&pks[..2], &[msgs[0], msgs[0]], &sig).is_err());
+
+        let sig = aggregate(&sks.iter().map(|sk| sk.sign(b"block")).collect::<Vec<_>>()).unwrap();
+        fast_aggregate_verify(&pks, b"block", &sig).unwrap();
+        assert!(fast_aggregate_verify(&pks[1..], b"block", &sig).is_err());
+        assert!(aggregate(&[]).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+//! Multi-recipient SM9 encryption for group messaging under one KGC.
+//!
+//! The message is sealed once with SM4-GCM under a random content key, and
+//! the content key is wrapped for every recipient with its own SM9 key
+//! encapsulation. An independent `r_i` is drawn per recipient, with
+//! `C1_i = [r_i](H1(ID_i || hid, N)·P1 + Ppube)` and
+//! `w_i = e(Ppube, P2)^r_i`, which recipient `i` gets back as
+//! `e(C1_i, de_i)`, exactly like in SM9 decryption. The pairing
+//! `e(Ppube, P2)` is computed once per message.
+//!
+//! * `K_i = KDF(C1_i || w_i || ID_i, 16)`, the content key is sent as `CK ⊕ K_i`
+//! * the message is sealed with SM4-GCM under `CK`, with the header as AAD
+//!
+//! Sharing one `r` between recipients would be broken: from two `C1` values
+//! anyone can solve for `[r]P1`, then `[r]Ppube` and `w`, without a key.
+//!
+//! Layout: `count(2, BE) || (id_len(2, BE) || ID || C1(65) || CK ⊕ K(16))* ||
+//! ciphertext || tag(16)`. The content key is fresh for every message, so
+//! the GCM nonce is fixed to zero. All recipients share the content key,
+//! which is the point of broadcasting: every one of them can read what the
+//! others can.
+use std::collections::HashSet;
+
+use gm_sm4::gcm::Sm4Gcm;
+use rand::RngCore;
+
+use crate::error::{Sm9Error, Sm9Result};
+use crate::fields::{fn_random_u256, FieldElement};
+use crate::key::{kdf, sm9_u256_hash1, Hid, Sm9EncKey};
+use crate::points::{sm9_u256_pairing, Point};
+use crate::u256::xor;
+use crate::{SM9_POINT_MONT_P1, SM9_TWIST_POINT_MONT_P2};
+
+const C1_LEN: usize = 65;
+const KEY_LEN: usize = 16;
+const TAG_LEN: usize = 16;
+const NONCE: [u8; 12] = [0; 12];
+
+/// `K = KDF(C1 || w || ID, 16)` as in SM9 encryption, all zero is rejected
+fn wrapping_key(c1: &[u8], w: &[u8], id: &[u8]) -> Sm9Result<Vec<u8>> {
+    let k = kdf(&[&c1[1..], w, id].concat(), KEY_LEN)?;
+    if k.iter().all(|&b| b == 0) {
+        return Err(Sm9Error::KdfHashError);
+    }
+    Ok(k)
+}
+
+/// Encrypt `msg` to all of `ids` under the encryption master public key `ppube`.
+pub fn encrypt_broadcast(ppube: &Point, ids: &[&[u8]], msg: &[u8]) -> Sm9Result<Vec<u8>> {
+    if ids.is_empty() || ids.len() > u16::MAX as usize {
+        return Err(Sm9Error::InvalidLength);
+    }
+    if ids.iter().collect::<HashSet<_>>().len() != ids.len() {
+        return Err(Sm9Error::DuplicateRecipient);
+    }
+    let mut ck = [0u8; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut ck);
+    let g = sm9_u256_pairing(&SM9_TWIST_POINT_MONT_P2, ppube);
+
+    let mut header = (ids.len() as u16).to_be_bytes().to_vec();
+    for &id in ids {
+        if id.len() > u16::MAX as usize {
+            return Err(Sm9Error::IdTooLong);
+        }
+        let q = SM9_POINT_MONT_P1
+            .point_mul(&sm9_u256_hash1(id, Hid::Enc.value()))
+            .point_add(ppube);
+        let (c1, k) = loop {
+            let r = fn_random_u256();
+            let c1 = q.point_mul(&r).to_bytes_be();
+            if let Ok(k) = wrapping_key(&c1, &g.pow(&r).to_bytes_be(), id) {
+                break (c1, k);
+            }
+        };
+        header.extend_from_slice(&(id.len() as u16).to_be_bytes());
+        header.extend_from_slice(id);
+        header.extend_from_slice(&c1);
+        header.extend_from_slice(&xor(&ck, &k, KEY_LEN));
+    }
+
+    let gcm = Sm4Gcm::new(&ck).map_err(|_| Sm9Error::InvalidKeyLength)?;
+    let sealed = gcm
+        .encrypt(&NONCE, &header, msg)
+        .map_err(|_| Sm9Error::InvalidLength)?;
+    Ok([header, sealed].concat())
+}
+
+/// `(ID, C1, CK ⊕ K)` of a recipient, `C1` left undecoded
+type Recipient<'a> = (&'a [u8], &'a [u8], &'a [u8]);
+
+/// The recipients and the header length
+fn parse_header(data: &[u8]) -> Sm9Result<(Vec<Recipient<'_>>, usize)> {
+    let take = |pos: usize, len: usize| data.get(pos..pos + len).ok_or(Sm9Error::InvalidLength);
+    let count = u16::from_be_bytes(take(0, 2)?.try_into().unwrap()) as usize;
+    let mut pos = 2;
+    let mut recipients = Vec::with_capacity(count);
+    for _ in 0..count {
+        let id_len = u16::from_be_bytes(take(pos, 2)?.try_into().unwrap()) as usize;
+        let id = take(pos + 2, id_len)?;
+        let c1 = take(pos + 2 + id_len, C1_LEN)?;
+        let wrapped = take(pos + 2 + id_len + C1_LEN, KEY_LEN)?;
+        recipients.push((id, c1, wrapped));
+        pos += 2 + id_len + C1_LEN + KEY_LEN;
+    }
+    if count == 0 || data.len() < pos + TAG_LEN {
+        return Err(Sm9Error::InvalidLength);
+    }
+    Ok((recipients, pos))
+}
+
+/// The identities a broadcast message is encrypted to
+pub fn recipients(data: &[u8]) -> Sm9Result<Vec<Vec<u8>>> {
+    Ok(parse_header(data)?
+        .0
+        .into_iter()
+        .map(|(id, _, _)| id.to_vec())
+        .collect())
+}
+
+/// Decrypt a message of [`encrypt_broadcast`] with the key extracted for `id`.
+///
+/// Fails with [`Sm9Error::NotARecipient`] when `id` is not in the header,
+/// [`Sm9Error::NotOnCurve`] for a bad `C1` and [`Sm9Error::HashNotEqual`]
+/// when the tag does not match, which is also what a key for another
+/// identity gets.
+pub fn decrypt_broadcast(key: &Sm9EncKey, id: &[u8], data: &[u8]) -> Sm9Result<Vec<u8>> {
+    let (recipients, header_len) = parse_header(data)?;
+    let (_, c1_bytes, wrapped) = recipients
+        .into_iter()
+        .find(|(rid, _, _)| *rid == id)
+        .ok_or(Sm9Error::NotARecipient)?;
+    let c1 = Point::from_bytes(c1_bytes)?;
+    if c1_bytes[0] != 0x04 || !c1.is_on_curve() {
+        return Err(Sm9Error::NotOnCurve);
+    }
+    let w = sm9_u256_pairing(&key.de, &c1);
+    open(data, header_len, id, c1_bytes, wrapped, &w.to_bytes_be())
+}
+
+/// Unwrap the content key with `w` and open the sealed message
+fn open(data: &[u8], header_len: usize, id: &[u8], c1: &[u8], wrapped: &[u8], w: &[u8]) -> Sm9Result<Vec<u8>> {
+    let ck = xor(wrapped, &wrapping_key(c1, w, id)?, KEY_LEN);
+    let gcm = Sm4Gcm::new(&ck).map_err(|_| Sm9Error::InvalidKeyLength)?;
+    let (header, sealed) = data.split_at(header_len);
+    gcm.decrypt(&NONCE, header, sealed)
+        .map_err(|_| Sm9Error::HashNotEqual)
+}
+
+#[cfg(test)]
+mod test_broadcast {
+    use super::*;
+    use crate::fields::{mod_n_inv, mod_n_sub};
+    use crate::key::Sm9EncMasterKey;
+
+    #[test]
+    fn test_broadcast_round_trip() {
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let ids: [&[u8]; 3] = [b"Alice", b"Bob", b"Carol"];
+        let c = encrypt_broadcast(&msk.ppube, &ids, b"meeting at noon").unwrap();
+        assert_eq!(ids.map(|id| id.to_vec()).to_vec(), recipients(&c).unwrap());
+        for id in ids {
+            let key = msk.extract_key(id).unwrap();
+            assert_eq!(
+                b"meeting at noon".to_vec(),
+                decrypt_broadcast(&key, id, &c).unwrap()
+            );
+        }
+
+        let eve = msk.extract_key(b"Eve").unwrap();
+        assert!(decrypt_broadcast(&eve, b"Eve", &c) == Err(Sm9Error::NotARecipient));
+        // a key for another identity gets the wrong w
+        assert!(decrypt_broadcast(&eve, b"Bob", &c) == Err(Sm9Error::HashNotEqual));
+
+        let c = encrypt_broadcast(&msk.ppube, &[b"Alice".as_slice()], b"").unwrap();
+        let key = msk.extract_key(b"Alice").unwrap();
+        assert_eq!(
+            Vec::<u8>::new(),
+            decrypt_broadcast(&key, b"Alice", &c).unwrap()
+        );
+    }
+
+    /// `w` of recipient `a` from the `C1` of `a` and `b`, if they share `r`:
+    /// `[r]P1 = (C1_a - C1_b) / (h_a - h_b)`, `[r]Ppube = C1_a - [h_a][r]P1`
+    fn combine(c1_a: &Point, c1_b: &Point, id_a: &[u8], id_b: &[u8]) -> Vec<u8> {
+        let h_a = sm9_u256_hash1(id_a, Hid::Enc.value());
+        let h_b = sm9_u256_hash1(id_b, Hid::Enc.value());
+        let rp1 = c1_a.point_sub(c1_b).point_mul(&mod_n_inv(&mod_n_sub(&h_a, &h_b)));
+        let rppube = c1_a.point_sub(&rp1.point_mul(&h_a));
+        sm9_u256_pairing(&SM9_TWIST_POINT_MONT_P2, &rppube).to_bytes_be()
+    }
+
+    #[test]
+    fn test_broadcast_combined_c1() {
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let (a, b): (&[u8], &[u8]) = (b"Alice", b"Bob");
+        let q = |id: &[u8]| {
+            SM9_POINT_MONT_P1
+                .point_mul(&sm9_u256_hash1(id, Hid::Enc.value()))
+                .point_add(&msk.ppube)
+        };
+
+        // the attack recovers w when the recipients share r
+        let r = fn_random_u256();
+        let w = sm9_u256_pairing(&SM9_TWIST_POINT_MONT_P2, &msk.ppube).pow(&r);
+        let shared = combine(&q(a).point_mul(&r), &q(b).point_mul(&r), a, b);
+        assert_eq!(w.to_bytes_be(), shared);
+
+        // and gets nowhere with a C1 per recipient
+        let c = encrypt_broadcast(&msk.ppube, &[a, b], b"secret").unwrap();
+        let (recipients, header_len) = parse_header(&c).unwrap();
+        let (_, c1_a, wrapped) = recipients[0];
+        let (_, c1_b, _) = recipients[1];
+        let w = combine(
+            &Point::from_bytes(c1_a).unwrap(),
+            &Point::from_bytes(c1_b).unwrap(),
+            a,
+            b,
+        );
+        assert!(open(&c, header_len, a, c1_a, wrapped, &w) == Err(Sm9Error::HashNotEqual));
+        assert_ne!(c1_a, c1_b);
+    }
+
+    #[test]
+    fn test_broadcast_rejects() {
+        let msk = Sm9EncMasterKey::master_key_generate();
+        assert!(encrypt_broadcast(&msk.ppube, &[], b"m") == Err(Sm9Error::InvalidLength));
+        assert!(
+            encrypt_broadcast(&msk.ppube, &[b"Bob".as_slice(), b"Bob"], b"m")
+                == Err(Sm9Error::DuplicateRecipient)
+        );
+
+        let c = encrypt_broadcast(&msk.ppube, &[b"Alice".as_slice(), b"Bob"], b"msg").unwrap();
+        let key = msk.extract_key(b"Bob").unwrap();
+        for i in (0..c.len()).step_by(3) {
+            let mut b = c.clone();
+            b[i] ^= 1;
+            assert!(decrypt_broadcast(&key, b"Bob", &b).is_err());
+        }
+        for n in 0..c.len() {
+            assert!(decrypt_broadcast(&key, b"Bob", &c[..n]).is_err());
+        }
+        // the tag, then Bob's wrapped key
+        let mut b = c.clone();
+        *b.last_mut().unwrap() ^= 1;
+        assert!(decrypt_broadcast(&key, b"Bob", &b) == Err(Sm9Error::HashNotEqual));
+        let (_, header_len) = parse_header(&c).unwrap();
+        let mut b = c.clone();
+        b[header_len - 1] ^= 1;
+        assert!(decrypt_broadcast(&key, b"Bob", &b) == Err(Sm9Error::HashNotEqual));
+    }
+}
@@ -0,0 +1,80 @@
+//! Known-broken inputs for the public decoders.
+//!
+//! Every valid encoding is cut at each length, extended, bit-flipped and
+//! replaced by garbage of the same and of other lengths. A decoder must reject
+//! the truncated and extended inputs and must not panic on the others, which
+//! may happen to be valid encodings. Decrypting and verifying reject them all.
+//! The GT encodings, each costing an exponentiation to check, are flipped at
+//! every 4th or 8th byte only. The runner is [`gm_core::corpus`].
+use gm_core::corpus::{accepts, resized, Corpus};
+use gm_core::{Decryptor, Signer, Verifier};
+
+use crate::gt::Gt;
+use crate::key::{Sm9Decryptor, Sm9EncKey, Sm9EncMasterKey, Sm9SignKey, Sm9SignMasterKey, Sm9Signer};
+use crate::pbe::{unwrap_enc_key, unwrap_sign_key, wrap_enc_key, wrap_sign_key};
+use crate::signature::Sm9Signature;
+use crate::SM9_POINT_MONT_P1;
+
+const CORPUS: Corpus = Corpus::new(&[1, 32, 64, 65, 97, 129, 194, 4096]);
+
+#[test]
+fn corpus_keys() {
+    let sign_key = Sm9SignMasterKey::master_key_generate().extract_key(b"Alice").unwrap();
+    let enc_key = Sm9EncMasterKey::master_key_generate().extract_key(b"Bob").unwrap();
+    CORPUS.check_decoder("Sm9SignKey::from_bytes", &sign_key.to_bytes(), Sm9SignKey::from_bytes);
+    CORPUS.check_decoder("Sm9EncKey::from_bytes", &enc_key.to_bytes(), Sm9EncKey::from_bytes);
+
+    // bit flips of the iteration count would only make this slow
+    let sealed = wrap_sign_key(&sign_key, b"password", 1).unwrap();
+    for b in resized(&sealed) {
+        assert!(!accepts("unwrap_sign_key", &b, &|b: &[u8]| unwrap_sign_key(b, b"password")));
+    }
+    let sealed = wrap_enc_key(&enc_key, b"password", 1).unwrap();
+    for b in resized(&sealed) {
+        assert!(!accepts("unwrap_enc_key", &b, &|b: &[u8]| unwrap_enc_key(b, b"password")));
+    }
+
+    let signer = Sm9Signer::new(sign_key);
+    for b in resized(&signer.precomputed()) {
+        assert!(!accepts("Sm9Signer::from_precomputed", &b, &|b: &[u8]| {
+            Sm9Signer::from_precomputed(sign_key, b)
+        }));
+    }
+}
+
+#[test]
+fn corpus_signatures() {
+    let msk = Sm9SignMasterKey::master_key_generate();
+    let key = msk.extract_key(b"Alice").unwrap();
+    let msg = b"corpus";
+    let sig = Signer::sign(&key, msg).unwrap().to_bytes();
+    CORPUS.check_decoder("Sm9Signature::from_bytes", &sig, Sm9Signature::from_bytes);
+    let decoded = Sm9Signature::from_bytes(&sig).unwrap();
+    CORPUS.check_decoder("Sm9Signature::from_bytes", &decoded.to_bytes_compressed(), Sm9Signature::from_bytes);
+    CORPUS.check_decoder("Sm9Signature::from_der", &decoded.to_der(), Sm9Signature::from_der);
+
+    let verifier = msk.verifier(b"Alice");
+    CORPUS.check_authenticated("verify", &sig, |b| {
+        Sm9Signature::from_bytes(b).and_then(|sig| verifier.verify(msg, &sig))
+    });
+}
+
+#[test]
+fn corpus_ciphertexts() {
+    let msk = Sm9EncMasterKey::master_key_generate();
+    let decryptor = Sm9Decryptor::new(msk.extract_key(b"Bob").unwrap(), b"Bob");
+    let c = msk.encrypt(b"Bob", b"corpus").unwrap();
+    CORPUS.check_authenticated("decrypt", &c, |b| decryptor.decrypt(b));
+
+    // the KDF output grows with the message
+    let long = vec![0x5au8; 1000];
+    assert_eq!(long, decryptor.decrypt(&msk.encrypt(b"Bob", &long).unwrap()).unwrap());
+}
+
+#[test]
+fn corpus_gt() {
+    let key = Sm9SignMasterKey::master_key_generate().extract_key(b"Alice").unwrap();
+    let g = Gt::pairing(&SM9_POINT_MONT_P1, &key.ppubs);
+    CORPUS.with_step(8).check_decoder("Gt::from_bytes", &g.to_bytes(), Gt::from_bytes);
+    CORPUS.with_step(4).check_decoder("Gt::from_compressed", &g.to_compressed(), Gt::from_compressed);
+}
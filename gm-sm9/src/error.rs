@@ -24,6 +24,22 @@ pub enum Sm9Error {
     InvalidDigest,
     InvalidSecretKey,
     KdfHashError,
+    InvalidPassword,
+    InvalidLength,
+    InvalidHid,
+    InvalidGt,
+    InvalidEncoding,
+    NotARecipient,
+    DuplicateRecipient,
+    InvalidIdentity,
+    OutsideDomain,
+    InvalidPeriod,
+    Expired,
+    NonCanonical,
+    InvalidKeyLength,
+    InvalidTable,
+    IoError,
+    TooManyIterations,
 }
 
 impl ::std::fmt::Debug for Sm9Error {
@@ -54,6 +70,22 @@ impl From<Sm9Error> for &str {
             Sm9Error::InvalidSecretKey => "invalid secret key",
             Sm9Error::KdfHashError => "KDF hash error",
             Sm9Error::InvalidDigest => "invalid signature digest",
+            Sm9Error::InvalidPassword => "wrong password or corrupted key data",
+            Sm9Error::InvalidLength => "invalid input length",
+            Sm9Error::InvalidHid => "invalid hid, custom values must not be 0x00 to 0x03",
+            Sm9Error::InvalidGt => "invalid GT element",
+            Sm9Error::InvalidEncoding => "malformed hex, base64 or PEM encoding",
+            Sm9Error::NotARecipient => "the identity is not a recipient of the message",
+            Sm9Error::DuplicateRecipient => "an identity is listed twice as a recipient",
+            Sm9Error::InvalidIdentity => "malformed hierarchical identity",
+            Sm9Error::OutsideDomain => "the identity is outside the delegated domain",
+            Sm9Error::InvalidPeriod => "invalid validity period, expected yyyymm",
+            Sm9Error::Expired => "the validity period is over",
+            Sm9Error::NonCanonical => "non-canonical encoding, the value is not below its modulus",
+            Sm9Error::InvalidKeyLength => "the KDF output length must be in [1, (2^32 - 1) * 32] bytes",
+            Sm9Error::InvalidTable => "malformed or corrupted precomputed table",
+            Sm9Error::IoError => "failed to read or write a file",
+            Sm9Error::TooManyIterations => "PBKDF2 iteration count above the accepted maximum",
         }
     }
 }
@@ -80,6 +112,22 @@ impl Display for Sm9Error {
             Sm9Error::InvalidSecretKey => "invalid secret key",
             Sm9Error::KdfHashError => "KDF hash error",
             Sm9Error::InvalidDigest => "invalid signature digest",
+            Sm9Error::InvalidPassword => "wrong password or corrupted key data",
+            Sm9Error::InvalidLength => "invalid input length",
+            Sm9Error::InvalidHid => "invalid hid, custom values must not be 0x00 to 0x03",
+            Sm9Error::InvalidGt => "invalid GT element",
+            Sm9Error::InvalidEncoding => "malformed hex, base64 or PEM encoding",
+            Sm9Error::NotARecipient => "the identity is not a recipient of the message",
+            Sm9Error::DuplicateRecipient => "an identity is listed twice as a recipient",
+            Sm9Error::InvalidIdentity => "malformed hierarchical identity",
+            Sm9Error::OutsideDomain => "the identity is outside the delegated domain",
+            Sm9Error::InvalidPeriod => "invalid validity period, expected yyyymm",
+            Sm9Error::Expired => "the validity period is over",
+            Sm9Error::NonCanonical => "non-canonical encoding, the value is not below its modulus",
+            Sm9Error::InvalidKeyLength => "the KDF output length must be in [1, (2^32 - 1) * 32] bytes",
+            Sm9Error::InvalidTable => "malformed or corrupted precomputed table",
+            Sm9Error::IoError => "failed to read or write a file",
+            Sm9Error::TooManyIterations => "PBKDF2 iteration count above the accepted maximum",
         };
         write!(f, "{}", err_msg)
     }
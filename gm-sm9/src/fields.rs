@@ -1,9 +1,10 @@
-use crate::u256::{u256_add, u256_cmp, u256_from_be_bytes, u256_mul, u256_sub, SM9_ONE, U256};
+use crate::error::{Sm9Error, Sm9Result};
+use crate::u256::{sm9_random_u256_with, u256_add, u256_cmp, u256_from_be_bytes, u256_mul, u256_sub, SM9_ONE, U256};
 use crate::{
     SM9_N, SM9_N_BARRETT_MU, SM9_N_MINUS_ONE, SM9_N_MINUS_TWO, SM9_N_NEG,
     SM9_U256_N_MINUS_ONE_BARRETT_MU,
 };
-use rand::RngCore;
+use rand::{CryptoRng, RngCore};
 use std::fmt::Debug;
 
 pub mod fp;
@@ -28,19 +29,26 @@ pub trait FieldElement: Sized + Copy + Clone + PartialEq + Eq + Debug {
     fn to_bytes_be(&self) -> Vec<u8>;
 }
 
-#[inline(always)]
+/// A uniform scalar in [1, n-1]
 pub fn fn_random_u256() -> U256 {
-    let mut rng = rand::thread_rng();
-    let mut buf: [u8; 32] = [0; 32];
-    let mut ret;
-    loop {
-        rng.fill_bytes(&mut buf[..]);
-        ret = u256_from_be_bytes(&buf);
-        if ret < SM9_N_MINUS_ONE && ret != [0, 0, 0, 0] {
-            break;
-        }
+    fn_random_with(&mut rand::thread_rng())
+}
+
+/// A uniform scalar in [1, n-1] from `rng`
+pub fn fn_random_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> U256 {
+    sm9_random_u256_with(rng, &SM9_N)
+}
+
+/// Decode a 32-byte big-endian scalar, rejecting values not below n.
+pub fn mod_n_from_bytes(buf: &[u8]) -> Sm9Result<U256> {
+    if buf.len() != 32 {
+        return Err(Sm9Error::InvalidFieldLen);
     }
-    ret
+    let k = u256_from_be_bytes(buf);
+    if u256_cmp(&k, &SM9_N) >= 0 {
+        return Err(Sm9Error::NonCanonical);
+    }
+    Ok(k)
 }
 
 pub fn mod_n_add(a: &U256, b: &U256) -> U256 {
@@ -158,6 +166,29 @@ pub fn mod_n_inv(a: &U256) -> U256 {
     mod_n_pow(a, &SM9_N_MINUS_TWO)
 }
 
+/// Invert every element with a single `mod_n_inv` (Montgomery's trick),
+/// zero elements are left as zero.
+pub fn mod_n_batch_inv(a: &[U256]) -> Vec<U256> {
+    let mut prefix = Vec::with_capacity(a.len());
+    let mut acc = SM9_ONE;
+    for x in a {
+        prefix.push(acc);
+        if !x.is_zero() {
+            acc = mod_n_mul(&acc, x);
+        }
+    }
+    let mut inv = mod_n_inv(&acc);
+    let mut r = vec![[0u64; 4]; a.len()];
+    for i in (0..a.len()).rev() {
+        if a[i].is_zero() {
+            continue;
+        }
+        r[i] = mod_n_mul(&inv, &prefix[i]);
+        inv = mod_n_mul(&inv, &a[i]);
+    }
+    r
+}
+
 pub fn mod_n_from_hash(ha: &[u8]) -> U256 {
     let mut h = SM9_ONE;
     let mut z: [u64; 5] = [0; 5];
@@ -170,13 +201,17 @@ pub fn mod_n_from_hash(ha: &[u8]) -> U256 {
 
     let (sum1, carry1) = r[4].overflowing_add(z[3]);
     r[4] = sum1;
-    let t = z[4] + carry1 as u64;
+    let (t, carry0) = z[4].overflowing_add(carry1 as u64);
     let (sum2, carry2) = r[5].overflowing_add(t);
     r[5] = sum2;
-    r[6] = u64::from(carry2);
+    r[6] = u64::from(carry0) + u64::from(carry2);
 
     r = u256_mul(&[r[5], r[6], 0, 0], &SM9_N_MINUS_ONE);
     h = u256_sub(&[z[0], z[1], z[2], z[3]], &[r[0], r[1], r[2], r[3]]).0;
+    // the Barrett quotient can be short, leaving h in [0, 3(n-1))
+    while u256_cmp(&h, &SM9_N_MINUS_ONE) >= 0 {
+        h = u256_sub(&h, &SM9_N_MINUS_ONE).0;
+    }
     h = mod_n_add(&h, &SM9_ONE);
     h
 }
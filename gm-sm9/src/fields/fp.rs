@@ -1,29 +1,26 @@
-use rand::RngCore;
+use gm_core::limb::ct_equal;
+use rand::{CryptoRng, RngCore};
 
+use crate::error::{Sm9Error, Sm9Result};
 use crate::fields::FieldElement;
 use crate::u256::{
-    u256_add, u256_cmp, u256_from_be_bytes, u256_mul, u256_sub, u256_to_be_bytes, u512_add,
-    SM9_ONE, SM9_ZERO, U256,
+    sm9_random_u256_with, u256_add, u256_cmp, u256_from_be_bytes, u256_mont_mul, u256_sub,
+    u256_to_be_bytes, SM9_ONE, SM9_ZERO, U256,
 };
 use crate::{
-    SM9_MODP_2E512, SM9_MODP_MONT_ONE, SM9_P, SM9_P_MINUS_ONE, SM9_P_MINUS_TWO, SM9_P_PRIME,
+    SM9_MODP_2E512, SM9_MODP_MONT_ONE, SM9_P, SM9_P_MINUS_TWO, SM9_P_PRIME,
 };
 
 pub type Fp = U256;
 
-#[inline(always)]
+/// A uniform field element in [1, p-1], plain
 pub fn fp_random_u256() -> U256 {
-    let mut rng = rand::thread_rng();
-    let mut buf: [u8; 32] = [0; 32];
-    let mut ret;
-    loop {
-        rng.fill_bytes(&mut buf[..]);
-        ret = u256_from_be_bytes(&buf);
-        if u256_cmp(&ret, &SM9_P_MINUS_ONE) < 0 && ret != [0, 0, 0, 0] {
-            break;
-        }
-    }
-    ret
+    fp_random_with(&mut rand::thread_rng())
+}
+
+/// A uniform field element in [1, p-1] from `rng`, plain
+pub fn fp_random_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> U256 {
+    sm9_random_u256_with(rng, &SM9_P)
 }
 
 pub(crate) fn fp_pow(a: &Fp, e: &U256) -> Fp {
@@ -42,6 +39,29 @@ pub(crate) fn fp_pow(a: &Fp, e: &U256) -> Fp {
     r
 }
 
+/// (p - 5) / 8 = 16c80000005474de3ac07569feb1d8e8a43e5269634f5ddb7cadf364fc6a28af
+const SM9_P_MINUS_FIVE_DIV_EIGHT: U256 = [
+    0x7cadf364fc6a28af,
+    0xa43e5269634f5ddb,
+    0x3ac07569feb1d8e8,
+    0x16c80000005474de,
+];
+
+/// Square root by Atkin's method, p = 5 mod 8, `None` for a non-square.
+///
+/// A fixed exponentiation, the time does not depend on `a`.
+pub(crate) fn fp_sqrt(a: &Fp) -> Option<Fp> {
+    let a2 = a.fp_double();
+    let b = fp_pow(&a2, &SM9_P_MINUS_FIVE_DIV_EIGHT);
+    let i = a2.fp_mul(&b.fp_sqr());
+    let r = a.fp_mul(&b).fp_mul(&i.fp_sub(&SM9_MODP_MONT_ONE));
+    if bool::from(ct_equal(&r.fp_sqr(), a)) {
+        Some(r)
+    } else {
+        None
+    }
+}
+
 pub fn fp_to_mont(a: &Fp) -> Fp {
     mont_mul(a, &SM9_MODP_2E512)
 }
@@ -50,44 +70,21 @@ pub fn fp_from_mont(a: &Fp) -> Fp {
     mont_mul(a, &SM9_ONE)
 }
 
-pub(crate) fn fp_from_bytes(buf: &[u8]) -> Fp {
-    let mut t = u256_from_be_bytes(buf);
-    t = fp_to_mont(&t);
-    t
+/// Decode 32 big-endian bytes to Montgomery form, rejecting values not below p.
+pub fn fp_from_bytes(buf: &[u8]) -> Sm9Result<Fp> {
+    if buf.len() != 32 {
+        return Err(Sm9Error::InvalidFieldLen);
+    }
+    let t = u256_from_be_bytes(buf);
+    if u256_cmp(&t, &SM9_P) >= 0 {
+        return Err(Sm9Error::NonCanonical);
+    }
+    Ok(fp_to_mont(&t))
 }
 
 pub fn mont_mul(a: &Fp, b: &Fp) -> Fp {
-    let mut r = [0u64; 4];
-
-    let mut t = [0u64; 8];
-
-    // z = a * b
-    let mut z = u256_mul(a, b);
-
-    // t = low(z) * p'
-    let z_low = [z[0], z[1], z[2], z[3]];
-    let t1 = u256_mul(&z_low, &SM9_P_PRIME);
-    t[0] = t1[0];
-    t[1] = t1[1];
-    t[2] = t1[2];
-    t[3] = t1[3];
-
-    // t = low(t) * p
-    let t_low = [t[0], t[1], t[2], t[3]];
-    t = u256_mul(&t_low, &SM9_P);
-
-    // z = z + t
-    let (sum, c) = u512_add(&z, &t);
-    z = sum;
-
-    // r = high(r)
-    r = [z[4], z[5], z[6], z[7]];
-    if c {
-        r = u256_add(&r, &SM9_MODP_MONT_ONE).0;
-    } else if u256_cmp(&r, &SM9_P) >= 0 {
-        r = u256_sub(&r, &SM9_P).0
-    }
-    r
+    // SM9_P_PRIME[0] = -p^-1 mod 2^64
+    u256_mont_mul(a, b, &SM9_P, SM9_P_PRIME[0])
 }
 
 impl FieldElement for Fp {
@@ -255,4 +252,34 @@ mod test_mod_operation {
         r.reverse();
         println!("fp_inv ={:x?}", r);
     }
+
+    #[test]
+    fn test_canonical_decoding() {
+        use crate::error::Sm9Error;
+        use crate::fields::fp::fp_from_bytes;
+        use crate::fields::mod_n_from_bytes;
+        use crate::signature::Sm9Signature;
+        use crate::u256::{u256_sub, u256_to_be_bytes, SM9_ONE};
+        use crate::{SM9_N, SM9_P};
+
+        let p = u256_to_be_bytes(&SM9_P);
+        let p_1 = u256_sub(&SM9_P, &SM9_ONE).0;
+        assert_eq!(p_1, fp_from_mont(&fp_from_bytes(&u256_to_be_bytes(&p_1)).unwrap()));
+        assert!(fp_from_bytes(&p) == Err(Sm9Error::NonCanonical));
+        assert!(fp_from_bytes(&[0xff; 32]) == Err(Sm9Error::NonCanonical));
+        assert!(fp_from_bytes(&p[1..]) == Err(Sm9Error::InvalidFieldLen));
+
+        let n = u256_to_be_bytes(&SM9_N);
+        assert!(mod_n_from_bytes(&n) == Err(Sm9Error::NonCanonical));
+        let n_1 = u256_sub(&SM9_N, &SM9_ONE).0;
+        assert!(mod_n_from_bytes(&u256_to_be_bytes(&n_1)) == Ok(n_1));
+
+        // h = n and a coordinate of S equal to p are both rejected
+        let mut sig = [&n[..], &[0x04], &[1; 64]].concat();
+        assert!(Sm9Signature::from_bytes(&sig).err() == Some(Sm9Error::NonCanonical));
+        sig[..32].copy_from_slice(&[0; 32]);
+        sig[31] = 1;
+        sig[33..65].copy_from_slice(&p);
+        assert!(Sm9Signature::from_bytes(&sig).err() == Some(Sm9Error::NonCanonical));
+    }
 }
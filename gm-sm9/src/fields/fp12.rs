@@ -1,13 +1,16 @@
-use crate::fields::fp::Fp;
 use crate::fields::fp2::Fp2;
 use crate::fields::fp4::Fp4;
 use crate::fields::FieldElement;
+use crate::fields::fp::fp_from_bytes;
 use crate::u256::{u256_cmp, U256};
 use crate::{
     SM9_MONT_ALPHA1, SM9_MONT_ALPHA2, SM9_MONT_ALPHA3, SM9_MONT_ALPHA4, SM9_MONT_ALPHA5,
     SM9_MONT_BETA, SM9_N_MINUS_ONE,
 };
 
+/// The BN parameter of the SM9 curve
+const SM9_T: u64 = 0x600000000058f98a;
+
 #[derive(Debug, Copy, Clone)]
 pub struct Fp12 {
     pub(crate) c0: Fp4,
@@ -77,39 +80,65 @@ impl Fp12 {
         }
     }
 
+    /// f^((p^4 - p^2 + 1) / n) with the BN parameter t, p = 36t^4 + 36t^3 + 24t^2 + 6t + 1,
+    /// following Scott et al., "On the final exponentiation for calculating
+    /// pairings on ordinary elliptic curves": three exponentiations by t, a
+    /// few Frobenius maps and the addition chain for
+    /// y0 * y1^2 * y2^6 * y3^12 * y4^18 * y5^30 * y6^36.
+    ///
+    /// f must be in the cyclotomic subgroup, i.e. the easy part was applied,
+    /// so inverses are conjugates and squarings are cyclotomic squarings.
     pub(crate) fn final_exponent_hard_part(&self) -> Fp12 {
-        // a2 = 0xd8000000019062ed0000b98b0cb27659
-        // a3 = 0x2400000000215d941
-        let a2: Fp = [0x0000b98b0cb27659, 0xd8000000019062ed, 0, 0];
-        let a3: Fp = [0x400000000215d941, 0x2, 0, 0];
-        let nine: Fp = [9, 0, 0, 0];
-
-        let mut t0 = self.pow(&a3);
-        t0 = t0.fp_inv();
-        let mut t1 = t0.fp12_frobenius();
-        t1 = t0.fp_mul(&t1);
-
-        t0 = t0.fp_mul(&t1);
-        let mut t2 = self.fp12_frobenius();
-        let mut t3 = t2.fp_mul(self);
-        t3 = t3.pow(&nine);
-
-        t0 = t0.fp_mul(&t3);
-        t3 = self.fp_sqr();
-        t3 = t3.fp_sqr();
-        t0 = t0.fp_mul(&t3);
-        t2 = t2.fp_sqr();
-        t2 = t2.fp_mul(&t1);
-
-        t1 = self.fp12_frobenius2();
-        t1 = t1.fp_mul(&t2);
+        let ft = self.cyclotomic_pow(SM9_T);
+        let ft2 = ft.cyclotomic_pow(SM9_T);
+        let ft3 = ft2.cyclotomic_pow(SM9_T);
+
+        let y0 = self
+            .fp12_frobenius()
+            .fp_mul(&self.fp12_frobenius2())
+            .fp_mul(&self.fp12_frobenius3());
+        let y1 = self.fp12_frobenius6();
+        let y2 = ft2.fp12_frobenius2();
+        let y3 = ft.fp12_frobenius().fp12_frobenius6();
+        let y4 = ft.fp_mul(&ft2.fp12_frobenius()).fp12_frobenius6();
+        let y5 = ft2.fp12_frobenius6();
+        let y6 = ft3.fp_mul(&ft3.fp12_frobenius()).fp12_frobenius6();
+
+        let mut t0 = y6.cyclotomic_sqr().fp_mul(&y4).fp_mul(&y5);
+        let mut t1 = y3.fp_mul(&y5).fp_mul(&t0);
+        t0 = t0.fp_mul(&y2);
+        t1 = t1.cyclotomic_sqr().fp_mul(&t0).cyclotomic_sqr();
+        t0 = t1.fp_mul(&y1);
+        t1 = t1.fp_mul(&y0);
+        t0.cyclotomic_sqr().fp_mul(&t1)
+    }
 
-        t2 = t1.pow(&a2);
-        t0 = t2.fp_mul(&t0);
-        t1 = self.fp12_frobenius3();
-        t1 = t1.fp_mul(&t0);
+    /// Granger-Scott squaring, x = a + b·w + c·w^2 over Fp4 with w^3 = v:
+    /// x^2 = (3a^2 - 2ā) + (3v·c^2 + 2b̄)·w + (3b^2 - 2c̄)·w^2,
+    /// only valid for x in the cyclotomic subgroup.
+    pub(crate) fn cyclotomic_sqr(&self) -> Fp12 {
+        let (a, b, c) = (self.c0, self.c1, self.c2);
+        let a2 = a.fp_sqr();
+        let b2 = b.fp_sqr();
+        let c2v = c.sqr_v();
+        Fp12 {
+            c0: a2.fp_triple().fp_sub(&a.conjugate().fp_double()),
+            c1: c2v.fp_triple().fp_add(&b.conjugate().fp_double()),
+            c2: b2.fp_triple().fp_sub(&c.conjugate().fp_double()),
+        }
+    }
 
-        t1
+    /// self^e by square and multiply from the top set bit of e, self must be
+    /// in the cyclotomic subgroup.
+    pub(crate) fn cyclotomic_pow(&self, e: u64) -> Fp12 {
+        let mut r = Fp12::one();
+        for i in (0..64 - e.leading_zeros()).rev() {
+            r = r.cyclotomic_sqr();
+            if (e >> i) & 1 == 1 {
+                r = r.fp_mul(self);
+            }
+        }
+        r
     }
 
     pub(crate) fn final_exponent(&self) -> Fp12 {
@@ -392,6 +421,32 @@ impl FieldElement for Fp12 {
 }
 
 impl Fp12 {
+    /// Inverse of `to_bytes_be`, 384 bytes, every coordinate must be below p.
+    pub(crate) fn from_bytes_be(b: &[u8]) -> Option<Fp12> {
+        if b.len() != 384 {
+            return None;
+        }
+        let mut fp = [[0u64; 4]; 12];
+        for (i, chunk) in b.chunks(32).enumerate() {
+            fp[i] = fp_from_bytes(chunk).ok()?;
+        }
+        let fp4 = |i: usize| Fp4 {
+            c1: Fp2 {
+                c1: fp[i],
+                c0: fp[i + 1],
+            },
+            c0: Fp2 {
+                c1: fp[i + 2],
+                c0: fp[i + 3],
+            },
+        };
+        Some(Fp12 {
+            c2: fp4(0),
+            c1: fp4(4),
+            c0: fp4(8),
+        })
+    }
+
     pub(crate) fn pow(&self, e: &U256) -> Self {
         assert!(u256_cmp(e, &SM9_N_MINUS_ONE) < 0);
         let mut w = 0_u64;
@@ -414,3 +469,61 @@ impl Fp12 {
         t
     }
 }
+
+#[cfg(test)]
+mod test_fp12 {
+    use crate::fields::fp::Fp;
+    use crate::fields::fp12::{Fp12, SM9_T};
+    use crate::fields::FieldElement;
+
+    /// The previous hard part, exponentiations by 6t + 5 and 6t^2 + 1
+    fn hard_part_reference(f: &Fp12) -> Fp12 {
+        // a2 = 0xd8000000019062ed0000b98b0cb27659
+        // a3 = 0x2400000000215d941
+        let a2: Fp = [0x0000b98b0cb27659, 0xd8000000019062ed, 0, 0];
+        let a3: Fp = [0x400000000215d941, 0x2, 0, 0];
+        let nine: Fp = [9, 0, 0, 0];
+
+        let mut t0 = f.pow(&a3);
+        t0 = t0.fp_inv();
+        let mut t1 = t0.fp12_frobenius();
+        t1 = t0.fp_mul(&t1);
+
+        t0 = t0.fp_mul(&t1);
+        let mut t2 = f.fp12_frobenius();
+        let mut t3 = t2.fp_mul(f);
+        t3 = t3.pow(&nine);
+
+        t0 = t0.fp_mul(&t3);
+        t3 = f.fp_sqr();
+        t3 = t3.fp_sqr();
+        t0 = t0.fp_mul(&t3);
+        t2 = t2.fp_sqr();
+        t2 = t2.fp_mul(&t1);
+
+        t1 = f.fp12_frobenius2();
+        t1 = t1.fp_mul(&t2);
+
+        t2 = t1.pow(&a2);
+        t0 = t2.fp_mul(&t0);
+        t1 = f.fp12_frobenius3();
+        t1 = t1.fp_mul(&t0);
+
+        t1
+    }
+
+
+    #[test]
+    fn test_cyclotomic() {
+        let bytes: Vec<u8> = (0..384u32).map(|i| ((i * 37 + 11) % 251) as u8 & 0x3f).collect();
+        let f = Fp12::from_bytes_be(&bytes).unwrap();
+        // easy part, f^((p^6 - 1)(p^2 + 1))
+        let mut easy = f.fp12_frobenius6().fp_mul(&f.fp_inv());
+        easy = easy.fp12_frobenius2().fp_mul(&easy);
+
+        assert_eq!(easy.fp_sqr(), easy.cyclotomic_sqr());
+        assert_eq!(easy.pow(&[SM9_T, 0, 0, 0]), easy.cyclotomic_pow(SM9_T));
+        assert_eq!(hard_part_reference(&easy), easy.final_exponent_hard_part());
+        assert_eq!(f.final_exponent(), hard_part_reference(&easy));
+    }
+}
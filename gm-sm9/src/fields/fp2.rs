@@ -1,4 +1,4 @@
-use crate::fields::fp::{fp_from_hex, Fp};
+use crate::fields::fp::{fp_from_hex, fp_from_mont, fp_sqrt, Fp};
 use crate::fields::FieldElement;
 
 #[derive(Debug, Copy, Clone)]
@@ -143,7 +143,7 @@ impl FieldElement for Fp2 {
             // r0 = 0
             // r1 = -(2 * a1)^-1
             r1 = self.c1.fp_double();
-            r1 = self.c1.fp_inv();
+            r1 = r1.fp_inv();
             r1 = r1.fp_neg();
         } else if self.c1.is_zero() {
             // r1 = 0
@@ -256,6 +256,43 @@ impl Fp2 {
         r
     }
 
+    /// Square root by the complex method, u^2 = -2, `None` for a non-square.
+    ///
+    /// With the norm `n = a0^2 + 2 * a1^2`, a root `x0 + x1 * u` has
+    /// `x0^2 = (a0 +- sqrt(n)) / 2` and `x1 = a1 / (2 * x0)`. Not constant
+    /// time, only used on public points.
+    pub(crate) fn sqrt(&self) -> Option<Fp2> {
+        if self.c1.is_zero() {
+            // a0 itself, or -2 * x1^2 for an a0 that is not a square in Fp
+            if let Some(x0) = fp_sqrt(&self.c0) {
+                return Some(Fp2 { c0: x0, c1: Fp::zero() });
+            }
+            let x1 = fp_sqrt(&self.c0.fp_neg().fp_div2())?;
+            return Some(Fp2 { c0: Fp::zero(), c1: x1 });
+        }
+        let n = self.c0.fp_sqr().fp_add(&self.c1.fp_sqr().fp_double());
+        let alpha = fp_sqrt(&n)?;
+        let x0 = match fp_sqrt(&self.c0.fp_add(&alpha).fp_div2()) {
+            Some(x0) => x0,
+            None => fp_sqrt(&self.c0.fp_sub(&alpha).fp_div2())?,
+        };
+        let x1 = self.c1.fp_mul(&x0.fp_double().fp_inv());
+        let r = Fp2 { c0: x0, c1: x1 };
+        if r.fp_sqr() == *self {
+            Some(r)
+        } else {
+            None
+        }
+    }
+
+    /// The sign of RFC 9380: the parity of c0, or of c1 when c0 is zero
+    pub(crate) fn sgn0(&self) -> u8 {
+        let c0 = fp_from_mont(&self.c0);
+        let c1 = fp_from_mont(&self.c1);
+        let zero0 = self.c0.is_zero() as u8;
+        (c0[0] as u8 & 1) | (zero0 & (c1[0] as u8 & 1))
+    }
+
     pub fn from_hex(hex: [&str; 2]) -> Fp2 {
         Fp2 {
             c0: fp_from_hex(hex[0]),
@@ -266,10 +303,47 @@ impl Fp2 {
 
 #[cfg(test)]
 mod test_mod_operation {
-    use crate::fields::fp::{fp_from_mont, fp_to_mont};
+    use crate::fields::fp::{fp_from_mont, fp_random_u256, fp_to_mont, Fp};
     use crate::fields::fp2::Fp2;
     use crate::fields::FieldElement;
 
+    #[test]
+    fn test_sqrt() {
+        let random = || Fp2 {
+            c0: fp_to_mont(&fp_random_u256()),
+            c1: fp_to_mont(&fp_random_u256()),
+        };
+        let mut non_squares = 0;
+        for i in 0..32 {
+            let mut a = random();
+            // c1 = 0 with c0 a square or not in Fp, both are squares in Fp2
+            if i % 8 == 0 {
+                a.c1 = Fp::zero();
+            }
+            let r = a.fp_sqr().sqrt().unwrap();
+            assert!(r == a || r == a.fp_neg());
+            match a.sqrt() {
+                Some(r) => assert_eq!(a, r.fp_sqr()),
+                None => non_squares += 1,
+            }
+        }
+        assert!(non_squares > 0 && non_squares < 32);
+        assert_eq!(Some(Fp2::zero()), Fp2::zero().sqrt());
+
+        // sgn0 is the parity of c0 unless c0 is zero
+        let one = Fp2::one();
+        assert_eq!(1, one.sgn0());
+        assert_eq!(0, one.fp_double().sgn0());
+        let u = Fp2 { c0: Fp::zero(), c1: one.c0 };
+        assert_eq!(1, u.sgn0());
+        assert_eq!(1, a_or_neg_sgn0(&random()));
+    }
+
+    /// exactly one of a, -a has sgn0 = 1
+    fn a_or_neg_sgn0(a: &Fp2) -> u8 {
+        a.sgn0() ^ a.fp_neg().sgn0()
+    }
+
     #[test]
     fn test_mod_op() {
         let mut a: Fp2 = Fp2 {
@@ -315,4 +389,14 @@ mod test_mod_operation {
         r.c1.reverse();
         println!("fp_add ={:x?}", r); // [1b6ac9eb2c47b62c, f61608b26c3c7e20, 674a48c4c509ac13, bbaf6d47d32c07c], c1: [74a3145c65ac54, 7541612178e584a9, 2248740e70606dc, aaafe2bcbd2f6a21]
     }
+
+    /// a1·u with a0 = 0 is inverted by -(2·a1)^-1·u, as u^2 = -2
+    #[test]
+    fn test_inv_pure_imaginary() {
+        let a = Fp2 {
+            c0: [0; 4],
+            c1: fp_to_mont(&[0x1234, 0x5678, 0x9abc, 0xdef0]),
+        };
+        assert_eq!(Fp2::one(), a.fp_mul(&a.fp_inv()));
+    }
 }
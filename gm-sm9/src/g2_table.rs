@@ -0,0 +1,194 @@
+//! The fixed-base table of the G2 generator `P2`, kept in a file between
+//! runs.
+//!
+//! [`TwistPoint::g_mul`] reads `[k]P2` from a [`FixedBase`] table of 52 × 16
+//! points, about 107 KB encoded, which the first call builds. A server that
+//! restarts often can write the table once with [`G2Table::write_to`] and
+//! install it at startup with [`G2Table::read_from`] and
+//! [`G2Table::install`]. The file is read into memory, there is no
+//! memory mapping. The SM2 generator and `P1` of SM9 need nothing of the kind,
+//! their tables are compiled in.
+//!
+//! File layout: [`MAGIC`], the row count as a big-endian `u32`, the points as
+//! `0x04 || x || y` row by row, and SM3 of everything before it. Loading
+//! checks the digest, that every point is on the twist, that the first row
+//! starts at `P2`, that each row starts at `[32]` times the start of the
+//! previous one and that each other entry is the one before it plus the start
+//! of its row. A table that loads is the table of `P2`, whoever wrote the
+//! file; the digest only reports corruption before the points are decoded.
+//!
+//! # Example
+//! ```rust
+//! use gm_sm9::g2_table::G2Table;
+//! use gm_sm9::points::TwistPoint;
+//!
+//! let path = std::env::temp_dir().join(format!("sm9-g2-doc-{}.bin", std::process::id()));
+//! G2Table::new().write_to(&path).unwrap();
+//! // at startup, before the first G2 scalar multiplication
+//! let table = G2Table::read_from(&path).unwrap();
+//! let k = [5, 0, 0, 0];
+//! assert_eq!(table.mul(&k).to_bytes_be(), TwistPoint::g_mul(&k).to_bytes_be());
+//! table.install();
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+use std::path::Path;
+use std::sync::OnceLock;
+
+use gm_core::group::{FixedBase, WINDOW};
+use gm_sm3::sm3_hash;
+
+use crate::error::{Sm9Error, Sm9Result};
+use crate::points::{TwistPoint, SM9_U256_MONT_G2};
+use crate::u256::U256;
+
+pub const MAGIC: &[u8; 8] = b"SM9G2TB1";
+
+const ROWS: usize = 256 / WINDOW + 1;
+const COLUMNS: usize = 1 << (WINDOW - 1);
+const POINT_LEN: usize = 129;
+
+static G2_TABLE: OnceLock<G2Table> = OnceLock::new();
+
+/// `[k]P2` by table lookups, one addition per window
+#[derive(Clone)]
+pub struct G2Table(FixedBase<TwistPoint>);
+
+impl Default for G2Table {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl G2Table {
+    /// Build the table, a few thousand G2 operations
+    pub fn new() -> G2Table {
+        G2Table(FixedBase::new(&SM9_U256_MONT_G2, 4))
+    }
+
+    pub fn mul(&self, k: &U256) -> TwistPoint {
+        self.0.mul(k)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let rows = self.0.rows();
+        let mut out = Vec::with_capacity(MAGIC.len() + 4 + rows.len() * COLUMNS * POINT_LEN + 32);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&(rows.len() as u32).to_be_bytes());
+        for p in rows.iter().flatten() {
+            out.extend_from_slice(&p.to_bytes_be());
+        }
+        let digest = sm3_hash(&out);
+        out.extend_from_slice(&digest);
+        out
+    }
+
+    /// Fails with [`Sm9Error::InvalidTable`] for a table of another layout,
+    /// a wrong digest or points that fail the checks above.
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<G2Table> {
+        let body_len = MAGIC.len() + 4 + ROWS * COLUMNS * POINT_LEN;
+        if b.len() != body_len + 32 || &b[..MAGIC.len()] != MAGIC || b[8..12] != (ROWS as u32).to_be_bytes() {
+            return Err(Sm9Error::InvalidTable);
+        }
+        let (body, digest) = b.split_at(body_len);
+        if sm3_hash(body) != digest {
+            return Err(Sm9Error::InvalidTable);
+        }
+        let mut rows = Vec::with_capacity(ROWS);
+        let mut expected = SM9_U256_MONT_G2;
+        for chunk in body[12..].chunks(COLUMNS * POINT_LEN) {
+            let mut row = [TwistPoint::zero(); COLUMNS];
+            for (p, enc) in row.iter_mut().zip(chunk.chunks(POINT_LEN)) {
+                if enc[0] != 0x04 {
+                    return Err(Sm9Error::InvalidTable);
+                }
+                *p = TwistPoint::from_bytes(enc).map_err(|_| Sm9Error::InvalidTable)?;
+                if !p.is_on_curve() {
+                    return Err(Sm9Error::InvalidTable);
+                }
+            }
+            if !row[0].point_equals(&expected) {
+                return Err(Sm9Error::InvalidTable);
+            }
+            // row j holds [j + 1] times row 0
+            if (1..COLUMNS).any(|j| !row[j].point_equals(&row[j - 1].point_add(&row[0]))) {
+                return Err(Sm9Error::InvalidTable);
+            }
+            expected = (0..WINDOW).fold(row[0], |p, _| p.point_double());
+            rows.push(row);
+        }
+        Ok(G2Table(FixedBase::from_rows(rows)))
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> Sm9Result<()> {
+        std::fs::write(path, self.to_bytes()).map_err(|_| Sm9Error::IoError)
+    }
+
+    pub fn read_from<P: AsRef<Path>>(path: P) -> Sm9Result<G2Table> {
+        let b = std::fs::read(path).map_err(|_| Sm9Error::IoError)?;
+        Self::from_bytes(&b)
+    }
+
+    /// Use this table for [`TwistPoint::g_mul`]. Returns false, and changes
+    /// nothing, once a table is in use.
+    pub fn install(self) -> bool {
+        G2_TABLE.set(self).is_ok()
+    }
+}
+
+pub(crate) fn g2_table() -> &'static G2Table {
+    G2_TABLE.get_or_init(G2Table::new)
+}
+
+#[cfg(test)]
+mod test_g2_table {
+    use super::*;
+    use crate::fields::fn_random_u256;
+
+    #[test]
+    fn test_g2_table() {
+        let table = G2Table::new();
+        for _ in 0..4 {
+            let k = fn_random_u256();
+            assert_eq!(table.mul(&k).to_bytes_be(), SM9_U256_MONT_G2.point_mul(&k).to_bytes_be());
+        }
+        assert!(table.mul(&[0; 4]).is_zero());
+
+        let b = table.to_bytes();
+        assert_eq!(MAGIC.len() + 4 + ROWS * COLUMNS * POINT_LEN + 32, b.len());
+        let loaded = G2Table::from_bytes(&b).unwrap();
+        let k = fn_random_u256();
+        assert_eq!(loaded.mul(&k).to_bytes_be(), table.mul(&k).to_bytes_be());
+
+        let mut bad = b.clone();
+        bad[1000] ^= 1;
+        assert_eq!(Err(Sm9Error::InvalidTable), G2Table::from_bytes(&bad).map(|_| ()));
+        assert!(G2Table::from_bytes(&b[..b.len() - 1]).is_err());
+        // a consistent digest over the wrong base point
+        let mut moved = b[..b.len() - 32].to_vec();
+        let (first, second) = (12..12 + POINT_LEN, 12 + POINT_LEN..12 + 2 * POINT_LEN);
+        let p2 = moved[second].to_vec();
+        moved[first].copy_from_slice(&p2);
+        let digest = sm3_hash(&moved);
+        moved.extend_from_slice(&digest);
+        assert!(G2Table::from_bytes(&moved).is_err());
+        // a consistent digest over a wrong entry inside a row
+        let mut swapped = b[..b.len() - 32].to_vec();
+        let (second, third) = (12 + POINT_LEN..12 + 2 * POINT_LEN, 12 + 2 * POINT_LEN..12 + 3 * POINT_LEN);
+        let p3 = swapped[third].to_vec();
+        swapped[second].copy_from_slice(&p3);
+        let digest = sm3_hash(&swapped);
+        swapped.extend_from_slice(&digest);
+        assert_eq!(Err(Sm9Error::InvalidTable), G2Table::from_bytes(&swapped).map(|_| ()));
+    }
+
+    #[test]
+    fn test_g2_table_file() {
+        let path = std::env::temp_dir().join(format!("gm-sm9-g2-table-{}.bin", std::process::id()));
+        G2Table::new().write_to(&path).unwrap();
+        let table = G2Table::read_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let k = fn_random_u256();
+        assert_eq!(table.mul(&k).to_bytes_be(), TwistPoint::g_mul(&k).to_bytes_be());
+        assert_eq!(Err(Sm9Error::IoError), G2Table::read_from(&path).map(|_| ()));
+    }
+}
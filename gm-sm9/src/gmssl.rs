@@ -0,0 +1,186 @@
+//! The SM9 ciphertext as GmSSL reads and writes it.
+//!
+//! ```text
+//! SM9Cipher ::= SEQUENCE {
+//!     EnType     INTEGER,      -- 0 for XOR, 2 for SM4-CBC
+//!     C1         BIT STRING,   -- 0x04 || x || y
+//!     C3         OCTET STRING, -- HMAC-SM3(K2, C2)
+//!     CipherText OCTET STRING  -- C2
+//! }
+//! ```
+//!
+//! The raw form of this crate is `C1 || C3 || C2` with the same KDF and MAC,
+//! so the XOR variant only differs in the framing. With SM4-CBC the KDF draws
+//! a 16-byte `K1`, the message is PKCS#7 padded and the IV is all zeros,
+//! since `K1` is used once.
+use gm_core::ct_eq;
+use gm_sm4::{CipherMode, Sm4CipherMode};
+
+use crate::error::{Sm9Error, Sm9Result};
+use crate::fields::{fn_random_u256, FieldElement};
+use crate::key::{kdf, sm3_hmac, sm9_u256_hash1, Hid, Sm9EncKey, Sm9EncMasterKey};
+use crate::points::{sm9_u256_pairing, Point};
+use crate::{SM9_POINT_MONT_P1, SM9_TWIST_POINT_MONT_P2};
+
+const C1_LEN: usize = 65;
+const C3_LEN: usize = 32;
+const SM4_KEY_LEN: usize = 16;
+
+/// How C2 is computed from the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sm9EncType {
+    Xor,
+    Sm4Cbc,
+}
+
+impl Sm9EncType {
+    /// The `EnType` of the DER form
+    pub const fn value(self) -> u8 {
+        match self {
+            Sm9EncType::Xor => 0,
+            Sm9EncType::Sm4Cbc => 2,
+        }
+    }
+
+    pub fn from_value(v: u8) -> Sm9Result<Sm9EncType> {
+        match v {
+            0 => Ok(Sm9EncType::Xor),
+            2 => Ok(Sm9EncType::Sm4Cbc),
+            _ => Err(Sm9Error::InvalidDer),
+        }
+    }
+}
+
+/// Frame a raw `C1 || C3 || C2` ciphertext as GmSSL's `SM9Cipher`.
+pub fn to_gmssl_der(raw: &[u8], enc_type: Sm9EncType) -> Sm9Result<Vec<u8>> {
+    if raw.len() < C1_LEN + C3_LEN {
+        return Err(Sm9Error::InvalidLength);
+    }
+    let (c1, rest) = raw.split_at(C1_LEN);
+    let (c3, c2) = rest.split_at(C3_LEN);
+    Ok(yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer.next().write_u8(enc_type.value());
+            writer.next().write_bitvec_bytes(c1, C1_LEN * 8);
+            writer.next().write_bytes(c3);
+            writer.next().write_bytes(c2);
+        });
+    }))
+}
+
+/// The `EnType` and the raw `C1 || C3 || C2` of a GmSSL `SM9Cipher`
+pub fn from_gmssl_der(der: &[u8]) -> Sm9Result<(Sm9EncType, Vec<u8>)> {
+    let (enc_type, c1, c3, c2) = yasna::parse_der(der, |reader| {
+        reader.read_sequence(|reader| {
+            let enc_type = reader.next().read_u8()?;
+            let (c1, bits) = reader.next().read_bitvec_bytes()?;
+            let c3 = reader.next().read_bytes()?;
+            let c2 = reader.next().read_bytes()?;
+            if bits != C1_LEN * 8 || c1.len() != C1_LEN || c3.len() != C3_LEN {
+                return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+            }
+            Ok((enc_type, c1, c3, c2))
+        })
+    })
+    .map_err(|_| Sm9Error::InvalidDer)?;
+    Ok((Sm9EncType::from_value(enc_type)?, [c1, c3, c2].concat()))
+}
+
+/// `K = KDF(C1 || w || ID, 16 + 32)` for a fresh `r`, with `C1` encoded
+fn encapsulate(ppube: &Point, id: &[u8]) -> Sm9Result<(Vec<u8>, Vec<u8>)> {
+    loop {
+        let r = fn_random_u256();
+        let q = SM9_POINT_MONT_P1
+            .point_mul(&sm9_u256_hash1(id, Hid::Enc.value()))
+            .point_add(ppube);
+        let c1 = q.point_mul(&r).to_bytes_be();
+        let w = sm9_u256_pairing(&SM9_TWIST_POINT_MONT_P2, ppube).pow(&r);
+        let k = kdf(&[&c1[1..], &w.to_bytes_be(), id].concat(), SM4_KEY_LEN + 32)?;
+        if k[..SM4_KEY_LEN].iter().any(|&b| b != 0) {
+            return Ok((c1, k));
+        }
+    }
+}
+
+fn sm4_cbc(k1: &[u8]) -> Sm9Result<Sm4CipherMode> {
+    Sm4CipherMode::new(k1, CipherMode::Cbc).map_err(|_| Sm9Error::KdfHashError)
+}
+
+impl Sm9EncMasterKey {
+    /// Encrypt to `id` in the DER form GmSSL reads.
+    pub fn encrypt_gmssl(&self, id: &[u8], data: &[u8], enc_type: Sm9EncType) -> Sm9Result<Vec<u8>> {
+        let raw = match enc_type {
+            Sm9EncType::Xor => self.encrypt(id, data)?,
+            Sm9EncType::Sm4Cbc => {
+                let (c1, k) = encapsulate(&self.ppube, id)?;
+                let (k1, k2) = k.split_at(SM4_KEY_LEN);
+                let c2 = sm4_cbc(k1)?
+                    .encrypt(data, &[0; 16])
+                    .map_err(|_| Sm9Error::InvalidLength)?;
+                [c1, sm3_hmac(k2, &c2, 32), c2].concat()
+            }
+        };
+        to_gmssl_der(&raw, enc_type)
+    }
+}
+
+impl Sm9EncKey {
+    /// Decrypt the DER form GmSSL writes, either `EnType`.
+    pub fn decrypt_gmssl(&self, id: &[u8], der: &[u8]) -> Sm9Result<Vec<u8>> {
+        let (enc_type, raw) = from_gmssl_der(der)?;
+        if enc_type == Sm9EncType::Xor {
+            return self.decrypt(id, &raw);
+        }
+        let (c1_bytes, rest) = raw.split_at(C1_LEN);
+        let (c3, c2) = rest.split_at(C3_LEN);
+        let c1 = Point::from_bytes(c1_bytes)?;
+        if c1_bytes[0] != 0x04 || !c1.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
+        }
+        let w = sm9_u256_pairing(&self.de, &c1);
+        let k = kdf(&[&c1_bytes[1..], &w.to_bytes_be(), id].concat(), SM4_KEY_LEN + 32)?;
+        let (k1, k2) = k.split_at(SM4_KEY_LEN);
+        if !ct_eq(&sm3_hmac(k2, c2, 32), c3) {
+            return Err(Sm9Error::InvalidDigest);
+        }
+        sm4_cbc(k1)?
+            .decrypt(c2, &[0; 16])
+            .map_err(|_| Sm9Error::InvalidDigest)
+    }
+}
+
+#[cfg(test)]
+mod test_gmssl {
+    use super::*;
+
+    #[test]
+    fn test_gmssl_der() {
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Bob").unwrap();
+        for enc_type in [Sm9EncType::Xor, Sm9EncType::Sm4Cbc] {
+            let der = msk.encrypt_gmssl(b"Bob", b"Chinese IBE standard", enc_type).unwrap();
+            // SEQUENCE { INTEGER, BIT STRING of 65 bytes, OCTET STRING of 32 bytes, ... }
+            let h = if der[1] < 0x80 { 2 } else { 2 + (der[1] & 0x7f) as usize };
+            assert_eq!([0x02, 0x01, enc_type.value(), 0x03, 0x42, 0x00, 0x04], der[h..h + 7]);
+            assert_eq!([0x04, 0x20], der[h + 71..h + 73]);
+            assert_eq!(b"Chinese IBE standard".to_vec(), key.decrypt_gmssl(b"Bob", &der).unwrap());
+            assert!(key.decrypt_gmssl(b"Alice", &der).is_err());
+
+            let (t, raw) = from_gmssl_der(&der).unwrap();
+            assert_eq!(enc_type, t);
+            assert_eq!(der, to_gmssl_der(&raw, t).unwrap());
+            for i in (0..der.len()).step_by(5) {
+                let mut b = der.clone();
+                b[i] ^= 1;
+                assert!(key.decrypt_gmssl(b"Bob", &b).is_err());
+            }
+        }
+
+        // the raw form of this crate converts both ways
+        let raw = msk.encrypt(b"Bob", b"abc").unwrap();
+        let der = to_gmssl_der(&raw, Sm9EncType::Xor).unwrap();
+        assert_eq!(b"abc".to_vec(), key.decrypt_gmssl(b"Bob", &der).unwrap());
+        assert!(to_gmssl_der(&raw[..96], Sm9EncType::Xor).is_err());
+        assert!(from_gmssl_der(&der[..der.len() - 1]).is_err());
+    }
+}
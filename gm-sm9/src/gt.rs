@@ -0,0 +1,169 @@
+use crate::error::{Sm9Error, Sm9Result};
+use crate::fields::fp::fp_from_bytes;
+use crate::fields::fp12::Fp12;
+use crate::fields::fp2::Fp2;
+use crate::fields::fp4::Fp4;
+use crate::fields::FieldElement;
+use crate::points::{sm9_u256_pairing, Point, TwistPoint};
+use crate::SM9_N_MINUS_TWO;
+
+/// An element of GT, the order-n subgroup of Fp12* the pairing maps into.
+///
+/// Two encodings are supported:
+///
+/// * 384 bytes, the 12 Fp coordinates from the highest to the lowest as hashed
+///   by H2 and the KDF of SM9
+/// * 192 bytes, the T2 torus compression: with Fp12 = Fp6(w) and x = a + b·w of
+///   norm 1, only c = (1 + a) / b in Fp6 is kept and x = (c + w) / (c - w).
+///   The identity, where b = 0, is encoded as all zeros.
+///
+/// Decoding checks that the element lies in GT.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Gt(pub(crate) Fp12);
+
+impl Gt {
+    /// e(P, Q) for P in G1 and Q in G2
+    pub fn pairing(p: &Point, q: &TwistPoint) -> Gt {
+        Gt(sm9_u256_pairing(q, p))
+    }
+
+    pub fn one() -> Gt {
+        Gt(Fp12::one())
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.0 == Fp12::one()
+    }
+
+    pub fn mul(&self, rhs: &Gt) -> Gt {
+        Gt(self.0.fp_mul(&rhs.0))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Gt> {
+        let x = Fp12::from_bytes_be(b).ok_or(Sm9Error::InvalidGt)?;
+        Gt::checked(x)
+    }
+
+    pub fn to_compressed(&self) -> Vec<u8> {
+        let x = self.0;
+        let y = conjugate(&x);
+        let b = x.fp_sub(&y);
+        if b.is_zero() {
+            return vec![0u8; 192];
+        }
+        // c = (1 + a) / b = (2 + x + conj(x)) * w / (x - conj(x))
+        let two = Fp12::one().fp_double();
+        let c = two.fp_add(&x).fp_add(&y).fp_mul(&w()).fp_mul(&b.fp_inv());
+        let mut out = c.c1.c1.to_bytes_be();
+        out.extend_from_slice(&c.c2.c0.to_bytes_be());
+        out.extend_from_slice(&c.c0.c0.to_bytes_be());
+        out
+    }
+
+    pub fn from_compressed(b: &[u8]) -> Sm9Result<Gt> {
+        if b.len() != 192 {
+            return Err(Sm9Error::InvalidGt);
+        }
+        if b.iter().all(|&x| x == 0) {
+            return Ok(Gt::one());
+        }
+        let c = Fp12 {
+            c0: Fp4 {
+                c0: fp2_from_bytes(&b[128..])?,
+                c1: Fp2::zero(),
+            },
+            c1: Fp4 {
+                c0: Fp2::zero(),
+                c1: fp2_from_bytes(&b[..64])?,
+            },
+            c2: Fp4 {
+                c0: fp2_from_bytes(&b[64..128])?,
+                c1: Fp2::zero(),
+            },
+        };
+        let x = c.fp_add(&w()).fp_mul(&c.fp_sub(&w()).fp_inv());
+        Gt::checked(x)
+    }
+
+    /// x^n = 1, computed as x^(n-2) * x^2 since `pow` takes exponents below n - 1
+    fn checked(x: Fp12) -> Sm9Result<Gt> {
+        if x.is_zero() || !x.pow(&SM9_N_MINUS_TWO).fp_mul(&x.fp_sqr()).eq(&Fp12::one()) {
+            return Err(Sm9Error::InvalidGt);
+        }
+        Ok(Gt(x))
+    }
+}
+
+fn w() -> Fp12 {
+    Fp12 {
+        c0: Fp4::zero(),
+        c1: Fp4::one(),
+        c2: Fp4::zero(),
+    }
+}
+
+/// x^(p^6), negates the odd powers of w, with v = w^3
+fn conjugate(x: &Fp12) -> Fp12 {
+    Fp12 {
+        c0: Fp4 {
+            c0: x.c0.c0,
+            c1: x.c0.c1.fp_neg(),
+        },
+        c1: Fp4 {
+            c0: x.c1.c0.fp_neg(),
+            c1: x.c1.c1,
+        },
+        c2: Fp4 {
+            c0: x.c2.c0,
+            c1: x.c2.c1.fp_neg(),
+        },
+    }
+}
+
+fn fp2_from_bytes(b: &[u8]) -> Sm9Result<Fp2> {
+    let fp = |b: &[u8]| fp_from_bytes(b).map_err(|_| Sm9Error::InvalidGt);
+    Ok(Fp2 {
+        c1: fp(&b[..32])?,
+        c0: fp(&b[32..64])?,
+    })
+}
+
+#[cfg(test)]
+mod test_gt {
+    use crate::gt::Gt;
+    use crate::key::Sm9SignMasterKey;
+    use crate::SM9_POINT_MONT_P1;
+
+    #[test]
+    fn test_gt_encodings() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Alice").unwrap();
+        let g = Gt::pairing(&SM9_POINT_MONT_P1, &key.ppubs);
+        let h = Gt::pairing(key.expose_secret(), &key.ppubs);
+
+        for x in [g, h, g.mul(&h), Gt::one()] {
+            let full = x.to_bytes();
+            let compressed = x.to_compressed();
+            println!("{}", hex::encode(&compressed));
+            assert_eq!(384, full.len());
+            assert_eq!(192, compressed.len());
+            assert_eq!(x, Gt::from_bytes(&full).unwrap());
+            assert_eq!(x, Gt::from_compressed(&compressed).unwrap());
+        }
+        assert!(Gt::from_compressed(&[0u8; 192]).unwrap().is_one());
+
+        // not in GT
+        let mut bad = g.to_bytes();
+        bad[383] ^= 1;
+        assert!(Gt::from_bytes(&bad).is_err());
+        let mut bad = g.to_compressed();
+        bad[191] ^= 1;
+        assert!(Gt::from_compressed(&bad).is_err());
+        assert!(Gt::from_bytes(&[0xffu8; 384]).is_err());
+        assert!(Gt::from_compressed(&g.to_compressed()[1..]).is_err());
+    }
+}
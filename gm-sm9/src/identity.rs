@@ -0,0 +1,219 @@
+//! Hierarchical identities such as `example.com/sales/alice`, and KGCs
+//! delegated to one branch of them.
+//!
+//! SM9 has a single level of keys, so a sub-KGC is the master key wrapped with
+//! a prefix policy: it only extracts keys for identities at or under its
+//! prefix. The key of an identity is the one of its canonical string, so keys
+//! from the root KGC and from any sub-KGC are the same.
+//!
+//! Canonical form: segments joined by `/`, each of 1 to 63 characters among
+//! `a-z 0-9 - . _ @ +`, not `.` or `..`, at most 255 bytes in total. Parsing
+//! trims surrounding whitespace and one leading and trailing `/`, and
+//! lowercases ASCII letters.
+use std::fmt::{Display, Formatter};
+
+use crate::error::{Sm9Error, Sm9Result};
+use crate::key::{Hid, Sm9EncKey, Sm9EncMasterKey, Sm9SignKey, Sm9SignMasterKey};
+
+pub const MAX_IDENTITY_LEN: usize = 255;
+pub const MAX_SEGMENT_LEN: usize = 63;
+
+const SEPARATOR: char = '/';
+
+fn check_segment(segment: &str) -> Sm9Result<()> {
+    let allowed = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || "-._@+".contains(c);
+    if segment.is_empty()
+        || segment.len() > MAX_SEGMENT_LEN
+        || segment == "."
+        || segment == ".."
+        || !segment.chars().all(allowed)
+    {
+        return Err(Sm9Error::InvalidIdentity);
+    }
+    Ok(())
+}
+
+/// A canonical hierarchical identity
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Identity(String);
+
+impl Identity {
+    /// Validate and canonicalize `s`.
+    pub fn parse(s: &str) -> Sm9Result<Identity> {
+        let s = s.trim();
+        let s = s.strip_prefix(SEPARATOR).unwrap_or(s);
+        let s = s.strip_suffix(SEPARATOR).unwrap_or(s).to_ascii_lowercase();
+        if s.len() > MAX_IDENTITY_LEN {
+            return Err(Sm9Error::IdTooLong);
+        }
+        s.split(SEPARATOR).try_for_each(check_segment)?;
+        Ok(Identity(s))
+    }
+
+    /// The identity one level below, `self/segment`
+    pub fn child(&self, segment: &str) -> Sm9Result<Identity> {
+        let segment = segment.to_ascii_lowercase();
+        check_segment(&segment)?;
+        Identity::parse(&format!("{}{}{}", self.0, SEPARATOR, segment))
+    }
+
+    /// The identity one level up, `None` at the top
+    pub fn parent(&self) -> Option<Identity> {
+        self.0
+            .rsplit_once(SEPARATOR)
+            .map(|(parent, _)| Identity(parent.to_string()))
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split(SEPARATOR)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.segments().count()
+    }
+
+    /// Whether `self` is `prefix` or below it, comparing whole segments
+    pub fn is_under(&self, prefix: &Identity) -> bool {
+        match self.0.strip_prefix(&prefix.0) {
+            Some(rest) => rest.is_empty() || rest.starts_with(SEPARATOR),
+            None => false,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The bytes hashed by H1
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl Display for Identity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A KGC restricted to the identities under `prefix`
+#[derive(Clone)]
+pub struct DelegatedKgc<K> {
+    master: K,
+    prefix: Identity,
+}
+
+impl<K: Copy> DelegatedKgc<K> {
+    pub fn prefix(&self) -> &Identity {
+        &self.prefix
+    }
+
+    pub fn allows(&self, id: &Identity) -> bool {
+        id.is_under(&self.prefix)
+    }
+
+    /// A sub-KGC for a branch of this one, it cannot be wider.
+    pub fn delegate(&self, prefix: &Identity) -> Sm9Result<DelegatedKgc<K>> {
+        self.check(prefix)?;
+        Ok(DelegatedKgc {
+            master: self.master,
+            prefix: prefix.clone(),
+        })
+    }
+
+    fn check(&self, id: &Identity) -> Sm9Result<()> {
+        if !self.allows(id) {
+            return Err(Sm9Error::OutsideDomain);
+        }
+        Ok(())
+    }
+}
+
+impl DelegatedKgc<Sm9SignMasterKey> {
+    pub fn extract_key(&self, id: &Identity) -> Sm9Result<Sm9SignKey> {
+        self.check(id)?;
+        self.master.extract_key_with_hid(id.as_bytes(), Hid::Sign)
+    }
+}
+
+impl DelegatedKgc<Sm9EncMasterKey> {
+    pub fn extract_key(&self, id: &Identity) -> Sm9Result<Sm9EncKey> {
+        self.check(id)?;
+        self.master.extract_key_with_hid(id.as_bytes(), Hid::Enc)
+    }
+}
+
+impl Sm9SignMasterKey {
+    /// A sub-KGC issuing signature keys under `prefix` only.
+    pub fn delegate(&self, prefix: &Identity) -> DelegatedKgc<Sm9SignMasterKey> {
+        DelegatedKgc {
+            master: *self,
+            prefix: prefix.clone(),
+        }
+    }
+}
+
+impl Sm9EncMasterKey {
+    /// A sub-KGC issuing encryption keys under `prefix` only.
+    pub fn delegate(&self, prefix: &Identity) -> DelegatedKgc<Sm9EncMasterKey> {
+        DelegatedKgc {
+            master: *self,
+            prefix: prefix.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_identity {
+    use super::*;
+
+    #[test]
+    fn test_identity_parse() {
+        let id = Identity::parse(" /Example.com/Sales/alice@example.com/ ").unwrap();
+        assert_eq!("example.com/sales/alice@example.com", id.as_str());
+        assert_eq!(3, id.depth());
+        assert_eq!("example.com/sales", id.parent().unwrap().as_str());
+        assert_eq!(None, Identity::parse("example.com").unwrap().parent());
+        assert_eq!(id, id.parent().unwrap().child("Alice@example.com").unwrap());
+
+        for bad in ["", "/", "a//b", "a/./b", "a/../b", "a b", "a/b\\c", "é"] {
+            assert!(Identity::parse(bad) == Err(Sm9Error::InvalidIdentity), "{}", bad);
+        }
+        assert!(Identity::parse(&"a".repeat(64)).is_err());
+        assert!(Identity::parse(&["a"; 129].join("/")) == Err(Sm9Error::IdTooLong));
+        assert!(id.child("x/y").is_err());
+
+        let sales = Identity::parse("example.com/sales").unwrap();
+        assert!(id.is_under(&sales) && sales.is_under(&sales));
+        assert!(!Identity::parse("example.com/salesforce/bob").unwrap().is_under(&sales));
+        assert!(!sales.is_under(&id));
+    }
+
+    #[test]
+    fn test_delegated_kgc() {
+        let root = Identity::parse("example.com").unwrap();
+        let sales = root.child("sales").unwrap();
+        let alice = sales.child("alice").unwrap();
+        let bob = root.child("hr").unwrap().child("bob").unwrap();
+
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let kgc = msk.delegate(&sales);
+        assert!(kgc.allows(&alice) && !kgc.allows(&bob));
+        assert!(kgc.extract_key(&bob).err() == Some(Sm9Error::OutsideDomain));
+        assert!(kgc.delegate(&root).is_err());
+        let sub = kgc.delegate(&alice).unwrap();
+        assert!(sub.extract_key(&sales).is_err());
+
+        // the same key as from the root
+        let sk = sub.extract_key(&alice).unwrap();
+        let (h, s) = sk.sign(b"quarterly report").unwrap();
+        msk.verify_sign(alice.as_bytes(), b"quarterly report", &h, &s).unwrap();
+
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let kgc = msk.delegate(&sales);
+        let c = msk.encrypt(alice.as_bytes(), b"hello").unwrap();
+        let key = kgc.extract_key(&alice).unwrap();
+        assert_eq!(b"hello".to_vec(), key.decrypt(alice.as_bytes(), &c).unwrap());
+        assert!(kgc.extract_key(&bob).is_err());
+    }
+}
@@ -1,27 +1,91 @@
 use crate::error::{Sm9Error, Sm9Result};
-use crate::fields::{mod_n_add, mod_n_from_hash, mod_n_inv, mod_n_mul, mod_n_sub, FieldElement};
-use crate::points::{sm9_u256_pairing, twist_point_add_full, Point, TwistPoint};
-use crate::u256::{sm9_random_u256, u256_cmp, xor, U256};
+use crate::fields::fp12::Fp12;
+use crate::fields::{
+    fn_random_u256, mod_n_add, mod_n_batch_inv, mod_n_from_hash, mod_n_inv, mod_n_mul, mod_n_sub,
+    FieldElement,
+};
+use crate::points::{
+    sm9_u256_pairing, sm9_u256_pairing_prepared, twist_point_add_full, G2Prepared, Point, TwistPoint,
+};
+use crate::signature::Sm9Signature;
+use crate::u256::{u256_cmp, u256_to_be_bytes, xor, U256};
 use crate::{
-    SM9_HASH1_PREFIX, SM9_HASH2_PREFIX, SM9_HID_ENC, SM9_HID_EXCH, SM9_HID_SIGN, SM9_N_MINUS_ONE,
-    SM9_POINT_MONT_P1, SM9_TWIST_POINT_MONT_P2,
+    SM9_HASH1_PREFIX, SM9_HASH2_PREFIX, SM9_N_MINUS_ONE, SM9_POINT_MONT_P1, SM9_TWIST_POINT_MONT_P2,
 };
+use gm_core::audit::{observe, observe_infallible, Operation};
+use gm_core::group::{batch_normalize, Normalize};
+use gm_core::{ct_eq, Decryptor, Encryptor, Signer, Verifier};
+use gm_sm3::kdf::sm3_kdf;
 use gm_sm3::sm3_hash;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::hash::{Hash, Hasher};
+
+/// The function identifier `hid` hashed together with the user ID by H1.
+///
+/// GM/T 0044 uses 0x01 for signature, 0x02 for key exchange and 0x03 for
+/// encryption keys. Deployments with their own values use [`Hid::custom`],
+/// the same value must be used for extraction and by the peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hid {
+    Sign,
+    Exch,
+    Enc,
+    Custom(u8),
+}
+
+impl Hid {
+    /// A deployment specific hid, 0x00 and the standard values are rejected.
+    pub fn custom(hid: u8) -> Sm9Result<Hid> {
+        let hid = Hid::Custom(hid);
+        hid.checked()?;
+        Ok(hid)
+    }
 
-#[derive(Copy, Debug, Clone)]
+    pub const fn value(self) -> u8 {
+        match self {
+            Hid::Sign => 0x01,
+            Hid::Exch => 0x02,
+            Hid::Enc => 0x03,
+            Hid::Custom(hid) => hid,
+        }
+    }
+
+    fn checked(self) -> Sm9Result<u8> {
+        match self {
+            Hid::Custom(0x00..=0x03) => Err(Sm9Error::InvalidHid),
+            _ => Ok(self.value()),
+        }
+    }
+}
+
+impl TryFrom<u8> for Hid {
+    type Error = Sm9Error;
+
+    fn try_from(hid: u8) -> Sm9Result<Hid> {
+        match hid {
+            0x01 => Ok(Hid::Sign),
+            0x02 => Ok(Hid::Exch),
+            0x03 => Ok(Hid::Enc),
+            _ => Hid::custom(hid),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
 pub struct Sm9EncKey {
     pub ppube: Point,
-    pub de: TwistPoint,
+    pub(crate) de: TwistPoint,
 }
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Clone)]
 pub struct Sm9EncMasterKey {
-    pub ke: U256,
+    pub(crate) ke: U256,
     pub ppube: Point,
 }
 
 pub fn generate_sign_master_key() -> Sm9SignMasterKey {
-    let ks = sm9_random_u256(&SM9_N_MINUS_ONE);
+    let ks = fn_random_u256();
     Sm9SignMasterKey {
         ks,
         ppubs: TwistPoint::g_mul(&ks),
@@ -29,7 +93,7 @@ pub fn generate_sign_master_key() -> Sm9SignMasterKey {
 }
 
 pub fn generate_enc_master_key() -> Sm9EncMasterKey {
-    let ke = sm9_random_u256(&SM9_N_MINUS_ONE);
+    let ke = fn_random_u256();
     Sm9EncMasterKey {
         ke,
         ppube: Point::g_mul(&ke),
@@ -37,29 +101,70 @@ pub fn generate_enc_master_key() -> Sm9EncMasterKey {
 }
 
 impl Sm9EncKey {
+    /// The private point de, keep it out of logs.
+    pub fn expose_secret(&self) -> &TwistPoint {
+        &self.de
+    }
+
+    /// de (129 bytes) || ppube (65 bytes)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.de.to_bytes_be();
+        bytes.extend_from_slice(&self.ppube.to_bytes_be());
+        bytes
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Sm9EncKey> {
+        if b.len() != 129 + 65 || b[0] != 0x04 || b[129] != 0x04 {
+            return Err(Sm9Error::InvalidPrivate);
+        }
+        let de = TwistPoint::from_bytes(&b[..129])?;
+        let ppube = Point::from_bytes(&b[129..])?;
+        if !de.is_on_curve() || !ppube.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
+        }
+        Ok(Sm9EncKey { ppube, de })
+    }
+
+    /// The plaintext length of a `cipher_len`-byte ciphertext, `None` when it
+    /// is too short to decrypt
+    pub const fn plaintext_len(cipher_len: usize) -> Option<usize> {
+        cipher_len.checked_sub(65 + 32)
+    }
+
     pub fn decrypt(&self, idb: &[u8], data: &[u8]) -> Sm9Result<Vec<u8>> {
+        observe("SM9", Operation::Decrypt, || self.decrypt_with(idb, data))
+    }
+
+    fn decrypt_with(&self, idb: &[u8], data: &[u8]) -> Sm9Result<Vec<u8>> {
+        if Self::plaintext_len(data.len()).is_none() {
+            return Err(Sm9Error::InvalidLength);
+        }
         let c1_bytes = &data[0..65];
         let c2 = &data[(65 + 32)..];
         let c3 = &data[65..(65 + 32)];
-        let c1 = Point::from_bytes(c1_bytes);
+        // B1: C1 must be on the curve
+        let c1 = Point::from_bytes(c1_bytes)?;
+        if c1_bytes[0] != 0x04 || !c1.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
+        }
         let w = sm9_u256_pairing(&self.de, &c1);
         let w_bytes = w.to_bytes_be();
         let mut k_append: Vec<u8> = vec![];
         k_append.extend_from_slice(&c1_bytes[1..65]);
         k_append.extend_from_slice(&w_bytes);
         k_append.extend_from_slice(idb);
-        let k = kdf(&k_append, (255 + 32) as usize);
+        let mlen = data.len() - (65 + 32);
+        let k = kdf(&k_append, mlen + 32)?;
         fn is_zero(x: &Vec<u8>) -> bool {
             x.iter().all(|&byte| byte == 0)
         }
 
         if !is_zero(&k) {
             let k = k.as_slice();
-            let mlen = data.len() - (65 + 32);
             let k1 = &k[0..mlen];
             let k2 = &k[mlen..];
             let u = sm3_hmac(k2, c2, 32);
-            if !u.as_slice().eq(c3) {
+            if !ct_eq(&u, c3) {
                 return Err(Sm9Error::InvalidDigest);
             }
             let m = xor(c2, &k1, k1.len());
@@ -72,105 +177,160 @@ impl Sm9EncKey {
 
 impl Sm9EncMasterKey {
     pub fn master_key_generate() -> Sm9EncMasterKey {
-        // k = rand(1, n-1)
-        let ke = sm9_random_u256(&SM9_N_MINUS_ONE);
-        Self {
-            ke,
-            ppube: Point::g_mul(&ke), // Ppube = ke * P1 in E(F_p)
-        }
-    }
-
-    pub fn encrypt(&self, idb: &[u8], data: &[u8]) -> Vec<u8> {
-        // A1: Q = H1(ID||hid,N) * P1 + Ppube
-        let t = sm9_u256_hash1(idb, SM9_HID_ENC);
-        let mut c1 = SM9_POINT_MONT_P1.point_mul(&t);
-        c1 = c1.point_add(&self.ppube);
-
-        let mut k = vec![];
-        loop {
-            // A2: rand r in [1, N-1]
-            let r = sm9_random_u256(&SM9_N_MINUS_ONE);
-
-            // A3: C1 = r * Q
-            c1 = c1.point_mul(&r);
-            let cbuf = c1.to_bytes_be();
-            let cbuf = cbuf.as_slice();
-
-            // A4: g = e(Ppube, P2)
-            let mut g = sm9_u256_pairing(&SM9_TWIST_POINT_MONT_P2, &self.ppube);
-
-            // A5: w = g^r
-            g = g.pow(&r);
-            let gbuf = g.to_bytes_be();
-            let gbuf = gbuf.as_slice();
-
-            // A6: K = KDF(C || w || ID_B, klen), if K == 0, goto A2
-            let mut k_append: Vec<u8> = vec![];
-            // k_append.push(0x04);
-            k_append.extend_from_slice(&cbuf[1..cbuf.len()]);
-            k_append.extend_from_slice(gbuf);
-            k_append.extend_from_slice(idb);
-            k = kdf(&k_append, (255 + 32) as usize);
-            fn is_zero(x: &Vec<u8>) -> bool {
-                x.iter().all(|&byte| byte == 0)
+        observe_infallible("SM9", Operation::KeyGen, || {
+            // k = rand(1, n-1)
+            let ke = fn_random_u256();
+            Self {
+                ke,
+                ppube: Point::g_mul(&ke), // Ppube = ke * P1 in E(F_p)
             }
+        })
+    }
 
-            if !is_zero(&k) {
-                break;
-            }
+    /// Restore a master key from ke in [1, n-1].
+    pub fn from_secret(ke: U256) -> Sm9Result<Sm9EncMasterKey> {
+        if ke.is_zero() || u256_cmp(&ke, &SM9_N_MINUS_ONE) > 0 {
+            return Err(Sm9Error::InvalidPrivate);
+        }
+        Ok(Self {
+            ke,
+            ppube: Point::g_mul(&ke),
+        })
+    }
+
+    /// The master secret ke, keep it out of logs.
+    pub fn expose_secret(&self) -> &U256 {
+        &self.ke
+    }
+
+    /// ke, big-endian and zero-padded to 32 bytes
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        u256_to_be_bytes(&self.ke).try_into().unwrap()
+    }
+
+    /// C1 (65 bytes) + C3 (32 bytes) + C2 (`plain_len` bytes)
+    pub const fn ciphertext_len(plain_len: usize) -> usize {
+        65 + 32 + plain_len
+    }
+
+    /// Fails with [`Sm9Error::InvalidKeyLength`] only for a message too long
+    /// for the KDF, about 2^37 bytes.
+    pub fn encrypt(&self, idb: &[u8], data: &[u8]) -> Sm9Result<Vec<u8>> {
+        encrypt(&self.ppube, idb, Hid::Enc.value(), data)
+    }
+
+    /// Encrypt to a key extracted with a non-standard `hid`.
+    pub fn encrypt_with_hid(&self, idb: &[u8], hid: Hid, data: &[u8]) -> Sm9Result<Vec<u8>> {
+        encrypt(&self.ppube, idb, hid.checked()?, data)
+    }
+
+    /// Encrypt with a caller supplied `r`, for checking known-answer test
+    /// vectors only. Reusing `r` reveals the plaintexts.
+    #[cfg(feature = "kat")]
+    pub fn encrypt_with_r(&self, idb: &[u8], data: &[u8], r: &U256) -> Sm9Result<Vec<u8>> {
+        if r.is_zero() || u256_cmp(r, &SM9_N_MINUS_ONE) > 0 {
+            return Err(Sm9Error::InvalidSecretKey);
         }
+        encrypt_with_r(&self.ppube, idb, Hid::Enc.value(), data, r)?.ok_or(Sm9Error::KdfHashError)
+    }
 
-        let k1 = &k[0..data.len()];
-        let k2 = &k[data.len()..];
-        let c2 = xor(k1, &data, data.len());
-        let c3 = sm3_hmac(k2, &c2, 32usize);
-        let mut c: Vec<u8> = vec![];
-        c.extend_from_slice(&c1.to_bytes_be());
-        c.extend_from_slice(&c3);
-        c.extend_from_slice(&c2);
-        c
+    pub fn encryptor(&self, idb: &[u8]) -> Sm9Encryptor {
+        Sm9Encryptor::new(self.ppube, idb)
     }
 
     pub fn extract_key(&self, id: &[u8]) -> Option<Sm9EncKey> {
-        // t1 = H1(ID || hid, N) + ke
-        let mut t = sm9_u256_hash1(id, SM9_HID_ENC);
-        t = mod_n_add(&t, &self.ke);
-        if t.is_zero() {
-            return None;
-        }
-        // t2 = ks * t1^-1
-        t = mod_n_inv(&t);
-
-        // ds = t2 * P1
-        t = mod_n_mul(&t, &self.ke);
-        Some(Sm9EncKey {
-            ppube: self.ppube,
-            de: TwistPoint::g_mul(&t),
+        self.extract_with(id, Hid::Enc.value())
+    }
+
+    /// Extract an encryption key with a non-standard `hid`, `None` is
+    /// reported as [`Sm9Error::ZeroDivisor`].
+    pub fn extract_key_with_hid(&self, id: &[u8], hid: Hid) -> Sm9Result<Sm9EncKey> {
+        self.extract_with(id, hid.checked()?)
+            .ok_or(Sm9Error::ZeroDivisor)
+    }
+
+    fn extract_with(&self, id: &[u8], hid: u8) -> Option<Sm9EncKey> {
+        observe_infallible("SM9", Operation::KeyGen, || {
+            // t1 = H1(ID || hid, N) + ke
+            let mut t = sm9_u256_hash1(id, hid);
+            t = mod_n_add(&t, &self.ke);
+            if t.is_zero() {
+                return None;
+            }
+            // t2 = ks * t1^-1
+            t = mod_n_inv(&t);
+
+            // ds = t2 * P1
+            t = mod_n_mul(&t, &self.ke);
+            Some(Sm9EncKey {
+                ppube: self.ppube,
+                de: TwistPoint::g_mul(&t),
+            })
         })
     }
 
+    /// Extract the encryption keys of many identities at once, the result is in
+    /// the order of `ids` and is `None` where `extract_key` would return `None`.
+    ///
+    /// The identities share one modular inversion for the scalars and one field
+    /// inversion to bring all the keys to affine form, which `extract_key` pays
+    /// per identity when the key is first serialized or used. Each key still
+    /// costs a fixed-base multiplication, from the same precomputed table as
+    /// `extract_key`.
+    pub fn extract_batch<T: AsRef<[u8]> + Sync>(&self, ids: &[T]) -> Vec<Option<Sm9EncKey>> {
+        let scalars = extract_scalars(&self.ke, ids, Hid::Enc.value());
+        extract_points(&scalars, TwistPoint::g_mul)
+            .into_iter()
+            .map(|de| {
+                de.map(|de| Sm9EncKey {
+                    ppube: self.ppube,
+                    de,
+                })
+            })
+            .collect()
+    }
+
     pub fn extract_exch_key(&self, id: &[u8]) -> Option<Sm9EncKey> {
-        // t1 = H1(ID || hid, N) + ke
-        let mut t = sm9_u256_hash1(id, SM9_HID_EXCH);
-        t = mod_n_add(&t, &self.ke);
-        if t.is_zero() {
-            return None;
-        }
-        // t2 = ks * t1^-1
-        t = mod_n_inv(&t);
-
-        // ds = t2 * P1
-        t = mod_n_mul(&t, &self.ke);
-        Some(Sm9EncKey {
-            ppube: self.ppube,
-            de: TwistPoint::g_mul(&t),
-        })
+        self.extract_with(id, Hid::Exch.value())
     }
 }
 
+// t2 = k * (H1(ID || hid, N) + k)^-1 for every identity, sharing one inversion
+fn extract_scalars<T: AsRef<[u8]> + Sync>(k: &U256, ids: &[T], hid: u8) -> Vec<Option<U256>> {
+    #[cfg(feature = "parallel")]
+    let ids = ids.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let ids = ids.iter();
+    let t1: Vec<U256> = ids
+        .map(|id| mod_n_add(&sm9_u256_hash1(id.as_ref(), hid), k))
+        .collect();
+    mod_n_batch_inv(&t1)
+        .iter()
+        .zip(t1.iter())
+        .map(|(inv, t)| {
+            if t.is_zero() {
+                None
+            } else {
+                Some(mod_n_mul(inv, k))
+            }
+        })
+        .collect()
+}
+
+// t2 * G for every scalar, normalized together with one inversion
+fn extract_points<G: Normalize + Send>(scalars: &[Option<U256>], g_mul: impl Fn(&U256) -> G + Sync) -> Vec<Option<G>> {
+    #[cfg(feature = "parallel")]
+    let iter = scalars.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let iter = scalars.iter();
+    let mut points: Vec<G> = iter.map(|t| t.as_ref().map_or_else(G::identity, &g_mul)).collect();
+    batch_normalize(&mut points);
+    scalars.iter().zip(points).map(|(t, p)| t.map(|_| p)).collect()
+}
+
 const BLOCK_SIZE: usize = 64;
 
-fn sm3_hmac(key: &[u8], message: &[u8], klen: usize) -> Vec<u8> {
+pub(crate) fn sm3_hmac(key: &[u8], message: &[u8], klen: usize) -> Vec<u8> {
     let mut ipad = [0x36u8; BLOCK_SIZE];
     let mut opad = [0x5cu8; BLOCK_SIZE];
 
@@ -202,184 +362,526 @@ fn sm3_hmac(key: &[u8], message: &[u8], klen: usize) -> Vec<u8> {
     sm3_hash(&opad_append).to_vec()
 }
 
-fn sm9_u256_hash1(id: &[u8], hid: u8) -> U256 {
-    let ct1: [u8; 4] = [0x00, 0x00, 0x00, 0x01];
-    let ct2: [u8; 4] = [0x00, 0x00, 0x00, 0x02];
-    let mut c3_append: Vec<u8> = vec![];
-    c3_append.extend_from_slice(&vec![SM9_HASH1_PREFIX]);
-    c3_append.extend_from_slice(id);
-    c3_append.extend_from_slice(&vec![hid]);
-    c3_append.extend_from_slice(&ct1);
-    let ha1 = sm3_hash(&c3_append);
-
-    let mut c3_append2: Vec<u8> = vec![];
-    c3_append2.extend_from_slice(&vec![SM9_HASH1_PREFIX]);
-    c3_append2.extend_from_slice(id);
-    c3_append2.extend_from_slice(&vec![hid]);
-    c3_append2.extend_from_slice(&ct2);
-    let ha2 = sm3_hash(&c3_append2);
+/// `H1(Z, n)` and `H2(Z, n)` of GM/T 0003.2, with `Z` given in `parts`:
+/// `Ha = SM3(prefix || Z || 0x00000001) || SM3(prefix || Z || 0x00000002)` cut
+/// to `hlen = 8 * ceil(5 * ceil(log2 n) / 32) = 320` bits, and
+/// `h = Ha mod (n - 1) + 1`, so h is in [1, n - 1].
+///
+/// `prefix` is 0x01 for H1 and 0x02 for H2.
+pub fn sm9_hash_to_range(prefix: u8, parts: &[&[u8]]) -> U256 {
+    let mut ha = Vec::with_capacity(64);
+    for ct in [1u32, 2] {
+        let mut z = vec![prefix];
+        for part in parts {
+            z.extend_from_slice(part);
+        }
+        z.extend_from_slice(&ct.to_be_bytes());
+        ha.extend_from_slice(&sm3_hash(&z));
+    }
+    mod_n_from_hash(&ha[..40])
+}
 
-    let mut ha = vec![];
-    ha.extend_from_slice(&ha1);
-    ha.extend_from_slice(&ha2);
-    let r = mod_n_from_hash(&ha);
-    r
+pub(crate) fn sm9_u256_hash1(id: &[u8], hid: u8) -> U256 {
+    sm9_hash_to_range(SM9_HASH1_PREFIX, &[id, &[hid]])
 }
 
 fn sm9_u256_hash2(data: &[u8], wbuf: &[u8]) -> U256 {
-    let ct1: [u8; 4] = [0x00, 0x00, 0x00, 0x01];
-    let ct2: [u8; 4] = [0x00, 0x00, 0x00, 0x02];
-    let mut c3_append: Vec<u8> = vec![];
-    c3_append.extend_from_slice(&vec![SM9_HASH2_PREFIX]);
-    c3_append.extend_from_slice(data);
-    c3_append.extend_from_slice(wbuf);
-    c3_append.extend_from_slice(&ct1);
-    let ha1 = sm3_hash(&c3_append);
-
-    let mut c3_append2: Vec<u8> = vec![];
-    c3_append2.extend_from_slice(&vec![SM9_HASH2_PREFIX]);
-    c3_append2.extend_from_slice(data);
-    c3_append2.extend_from_slice(wbuf);
-    c3_append2.extend_from_slice(&ct2);
-    let ha2 = sm3_hash(&c3_append2);
-
-    let mut ha = vec![];
-    ha.extend_from_slice(&ha1);
-    ha.extend_from_slice(&ha2);
-    let r = mod_n_from_hash(&ha);
-    r
-}
-
-fn kdf(z: &[u8], klen: usize) -> Vec<u8> {
-    let mut ct = 0x00000001u32;
-    let bound = ((klen as f64) / 32.0).ceil() as u32;
-    let mut h_a = Vec::new();
-    for _i in 1..bound {
-        let mut prepend = Vec::new();
-        prepend.extend_from_slice(z);
-        prepend.extend_from_slice(&ct.to_be_bytes());
-
-        let h_a_i = sm3_hash(&prepend[..]);
-        h_a.extend_from_slice(&h_a_i);
-        ct += 1;
-    }
-
-    let mut prepend = Vec::new();
-    prepend.extend_from_slice(z);
-    prepend.extend_from_slice(&ct.to_be_bytes());
-
-    let last = sm3_hash(&prepend[..]);
-    if klen % 32 == 0 {
-        h_a.extend_from_slice(&last);
-    } else {
-        h_a.extend_from_slice(&last[0..(klen % 32)]);
-    }
-    h_a
+    sm9_hash_to_range(SM9_HASH2_PREFIX, &[data, wbuf])
 }
 
-#[derive(Copy, Debug, Clone)]
+pub(crate) fn kdf(z: &[u8], klen: usize) -> Sm9Result<Vec<u8>> {
+    sm3_kdf(z, klen).map_err(|_| Sm9Error::InvalidKeyLength)
+}
+
+#[derive(Copy, Clone)]
 pub struct Sm9SignKey {
     pub ppubs: TwistPoint,
-    pub ds: Point,
+    pub(crate) ds: Point,
 }
 
 impl Sm9SignKey {
+    /// The private point ds, keep it out of logs.
+    pub fn expose_secret(&self) -> &Point {
+        &self.ds
+    }
+
+    /// ds (65 bytes) || ppubs (129 bytes)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.ds.to_bytes_be();
+        bytes.extend_from_slice(&self.ppubs.to_bytes_be());
+        bytes
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Sm9SignKey> {
+        if b.len() != 65 + 129 || b[0] != 0x04 || b[65] != 0x04 {
+            return Err(Sm9Error::InvalidPrivate);
+        }
+        let ds = Point::from_bytes(&b[..65])?;
+        let ppubs = TwistPoint::from_bytes(&b[65..])?;
+        if !ds.is_on_curve() || !ppubs.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
+        }
+        Ok(Sm9SignKey { ppubs, ds })
+    }
+
     /// return (h, S)
     pub fn sign(&self, data: &[u8]) -> Sm9Result<(U256, Point)> {
-        // A1: g = e(P1, Ppubs)
-        let g = sm9_u256_pairing(&self.ppubs, &SM9_POINT_MONT_P1);
-        let mut h: U256 = [0, 0, 0, 0];
-        let mut r: U256 = [0, 0, 0, 0];
-        loop {
-            // A2: rand r in [1, N-1]
-            r = sm9_random_u256(&SM9_N_MINUS_ONE);
-
-            // A3: w = g^r
-            let w = g.pow(&r);
-            let wbuf = w.to_bytes_be();
-            let wbuf = wbuf.as_slice();
-
-            // A4: h = H2(M || w, N)
-            h = sm9_u256_hash2(data, wbuf);
-
-            // A5: l = (r - h) mod N, if l = 0, goto A2
-            r = mod_n_sub(&r, &h);
-
-            if !r.is_zero() {
-                break;
-            }
+        observe("SM9", Operation::Sign, || {
+            // A1: g = e(P1, Ppubs)
+            let g = sm9_u256_pairing(&self.ppubs, &SM9_POINT_MONT_P1);
+            sign_prepared(&g, &self.ds, data)
+        })
+    }
+}
+
+/// A signing key together with g = e(P1, Ppubs), so a signature costs one GT
+/// exponentiation instead of a pairing and an exponentiation.
+///
+/// g only depends on the master public key. Services that start often can
+/// store [`Sm9Signer::precomputed`] next to the key and restore the signer
+/// with [`Sm9Signer::from_precomputed`], the value must come from the same
+/// trusted storage as the key since it is not checked against Ppubs.
+#[derive(Debug, Clone)]
+pub struct Sm9Signer {
+    key: Sm9SignKey,
+    g: Fp12,
+}
+
+impl Sm9Signer {
+    pub fn new(key: Sm9SignKey) -> Self {
+        let g = sm9_u256_pairing(&key.ppubs, &SM9_POINT_MONT_P1);
+        Self { key, g }
+    }
+
+    /// g in the 384-byte form hashed by H2
+    pub fn precomputed(&self) -> Vec<u8> {
+        self.g.to_bytes_be()
+    }
+
+    pub fn from_precomputed(key: Sm9SignKey, g: &[u8]) -> Sm9Result<Self> {
+        let g = Fp12::from_bytes_be(g).ok_or(Sm9Error::InvalidGt)?;
+        if g.is_zero() {
+            return Err(Sm9Error::InvalidGt);
         }
+        Ok(Self { key, g })
+    }
 
-        // A6: S = l * dsA
-        let s = self.ds.point_mul(&r);
+    pub fn key(&self) -> &Sm9SignKey {
+        &self.key
+    }
 
-        Ok((h, s))
+    /// return (h, S)
+    pub fn sign(&self, data: &[u8]) -> Sm9Result<(U256, Point)> {
+        observe("SM9", Operation::Sign, || sign_prepared(&self.g, &self.key.ds, data))
     }
 }
 
-#[derive(Copy, Debug, Clone)]
+fn sign_prepared(g: &Fp12, ds: &Point, data: &[u8]) -> Sm9Result<(U256, Point)> {
+    let (h, l) = loop {
+        // A2: rand r in [1, N-1]
+        let r = fn_random_u256();
+
+        // A3: w = g^r
+        let w = g.pow(&r);
+        let wbuf = w.to_bytes_be();
+        let wbuf = wbuf.as_slice();
+
+        // A4: h = H2(M || w, N)
+        let h = sm9_u256_hash2(data, wbuf);
+
+        // A5: l = (r - h) mod N, if l = 0, goto A2
+        let l = mod_n_sub(&r, &h);
+
+        if !l.is_zero() {
+            break (h, l);
+        }
+    };
+
+    // A6: S = l * dsA
+    let s = ds.point_mul(&l);
+
+    Ok((h, s))
+}
+
+#[derive(Copy, Clone)]
 pub struct Sm9SignMasterKey {
-    pub ks: U256,
+    pub(crate) ks: U256,
     pub ppubs: TwistPoint,
 }
 
+// The secret halves of the keys are never printed, use `expose_secret`.
+macro_rules! redacted_debug {
+    ($($t:ident),*) => {
+        $(impl std::fmt::Debug for $t {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, concat!(stringify!($t), "(…)"))
+            }
+        })*
+    };
+}
+
+redacted_debug!(Sm9EncKey, Sm9EncMasterKey, Sm9SignKey, Sm9SignMasterKey);
+
 impl Sm9SignMasterKey {
     pub fn master_key_generate() -> Self {
-        // k = rand(1, n-1)
-        let ks = sm9_random_u256(&SM9_N_MINUS_ONE);
-        Self {
-            ks,
-            ppubs: TwistPoint::g_mul(&ks), // Ppubs = k * P2 in E'(F_p^2)
+        observe_infallible("SM9", Operation::KeyGen, || {
+            // k = rand(1, n-1)
+            let ks = fn_random_u256();
+            Self {
+                ks,
+                ppubs: TwistPoint::g_mul(&ks), // Ppubs = k * P2 in E'(F_p^2)
+            }
+        })
+    }
+
+    /// Restore a master key from ks in [1, n-1].
+    pub fn from_secret(ks: U256) -> Sm9Result<Self> {
+        if ks.is_zero() || u256_cmp(&ks, &SM9_N_MINUS_ONE) > 0 {
+            return Err(Sm9Error::InvalidPrivate);
         }
+        Ok(Self {
+            ks,
+            ppubs: TwistPoint::g_mul(&ks),
+        })
+    }
+
+    /// The master secret ks, keep it out of logs.
+    pub fn expose_secret(&self) -> &U256 {
+        &self.ks
+    }
+
+    /// ks, big-endian and zero-padded to 32 bytes
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        u256_to_be_bytes(&self.ks).try_into().unwrap()
     }
 
     pub fn extract_key(&self, idb: &[u8]) -> Option<Sm9SignKey> {
-        // t1 = H1(ID || hid, N) + ks
-        let mut t = sm9_u256_hash1(idb, SM9_HID_SIGN);
-        t = mod_n_add(&t, &self.ks);
-        if t.is_zero() {
-            return None;
-        }
-        // t2 = ks * t1^-1
-        t = mod_n_inv(&t);
-
-        // ds = t2 * P1
-        t = mod_n_mul(&t, &self.ks);
-        Some(Sm9SignKey {
-            ppubs: self.ppubs,
-            ds: Point::g_mul(&t),
+        self.extract_with(idb, Hid::Sign.value())
+    }
+
+    /// Extract a signing key with a non-standard `hid`, `None` is
+    /// reported as [`Sm9Error::ZeroDivisor`].
+    pub fn extract_key_with_hid(&self, idb: &[u8], hid: Hid) -> Sm9Result<Sm9SignKey> {
+        self.extract_with(idb, hid.checked()?)
+            .ok_or(Sm9Error::ZeroDivisor)
+    }
+
+    fn extract_with(&self, idb: &[u8], hid: u8) -> Option<Sm9SignKey> {
+        observe_infallible("SM9", Operation::KeyGen, || {
+            // t1 = H1(ID || hid, N) + ks
+            let mut t = sm9_u256_hash1(idb, hid);
+            t = mod_n_add(&t, &self.ks);
+            if t.is_zero() {
+                return None;
+            }
+            // t2 = ks * t1^-1
+            t = mod_n_inv(&t);
+
+            // ds = t2 * P1
+            t = mod_n_mul(&t, &self.ks);
+            Some(Sm9SignKey {
+                ppubs: self.ppubs,
+                ds: Point::g_mul(&t),
+            })
         })
     }
 
+    /// Extract the signing keys of many identities at once, the result is in
+    /// the order of `ids` and is `None` where `extract_key` would return `None`.
+    ///
+    /// The identities share one modular inversion for the scalars and one field
+    /// inversion to bring all the keys to affine form, which `extract_key` pays
+    /// per identity when the key is first serialized or used. Each key still
+    /// costs a fixed-base multiplication, from the same precomputed table as
+    /// `extract_key`.
+    pub fn extract_batch<T: AsRef<[u8]> + Sync>(&self, ids: &[T]) -> Vec<Option<Sm9SignKey>> {
+        let scalars = extract_scalars(&self.ks, ids, Hid::Sign.value());
+        extract_points(&scalars, |t| Point::g_mul(t))
+            .into_iter()
+            .map(|ds| {
+                ds.map(|ds| Sm9SignKey {
+                    ppubs: self.ppubs,
+                    ds,
+                })
+            })
+            .collect()
+    }
+
     pub fn verify_sign(&self, id: &[u8], data: &[u8], h: &U256, s: &Point) -> Sm9Result<()> {
-        let g = sm9_u256_pairing(&self.ppubs, &SM9_POINT_MONT_P1);
-        let t = g.pow(h);
-        // B5: h1 = H1(ID || hid, N)
-        let h1 = sm9_u256_hash1(id, SM9_HID_SIGN);
-        let mut p = TwistPoint::g_mul(&h1);
-        p = twist_point_add_full(&self.ppubs, &p);
-
-        let u = sm9_u256_pairing(&p, s);
-        let w = u.fp_mul(&t);
-        let wbuf = w.to_bytes_be();
-        let wbuf = wbuf.as_slice();
-        let h2 = sm9_u256_hash2(data, wbuf);
-        if u256_cmp(&h2, h) != 0 {
-            Err(Sm9Error::InvalidDigest)
-        } else {
-            Ok(())
+        verify_sign(&self.ppubs, id, Hid::Sign.value(), data, h, s)
+    }
+
+    pub fn verifier(&self, id: &[u8]) -> Sm9Verifier {
+        Sm9Verifier::new(self.ppubs, id)
+    }
+}
+
+fn encrypt(ppube: &Point, idb: &[u8], hid: u8, data: &[u8]) -> Sm9Result<Vec<u8>> {
+    observe("SM9", Operation::Encrypt, || loop {
+        // A2: rand r in [1, N-1]
+        let r = fn_random_u256();
+        if let Some(c) = encrypt_with_r(ppube, idb, hid, data, &r)? {
+            return Ok(c);
+        }
+    })
+}
+
+/// Returns `None` when K is zero and another `r` must be chosen.
+fn encrypt_with_r(ppube: &Point, idb: &[u8], hid: u8, data: &[u8], r: &U256) -> Sm9Result<Option<Vec<u8>>> {
+    // A1: Q = H1(ID||hid,N) * P1 + Ppube
+    let t = sm9_u256_hash1(idb, hid);
+    let mut c1 = SM9_POINT_MONT_P1.point_mul(&t);
+    c1 = c1.point_add(ppube);
+
+    // A3: C1 = r * Q
+    c1 = c1.point_mul(r);
+    let cbuf = c1.to_bytes_be();
+    let cbuf = cbuf.as_slice();
+
+    // A4: g = e(Ppube, P2)
+    let mut g = sm9_u256_pairing(&SM9_TWIST_POINT_MONT_P2, ppube);
+
+    // A5: w = g^r
+    g = g.pow(r);
+    let gbuf = g.to_bytes_be();
+    let gbuf = gbuf.as_slice();
+
+    // A6: K = KDF(C || w || ID_B, klen), if K == 0, goto A2
+    let mut k_append: Vec<u8> = vec![];
+    k_append.extend_from_slice(&cbuf[1..cbuf.len()]);
+    k_append.extend_from_slice(gbuf);
+    k_append.extend_from_slice(idb);
+    // klen = mlen + 32, K2 is the 32-byte MAC key
+    let k = kdf(&k_append, data.len() + 32)?;
+    if k.iter().all(|&byte| byte == 0) {
+        return Ok(None);
+    }
+
+    let k1 = &k[0..data.len()];
+    let k2 = &k[data.len()..];
+    let c2 = xor(k1, data, data.len());
+    let c3 = sm3_hmac(k2, &c2, 32usize);
+    let mut c: Vec<u8> = vec![];
+    c.extend_from_slice(&c1.to_bytes_be());
+    c.extend_from_slice(&c3);
+    c.extend_from_slice(&c2);
+    Ok(Some(c))
+}
+
+fn verify_sign(
+    ppubs: &TwistPoint,
+    id: &[u8],
+    hid: u8,
+    data: &[u8],
+    h: &U256,
+    s: &Point,
+) -> Sm9Result<()> {
+    observe("SM9", Operation::Verify, || {
+        let g = sm9_u256_pairing(ppubs, &SM9_POINT_MONT_P1);
+        let p = G2Prepared::new(&sign_public_point(ppubs, id, hid));
+        verify_prepared(&g, &p, data, h, s)
+    })
+}
+
+/// P = H1(ID || hid, N) * P2 + Ppubs
+fn sign_public_point(ppubs: &TwistPoint, id: &[u8], hid: u8) -> TwistPoint {
+    // B5: h1 = H1(ID || hid, N)
+    let h1 = sm9_u256_hash1(id, hid);
+    let p = TwistPoint::g_mul(&h1);
+    twist_point_add_full(ppubs, &p)
+}
+
+/// `g` = e(P1, Ppubs), `p` the lines of [`sign_public_point`]
+fn verify_prepared(g: &Fp12, p: &G2Prepared, data: &[u8], h: &U256, s: &Point) -> Sm9Result<()> {
+    let t = g.pow(h);
+    let u = sm9_u256_pairing_prepared(p, s);
+    let w = u.fp_mul(&t);
+    let wbuf = w.to_bytes_be();
+    let wbuf = wbuf.as_slice();
+    let h2 = sm9_u256_hash2(data, wbuf);
+    if u256_cmp(&h2, h) != 0 {
+        Err(Sm9Error::InvalidDigest)
+    } else {
+        Ok(())
+    }
+}
+
+/// Verifies signatures of the user `id` under the signing master public key `ppubs`.
+///
+/// The pairing g = e(P1, Ppubs) and the G2 point H1(ID || hid, N) * P2 + Ppubs
+/// are computed once in [`Sm9Verifier::new`], together with the Miller loop
+/// lines of the latter, a verification then costs the line evaluations at S,
+/// the final exponentiation and one GT exponentiation. Use [`Sm9Verifier::with_id`] to reuse g
+/// for other signers under the same master key.
+#[derive(Debug, Clone)]
+pub struct Sm9Verifier {
+    ppubs: TwistPoint,
+    id: Vec<u8>,
+    hid: u8,
+    g: Fp12,
+    p: G2Prepared,
+}
+
+/// Equal for the same master public key, identity and `hid`, the cached
+/// pairing follows from them.
+impl PartialEq for Sm9Verifier {
+    fn eq(&self, other: &Self) -> bool {
+        (self.ppubs, &self.id, self.hid) == (other.ppubs, &other.id, other.hid)
+    }
+}
+
+impl Eq for Sm9Verifier {}
+
+impl Hash for Sm9Verifier {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.ppubs, &self.id, self.hid).hash(state)
+    }
+}
+
+impl Sm9Verifier {
+    pub fn new(ppubs: TwistPoint, id: &[u8]) -> Self {
+        Self::new_with(ppubs, id, Hid::Sign.value())
+    }
+
+    /// A verifier for keys extracted with a non-standard `hid`.
+    pub fn new_with_hid(ppubs: TwistPoint, id: &[u8], hid: Hid) -> Sm9Result<Self> {
+        Ok(Self::new_with(ppubs, id, hid.checked()?))
+    }
+
+    fn new_with(ppubs: TwistPoint, id: &[u8], hid: u8) -> Self {
+        let g = sm9_u256_pairing(&ppubs, &SM9_POINT_MONT_P1);
+        Self {
+            ppubs,
+            id: id.to_vec(),
+            hid,
+            g,
+            p: G2Prepared::new(&sign_public_point(&ppubs, id, hid)),
+        }
+    }
+
+    /// A verifier for another signer under the same master public key and hid.
+    pub fn with_id(&self, id: &[u8]) -> Self {
+        Self {
+            ppubs: self.ppubs,
+            id: id.to_vec(),
+            hid: self.hid,
+            g: self.g,
+            p: G2Prepared::new(&sign_public_point(&self.ppubs, id, self.hid)),
+        }
+    }
+
+    pub fn ppubs(&self) -> &TwistPoint {
+        &self.ppubs
+    }
+
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    pub fn verify_sign(&self, data: &[u8], h: &U256, s: &Point) -> Sm9Result<()> {
+        observe("SM9", Operation::Verify, || verify_prepared(&self.g, &self.p, data, h, s))
+    }
+
+    /// Verify many signatures of this signer, the results are in input order.
+    /// With the `parallel` feature they are checked on the rayon thread pool.
+    pub fn verify_batch(&self, items: &[(&[u8], Sm9Signature)]) -> Vec<Sm9Result<()>> {
+        #[cfg(feature = "parallel")]
+        let items = items.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let items = items.iter();
+        items
+            .map(|(data, sig)| self.verify_sign(data, &sig.h, &sig.s))
+            .collect()
+    }
+}
+
+/// Encrypts to the user `id` under the encryption master public key `ppube`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sm9Encryptor {
+    pub ppube: Point,
+    pub id: Vec<u8>,
+    pub hid: Hid,
+}
+
+impl Sm9Encryptor {
+    pub fn new(ppube: Point, id: &[u8]) -> Self {
+        Self {
+            ppube,
+            id: id.to_vec(),
+            hid: Hid::Enc,
         }
     }
 }
 
+/// Decrypts with the user key `key` extracted for `id`.
+#[derive(Debug, Clone)]
+pub struct Sm9Decryptor {
+    pub key: Sm9EncKey,
+    pub id: Vec<u8>,
+}
+
+impl Sm9Decryptor {
+    pub fn new(key: Sm9EncKey, id: &[u8]) -> Self {
+        Self {
+            key,
+            id: id.to_vec(),
+        }
+    }
+}
+
+impl Signer for Sm9SignKey {
+    type Signature = Sm9Signature;
+    type Error = Sm9Error;
+
+    fn sign(&self, msg: &[u8]) -> Sm9Result<Sm9Signature> {
+        let (h, s) = Sm9SignKey::sign(self, msg)?;
+        Ok(Sm9Signature { h, s })
+    }
+}
+
+impl Signer for Sm9Signer {
+    type Signature = Sm9Signature;
+    type Error = Sm9Error;
+
+    fn sign(&self, msg: &[u8]) -> Sm9Result<Sm9Signature> {
+        let (h, s) = Sm9Signer::sign(self, msg)?;
+        Ok(Sm9Signature { h, s })
+    }
+}
+
+impl Verifier for Sm9Verifier {
+    type Signature = Sm9Signature;
+    type Error = Sm9Error;
+
+    fn verify(&self, msg: &[u8], sig: &Sm9Signature) -> Sm9Result<()> {
+        self.verify_sign(msg, &sig.h, &sig.s)
+    }
+}
+
+impl Encryptor for Sm9Encryptor {
+    type Error = Sm9Error;
+
+    fn encrypt(&self, msg: &[u8]) -> Sm9Result<Vec<u8>> {
+        encrypt(&self.ppube, &self.id, self.hid.checked()?, msg)
+    }
+}
+
+impl Decryptor for Sm9Decryptor {
+    type Error = Sm9Error;
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Sm9Result<Vec<u8>> {
+        self.key.decrypt(&self.id, ciphertext)
+    }
+}
+
 pub fn exch_step_1a(msk: &Sm9EncMasterKey, idb: &[u8]) -> (Point, U256) {
     // A1: Q = H1(ID_B||hid,N) * P1 + Ppube
-    let mut ra = sm9_u256_hash1(idb, SM9_HID_EXCH);
+    let mut ra = sm9_u256_hash1(idb, Hid::Exch.value());
     let mut r = SM9_POINT_MONT_P1.point_mul(&ra);
     r = r.point_add(&msk.ppube);
 
     // A2: rand rA in [1, N-1]
-    ra = sm9_random_u256(&SM9_N_MINUS_ONE);
+    ra = fn_random_u256();
     // ra = u256_from_hex("00005879DD1D51E175946F23B1B41E93BA31C584AE59A426EC1046A4D03B06C8");
 
     // A3: RA = rA * Q
@@ -397,13 +899,13 @@ pub fn exch_step_1b(
     klen: usize,
 ) -> Sm9Result<(Point, Vec<u8>)> {
     // B1: Q = H1(ID_A||hid,N) * P1 + Ppube
-    let mut rb = sm9_u256_hash1(ida, SM9_HID_EXCH);
+    let mut rb = sm9_u256_hash1(ida, Hid::Exch.value());
     let mut r = SM9_POINT_MONT_P1.point_mul(&rb);
     r = r.point_add(&msk.ppube);
     let mut sk = vec![];
     loop {
         // B2: rand rB in [1, N-1]
-        rb = sm9_random_u256(&SM9_N_MINUS_ONE);
+        rb = fn_random_u256();
 
         // rb = u256_from_hex("00018B98C44BEF9F8537FB7D071B2C928B3BC65BD3D69E1EEE213564905634FE");
 
@@ -435,7 +937,7 @@ pub fn exch_step_1b(
         pre_append.extend_from_slice(&g2);
         pre_append.extend_from_slice(&g3);
 
-        sk = kdf(&pre_append, klen);
+        sk = kdf(&pre_append, klen)?;
 
         fn is_zero(x: &Vec<u8>, klen: usize) -> bool {
             let mut ret = true;
@@ -492,7 +994,7 @@ pub fn exch_step_2a(
         pre_append.extend_from_slice(&g2);
         pre_append.extend_from_slice(&g3);
 
-        sk = kdf(&pre_append, klen);
+        sk = kdf(&pre_append, klen)?;
         fn is_zero(x: &Vec<u8>, klen: usize) -> bool {
             let mut ret = true;
             for i in 0..klen {
@@ -512,11 +1014,65 @@ pub fn exch_step_2a(
 
 #[cfg(test)]
 mod sm9_key_test {
+    use crate::error::Sm9Error;
     use crate::key::{
-        exch_step_1a, exch_step_1b, exch_step_2a, Sm9EncKey, Sm9EncMasterKey, Sm9SignMasterKey,
+        exch_step_1a, exch_step_1b, exch_step_2a, Hid, Sm9Decryptor, Sm9EncKey, Sm9EncMasterKey,
+        Sm9SignMasterKey, Sm9Signer, Sm9Verifier,
     };
+    use crate::fields::fp::Fp;
+    use crate::fields::fp2::Fp2;
+    use crate::fields::FieldElement;
     use crate::points::{Point, TwistPoint};
-    use crate::u256::u256_from_be_bytes;
+    use crate::signature::Sm9Signature;
+    use crate::fields::mod_n_from_hash;
+    use crate::key::sm9_hash_to_range;
+    use crate::u256::{u256_from_be_bytes, u256_sub, u256_to_be_bytes};
+    use crate::SM9_N;
+    use gm_core::{Decryptor, Encryptor, Signer, Verifier};
+
+    #[test]
+    fn test_hash_to_range() {
+        // H1("Alice" || 0x01, n) of the GM/T 0003.5 signature example
+        assert_eq!(
+            u256_from_be_bytes(&hex::decode("2ACC468C3926B0BDB2767E99FF26E084DE9CED8DBC7D5FBF418027B667862FAB").unwrap()),
+            sm9_hash_to_range(0x01, &[b"Alice", &[0x01]])
+        );
+        let w: Vec<u8> = (0..32).collect();
+        assert_eq!(
+            u256_from_be_bytes(&hex::decode("84FBD1A33AA1A3643DA9A4ACF5F1760B4259F98FBA7320E3CEE0128D5F98EF3D").unwrap()),
+            sm9_hash_to_range(0x02, &[b"Chinese IBS standard", &w])
+        );
+        // how Z is split does not matter
+        assert_eq!(
+            sm9_hash_to_range(0x02, &[b"Chinese IBS standard", &w]),
+            sm9_hash_to_range(0x02, &[b"Chinese", b" IBS standard", &w[..5], &w[5..]])
+        );
+
+        // the reduction of the 320-bit Ha at its edges
+        let reduce = |ha: &[u8]| mod_n_from_hash(ha);
+        let n_minus = |k: u64| {
+            let mut b = [0u8; 40];
+            b[8..].copy_from_slice(&u256_to_be_bytes(&u256_sub(&SM9_N, &[k, 0, 0, 0]).0));
+            b
+        };
+        assert_eq!([1, 0, 0, 0], reduce(&[0; 40]));
+        assert_eq!(u256_sub(&SM9_N, &[1, 0, 0, 0]).0, reduce(&n_minus(2)));
+        assert_eq!([1, 0, 0, 0], reduce(&n_minus(1)));
+        assert_eq!([2, 0, 0, 0], reduce(&n_minus(0)));
+        assert_eq!(
+            u256_from_be_bytes(&hex::decode("3D3341B26AD6D031E43238C6840B1846B9C8CF71A0440B49A6D297052DC62AA8").unwrap()),
+            reduce(&[0xff; 40])
+        );
+    }
+
+    #[test]
+    fn test_ciphertext_len() {
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let c = msk.encrypt(b"Bob", b"abc").unwrap();
+        assert_eq!(Sm9EncMasterKey::ciphertext_len(3), c.len());
+        assert_eq!(Some(3), Sm9EncKey::plaintext_len(c.len()));
+        assert_eq!(None, Sm9EncKey::plaintext_len(96));
+    }
 
     #[test]
     fn test_encrypt() {
@@ -550,7 +1106,7 @@ mod sm9_key_test {
         );
         assert_eq!(true, r.unwrap().de.point_equals(&r_de));
 
-        let ret = msk.encrypt(&idb, &data);
+        let ret = msk.encrypt(&idb, &data).unwrap();
         println!("Message =    {:?}", &data);
         println!("Ciphertext = {:?}", ret);
         let m = r.unwrap().decrypt(&idb, &ret).expect("Decryption failed");
@@ -558,6 +1114,180 @@ mod sm9_key_test {
         assert_eq!(true, data == m.as_slice());
     }
 
+    #[test]
+    fn test_custom_hid() {
+        assert!(Hid::custom(0x00).is_err());
+        assert!(Hid::custom(0x02).is_err());
+        assert_eq!(Ok(Hid::Enc), Hid::try_from(0x03));
+        assert_eq!(0x81, Hid::try_from(0x81).unwrap().value());
+        let hid = Hid::custom(0x81).unwrap();
+
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key_with_hid(b"Alice", hid).unwrap();
+        let (h, s) = key.sign(b"custom hid").unwrap();
+        let verifier = Sm9Verifier::new_with_hid(msk.ppubs, b"Alice", hid).unwrap();
+        assert!(verifier.verify_sign(b"custom hid", &h, &s).is_ok());
+        assert!(msk.verify_sign(b"Alice", b"custom hid", &h, &s).is_err());
+        assert!(msk
+            .extract_key_with_hid(b"Alice", Hid::Custom(0x01))
+            .is_err());
+
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let key = msk.extract_key_with_hid(b"Bob", hid).unwrap();
+        let c = msk.encrypt_with_hid(b"Bob", hid, b"custom hid").unwrap();
+        assert_eq!(b"custom hid".to_vec(), key.decrypt(b"Bob", &c).unwrap());
+        let c = msk.encrypt(b"Bob", b"custom hid").unwrap();
+        assert!(key.decrypt(b"Bob", &c).is_err());
+
+        let mut encryptor = msk.encryptor(b"Bob");
+        encryptor.hid = Hid::Custom(0x00);
+        assert!(encryptor.encrypt(b"custom hid").is_err());
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Alice").unwrap();
+        let msgs: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 10]).collect();
+        let mut items: Vec<(&[u8], Sm9Signature)> = msgs
+            .iter()
+            .map(|m| (m.as_slice(), Signer::sign(&key, m).unwrap()))
+            .collect();
+        items[2].0 = b"tampered";
+        let results = msk.verifier(b"Alice").verify_batch(&items);
+        println!("{:?}", results);
+        assert_eq!(vec![true, true, false, true], results.iter().map(|r| r.is_ok()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_signer_precomputed() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Alice").unwrap();
+        let signer = Sm9Signer::new(key);
+        let g = signer.precomputed();
+        assert_eq!(384, g.len());
+
+        let restored = Sm9Signer::from_precomputed(key, &g).unwrap();
+        assert_eq!(g, restored.precomputed());
+        let verifier = msk.verifier(b"Alice");
+        for signer in [&signer, &restored] {
+            let (h, s) = signer.sign(b"Chinese IBS standard").unwrap();
+            verifier.verify_sign(b"Chinese IBS standard", &h, &s).unwrap();
+        }
+        let sig = Signer::sign(&restored, b"trait").unwrap();
+        Verifier::verify(&verifier, b"trait", &sig).unwrap();
+
+        assert!(Sm9Signer::from_precomputed(key, &g[1..]).is_err());
+        assert!(Sm9Signer::from_precomputed(key, &[0xffu8; 384]).is_err());
+        assert!(Sm9Signer::from_precomputed(key, &[0u8; 384]).is_err());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let fp = msk.ppubs.fingerprint();
+        println!("Ppubs fingerprint = {}", fp);
+        let restored = TwistPoint::from_bytes(&msk.ppubs.to_bytes_be()).unwrap();
+        assert_eq!(fp, restored.fingerprint());
+        // a compressed master public key verifies what the full one does
+        let compressed = TwistPoint::from_bytes(&msk.ppubs.to_bytes_compressed()).unwrap();
+        assert_eq!(fp, compressed.fingerprint());
+        let sig = Signer::sign(&msk.extract_key(b"Alice").unwrap(), b"msg").unwrap();
+        Verifier::verify(&Sm9Verifier::new(compressed, b"Alice"), b"msg", &sig).unwrap();
+        let mke = Sm9EncMasterKey::master_key_generate();
+        assert!(Point::from_bytes(&mke.ppube.to_bytes_compressed()).unwrap().point_equals(&mke.ppube));
+        // the projective form does not change it
+        let doubled = msk.ppubs.point_double();
+        assert_eq!(doubled.to_affine_point().fingerprint(), doubled.fingerprint());
+
+        let mke = crate::key::generate_enc_master_key();
+        assert_eq!(gm_sm3::sm3_hash(&mke.ppube.to_bytes_be()), *mke.ppube.fingerprint().as_bytes());
+    }
+
+    #[test]
+    fn test_key_components() {
+        let mut ks = [0u64; 4];
+        ks[0] = 7;
+        let msk = Sm9SignMasterKey::from_secret(ks).unwrap();
+        let mut expected = [0u8; 32];
+        expected[31] = 7;
+        assert_eq!(expected, msk.to_be_bytes());
+        let ppubs = msk.ppubs.to_bytes_be();
+        assert_eq!(ppubs[1..], [msk.ppubs.x_bytes(), msk.ppubs.y_bytes()].concat());
+
+        let mke = Sm9EncMasterKey::master_key_generate();
+        let restored = Sm9EncMasterKey::from_secret(u256_from_be_bytes(&mke.to_be_bytes())).unwrap();
+        assert_eq!(mke.ppube, restored.ppube);
+        assert_eq!(mke.ppube.to_bytes_be()[1..], [mke.ppube.x_bytes(), mke.ppube.y_bytes()].concat());
+    }
+
+    #[cfg(feature = "audit")]
+    #[test]
+    fn test_audit_events() {
+        use std::sync::{Arc, Mutex};
+        use std::thread::{self, ThreadId};
+
+        use gm_core::audit::{set_observer, Event, Observer, Operation};
+
+        // other tests run at the same time, keep the events of this thread
+        struct Record(ThreadId, Mutex<Vec<(Operation, bool)>>);
+
+        impl Observer for Record {
+            fn on_event(&self, e: &Event) {
+                if thread::current().id() == self.0 {
+                    assert_eq!("SM9", e.algorithm);
+                    self.1.lock().unwrap().push((e.operation, e.ok));
+                }
+            }
+        }
+
+        let record = Arc::new(Record(thread::current().id(), Mutex::new(vec![])));
+        set_observer(Some(record.clone()));
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Alice").unwrap();
+        let (h, s) = key.sign(b"msg").unwrap();
+        assert!(msk.verify_sign(b"Bob", b"msg", &h, &s).is_err());
+        set_observer(None);
+
+        use Operation::*;
+        let events = record.1.lock().unwrap().clone();
+        let expected = [
+            (KeyGen, true),
+            (KeyGen, true),
+            (Pairing, true),
+            (Sign, true),
+            (Pairing, true),
+            (Pairing, true),
+            (Verify, false),
+        ];
+        assert_eq!(expected.to_vec(), events);
+    }
+
+    #[test]
+    fn test_extract_batch() {
+        let ids = ["Alice", "Bob", "Carol", "Dave"];
+
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let keys = msk.extract_batch(&ids);
+        assert_eq!(ids.len(), keys.len());
+        for (id, key) in ids.iter().zip(keys.iter()) {
+            let expected = msk.extract_key(id.as_bytes()).unwrap();
+            assert!(key.unwrap().ds.point_equals(&expected.ds));
+            assert_eq!(expected.to_bytes(), key.unwrap().to_bytes());
+            assert_eq!(Fp::one(), key.unwrap().ds.z);
+        }
+
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let keys = msk.extract_batch(&ids);
+        for (id, key) in ids.iter().zip(keys.iter()) {
+            let expected = msk.extract_key(id.as_bytes()).unwrap();
+            assert!(key.unwrap().de.point_equals(&expected.de));
+            assert_eq!(expected.to_bytes(), key.unwrap().to_bytes());
+            assert_eq!(Fp2::one(), key.unwrap().de.z);
+        }
+        assert!(msk.extract_batch::<&str>(&[]).is_empty());
+    }
+
     #[test]
     fn test_sign_verify() {
         let data: [u8; 20] = [
@@ -613,6 +1343,9 @@ mod sm9_key_test {
         let (ra, ra_) = exch_step_1a(&msk, &idb);
         let (rb, skb) = exch_step_1b(&msk, &ida, &idb, &key_b, &ra, klen).unwrap();
         let ska = exch_step_2a(&msk, &ida, &idb, &key_a, ra_, &ra, &rb, klen).unwrap();
+        // an empty key used to loop forever looking for a non-zero one
+        assert!(exch_step_1b(&msk, &ida, &idb, &key_b, &ra, 0).err() == Some(Sm9Error::InvalidKeyLength));
+        assert!(exch_step_2a(&msk, &ida, &idb, &key_a, ra_, &ra, &rb, 0).err() == Some(Sm9Error::InvalidKeyLength));
         println!("SKB = {:?}", &skb);
         println!("SKA = {:?}", &ska);
         for i in 0..klen {
@@ -621,4 +1354,104 @@ mod sm9_key_test {
             }
         }
     }
+
+    /// GM/T 0044-2016 part 5, encryption example with the KDF based stream cipher
+    #[cfg(feature = "kat")]
+    #[test]
+    fn test_encrypt_kat() {
+        let idb = b"Bob";
+        let ke = u256_from_be_bytes(
+            &hex::decode("0001EDEE3778F441F8DEA3D9FA0ACC4E07EE36C93F9A08618AF4AD85CEDE1C22")
+                .unwrap(),
+        );
+        let msk = Sm9EncMasterKey {
+            ke,
+            ppube: Point::g_mul(&ke),
+        };
+        let r = u256_from_be_bytes(
+            &hex::decode("0000AAC0541779C8FC45E3E2CB25C12B5D2576B2129AE8BB5EE2CBE5EC9E785C")
+                .unwrap(),
+        );
+        let msg = b"Chinese IBE standard";
+        let c = msk.encrypt_with_r(idb, msg, &r).unwrap();
+        println!("C = {}", hex::encode_upper(&c));
+        // C1 and C2 match the standard byte for byte. C3 is HMAC-SM3(K2, C2) as
+        // in GmSSL, the standard's MAC(K2, C2) = SM3(C2 || K2) gives
+        // BA672387BCD6DE5016A158A52BB2E7FC429197BCAB70B25AFEE37A2B9DB9F367.
+        assert_eq!(
+            "04\
+             2445471164490618E1EE20528FF1D545B0F14C8BCAA44544F03DAB5DAC07D8FF\
+             42FFCA97D57CDDC05EA405F2E586FEB3A6930715532B8000759F13059ED59AC0",
+            hex::encode_upper(&c[..65])
+        );
+        assert_eq!(
+            "1B5F5B0E951489682F3E64E1378CDD5DA9513B1C",
+            hex::encode_upper(&c[65 + 32..])
+        );
+        assert_eq!(
+            "A9CF7B5A85182A3D500B46C18332392C06AF2655092434452E4B8D936DB13D81",
+            hex::encode_upper(&c[65..65 + 32])
+        );
+        let sk = msk.extract_key(idb).unwrap();
+        assert_eq!(msg.to_vec(), sk.decrypt(idb, &c).unwrap());
+    }
+
+    #[test]
+    fn test_verifier() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let alice = msk.extract_key(b"Alice").unwrap();
+        let bob = msk.extract_key(b"Bob").unwrap();
+        let verifier = msk.verifier(b"Alice");
+        for i in 0..3u8 {
+            let msg = [b'm', i];
+            let (h, s) = alice.sign(&msg).unwrap();
+            verifier.verify_sign(&msg, &h, &s).unwrap();
+            msk.verify_sign(b"Alice", &msg, &h, &s).unwrap();
+            assert!(verifier.verify_sign(&[b'x', i], &h, &s).is_err());
+        }
+
+        let (h, s) = bob.sign(b"from bob").unwrap();
+        assert!(verifier.verify_sign(b"from bob", &h, &s).is_err());
+        let bob_verifier = verifier.with_id(b"Bob");
+        assert_eq!(b"Bob", bob_verifier.id());
+        bob_verifier.verify_sign(b"from bob", &h, &s).unwrap();
+    }
+
+    #[test]
+    fn test_secret_debug() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        assert_eq!("Sm9SignMasterKey(…)", format!("{:?}", msk));
+        let key = msk.extract_key(b"Alice").unwrap();
+        assert_eq!("Sm9SignKey(…)", format!("{:?}", key));
+        let restored = Sm9SignMasterKey::from_secret(*msk.expose_secret()).unwrap();
+        assert!(restored.ppubs.point_equals(&msk.ppubs));
+        assert!(Sm9SignMasterKey::from_secret([0u64; 4]).is_err());
+
+        let msk = Sm9EncMasterKey::master_key_generate();
+        assert_eq!("Sm9EncMasterKey(…)", format!("{:?}", msk));
+        let key = msk.extract_key(b"Bob").unwrap();
+        let decryptor = Sm9Decryptor::new(key, b"Bob");
+        println!("{:?}", decryptor);
+        assert!(format!("{:?}", decryptor).contains("key: Sm9EncKey(…)"));
+    }
+
+    #[test]
+    fn test_traits() {
+        let ida = b"Alice";
+        let sign_msk = Sm9SignMasterKey::master_key_generate();
+        let sk = sign_msk.extract_key(ida).unwrap();
+        let sig = Signer::sign(&sk, b"hello traits").unwrap();
+        let sig = Sm9Signature::from_bytes(&sig.to_bytes()).unwrap();
+        let verifier = sign_msk.verifier(ida);
+        verifier.verify(b"hello traits", &sig).unwrap();
+        assert!(verifier.verify(b"hello trait", &sig).is_err());
+        assert!(sign_msk.verifier(b"Bob").verify(b"hello traits", &sig).is_err());
+
+        let idb = b"Bob";
+        let enc_msk = Sm9EncMasterKey::master_key_generate();
+        let decryptor = Sm9Decryptor::new(enc_msk.extract_key(idb).unwrap(), idb);
+        let ct = enc_msk.encryptor(idb).encrypt(b"hello traits").unwrap();
+        assert_eq!(b"hello traits".to_vec(), decryptor.decrypt(&ct).unwrap());
+        assert!(decryptor.decrypt(&ct[..64]).is_err());
+    }
 }
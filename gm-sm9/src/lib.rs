@@ -2,11 +2,29 @@
 use crate::fields::fp2::Fp2;
 use crate::points::{Point, TwistPoint};
 use crate::u256::U256;
+use gm_core::limb::{const_add_mod, const_eq, const_mont_mul, const_sub, const_sub_mod};
 
+pub mod bls;
+pub mod broadcast;
+#[cfg(test)]
+mod corpus;
 pub mod error;
 pub mod fields;
+pub mod g2_table;
+pub mod gmssl;
+#[cfg(all(test, gmssl_ffi))]
+mod gmssl_ffi;
+pub mod gt;
+pub mod identity;
 pub mod key;
+pub mod pbe;
+pub mod period;
+#[cfg(test)]
+mod point_vectors;
 pub mod points;
+#[cfg(test)]
+mod properties;
+pub mod signature;
 mod sm9_p256_table;
 pub mod u256;
 
@@ -30,10 +48,7 @@ pub mod u256;
 ///             .unwrap(),
 ///     );
 ///
-///     let msk = Sm9EncMasterKey {
-///         ke,
-///         ppube: Point::g_mul(&ke),
-///     };
+///     let msk = Sm9EncMasterKey::from_secret(ke).unwrap();
 ///
 ///     let r = msk.extract_key(&id);
 ///     let r_de = TwistPoint::from_hex(
@@ -46,9 +61,9 @@ pub mod u256;
 ///             "7AA5E47570DA7600CD760A0CF7BEAF71C447F3844753FE74FA7BA92CA7D3B55F",
 ///         ],
 ///     );
-///     assert_eq!(true, r.unwrap().de.point_equals(&r_de));
+///     assert_eq!(true, r.unwrap().expose_secret().point_equals(&r_de));
 ///
-///     let ret = msk.encrypt(&id, &data);
+///     let ret = msk.encrypt(&id, &data).unwrap();
 ///     println!("Ciphertext = {:?}", ret);
 ///
 ///     let m = r.unwrap().decrypt(&id, &ret).expect("Decryption failed");
@@ -76,10 +91,7 @@ pub mod u256;
 ///             &hex::decode("000130E78459D78545CB54C587E02CF480CE0B66340F319F348A1D5B1F2DC5F4")
 ///                 .unwrap(),
 ///         );
-///         let msk = Sm9SignMasterKey {
-///             ks,
-///             ppubs: TwistPoint::g_mul(&ks),
-///         };
+///         let msk = Sm9SignMasterKey::from_secret(ks).unwrap();
 ///
 ///         let r_ds = Point::from_hex([
 ///             "A5702F05CF1315305E2D6EB64B0DEB923DB1A0BCF0CAFF90523AC8754AA69820",
@@ -87,7 +99,7 @@ pub mod u256;
 ///         ]);
 ///         let r = msk.extract_key(&ida);
 ///         let ps = r.unwrap();
-///         assert_eq!(true, ps.ds.point_equals(&r_ds));
+///         assert_eq!(true, ps.expose_secret().point_equals(&r_ds));
 ///
 ///         println!("Message =    {:?}", &data);
 ///         let (h, s) = ps.sign(&data).unwrap();
@@ -326,10 +338,6 @@ pub(crate) const SM9_U256_N_MINUS_ONE_BARRETT_MU: [u64; 4] = [
     0x67980e0beb5759a6,
 ];
 
-pub(crate) const SM9_HID_ENC: u8 = 0x03;
-pub(crate) const SM9_HID_EXCH: u8 = 0x02;
-pub(crate) const SM9_HID_SIGN: u8 = 0x01;
-
 pub(crate) const SM9_HASH1_PREFIX: u8 = 0x01;
 pub(crate) const SM9_HASH2_PREFIX: u8 = 0x02;
 
@@ -394,3 +402,49 @@ pub(crate) const SM9_TWIST_POINT_MONT_P2: TwistPoint = TwistPoint {
     },
 };
 
+const fn const_mont(a: &U256) -> U256 {
+    const_mont_mul(a, &SM9_MODP_2E512, &SM9_P, SM9_P_PRIME[0])
+}
+
+const fn const_mul(a: &U256, b: &U256) -> U256 {
+    const_mont_mul(a, b, &SM9_P, SM9_P_PRIME[0])
+}
+
+/// `(a0 + a1 u)(b0 + b1 u)` with `u^2 = -2`
+const fn const_fp2_mul(a: &Fp2, b: &Fp2) -> Fp2 {
+    let t = const_mul(&a.c1, &b.c1);
+    let c0 = const_sub_mod(&const_mul(&a.c0, &b.c0), &const_add_mod(&t, &t, &SM9_P), &SM9_P);
+    let c1 = const_add_mod(&const_mul(&a.c0, &b.c1), &const_mul(&a.c1, &b.c0), &SM9_P);
+    Fp2 { c0, c1 }
+}
+
+// The constants above are checked when the crate is built, a transcription
+// error fails the build.
+const _: () = {
+    let one = [1, 0, 0, 0];
+    assert!(SM9_P[0].wrapping_mul(SM9_P_PRIME[0]) == u64::MAX);
+    assert!(SM9_P[0].wrapping_mul(SM9_MODP_MU) == 1);
+    assert!(const_eq(&const_sub(&SM9_P, &one).0, &SM9_P_MINUS_ONE));
+    assert!(const_eq(&const_sub(&SM9_P, &[2, 0, 0, 0]).0, &SM9_P_MINUS_TWO));
+    assert!(const_eq(&const_sub(&[0; 4], &SM9_N).0, &SM9_N_NEG));
+    assert!(const_eq(&const_sub(&SM9_N, &one).0, &SM9_N_MINUS_ONE));
+    assert!(const_eq(&const_sub(&SM9_N, &[2, 0, 0, 0]).0, &SM9_N_MINUS_TWO));
+    assert!(const_eq(&const_mont(&one), &SM9_MODP_MONT_ONE));
+    assert!(const_eq(&const_mont(&[5, 0, 0, 0]), &SM9_MODP_MONT_FIVE));
+    assert!(const_eq(&SM9_FP2_MONT_5U[1], &SM9_MODP_MONT_FIVE));
+
+    // P1 is on y^2 = x^3 + 5
+    let p1 = SM9_POINT_MONT_P1;
+    assert!(const_eq(&p1.z, &SM9_MODP_MONT_ONE));
+    let rhs = const_add_mod(&const_mul(&const_mul(&p1.x, &p1.x), &p1.x), &SM9_MODP_MONT_FIVE, &SM9_P);
+    assert!(const_eq(&const_mul(&p1.y, &p1.y), &rhs));
+
+    // P2 is on the twist y^2 = x^3 + 5u
+    let p2 = SM9_TWIST_POINT_MONT_P2;
+    assert!(const_eq(&p2.z.c0, &SM9_MODP_MONT_ONE) && const_eq(&p2.z.c1, &[0; 4]));
+    let x3 = const_fp2_mul(&const_fp2_mul(&p2.x, &p2.x), &p2.x);
+    let y2 = const_fp2_mul(&p2.y, &p2.y);
+    assert!(const_eq(&y2.c0, &x3.c0));
+    assert!(const_eq(&y2.c1, &const_add_mod(&x3.c1, &SM9_MODP_MONT_FIVE, &SM9_P)));
+};
+
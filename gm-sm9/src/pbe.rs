@@ -0,0 +1,205 @@
+//! Password protection of extracted SM9 user private keys.
+//!
+//! A KGC has to hand the user key over to its owner, this module seals the
+//! serialized key with a password:
+//!
+//! * PBKDF2-HMAC-SM3(password, salt, iterations) derives 48 bytes,
+//!   the first 16 bytes are the SM4 key, the last 32 bytes the MAC key
+//! * the key bytes are encrypted with SM4-CBC under a random IV
+//! * HMAC-SM3 authenticates everything before the tag
+//!
+//! Layout: `version(1) || hid(1) || iterations(4, BE) || salt(16) || iv(16) || ciphertext || mac(32)`
+//!
+//! The iteration count is read before the MAC can be checked, so counts above
+//! [`MAX_ITERATIONS`] fail with [`Sm9Error::TooManyIterations`] instead of
+//! tying up the reader, and cannot be used when wrapping either.
+use gm_core::ct_eq;
+use gm_core::encoding::{pem_decode_label, pem_encode};
+use gm_sm3::hmac::sm3_hmac;
+use gm_sm3::pbkdf2::{pbkdf2_sm3, MAX_ITERATIONS};
+use gm_sm4::{CipherMode, Sm4CipherMode};
+use rand::RngCore;
+
+use crate::error::{Sm9Error, Sm9Result};
+use crate::key::{Hid, Sm9EncKey, Sm9SignKey};
+
+/// Recommended PBKDF2 iteration count for newly wrapped keys
+pub const SM9_PBE_DEFAULT_ITERATIONS: u32 = 10000;
+
+pub const SM9_PBE_SALT_LEN: usize = 16;
+
+/// PEM labels of the sealed keys, the label is checked on input
+pub const SM9_SIGN_KEY_PEM_LABEL: &str = "ENCRYPTED SM9 SIGN PRIVATE KEY";
+pub const SM9_ENC_KEY_PEM_LABEL: &str = "ENCRYPTED SM9 ENC PRIVATE KEY";
+
+const SM9_PBE_VERSION: u8 = 0x01;
+const HEADER_LEN: usize = 1 + 1 + 4 + SM9_PBE_SALT_LEN + 16;
+const MAC_LEN: usize = 32;
+
+/// Seal a signature user key with `password`.
+pub fn wrap_sign_key(key: &Sm9SignKey, password: &[u8], iterations: u32) -> Sm9Result<Vec<u8>> {
+    wrap(&key.to_bytes(), Hid::Sign.value(), password, iterations)
+}
+
+pub fn unwrap_sign_key(data: &[u8], password: &[u8]) -> Sm9Result<Sm9SignKey> {
+    let key = unwrap(data, Hid::Sign.value(), password)?;
+    Sm9SignKey::from_bytes(&key)
+}
+
+/// Seal an encryption user key with `password`.
+pub fn wrap_enc_key(key: &Sm9EncKey, password: &[u8], iterations: u32) -> Sm9Result<Vec<u8>> {
+    wrap(&key.to_bytes(), Hid::Enc.value(), password, iterations)
+}
+
+pub fn unwrap_enc_key(data: &[u8], password: &[u8]) -> Sm9Result<Sm9EncKey> {
+    let key = unwrap(data, Hid::Enc.value(), password)?;
+    Sm9EncKey::from_bytes(&key)
+}
+
+/// [`wrap_sign_key`] armored as PEM.
+pub fn wrap_sign_key_pem(key: &Sm9SignKey, password: &[u8], iterations: u32) -> Sm9Result<String> {
+    to_pem(SM9_SIGN_KEY_PEM_LABEL, &wrap_sign_key(key, password, iterations)?)
+}
+
+pub fn unwrap_sign_key_pem(pem: &str, password: &[u8]) -> Sm9Result<Sm9SignKey> {
+    unwrap_sign_key(&from_pem(SM9_SIGN_KEY_PEM_LABEL, pem)?, password)
+}
+
+/// [`wrap_enc_key`] armored as PEM.
+pub fn wrap_enc_key_pem(key: &Sm9EncKey, password: &[u8], iterations: u32) -> Sm9Result<String> {
+    to_pem(SM9_ENC_KEY_PEM_LABEL, &wrap_enc_key(key, password, iterations)?)
+}
+
+pub fn unwrap_enc_key_pem(pem: &str, password: &[u8]) -> Sm9Result<Sm9EncKey> {
+    unwrap_enc_key(&from_pem(SM9_ENC_KEY_PEM_LABEL, pem)?, password)
+}
+
+fn to_pem(label: &str, data: &[u8]) -> Sm9Result<String> {
+    pem_encode(label, data).map_err(|_| Sm9Error::InvalidEncoding)
+}
+
+fn from_pem(label: &str, pem: &str) -> Sm9Result<Vec<u8>> {
+    pem_decode_label(label, pem).map_err(|_| Sm9Error::InvalidEncoding)
+}
+
+fn wrap(key: &[u8], hid: u8, password: &[u8], iterations: u32) -> Sm9Result<Vec<u8>> {
+    if iterations == 0 {
+        return Err(Sm9Error::InvalidPassword);
+    }
+    if iterations > MAX_ITERATIONS {
+        return Err(Sm9Error::TooManyIterations);
+    }
+    let mut rng = rand::thread_rng();
+    let mut salt = [0u8; SM9_PBE_SALT_LEN];
+    let mut iv = [0u8; 16];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut iv);
+
+    let dk = pbkdf2_sm3(password, &salt, iterations, 48).map_err(|_| Sm9Error::InvalidPassword)?;
+    let cipher = Sm4CipherMode::new(&dk[..16], CipherMode::Cbc).unwrap();
+    let ct = cipher.encrypt(key, &iv).unwrap();
+
+    let mut out: Vec<u8> = vec![SM9_PBE_VERSION, hid];
+    out.extend_from_slice(&iterations.to_be_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&iv);
+    out.extend_from_slice(&ct);
+    let mac = sm3_hmac(&dk[16..], &out);
+    out.extend_from_slice(&mac);
+    Ok(out)
+}
+
+fn unwrap(data: &[u8], hid: u8, password: &[u8]) -> Sm9Result<Vec<u8>> {
+    if data.len() < HEADER_LEN + 16 + MAC_LEN
+        || data[0] != SM9_PBE_VERSION
+        || data[1] != hid
+    {
+        return Err(Sm9Error::InvalidPassword);
+    }
+    let iterations = u32::from_be_bytes([data[2], data[3], data[4], data[5]]);
+    if iterations == 0 {
+        return Err(Sm9Error::InvalidPassword);
+    }
+    if iterations > MAX_ITERATIONS {
+        return Err(Sm9Error::TooManyIterations);
+    }
+    let salt = &data[6..6 + SM9_PBE_SALT_LEN];
+    let iv = &data[6 + SM9_PBE_SALT_LEN..HEADER_LEN];
+    let (body, mac) = data.split_at(data.len() - MAC_LEN);
+
+    let dk = pbkdf2_sm3(password, salt, iterations, 48).map_err(|_| Sm9Error::InvalidPassword)?;
+    if !ct_eq(&sm3_hmac(&dk[16..], body), mac) {
+        return Err(Sm9Error::InvalidPassword);
+    }
+    let cipher = Sm4CipherMode::new(&dk[..16], CipherMode::Cbc).unwrap();
+    cipher
+        .decrypt(&body[HEADER_LEN..], iv)
+        .map_err(|_| Sm9Error::InvalidPassword)
+}
+
+#[cfg(test)]
+mod test_pbe {
+    use crate::error::Sm9Error;
+    use crate::key::{Sm9EncMasterKey, Sm9SignMasterKey};
+    use crate::pbe::{
+        unwrap_enc_key, unwrap_enc_key_pem, unwrap_sign_key, unwrap_sign_key_pem, wrap_enc_key,
+        wrap_sign_key, wrap_sign_key_pem,
+    };
+    use gm_sm3::pbkdf2::MAX_ITERATIONS;
+
+    #[test]
+    fn test_wrap_sign_key() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Alice").unwrap();
+        let sealed = wrap_sign_key(&key, b"123456", 100).unwrap();
+        println!("sealed = {}", hex::encode(&sealed));
+
+        let opened = unwrap_sign_key(&sealed, b"123456").unwrap();
+        assert!(opened.ds.point_equals(&key.ds));
+        assert_eq!(key.ppubs.to_bytes_be(), opened.ppubs.to_bytes_be());
+
+        assert!(unwrap_sign_key(&sealed, b"654321").err() == Some(Sm9Error::InvalidPassword));
+        assert!(unwrap_enc_key(&sealed, b"123456").err() == Some(Sm9Error::InvalidPassword));
+    }
+
+    #[test]
+    fn test_wrap_enc_key() {
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Bob").unwrap();
+        let sealed = wrap_enc_key(&key, b"password", 100).unwrap();
+        let opened = unwrap_enc_key(&sealed, b"password").unwrap();
+        assert_eq!(key.to_bytes(), opened.to_bytes());
+
+        let mut tampered = sealed.clone();
+        tampered[40] ^= 0x01;
+        assert!(unwrap_enc_key(&tampered, b"password").err() == Some(Sm9Error::InvalidPassword));
+
+        // a crafted count is refused before any PBKDF2 work
+        let mut costly = sealed.clone();
+        costly[2..6].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(unwrap_enc_key(&costly, b"password").err() == Some(Sm9Error::TooManyIterations));
+        costly[2..6].copy_from_slice(&(MAX_ITERATIONS + 1).to_be_bytes());
+        assert!(unwrap_enc_key(&costly, b"password").err() == Some(Sm9Error::TooManyIterations));
+        assert!(wrap_enc_key(&key, b"password", MAX_ITERATIONS + 1).err() == Some(Sm9Error::TooManyIterations));
+
+        let c = msk.encrypt(b"Bob", b"hello").unwrap();
+        assert_eq!(b"hello".to_vec(), opened.decrypt(b"Bob", &c).unwrap());
+    }
+
+    #[test]
+    fn test_wrap_key_pem() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key(b"Alice").unwrap();
+        let pem = wrap_sign_key_pem(&key, b"123456", 100).unwrap();
+        println!("{}", pem);
+        assert!(pem.starts_with("-----BEGIN ENCRYPTED SM9 SIGN PRIVATE KEY-----\n"));
+        let opened = unwrap_sign_key_pem(&pem, b"123456").unwrap();
+        assert_eq!(key.to_bytes(), opened.to_bytes());
+
+        // a sign key is not accepted as an enc key, whatever the password
+        assert!(unwrap_enc_key_pem(&pem, b"123456").err() == Some(Sm9Error::InvalidEncoding));
+        let relabeled = pem.replace("SM9 SIGN", "SM9 ENC");
+        assert!(unwrap_enc_key_pem(&relabeled, b"123456").err() == Some(Sm9Error::InvalidPassword));
+        assert!(unwrap_sign_key_pem(&pem[1..], b"123456").err() == Some(Sm9Error::InvalidEncoding));
+    }
+}
@@ -0,0 +1,214 @@
+//! Revocation by validity period: keys are extracted for `ID || yyyymm`.
+//!
+//! A sender encrypts to, and a verifier checks against, the identity of the
+//! current month. A KGC that stops extracting keys for a user has revoked them
+//! from the next month on. It does not extract keys for a month that is
+//! already over.
+use std::fmt::{Display, Formatter};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Sm9Error, Sm9Result};
+use crate::key::{Sm9EncKey, Sm9EncMasterKey, Sm9SignKey, Sm9SignMasterKey};
+use crate::points::Point;
+use crate::u256::U256;
+
+/// A calendar month in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ValidityPeriod {
+    year: u16,
+    month: u8,
+}
+
+impl ValidityPeriod {
+    pub fn new(year: u16, month: u8) -> Sm9Result<ValidityPeriod> {
+        if year > 9999 || !(1..=12).contains(&month) {
+            return Err(Sm9Error::InvalidPeriod);
+        }
+        Ok(ValidityPeriod { year, month })
+    }
+
+    /// The month of `secs` seconds after the Unix epoch, an error after 9999.
+    pub fn from_unix_secs(secs: u64) -> Sm9Result<ValidityPeriod> {
+        // civil_from_days, Howard Hinnant
+        let z = (secs / 86400) as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        let year = u16::try_from(year).map_err(|_| Sm9Error::InvalidPeriod)?;
+        Self::new(year, month as u8)
+    }
+
+    /// The current month of the system clock
+    pub fn current() -> Sm9Result<ValidityPeriod> {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self::from_unix_secs(secs)
+    }
+
+    /// The following month, `None` after 9999-12.
+    pub fn next(&self) -> Option<ValidityPeriod> {
+        match self.month {
+            12 => Self::new(self.year + 1, 1).ok(),
+            m => Some(ValidityPeriod {
+                year: self.year,
+                month: m + 1,
+            }),
+        }
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// `yyyymm` in ASCII
+    pub fn to_bytes(&self) -> [u8; 6] {
+        let (y, m) = (self.year, self.month);
+        let digit = |n: u16| b'0' + (n % 10) as u8;
+        [
+            digit(y / 1000),
+            digit(y / 100),
+            digit(y / 10),
+            digit(y),
+            digit(u16::from(m) / 10),
+            digit(u16::from(m)),
+        ]
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<ValidityPeriod> {
+        if b.len() != 6 || !b.iter().all(u8::is_ascii_digit) {
+            return Err(Sm9Error::InvalidPeriod);
+        }
+        let number = |d: &[u8]| d.iter().fold(0u16, |n, c| n * 10 + u16::from(c - b'0'));
+        Self::new(number(&b[..4]), number(&b[4..]) as u8)
+    }
+}
+
+impl Display for ValidityPeriod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}{:02}", self.year, self.month)
+    }
+}
+
+/// `ID || yyyymm`, the identity keys are extracted for
+pub fn id_with_period(id: &[u8], period: &ValidityPeriod) -> Vec<u8> {
+    [id, &period.to_bytes()].concat()
+}
+
+fn check_not_expired(period: &ValidityPeriod, now: &ValidityPeriod) -> Sm9Result<()> {
+    if period < now {
+        return Err(Sm9Error::Expired);
+    }
+    Ok(())
+}
+
+impl Sm9EncMasterKey {
+    /// The key of `id` for `period`, refused once `period` is before `now`.
+    pub fn extract_key_for_period(
+        &self,
+        id: &[u8],
+        period: &ValidityPeriod,
+        now: &ValidityPeriod,
+    ) -> Sm9Result<Sm9EncKey> {
+        check_not_expired(period, now)?;
+        self.extract_key(&id_with_period(id, period))
+            .ok_or(Sm9Error::ZeroDivisor)
+    }
+
+    /// Encrypt to `id` for the current month.
+    pub fn encrypt_for_current_period(&self, id: &[u8], data: &[u8]) -> Sm9Result<Vec<u8>> {
+        self.encrypt(&id_with_period(id, &ValidityPeriod::current()?), data)
+    }
+}
+
+impl Sm9EncKey {
+    pub fn decrypt_for_period(&self, id: &[u8], period: &ValidityPeriod, data: &[u8]) -> Sm9Result<Vec<u8>> {
+        self.decrypt(&id_with_period(id, period), data)
+    }
+}
+
+impl Sm9SignMasterKey {
+    /// The key of `id` for `period`, refused once `period` is before `now`.
+    pub fn extract_key_for_period(
+        &self,
+        id: &[u8],
+        period: &ValidityPeriod,
+        now: &ValidityPeriod,
+    ) -> Sm9Result<Sm9SignKey> {
+        check_not_expired(period, now)?;
+        self.extract_key(&id_with_period(id, period))
+            .ok_or(Sm9Error::ZeroDivisor)
+    }
+
+    /// Verify a signature of `id` made with the key of the current month.
+    pub fn verify_sign_for_current_period(&self, id: &[u8], data: &[u8], h: &U256, s: &Point) -> Sm9Result<()> {
+        self.verify_sign(&id_with_period(id, &ValidityPeriod::current()?), data, h, s)
+    }
+}
+
+#[cfg(test)]
+mod test_period {
+    use super::*;
+
+    #[test]
+    fn test_validity_period() {
+        let p = ValidityPeriod::from_unix_secs(1_700_000_000).unwrap();
+        assert_eq!("202311", p.to_string());
+        assert_eq!(p, ValidityPeriod::from_bytes(b"202311").unwrap());
+        assert_eq!(*b"202311", p.to_bytes());
+        assert_eq!("197001", ValidityPeriod::from_unix_secs(0).unwrap().to_string());
+        assert_eq!("200002", ValidityPeriod::from_unix_secs(951_782_399).unwrap().to_string());
+        assert_eq!("200003", ValidityPeriod::from_unix_secs(951_868_800).unwrap().to_string());
+        assert_eq!("202401", ValidityPeriod::new(2023, 12).unwrap().next().unwrap().to_string());
+        assert!(p < p.next().unwrap());
+        for bad in [&b"202313"[..], b"202300", b"2023-1", b"20231"] {
+            assert!(ValidityPeriod::from_bytes(bad).is_err());
+        }
+        assert_eq!(b"alice@example.com202311".to_vec(), id_with_period(b"alice@example.com", &p));
+    }
+
+    #[test]
+    fn test_validity_period_bounds() {
+        let last = ValidityPeriod::new(9999, 12).unwrap();
+        assert_eq!(*b"999912", last.to_bytes());
+        assert_eq!(None, last.next());
+        assert_eq!(Some(last), ValidityPeriod::new(9999, 11).unwrap().next());
+        assert_eq!(*b"000101", ValidityPeriod::new(1, 1).unwrap().to_bytes());
+
+        // 253402300799 is 9999-12-31T23:59:59Z
+        assert_eq!(last, ValidityPeriod::from_unix_secs(253_402_300_799).unwrap());
+        assert_eq!(Err(Sm9Error::InvalidPeriod), ValidityPeriod::from_unix_secs(253_402_300_800));
+        assert_eq!(Err(Sm9Error::InvalidPeriod), ValidityPeriod::from_unix_secs(u64::MAX));
+    }
+
+    #[test]
+    fn test_period_keys() {
+        let now = ValidityPeriod::current().unwrap();
+        let id = b"alice@example.com";
+
+        let msk = Sm9EncMasterKey::master_key_generate();
+        let key = msk.extract_key_for_period(id, &now, &now).unwrap();
+        let c = msk.encrypt_for_current_period(id, b"hello").unwrap();
+        assert_eq!(b"hello".to_vec(), key.decrypt_for_period(id, &now, &c).unwrap());
+        // a key for another month does not decrypt
+        let old = ValidityPeriod::new(2000, 1).unwrap();
+        assert!(msk.extract_key_for_period(id, &old, &now).err() == Some(Sm9Error::Expired));
+        let later = now.next().unwrap();
+        let next = msk.extract_key_for_period(id, &later, &now).unwrap();
+        assert!(next.decrypt_for_period(id, &later, &c).is_err());
+
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let key = msk.extract_key_for_period(id, &now, &now).unwrap();
+        let (h, s) = key.sign(b"msg").unwrap();
+        msk.verify_sign_for_current_period(id, b"msg", &h, &s).unwrap();
+        assert!(msk.verify_sign(id, b"msg", &h, &s).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+//! Point arithmetic of G1 and G2 against vectors from an independent affine
+//! implementation, `scripts/point_vectors.py`, committed at the end of this
+//! file: sums with the identity, `P + (-P)` and `P + P`, doubling the
+//! identity, and multiples by `0`, `1`, `n - 2`, `n - 1`, `n` and wide scalars,
+//! of the generator and of `[7]` times it. Every scalar multiplication of the
+//! crate must agree.
+use gm_core::encoding::{hex_decode, hex_encode};
+use gm_core::group::{ladder_mul, window_mul, wnaf_mul, Group};
+
+use crate::points::{twist_point_add_full, Point, TwistPoint};
+use crate::u256::{u256_from_be_bytes, U256};
+
+/// The empty string is the infinity
+fn g1(s: &str) -> Point {
+    if s.is_empty() {
+        return Point::zero();
+    }
+    Point::from_bytes(&hex_decode(s).unwrap()).unwrap()
+}
+
+fn g2(s: &str) -> TwistPoint {
+    if s.is_empty() {
+        return TwistPoint::zero();
+    }
+    TwistPoint::from_bytes(&hex_decode(s).unwrap()).unwrap()
+}
+
+fn encode_g1(p: &Point) -> String {
+    if p.is_zero() {
+        return String::new();
+    }
+    hex_encode(&p.to_bytes_be())
+}
+
+fn encode_g2(p: &TwistPoint) -> String {
+    if p.is_zero() {
+        return String::new();
+    }
+    hex_encode(&p.to_bytes_be())
+}
+
+fn scalar(k: &str) -> U256 {
+    u256_from_be_bytes(&hex_decode(k).unwrap())
+}
+
+#[test]
+fn test_g1() {
+    for (a, b, sum) in G1_ADD {
+        let (p, q) = (g1(a), g1(b));
+        assert_eq!(*sum, encode_g1(&p.point_add(&q)), "{} + {}", a, b);
+        assert_eq!(*sum, encode_g1(&Group::add(&q, &p)), "{} + {}", b, a);
+    }
+    for (a, double) in G1_DOUBLE {
+        assert_eq!(*double, encode_g1(&g1(a).point_double()), "2 * {}", a);
+    }
+    let generator = encode_g1(&Point::g_mul(&[1, 0, 0, 0]));
+    for (k, a, product) in G1_MUL {
+        let (k, p) = (scalar(k), g1(a));
+        assert_eq!(*product, encode_g1(&p.point_mul(&k)), "{:x?} * {}", k, a);
+        assert_eq!(*product, encode_g1(&window_mul(&p, &k)));
+        assert_eq!(*product, encode_g1(&ladder_mul(&p, &k)));
+        assert_eq!(*product, encode_g1(&wnaf_mul(&p, &k)));
+        if *a == generator {
+            assert_eq!(*product, encode_g1(&Point::g_mul(&k)));
+        }
+    }
+}
+
+#[test]
+fn test_g2() {
+    for (a, b, sum) in G2_ADD {
+        let (p, q) = (g2(a), g2(b));
+        assert_eq!(*sum, encode_g2(&twist_point_add_full(&p, &q)), "{} + {}", a, b);
+        // the mixed addition takes an affine right-hand side
+        assert_eq!(*sum, encode_g2(&p.point_add(&q)), "{} + {}", a, b);
+    }
+    for (a, double) in G2_DOUBLE {
+        assert_eq!(*double, encode_g2(&g2(a).point_double()), "2 * {}", a);
+    }
+    let generator = encode_g2(&TwistPoint::g_mul(&[1, 0, 0, 0]));
+    for (k, a, product) in G2_MUL {
+        let (k, p) = (scalar(k), g2(a));
+        assert_eq!(*product, encode_g2(&p.point_mul(&k)), "{:x?} * {}", k, a);
+        assert_eq!(*product, encode_g2(&window_mul(&p, &k)));
+        assert_eq!(*product, encode_g2(&ladder_mul(&p, &k)));
+        assert_eq!(*product, encode_g2(&wnaf_mul(&p, &k)));
+        if *a == generator {
+            assert_eq!(*product, encode_g2(&TwistPoint::g_mul(&k)));
+        }
+    }
+}
+
+// generated by scripts/point_vectors.py sm9, do not edit
+const G1_ADD: &[(&str, &str, &str)] = &[
+    ("0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0498308a2cc761cd353d43546fb2f8b3a661d539acee2eee2f33347c295563f4b25c8edf80776ea1ddca48a0cbb2fee68bd1ccbac88b2a814bc25b85d0d412a1fd",
+     "046ac1f0dd2548250f9ca4a3926e98b10e294b3dca9f52a9be66f106a10560255471bbfd12e9a21877b25af9f5ab96b9178bc072871e7cf2491291c84942a70aee"),
+    ("0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd94417225b381c0ea72f3463d99556b8905d6927f201acaa6d9294e50d9129f67",
+     ""),
+    ("",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616"),
+    ("0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616"),
+    ("",
+     "",
+     ""),
+    ("044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "0455e10cd51211fa67b8b4bd436760f6f76d56542c35f428c094085a992eeb7dbe2502ec22bf6067e04ed6a52e091317da497d86fbc162b44db3c315f25633003e"),
+    ("04470f8b28ef0d3f2c72bf69aec077958527016f6e5c1c6d45bd3cb70d8c023b3a2f3162719be8c0ca44d88e896494940f995ecfff61063253f039155f2252a2f1",
+     "0499aa6e6ab916e3f473b7c43220d4e80cf63dc3427eca65eda1186d5aefd4824bae350bd9fc141735a70236b82491d7c13dc37152a4e7c32a405646ac2d170660",
+     "046e7bebd815b96e19d2c22994b0e303a7bb3a0141bc2f105c58cdbe17a53966ab0732890d1cb26f9b0c85f6c0f811d18128a8756630dcf845b7b9f3598b20dc48"),
+    ("0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd94417225b381c0ea72f3463d99556b8905d6927f201acaa6d9294e50d9129f67",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     ""),
+    ("04470f8b28ef0d3f2c72bf69aec077958527016f6e5c1c6d45bd3cb70d8c023b3a2f3162719be8c0ca44d88e896494940f995ecfff61063253f039155f2252a2f1",
+     "0499aa6e6ab916e3f473b7c43220d4e80cf63dc3427eca65eda1186d5aefd4824b080af426068f8fbc2f017497d0fcef83e42f21f875932bb1a519547bb63a3f1d",
+     "043f0d3b1d62644f0ea610cc25dbc69f61b0b910bd3b4298360aa2ede9952711639030818acac69ba07e7d84eca4983737896040d8d3a8d705cbcebaf207d83733"),
+];
+
+const G1_DOUBLE: &[(&str, &str)] = &[
+    ("0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0498308a2cc761cd353d43546fb2f8b3a661d539acee2eee2f33347c295563f4b25c8edf80776ea1ddca48a0cbb2fee68bd1ccbac88b2a814bc25b85d0d412a1fd"),
+    ("",
+     ""),
+    ("044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "0455e10cd51211fa67b8b4bd436760f6f76d56542c35f428c094085a992eeb7dbe2502ec22bf6067e04ed6a52e091317da497d86fbc162b44db3c315f25633003e"),
+    ("04470f8b28ef0d3f2c72bf69aec077958527016f6e5c1c6d45bd3cb70d8c023b3a2f3162719be8c0ca44d88e896494940f995ecfff61063253f039155f2252a2f1",
+     "042221ffb304b474997b2268a4704b52bb76b0887e1ecad0dbcdba527c97ea20a88d8c6a1572e2795019ca98cffbe14c6521df726f45e9a48ccfba8e08f24376df"),
+    ("0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd94417225b381c0ea72f3463d99556b8905d6927f201acaa6d9294e50d9129f67",
+     "0498308a2cc761cd353d43546fb2f8b3a661d539acee2eee2f33347c295563f4b259b1207f8b3505140bbb0a84428fe0b95025d8828f506d90231415570f3ea380"),
+];
+
+const G1_MUL: &[(&str, &str, &str)] = &[
+    ("0000000000000000000000000000000000000000000000000000000000000000",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     ""),
+    ("0000000000000000000000000000000000000000000000000000000000000000",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     ""),
+    ("0000000000000000000000000000000000000000000000000000000000000001",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616"),
+    ("0000000000000000000000000000000000000000000000000000000000000001",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070"),
+    ("0000000000000000000000000000000000000000000000000000000000000002",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0498308a2cc761cd353d43546fb2f8b3a661d539acee2eee2f33347c295563f4b25c8edf80776ea1ddca48a0cbb2fee68bd1ccbac88b2a814bc25b85d0d412a1fd"),
+    ("0000000000000000000000000000000000000000000000000000000000000002",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "0455e10cd51211fa67b8b4bd436760f6f76d56542c35f428c094085a992eeb7dbe2502ec22bf6067e04ed6a52e091317da497d86fbc162b44db3c315f25633003e"),
+    ("0000000000000000000000000000000000000000000000000000000000000003",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "046ac1f0dd2548250f9ca4a3926e98b10e294b3dca9f52a9be66f106a10560255471bbfd12e9a21877b25af9f5ab96b9178bc072871e7cf2491291c84942a70aee"),
+    ("0000000000000000000000000000000000000000000000000000000000000003",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "042a062b2022f65cc1f6fe9a25fa52c663f0c9039ffb1d0a066d3d850e46a3ba19ae5b00f7dd46e49fcbf671e6b46e5b033aac3b7aa824825237b0cfb07738e076"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf23",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0498308a2cc761cd353d43546fb2f8b3a661d539acee2eee2f33347c295563f4b259b1207f8b3505140bbb0a84428fe0b95025d8828f506d90231415570f3ea380"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf23",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "0455e10cd51211fa67b8b4bd436760f6f76d56542c35f428c094085a992eeb7dbe913d13dd43433f11872d0621ec7baf6ad8750c4f59183a8e31ac85358d1e453f"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf24",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd94417225b381c0ea72f3463d99556b8905d6927f201acaa6d9294e50d9129f67"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf24",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd9472f5b9cd2233ae9fd56e6262c028b785acdaed95f5f7d7e46a4cd4e4c5c150d"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf25",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     ""),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf25",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     ""),
+    ("8000000000000000000000000000000000000000000000000000000000000000",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "044a0b8c5147798404e36495f67cf4a82f644cbc8aab5b0d2025ca8e42622c9de037618f94d58b19fabb3fd346c2ac93485b153d3403e1c5b249d05ed78d3099e6"),
+    ("8000000000000000000000000000000000000000000000000000000000000000",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "0454971983062513aa3a5e11b25b2b194ba89cc954b339abb9dfeea75fc33ea6fc6714822a6d700606a883e186d5cca16233c39e8efcf7198e9a1836a00f0abd55"),
+    ("00000000000000000000000000000000ffffffffffffffffffffffffffffffff",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "042c3fc7035c38bec8c48d53d18de6a1ba9b74621a5ef43803d42d00dcf646bfeb23469210423fe15267e784dbd7bb22445c7ab525aa59289717a336cc59c97c7e"),
+    ("00000000000000000000000000000000ffffffffffffffffffffffffffffffff",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "0442d97c33ff578136d14f8ebda9c50d1dc8a2e888911c1772a505a1d5588daf7a1087cad21d0eb124e22978c23ac4a4e54adbc529603cb042e174fc36a21317da"),
+    ("2b5ebaa061076dc3ba6ace6c0a78250fb339a4769ddcc6f8efb6fbfe8de4ab48",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "044204469a15a323274569540ce0bbc0b4345772d414f542b17e74d8724d16787500694a76fd8d7cae3e1f614195a1f0916336af98a2325c1a63679778aa09c4da"),
+    ("2b5ebaa061076dc3ba6ace6c0a78250fb339a4769ddcc6f8efb6fbfe8de4ab48",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "049739b8b339c41cb27f75fa2d54779d02067e0f3072c54f416529d682e533207d9b6b99405b8c448424e59175e949811ce118405c54ba66dd917ced1a9c3979d4"),
+    ("2b1e1885283b73a66c2ea417b99de255f386825473b7a490f23b2cc4b4174a68",
+     "0493de051d62bf718ff5ed0704487d01d6e1e4086909dc3280e8c4e4817c66dddd21fe8dda4f21e607631065125c395bbc1c1c00cbfa6024350c464cd70a3ea616",
+     "041ba1db9297d89b2d4741b7a5702ca79bc11825c93e8a054b74853e9d82e7a1ab1f61b08d0e768a8a744eac0dc7b9588706a005cc13b7f7cdb41934a0cae78427"),
+    ("2b1e1885283b73a66c2ea417b99de255f386825473b7a490f23b2cc4b4174a68",
+     "044e23307000bfdfa62183a3446649818889314051c95daf09dd2823b988e21dd96f10a46330806c07d8acc529c98c3bccc724e471bb1b715d9ecacdd996f53070",
+     "0436b2c4ad4e4d36bffb2597b309eb1c88594ac7d57e7be31bf3dc2746434f3fc569201e07c8b95990c54c2c6ae13961e3ff349b95718c440af5ab4e1b855f5877"),
+];
+
+const G2_ADD: &[(&str, &str, &str)] = &[
+    ("0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "04513f149ab53e94bb3a0367c61ff87670e025db30c57f84594e4ba4d7b3c656cf2a74f8561b91993205eb512576ad56221ea5963f3da078240d55594fb051ea86776de41db0511b8976d69c982dd4757d641487c68d13cbee7069396c20cd34598e3d9ec4e63d5b9f83081fb97b715430c8bfc6f1a1321a89627b9a4e8961c7bd",
+     "044dd9b503b00f0e8334e5cbdc9ff80deb4b207a1b1fda2382f3812bd5687937c09e5437ea263653ea0617ca82c5ce5db4937dece2f762a6fbdae7fb3032f9b154b1174c2d2b36cee03e1a7081eb71f60c35fac603f2b550218ec935c1e00bdd5b3d491f4ffb2a4ab249e396fe8e58b6e8cb23ef935309e576bc5a9a3b4fd97090"),
+    ("0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b9eef64f6d41f4adf6f499e29c8cfe0581abbe9db7733261e6001d3bc5e6559e70e70d72ae8e5694b76d23b3ab8673752da02d8b27360e6ca8359df8219b79db6",
+     ""),
+    ("",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7"),
+    ("0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7"),
+    ("",
+     "",
+     ""),
+    ("04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "0472957ba4f859a505e27c8eae9e3a31af529cb5680eb33c0a517abf442cb246382035f6ef7a3fbd1bc51fa3c4c9a53835b593dea01a79fcdbeb7c484fd14167019dc4a1382a45378b3616885afa7d13d38e1bec464c234461fe5757d74abc609015152d394efc7d77578ed4d7bdaf8f72d098908451d7c217e78081a219d4cfb8"),
+    ("043a42a6f74cdec5ae496c68ae3fa1a69a74c8a6d727e7002cee2468898b6c6940a4ab22aa96c4e2f79eed9fe888d33408fd0ed7a7db86ed9ba2814c721e56d2165859e07d24ddd964c4a01b03709f468ca0aab77abfd16a48545d6ec6abf08dff20a12437e2c090658128c97b1bbb01e2121a4dc8ccbb72b4ffbe9a3ed24fc643",
+     "0448a8e95debe7831f619aac340163396e427c7aace4d27c927a9296cd4e2f5d429b88018f081c4c285a5c2a096d53690f071ab54814ae295be733d893822e77e76a4998c8fccbae87fc6c5ee45638380a347f8eeb0445a21d5c84e5cd3093ee11718c9363c587fb522bc2f9f2c18fd93b33e3aa7ce744759721de49e6d749db8b",
+     "04a108b0e862df1977b58cc9b5f1a44dd1340afb4e7ed1b637c82c7f1b221792c71aa3e67d66f2bc89bae04875537c48ed78bf2933b4630c714af47f88036fdfd5018ece2c0112d70beadcfeaadf6d99b98125be0b32e3b8aa891162a2219342cd4f9e5141dee9ffd9cb39def7d195b1770f8dbdf1e68ed6dca15aface3fe5d819"),
+    ("0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b9eef64f6d41f4adf6f499e29c8cfe0581abbe9db7733261e6001d3bc5e6559e70e70d72ae8e5694b76d23b3ab8673752da02d8b27360e6ca8359df8219b79db6",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     ""),
+    ("043a42a6f74cdec5ae496c68ae3fa1a69a74c8a6d727e7002cee2468898b6c6940a4ab22aa96c4e2f79eed9fe888d33408fd0ed7a7db86ed9ba2814c721e56d2165859e07d24ddd964c4a01b03709f468ca0aab77abfd16a48545d6ec6abf08dff20a12437e2c090658128c97b1bbb01e2121a4dc8ccbb72b4ffbe9a3ed24fc643",
+     "0448a8e95debe7831f619aac340163396e427c7aace4d27c927a9296cd4e2f5d429b88018f081c4c285a5c2a096d53690f071ab54814ae295be733d893822e77e74bf6673705d7f869d9974c6b9f568f3aed73046016354cbe88eab55ab2bd576c44b36c9c3d1bab9faa40b15d33feee09ee0ee8ce33367944c39151410c0769f2",
+     "046e32f3d7f12a6b49e7c2fda226791ffcaf26622eadeb5f02c521e78543a54d000cb14167f7909be2ed0e475bd2ec465d50c9a8cdffa8d724ccbe7a0242e7ba625cb783925c9b00c28093713906aa3574c2ebf68d5772e674703fca86f79fe60994d95e78ac9f459fa4997d9e36972f55c0100cd78b55cdccad1fcb55a882e71f"),
+];
+
+const G2_DOUBLE: &[(&str, &str)] = &[
+    ("0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "04513f149ab53e94bb3a0367c61ff87670e025db30c57f84594e4ba4d7b3c656cf2a74f8561b91993205eb512576ad56221ea5963f3da078240d55594fb051ea86776de41db0511b8976d69c982dd4757d641487c68d13cbee7069396c20cd34598e3d9ec4e63d5b9f83081fb97b715430c8bfc6f1a1321a89627b9a4e8961c7bd"),
+    ("",
+     ""),
+    ("04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "0472957ba4f859a505e27c8eae9e3a31af529cb5680eb33c0a517abf442cb246382035f6ef7a3fbd1bc51fa3c4c9a53835b593dea01a79fcdbeb7c484fd14167019dc4a1382a45378b3616885afa7d13d38e1bec464c234461fe5757d74abc609015152d394efc7d77578ed4d7bdaf8f72d098908451d7c217e78081a219d4cfb8"),
+    ("043a42a6f74cdec5ae496c68ae3fa1a69a74c8a6d727e7002cee2468898b6c6940a4ab22aa96c4e2f79eed9fe888d33408fd0ed7a7db86ed9ba2814c721e56d2165859e07d24ddd964c4a01b03709f468ca0aab77abfd16a48545d6ec6abf08dff20a12437e2c090658128c97b1bbb01e2121a4dc8ccbb72b4ffbe9a3ed24fc643",
+     "0458e4aa37a4ff0a9f5d252c5392d148afe12bfe920319ef8d8f67c3a5a95a209932eb67803b00786bdbfe0ea65f093fdd154c514bfd763736766eef094a66df853b32cc3d68415fce855df1edbc6b772ba526dd40d04503f75ee9f26c640579905b1074cf95c3ca68c557e89bf5204f81e9aa0f522d02c627ab751243c11b7324"),
+    ("0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b9eef64f6d41f4adf6f499e29c8cfe0581abbe9db7733261e6001d3bc5e6559e70e70d72ae8e5694b76d23b3ab8673752da02d8b27360e6ca8359df8219b79db6",
+     "04513f149ab53e94bb3a0367c61ff87670e025db30c57f84594e4ba4d7b3c656cf2a74f8561b91993205eb512576ad56221ea5963f3da078240d55594fb051ea863ed21be252528b685f2d0eb7c7ba51c7bdde0b848d6722ed750661bbc28411242802613b1c664b5252fb8b967a1d73145932cc597948d45282f400d959ef7dc0"),
+];
+
+const G2_MUL: &[(&str, &str, &str)] = &[
+    ("0000000000000000000000000000000000000000000000000000000000000000",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     ""),
+    ("0000000000000000000000000000000000000000000000000000000000000000",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     ""),
+    ("0000000000000000000000000000000000000000000000000000000000000001",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7"),
+    ("0000000000000000000000000000000000000000000000000000000000000001",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b"),
+    ("0000000000000000000000000000000000000000000000000000000000000002",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "04513f149ab53e94bb3a0367c61ff87670e025db30c57f84594e4ba4d7b3c656cf2a74f8561b91993205eb512576ad56221ea5963f3da078240d55594fb051ea86776de41db0511b8976d69c982dd4757d641487c68d13cbee7069396c20cd34598e3d9ec4e63d5b9f83081fb97b715430c8bfc6f1a1321a89627b9a4e8961c7bd"),
+    ("0000000000000000000000000000000000000000000000000000000000000002",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "0472957ba4f859a505e27c8eae9e3a31af529cb5680eb33c0a517abf442cb246382035f6ef7a3fbd1bc51fa3c4c9a53835b593dea01a79fcdbeb7c484fd14167019dc4a1382a45378b3616885afa7d13d38e1bec464c234461fe5757d74abc609015152d394efc7d77578ed4d7bdaf8f72d098908451d7c217e78081a219d4cfb8"),
+    ("0000000000000000000000000000000000000000000000000000000000000003",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "044dd9b503b00f0e8334e5cbdc9ff80deb4b207a1b1fda2382f3812bd5687937c09e5437ea263653ea0617ca82c5ce5db4937dece2f762a6fbdae7fb3032f9b154b1174c2d2b36cee03e1a7081eb71f60c35fac603f2b550218ec935c1e00bdd5b3d491f4ffb2a4ab249e396fe8e58b6e8cb23ef935309e576bc5a9a3b4fd97090"),
+    ("0000000000000000000000000000000000000000000000000000000000000003",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "04096851f0b60c6b66634e7e67269faf24eb11fe756e7dd6e2cc13dd0631c9cfdb78d89c67a61a5587a35b94168420a1a49648019d9576c2e9669b510c1d90c44b634565111a3ae9eb38d6371923ac7e07818666da4db4a05e99777abf44ca43c11b4e105369c821ed4fd5f100cc3efe6e6d8e8f239451e05b4482997201311ff0"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf23",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "04513f149ab53e94bb3a0367c61ff87670e025db30c57f84594e4ba4d7b3c656cf2a74f8561b91993205eb512576ad56221ea5963f3da078240d55594fb051ea863ed21be252528b685f2d0eb7c7ba51c7bdde0b848d6722ed750661bbc28411242802613b1c664b5252fb8b967a1d73145932cc597948d45282f400d959ef7dc0"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf23",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "0472957ba4f859a505e27c8eae9e3a31af529cb5680eb33c0a517abf442cb246382035f6ef7a3fbd1bc51fa3c4c9a53835b593dea01a79fcdbeb7c484fd1416701187b5ec7d85e6f669fed22f4fb11b37193d6a704ce57aa79e71843509894e4eda12ad2c6b3a7297a7e74d67837df37d2515a02c6c8a32cc3fdef1985c97c75c5"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf24",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b9eef64f6d41f4adf6f499e29c8cfe0581abbe9db7733261e6001d3bc5e6559e70e70d72ae8e5694b76d23b3ab8673752da02d8b27360e6ca8359df8219b79db6"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf24",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84a12fec3553862467576c3cb46e8c7313daa3dda2c9f70533f99a5fcb28994ea80840a73728bb8329ce2779f84f0af02b9b6b1aeeab892f2a5cb7a51a4274f2f72"),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf25",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     ""),
+    ("b640000002a3a6f1d603ab4ff58ec74449f2934b18ea8beee56ee19cd69ecf25",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     ""),
+    ("8000000000000000000000000000000000000000000000000000000000000000",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "0466cab11af9b19b94a8e07c17dcc292825fb93f2546c077fe0b06cf34c53e47a0594e1ceb0692eb36ac5a9afa5e54722d1a38b9e6700d4c96a99efb20e861096b8da605162242f0a718aeb9924e76505a07cae4fcb83f3de3b2f35a410b5e963f3464199adceb6a17ff6d4a8902b4c988005a1c87e983f090d25ec4a60a1209fb"),
+    ("8000000000000000000000000000000000000000000000000000000000000000",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "0420b3cd47e2883551fb33e9ef67d6cde9b0595f4b97660d2a2959fdd19db7127145f4236ceed0593f8e55ad42025c16259f7ec8596479ce97c20032ecc6f74f27322a13c9afbae2d42a07f8572018e705c3ecab4cc4b68fd2f3dc549f6237b42e0c77b1b7165e9bada3124498384c3ca7e0e63a43ee5f193b6afd7a288fe46e1c"),
+    ("00000000000000000000000000000000ffffffffffffffffffffffffffffffff",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "041bfc110cb6d181b71d95ee7304f574597e19abd28ba30dcdc706177f73797c9389f6a0e281074479eca539ee2efb3a6dadb4d86765834506b8e07fb9669ed88c23b8748b7d7ec87d7dd5232d120fcb7eeb285b07789dd156a27aba7932000be550a34110f71f3eb3d36b0374fd1e49749fdd600bf347a7e8c1bdca82eac37500"),
+    ("00000000000000000000000000000000ffffffffffffffffffffffffffffffff",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "046663bb768e91c3e304faae0134996a84c2a7294c10c908609859adfddcfef68954fd1d401000eac91a30f9b0e886c32a3f510e488c6694aa3baea501a37107f6778d04223c412f3d8ed3ec24a504938daaddb6e6d849a29d9c5f955dd5a5661aaccfb9cbbed2e0747c3d10899b9d7e363233602ce4c15c9ad33bad4272eeb499"),
+    ("239edd3a7de0d208d886c5d060fa1c95e553fb510e06acd4694398c5e11e99fc",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "0466c6e5981d0e6107fd192beca22862c9b26999501cc4b58ad5e76e9cc9800f8eb5d0e8d6cbc905738cb2fdf3d682a18d07682f59fed101df8ccd0f2085b60481a02d21fc28adec681bc691d31eebfe41e633a93d07a5ba23b055d92fc3f48ed6a05fb61454db83e08804ccadcee97187b66d7212189c7a1784dfc48772174055"),
+    ("239edd3a7de0d208d886c5d060fa1c95e553fb510e06acd4694398c5e11e99fc",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "0485e1b296af41ea28fd09bc070ab6cb35de42ca52b394829afbc48d4084ab765d1b1218d67287e4fe514fa7b5d7829fa91d4061304386e1a76d1955d488056a0d2f98cf85ba7f3091a84f49667aa59fcb7334db2c6a45c1c221f0505d07241c677c483f1b13a19fecc30088e29faef79bfc9d3132a6da870f12cd9092d0855eca"),
+    ("32bba064ebc1d3d2899f57f77f2a75ec92aeb20c15b7d95f8034a6a704789366",
+     "0485aef3d078640c98597b6027b441a01ff1dd2c190f5e93c454806c11d88061413722755292130b08d2aab97fd34ec120ee265948d19c17abf9b7213baf82d65b17509b092e845c1266ba0d262cbee6ed0736a96fa347c8bd856dc76b84ebeb96a7cf28d519be3da65f3170153d278ff247efba98a71a08116215bba5c999a7c7",
+     "040b30a850ddff38f7167cd092d625cf70df018824317af58e7f27b68f1f5358439434890a234e62a609215e98ac302d9d9f2573331a20a45ff0c76db1332aa7ab704a03f2359bac7b38832d48500d9cbd6024813f431489d9dbd7393afd17cf4047a5a5df477faf166fb62fcff76286c9363e00edd6af4205c5899471310b47d3"),
+    ("32bba064ebc1d3d2899f57f77f2a75ec92aeb20c15b7d95f8034a6a704789366",
+     "04482c1ced2be295538f6e6ba4f2fdb5a1dc80f02458d8d8d3e4985cd280d1dff143a7e2ceb40edc175a7de5c115cf798b1d26f617b4e8ff24f962be4231cda84aa3413caaca41607c5f3fe0090cc7960777b4b91e7b0a9b9c4bc99e7559bc5afd32358c8d76eb7454f38c0bcb04dfc48b6b40e46061e7fc3619f54983bc02160b",
+     "0472a95d0af13f56ca1e08f6049aeaf304b47378cdc8c20e60c6b230817d4306d74c2f26f3c6b4087e66128c16fb411868cf5bc313b3cc9a32e21a151d23808f3aabc8a9db63d9e2495e8aeb9bdbe4805d6307b81c630bdbafc8d5672a53081c8b92d8ea8b5882b79a346655e0082fd1a2aa1ca0734a897033c270b6f50bb48ba9"),
+];
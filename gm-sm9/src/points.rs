@@ -1,10 +1,19 @@
-use crate::fields::fp::{fp_from_bytes, fp_from_hex, Fp};
+use crate::error::{Sm9Error, Sm9Result};
+use crate::fields::fp::{fp_from_bytes, fp_from_hex, fp_from_mont, fp_sqrt, Fp};
 use crate::fields::fp12::Fp12;
 use crate::fields::fp2::Fp2;
 use crate::fields::FieldElement;
 use crate::sm9_p256_table::SM9_P256_PRECOMPUTED;
 use crate::u256::{sm9_u256_get_booth, u256_cmp, u256_to_bits, SM9_ZERO, U256};
 use crate::{SM9_MODP_MONT_FIVE, SM9_MODP_MONT_ONE};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use gm_core::audit::{observe_infallible, Operation};
+use gm_core::group::{window_mul, Choice, ConditionallySelectable, Group, Normalize};
+use gm_core::limb::select;
+use gm_core::Fingerprint;
+use gm_sm3::sm3_hash;
 
 #[derive(Copy, Debug, Clone)]
 pub struct Point {
@@ -14,14 +23,26 @@ pub struct Point {
 }
 
 impl Point {
-    pub(crate) fn from_bytes(b: &[u8]) -> Self {
-        let x = fp_from_bytes(&b[1..33]);
-        let y = fp_from_bytes(&b[33..65]);
-        Self {
-            x,
-            y,
+    /// Decode `0x04 || x || y`, or the compressed `0x02/0x03 || x`
+    /// Decode `0x04 || x || y`, 65 bytes, or the compressed `0x02/0x03 || x`,
+    /// 33 bytes, as written by [`Point::to_bytes_be`] and
+    /// [`Point::to_bytes_compressed`]. The point must be on the curve.
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Self> {
+        match (b.len(), b.first()) {
+            (33, Some(0x02 | 0x03)) => return Self::decompress(&b[1..], b[0] & 0x01),
+            (65, Some(0x04)) => {}
+            (33 | 65, _) => return Err(Sm9Error::InvalidEncoding),
+            _ => return Err(Sm9Error::InvalidLength),
+        }
+        let p = Self {
+            x: fp_from_bytes(&b[1..33])?,
+            y: fp_from_bytes(&b[33..65])?,
             z: SM9_MODP_MONT_ONE,
+        };
+        if !p.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
         }
+        Ok(p)
     }
 
     pub fn to_bytes_be(&self) -> Vec<u8> {
@@ -33,6 +54,43 @@ impl Point {
         ppend
     }
 
+    /// `0x02 || x` for an even y, `0x03 || x` for an odd one
+    pub fn to_bytes_compressed(&self) -> Vec<u8> {
+        let b = self.to_bytes_be();
+        let mut r = vec![0x02 | (b[64] & 0x01)];
+        r.extend_from_slice(&b[1..33]);
+        r
+    }
+
+    /// y from `y^2 = x^3 + 5`, negated in constant time to the parity `y_q`
+    fn decompress(x: &[u8], y_q: u8) -> Sm9Result<Self> {
+        let x = fp_from_bytes(x)?;
+        let yy = x.fp_sqr().fp_mul(&x).fp_add(&SM9_MODP_MONT_FIVE);
+        let y = fp_sqrt(&yy).ok_or(Sm9Error::FieldSqrtError)?;
+        let odd = fp_from_mont(&y)[0] as u8 & 0x01;
+        let y = select(&y, &SM9_ZERO.fp_sub(&y), Choice::from(odd ^ y_q));
+        Ok(Self {
+            x,
+            y,
+            z: SM9_MODP_MONT_ONE,
+        })
+    }
+
+    /// The affine x, big-endian and zero-padded to 32 bytes
+    pub fn x_bytes(&self) -> [u8; 32] {
+        self.to_bytes_be()[1..33].try_into().unwrap()
+    }
+
+    /// The affine y, big-endian and zero-padded to 32 bytes
+    pub fn y_bytes(&self) -> [u8; 32] {
+        self.to_bytes_be()[33..].try_into().unwrap()
+    }
+
+    /// SM3 of [`Point::to_bytes_be`], identifies an encryption master public key.
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint(sm3_hash(&self.to_bytes_be()))
+    }
+
     pub fn is_on_curve(&self) -> bool {
         let (mut t0, mut t1, mut t2) = (Fp::zero(), Fp::zero(), Fp::zero());
         if u256_cmp(&self.z, &SM9_MODP_MONT_ONE) == 0 {
@@ -195,7 +253,7 @@ pub const SM9_U256_PPUBS: TwistPoint = TwistPoint {
     },
 };
 
-const SM9_U256_MONT_G2: TwistPoint = TwistPoint {
+pub(crate) const SM9_U256_MONT_G2: TwistPoint = TwistPoint {
     x: Fp2 {
         c0: [
             0x260226a68ce2da8f,
@@ -421,56 +479,7 @@ impl Point {
     }
 
     pub fn point_mul(&self, k: &[u64]) -> Self {
-        let mut pre_table = vec![];
-        for _ in 0..16 {
-            pre_table.push(Point::zero());
-        }
-        let window_size = 5u64;
-        let n = (256 + window_size - 1) / window_size;
-        pre_table[0] = *self;
-        pre_table[2 - 1] = pre_table[1 - 1].point_double();
-        pre_table[4 - 1] = pre_table[2 - 1].point_double();
-        pre_table[8 - 1] = pre_table[4 - 1].point_double();
-        pre_table[16 - 1] = pre_table[8 - 1].point_double();
-
-        pre_table[3 - 1] = pre_table[2 - 1].point_add(self);
-        pre_table[6 - 1] = pre_table[3 - 1].point_double();
-        pre_table[12 - 1] = pre_table[6 - 1].point_double();
-
-        pre_table[5 - 1] = pre_table[3 - 1].point_add(&pre_table[2 - 1]);
-        pre_table[10 - 1] = pre_table[5 - 1].point_double();
-
-        pre_table[7 - 1] = pre_table[4 - 1].point_add(&pre_table[3 - 1]);
-        pre_table[14 - 1] = pre_table[7 - 1].point_double();
-
-        pre_table[9 - 1] = pre_table[4 - 1].point_add(&pre_table[5 - 1]);
-        pre_table[11 - 1] = pre_table[6 - 1].point_add(&pre_table[5 - 1]);
-        pre_table[13 - 1] = pre_table[7 - 1].point_add(&pre_table[6 - 1]);
-        pre_table[15 - 1] = pre_table[8 - 1].point_add(&pre_table[7 - 1]);
-
-        let mut r = Point::zero();
-        let mut r_infinity = true;
-        for i in (0..n).rev() {
-            let booth = sm9_u256_get_booth(k, window_size, i);
-            if r_infinity {
-                if booth != 0 {
-                    r = pre_table[(booth - 1) as usize];
-                    r_infinity = false;
-                }
-            } else {
-                r = r.point_double_x5();
-                if booth > 0 {
-                    r = r.point_add(&pre_table[(booth - 1) as usize])
-                } else if booth < 0 {
-                    r = r.point_sub(&pre_table[(-booth - 1) as usize])
-                }
-            }
-        }
-
-        if r_infinity {
-            r = Point::zero();
-        }
-        r
+        window_mul(self, k)
     }
 
     pub fn to_jacobi(&self) -> Self {
@@ -482,21 +491,6 @@ impl Point {
     }
 
     pub fn g_mul(k: &[u64]) -> Point {
-        let mut pre_com_points: Vec<Vec<Point>> = vec![];
-        let p = &SM9_P256_PRECOMPUTED;
-        for i in 0..p.len() {
-            let mut points = vec![];
-            let p1 = p[i];
-            for j in 0..(p1.len() / 2) {
-                points.push(Point {
-                    x: p1[j * 2],
-                    y: p1[j * 2 + 1],
-                    z: Fp::one(),
-                })
-            }
-            pre_com_points.push(points);
-        }
-
         let mut r = Point::zero();
         let window_size = 7u64;
         let mut r_infinity = true;
@@ -505,15 +499,15 @@ impl Point {
             let booth = sm9_u256_get_booth(&k, window_size, i);
             if r_infinity {
                 if booth != 0 {
-                    r = pre_com_points[i as usize][(booth - 1) as usize];
+                    r = precomputed_point(i as usize, (booth - 1) as usize);
                     r_infinity = false;
                 }
             } else {
                 if booth > 0 {
-                    let p = pre_com_points[i as usize][(booth - 1) as usize];
+                    let p = precomputed_point(i as usize, (booth - 1) as usize);
                     r = r.point_add(&p);
                 } else if booth < 0 {
-                    let p = pre_com_points[i as usize][(-booth - 1) as usize];
+                    let p = precomputed_point(i as usize, (-booth - 1) as usize);
                     r = r.point_sub(&p);
                 }
             }
@@ -522,7 +516,182 @@ impl Point {
     }
 }
 
+impl ConditionallySelectable for Point {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Point {
+            x: select(&a.x, &b.x, choice),
+            y: select(&a.y, &b.y, choice),
+            z: select(&a.z, &b.z, choice),
+        }
+    }
+}
+
+impl Group for Point {
+    fn identity() -> Self {
+        Point::zero()
+    }
+
+    fn is_identity(&self) -> bool {
+        self.is_zero()
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        self.point_add(rhs)
+    }
+
+    fn double(&self) -> Self {
+        self.point_double()
+    }
+
+    fn neg(&self) -> Self {
+        self.point_neg()
+    }
+}
+
+impl Normalize for Point {
+    type Field = Fp;
+
+    fn z(&self) -> Fp {
+        self.z
+    }
+
+    fn field_mul(a: &Fp, b: &Fp) -> Fp {
+        a.fp_mul(b)
+    }
+
+    fn field_inv(a: &Fp) -> Fp {
+        a.fp_inv()
+    }
+
+    fn normalize_with(&self, z_inv: &Fp) -> Self {
+        let z_inv2 = z_inv.fp_sqr();
+        Point {
+            x: self.x.fp_mul(&z_inv2),
+            y: self.y.fp_mul(&z_inv2.fp_mul(z_inv)),
+            z: SM9_MODP_MONT_ONE,
+        }
+    }
+}
+
+// (j + 1) * 2^(7i) * P1 read straight from the table, so g_mul does not allocate
+fn precomputed_point(i: usize, j: usize) -> Point {
+    let p = &SM9_P256_PRECOMPUTED[i];
+    Point {
+        x: p[j * 2],
+        y: p[j * 2 + 1],
+        z: Fp::one(),
+    }
+}
+
 impl TwistPoint {
+    /// Decode `0x04 || x || y`, 129 bytes, or the compressed `0x02/0x03 || x`,
+    /// 65 bytes, as written by [`TwistPoint::to_bytes_be`] and
+    /// [`TwistPoint::to_bytes_compressed`]. The point must be on the twist,
+    /// membership of the order-n subgroup is left to the caller.
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Self> {
+        match (b.len(), b.first()) {
+            (65, Some(0x02 | 0x03)) => return Self::decompress(&b[1..], b[0] & 0x01),
+            (129, Some(0x04)) => {}
+            (65 | 129, _) => return Err(Sm9Error::InvalidEncoding),
+            _ => return Err(Sm9Error::InvalidLength),
+        }
+        let x = Fp2 {
+            c1: fp_from_bytes(&b[1..33])?,
+            c0: fp_from_bytes(&b[33..65])?,
+        };
+        let y = Fp2 {
+            c1: fp_from_bytes(&b[65..97])?,
+            c0: fp_from_bytes(&b[97..129])?,
+        };
+        let p = Self { x, y, z: Fp2::one() };
+        if !p.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
+        }
+        Ok(p)
+    }
+
+    /// 0x04 || x || y, each Fp2 coordinate encoded as c1 || c0
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut ppend: Vec<u8> = vec![];
+        let p = self.to_affine_point();
+        ppend.push(0x04); // uncompress point
+        ppend.extend_from_slice(&p.x.to_bytes_be());
+        ppend.extend_from_slice(&p.y.to_bytes_be());
+        ppend
+    }
+
+    /// `0x02 || x` or `0x03 || x` by the [`sgn0`](Fp2::sgn0) of y, 65
+    /// bytes instead of 129
+    pub fn to_bytes_compressed(&self) -> Vec<u8> {
+        let p = self.to_affine_point();
+        let mut r = vec![0x02 | p.y.sgn0()];
+        r.extend_from_slice(&p.x.to_bytes_be());
+        r
+    }
+
+    /// y from `y^2 = x^3 + 5u`, negated to the sign `y_q`
+    fn decompress(x: &[u8], y_q: u8) -> Sm9Result<Self> {
+        let x = Fp2 {
+            c1: fp_from_bytes(&x[..32])?,
+            c0: fp_from_bytes(&x[32..])?,
+        };
+        let yy = x.fp_sqr().fp_mul(&x).fp_add(&Fp2 {
+            c0: Fp::zero(),
+            c1: SM9_MODP_MONT_FIVE,
+        });
+        let mut y = yy.sqrt().ok_or(Sm9Error::FieldSqrtError)?;
+        if y.sgn0() != y_q {
+            y = y.fp_neg();
+        }
+        Ok(Self { x, y, z: Fp2::one() })
+    }
+
+    /// The affine x as c1 || c0, each 32 bytes big-endian
+    pub fn x_bytes(&self) -> [u8; 64] {
+        self.to_bytes_be()[1..65].try_into().unwrap()
+    }
+
+    /// The affine y as c1 || c0, each 32 bytes big-endian
+    pub fn y_bytes(&self) -> [u8; 64] {
+        self.to_bytes_be()[65..].try_into().unwrap()
+    }
+
+    /// SM3 of [`TwistPoint::to_bytes_be`], identifies a signing master public key.
+    pub fn fingerprint(&self) -> Fingerprint {
+        Fingerprint(sm3_hash(&self.to_bytes_be()))
+    }
+
+    pub fn to_affine_point(&self) -> TwistPoint {
+        if self.z.eq(&Fp2::one()) {
+            return *self;
+        }
+        let mut z_inv = self.z.fp_inv();
+        let mut y = self.y.fp_mul(&z_inv);
+        z_inv = z_inv.fp_sqr();
+        let x = self.x.fp_mul(&z_inv);
+        y = y.fp_mul(&z_inv);
+        TwistPoint { x, y, z: Fp2::one() }
+    }
+
+    /// y^2 = x^3 + 5u on the twist curve
+    pub fn is_on_curve(&self) -> bool {
+        if self.is_zero() {
+            return false;
+        }
+        let p = self.to_affine_point();
+        let t0 = p.y.fp_sqr();
+        let mut t1 = p.x.fp_sqr().fp_mul(&p.x);
+        t1 = t1.fp_add(&Fp2 {
+            c0: Fp::zero(),
+            c1: SM9_MODP_MONT_FIVE,
+        });
+        t0.eq(&t1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.z.is_zero()
+    }
+
     pub fn from_hex(x_data: [&str; 2], y_data: [&str; 2]) -> Self {
         Self {
             x: Fp2::from_hex(x_data),
@@ -591,6 +760,7 @@ impl TwistPoint {
         }
     }
 
+    /// Mixed addition, `rhs` must be affine (z = 1), see [`TwistPoint::to_affine_point`].
     pub fn point_add(&self, rhs: &Self) -> Self {
         let x1 = self.x;
         let y1 = self.y;
@@ -669,8 +839,74 @@ impl TwistPoint {
         r
     }
 
+    /// `[k]P2` from the table of [`crate::g2_table`], built on first use
+    /// unless one was loaded
     pub fn g_mul(k: &U256) -> TwistPoint {
-        SM9_U256_MONT_G2.point_mul(k)
+        crate::g2_table::g2_table().mul(k)
+    }
+}
+
+fn select_fp2(a: &Fp2, b: &Fp2, choice: Choice) -> Fp2 {
+    Fp2 {
+        c0: select(&a.c0, &b.c0, choice),
+        c1: select(&a.c1, &b.c1, choice),
+    }
+}
+
+impl ConditionallySelectable for TwistPoint {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        TwistPoint {
+            x: select_fp2(&a.x, &b.x, choice),
+            y: select_fp2(&a.y, &b.y, choice),
+            z: select_fp2(&a.z, &b.z, choice),
+        }
+    }
+}
+
+impl Group for TwistPoint {
+    fn identity() -> Self {
+        TwistPoint::zero()
+    }
+
+    fn is_identity(&self) -> bool {
+        self.is_zero()
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        twist_point_add_full(self, rhs)
+    }
+
+    fn double(&self) -> Self {
+        self.point_double()
+    }
+
+    fn neg(&self) -> Self {
+        self.point_neg()
+    }
+}
+
+impl Normalize for TwistPoint {
+    type Field = Fp2;
+
+    fn z(&self) -> Fp2 {
+        self.z
+    }
+
+    fn field_mul(a: &Fp2, b: &Fp2) -> Fp2 {
+        a.fp_mul(b)
+    }
+
+    fn field_inv(a: &Fp2) -> Fp2 {
+        a.fp_inv()
+    }
+
+    fn normalize_with(&self, z_inv: &Fp2) -> Self {
+        let z_inv2 = z_inv.fp_sqr();
+        TwistPoint {
+            x: self.x.fp_mul(&z_inv2),
+            y: self.y.fp_mul(&z_inv2.fp_mul(z_inv)),
+            z: Fp2::one(),
+        }
     }
 }
 
@@ -731,56 +967,137 @@ pub(crate) fn twist_point_add_full(p1: &TwistPoint, p2: &TwistPoint) -> TwistPoi
     }
 }
 
-pub(crate) fn sm9_u256_pairing(q: &TwistPoint, p: &Point) -> Fp12 {
-    let abits: Vec<char> = "00100000000000000000000000000000000000010000101100020200101000020"
-        .chars()
-        .collect();
+/// Points are equal as points of the curve, whatever their Jacobian
+/// coordinates, and ordered by their encoding with the infinity first.
+macro_rules! impl_canonical_cmp {
+    ($point:ty) => {
+        impl $point {
+            /// `0x00` for the infinity, [`Self::to_bytes_be`] otherwise
+            fn canonical_bytes(&self) -> Vec<u8> {
+                if self.is_zero() {
+                    vec![0]
+                } else {
+                    self.to_bytes_be()
+                }
+            }
+        }
 
-    let mut pre: [Fp2; 5] = [Fp2::zero(); 5];
-    let mut t = TwistPoint {
-        x: q.x.clone(),
-        y: q.y.clone(),
-        z: q.z.clone(),
+        impl PartialEq for $point {
+            fn eq(&self, other: &Self) -> bool {
+                self.canonical_bytes() == other.canonical_bytes()
+            }
+        }
+
+        impl Eq for $point {}
+
+        impl Hash for $point {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.canonical_bytes().hash(state)
+            }
+        }
+
+        impl Ord for $point {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.canonical_bytes().cmp(&other.canonical_bytes())
+            }
+        }
+
+        impl PartialOrd for $point {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
     };
+}
 
-    let mut lw: [Fp2; 3] = [Fp2::zero(); 3];
+impl_canonical_cmp!(Point);
+impl_canonical_cmp!(TwistPoint);
+
+/// 6t + 2 = 0x2400000000215d93e in signed binary from the second most
+/// significant digit down, 1 for +1 and 2 for -1. With the implicit leading
+/// digit it has 11 non-zero digits, as many as the NAF, so the loop takes 65
+/// doublings and 10 additions.
+const SM9_LOOP_DIGITS: &[u8; 65] = b"00100000000000000000000000000000000000010000101100020200101000020";
+
+/// The Miller loop lines of a fixed G2 point Q.
+///
+/// A line evaluated at P = (x, y) is `[l0, l1 * x, l2 * y]` where l0, l1 and l2
+/// only depend on Q, so they are computed once and reused for every P.
+#[derive(Debug, Clone)]
+pub(crate) struct G2Prepared {
+    lines: Vec<[Fp2; 3]>,
+}
 
+impl G2Prepared {
+    pub(crate) fn new(q: &TwistPoint) -> Self {
+        // evaluating at (1, 1) leaves the coefficients
+        let unit = Point {
+            x: SM9_MODP_MONT_ONE,
+            y: SM9_MODP_MONT_ONE,
+            z: SM9_MODP_MONT_ONE,
+        };
+        let mut lines = Vec::with_capacity(SM9_LOOP_DIGITS.len() + 12);
+        let mut lw: [Fp2; 3] = [Fp2::zero(); 3];
+        let mut t = *q;
+        let q_neg = q.point_neg();
+
+        let mut pre: [Fp2; 5] = [Fp2::zero(); 5];
+        pre[0] = q.y.fp_sqr();
+        pre[4] = q.x.fp_mul(&q.z);
+        pre[4] = pre[4].fp_double();
+        pre[1] = q.z.fp_sqr();
+        pre[1] = q.z.fp_mul(&pre[1]);
+        pre[2] = pre[1].fp_double();
+        pre[3] = pre[1].fp_double();
+        pre[3] = pre[3].fp_neg();
+
+        for &digit in SM9_LOOP_DIGITS {
+            t = sm9_u256_eval_g_tangent(&mut lw, &t, &unit);
+            lines.push(lw);
+            if digit == b'1' {
+                t = sm9_u256_eval_g_line(&mut lw, &pre, &t, q, &unit);
+                lines.push(lw);
+            } else if digit == b'2' {
+                t = sm9_u256_eval_g_line(&mut lw, &pre, &t, &q_neg, &unit);
+                lines.push(lw);
+            }
+        }
+
+        let q1 = q.point_pi1();
+        let q2 = q.point_neg_pi2();
+        t = sm9_u256_eval_g_line_no_pre(&mut lw, &t, &q1, &unit);
+        lines.push(lw);
+        sm9_u256_eval_g_line_no_pre(&mut lw, &t, &q2, &unit);
+        lines.push(lw);
+        Self { lines }
+    }
+}
+
+pub(crate) fn sm9_u256_pairing(q: &TwistPoint, p: &Point) -> Fp12 {
+    sm9_u256_pairing_prepared(&G2Prepared::new(q), p)
+}
+
+/// e(P, Q) with the lines of Q computed by [`G2Prepared::new`]
+pub(crate) fn sm9_u256_pairing_prepared(q: &G2Prepared, p: &Point) -> Fp12 {
+    observe_infallible("SM9", Operation::Pairing, || miller_loop(q, p).final_exponent())
+}
+
+fn miller_loop(q: &G2Prepared, p: &Point) -> Fp12 {
     let p_affine = p.to_affine_point();
-    let mut q1 = q.point_neg();
+    let eval = |lw: &[Fp2; 3]| [lw[0], lw[1].fp_mul_fp(&p_affine.x), lw[2].fp_mul_fp(&p_affine.y)];
 
-    pre[0] = q.y.fp_sqr();
-    pre[4] = q.x.fp_mul(&q.z);
-    pre[4] = pre[4].fp_double();
-    pre[1] = q.z.fp_sqr();
-    pre[1] = q.z.fp_mul(&pre[1]);
-    pre[2] = pre[1].fp_mul_fp(&p_affine.y);
-    pre[2] = pre[2].fp_double();
-    pre[3] = pre[1].fp_mul_fp(&p_affine.x);
-    pre[3] = pre[3].fp_double();
-    pre[3] = pre[3].fp_neg();
+    let mut lines = q.lines.iter();
     let mut r = Fp12::one();
-    for i in 0..abits.len() {
+    for &digit in SM9_LOOP_DIGITS {
         r = r.fp_sqr();
-        t = sm9_u256_eval_g_tangent(&mut lw, &t, &p_affine);
-        r = r.fp_line_mul(&lw);
-        if abits[i] == '1' {
-            t = sm9_u256_eval_g_line(&mut lw, &pre, &t, &q, &p_affine);
-            r = r.fp_line_mul(&lw);
-        } else if abits[i] == '2' {
-            t = sm9_u256_eval_g_line(&mut lw, &pre, &t, &q1, &p_affine);
-            r = r.fp_line_mul(&lw);
+        r = r.fp_line_mul(&eval(lines.next().unwrap()));
+        if digit != b'0' {
+            r = r.fp_line_mul(&eval(lines.next().unwrap()));
         }
     }
-
-    q1 = q.point_pi1();
-    let q2 = q.point_neg_pi2();
-    t = sm9_u256_eval_g_line_no_pre(&mut lw, &t, &q1, &p_affine);
-    r = r.fp_line_mul(&lw);
-
-    t = sm9_u256_eval_g_line_no_pre(&mut lw, &t, &q2, &p_affine);
-    r = r.fp_line_mul(&lw);
-
-    r = r.final_exponent();
+    for lw in lines {
+        r = r.fp_line_mul(&eval(lw));
+    }
     r
 }
 
@@ -976,8 +1293,123 @@ pub(crate) fn sm9_u256_eval_g_tangent(lw: &mut [Fp2; 3], p: &TwistPoint, q: &Poi
 
 #[cfg(test)]
 mod test_point_operation {
-    use crate::points::{twist_point_add_full, Point, TwistPoint, SM9_U256_MONT_G2};
+    use crate::error::Sm9Error;
+    use crate::points::{
+        sm9_u256_pairing, sm9_u256_pairing_prepared, twist_point_add_full, G2Prepared, Point,
+        TwistPoint, SM9_U256_MONT_G2,
+    };
+    use crate::fields::fn_random_u256;
     use crate::u256::u256_from_be_bytes;
+    use crate::{SM9_MODP_MONT_ONE, SM9_N};
+    use gm_core::group::{batch_normalize, ladder_mul, wnaf_mul};
+
+    #[test]
+    fn test_generic_scalar_mul() {
+        let p = Point::g_mul(&fn_random_u256());
+        for _ in 0..8 {
+            let k = fn_random_u256();
+            let expected = Point::g_mul(&k);
+            assert!(expected.point_equals(&Point::g_mul(&[1, 0, 0, 0]).point_mul(&k)));
+            assert!(p.point_mul(&k).point_equals(&ladder_mul(&p, &k)));
+            assert!(p.point_mul(&k).point_equals(&wnaf_mul(&p, &k)));
+        }
+        assert!(p.point_mul(&[0; 4]).is_zero());
+        assert!(p.point_mul(&SM9_N).is_zero());
+
+        let q = p.point_double();
+        let mut points = [p, Point::zero(), q];
+        batch_normalize(&mut points);
+        assert!(points[1].is_zero());
+        assert!(points[0].z == SM9_MODP_MONT_ONE && points[0].point_equals(&p));
+        assert!(points[2].z == SM9_MODP_MONT_ONE && points[2].point_equals(&q));
+        assert_eq!(q.to_bytes_be(), points[2].to_bytes_be());
+    }
+
+    #[test]
+    fn test_point_eq_hash_ord() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let p = Point::g_mul(&fn_random_u256());
+        let q = p.point_double().point_add(&p.point_neg());
+        assert_eq!(p, q);
+        let points = [p, q, Point::zero(), p.point_double(), Point::zero()];
+        assert_eq!(3, points.iter().collect::<HashSet<_>>().len());
+        let sorted: Vec<Point> = points.into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+        assert!(sorted[0].is_zero());
+        assert!(sorted[1].to_bytes_be() < sorted[2].to_bytes_be());
+
+        let t = TwistPoint::g_mul(&fn_random_u256());
+        let u = twist_point_add_full(&t.point_double(), &t.point_neg());
+        assert_ne!(t.z, u.z);
+        assert_eq!(t, u);
+        assert_ne!(t, t.point_neg());
+        let twists = [t, u, TwistPoint::zero(), t.point_double()];
+        assert_eq!(3, twists.iter().collect::<HashSet<_>>().len());
+        assert_eq!(TwistPoint::zero(), *twists.iter().min().unwrap());
+    }
+
+    #[test]
+    fn test_point_compressed() {
+        use crate::error::Sm9Error;
+
+        for _ in 0..8 {
+            let p = Point::g_mul(&fn_random_u256());
+            for q in [p, p.point_neg()] {
+                let b = q.to_bytes_compressed();
+                assert_eq!(33, b.len());
+                assert_eq!(q, Point::from_bytes(&b).unwrap());
+            }
+        }
+        let mut rejected = 0;
+        for i in 1..32u8 {
+            for flag in [0x02, 0x03] {
+                let mut b = [0u8; 33];
+                b[0] = flag;
+                b[32] = i;
+                match Point::from_bytes(&b) {
+                    Ok(q) => {
+                        assert!(q.is_on_curve());
+                        assert_eq!(b.to_vec(), q.to_bytes_compressed());
+                    }
+                    Err(e) => {
+                        assert_eq!(Sm9Error::FieldSqrtError, e);
+                        rejected += 1;
+                    }
+                }
+            }
+        }
+        assert!(rejected > 0 && rejected < 62);
+    }
+
+    #[test]
+    fn test_twist_point_compressed() {
+        use crate::error::Sm9Error;
+
+        for _ in 0..8 {
+            let p = TwistPoint::g_mul(&fn_random_u256());
+            for q in [p, p.point_neg()] {
+                let b = q.to_bytes_compressed();
+                assert_eq!(65, b.len());
+                let r = TwistPoint::from_bytes(&b).unwrap();
+                assert!(r.is_on_curve());
+                assert_eq!(q, r);
+            }
+        }
+        let mut rejected = 0;
+        for i in 1..16u8 {
+            let mut b = [0u8; 65];
+            b[0] = 0x02;
+            b[64] = i;
+            match TwistPoint::from_bytes(&b) {
+                Ok(q) => assert_eq!(b.to_vec(), q.to_bytes_compressed()),
+                Err(e) => {
+                    assert_eq!(Sm9Error::FieldSqrtError, e);
+                    rejected += 1;
+                }
+            }
+        }
+        assert!(rejected > 0 && rejected < 15);
+    }
 
     #[test]
     fn test_g_mul() {
@@ -1270,4 +1702,52 @@ mod test_point_operation {
         );
         assert_eq!(true, r.point_equals(&ret))
     }
+
+    #[test]
+    fn test_twist_point_bytes() {
+        let k = u256_from_be_bytes(
+            &hex::decode("123456789abcdef00fedcba987654321123456789abcdef00fedcba987654321")
+                .unwrap(),
+        );
+        let r = SM9_U256_MONT_G2.point_mul(&k);
+        assert!(SM9_U256_MONT_G2.is_on_curve());
+        assert!(r.is_on_curve());
+
+        let bytes = r.to_bytes_be();
+        assert_eq!(129, bytes.len());
+        assert_eq!(
+            "04920ef6fb3a2acff52aa0c004c18feca149dfd33d98086f8f402ea9e0de303c49",
+            hex::encode(&bytes[..33])
+        );
+        let p = TwistPoint::from_bytes(&bytes).unwrap();
+        assert!(p.is_on_curve());
+        assert!(p.point_equals(&r));
+
+        let mut bad = bytes.clone();
+        bad[128] ^= 1;
+        assert_eq!(Err(Sm9Error::NotOnCurve), TwistPoint::from_bytes(&bad));
+        assert_eq!(Err(Sm9Error::InvalidLength), TwistPoint::from_bytes(&bytes[..128]));
+        bad[0] = 0x02;
+        assert_eq!(Err(Sm9Error::InvalidEncoding), TwistPoint::from_bytes(&bad));
+    }
+
+    #[test]
+    fn test_pairing_prepared() {
+        let a = u256_from_be_bytes(
+            &hex::decode("00000000000000000000000000000000000000000000000000000000075bcd15")
+                .unwrap(),
+        );
+        let p = Point::g_mul(&[7, 0, 0, 0]);
+        let q = SM9_U256_MONT_G2.point_mul(&a);
+        let prepared = G2Prepared::new(&q);
+        let pa = p.point_mul(&a);
+        let e = sm9_u256_pairing_prepared(&prepared, &p);
+        assert_eq!(e, sm9_u256_pairing(&q, &p));
+        assert_eq!(
+            sm9_u256_pairing_prepared(&prepared, &pa),
+            sm9_u256_pairing(&q, &pa)
+        );
+        // e(aP, Q) = e(P, aQ)
+        assert_eq!(sm9_u256_pairing(&SM9_U256_MONT_G2, &pa), e);
+    }
 }
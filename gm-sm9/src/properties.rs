@@ -0,0 +1,256 @@
+//! Property tests of the arithmetic layers against num-bigint.
+//!
+//! The inputs come from a seeded generator that mixes uniform values with the
+//! edge cases limb-carry bugs hide behind: 0, 1, p and n and their neighbours,
+//! all-ones limbs and values with a single limb set. A failure prints the case
+//! number, rerun with the same seed to reproduce it.
+use num_bigint::BigUint;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::fields::fp::{fp_from_mont, fp_sqrt, fp_to_mont, Fp};
+use crate::fields::fp2::Fp2;
+use crate::fields::{mod_n_add, mod_n_inv, mod_n_mul, mod_n_sub, FieldElement};
+use crate::points::{twist_point_add_full, Point, TwistPoint};
+use crate::u256::{
+    u256_add, u256_cmp, u256_div_rem, u256_from_be_bytes, u256_mul, u256_sub, u256_to_be_bytes,
+    u512_mod_u256, U256,
+};
+use crate::{SM9_MODP_MONT_ONE, SM9_N, SM9_P};
+
+const CASES: usize = 256;
+const SEED: u64 = 0x5eed_0617;
+
+fn big(a: &[u64]) -> BigUint {
+    let digits: Vec<u32> = a.iter().flat_map(|&w| [w as u32, (w >> 32) as u32]).collect();
+    BigUint::from_slice(&digits)
+}
+
+fn from_big(a: &BigUint) -> U256 {
+    let mut r = [0u64; 4];
+    for (r, d) in r.iter_mut().zip(a.to_u64_digits()) {
+        *r = d;
+    }
+    r
+}
+
+fn edge_cases() -> Vec<U256> {
+    let mut v = vec![
+        [0; 4],
+        [1, 0, 0, 0],
+        [u64::MAX; 4],
+        [u64::MAX, 0, 0, 0],
+        [0, 0, 0, 1 << 63],
+        [0, u64::MAX, 0, u64::MAX],
+        SM9_P,
+        SM9_N,
+    ];
+    for m in [SM9_P, SM9_N] {
+        v.push(u256_sub(&m, &[1, 0, 0, 0]).0);
+        v.push(u256_sub(&m, &[2, 0, 0, 0]).0);
+        v.push(u256_add(&m, &[1, 0, 0, 0]).0);
+    }
+    v
+}
+
+/// Any 256-bit value, an edge case one time in four
+fn gen_u256(rng: &mut StdRng) -> U256 {
+    if rng.gen_ratio(1, 4) {
+        let edges = edge_cases();
+        return edges[rng.gen_range(0..edges.len())];
+    }
+    let mut r: U256 = rng.gen();
+    // single limbs and all-ones limbs propagate carries the furthest
+    for w in r.iter_mut() {
+        match rng.gen_range(0..8) {
+            0 => *w = 0,
+            1 => *w = u64::MAX,
+            _ => {}
+        }
+    }
+    r
+}
+
+/// A value below `m`
+fn gen_below(rng: &mut StdRng, m: &U256) -> U256 {
+    from_big(&(big(&gen_u256(rng)) % big(m)))
+}
+
+/// A non-zero scalar mod n
+fn gen_scalar(rng: &mut StdRng) -> U256 {
+    loop {
+        let k = gen_below(rng, &SM9_N);
+        if k != [0; 4] {
+            return k;
+        }
+    }
+}
+
+/// An Fp2 element in the Montgomery domain and its reference coordinates
+fn gen_fp2(rng: &mut StdRng) -> (Fp2, [BigUint; 2]) {
+    let (c0, c1) = (gen_below(rng, &SM9_P), gen_below(rng, &SM9_P));
+    let x = Fp2 {
+        c0: fp_to_mont(&c0),
+        c1: fp_to_mont(&c1),
+    };
+    (x, [big(&c0), big(&c1)])
+}
+
+fn fp2_ref(x: &Fp2) -> [BigUint; 2] {
+    [big(&fp_from_mont(&x.c0)), big(&fp_from_mont(&x.c1))]
+}
+
+fn check(name: &str, cases: usize, mut f: impl FnMut(&mut StdRng)) {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    for i in 0..cases {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut rng)));
+        if let Err(e) = result {
+            panic!("property {} failed at case {} (seed {:#x}): {:?}", name, i, SEED, e);
+        }
+    }
+}
+
+#[test]
+fn prop_u256_ops() {
+    let r256 = BigUint::from(1u8) << 256;
+    check("u256", CASES, |rng| {
+        let (a, b) = (gen_u256(rng), gen_u256(rng));
+        let (ba, bb) = (big(&a), big(&b));
+
+        let (s, carry) = u256_add(&a, &b);
+        assert_eq!(big(&s) + (BigUint::from(carry as u8) << 256), &ba + &bb);
+        let (d, borrow) = u256_sub(&a, &b);
+        assert_eq!(big(&d), (&ba + &r256 - &bb) % &r256);
+        assert_eq!(borrow, ba < bb);
+        assert_eq!(big(&u256_mul(&a, &b)), &ba * &bb);
+        assert_eq!(u256_cmp(&a, &b), ba.cmp(&bb) as i32);
+        assert_eq!(a, u256_from_be_bytes(&u256_to_be_bytes(&a)));
+
+        if bb != BigUint::from(0u8) {
+            let (q, r) = u256_div_rem(&a, &b);
+            assert_eq!((big(&q), big(&r)), (&ba / &bb, &ba % &bb));
+            let c = gen_u256(rng);
+            let wide = [a[0], a[1], a[2], a[3], c[0], c[1], c[2], c[3]];
+            assert_eq!(big(&u512_mod_u256(&wide, &b)), big(&wide) % &bb);
+        }
+    });
+}
+
+#[test]
+fn prop_fp_field() {
+    let p = big(&SM9_P);
+    check("fp", CASES, |rng| {
+        let (a, b, c) = (gen_below(rng, &SM9_P), gen_below(rng, &SM9_P), gen_below(rng, &SM9_P));
+        let (ma, mb, mc): (Fp, Fp, Fp) = (fp_to_mont(&a), fp_to_mont(&b), fp_to_mont(&c));
+        assert_eq!(a, fp_from_mont(&ma));
+
+        // against the reference
+        assert_eq!(big(&fp_from_mont(&ma.fp_add(&mb))), (big(&a) + big(&b)) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_sub(&mb))), (big(&a) + &p - big(&b)) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_mul(&mb))), big(&a) * big(&b) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_sqr())), big(&a) * big(&a) % &p);
+        assert_eq!(big(&fp_from_mont(&ma.fp_neg())), (&p - big(&a)) % &p);
+        assert_eq!(ma, ma.fp_div2().fp_double());
+        assert_eq!(ma.fp_triple(), ma.fp_double().fp_add(&ma));
+
+        // ring axioms
+        assert_eq!(ma.fp_add(&mb), mb.fp_add(&ma));
+        assert_eq!(ma.fp_mul(&mb), mb.fp_mul(&ma));
+        assert_eq!(ma.fp_add(&mb).fp_add(&mc), ma.fp_add(&mb.fp_add(&mc)));
+        assert_eq!(ma.fp_mul(&mb).fp_mul(&mc), ma.fp_mul(&mb.fp_mul(&mc)));
+        assert_eq!(ma.fp_mul(&mb.fp_add(&mc)), ma.fp_mul(&mb).fp_add(&ma.fp_mul(&mc)));
+        assert!(ma.fp_add(&ma.fp_neg()).is_zero());
+
+        if !ma.is_zero() {
+            assert_eq!(SM9_MODP_MONT_ONE, ma.fp_mul(&ma.fp_inv()));
+        }
+        let sq = ma.fp_sqr();
+        assert_eq!(sq, fp_sqrt(&sq).unwrap().fp_sqr());
+    });
+}
+
+#[test]
+fn prop_fp2_field() {
+    let p = big(&SM9_P);
+    check("fp2", CASES, |rng| {
+        let (a, [a0, a1]) = gen_fp2(rng);
+        let (b, [b0, b1]) = gen_fp2(rng);
+        let (c, _) = gen_fp2(rng);
+
+        // u^2 = -2
+        let two = BigUint::from(2u8);
+        let r0 = (&a0 * &b0 + &two * (&p - &a1) * &b1) % &p;
+        let r1 = (&a0 * &b1 + &a1 * &b0) % &p;
+        assert_eq!([r0, r1], fp2_ref(&a.fp_mul(&b)));
+        assert_eq!(
+            [(&a0 + &b0) % &p, (&a1 + &b1) % &p],
+            fp2_ref(&a.fp_add(&b))
+        );
+        assert_eq!(a.fp_mul(&a), a.fp_sqr());
+        assert_eq!(a, a.fp_div2().fp_double());
+
+        assert_eq!(a.fp_mul(&b), b.fp_mul(&a));
+        assert_eq!(a.fp_mul(&b).fp_mul(&c), a.fp_mul(&b.fp_mul(&c)));
+        assert_eq!(a.fp_mul(&b.fp_add(&c)), a.fp_mul(&b).fp_add(&a.fp_mul(&c)));
+        assert!(a.fp_add(&a.fp_neg()).is_zero());
+        assert_eq!(a.fp_sub(&b), a.fp_add(&b.fp_neg()));
+        if !a.is_zero() {
+            assert_eq!(Fp2::one(), a.fp_mul(&a.fp_inv()));
+        }
+    });
+}
+
+#[test]
+fn prop_mod_n_scalars() {
+    let n = big(&SM9_N);
+    check("mod n", CASES, |rng| {
+        let (a, b, c) = (gen_below(rng, &SM9_N), gen_below(rng, &SM9_N), gen_below(rng, &SM9_N));
+        assert_eq!(big(&mod_n_add(&a, &b)), (big(&a) + big(&b)) % &n);
+        assert_eq!(big(&mod_n_sub(&a, &b)), (big(&a) + &n - big(&b)) % &n);
+        assert_eq!(big(&mod_n_mul(&a, &b)), big(&a) * big(&b) % &n);
+        assert_eq!(mod_n_mul(&a, &mod_n_add(&b, &c)), mod_n_add(&mod_n_mul(&a, &b), &mod_n_mul(&a, &c)));
+        if a != [0; 4] {
+            assert_eq!([1, 0, 0, 0], mod_n_mul(&a, &mod_n_inv(&a)));
+        }
+    });
+}
+
+#[test]
+fn prop_g1_group() {
+    check("G1", CASES / 4, |rng| {
+        let (a, b) = (gen_scalar(rng), gen_scalar(rng));
+        let (pa, pb) = (Point::g_mul(&a), Point::g_mul(&b));
+        let pc = Point::g_mul(&gen_scalar(rng));
+        assert!(pa.is_on_curve());
+
+        assert!(Point::g_mul(&mod_n_add(&a, &b)).point_equals(&pa.point_add(&pb)));
+        assert!(pa.point_mul(&b).point_equals(&pb.point_mul(&a)));
+        assert!(pa.point_add(&pb).point_equals(&pb.point_add(&pa)));
+        assert!(pa.point_add(&pb).point_add(&pc).point_equals(&pa.point_add(&pb.point_add(&pc))));
+        assert!(pa.point_add(&pa).point_equals(&pa.point_double()));
+        assert!(pa.point_add(&pa.point_neg()).is_zero());
+        assert!(pa.point_sub(&pb).point_add(&pb).point_equals(&pa));
+        assert!(Point::from_bytes(&pa.to_bytes_be()).unwrap().point_equals(&pa));
+    });
+}
+
+#[test]
+fn prop_g2_group() {
+    check("G2", CASES / 8, |rng| {
+        let (a, b) = (gen_scalar(rng), gen_scalar(rng));
+        let (qa, qb) = (TwistPoint::g_mul(&a), TwistPoint::g_mul(&b));
+        assert!(qa.is_on_curve());
+
+        // point_add is the mixed addition, twist_point_add_full takes any z
+        let qb_affine = qb.to_affine_point();
+        let sum = twist_point_add_full(&qa, &qb);
+        assert!(TwistPoint::g_mul(&mod_n_add(&a, &b)).point_equals(&sum));
+        assert!(sum.point_equals(&qa.point_add(&qb_affine)));
+        assert!(sum.point_equals(&twist_point_add_full(&qb, &qa)));
+        assert!(qa.point_mul(&b).point_equals(&qb.point_mul(&a)));
+        assert!(twist_point_add_full(&qa, &qa).point_equals(&qa.point_double()));
+        assert!(twist_point_add_full(&qa, &qa.point_neg()).is_zero());
+        assert!(qa.point_sub(&qb).point_equals(&twist_point_add_full(&qa, &qb.point_neg())));
+        assert!(TwistPoint::from_bytes(&qa.to_bytes_be()).unwrap().point_equals(&qa));
+    });
+}
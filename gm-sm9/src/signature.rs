@@ -0,0 +1,130 @@
+use crate::error::{Sm9Error, Sm9Result};
+use crate::points::Point;
+use crate::fields::mod_n_from_bytes;
+use crate::u256::{u256_to_be_bytes, SM9_ZERO, U256};
+
+/// SM9 signature value (h, S)
+///
+/// [`Sm9Signature::to_bytes`] is `h (32 bytes) || S (65 bytes, uncompressed)`,
+/// [`Sm9Signature::to_bytes_compressed`] is `h || S (33 bytes)`, and
+/// [`Sm9Signature::from_bytes`] reads both. [`Sm9Signature::to_der`] is the
+/// GM/T 0044 form GmSSL and SM9 middleware use:
+///
+/// ```text
+/// SM9Signature ::= SEQUENCE {
+///     h OCTET STRING, -- 32 bytes
+///     S BIT STRING    -- 0x04 || x || y
+/// }
+/// ```
+///
+/// Every decoder requires h in [1, n-1].
+#[derive(Copy, Debug, Clone)]
+pub struct Sm9Signature {
+    pub h: U256,
+    pub s: Point,
+}
+
+impl Sm9Signature {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = u256_to_be_bytes(&self.h);
+        bytes.extend_from_slice(&self.s.to_bytes_be());
+        bytes
+    }
+
+    /// 65 bytes instead of 97
+    pub fn to_bytes_compressed(&self) -> Vec<u8> {
+        let mut bytes = u256_to_be_bytes(&self.h);
+        bytes.extend_from_slice(&self.s.to_bytes_compressed());
+        bytes
+    }
+
+    pub fn from_bytes(b: &[u8]) -> Sm9Result<Self> {
+        let uncompressed = b.len() == 32 + 65 && b[32] == 0x04;
+        let compressed = b.len() == 32 + 33 && (b[32] == 0x02 || b[32] == 0x03);
+        if !uncompressed && !compressed {
+            return Err(Sm9Error::InvalidLength);
+        }
+        Self::from_parts(&b[..32], &b[32..])
+    }
+
+    pub fn to_der(&self) -> Vec<u8> {
+        let h = u256_to_be_bytes(&self.h);
+        let s = self.s.to_bytes_be();
+        yasna::construct_der(|writer| {
+            writer.write_sequence(|writer| {
+                writer.next().write_bytes(&h);
+                writer.next().write_bitvec_bytes(&s, s.len() * 8);
+            });
+        })
+    }
+
+    pub fn from_der(der: &[u8]) -> Sm9Result<Self> {
+        let (h, s) = yasna::parse_der(der, |reader| {
+            reader.read_sequence(|reader| {
+                let h = reader.next().read_bytes()?;
+                let (s, bits) = reader.next().read_bitvec_bytes()?;
+                if h.len() != 32 || s.len() != 65 || bits != 65 * 8 || s[0] != 0x04 {
+                    return Err(yasna::ASN1Error::new(yasna::ASN1ErrorKind::Invalid));
+                }
+                Ok((h, s))
+            })
+        })
+        .map_err(|_| Sm9Error::InvalidDer)?;
+        Self::from_parts(&h, &s)
+    }
+
+    /// h in [1, n-1] and S on the curve, S already checked for its prefix
+    fn from_parts(h: &[u8], s: &[u8]) -> Sm9Result<Self> {
+        let h = mod_n_from_bytes(h)?;
+        if h == SM9_ZERO {
+            return Err(Sm9Error::ZeroSig);
+        }
+        let s = Point::from_bytes(s)?;
+        if !s.is_on_curve() {
+            return Err(Sm9Error::NotOnCurve);
+        }
+        Ok(Self { h, s })
+    }
+}
+
+#[cfg(test)]
+mod test_signature {
+    use super::*;
+    use crate::key::Sm9SignMasterKey;
+    use crate::SM9_N;
+
+    #[test]
+    fn test_signature_forms() {
+        let msk = Sm9SignMasterKey::master_key_generate();
+        let (h, s) = msk.extract_key(b"Alice").unwrap().sign(b"msg").unwrap();
+        let sig = Sm9Signature { h, s };
+
+        let compressed = sig.to_bytes_compressed();
+        assert_eq!(65, compressed.len());
+        let der = sig.to_der();
+        // SEQUENCE { OCTET STRING of 32 bytes, BIT STRING of 65 bytes }
+        assert_eq!([0x30, 0x66, 0x04, 0x20], der[..4]);
+        assert_eq!([0x03, 0x42, 0x00, 0x04], der[36..40]);
+        for decoded in [
+            Sm9Signature::from_bytes(&sig.to_bytes()).unwrap(),
+            Sm9Signature::from_bytes(&compressed).unwrap(),
+            Sm9Signature::from_der(&der).unwrap(),
+        ] {
+            assert_eq!(h, decoded.h);
+            assert_eq!(s, decoded.s);
+            msk.verify_sign(b"Alice", b"msg", &decoded.h, &decoded.s).unwrap();
+        }
+
+        // h = 0 and h = n are out of range in every form
+        for bad_h in [[0u8; 32].to_vec(), u256_to_be_bytes(&SM9_N)] {
+            let mut b = compressed.clone();
+            b[..32].copy_from_slice(&bad_h);
+            assert!(Sm9Signature::from_bytes(&b).is_err());
+            let mut d = der.clone();
+            d[4..36].copy_from_slice(&bad_h);
+            assert!(Sm9Signature::from_der(&d).is_err());
+        }
+        assert_eq!(Err(Sm9Error::InvalidLength), Sm9Signature::from_bytes(&compressed[..64]).map(|_| ()));
+        assert_eq!(Err(Sm9Error::InvalidDer), Sm9Signature::from_der(&der[..der.len() - 1]).map(|_| ()));
+    }
+}
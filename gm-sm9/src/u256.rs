@@ -1,5 +1,5 @@
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use rand::RngCore;
+use rand::{CryptoRng, RngCore};
 use std::io::Cursor;
 
 pub type U256 = [u64; 4];
@@ -8,21 +8,31 @@ pub type U512 = [u64; 8];
 pub(crate) const SM9_ZERO: U256 = [0, 0, 0, 0];
 pub(crate) const SM9_ONE: U256 = [1, 0, 0, 0];
 
-#[inline(always)]
+/// Uniform in [1, range-1]
 pub fn sm9_random_u256(range: &U256) -> U256 {
-    let mut rng = rand::thread_rng();
-    let mut ret;
+    sm9_random_u256_with(&mut rand::thread_rng(), range)
+}
+
+/// Uniform in [1, range-1] from `rng`, rejecting the 32-byte draws out of
+/// range so that there is no modulo bias
+pub fn sm9_random_u256_with<R: RngCore + CryptoRng + ?Sized>(rng: &mut R, range: &U256) -> U256 {
+    let mut buf = [0u8; 32];
     loop {
-        let mut buf: [u8; 32] = [0; 32];
-        rng.fill_bytes(&mut buf[..]);
-        ret = u256_from_be_bytes(&buf);
-        if u256_cmp(&ret, range) < 0 && ret >= [1, 0, 0, 0] {
-            break;
+        rng.fill_bytes(&mut buf);
+        let ret = u256_from_be_bytes(&buf);
+        if u256_cmp(&ret, range) < 0 && ret != SM9_ZERO {
+            return ret;
         }
     }
-    ret
 }
 
+#[cfg(feature = "asm")]
+#[inline(always)]
+pub fn u256_add(a: &U256, b: &U256) -> (U256, bool) {
+    gm_core::limb::add(a, b)
+}
+
+#[cfg(not(feature = "asm"))]
 #[inline(always)]
 pub const fn u256_add(a: &U256, b: &U256) -> (U256, bool) {
     let mut sum = [0; 4];
@@ -65,6 +75,13 @@ pub const fn u512_add(a: &U512, b: &U512) -> (U512, bool) {
     (sum, carry)
 }
 
+#[cfg(feature = "asm")]
+#[inline(always)]
+pub fn u256_sub(a: &U256, b: &U256) -> (U256, bool) {
+    gm_core::limb::sub(a, b)
+}
+
+#[cfg(not(feature = "asm"))]
 #[inline(always)]
 pub const fn u256_sub(a: &U256, b: &U256) -> (U256, bool) {
     let mut r = [0; 4];
@@ -140,33 +157,116 @@ pub const fn u256_cmp(a: &U256, b: &U256) -> i32 {
 
 #[inline(always)]
 pub fn u256_mul(a: &U256, b: &U256) -> U512 {
-    let mut a_: [u64; 8] = [0; 8];
-    let mut b_: [u64; 8] = [0; 8];
-    let mut ret: [u64; 8] = [0; 8];
-    let mut s: [u64; 16] = [0; 16];
-
-    for i in 0..4 {
-        a_[2 * i] = a[i] & 0xffffffff;
-        b_[2 * i] = b[i] & 0xffffffff;
-        a_[2 * i + 1] = a[i] >> 32;
-        b_[2 * i + 1] = b[i] >> 32;
-    }
-
-    let mut u = 0;
-    for i in 0..8 {
-        u = 0;
-        for j in 0..8 {
-            u = s[i + j] + a_[i] * b_[j] + u;
-            s[i + j] = u & 0xffffffff;
-            u >>= 32;
+    gm_core::limb::mul(a, b)
+}
+
+/// `(a / b, a % b)`, panics if b is zero
+pub fn u256_div_rem(a: &U256, b: &U256) -> (U256, U256) {
+    let (q, r) = div_rem_limbs(a, b);
+    ([q[0], q[1], q[2], q[3]], r)
+}
+
+/// `a mod m` for any non-zero m, e.g. a 512-bit hash reduced mod p or n - 1
+pub fn u512_mod_u256(a: &U512, m: &U256) -> U256 {
+    div_rem_limbs(a, m).1
+}
+
+/// Knuth's algorithm D (TAOCP 4.3.1) on little-endian limbs, `a` of at most
+/// 8 limbs.
+fn div_rem_limbs(a: &[u64], b: &U256) -> (U512, U256) {
+    let n = b.iter().rposition(|&l| l != 0).expect("division by zero") + 1;
+    let mut q = [0u64; 8];
+    let mut r = [0u64; 4];
+    let len = a.iter().rposition(|&l| l != 0).map_or(0, |i| i + 1);
+    if len < n {
+        r[..len].copy_from_slice(&a[..len]);
+        return (q, r);
+    }
+    if n == 1 {
+        let d = b[0] as u128;
+        let mut rem = 0u128;
+        for j in (0..len).rev() {
+            let num = (rem << 64) | a[j] as u128;
+            q[j] = (num / d) as u64;
+            rem = num % d;
         }
-        s[i + 8] = u;
+        r[0] = rem as u64;
+        return (q, r);
     }
 
-    for i in 0..8 {
-        ret[i] = (s[2 * i + 1] << 32) | s[2 * i];
+    // normalize so the top limb of the divisor has its high bit set
+    let s = b[n - 1].leading_zeros();
+    let shl = |x: &[u64], i: usize| {
+        let lo = if i > 0 && s > 0 { x[i - 1] >> (64 - s) } else { 0 };
+        let hi = if i < x.len() { x[i] << s } else { 0 };
+        hi | lo
+    };
+    let mut v = [0u64; 4];
+    for (i, v) in v.iter_mut().enumerate().take(n) {
+        *v = shl(&b[..n], i);
     }
-    ret
+    let mut u = [0u64; 9];
+    for (i, u) in u.iter_mut().enumerate().take(len + 1) {
+        *u = shl(&a[..len], i);
+    }
+
+    const B: u128 = 1 << 64;
+    for j in (0..=len - n).rev() {
+        let num = ((u[j + n] as u128) << 64) | u[j + n - 1] as u128;
+        let mut qhat = num / v[n - 1] as u128;
+        let mut rhat = num % v[n - 1] as u128;
+        while qhat >= B || qhat * v[n - 2] as u128 > ((rhat << 64) | u[j + n - 2] as u128) {
+            qhat -= 1;
+            rhat += v[n - 1] as u128;
+            if rhat >= B {
+                break;
+            }
+        }
+
+        // u[j..=j+n] -= qhat * v
+        let mut carry = 0u128;
+        let mut borrow = 0u64;
+        for i in 0..n {
+            let p = qhat * v[i] as u128 + carry;
+            carry = p >> 64;
+            let (t, b1) = u[i + j].overflowing_sub(p as u64);
+            let (t, b2) = t.overflowing_sub(borrow);
+            u[i + j] = t;
+            borrow = b1 as u64 + b2 as u64;
+        }
+        let (t, b1) = u[j + n].overflowing_sub(carry as u64);
+        let (t, b2) = t.overflowing_sub(borrow);
+        u[j + n] = t;
+
+        // qhat was one too large, add v back
+        if b1 || b2 {
+            qhat -= 1;
+            let mut carry = 0u128;
+            for i in 0..n {
+                let t = u[i + j] as u128 + v[i] as u128 + carry;
+                u[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u64);
+        }
+        q[j] = qhat as u64;
+    }
+
+    for (i, r) in r.iter_mut().enumerate().take(n) {
+        let hi = if s > 0 { u[i + 1] << (64 - s) } else { 0 };
+        *r = (u[i] >> s) | hi;
+    }
+    (q, r)
+}
+
+/// Montgomery multiplication a * b * 2^-256 mod p (CIOS), for a, b < p.
+///
+/// `p_inv` is -p^-1 mod 2^64. The SM9 primes have no sparse form to exploit,
+/// interleaving the reduction with the multiplication word by word avoids
+/// the full 512-bit product and the two extra 256-bit multiplications.
+#[inline(always)]
+pub fn u256_mont_mul(a: &U256, b: &U256, p: &U256, p_inv: u64) -> U256 {
+    gm_core::limb::mont_mul(a, b, p, p_inv)
 }
 
 #[inline(always)]
@@ -245,8 +345,10 @@ mod test_operation {
     use num_bigint::BigUint;
 
     use crate::u256::{
-        sm9_u256_get_booth, u256_add, u256_from_be_bytes, u256_mul, u256_sub, u256_to_be_bytes,
+        sm9_u256_get_booth, u256_add, u256_div_rem, u256_from_be_bytes, u256_mont_mul, u256_mul,
+        u256_sub, u256_to_be_bytes, u512_mod_u256, U256,
     };
+    use crate::{SM9_N, SM9_P, SM9_P_PRIME};
 
     #[test]
     fn test_raw_add_u64() {
@@ -327,4 +429,89 @@ mod test_operation {
             println!("i = {}, booth = {}", i, booth);
         }
     }
+
+    #[test]
+    fn test_div_rem() {
+        let a = u256_from_be_bytes(
+            &hex::decode("85AEF3D078640C98597B6027B441A01FF1DD2C190F5E93C454806C11D8806141")
+                .unwrap(),
+        );
+        let n_minus_one = u256_sub(&SM9_N, &[1, 0, 0, 0]).0;
+        for b in [[7, 0, 0, 0], [0, 1, 0, 0], [u64::MAX, u64::MAX, 0, 0], n_minus_one, a, SM9_P] {
+            let (q, r) = u256_div_rem(&a, &b);
+            let (big_a, big_b) = (BigUint::from_slice(&to_u32(&a)), BigUint::from_slice(&to_u32(&b)));
+            assert_eq!(BigUint::from_slice(&to_u32(&q)), &big_a / &big_b);
+            assert_eq!(BigUint::from_slice(&to_u32(&r)), &big_a % &big_b);
+        }
+        assert_eq!(([0; 4], [5, 0, 0, 0]), u256_div_rem(&[5, 0, 0, 0], &SM9_N));
+
+        // 2^512 - 1 and n * (n - 1) reduced mod n - 1
+        assert_eq!(
+            u256_from_be_bytes(
+                &hex::decode("83AD03DD497968D169DE2FFA6F2C449DED5132D953590A7E1F9A5A150000EA1B")
+                    .unwrap()
+            ),
+            u512_mod_u256(&[u64::MAX; 8], &n_minus_one)
+        );
+        assert_eq!([0; 4], u512_mod_u256(&u256_mul(&SM9_N, &n_minus_one), &n_minus_one));
+    }
+
+    fn to_u32(a: &[u64]) -> Vec<u32> {
+        a.iter().flat_map(|&w| [w as u32, (w >> 32) as u32]).collect()
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_div_by_zero() {
+        u256_div_rem(&[1, 0, 0, 0], &[0; 4]);
+    }
+
+    #[test]
+    fn test_mont_mul() {
+        fn to_big(a: &U256) -> BigUint {
+            BigUint::from_bytes_be(&u256_to_be_bytes(a))
+        }
+
+        let p = to_big(&SM9_P);
+        let r_inv = (BigUint::from(1u8) << 256usize).modpow(&(&p - 2u8), &p);
+        let mut a = [
+            0x54806C11D8806141,
+            0xF1DD2C190F5E93C4,
+            0x597B6027B441A01F,
+            0x45AEF3D078640C98,
+        ];
+        let mut b = [
+            0x0E75C05FB4E3216D,
+            0x1006E85F5CDFF073,
+            0x1A7CE027B7A46F74,
+            0x41E00A53DDA532DA,
+        ];
+        for _ in 0..100 {
+            let r = u256_mont_mul(&a, &b, &SM9_P, SM9_P_PRIME[0]);
+            assert_eq!(to_big(&a) * to_big(&b) * &r_inv % &p, to_big(&r));
+            b = a;
+            a = r;
+        }
+
+        let p_minus_one = u256_sub(&SM9_P, &[1, 0, 0, 0]).0;
+        let r = u256_mont_mul(&p_minus_one, &p_minus_one, &SM9_P, SM9_P_PRIME[0]);
+        assert_eq!(&r_inv % &p, to_big(&r));
+        assert!(to_big(&SM9_N) < p);
+    }
+
+    #[test]
+    fn test_random_with() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // the same seed gives the same scalar, always in [1, n-1]
+        let a = crate::fields::fn_random_with(&mut StdRng::seed_from_u64(7));
+        assert_eq!(a, crate::fields::fn_random_with(&mut StdRng::seed_from_u64(7)));
+        let mut rng = StdRng::seed_from_u64(8);
+        for _ in 0..1000 {
+            let k = crate::fields::fn_random_with(&mut rng);
+            assert!(k != [0; 4] && super::u256_cmp(&k, &SM9_N) < 0);
+            let x = crate::fields::fp::fp_random_with(&mut rng);
+            assert!(x != [0; 4] && super::u256_cmp(&x, &SM9_P) < 0);
+        }
+    }
 }
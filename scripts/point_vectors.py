@@ -0,0 +1,191 @@
+#!/usr/bin/env python3
+"""Point arithmetic test vectors for gm-sm2 and gm-sm9.
+
+Affine formulas over Python integers, independent of the Rust code, with
+fixed scalars and a seeded RNG so the output never changes. Prints the
+`const` tables at the end of gm-sm2/src/point_vectors.rs and
+gm-sm9/src/point_vectors.rs:
+
+    python3 scripts/point_vectors.py sm2
+    python3 scripts/point_vectors.py sm9
+
+Points are uncompressed SEC1 hex, an Fp2 coordinate as c1 || c0, and the empty
+string is the point at infinity.
+"""
+import random
+import sys
+
+
+class Fp2:
+    """c0 + c1 u with u^2 = -2"""
+
+    def __init__(self, p, c0, c1=0):
+        self.p, self.c0, self.c1 = p, c0 % p, c1 % p
+
+    def __add__(self, o):
+        return Fp2(self.p, self.c0 + o.c0, self.c1 + o.c1)
+
+    def __sub__(self, o):
+        return Fp2(self.p, self.c0 - o.c0, self.c1 - o.c1)
+
+    def __neg__(self):
+        return Fp2(self.p, -self.c0, -self.c1)
+
+    def __mul__(self, o):
+        if isinstance(o, int):
+            return Fp2(self.p, self.c0 * o, self.c1 * o)
+        return Fp2(self.p, self.c0 * o.c0 - 2 * self.c1 * o.c1, self.c0 * o.c1 + self.c1 * o.c0)
+
+    def inv(self):
+        norm = pow(self.c0 * self.c0 + 2 * self.c1 * self.c1, -1, self.p)
+        return Fp2(self.p, self.c0 * norm, -self.c1 * norm)
+
+    def is_zero(self):
+        return self.c0 == 0 and self.c1 == 0
+
+    def __eq__(self, o):
+        return self.c0 == o.c0 and self.c1 == o.c1
+
+    def hex(self):
+        return "%064x%064x" % (self.c1, self.c0)
+
+
+class Fp:
+    def __init__(self, p, v):
+        self.p, self.v = p, v % p
+
+    def __add__(self, o):
+        return Fp(self.p, self.v + o.v)
+
+    def __sub__(self, o):
+        return Fp(self.p, self.v - o.v)
+
+    def __neg__(self):
+        return Fp(self.p, -self.v)
+
+    def __mul__(self, o):
+        return Fp(self.p, self.v * (o if isinstance(o, int) else o.v))
+
+    def inv(self):
+        return Fp(self.p, pow(self.v, -1, self.p))
+
+    def is_zero(self):
+        return self.v == 0
+
+    def __eq__(self, o):
+        return self.v == o.v
+
+    def hex(self):
+        return "%064x" % self.v
+
+
+class Curve:
+    """y^2 = x^3 + a x + b, None is the point at infinity"""
+
+    def __init__(self, a, b):
+        self.a, self.b = a, b
+
+    def on_curve(self, P):
+        x, y = P
+        return y * y == x * x * x + self.a * x + self.b
+
+    def neg(self, P):
+        return None if P is None else (P[0], -P[1])
+
+    def add(self, P, Q):
+        if P is None:
+            return Q
+        if Q is None:
+            return P
+        (x1, y1), (x2, y2) = P, Q
+        if x1 == x2:
+            if (y1 + y2).is_zero():
+                return None
+            return self.double(P)
+        lam = (y2 - y1) * (x2 - x1).inv()
+        x3 = lam * lam - x1 - x2
+        return (x3, lam * (x1 - x3) - y1)
+
+    def double(self, P):
+        if P is None or P[1].is_zero():
+            return None
+        x, y = P
+        lam = (x * x * 3 + self.a) * (y * 2).inv()
+        x3 = lam * lam - x * 2
+        return (x3, lam * (x - x3) - y)
+
+    def mul(self, k, P):
+        R = None
+        for bit in bin(k)[2:] if k else "":
+            R = self.double(R)
+            if bit == "1":
+                R = self.add(R, P)
+        return R
+
+
+def enc(P):
+    return "" if P is None else "04" + P[0].hex() + P[1].hex()
+
+
+def vectors(curve, G, n, prefix, rng):
+    assert curve.on_curve(G) and curve.mul(n, G) is None
+    P = curve.mul(7, G)
+    R = curve.mul(rng.randrange(1, n), G)
+    S = curve.mul(rng.randrange(1, n), G)
+    add = [
+        (G, curve.double(G)),
+        (G, curve.neg(G)),
+        (None, G),
+        (G, None),
+        (None, None),
+        (P, P),
+        (R, S),
+        (curve.mul(n - 1, G), G),
+        (R, curve.neg(S)),
+    ]
+    dbl = [G, None, P, R, curve.mul(n - 1, G)]
+    ks = [0, 1, 2, 3, n - 2, n - 1, n, 1 << 255, (1 << 128) - 1, rng.randrange(1, n), rng.randrange(1, n)]
+    mul = [(k, B) for k in ks for B in (G, P)]
+
+    print("const %sADD: &[(&str, &str, &str)] = &[" % prefix)
+    for a, b in add:
+        print('    ("%s",\n     "%s",\n     "%s"),' % (enc(a), enc(b), enc(curve.add(a, b))))
+    print("];\n")
+    print("const %sDOUBLE: &[(&str, &str)] = &[" % prefix)
+    for a in dbl:
+        print('    ("%s",\n     "%s"),' % (enc(a), enc(curve.double(a))))
+    print("];\n")
+    print("const %sMUL: &[(&str, &str, &str)] = &[" % prefix)
+    for k, B in mul:
+        print('    ("%064x",\n     "%s",\n     "%s"),' % (k, enc(B), enc(curve.mul(k, B))))
+    print("];")
+
+
+def sm2():
+    p = 0xFFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFF
+    b = 0x28E9FA9E9D9F5E344D5A9E4BCF6509A7F39789F515AB8F92DDBCBD414D940E93
+    n = 0xFFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFF7203DF6B21C6052B53BBF40939D54123
+    gx = 0x32C4AE2C1F1981195F9904466A39C9948FE30BBFF2660BE1715A4589334C74C7
+    gy = 0xBC3736A2F4F6779C59BDCEE36B692153D0A9877CC62A474002DF32E52139F0A0
+    curve = Curve(Fp(p, -3), Fp(p, b))
+    print("// generated by scripts/point_vectors.py sm2, do not edit")
+    vectors(curve, (Fp(p, gx), Fp(p, gy)), n, "", random.Random(2))
+
+
+def sm9():
+    p = 0xB640000002A3A6F1D603AB4FF58EC74521F2934B1A7AEEDBE56F9B27E351457D
+    n = 0xB640000002A3A6F1D603AB4FF58EC74449F2934B18EA8BEEE56EE19CD69ECF25
+    p1 = (Fp(p, 0x93DE051D62BF718FF5ED0704487D01D6E1E4086909DC3280E8C4E4817C66DDDD),
+          Fp(p, 0x21FE8DDA4F21E607631065125C395BBC1C1C00CBFA6024350C464CD70A3EA616))
+    rng = random.Random(9)
+    print("// generated by scripts/point_vectors.py sm9, do not edit")
+    vectors(Curve(Fp(p, 0), Fp(p, 5)), p1, n, "G1_", rng)
+    print()
+    p2 = (Fp2(p, 0x3722755292130B08D2AAB97FD34EC120EE265948D19C17ABF9B7213BAF82D65B,
+              0x85AEF3D078640C98597B6027B441A01FF1DD2C190F5E93C454806C11D8806141),
+          Fp2(p, 0xA7CF28D519BE3DA65F3170153D278FF247EFBA98A71A08116215BBA5C999A7C7,
+              0x17509B092E845C1266BA0D262CBEE6ED0736A96FA347C8BD856DC76B84EBEB96))
+    vectors(Curve(Fp2(p, 0), Fp2(p, 0, 5)), p2, n, "G2_", rng)
+
+
+{"sm2": sm2, "sm9": sm9}[sys.argv[1]]()